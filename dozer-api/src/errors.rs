@@ -0,0 +1,84 @@
+use dozer_sql_expression::sqlparser::parser::ParserError;
+use dozer_types::{
+    errors::types::TypeError,
+    serde_json,
+    thiserror::{self, Error},
+    tonic::{self, Status},
+    types::{Field, FieldType},
+};
+
+use crate::query::Operator;
+
+#[derive(Error, Debug)]
+pub enum ApiError {
+    #[error("Endpoint not found: {0}")]
+    EndpointNotFound(String),
+
+    #[error("Query is not valid JSON: {0}")]
+    QueryJson(#[from] serde_json::Error),
+
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+
+    #[error("Field not found: {0}")]
+    FieldNotFound(String),
+
+    #[error("Invalid value for field {field}: {error}")]
+    InvalidValue {
+        field: String,
+        #[source]
+        error: TypeError,
+    },
+
+    #[error("Operator {operator} is not supported on field {field} of type {typ}")]
+    UnsupportedOperator {
+        operator: Operator,
+        field: String,
+        typ: FieldType,
+    },
+
+    #[error("SQL parse error: {0}")]
+    SqlParse(#[from] ParserError),
+
+    #[error("Unsupported SQL: {0}")]
+    UnsupportedSql(String),
+
+    #[error(transparent)]
+    Expression(#[from] dozer_sql_expression::error::Error),
+
+    #[error("Another record has primary key {0:?}")]
+    DuplicateKey(Vec<Field>),
+
+    #[error("Schema of endpoint {0} changed during the query")]
+    SchemaChanged(String),
+
+    #[error("Invalid address {0}")]
+    InvalidAddress(String),
+
+    #[error("Failed to bind {0}: {1}")]
+    Bind(String, #[source] std::io::Error),
+
+    #[error("gRPC server failed: {0}")]
+    Transport(#[from] tonic::transport::Error),
+}
+
+impl From<ApiError> for Status {
+    fn from(error: ApiError) -> Self {
+        match error {
+            ApiError::EndpointNotFound(_) => Status::not_found(error.to_string()),
+            ApiError::QueryJson(_)
+            | ApiError::InvalidQuery(_)
+            | ApiError::FieldNotFound(_)
+            | ApiError::InvalidValue { .. }
+            | ApiError::UnsupportedOperator { .. }
+            | ApiError::SqlParse(_)
+            | ApiError::UnsupportedSql(_)
+            | ApiError::Expression(_) => Status::invalid_argument(error.to_string()),
+            ApiError::SchemaChanged(_) => Status::aborted(error.to_string()),
+            ApiError::DuplicateKey(_) => Status::already_exists(error.to_string()),
+            ApiError::InvalidAddress(_) | ApiError::Bind(_, _) | ApiError::Transport(_) => {
+                Status::internal(error.to_string())
+            }
+        }
+    }
+}
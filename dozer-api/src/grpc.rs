@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use dozer_core::tokio;
+use dozer_core::tokio::sync::{broadcast::error::RecvError, mpsc};
+use dozer_types::grpc_types::common::common_grpc_service_server::{
+    CommonGrpcService, CommonGrpcServiceServer,
+};
+use dozer_types::grpc_types::common::{
+    CountResponse, GetEndpointsRequest, GetEndpointsResponse, GetFieldsRequest, GetFieldsResponse,
+    OnEventRequest, QueryRequest, QueryResponse,
+};
+use dozer_types::grpc_types::conversions::{field_definition_to_grpc, field_to_grpc};
+use dozer_types::grpc_types::types::{EventType, Operation, OperationType, Record};
+use dozer_types::log::{info, warn};
+use dozer_types::models::api_config::{default_grpc_port, default_host, GrpcApiOptions};
+use dozer_types::parking_lot::RwLock;
+use dozer_types::tonic::{self, Request, Response, Status};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::errors::ApiError;
+use crate::query::{BoundFilter, FilterExpression, QueryExpression, DEFAULT_MAX_NUM_RECORDS};
+use crate::store::{Event, Store, StoredRecord, Table};
+
+const EVENT_BUFFER_SIZE: usize = 64;
+
+fn record_to_grpc(record: &StoredRecord) -> Record {
+    Record {
+        values: record.values.iter().cloned().map(field_to_grpc).collect(),
+        id: record.id,
+        version: record.version,
+    }
+}
+
+fn event_to_grpc(event: &Event) -> Operation {
+    Operation {
+        typ: event.typ as i32,
+        old: event.old.as_ref().map(record_to_grpc),
+        new: Some(record_to_grpc(&event.new)),
+        endpoint: event.endpoint.clone(),
+    }
+}
+
+/// What an `OnEvent` stream receives of an endpoint.
+#[derive(Debug)]
+struct Subscription {
+    typ: EventType,
+    filter: Option<BoundFilter>,
+}
+
+impl Subscription {
+    fn matches(&self, event: &Event) -> bool {
+        let type_matches = match self.typ {
+            EventType::All => true,
+            EventType::InsertOnly => event.typ == OperationType::Insert,
+            EventType::UpdateOnly => event.typ == OperationType::Update,
+            EventType::DeleteOnly => event.typ == OperationType::Delete,
+        };
+        type_matches
+            && self
+                .filter
+                .as_ref()
+                .map_or(true, |filter| filter.matches(&event.new.values))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CommonService {
+    store: Arc<Store>,
+    default_max_num_records: usize,
+}
+
+impl CommonService {
+    pub fn new(store: Arc<Store>, default_max_num_records: Option<usize>) -> Self {
+        Self {
+            store,
+            default_max_num_records: default_max_num_records.unwrap_or(DEFAULT_MAX_NUM_RECORDS),
+        }
+    }
+
+    fn table(&self, endpoint: &str) -> Result<Arc<RwLock<Table>>, ApiError> {
+        self.store
+            .table(endpoint)
+            .ok_or_else(|| ApiError::EndpointNotFound(endpoint.to_string()))
+    }
+
+    fn parse_query(query: Option<&str>) -> Result<QueryExpression, ApiError> {
+        match query {
+            Some(query) if !query.trim().is_empty() => QueryExpression::parse_str(query),
+            _ => Ok(QueryExpression::default()),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl CommonGrpcService for CommonService {
+    /// Only the filter of the query is applied.
+    async fn count(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<CountResponse>, Status> {
+        let request = request.into_inner();
+        let table = self.table(&request.endpoint)?;
+        let query = Self::parse_query(request.query.as_deref())?;
+        let table = table.read();
+        let filter = query
+            .filter
+            .map(|filter| filter.bind(table.schema()))
+            .transpose()?;
+        Ok(Response::new(CountResponse {
+            count: table.count(filter.as_ref()) as u64,
+        }))
+    }
+
+    async fn query(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<QueryResponse>, Status> {
+        let request = request.into_inner();
+        let table = self.table(&request.endpoint)?;
+        let query = Self::parse_query(request.query.as_deref())?;
+        let table = table.read();
+        let query = query.bind(table.schema(), self.default_max_num_records)?;
+        Ok(Response::new(QueryResponse {
+            fields: field_definition_to_grpc(table.schema().fields.clone()),
+            records: table
+                .query(&query)
+                .into_iter()
+                .map(record_to_grpc)
+                .collect(),
+        }))
+    }
+
+    type OnEventStream = ReceiverStream<Result<Operation, Status>>;
+
+    async fn on_event(
+        &self,
+        request: Request<OnEventRequest>,
+    ) -> Result<Response<Self::OnEventStream>, Status> {
+        let subscriptions = request
+            .into_inner()
+            .endpoints
+            .into_iter()
+            .map(|(endpoint, event_filter)| {
+                let schema = self.table(&endpoint)?.read().schema().clone();
+                let filter = event_filter
+                    .filter
+                    .as_deref()
+                    .filter(|filter| !filter.trim().is_empty())
+                    .map(|filter| FilterExpression::parse_str(filter)?.bind(&schema))
+                    .transpose()?;
+                let subscription = Subscription {
+                    typ: event_filter.r#type(),
+                    filter,
+                };
+                Ok((endpoint, subscription))
+            })
+            .collect::<Result<HashMap<_, _>, ApiError>>()?;
+
+        let mut events = self.store.subscribe();
+        let (sender, receiver) = mpsc::channel(EVENT_BUFFER_SIZE);
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(count)) => {
+                        warn!("OnEvent subscriber lagged behind by {count} events, closing stream");
+                        let _ = sender
+                            .send(Err(Status::data_loss(format!(
+                                "Subscriber lagged behind by {count} events"
+                            ))))
+                            .await;
+                        return;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                let Some(subscription) = subscriptions.get(&event.endpoint) else {
+                    continue;
+                };
+                if subscription.matches(&event)
+                    && sender.send(Ok(event_to_grpc(&event))).await.is_err()
+                {
+                    // The client is gone.
+                    return;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn get_endpoints(
+        &self,
+        _request: Request<GetEndpointsRequest>,
+    ) -> Result<Response<GetEndpointsResponse>, Status> {
+        Ok(Response::new(GetEndpointsResponse {
+            endpoints: self.store.endpoints(),
+        }))
+    }
+
+    async fn get_fields(
+        &self,
+        request: Request<GetFieldsRequest>,
+    ) -> Result<Response<GetFieldsResponse>, Status> {
+        let table = self.table(&request.into_inner().endpoint)?;
+        let schema = table.read().schema().clone();
+        Ok(Response::new(GetFieldsResponse {
+            primary_index: schema
+                .primary_index
+                .iter()
+                .map(|index| *index as i32)
+                .collect(),
+            fields: field_definition_to_grpc(schema.fields),
+        }))
+    }
+}
+
+/// Serves `CommonGrpcService` on the tables of `store` until `shutdown` completes.
+pub async fn serve(
+    store: Arc<Store>,
+    options: &GrpcApiOptions,
+    default_max_num_records: Option<usize>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), ApiError> {
+    let host = options.host.clone().unwrap_or_else(default_host);
+    let port = options.port.unwrap_or_else(default_grpc_port);
+    let address = format!("{host}:{port}");
+    let addr: SocketAddr = address
+        .parse()
+        .map_err(|_| ApiError::InvalidAddress(address.clone()))?;
+
+    let service = CommonGrpcServiceServer::new(CommonService::new(store, default_max_num_records));
+    // gRPC-Web also answers CORS preflight requests.
+    let web = options.web != Some(false) || options.cors == Some(true);
+
+    info!("Starting gRPC server on {address}");
+    tonic::transport::Server::builder()
+        .accept_http1(web)
+        .add_optional_service(web.then(|| tonic_web::enable(service.clone())))
+        .add_optional_service((!web).then_some(service))
+        .serve_with_shutdown(addr, shutdown)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,9 @@
+pub mod errors;
+pub mod grpc;
+pub mod query;
+mod sink;
+pub use sink::ApiSinkFactory;
+pub mod sql;
+pub mod store;
+#[cfg(test)]
+mod tests;
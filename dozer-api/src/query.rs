@@ -0,0 +1,347 @@
+//! The JSON query language of the APIs.
+//!
+//! A query is an object with optional `$filter`, `$order_by`, `$limit` and `$skip` keys:
+//!
+//! ```json
+//! {
+//!     "$filter": { "name": "John", "age": { "$gte": 18 }, "$and": [{ "city": { "$contains": "ber" } }] },
+//!     "$order_by": [{ "age": "desc" }, { "name": "asc" }],
+//!     "$limit": 10,
+//!     "$skip": 20
+//! }
+//! ```
+//!
+//! A plain value in a filter compares for equality. The conditions of a filter object are combined with AND.
+//! The keys of an object are unordered, so sorting by several fields takes an array.
+
+use std::cmp::Ordering;
+use std::fmt::{self, Display};
+
+use dozer_types::json_value_to_field;
+use dozer_types::serde_json::{self, Map, Value};
+use dozer_types::types::{Field, FieldType, Schema};
+
+use crate::errors::ApiError;
+
+/// The number of records a query returns when it has no `$limit` and the config no `default_max_num_records`.
+pub const DEFAULT_MAX_NUM_RECORDS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Lt,
+    Lte,
+    Eq,
+    Gt,
+    Gte,
+    Contains,
+}
+
+impl Operator {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "$lt" => Some(Operator::Lt),
+            "$lte" => Some(Operator::Lte),
+            "$eq" => Some(Operator::Eq),
+            "$gt" => Some(Operator::Gt),
+            "$gte" => Some(Operator::Gte),
+            "$contains" => Some(Operator::Contains),
+            _ => None,
+        }
+    }
+
+    /// Null only equals null, and is neither less nor greater than any value.
+    fn matches(&self, field: &Field, value: &Field) -> bool {
+        match self {
+            Operator::Eq => field == value,
+            Operator::Contains => match (field, value) {
+                (Field::String(field) | Field::Text(field), Field::String(value)) => {
+                    field.contains(value.as_str())
+                }
+                _ => false,
+            },
+            _ if field == &Field::Null || value == &Field::Null => false,
+            Operator::Lt => field < value,
+            Operator::Lte => field <= value,
+            Operator::Gt => field > value,
+            Operator::Gte => field >= value,
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Operator::Lt => "$lt",
+            Operator::Lte => "$lte",
+            Operator::Eq => "$eq",
+            Operator::Gt => "$gt",
+            Operator::Gte => "$gte",
+            Operator::Contains => "$contains",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpression {
+    Simple(String, Operator, Value),
+    And(Vec<FilterExpression>),
+}
+
+impl FilterExpression {
+    pub fn parse(value: Value) -> Result<Self, ApiError> {
+        let Value::Object(conditions) = value else {
+            return Err(ApiError::InvalidQuery(format!(
+                "filter must be an object, got {value}"
+            )));
+        };
+        let mut expressions = conditions
+            .into_iter()
+            .map(|(key, value)| parse_condition(key, value))
+            .collect::<Result<Vec<_>, _>>()?;
+        if expressions.len() == 1 {
+            Ok(expressions.remove(0))
+        } else {
+            Ok(FilterExpression::And(expressions))
+        }
+    }
+
+    pub fn parse_str(filter: &str) -> Result<Self, ApiError> {
+        Self::parse(serde_json::from_str(filter)?)
+    }
+
+    /// Resolves the field names and converts the values to the field types of `schema`.
+    pub fn bind(&self, schema: &Schema) -> Result<BoundFilter, ApiError> {
+        match self {
+            FilterExpression::Simple(field_name, operator, value) => {
+                let (index, field) = schema
+                    .get_field_index(field_name)
+                    .map_err(|_| ApiError::FieldNotFound(field_name.clone()))?;
+                let value = if *operator == Operator::Contains {
+                    if !matches!(field.typ, FieldType::String | FieldType::Text) {
+                        return Err(ApiError::UnsupportedOperator {
+                            operator: *operator,
+                            field: field_name.clone(),
+                            typ: field.typ,
+                        });
+                    }
+                    json_value_to_field(value.clone(), FieldType::String, false)
+                } else {
+                    json_value_to_field(value.clone(), field.typ, true)
+                }
+                .map_err(|error| ApiError::InvalidValue {
+                    field: field_name.clone(),
+                    error,
+                })?;
+                Ok(BoundFilter::Simple(index, *operator, value))
+            }
+            FilterExpression::And(expressions) => Ok(BoundFilter::And(
+                expressions
+                    .iter()
+                    .map(|expression| expression.bind(schema))
+                    .collect::<Result<_, _>>()?,
+            )),
+        }
+    }
+}
+
+fn parse_condition(key: String, value: Value) -> Result<FilterExpression, ApiError> {
+    if key == "$and" {
+        let Value::Array(filters) = value else {
+            return Err(ApiError::InvalidQuery(format!(
+                "$and must be an array, got {value}"
+            )));
+        };
+        return Ok(FilterExpression::And(
+            filters
+                .into_iter()
+                .map(FilterExpression::parse)
+                .collect::<Result<_, _>>()?,
+        ));
+    }
+    if key.starts_with('$') {
+        return Err(ApiError::InvalidQuery(format!("unknown operator {key}")));
+    }
+
+    match value {
+        Value::Object(operators) => {
+            let mut expressions = operators
+                .into_iter()
+                .map(|(name, value)| {
+                    let operator = Operator::parse(&name).ok_or_else(|| {
+                        ApiError::InvalidQuery(format!("unknown operator {name}"))
+                    })?;
+                    Ok(FilterExpression::Simple(key.clone(), operator, value))
+                })
+                .collect::<Result<Vec<_>, ApiError>>()?;
+            match expressions.len() {
+                0 => Err(ApiError::InvalidQuery(format!(
+                    "no operator given for field {key}"
+                ))),
+                1 => Ok(expressions.remove(0)),
+                _ => Ok(FilterExpression::And(expressions)),
+            }
+        }
+        value => Ok(FilterExpression::Simple(key, Operator::Eq, value)),
+    }
+}
+
+/// A filter whose fields are resolved to indexes in the record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoundFilter {
+    Simple(usize, Operator, Field),
+    And(Vec<BoundFilter>),
+}
+
+impl BoundFilter {
+    pub fn matches(&self, values: &[Field]) -> bool {
+        match self {
+            BoundFilter::Simple(index, operator, value) => operator.matches(&values[*index], value),
+            BoundFilter::And(filters) => filters.iter().all(|filter| filter.matches(values)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortOption {
+    pub field_name: String,
+    pub direction: SortDirection,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueryExpression {
+    pub filter: Option<FilterExpression>,
+    pub order_by: Vec<SortOption>,
+    pub limit: Option<usize>,
+    pub skip: usize,
+}
+
+impl QueryExpression {
+    pub fn parse(value: Value) -> Result<Self, ApiError> {
+        let Value::Object(query) = value else {
+            return Err(ApiError::InvalidQuery(format!(
+                "query must be an object, got {value}"
+            )));
+        };
+        let mut expression = QueryExpression::default();
+        for (key, value) in query {
+            match key.as_str() {
+                "$filter" => expression.filter = Some(FilterExpression::parse(value)?),
+                "$order_by" => expression.order_by = parse_order_by(value)?,
+                "$limit" => expression.limit = Some(parse_count(&key, &value)?),
+                "$skip" => expression.skip = parse_count(&key, &value)?,
+                _ => return Err(ApiError::InvalidQuery(format!("unknown key {key}"))),
+            }
+        }
+        Ok(expression)
+    }
+
+    pub fn parse_str(query: &str) -> Result<Self, ApiError> {
+        Self::parse(serde_json::from_str(query)?)
+    }
+
+    pub fn bind(&self, schema: &Schema, default_limit: usize) -> Result<BoundQuery, ApiError> {
+        let filter = self
+            .filter
+            .as_ref()
+            .map(|filter| filter.bind(schema))
+            .transpose()?;
+        let order_by = self
+            .order_by
+            .iter()
+            .map(|option| {
+                let (index, _) = schema
+                    .get_field_index(&option.field_name)
+                    .map_err(|_| ApiError::FieldNotFound(option.field_name.clone()))?;
+                Ok((index, option.direction))
+            })
+            .collect::<Result<_, ApiError>>()?;
+        Ok(BoundQuery {
+            filter,
+            order_by,
+            limit: self.limit.unwrap_or(default_limit),
+            skip: self.skip,
+        })
+    }
+}
+
+fn parse_order_by(value: Value) -> Result<Vec<SortOption>, ApiError> {
+    let options = match value {
+        Value::Array(options) => options,
+        value => vec![value],
+    };
+    let mut order_by = vec![];
+    for option in options {
+        let Value::Object(option) = option else {
+            return Err(ApiError::InvalidQuery(format!(
+                "$order_by must be an object or an array of objects, got {option}"
+            )));
+        };
+        order_by.extend(parse_sort_options(option)?);
+    }
+    Ok(order_by)
+}
+
+fn parse_sort_options(options: Map<String, Value>) -> Result<Vec<SortOption>, ApiError> {
+    options
+        .into_iter()
+        .map(|(field_name, direction)| {
+            let direction = match direction.as_str() {
+                Some("asc") => SortDirection::Ascending,
+                Some("desc") => SortDirection::Descending,
+                _ => {
+                    return Err(ApiError::InvalidQuery(format!(
+                    "sort direction of {field_name} must be \"asc\" or \"desc\", got {direction}"
+                )))
+                }
+            };
+            Ok(SortOption {
+                field_name,
+                direction,
+            })
+        })
+        .collect()
+}
+
+fn parse_count(key: &str, value: &Value) -> Result<usize, ApiError> {
+    value.as_u64().map(|count| count as usize).ok_or_else(|| {
+        ApiError::InvalidQuery(format!("{key} must be a non-negative integer, got {value}"))
+    })
+}
+
+/// A query whose fields are resolved to indexes in the record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundQuery {
+    pub filter: Option<BoundFilter>,
+    pub order_by: Vec<(usize, SortDirection)>,
+    pub limit: usize,
+    pub skip: usize,
+}
+
+impl BoundQuery {
+    pub fn matches(&self, values: &[Field]) -> bool {
+        self.filter
+            .as_ref()
+            .map_or(true, |filter| filter.matches(values))
+    }
+
+    pub fn compare(&self, left: &[Field], right: &[Field]) -> Ordering {
+        for (index, direction) in &self.order_by {
+            let ordering = left[*index].cmp(&right[*index]);
+            let ordering = match direction {
+                SortDirection::Ascending => ordering,
+                SortDirection::Descending => ordering.reverse(),
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+}
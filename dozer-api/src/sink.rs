@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{PortHandle, Sink, SinkFactory};
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::grpc_types::types::OperationType;
+use dozer_types::models::sink::ApiSinkConfig;
+use dozer_types::node::OpIdentifier;
+use dozer_types::parking_lot::RwLock;
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Field, Operation, Schema, TableOperation};
+
+use crate::store::{Event, Store, StoredRecord, Table};
+
+#[derive(Debug)]
+pub struct ApiSinkFactory {
+    config: ApiSinkConfig,
+    store: Arc<Store>,
+}
+
+impl ApiSinkFactory {
+    pub fn new(config: ApiSinkConfig, store: Arc<Store>) -> Self {
+        Self { config, store }
+    }
+
+    fn endpoint(&self) -> &str {
+        self.config
+            .endpoint
+            .as_deref()
+            .unwrap_or(&self.config.table_name)
+    }
+}
+
+#[async_trait]
+impl SinkFactory for ApiSinkFactory {
+    fn type_name(&self) -> String {
+        "api".to_string()
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_input_port_name(&self, _port: &PortHandle) -> String {
+        self.config.table_name.clone()
+    }
+
+    fn prepare(&self, input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        debug_assert!(input_schemas.len() == 1);
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        mut input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        let schema = input_schemas.remove(&DEFAULT_PORT_HANDLE).unwrap();
+        let endpoint = self.endpoint().to_string();
+        let table = self.store.create_table(endpoint.clone(), schema);
+        Ok(Box::new(ApiSink {
+            endpoint,
+            table,
+            store: self.store.clone(),
+        }))
+    }
+}
+
+/// Materializes its input in a table of the store.
+///
+/// The table lives in memory only, so the sink never resumes and the table is rebuilt from the sources on restart.
+#[derive(Debug)]
+pub(crate) struct ApiSink {
+    endpoint: String,
+    table: Arc<RwLock<Table>>,
+    store: Arc<Store>,
+}
+
+impl ApiSink {
+    fn event(&self, typ: OperationType, old: Option<StoredRecord>, new: StoredRecord) -> Event {
+        Event {
+            endpoint: self.endpoint.clone(),
+            typ,
+            old,
+            new,
+        }
+    }
+
+    fn insert(&self, table: &mut Table, values: Vec<Field>) -> Event {
+        match table.insert(values) {
+            (None, new) => self.event(OperationType::Insert, None, new),
+            (old, new) => self.event(OperationType::Update, old, new),
+        }
+    }
+}
+
+impl Sink for ApiSink {
+    fn commit(&mut self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
+        // Events are published after the table is unlocked, and only once the change is visible to queries.
+        let events = {
+            let mut table = self.table.write();
+            match op.op {
+                Operation::Insert { new } => vec![self.insert(&mut table, new.values)],
+                Operation::BatchInsert { new } => new
+                    .into_iter()
+                    .map(|record| self.insert(&mut table, record.values))
+                    .collect(),
+                Operation::Update { old, new } => match table.update(&old.values, new.values)? {
+                    (None, new) => vec![self.event(OperationType::Insert, None, new)],
+                    (old, new) => vec![self.event(OperationType::Update, old, new)],
+                },
+                Operation::Delete { old } => table
+                    .delete(&old.values)
+                    .map(|old| self.event(OperationType::Delete, None, old))
+                    .into_iter()
+                    .collect(),
+            }
+        };
+        for event in events {
+            self.store.publish(event);
+        }
+        Ok(())
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        _id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, _source_state: &[u8]) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(None)
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        Ok(None)
+    }
+
+    fn persists_op_id(&self) -> bool {
+        false
+    }
+
+    fn on_schema_change(&mut self, _port: PortHandle, schema: &Schema) -> Result<(), BoxedError> {
+        self.table.write().evolve(schema.clone());
+        Ok(())
+    }
+
+    fn on_truncate(&mut self, _port: PortHandle) -> Result<(), BoxedError> {
+        let deleted = self.table.write().clear();
+        for old in deleted {
+            self.store
+                .publish(self.event(OperationType::Delete, None, old));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,295 @@
+//! Evaluates read-only `SELECT` statements against the tables of the store.
+//!
+//! A query reads a single table, optionally filtered by `WHERE`, sorted by `ORDER BY` and paginated by
+//! `LIMIT` and `OFFSET`. Joins, grouping and aggregations are not supported.
+
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use dozer_core::tokio::runtime::Runtime;
+use dozer_sql_expression::builder::ExpressionBuilder;
+use dozer_sql_expression::execution::Expression;
+use dozer_sql_expression::sqlparser::ast::{
+    Expr, Offset, OrderByExpr, Query, Select, SelectItem, SetExpr, Statement, TableFactor,
+    Value as SqlValue,
+};
+use dozer_sql_expression::sqlparser::dialect::DozerDialect;
+use dozer_sql_expression::sqlparser::parser::Parser;
+use dozer_types::types::{Field, FieldDefinition, Record, Schema, SourceDefinition};
+
+use crate::errors::ApiError;
+use crate::store::Store;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlResult {
+    Rows {
+        fields: Vec<FieldDefinition>,
+        rows: Vec<Vec<Field>>,
+    },
+    /// A statement that is accepted for client compatibility but has no effect, with its command tag.
+    Command(&'static str),
+    Empty,
+}
+
+struct SortKey {
+    expression: Expression,
+    ascending: bool,
+    nulls_first: bool,
+}
+
+/// A `SELECT` bound to the schema of the table it reads.
+struct SelectPlan {
+    /// `None` for a `SELECT` without `FROM`, which evaluates its projection once.
+    endpoint: Option<String>,
+    schema: Schema,
+    projection: Vec<(String, Expression)>,
+    filter: Option<Expression>,
+    order_by: Vec<SortKey>,
+    limit: Option<usize>,
+    offset: usize,
+}
+
+pub async fn execute(
+    store: &Store,
+    sql: &str,
+    runtime: Arc<Runtime>,
+) -> Result<Vec<SqlResult>, ApiError> {
+    let statements = Parser::parse_sql(&DozerDialect {}, sql)?;
+    if statements.is_empty() {
+        return Ok(vec![SqlResult::Empty]);
+    }
+    let mut results = vec![];
+    for statement in statements {
+        let result = match statement {
+            Statement::Query(query) => {
+                let plan = plan(store, &query, runtime.clone()).await?;
+                execute_plan(store, plan)?
+            }
+            // Clients set session parameters on connect.
+            Statement::SetVariable { .. } => SqlResult::Command("SET"),
+            statement => {
+                return Err(ApiError::UnsupportedSql(format!(
+                    "only SELECT queries are supported, got {statement}"
+                )))
+            }
+        };
+        results.push(result);
+    }
+    Ok(results)
+}
+
+async fn plan(store: &Store, query: &Query, runtime: Arc<Runtime>) -> Result<SelectPlan, ApiError> {
+    if query.with.is_some() {
+        return Err(ApiError::UnsupportedSql("WITH".to_string()));
+    }
+    let SetExpr::Select(select) = query.body.as_ref() else {
+        return Err(ApiError::UnsupportedSql(format!("{}", query.body)));
+    };
+    let (endpoint, schema) = from_table(store, select)?;
+    if select.distinct.is_some() {
+        return Err(ApiError::UnsupportedSql("DISTINCT".to_string()));
+    }
+    if !select.group_by.is_empty() || select.having.is_some() {
+        return Err(ApiError::UnsupportedSql("GROUP BY".to_string()));
+    }
+
+    let mut projection = vec![];
+    for item in &select.projection {
+        match item {
+            SelectItem::Wildcard(_) => projection.extend(
+                schema
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(index, field)| (field.name.clone(), Expression::Column { index })),
+            ),
+            SelectItem::UnnamedExpr(expr) => {
+                let name = match expr {
+                    Expr::Identifier(ident) => ident.value.clone(),
+                    Expr::CompoundIdentifier(idents) => idents
+                        .last()
+                        .map_or_else(String::new, |ident| ident.value.clone()),
+                    expr => expr.to_string(),
+                };
+                projection.push((name, build(expr, &schema, runtime.clone()).await?));
+            }
+            SelectItem::ExprWithAlias { expr, alias } => {
+                projection.push((
+                    alias.value.clone(),
+                    build(expr, &schema, runtime.clone()).await?,
+                ));
+            }
+            SelectItem::QualifiedWildcard(name, _) => {
+                return Err(ApiError::UnsupportedSql(format!("{name}.*")))
+            }
+        }
+    }
+
+    let filter = match &select.selection {
+        Some(expr) => Some(build(expr, &schema, runtime.clone()).await?),
+        None => None,
+    };
+    let mut order_by = vec![];
+    for OrderByExpr {
+        expr,
+        asc,
+        nulls_first,
+        ..
+    } in &query.order_by
+    {
+        let ascending = asc.unwrap_or(true);
+        order_by.push(SortKey {
+            expression: build(expr, &schema, runtime.clone()).await?,
+            ascending,
+            // Nulls sort as if greater than any value, as in Postgres.
+            nulls_first: nulls_first.unwrap_or(!ascending),
+        });
+    }
+
+    Ok(SelectPlan {
+        endpoint,
+        schema,
+        projection,
+        filter,
+        order_by,
+        limit: query.limit.as_ref().map(count).transpose()?,
+        offset: query
+            .offset
+            .as_ref()
+            .map(|Offset { value, .. }| count(value))
+            .transpose()?
+            .unwrap_or(0),
+    })
+}
+
+fn from_table(store: &Store, select: &Select) -> Result<(Option<String>, Schema), ApiError> {
+    match select.from.as_slice() {
+        [] => Ok((None, Schema::default())),
+        [table] if table.joins.is_empty() => {
+            let TableFactor::Table { name, .. } = &table.relation else {
+                return Err(ApiError::UnsupportedSql(table.relation.to_string()));
+            };
+            let endpoint = name
+                .0
+                .iter()
+                .map(ExpressionBuilder::normalize_ident)
+                .collect::<Vec<_>>()
+                .join(".");
+            let schema = store
+                .table(&endpoint)
+                .ok_or_else(|| ApiError::EndpointNotFound(endpoint.clone()))?
+                .read()
+                .schema()
+                .clone();
+            Ok((Some(endpoint), schema))
+        }
+        _ => Err(ApiError::UnsupportedSql("JOIN".to_string())),
+    }
+}
+
+async fn build(
+    expr: &Expr,
+    schema: &Schema,
+    runtime: Arc<Runtime>,
+) -> Result<Expression, ApiError> {
+    // Aggregations are not parsed, so aggregate functions fail as unknown functions.
+    Ok(ExpressionBuilder::new(0, runtime)
+        .build(false, expr, schema, &[])
+        .await?)
+}
+
+fn count(expr: &Expr) -> Result<usize, ApiError> {
+    match expr {
+        Expr::Value(SqlValue::Number(number, _)) => number
+            .parse()
+            .map_err(|_| ApiError::UnsupportedSql(format!("LIMIT or OFFSET {number}"))),
+        expr => Err(ApiError::UnsupportedSql(format!("LIMIT or OFFSET {expr}"))),
+    }
+}
+
+fn compare(order_by: &[SortKey], left: &[Field], right: &[Field]) -> Ordering {
+    for (key, (left, right)) in order_by.iter().zip(left.iter().zip(right)) {
+        let ordering = match (left, right) {
+            (Field::Null, Field::Null) => Ordering::Equal,
+            (Field::Null, _) if key.nulls_first => Ordering::Less,
+            (Field::Null, _) => Ordering::Greater,
+            (_, Field::Null) if key.nulls_first => Ordering::Greater,
+            (_, Field::Null) => Ordering::Less,
+            (left, right) if key.ascending => left.cmp(right),
+            (left, right) => right.cmp(left),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+fn execute_plan(store: &Store, mut plan: SelectPlan) -> Result<SqlResult, ApiError> {
+    let fields = plan
+        .projection
+        .iter()
+        .map(|(name, expression)| {
+            let typ = expression.get_type(&plan.schema)?;
+            Ok(FieldDefinition::new(
+                name.clone(),
+                typ.return_type,
+                typ.nullable,
+                SourceDefinition::Dynamic,
+            ))
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    let records = match &plan.endpoint {
+        Some(endpoint) => {
+            let table = store
+                .table(endpoint)
+                .ok_or_else(|| ApiError::EndpointNotFound(endpoint.clone()))?;
+            let table = table.read();
+            // The table is replaced when the pipeline restarts.
+            if table.schema() != &plan.schema {
+                return Err(ApiError::SchemaChanged(endpoint.clone()));
+            }
+            let mut records = vec![];
+            for record in table.records() {
+                let record = Record::new(record.values.clone());
+                let matches = match &mut plan.filter {
+                    Some(filter) => filter.evaluate(&record, &plan.schema)? == Field::Boolean(true),
+                    None => true,
+                };
+                if matches {
+                    records.push(record);
+                }
+            }
+            records
+        }
+        None => vec![Record::new(vec![])],
+    };
+
+    let mut rows = records
+        .into_iter()
+        .map(|record| {
+            let keys = plan
+                .order_by
+                .iter_mut()
+                .map(|key| key.expression.evaluate(&record, &plan.schema))
+                .collect::<Result<Vec<_>, _>>()?;
+            let values = plan
+                .projection
+                .iter_mut()
+                .map(|(_, expression)| expression.evaluate(&record, &plan.schema))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((keys, values))
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+    if !plan.order_by.is_empty() {
+        rows.sort_by(|(left, _), (right, _)| compare(&plan.order_by, left, right));
+    }
+    let rows = rows
+        .into_iter()
+        .skip(plan.offset)
+        .take(plan.limit.unwrap_or(usize::MAX))
+        .map(|(_, values)| values)
+        .collect();
+    Ok(SqlResult::Rows { fields, rows })
+}
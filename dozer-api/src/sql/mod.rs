@@ -0,0 +1,3 @@
+pub mod executor;
+pub mod server;
+pub mod types;
@@ -0,0 +1,150 @@
+//! A PostgreSQL wire protocol server answering simple queries with [`execute`].
+//!
+//! There's no authentication, and the extended query protocol (prepared statements) is not supported.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use dozer_core::tokio::{self, net::TcpListener, runtime::Runtime};
+use dozer_types::log::{error, info};
+use dozer_types::models::api_config::{default_host, default_sql_port, PgWireOptions};
+use dozer_types::tonic::async_trait;
+use dozer_types::types::FieldDefinition;
+use futures::stream;
+use pgwire::api::auth::noop::NoopStartupHandler;
+use pgwire::api::query::{PlaceholderExtendedQueryHandler, SimpleQueryHandler};
+use pgwire::api::results::{DataRowEncoder, FieldFormat, FieldInfo, QueryResponse, Response, Tag};
+use pgwire::api::{ClientInfo, MakeHandler, StatelessMakeHandler};
+use pgwire::error::{ErrorInfo, PgWireError, PgWireResult};
+use pgwire::tokio::process_socket;
+
+use super::executor::{execute, SqlResult};
+use super::types::{pg_text, pg_type};
+use crate::errors::ApiError;
+use crate::store::Store;
+
+struct SqlQueryHandler {
+    store: Arc<Store>,
+    runtime: Arc<Runtime>,
+}
+
+#[async_trait]
+impl SimpleQueryHandler for SqlQueryHandler {
+    async fn do_query<'a, C>(
+        &self,
+        _client: &mut C,
+        query: &'a str,
+    ) -> PgWireResult<Vec<Response<'a>>>
+    where
+        C: ClientInfo + Unpin + Send + Sync,
+    {
+        let results = execute(&self.store, query, self.runtime.clone())
+            .await
+            .map_err(pg_error)?;
+        results.into_iter().map(response).collect()
+    }
+}
+
+fn response<'a>(result: SqlResult) -> PgWireResult<Response<'a>> {
+    match result {
+        SqlResult::Rows { fields, rows } => {
+            let schema = Arc::new(fields.iter().map(field_info).collect::<Vec<_>>());
+            let rows = rows
+                .into_iter()
+                .map(|row| {
+                    let mut encoder = DataRowEncoder::new(schema.clone());
+                    for field in &row {
+                        encoder.encode_field(&pg_text(field))?;
+                    }
+                    encoder.finish()
+                })
+                .collect::<Vec<_>>();
+            Ok(Response::Query(QueryResponse::new(
+                schema,
+                stream::iter(rows),
+            )))
+        }
+        SqlResult::Command(tag) => Ok(Response::Execution(Tag::new_for_execution(tag, None))),
+        SqlResult::Empty => Ok(Response::EmptyQuery),
+    }
+}
+
+fn field_info(field: &FieldDefinition) -> FieldInfo {
+    FieldInfo::new(
+        field.name.clone(),
+        None,
+        None,
+        pg_type(field.typ),
+        FieldFormat::Text,
+    )
+}
+
+fn pg_error(error: ApiError) -> PgWireError {
+    let code = match &error {
+        ApiError::EndpointNotFound(_) | ApiError::SchemaChanged(_) => "42P01",
+        ApiError::SqlParse(_) => "42601",
+        ApiError::UnsupportedSql(_) => "0A000",
+        ApiError::Expression(_) => "42000",
+        _ => "XX000",
+    };
+    PgWireError::UserError(Box::new(ErrorInfo::new(
+        "ERROR".to_string(),
+        code.to_string(),
+        error.to_string(),
+    )))
+}
+
+/// Serves the tables of `store` over the PostgreSQL wire protocol until `shutdown` completes.
+pub async fn serve(
+    store: Arc<Store>,
+    options: &PgWireOptions,
+    runtime: Arc<Runtime>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), ApiError> {
+    let host = options.host.clone().unwrap_or_else(default_host);
+    let port = options.port.unwrap_or_else(default_sql_port);
+    let address = format!("{host}:{port}");
+    let listener = TcpListener::bind(&address)
+        .await
+        .map_err(|e| ApiError::Bind(address.clone(), e))?;
+
+    let startup_handler = Arc::new(StatelessMakeHandler::new(Arc::new(NoopStartupHandler)));
+    let query_handler = Arc::new(StatelessMakeHandler::new(Arc::new(SqlQueryHandler {
+        store,
+        runtime,
+    })));
+    let extended_query_handler = Arc::new(StatelessMakeHandler::new(Arc::new(
+        PlaceholderExtendedQueryHandler,
+    )));
+
+    info!("Starting pgwire server on {address}");
+    tokio::pin!(shutdown);
+    loop {
+        let socket = tokio::select! {
+            _ = &mut shutdown => return Ok(()),
+            accepted = listener.accept() => match accepted {
+                Ok((socket, _)) => socket,
+                Err(e) => {
+                    error!("Failed to accept pgwire connection: {e}");
+                    continue;
+                }
+            },
+        };
+        let startup_handler = startup_handler.make();
+        let query_handler = query_handler.make();
+        let extended_query_handler = extended_query_handler.make();
+        tokio::spawn(async move {
+            if let Err(e) = process_socket(
+                socket,
+                None,
+                startup_handler,
+                query_handler,
+                extended_query_handler,
+            )
+            .await
+            {
+                error!("pgwire connection failed: {e}");
+            }
+        });
+    }
+}
@@ -0,0 +1,72 @@
+//! Postgres representation of Dozer values. Values are always sent in the text format.
+
+use dozer_types::json_types::json_to_string;
+use dozer_types::types::{Field, FieldType};
+use pgwire::api::Type;
+
+/// The Postgres type a field is sent as. Unsigned and 128 bit integers don't fit `int8` and are sent as `numeric`.
+pub fn pg_type(typ: FieldType) -> Type {
+    match typ {
+        FieldType::UInt | FieldType::U128 | FieldType::I128 | FieldType::Decimal => Type::NUMERIC,
+        FieldType::Int => Type::INT8,
+        FieldType::Int8 => Type::INT2,
+        FieldType::Float => Type::FLOAT8,
+        FieldType::Boolean => Type::BOOL,
+        FieldType::String => Type::VARCHAR,
+        FieldType::Text => Type::TEXT,
+        FieldType::Binary => Type::BYTEA,
+        FieldType::Timestamp => Type::TIMESTAMPTZ,
+        FieldType::Date => Type::DATE,
+        FieldType::Json => Type::JSONB,
+        FieldType::Point => Type::POINT,
+        FieldType::Duration => Type::INTERVAL,
+    }
+}
+
+/// The text format of a value of the type returned by [`pg_type`], `None` for null.
+pub fn pg_text(field: &Field) -> Option<String> {
+    let text = match field {
+        Field::UInt(value) => value.to_string(),
+        Field::U128(value) => value.to_string(),
+        Field::Int(value) => value.to_string(),
+        Field::Int8(value) => value.to_string(),
+        Field::I128(value) => value.to_string(),
+        Field::Float(value) => match value.0 {
+            value if value.is_nan() => "NaN".to_string(),
+            value if value == f64::INFINITY => "Infinity".to_string(),
+            value if value == f64::NEG_INFINITY => "-Infinity".to_string(),
+            value => value.to_string(),
+        },
+        Field::Boolean(value) => if *value { "t" } else { "f" }.to_string(),
+        Field::String(value) | Field::Text(value) => value.clone(),
+        Field::Binary(value) => {
+            let mut text = String::with_capacity(2 + value.len() * 2);
+            text.push_str("\\x");
+            for byte in value {
+                text.push_str(&format!("{byte:02x}"));
+            }
+            text
+        }
+        Field::Decimal(value) => value.to_string(),
+        Field::Timestamp(value) => value
+            .naive_utc()
+            .format("%Y-%m-%d %H:%M:%S%.f+00")
+            .to_string(),
+        Field::Date(value) => value.format("%Y-%m-%d").to_string(),
+        Field::Json(value) => json_to_string(value),
+        Field::Point(point) => format!("({},{})", point.0.x().0, point.0.y().0),
+        Field::Duration(duration) => {
+            let duration = duration.0;
+            let seconds = duration.as_secs();
+            format!(
+                "{:02}:{:02}:{:02}.{:06}",
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60,
+                duration.subsec_micros()
+            )
+        }
+        Field::Null => return None,
+    };
+    Some(text)
+}
@@ -0,0 +1,343 @@
+//! The in-memory tables served by the APIs.
+
+use std::collections::hash_map::{Entry, RandomState};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+use std::sync::Arc;
+
+use dozer_core::dag_schemas::evolve_record;
+use dozer_core::tokio::sync::broadcast;
+use dozer_types::grpc_types::types::OperationType;
+use dozer_types::parking_lot::RwLock;
+use dozer_types::types::{Field, Record, Schema};
+
+use crate::errors::ApiError;
+use crate::query::{BoundFilter, BoundQuery, SortDirection};
+
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredRecord {
+    /// Assigned on insert, kept across updates.
+    pub id: u64,
+    /// Starts at 1 and increases with every update of the record.
+    pub version: u32,
+    pub values: Vec<Field>,
+}
+
+/// A table keyed by the primary index of its schema.
+///
+/// Records of a table without primary index are looked up by all their values.
+#[derive(Debug)]
+pub struct Table {
+    schema: Schema,
+    records: BTreeMap<u64, StoredRecord>,
+    /// Ordered, so queries sorting by the primary key don't sort.
+    primary_index: BTreeMap<Vec<Field>, u64>,
+    /// Ids of the records of a table without primary index, by the hash of their values.
+    value_index: HashMap<u64, Vec<u64>>,
+    hasher: RandomState,
+    next_id: u64,
+}
+
+impl Table {
+    pub fn new(schema: Schema) -> Self {
+        Self {
+            schema,
+            records: BTreeMap::new(),
+            primary_index: BTreeMap::new(),
+            value_index: HashMap::new(),
+            hasher: RandomState::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// The records in insertion order.
+    pub fn records(&self) -> impl Iterator<Item = &StoredRecord> {
+        self.records.values()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&StoredRecord> {
+        self.records.get(&id)
+    }
+
+    fn key(&self, values: &[Field]) -> Option<Vec<Field>> {
+        if self.schema.primary_index.is_empty() {
+            None
+        } else {
+            Some(
+                self.schema
+                    .primary_index
+                    .iter()
+                    .map(|index| values[*index].clone())
+                    .collect(),
+            )
+        }
+    }
+
+    fn find(&self, values: &[Field]) -> Option<u64> {
+        match self.key(values) {
+            Some(key) => self.primary_index.get(&key).copied(),
+            None => self
+                .value_index
+                .get(&self.hasher.hash_one(values))?
+                .iter()
+                .copied()
+                .find(|id| self.records[id].values == values),
+        }
+    }
+
+    /// Keeps the ids of records with equal values in insertion order, so the first inserted one is found first.
+    fn index_values(&mut self, id: u64, values: &[Field]) {
+        let ids = self
+            .value_index
+            .entry(self.hasher.hash_one(values))
+            .or_default();
+        ids.insert(ids.partition_point(|other| *other < id), id);
+    }
+
+    fn unindex_values(&mut self, id: u64, values: &[Field]) {
+        if let Entry::Occupied(mut entry) = self.value_index.entry(self.hasher.hash_one(values)) {
+            entry.get_mut().retain(|other| *other != id);
+            if entry.get().is_empty() {
+                entry.remove();
+            }
+        }
+    }
+
+    /// Inserts a record, replacing the one with the same primary key if any.
+    ///
+    /// Returns the replaced record and the stored one.
+    pub fn insert(&mut self, values: Vec<Field>) -> (Option<StoredRecord>, StoredRecord) {
+        match self.key(&values) {
+            Some(key) => match self.primary_index.get(&key) {
+                Some(id) => self.replace(*id, values),
+                None => {
+                    let record = self.push(values);
+                    self.primary_index.insert(key, record.id);
+                    (None, record)
+                }
+            },
+            None => {
+                let record = self.push(values);
+                self.index_values(record.id, &record.values);
+                (None, record)
+            }
+        }
+    }
+
+    /// Updates the record matching `old`, inserting `new` if there's none.
+    ///
+    /// Returns the updated record and the stored one. Fails if the primary key changes to the one of another record.
+    pub fn update(
+        &mut self,
+        old: &[Field],
+        new: Vec<Field>,
+    ) -> Result<(Option<StoredRecord>, StoredRecord), ApiError> {
+        let Some(id) = self.find(old) else {
+            return Ok(self.insert(new));
+        };
+        match self.key(&new) {
+            Some(new_key) => {
+                if self
+                    .primary_index
+                    .get(&new_key)
+                    .is_some_and(|other| *other != id)
+                {
+                    return Err(ApiError::DuplicateKey(new_key));
+                }
+                if let Some(old_key) = self.key(old) {
+                    self.primary_index.remove(&old_key);
+                }
+                self.primary_index.insert(new_key, id);
+            }
+            None => {
+                self.unindex_values(id, old);
+                self.index_values(id, &new);
+            }
+        }
+        Ok(self.replace(id, new))
+    }
+
+    /// Deletes the record matching `old`.
+    pub fn delete(&mut self, old: &[Field]) -> Option<StoredRecord> {
+        let id = self.find(old)?;
+        match self.key(old) {
+            Some(key) => {
+                self.primary_index.remove(&key);
+            }
+            None => self.unindex_values(id, old),
+        }
+        self.records.remove(&id)
+    }
+
+    /// Deletes all the records, returning them in insertion order.
+    ///
+    /// Ids of records inserted later are not reused.
+    pub fn clear(&mut self) -> Vec<StoredRecord> {
+        self.primary_index.clear();
+        self.value_index.clear();
+        std::mem::take(&mut self.records).into_values().collect()
+    }
+
+    /// Converts the records to `schema`, the result of a schema change of the table's input.
+    ///
+    /// Fields keep their index; appended fields are `Null` in existing records.
+    pub fn evolve(&mut self, schema: Schema) {
+        for record in self.records.values_mut() {
+            let values = std::mem::take(&mut record.values);
+            record.values = evolve_record(Record::new(values), &schema).values;
+        }
+        self.schema = schema;
+        // Widened values compare and hash differently.
+        self.primary_index.clear();
+        self.value_index.clear();
+        let records = std::mem::take(&mut self.records);
+        for record in records.values() {
+            match self.key(&record.values) {
+                Some(key) => {
+                    self.primary_index.insert(key, record.id);
+                }
+                None => self.index_values(record.id, &record.values),
+            }
+        }
+        self.records = records;
+    }
+
+    pub fn count(&self, filter: Option<&BoundFilter>) -> usize {
+        match filter {
+            Some(filter) => self
+                .records
+                .values()
+                .filter(|record| filter.matches(&record.values))
+                .count(),
+            None => self.records.len(),
+        }
+    }
+
+    /// Records that match the filter of `query`, sorted and paginated.
+    ///
+    /// Records are in insertion order without `order_by`, and in primary key order when `order_by` is a prefix of the primary key.
+    pub fn query(&self, query: &BoundQuery) -> Vec<&StoredRecord> {
+        let matches = |record: &&StoredRecord| query.matches(&record.values);
+        let records: Box<dyn Iterator<Item = &StoredRecord> + '_> = if query.order_by.is_empty() {
+            Box::new(self.records.values().filter(matches))
+        } else if let Some(direction) = self.primary_index_order(&query.order_by) {
+            let records = self
+                .primary_index
+                .values()
+                .map(|id| &self.records[id])
+                .filter(matches);
+            match direction {
+                SortDirection::Ascending => Box::new(records),
+                SortDirection::Descending => Box::new(records.rev()),
+            }
+        } else {
+            let mut records = self.records.values().filter(matches).collect::<Vec<_>>();
+            records.sort_by(|left, right| query.compare(&left.values, &right.values));
+            Box::new(records.into_iter())
+        };
+        records.skip(query.skip).take(query.limit).collect()
+    }
+
+    /// The direction to iterate the primary index in, if `order_by` sorts by a prefix of the primary key in one direction.
+    fn primary_index_order(&self, order_by: &[(usize, SortDirection)]) -> Option<SortDirection> {
+        let (_, direction) = order_by.first()?;
+        let is_prefix = order_by.len() <= self.schema.primary_index.len()
+            && order_by
+                .iter()
+                .zip(&self.schema.primary_index)
+                .all(|((index, other), key)| index == key && other == direction);
+        is_prefix.then_some(*direction)
+    }
+
+    fn push(&mut self, values: Vec<Field>) -> StoredRecord {
+        let record = StoredRecord {
+            id: self.next_id,
+            version: 1,
+            values,
+        };
+        self.next_id += 1;
+        self.records.insert(record.id, record.clone());
+        record
+    }
+
+    fn replace(&mut self, id: u64, values: Vec<Field>) -> (Option<StoredRecord>, StoredRecord) {
+        let old = self.records.remove(&id);
+        let record = StoredRecord {
+            id,
+            version: old.as_ref().map_or(1, |old| old.version + 1),
+            values,
+        };
+        self.records.insert(id, record.clone());
+        (old, record)
+    }
+}
+
+/// A change to a table, published to the `OnEvent` subscribers.
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub endpoint: String,
+    pub typ: OperationType,
+    /// The record before an update.
+    pub old: Option<StoredRecord>,
+    /// The record after an insert or update, or the deleted record.
+    pub new: StoredRecord,
+}
+
+/// The tables of all API sinks, shared by the sinks and the servers.
+#[derive(Debug)]
+pub struct Store {
+    tables: RwLock<HashMap<String, Arc<RwLock<Table>>>>,
+    events: broadcast::Sender<Event>,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self {
+            tables: Default::default(),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        }
+    }
+}
+
+impl Store {
+    /// Creates an empty table, replacing any table of the same endpoint.
+    pub fn create_table(&self, endpoint: String, schema: Schema) -> Arc<RwLock<Table>> {
+        let table = Arc::new(RwLock::new(Table::new(schema)));
+        self.tables.write().insert(endpoint, table.clone());
+        table
+    }
+
+    pub fn table(&self, endpoint: &str) -> Option<Arc<RwLock<Table>>> {
+        self.tables.read().get(endpoint).cloned()
+    }
+
+    /// The endpoint names, sorted.
+    pub fn endpoints(&self) -> Vec<String> {
+        let mut endpoints = self.tables.read().keys().cloned().collect::<Vec<_>>();
+        endpoints.sort();
+        endpoints
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    pub fn publish(&self, event: Event) {
+        // There may be no subscriber.
+        let _ = self.events.send(event);
+    }
+}
@@ -0,0 +1,624 @@
+use std::sync::Arc;
+
+use dozer_core::event::EventHub;
+use dozer_core::node::{Sink, SinkFactory};
+use dozer_core::tokio::runtime::Runtime;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_types::chrono::{DateTime, NaiveDate};
+use dozer_types::grpc_types::common::common_grpc_service_server::CommonGrpcService;
+use dozer_types::grpc_types::common::{
+    GetEndpointsRequest, GetFieldsRequest, OnEventRequest, QueryRequest,
+};
+use dozer_types::grpc_types::conversions::field_to_grpc;
+use dozer_types::grpc_types::types::{EventFilter, EventType, OperationType};
+use dozer_types::models::sink::ApiSinkConfig;
+use dozer_types::serde_json::json;
+use dozer_types::tonic::{Code, Request};
+use dozer_types::types::{
+    DozerDuration, Field, FieldDefinition, FieldType, Operation, Record, Schema, TableOperation,
+    TimeUnit,
+};
+use pgwire::api::Type;
+use tokio_stream::StreamExt;
+
+use crate::errors::ApiError;
+use crate::grpc::CommonService;
+use crate::query::{
+    BoundFilter, FilterExpression, Operator, QueryExpression, SortDirection, SortOption,
+};
+use crate::sql::executor::{execute, SqlResult};
+use crate::sql::types::{pg_text, pg_type};
+use crate::store::{Store, Table};
+use crate::ApiSinkFactory;
+
+fn field(name: &str, typ: FieldType) -> FieldDefinition {
+    FieldDefinition {
+        name: name.to_string(),
+        typ,
+        nullable: true,
+        source: Default::default(),
+        description: None,
+    }
+}
+
+fn get_schema() -> Schema {
+    Schema {
+        fields: vec![
+            field("id", FieldType::UInt),
+            field("name", FieldType::String),
+            field("age", FieldType::Int),
+        ],
+        primary_index: vec![0],
+    }
+}
+
+fn values(id: u64, name: &str, age: i64) -> Vec<Field> {
+    vec![
+        Field::UInt(id),
+        Field::String(name.to_string()),
+        Field::Int(age),
+    ]
+}
+
+#[test]
+fn test_parse_query() {
+    let query = QueryExpression::parse(json!({
+        "$filter": { "$and": [{ "age": { "$gte": 18 } }, { "name": "John" }] },
+        "$order_by": [{ "age": "desc" }, { "name": "asc" }],
+        "$limit": 10,
+        "$skip": 20,
+    }))
+    .unwrap();
+    assert_eq!(
+        query,
+        QueryExpression {
+            filter: Some(FilterExpression::And(vec![
+                FilterExpression::Simple("age".to_string(), Operator::Gte, json!(18)),
+                FilterExpression::Simple("name".to_string(), Operator::Eq, json!("John")),
+            ])),
+            order_by: vec![
+                SortOption {
+                    field_name: "age".to_string(),
+                    direction: SortDirection::Descending,
+                },
+                SortOption {
+                    field_name: "name".to_string(),
+                    direction: SortDirection::Ascending,
+                },
+            ],
+            limit: Some(10),
+            skip: 20,
+        }
+    );
+
+    let bound = query.bind(&get_schema(), 50).unwrap();
+    assert_eq!(
+        bound.filter,
+        Some(BoundFilter::And(vec![
+            BoundFilter::Simple(2, Operator::Gte, Field::Int(18)),
+            BoundFilter::Simple(1, Operator::Eq, Field::String("John".to_string())),
+        ]))
+    );
+    assert_eq!(
+        bound.order_by,
+        vec![
+            (2, SortDirection::Descending),
+            (1, SortDirection::Ascending)
+        ]
+    );
+
+    assert!(QueryExpression::parse(json!({ "$where": {} })).is_err());
+    assert!(QueryExpression::parse(json!({ "$filter": { "age": { "$ne": 1 } } })).is_err());
+    assert!(QueryExpression::parse(json!({ "$limit": -1 })).is_err());
+    assert!(QueryExpression::parse(json!({ "$filter": { "email": 1 } }))
+        .unwrap()
+        .bind(&get_schema(), 50)
+        .is_err());
+    assert!(
+        QueryExpression::parse(json!({ "$filter": { "age": "old" } }))
+            .unwrap()
+            .bind(&get_schema(), 50)
+            .is_err()
+    );
+    assert!(
+        QueryExpression::parse(json!({ "$filter": { "age": { "$contains": 1 } } }))
+            .unwrap()
+            .bind(&get_schema(), 50)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_table_operations() {
+    let mut table = Table::new(get_schema());
+    let (old, alice) = table.insert(values(1, "alice", 30));
+    assert_eq!(old, None);
+    assert_eq!((alice.id, alice.version), (0, 1));
+    table.insert(values(2, "bob", 25));
+
+    // Same primary key replaces the record.
+    let (old, alice) = table.insert(values(1, "alice", 31));
+    assert_eq!(old.unwrap().values, values(1, "alice", 30));
+    assert_eq!((alice.id, alice.version), (0, 2));
+
+    // Primary key change keeps the record id.
+    let (old, alice) = table
+        .update(&values(1, "alice", 31), values(3, "alice", 31))
+        .unwrap();
+    assert_eq!(old.unwrap().version, 2);
+    assert_eq!((alice.id, alice.version), (0, 3));
+    assert_eq!(table.len(), 2);
+
+    // Primary key change to the key of another record fails, leaving both records.
+    assert!(matches!(
+        table.update(&values(3, "alice", 31), values(2, "alice", 31)),
+        Err(ApiError::DuplicateKey(_))
+    ));
+    assert_eq!(table.len(), 2);
+    assert_eq!(table.get(0).unwrap().values, values(3, "alice", 31));
+
+    assert!(table.delete(&values(1, "alice", 31)).is_none());
+    assert_eq!(table.delete(&values(3, "alice", 31)).unwrap().id, 0);
+    assert_eq!(table.len(), 1);
+
+    // Without primary index, records are matched on all their values.
+    let mut table = Table::new(Schema {
+        primary_index: vec![],
+        ..get_schema()
+    });
+    table.insert(values(1, "alice", 30));
+    table.insert(values(1, "alice", 30));
+    assert_eq!(table.len(), 2);
+    assert!(table.delete(&values(1, "alice", 31)).is_none());
+    assert!(table.delete(&values(1, "alice", 30)).is_some());
+    assert_eq!(table.len(), 1);
+
+    // Updates match the first inserted of equal records.
+    table.insert(values(1, "alice", 30));
+    let (old, alice) = table
+        .update(&values(1, "alice", 30), values(1, "alice", 31))
+        .unwrap();
+    assert_eq!(old.unwrap().id, 1);
+    assert_eq!((alice.id, alice.version), (1, 2));
+    assert_eq!(table.delete(&values(1, "alice", 31)).unwrap().id, 1);
+    assert_eq!(table.delete(&values(1, "alice", 30)).unwrap().id, 2);
+    assert!(table.is_empty());
+}
+
+#[test]
+fn test_table_clear() {
+    let mut table = Table::new(get_schema());
+    table.insert(values(1, "alice", 30));
+    table.insert(values(2, "bob", 25));
+    let deleted = table.clear();
+    assert_eq!(
+        deleted.iter().map(|record| record.id).collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+    assert!(table.is_empty());
+
+    // The primary index is cleared too, and ids keep increasing.
+    let (old, alice) = table.insert(values(1, "alice", 30));
+    assert_eq!(old, None);
+    assert_eq!((alice.id, alice.version), (2, 1));
+}
+
+#[test]
+fn test_table_schema_change() {
+    let mut table = Table::new(get_schema());
+    table.insert(values(1, "alice", 30));
+
+    let mut schema = get_schema();
+    schema.fields[0].typ = FieldType::U128;
+    schema.fields.push(field("email", FieldType::String));
+    table.evolve(schema.clone());
+    assert_eq!(table.schema(), &schema);
+    assert_eq!(
+        table.get(0).unwrap().values,
+        vec![
+            Field::U128(1),
+            Field::String("alice".to_string()),
+            Field::Int(30),
+            Field::Null,
+        ]
+    );
+
+    // The primary index is rebuilt with the widened keys.
+    let (old, bob) = table.insert(vec![
+        Field::U128(1),
+        Field::String("bob".to_string()),
+        Field::Int(25),
+        Field::String("bob@example.com".to_string()),
+    ]);
+    assert_eq!(old.unwrap().id, 0);
+    assert_eq!((bob.id, bob.version), (0, 2));
+    assert_eq!(table.len(), 1);
+}
+
+#[test]
+fn test_table_query() {
+    let mut table = Table::new(get_schema());
+    for (id, name, age) in [
+        (1, "alice", 30),
+        (2, "bob", 25),
+        (3, "carol", 35),
+        (4, "dan", 25),
+    ] {
+        table.insert(values(id, name, age));
+    }
+    table.insert(vec![
+        Field::UInt(5),
+        Field::String("erin".to_string()),
+        Field::Null,
+    ]);
+
+    let query = |query| {
+        let query = QueryExpression::parse(query)
+            .unwrap()
+            .bind(table.schema(), 50)
+            .unwrap();
+        table
+            .query(&query)
+            .into_iter()
+            .map(|record| record.values[0].clone())
+            .collect::<Vec<_>>()
+    };
+    let ids = |ids: &[u64]| ids.iter().map(|id| Field::UInt(*id)).collect::<Vec<_>>();
+
+    assert_eq!(query(json!({})), ids(&[1, 2, 3, 4, 5]));
+    assert_eq!(query(json!({ "$filter": { "age": 25 } })), ids(&[2, 4]));
+    // Null is neither less nor greater than a value.
+    assert_eq!(
+        query(json!({ "$filter": { "age": { "$gt": 25 } } })),
+        ids(&[1, 3])
+    );
+    assert_eq!(query(json!({ "$filter": { "age": null } })), ids(&[5]));
+    assert_eq!(
+        query(json!({ "$filter": { "age": { "$gte": 25, "$lt": 35 } } })),
+        ids(&[1, 2, 4])
+    );
+    assert_eq!(
+        query(json!({ "$filter": { "name": { "$contains": "a" } } })),
+        ids(&[1, 3, 4])
+    );
+    assert_eq!(
+        query(json!({ "$filter": { "$and": [{ "age": 25 }, { "name": "dan" }] } })),
+        ids(&[4])
+    );
+    assert_eq!(
+        query(json!({ "$order_by": [{ "age": "desc" }, { "name": "desc" }] })),
+        ids(&[5, 3, 1, 4, 2])
+    );
+    assert_eq!(
+        query(json!({ "$order_by": { "age": "asc" }, "$skip": 1, "$limit": 2 })),
+        ids(&[4, 1])
+    );
+    // Ordering by the primary key iterates the primary index.
+    assert_eq!(
+        query(json!({ "$order_by": { "id": "desc" }, "$skip": 1, "$limit": 2 })),
+        ids(&[4, 3])
+    );
+    assert_eq!(
+        query(json!({ "$filter": { "age": 25 }, "$order_by": { "id": "asc" } })),
+        ids(&[2, 4])
+    );
+
+    let filter = FilterExpression::parse(json!({ "age": 25 }))
+        .unwrap()
+        .bind(table.schema())
+        .unwrap();
+    assert_eq!(table.count(Some(&filter)), 2);
+    assert_eq!(table.count(None), 5);
+}
+
+fn build_sink(runtime: &Runtime, store: &Arc<Store>) -> Box<dyn Sink> {
+    let factory = ApiSinkFactory::new(
+        ApiSinkConfig {
+            table_name: "users".to_string(),
+            endpoint: Some("people".to_string()),
+        },
+        store.clone(),
+    );
+    runtime
+        .block_on(factory.build(
+            [(DEFAULT_PORT_HANDLE, get_schema())].into_iter().collect(),
+            EventHub::new(10),
+        ))
+        .unwrap()
+}
+
+fn operation(op: Operation) -> TableOperation {
+    TableOperation {
+        id: None,
+        op,
+        port: DEFAULT_PORT_HANDLE,
+    }
+}
+
+fn query_request(query: Option<&str>) -> Request<QueryRequest> {
+    Request::new(QueryRequest {
+        endpoint: "people".to_string(),
+        query: query.map(str::to_string),
+    })
+}
+
+#[test]
+fn test_common_service() {
+    let runtime = Runtime::new().unwrap();
+    let store = Arc::new(Store::default());
+    let mut sink = build_sink(&runtime, &store);
+    let service = CommonService::new(store.clone(), Some(2));
+
+    sink.process(operation(Operation::BatchInsert {
+        new: vec![
+            Record::new(values(1, "alice", 30)),
+            Record::new(values(2, "bob", 25)),
+            Record::new(values(3, "carol", 35)),
+        ],
+    }))
+    .unwrap();
+
+    let endpoints = runtime
+        .block_on(service.get_endpoints(Request::new(GetEndpointsRequest {})))
+        .unwrap()
+        .into_inner();
+    assert_eq!(endpoints.endpoints, vec!["people".to_string()]);
+
+    let fields = runtime
+        .block_on(service.get_fields(Request::new(GetFieldsRequest {
+            endpoint: "people".to_string(),
+        })))
+        .unwrap()
+        .into_inner();
+    assert_eq!(fields.primary_index, vec![0]);
+    assert_eq!(fields.fields.len(), 3);
+
+    let count = runtime
+        .block_on(service.count(query_request(Some(
+            r#"{ "$filter": { "age": { "$lt": 35 } } }"#,
+        ))))
+        .unwrap()
+        .into_inner();
+    assert_eq!(count.count, 2);
+
+    // The default limit applies.
+    let response = runtime
+        .block_on(service.query(query_request(None)))
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.fields.len(), 3);
+    assert_eq!(response.records.len(), 2);
+
+    let response = runtime
+        .block_on(service.query(query_request(Some(
+            r#"{ "$order_by": { "age": "desc" }, "$limit": 1 }"#,
+        ))))
+        .unwrap()
+        .into_inner();
+    assert_eq!(
+        response.records[0].values,
+        values(3, "carol", 35)
+            .into_iter()
+            .map(field_to_grpc)
+            .collect::<Vec<_>>()
+    );
+
+    let error = runtime
+        .block_on(service.query(Request::new(QueryRequest {
+            endpoint: "users".to_string(),
+            query: None,
+        })))
+        .unwrap_err();
+    assert_eq!(error.code(), Code::NotFound);
+    let error = runtime
+        .block_on(service.query(query_request(Some("{"))))
+        .unwrap_err();
+    assert_eq!(error.code(), Code::InvalidArgument);
+}
+
+#[test]
+fn test_on_event() {
+    let runtime = Runtime::new().unwrap();
+    let store = Arc::new(Store::default());
+    let mut sink = build_sink(&runtime, &store);
+    let service = CommonService::new(store.clone(), None);
+
+    let request = |typ: EventType, filter: Option<&str>| {
+        Request::new(OnEventRequest {
+            endpoints: [(
+                "people".to_string(),
+                EventFilter {
+                    r#type: typ as i32,
+                    filter: filter.map(str::to_string),
+                },
+            )]
+            .into_iter()
+            .collect(),
+        })
+    };
+    let mut all = runtime
+        .block_on(service.on_event(request(
+            EventType::All,
+            Some(r#"{ "age": { "$gte": 30 } }"#),
+        )))
+        .unwrap()
+        .into_inner();
+    let mut deletes = runtime
+        .block_on(service.on_event(request(EventType::DeleteOnly, None)))
+        .unwrap()
+        .into_inner();
+
+    sink.process(operation(Operation::Insert {
+        new: Record::new(values(1, "alice", 30)),
+    }))
+    .unwrap();
+    // Filtered out.
+    sink.process(operation(Operation::Insert {
+        new: Record::new(values(2, "bob", 25)),
+    }))
+    .unwrap();
+    sink.process(operation(Operation::Update {
+        old: Record::new(values(1, "alice", 30)),
+        new: Record::new(values(1, "alice", 31)),
+    }))
+    .unwrap();
+    sink.process(operation(Operation::Delete {
+        old: Record::new(values(1, "alice", 31)),
+    }))
+    .unwrap();
+
+    let events = runtime.block_on(async {
+        let mut events = vec![];
+        for _ in 0..3 {
+            events.push(all.next().await.unwrap().unwrap());
+        }
+        events
+    });
+    assert_eq!(
+        events.iter().map(|event| event.typ()).collect::<Vec<_>>(),
+        vec![
+            OperationType::Insert,
+            OperationType::Update,
+            OperationType::Delete
+        ]
+    );
+    assert_eq!(events[1].old.as_ref().unwrap().version, 1);
+    assert_eq!(events[1].new.as_ref().unwrap().version, 2);
+    assert!(events.iter().all(|event| event.endpoint == "people"));
+
+    let delete = runtime.block_on(deletes.next()).unwrap().unwrap();
+    assert_eq!(delete.typ(), OperationType::Delete);
+    assert_eq!(delete.new.unwrap().id, 0);
+
+    let error = runtime
+        .block_on(service.on_event(request(EventType::All, Some(r#"{ "email": 1 }"#))))
+        .unwrap_err();
+    assert_eq!(error.code(), Code::InvalidArgument);
+}
+
+fn sql_rows(runtime: &Arc<Runtime>, store: &Store, sql: &str) -> Vec<Vec<Field>> {
+    let results = runtime
+        .block_on(execute(store, sql, runtime.clone()))
+        .unwrap();
+    match results.into_iter().next().unwrap() {
+        SqlResult::Rows { rows, .. } => rows,
+        result => panic!("Expected rows, got {result:?}"),
+    }
+}
+
+#[test]
+fn test_sql_select() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let store = Store::default();
+    let table = store.create_table("users".to_string(), get_schema());
+    for (id, name, age) in [(1, "alice", 30), (2, "bob", 25), (3, "carol", 35)] {
+        table.write().insert(values(id, name, age));
+    }
+    table.write().insert(vec![
+        Field::UInt(4),
+        Field::String("dan".to_string()),
+        Field::Null,
+    ]);
+
+    let results = runtime
+        .block_on(execute(
+            &store,
+            "SELECT name, age + 1 AS next_age FROM users WHERE age > 26 ORDER BY age DESC",
+            runtime.clone(),
+        ))
+        .unwrap();
+    let SqlResult::Rows { fields, rows } = &results[0] else {
+        panic!("Expected rows, got {results:?}");
+    };
+    assert_eq!(
+        fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.typ))
+            .collect::<Vec<_>>(),
+        vec![("name", FieldType::String), ("next_age", FieldType::Int)]
+    );
+    assert_eq!(
+        rows,
+        &vec![
+            vec![Field::String("carol".to_string()), Field::Int(36)],
+            vec![Field::String("alice".to_string()), Field::Int(31)],
+        ]
+    );
+
+    // Nulls sort last in ascending order, first in descending order.
+    assert_eq!(
+        sql_rows(&runtime, &store, "SELECT id FROM users ORDER BY age"),
+        vec![
+            vec![Field::UInt(2)],
+            vec![Field::UInt(1)],
+            vec![Field::UInt(3)],
+            vec![Field::UInt(4)],
+        ]
+    );
+    assert_eq!(
+        sql_rows(
+            &runtime,
+            &store,
+            "SELECT * FROM users ORDER BY age DESC LIMIT 2 OFFSET 1"
+        ),
+        vec![values(3, "carol", 35), values(1, "alice", 30)]
+    );
+    assert_eq!(
+        sql_rows(&runtime, &store, "SELECT 1 + 1"),
+        vec![vec![Field::Int(2)]]
+    );
+
+    let results = runtime
+        .block_on(execute(
+            &store,
+            "SET extra_float_digits = 3",
+            runtime.clone(),
+        ))
+        .unwrap();
+    assert_eq!(results, vec![SqlResult::Command("SET")]);
+
+    for sql in [
+        "SELECT * FROM orders",
+        "SELECT COUNT(id) FROM users",
+        "SELECT name FROM users GROUP BY name",
+        "DELETE FROM users",
+        "SELECT email FROM users",
+        "SELEC 1",
+    ] {
+        assert!(
+            runtime
+                .block_on(execute(&store, sql, runtime.clone()))
+                .is_err(),
+            "{sql} should fail"
+        );
+    }
+}
+
+#[test]
+fn test_pg_text() {
+    assert_eq!(pg_type(FieldType::UInt), Type::NUMERIC);
+    assert_eq!(pg_type(FieldType::Timestamp), Type::TIMESTAMPTZ);
+    assert_eq!(pg_text(&Field::Null), None);
+    assert_eq!(pg_text(&Field::Boolean(true)).unwrap(), "t");
+    assert_eq!(pg_text(&Field::Float(f64::NAN.into())).unwrap(), "NaN");
+    assert_eq!(pg_text(&Field::Binary(vec![0, 0xab])).unwrap(), "\\x00ab");
+    assert_eq!(
+        pg_text(&Field::Timestamp(
+            DateTime::parse_from_rfc3339("2024-01-02T03:04:05.5+02:00").unwrap()
+        ))
+        .unwrap(),
+        "2024-01-02 01:04:05.500+00"
+    );
+    assert_eq!(
+        pg_text(&Field::Date(NaiveDate::from_ymd_opt(2024, 1, 2).unwrap())).unwrap(),
+        "2024-01-02"
+    );
+    assert_eq!(
+        pg_text(&Field::Duration(DozerDuration(
+            std::time::Duration::from_micros(90_061_000_001),
+            TimeUnit::Microseconds
+        )))
+        .unwrap(),
+        "25:01:01.000001"
+    );
+}
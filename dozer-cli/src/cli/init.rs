@@ -143,6 +143,7 @@ pub fn generate_connection(connection_name: &str) -> Connection {
             let mongo_config = MongodbConfig {
                 connection_string:
                     "mongodb://<username>:<password>@localhost:27017/<database_name>".to_owned(),
+                typed_columns: None,
             };
             let connection: Connection = Connection {
                 name: "mongodb".to_owned(),
@@ -161,6 +162,8 @@ pub fn generate_connection(connection_name: &str) -> Connection {
                 connection_url: None,
                 schema: None,
                 batch_size: None,
+                snapshot_chunk_size: None,
+                snapshot_parallelism: None,
             };
             let connection: Connection = Connection {
                 name: "postgres".to_owned(),
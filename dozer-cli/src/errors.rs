@@ -50,6 +50,8 @@ pub enum OrchestrationError {
     RestServeFailed(#[source] std::io::Error),
     #[error("Failed to server gRPC API: {0:?}")]
     GrpcServeFailed(#[source] tonic::transport::Error),
+    #[error("Failed to serve API: {0}")]
+    ApiServeFailed(#[from] dozer_api::errors::ApiError),
     #[error("Failed to server pgwire: {0}")]
     PGWireServerFailed(#[source] std::io::Error),
     #[error("Cache {0} has reached its maximum size. Try to increase `cache_max_map_size` in the config.")]
@@ -68,6 +70,8 @@ pub enum OrchestrationError {
     SourceValidationError(String),
     #[error("connection: {0:?} not found")]
     ConnectionNotFound(String),
+    #[error("connection: {0:?} is a {2} connection, expected {1}")]
+    ConnectionTypeMismatch(String, &'static str, String),
     #[error("Pipeline validation failed")]
     PipelineValidationError,
     #[error("Output table {0} not used in any sink")]
@@ -23,19 +23,21 @@ impl HomeDir {
         Ok(build_path)
     }
 
-    fn get_build_path(&self, build_id: BuildId) -> BuildPath {
+    pub fn get_build_path(&self, build_id: BuildId) -> BuildPath {
         let build_dir = self.home_dir.join(&build_id.name);
 
         let contracts_dir = build_dir.join("contracts");
         let descriptor_path = contracts_dir.join("file_descriptor_set.bin");
 
         let data_dir = build_dir.join("data");
+        let processor_state_dir = data_dir.join("processors");
 
         BuildPath {
             id: build_id,
             contracts_dir,
             descriptor_path,
             data_dir,
+            processor_state_dir,
         }
     }
 }
@@ -63,4 +65,6 @@ pub struct BuildPath {
     pub contracts_dir: Utf8PathBuf,
     pub descriptor_path: Utf8PathBuf,
     pub data_dir: Utf8PathBuf,
+    /// Where stateful processors checkpoint their state.
+    pub processor_state_dir: Utf8PathBuf,
 }
@@ -24,7 +24,15 @@ use std::hash::Hash;
 use tokio::runtime::Runtime;
 
 use crate::pipeline::dummy_sink::DummySinkFactory;
+use dozer_api::store::Store;
+use dozer_api::ApiSinkFactory;
+use dozer_sink_aerospike::AerospikeSinkFactory;
 use dozer_sink_clickhouse::ClickhouseSinkFactory;
+use dozer_sink_kafka::KafkaSinkFactory;
+use dozer_sink_lake::storage::Storage;
+use dozer_sink_lake::LakeSinkFactory;
+use dozer_sink_oracle::OracleSinkFactory;
+use dozer_sink_postgres::PostgresSinkFactory;
 
 use super::source_builder::SourceBuilder;
 use crate::errors::OrchestrationError;
@@ -55,9 +63,11 @@ pub struct PipelineBuilder<'a> {
     labels: DozerMonitorContext,
     flags: Flags,
     udfs: &'a [UdfConfig],
+    store: Arc<Store>,
 }
 
 impl<'a> PipelineBuilder<'a> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         connections: &'a [Connection],
         sources: &'a [Source],
@@ -66,6 +76,7 @@ impl<'a> PipelineBuilder<'a> {
         labels: DozerMonitorContext,
         flags: Flags,
         udfs: &'a [UdfConfig],
+        store: Arc<Store>,
     ) -> Self {
         Self {
             connections,
@@ -75,6 +86,7 @@ impl<'a> PipelineBuilder<'a> {
             labels,
             flags,
             udfs,
+            store,
         }
     }
 
@@ -248,7 +260,78 @@ impl<'a> PipelineBuilder<'a> {
                 SinkConfig::Clickhouse(config) => {
                     let sink =
                         Box::new(ClickhouseSinkFactory::new(config.clone(), runtime.clone()));
-                    let table_info = get_table_info(&config.source_table_name)?;
+                    let table_infos = config
+                        .tables
+                        .iter()
+                        .enumerate()
+                        .map(|(port, table)| {
+                            Ok((
+                                get_table_info(&table.source_table_name)?,
+                                port as PortHandle,
+                            ))
+                        })
+                        .collect::<Result<Vec<_>, OrchestrationError>>()?;
+                    add_sink_to_pipeline(&mut pipeline, sink, id, table_infos);
+                }
+                SinkConfig::Aerospike(config) => {
+                    let connection = find_connection(self.connections, &config.connection)?;
+                    let ConnectionConfig::Aerospike(connection) = connection else {
+                        return Err(connection_type_mismatch(
+                            &config.connection,
+                            "aerospike",
+                            connection,
+                        ));
+                    };
+                    let sink = Box::new(AerospikeSinkFactory::new(
+                        connection.clone(),
+                        config.clone(),
+                    ));
+                    let table_infos = config
+                        .tables
+                        .iter()
+                        .enumerate()
+                        .map(|(port, table)| {
+                            Ok((
+                                get_table_info(&table.source_table_name)?,
+                                port as PortHandle,
+                            ))
+                        })
+                        .collect::<Result<Vec<_>, OrchestrationError>>()?;
+                    add_sink_to_pipeline(&mut pipeline, sink, id, table_infos);
+                }
+                SinkConfig::Oracle(config) => {
+                    let connection = find_connection(self.connections, &config.connection)?;
+                    let ConnectionConfig::Oracle(connection) = connection else {
+                        return Err(connection_type_mismatch(
+                            &config.connection,
+                            "oracle",
+                            connection,
+                        ));
+                    };
+                    let sink = Box::new(OracleSinkFactory::new(connection.clone(), config.clone()));
+                    let table_info = get_table_info(&config.table_name)?;
+                    add_sink_to_pipeline(
+                        &mut pipeline,
+                        sink,
+                        id,
+                        vec![(table_info, DEFAULT_PORT_HANDLE)],
+                    );
+                }
+                SinkConfig::Postgres(config) => {
+                    let connection = find_connection(self.connections, &config.connection)?;
+                    let ConnectionConfig::Postgres(connection) = connection else {
+                        return Err(connection_type_mismatch(
+                            &config.connection,
+                            "postgres",
+                            connection,
+                        ));
+                    };
+                    let sink = Box::new(PostgresSinkFactory::new(
+                        connection.clone(),
+                        config.clone(),
+                        runtime.clone(),
+                    ));
+                    let table_info = get_table_info(&config.table_name)?;
                     add_sink_to_pipeline(
                         &mut pipeline,
                         sink,
@@ -256,9 +339,63 @@ impl<'a> PipelineBuilder<'a> {
                         vec![(table_info, DEFAULT_PORT_HANDLE)],
                     );
                 }
-                x => {
-                    return Err(OrchestrationError::UnsupportedFeature(x.name()));
+                SinkConfig::Kafka(config) => {
+                    let connection = find_connection(self.connections, &config.connection)?;
+                    let ConnectionConfig::Kafka(connection) = connection else {
+                        return Err(connection_type_mismatch(
+                            &config.connection,
+                            "kafka",
+                            connection,
+                        ));
+                    };
+                    let sink = Box::new(KafkaSinkFactory::new(
+                        connection.clone(),
+                        config.clone(),
+                        runtime.clone(),
+                    ));
+                    let table_info = get_table_info(&config.table_name)?;
+                    add_sink_to_pipeline(
+                        &mut pipeline,
+                        sink,
+                        id,
+                        vec![(table_info, DEFAULT_PORT_HANDLE)],
+                    );
                 }
+                SinkConfig::Lake(config) => {
+                    let storage = match find_connection(self.connections, &config.connection)? {
+                        ConnectionConfig::LocalStorage(storage) => {
+                            Storage::Local(storage.details.clone())
+                        }
+                        ConnectionConfig::S3Storage(storage) => {
+                            Storage::S3(storage.details.clone())
+                        }
+                        connection => {
+                            return Err(connection_type_mismatch(
+                                &config.connection,
+                                "localstorage or s3storage",
+                                connection,
+                            ))
+                        }
+                    };
+                    let sink = Box::new(LakeSinkFactory::new(
+                        storage,
+                        config.clone(),
+                        runtime.clone(),
+                    ));
+                    let table_info = get_table_info(&config.table_name)?;
+                    add_sink_to_pipeline(
+                        &mut pipeline,
+                        sink,
+                        id,
+                        vec![(table_info, DEFAULT_PORT_HANDLE)],
+                    );
+                }
+                SinkConfig::Api(config) => add_sink_to_pipeline(
+                    &mut pipeline,
+                    Box::new(ApiSinkFactory::new(config.clone(), self.store.clone())),
+                    id,
+                    vec![(get_table_info(&config.table_name)?, DEFAULT_PORT_HANDLE)],
+                ),
             }
         }
 
@@ -285,6 +422,29 @@ fn dedup<T: Eq + Hash + Clone>(v: &mut Vec<T>) {
     v.retain(|e| uniques.insert(e.clone()));
 }
 
+fn find_connection<'a>(
+    connections: &'a [Connection],
+    name: &str,
+) -> Result<&'a ConnectionConfig, OrchestrationError> {
+    connections
+        .iter()
+        .find(|connection| connection.name == name)
+        .map(|connection| &connection.config)
+        .ok_or_else(|| OrchestrationError::ConnectionNotFound(name.to_string()))
+}
+
+fn connection_type_mismatch(
+    name: &str,
+    expected: &'static str,
+    connection: &ConnectionConfig,
+) -> OrchestrationError {
+    OrchestrationError::ConnectionTypeMismatch(
+        name.to_string(),
+        expected,
+        connection.get_type_name(),
+    )
+}
+
 fn table_names(sink: &Sink) -> Vec<&String> {
     match &sink.config {
         SinkConfig::Dummy(sink) => vec![&sink.table_name],
@@ -293,8 +453,16 @@ fn table_names(sink: &Sink) -> Vec<&String> {
             .iter()
             .map(|table| &table.source_table_name)
             .collect(),
-        SinkConfig::Clickhouse(sink) => vec![&sink.source_table_name],
+        SinkConfig::Clickhouse(sink) => sink
+            .tables
+            .iter()
+            .map(|table| &table.source_table_name)
+            .collect(),
         SinkConfig::Oracle(sink) => vec![&sink.table_name],
+        SinkConfig::Postgres(sink) => vec![&sink.table_name],
+        SinkConfig::Kafka(sink) => vec![&sink.table_name],
+        SinkConfig::Lake(sink) => vec![&sink.table_name],
+        SinkConfig::Api(sink) => vec![&sink.table_name],
     }
 }
 
@@ -378,6 +378,12 @@ async fn forward_message_to_pipeline(
                     break;
                 }
             }
+            IngestionMessage::SchemaChange { table_index, .. }
+            | IngestionMessage::Truncate { table_index } => {
+                if sender.send((ports[*table_index], message)).await.is_err() {
+                    break;
+                }
+            }
             IngestionMessage::TransactionInfo(_) => {
                 // For transaction level messages, we can send to any port.
                 if sender.send((ports[0], message)).await.is_err() {
@@ -190,4 +190,17 @@ impl Sink for DummySink {
     fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
         Ok(None)
     }
+
+    fn persists_op_id(&self) -> bool {
+        false
+    }
+
+    fn on_schema_change(&mut self, _port: PortHandle, _schema: &Schema) -> Result<(), BoxedError> {
+        // Fields keep their index across schema changes, so `inserted_at_index` stays valid.
+        Ok(())
+    }
+
+    fn on_truncate(&mut self, _port: PortHandle) -> Result<(), BoxedError> {
+        Ok(())
+    }
 }
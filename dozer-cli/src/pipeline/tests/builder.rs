@@ -68,6 +68,7 @@ fn load_multi_sources() {
         Default::default(),
         Flags::default(),
         &config.udfs,
+        Default::default(),
     );
 
     let runtime = tokio::runtime::Builder::new_current_thread()
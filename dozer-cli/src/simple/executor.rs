@@ -1,3 +1,4 @@
+use dozer_api::store::Store;
 use dozer_core::shutdown::ShutdownReceiver;
 use dozer_tracing::DozerMonitorContext;
 use dozer_types::models::flags::Flags;
@@ -23,6 +24,7 @@ pub struct Executor<'a> {
     sinks: &'a [Sink],
     labels: DozerMonitorContext,
     udfs: &'a [UdfConfig],
+    store: Arc<Store>,
 }
 
 impl<'a> Executor<'a> {
@@ -36,6 +38,7 @@ impl<'a> Executor<'a> {
         sinks: &'a [Sink],
         labels: DozerMonitorContext,
         udfs: &'a [UdfConfig],
+        store: Arc<Store>,
     ) -> Result<Executor<'a>, OrchestrationError> {
         Ok(Executor {
             connections,
@@ -44,6 +47,7 @@ impl<'a> Executor<'a> {
             sinks,
             labels,
             udfs,
+            store,
         })
     }
 
@@ -62,6 +66,7 @@ impl<'a> Executor<'a> {
             self.labels.clone(),
             flags,
             self.udfs,
+            self.store,
         );
 
         let dag = builder.build(runtime, shutdown).await?;
@@ -10,6 +10,8 @@ use crate::utils::get_executor_options;
 
 use crate::flatten_join_handle;
 use camino::Utf8PathBuf;
+use dozer_api::store::Store;
+use dozer_api::{grpc, sql};
 use dozer_core::app::AppPipeline;
 use dozer_core::dag_schemas::DagSchemas;
 use dozer_core::event::EventHub;
@@ -28,6 +30,7 @@ use dozer_sql::builder::statement_to_pipeline;
 use dozer_sql::errors::PipelineError;
 use dozer_types::log::info;
 use dozer_types::models::config::{default_home_dir, Config};
+use dozer_types::models::sink::SinkConfig;
 use dozer_types::tracing::error;
 use futures::stream::FuturesUnordered;
 use futures::{FutureExt, StreamExt};
@@ -79,6 +82,7 @@ impl SimpleOrchestrator {
         shutdown: ShutdownReceiver,
         api_notifier: Option<oneshot::Sender<()>>,
     ) -> Result<(), OrchestrationError> {
+        let store = Arc::new(Store::default());
         let executor = Executor::new(
             &self.config.connections,
             &self.config.sources,
@@ -86,12 +90,18 @@ impl SimpleOrchestrator {
             &self.config.sinks,
             self.labels.clone(),
             &self.config.udfs,
+            store.clone(),
         )
         .await?;
+        let mut executor_options = get_executor_options(&self.config);
+        if self.config.app.checkpoint_processor_state == Some(true) {
+            let build_path = HomeDir::new(self.home_dir()).get_build_path(BuildId::first());
+            executor_options.state_dir = Some(build_path.processor_state_dir.into_std_path_buf());
+        }
         let dag_executor = executor
             .create_dag_executor(
                 &self.runtime,
-                get_executor_options(&self.config),
+                executor_options,
                 shutdown.clone(),
                 self.config.flags.clone(),
             )
@@ -111,6 +121,46 @@ impl SimpleOrchestrator {
         let mut futures = FuturesUnordered::new();
         futures.push(flatten_join_handle(pipeline_future).boxed());
 
+        let has_api_sinks = self
+            .config
+            .sinks
+            .iter()
+            .any(|sink| matches!(sink.config, SinkConfig::Api(_)));
+        let grpc_options = &self.config.api.grpc;
+        if has_api_sinks && grpc_options.enabled != Some(false) {
+            let store = store.clone();
+            let grpc_options = grpc_options.clone();
+            let default_max_num_records = self.config.api.default_max_num_records;
+            let shutdown_future = shutdown.create_shutdown_future();
+            futures.push(
+                async move {
+                    grpc::serve(
+                        store,
+                        &grpc_options,
+                        default_max_num_records,
+                        shutdown_future,
+                    )
+                    .await
+                    .map_err(OrchestrationError::from)
+                }
+                .boxed(),
+            );
+        }
+        let pgwire_options = &self.config.api.pgwire;
+        if has_api_sinks && pgwire_options.enabled != Some(false) {
+            let pgwire_options = pgwire_options.clone();
+            let runtime = self.runtime.clone();
+            let shutdown_future = shutdown.create_shutdown_future();
+            futures.push(
+                async move {
+                    sql::server::serve(store, &pgwire_options, runtime, shutdown_future)
+                        .await
+                        .map_err(OrchestrationError::from)
+                }
+                .boxed(),
+            );
+        }
+
         while let Some(result) = futures.next().await {
             result?;
         }
@@ -173,6 +223,7 @@ impl SimpleOrchestrator {
             self.labels.clone(),
             self.config.flags.clone(),
             &self.config.udfs,
+            Default::default(),
         );
         let dag = builder.build(&self.runtime, shutdown).await?;
         // Populate schemas.
@@ -30,5 +30,7 @@ pub fn get_executor_options(config: &Config) -> ExecutorOptions {
         channel_buffer_sz: get_buffer_size(config) as usize,
         error_threshold: Some(get_error_threshold(config)),
         event_hub_capacity: get_event_hub_capacity(config),
+        state_dir: None,
+        state_memory_budget: config.app.state_memory_budget,
     }
 }
@@ -1,19 +1,21 @@
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
     fmt::Debug,
+    path::Path,
 };
 
 use daggy::{petgraph::visit::IntoNodeIdentifiers, NodeIndex};
 use dozer_types::{
     log::warn,
-    node::{NodeHandle, OpIdentifier},
+    node::{NodeHandle, OpIdentifier, SourceState, SourceStates},
 };
 
 use crate::{
     dag_schemas::{DagHaveSchemas, DagSchemas, EdgeType},
     errors::ExecutionError,
     event::EventHub,
-    node::{Processor, Sink, SinkFactory, Source},
+    node::{Processor, ProcessorFactory, Sink, SinkFactory, Source},
+    state_store::StateStore,
     NodeKind as DagNodeKind,
 };
 
@@ -28,18 +30,29 @@ pub struct NodeType {
 
 #[derive(Debug)]
 /// Node kind, source, processor or sink. Source has a checkpoint to start from.
+/// Sink has the checkpoint it persisted, if its source resumes, to skip the operations up to it.
+/// A stateful processor has a store to checkpoint its state to, if the pipeline has a state directory,
+/// or to spill its state to, if the pipeline has a state memory budget.
 pub enum NodeKind {
     Source {
         source: Box<dyn Source>,
         last_checkpoint: Option<OpIdentifier>,
     },
-    Processor(Box<dyn Processor>),
-    Sink(Box<dyn Sink>),
+    Processor {
+        processor: Box<dyn Processor>,
+        state_store: Option<StateStore>,
+    },
+    Sink {
+        sink: Box<dyn Sink>,
+        last_checkpoint: Option<OpIdentifier>,
+    },
 }
 
 /// Builder DAG builds all the sources, processors and sinks.
 /// It also asks each source if its possible to start from the given checkpoint.
 /// If not possible, it resets metadata and updates the checkpoint.
+/// Stateful processors are restored from their state store in `state_dir`, if given.
+/// With a state memory budget but no `state_dir`, they get a temporary store to spill to.
 #[derive(Debug)]
 pub struct BuilderDag {
     graph: daggy::Dag<NodeType, EdgeType>,
@@ -50,6 +63,8 @@ impl BuilderDag {
     pub async fn new(
         dag_schemas: DagSchemas,
         event_hub_capacity: usize,
+        state_dir: Option<&Path>,
+        state_memory_budget: Option<usize>,
     ) -> Result<Self, ExecutionError> {
         // Collect input output schemas.
         let mut input_schemas = HashMap::new();
@@ -113,21 +128,23 @@ impl BuilderDag {
                     }
                 }
 
+                // A source only resumes if all its sinks that persist op ids have a checkpoint.
                 let op_id = sink.get_latest_op_id().map_err(ExecutionError::Sink)?;
-                if let Some(op_id) = op_id {
-                    match source_op_ids.entry(source.clone()) {
-                        Entry::Occupied(mut entry) => {
-                            *entry.get_mut() = op_id.min(*entry.get());
-                        }
-                        Entry::Vacant(entry) => {
-                            entry.insert(op_id);
-                        }
-                    }
+                if sink.persists_op_id() {
+                    source_op_ids
+                        .entry(source.clone())
+                        .and_modify(|checkpoint: &mut Option<OpIdentifier>| {
+                            *checkpoint = checkpoint.zip(op_id).map(|(a, b)| a.min(b));
+                        })
+                        .or_insert(op_id);
                 }
 
                 let new_node_index = graph.add_node(NodeType {
                     handle,
-                    kind: NodeKind::Sink(sink),
+                    kind: NodeKind::Sink {
+                        sink,
+                        last_checkpoint: op_id,
+                    },
                 });
                 node_index_map.insert(node_index, new_node_index);
                 source_id_to_sinks
@@ -137,73 +154,181 @@ impl BuilderDag {
             }
         }
 
-        // Build sources, processors, and collect source states.
+        // Build processors and open the state stores of stateful ones.
+        let mut stateful_processors = vec![];
         for (node_index, node) in nodes.iter_mut().enumerate() {
-            let Some(node) = node.take() else {
+            let Some((handle, processor)) = take_processor(node) else {
                 continue;
             };
             let node_index = NodeIndex::new(node_index);
-            let node = match node.kind {
-                DagNodeKind::Source(source) => {
-                    let source = source
-                        .build(
-                            output_schemas
-                                .remove(&node_index)
-                                .expect("we collected all output schemas"),
-                            event_hub.clone(),
-                            source_states.remove(&node.handle),
-                        )
-                        .map_err(ExecutionError::Factory)?;
-
-                    // Write state to relevant sink.
-                    let state = source
-                        .serialize_state()
-                        .await
-                        .map_err(ExecutionError::Source)?;
-                    let mut checkpoint = None;
-                    for sink in source_id_to_sinks.remove(&node.handle).unwrap_or_default() {
-                        let sink = &mut graph[sink];
-                        let sink_handle = &sink.handle;
-                        let NodeKind::Sink(sink) = &mut sink.kind else {
-                            unreachable!()
-                        };
-                        sink.set_source_state(&state)
-                            .map_err(ExecutionError::Sink)?;
-                        if let Some(sink_checkpoint) = source_op_ids.remove(sink_handle) {
-                            checkpoint =
-                                Some(checkpoint.unwrap_or(sink_checkpoint).min(sink_checkpoint));
-                        }
-                    }
+            let processor = processor
+                .build(
+                    input_schemas
+                        .remove(&node_index)
+                        .expect("we collected all input schemas"),
+                    output_schemas
+                        .remove(&node_index)
+                        .expect("we collected all output schemas"),
+                    event_hub.clone(),
+                )
+                .await
+                .map_err(ExecutionError::Factory)?;
 
-                    NodeType {
-                        handle: node.handle,
-                        kind: NodeKind::Source {
-                            source,
-                            last_checkpoint: checkpoint,
-                        },
-                    }
+            let state_store = if processor.is_stateful() {
+                match (state_dir, state_memory_budget) {
+                    (Some(state_dir), _) => Some(StateStore::open(state_dir, &handle)),
+                    (None, Some(_)) => Some(StateStore::temporary()),
+                    (None, None) => None,
                 }
-                DagNodeKind::Processor(processor) => {
-                    let processor = processor
-                        .build(
-                            input_schemas
-                                .remove(&node_index)
-                                .expect("we collected all input schemas"),
-                            output_schemas
-                                .remove(&node_index)
-                                .expect("we collected all output schemas"),
-                            event_hub.clone(),
-                        )
-                        .await
-                        .map_err(ExecutionError::Factory)?;
-                    NodeType {
-                        handle: node.handle,
-                        kind: NodeKind::Processor(processor),
-                    }
+                .transpose()
+                .map_err(|error| ExecutionError::StateStore {
+                    node: handle.clone(),
+                    error,
+                })?
+                .map(|store| store.with_memory_budget(state_memory_budget))
+            } else {
+                None
+            };
+
+            let new_node_index = graph.add_node(NodeType {
+                handle: handle.clone(),
+                kind: NodeKind::Processor {
+                    processor,
+                    state_store: state_store.clone(),
+                },
+            });
+            node_index_map.insert(node_index, new_node_index);
+            if let Some(store) = state_store {
+                let sources = std::mem::take(&mut affecting_sources[node_index.index()]);
+                stateful_processors.push((new_node_index, handle, sources, store));
+            }
+        }
+
+        // A source only resumes if the stateful processors it affects have a checkpoint at the op id
+        // it resumes from, which they roll back to. Processors are usually ahead of the sinks, so
+        // that's not always their last checkpoint. Otherwise the processors start over with it.
+        // Temporary stores always start empty, so there's nothing to check.
+        let mut restarting_sources = HashSet::new();
+        let durable_processors = if state_dir.is_some() {
+            stateful_processors.as_slice()
+        } else {
+            &[]
+        };
+        for (_, handle, sources, store) in durable_processors {
+            let state_store_error = |error| ExecutionError::StateStore {
+                node: handle.clone(),
+                error,
+            };
+            let checkpoints = store.checkpoints().map_err(state_store_error)?;
+            let is_resumed_at = |checkpoint: &SourceStates| {
+                sources.iter().all(|source| {
+                    is_checkpointed_at(
+                        checkpoint.get(source),
+                        source_op_ids.get(source).copied().flatten(),
+                    )
+                })
+            };
+            if checkpoints.is_empty() && is_resumed_at(&SourceStates::new()) {
+                continue;
+            }
+            match checkpoints
+                .into_iter()
+                .find(|(_, source_states)| is_resumed_at(source_states))
+            {
+                Some((checkpoint, _)) => store.roll_back(checkpoint).map_err(state_store_error)?,
+                None => {
+                    warn!("State of {handle} is not checkpointed where {sources:?} resume");
+                    restarting_sources.extend(sources.iter().cloned());
                 }
-                DagNodeKind::Sink(_) => unreachable!(),
+            }
+        }
+        // A processor that starts over needs all its sources to start over.
+        loop {
+            let mut changed = false;
+            for (_, _, sources, _) in &stateful_processors {
+                if !sources.is_disjoint(&restarting_sources)
+                    && !sources.is_subset(&restarting_sources)
+                {
+                    restarting_sources.extend(sources.iter().cloned());
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        for source in &restarting_sources {
+            source_states.remove(source);
+            source_op_ids.remove(source);
+        }
+
+        for (node_index, handle, sources, store) in stateful_processors {
+            if !sources.is_disjoint(&restarting_sources) {
+                store.clear().map_err(|error| ExecutionError::StateStore {
+                    node: handle.clone(),
+                    error,
+                })?;
+            }
+            let NodeKind::Processor { processor, .. } = &mut graph[node_index].kind else {
+                unreachable!()
+            };
+            processor
+                .restore(store)
+                .map_err(|error| ExecutionError::RestoreProcessor {
+                    node: handle,
+                    error,
+                })?;
+        }
+
+        // Build sources and write their states to the sinks.
+        for (node_index, node) in nodes.iter_mut().enumerate() {
+            let Some(node) = node.take() else {
+                continue;
             };
-            let new_node_index = graph.add_node(node);
+            let node_index = NodeIndex::new(node_index);
+            let DagNodeKind::Source(source) = node.kind else {
+                unreachable!("sinks and processors are built")
+            };
+            let source = source
+                .build(
+                    output_schemas
+                        .remove(&node_index)
+                        .expect("we collected all output schemas"),
+                    event_hub.clone(),
+                    source_states.remove(&node.handle),
+                )
+                .map_err(ExecutionError::Factory)?;
+
+            // Write state to relevant sink.
+            let state = source
+                .serialize_state()
+                .await
+                .map_err(ExecutionError::Source)?;
+            // The smallest op id persisted by the sinks of this source, so no sink misses data.
+            let checkpoint = source_op_ids.remove(&node.handle).flatten();
+            for sink in source_id_to_sinks.remove(&node.handle).unwrap_or_default() {
+                let NodeKind::Sink {
+                    sink,
+                    last_checkpoint,
+                } = &mut graph[sink].kind
+                else {
+                    unreachable!()
+                };
+                sink.set_source_state(&state)
+                    .map_err(ExecutionError::Sink)?;
+                // A source that starts over sends everything again.
+                if checkpoint.is_none() {
+                    *last_checkpoint = None;
+                }
+            }
+
+            let new_node_index = graph.add_node(NodeType {
+                handle: node.handle,
+                kind: NodeKind::Source {
+                    source,
+                    last_checkpoint: checkpoint,
+                },
+            });
             node_index_map.insert(node_index, new_node_index);
         }
 
@@ -230,6 +355,31 @@ impl BuilderDag {
     }
 }
 
+fn take_processor(
+    node: &mut Option<super::NodeType>,
+) -> Option<(NodeHandle, Box<dyn ProcessorFactory>)> {
+    let super::NodeType { handle, kind } = node.take()?;
+    if let super::NodeKind::Processor(processor) = kind {
+        Some((handle, processor))
+    } else {
+        *node = Some(super::NodeType { handle, kind });
+        None
+    }
+}
+
+/// Whether a processor checkpointed with the source in `checkpointed` state can continue if the
+/// source resumes from `resume_from`.
+fn is_checkpointed_at(
+    checkpointed: Option<&SourceState>,
+    resume_from: Option<OpIdentifier>,
+) -> bool {
+    match (checkpointed, resume_from) {
+        (None | Some(SourceState::NotStarted), None) => true,
+        (Some(SourceState::Restartable(op_id)), Some(resume_from)) => *op_id == resume_from,
+        _ => false,
+    }
+}
+
 fn take_sink(node: &mut Option<super::NodeType>) -> Option<(NodeHandle, Box<dyn SinkFactory>)> {
     let super::NodeType { handle, kind } = node.take()?;
     if let super::NodeKind::Sink(sink) = kind {
@@ -1,4 +1,4 @@
-use crate::errors::ExecutionError;
+use crate::errors::{ExecutionError, SchemaChangeError};
 use crate::{Dag, EdgeHavePorts, NodeKind};
 
 use crate::node::{OutputPortType, PortHandle};
@@ -8,8 +8,9 @@ use daggy::petgraph::Direction;
 use daggy::{NodeIndex, Walker};
 use dozer_types::log::{error, info};
 use dozer_types::node::NodeHandle;
+use dozer_types::ordered_float::OrderedFloat;
 use dozer_types::serde::{Deserialize, Serialize};
-use dozer_types::types::Schema;
+use dozer_types::types::{Field, FieldDefinition, FieldType, Operation, Record, Schema};
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 
@@ -167,6 +168,181 @@ impl DagHaveSchemas for DagSchemas {
     }
 }
 
+/// How the schema a source reports after a schema change maps onto the schema the pipeline is running with.
+///
+/// Fields are matched by name. Fields keep their index, so records produced before the change stay valid:
+/// new fields are appended as nullable, dropped fields become nullable and are `Null` from then on,
+/// and field types can only be widened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaChange {
+    /// The schema of the records after the change.
+    pub schema: Schema,
+    /// For every field of the reported schema, its index in `schema`.
+    pub mapping: Vec<usize>,
+}
+
+impl SchemaChange {
+    pub fn new(current: &Schema, reported: &Schema) -> Result<Self, SchemaChangeError> {
+        let mut schema = current.clone();
+        let mut mapping = vec![];
+        for field in &reported.fields {
+            match schema.fields.iter().position(|f| f.name == field.name) {
+                Some(index) => {
+                    let existing = &mut schema.fields[index];
+                    if !can_widen(existing.typ, field.typ) {
+                        return Err(SchemaChangeError::TypeChanged {
+                            field: field.name.clone(),
+                            from: existing.typ,
+                            to: field.typ,
+                        });
+                    }
+                    existing.typ = field.typ;
+                    existing.nullable |= field.nullable;
+                    mapping.push(index);
+                }
+                None => {
+                    let mut field = field.clone();
+                    field.nullable = true;
+                    mapping.push(schema.fields.len());
+                    schema.fields.push(field);
+                }
+            }
+        }
+
+        for (index, field) in schema.fields.iter_mut().enumerate() {
+            if !mapping.contains(&index) {
+                if current.primary_index.contains(&index) {
+                    return Err(SchemaChangeError::PrimaryKeyDropped(field.name.clone()));
+                }
+                field.nullable = true;
+            }
+        }
+
+        Ok(Self { schema, mapping })
+    }
+
+    /// Converts a record with the reported schema to a record with the evolved schema.
+    pub fn map_record(&self, record: Record) -> Record {
+        let mut values = vec![Field::Null; self.schema.fields.len()];
+        for (value, index) in record.values.into_iter().zip(&self.mapping) {
+            values[*index] = widen_field(value, self.schema.fields[*index].typ);
+        }
+        Record {
+            values,
+            lifetime: record.lifetime,
+        }
+    }
+
+    pub fn map_operation(&self, op: Operation) -> Operation {
+        match op {
+            Operation::Insert { new } => Operation::Insert {
+                new: self.map_record(new),
+            },
+            Operation::Delete { old } => Operation::Delete {
+                old: self.map_record(old),
+            },
+            Operation::Update { old, new } => Operation::Update {
+                old: self.map_record(old),
+                new: self.map_record(new),
+            },
+            Operation::BatchInsert { new } => Operation::BatchInsert {
+                new: new
+                    .into_iter()
+                    .map(|record| self.map_record(record))
+                    .collect(),
+            },
+        }
+    }
+}
+
+/// Converts a record produced before a schema change to `schema`, filling the appended fields with `Null`.
+pub fn evolve_record(mut record: Record, schema: &Schema) -> Record {
+    evolve_values(&mut record.values, &schema.fields);
+    record
+}
+
+/// Converts the values of a record produced before a schema change to `fields`, filling the appended fields with `Null`.
+pub fn evolve_values(values: &mut Vec<Field>, fields: &[FieldDefinition]) {
+    values.resize(fields.len(), Field::Null);
+    for (value, field) in values.iter_mut().zip(fields) {
+        *value = widen_field(std::mem::replace(value, Field::Null), field.typ);
+    }
+}
+
+/// How the fields of a schema changed, as [`SchemaChange::new`] changes them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChanges<'a> {
+    /// The indexes of the existing fields that became nullable or were widened to another type.
+    pub altered: Vec<usize>,
+    /// The fields appended after the existing ones.
+    pub appended: &'a [FieldDefinition],
+}
+
+/// Compares `current` with the fields `new` it evolved to. Sinks check schema changes with this,
+/// to alter the columns of the existing fields and add columns for the appended ones.
+pub fn field_changes<'a>(
+    current: &[FieldDefinition],
+    new: &'a [FieldDefinition],
+) -> Result<FieldChanges<'a>, SchemaChangeError> {
+    if new.len() < current.len() {
+        return Err(SchemaChangeError::Unsupported);
+    }
+    let mut altered = vec![];
+    for (index, (current, new)) in current.iter().zip(new).enumerate() {
+        if current.name != new.name
+            || current.source != new.source
+            || (current.nullable && !new.nullable)
+        {
+            return Err(SchemaChangeError::Unsupported);
+        }
+        if !can_widen(current.typ, new.typ) {
+            return Err(SchemaChangeError::TypeChanged {
+                field: new.name.clone(),
+                from: current.typ,
+                to: new.typ,
+            });
+        }
+        if current.typ != new.typ || current.nullable != new.nullable {
+            altered.push(index);
+        }
+    }
+    Ok(FieldChanges {
+        altered,
+        appended: &new[current.len()..],
+    })
+}
+
+fn can_widen(from: FieldType, to: FieldType) -> bool {
+    use FieldType::*;
+    from == to
+        || matches!(
+            (from, to),
+            (Int8, Int | I128 | Float | Decimal)
+                | (Int, I128 | Decimal)
+                | (UInt, U128 | I128 | Decimal)
+                | (String, Text)
+                | (Text, String)
+        )
+}
+
+/// Converts `field` to `typ`, which must be the same or a wider type.
+pub fn widen_field(field: Field, typ: FieldType) -> Field {
+    match (field, typ) {
+        (Field::Int8(value), FieldType::Int) => Field::Int(value.into()),
+        (Field::Int8(value), FieldType::I128) => Field::I128(value.into()),
+        (Field::Int8(value), FieldType::Float) => Field::Float(OrderedFloat(value.into())),
+        (Field::Int8(value), FieldType::Decimal) => Field::Decimal(value.into()),
+        (Field::Int(value), FieldType::I128) => Field::I128(value.into()),
+        (Field::Int(value), FieldType::Decimal) => Field::Decimal(value.into()),
+        (Field::UInt(value), FieldType::U128) => Field::U128(value.into()),
+        (Field::UInt(value), FieldType::I128) => Field::I128(value.into()),
+        (Field::UInt(value), FieldType::Decimal) => Field::Decimal(value.into()),
+        (Field::String(value), FieldType::Text) => Field::Text(value),
+        (Field::Text(value), FieldType::String) => Field::String(value),
+        (field, _) => field,
+    }
+}
+
 fn validate_connectivity(dag: &Dag) {
     // Every source or processor has at least one outgoing edge.
     for (node_index, node) in dag.graph().node_references() {
@@ -339,6 +515,7 @@ fn validate_input_schemas(
 #[cfg(test)]
 mod tests {
     use dozer_types::node::NodeHandle;
+    use dozer_types::types::SourceDefinition;
 
     use super::*;
 
@@ -524,4 +701,158 @@ mod tests {
             .unwrap();
         validate_connectivity(&dag);
     }
+
+    fn field(name: &str, typ: FieldType, nullable: bool) -> FieldDefinition {
+        FieldDefinition::new(name.to_string(), typ, nullable, SourceDefinition::Dynamic)
+    }
+
+    fn users_schema() -> Schema {
+        let mut schema = Schema::default();
+        schema
+            .field(field("id", FieldType::Int, false), true)
+            .field(field("name", FieldType::String, false), false)
+            .field(field("age", FieldType::Int8, true), false);
+        schema
+    }
+
+    #[test]
+    fn schema_change_appends_added_fields_as_nullable() {
+        let current = users_schema();
+        let mut reported = Schema::default();
+        reported
+            .field(field("id", FieldType::Int, false), true)
+            .field(field("email", FieldType::String, false), false)
+            .field(field("name", FieldType::String, false), false)
+            .field(field("age", FieldType::Int8, true), false);
+
+        let change = SchemaChange::new(&current, &reported).unwrap();
+        let mut expected = users_schema();
+        expected.field(field("email", FieldType::String, true), false);
+        assert_eq!(change.schema, expected);
+        assert_eq!(change.mapping, vec![0, 3, 1, 2]);
+
+        let record = Record::new(vec![
+            Field::Int(1),
+            Field::String("a@b.c".to_string()),
+            Field::String("a".to_string()),
+            Field::Null,
+        ]);
+        assert_eq!(
+            change.map_record(record).values,
+            vec![
+                Field::Int(1),
+                Field::String("a".to_string()),
+                Field::Null,
+                Field::String("a@b.c".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn schema_change_keeps_dropped_fields_as_null() {
+        let current = users_schema();
+        let mut reported = Schema::default();
+        reported
+            .field(field("id", FieldType::Int, false), true)
+            .field(field("age", FieldType::Int8, true), false);
+
+        let change = SchemaChange::new(&current, &reported).unwrap();
+        assert_eq!(
+            change.schema.fields[1],
+            field("name", FieldType::String, true)
+        );
+        assert_eq!(
+            change
+                .map_record(Record::new(vec![Field::Int(1), Field::Int8(2)]))
+                .values,
+            vec![Field::Int(1), Field::Null, Field::Int8(2)]
+        );
+    }
+
+    #[test]
+    fn schema_change_widens_types() {
+        let current = users_schema();
+        let mut reported = Schema::default();
+        reported
+            .field(field("id", FieldType::Decimal, false), true)
+            .field(field("name", FieldType::Text, false), false)
+            .field(field("age", FieldType::Int, true), false);
+
+        let change = SchemaChange::new(&current, &reported).unwrap();
+        assert_eq!(change.schema, reported);
+
+        let old = Record::new(vec![
+            Field::Int(1),
+            Field::String("a".to_string()),
+            Field::Int8(2),
+        ]);
+        assert_eq!(
+            evolve_record(old, &change.schema).values,
+            vec![
+                Field::Decimal(1.into()),
+                Field::Text("a".to_string()),
+                Field::Int(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn schema_change_rejects_incompatible_changes() {
+        let current = users_schema();
+
+        let mut narrowed = users_schema();
+        narrowed.fields[0].typ = FieldType::Int8;
+        assert!(matches!(
+            SchemaChange::new(&current, &narrowed),
+            Err(SchemaChangeError::TypeChanged { field, .. }) if field == "id"
+        ));
+
+        let mut changed = users_schema();
+        changed.fields[1].typ = FieldType::Boolean;
+        assert!(matches!(
+            SchemaChange::new(&current, &changed),
+            Err(SchemaChangeError::TypeChanged { field, .. }) if field == "name"
+        ));
+
+        let mut without_key = Schema::default();
+        without_key.field(field("name", FieldType::String, false), false);
+        assert!(matches!(
+            SchemaChange::new(&current, &without_key),
+            Err(SchemaChangeError::PrimaryKeyDropped(field)) if field == "id"
+        ));
+    }
+
+    #[test]
+    fn field_changes_allows_evolved_fields() {
+        let current = users_schema();
+        let mut new = users_schema();
+        new.field(field("email", FieldType::String, true), false);
+        assert_eq!(
+            field_changes(&current.fields, &new.fields).unwrap(),
+            FieldChanges {
+                altered: vec![],
+                appended: &[field("email", FieldType::String, true)],
+            }
+        );
+
+        new.fields[0].typ = FieldType::I128;
+        new.fields[1].nullable = true;
+        assert_eq!(
+            field_changes(&current.fields, &new.fields).unwrap().altered,
+            vec![0, 1]
+        );
+
+        new.fields[2].nullable = false;
+        assert!(matches!(
+            field_changes(&current.fields, &new.fields),
+            Err(SchemaChangeError::Unsupported)
+        ));
+
+        let mut new = users_schema();
+        new.fields[0].typ = FieldType::Int8;
+        assert!(matches!(
+            field_changes(&current.fields, &new.fields),
+            Err(SchemaChangeError::TypeChanged { .. })
+        ));
+    }
 }
@@ -1,10 +1,12 @@
 use std::path::PathBuf;
 
 use crate::node::PortHandle;
+use crate::state_store::StateStoreError;
 use dozer_types::errors::internal::BoxedError;
 use dozer_types::errors::types::{DeserializationError, SerializationError};
 use dozer_types::node::NodeHandle;
 use dozer_types::thiserror::Error;
+use dozer_types::types::FieldType;
 use dozer_types::{bincode, thiserror};
 
 #[derive(Error, Debug)]
@@ -51,6 +53,57 @@ pub enum ExecutionError {
     FailedToCreateCheckpoint(BoxedError),
     #[error("Failed to serialize record writer: {0}")]
     SerializeRecordWriter(#[source] SerializationError),
+    #[error("Incompatible schema change on port {port} of {node}: {error}")]
+    IncompatibleSchemaChange {
+        node: NodeHandle,
+        port: PortHandle,
+        #[source]
+        error: SchemaChangeError,
+    },
+    #[error("Node {node} failed to apply schema change on port {port}: {error}")]
+    SchemaChangeNotApplied {
+        node: NodeHandle,
+        port: PortHandle,
+        #[source]
+        error: BoxedError,
+    },
+    #[error("Node {node} failed to truncate port {port}: {error}")]
+    TruncateNotApplied {
+        node: NodeHandle,
+        port: PortHandle,
+        #[source]
+        error: BoxedError,
+    },
+    #[error("Failed to restore processor {node}: {error}")]
+    RestoreProcessor {
+        node: NodeHandle,
+        #[source]
+        error: BoxedError,
+    },
+    #[error("State store of {node} failed: {error}")]
+    StateStore {
+        node: NodeHandle,
+        #[source]
+        error: StateStoreError,
+    },
+}
+
+#[derive(Error, Debug)]
+#[error("Truncate is not supported")]
+pub struct TruncateUnsupported;
+
+#[derive(Error, Debug)]
+pub enum SchemaChangeError {
+    #[error("Field {field} cannot change type from {from} to {to}")]
+    TypeChanged {
+        field: String,
+        from: FieldType,
+        to: FieldType,
+    },
+    #[error("Primary key field {0} cannot be dropped")]
+    PrimaryKeyDropped(String),
+    #[error("Schema changes are not supported")]
+    Unsupported,
 }
 
 impl<T> From<crossbeam::channel::SendError<T>> for ExecutionError {
@@ -23,6 +23,7 @@ use daggy::petgraph::{
 };
 use dozer_tracing::DozerMonitorContext;
 use dozer_types::node::NodeHandle;
+use dozer_types::types::Schema;
 use tokio::sync::Mutex;
 
 #[derive(Debug)]
@@ -39,6 +40,8 @@ pub struct EdgeType {
     pub output_port: PortHandle,
     /// Edge kind.
     pub edge_kind: EdgeKind,
+    /// The schema of the data flowing through this edge when the pipeline was built.
+    pub schema: Schema,
     /// The sender for data flowing downstream. Edges that have same source and target node share the same sender.
     pub sender: Sender<ExecutorOperation>,
     /// The record writer for persisting data for downstream queries, if persistency is needed. Different edges with the same output port share the same record writer.
@@ -120,6 +123,7 @@ impl ExecutionDag {
             let edge = EdgeType {
                 output_port,
                 edge_kind,
+                schema: edge.schema.clone(),
                 sender,
                 record_writer,
                 input_port: edge.input_port,
@@ -205,6 +209,16 @@ impl ExecutionDag {
         senders.into_values().collect()
     }
 
+    pub fn collect_output_schemas(
+        &self,
+        node_index: daggy::NodeIndex,
+    ) -> HashMap<PortHandle, Schema> {
+        self.graph
+            .edges(node_index)
+            .map(|edge| (edge.weight().output_port, edge.weight().schema.clone()))
+            .collect()
+    }
+
     pub async fn collect_record_writers(
         &mut self,
         node_index: daggy::NodeIndex,
@@ -8,6 +8,7 @@ use daggy::petgraph::visit::IntoNodeIdentifiers;
 use dozer_tracing::DozerMonitorContext;
 use futures::Future;
 use std::fmt::Debug;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::thread::{self, Builder};
@@ -19,6 +20,10 @@ pub struct ExecutorOptions {
     pub channel_buffer_sz: usize,
     pub event_hub_capacity: usize,
     pub error_threshold: Option<u32>,
+    /// Where stateful processors checkpoint their state. Their state is not durable if `None`.
+    pub state_dir: Option<PathBuf>,
+    /// How many bytes of state each stateful processor may keep in memory before spilling to disk.
+    pub state_memory_budget: Option<usize>,
 }
 
 impl Default for ExecutorOptions {
@@ -27,6 +32,8 @@ impl Default for ExecutorOptions {
             channel_buffer_sz: 20_000,
             event_hub_capacity: 100,
             error_threshold: Some(0),
+            state_dir: None,
+            state_memory_budget: None,
         }
     }
 }
@@ -59,7 +66,13 @@ impl DagExecutor {
     pub async fn new(dag: Dag, options: ExecutorOptions) -> Result<Self, ExecutionError> {
         let dag_schemas = DagSchemas::new(dag).await?;
 
-        let builder_dag = BuilderDag::new(dag_schemas, options.event_hub_capacity).await?;
+        let builder_dag = BuilderDag::new(
+            dag_schemas,
+            options.event_hub_capacity,
+            options.state_dir.as_deref(),
+            options.state_memory_budget,
+        )
+        .await?;
 
         Ok(Self {
             builder_dag,
@@ -97,11 +110,11 @@ impl DagExecutor {
             };
             match node {
                 NodeKind::Source { .. } => unreachable!("We already started the source node"),
-                NodeKind::Processor(_) => {
+                NodeKind::Processor { .. } => {
                     let processor_node = ProcessorNode::new(&mut execution_dag, node_index).await;
                     join_handles.push(start_processor(processor_node)?);
                 }
-                NodeKind::Sink(_) => {
+                NodeKind::Sink { .. } => {
                     let sink_node = SinkNode::new(&mut execution_dag, node_index);
                     join_handles.push(start_sink(sink_node)?);
                 }
@@ -4,13 +4,17 @@ use std::{borrow::Cow, mem::swap};
 use crossbeam::channel::Receiver;
 use daggy::NodeIndex;
 use dozer_types::node::{NodeHandle, OpIdentifier};
-use dozer_types::types::TableOperation;
+use dozer_types::types::{Schema, TableOperation};
 
 use crate::epoch::Epoch;
 use crate::error_manager::ErrorManager;
 use crate::executor_operation::ExecutorOperation;
+use crate::state_store::StateStore;
 use crate::{
-    builder_dag::NodeKind, errors::ExecutionError, forwarder::ChannelManager, node::Processor,
+    builder_dag::NodeKind,
+    errors::ExecutionError,
+    forwarder::ChannelManager,
+    node::{PortHandle, Processor},
 };
 
 use super::{execution_dag::ExecutionDag, name::Name, receiver_loop::ReceiverLoop};
@@ -28,6 +32,8 @@ pub struct ProcessorNode {
     receivers: Vec<Receiver<ExecutorOperation>>,
     /// The processor.
     processor: Box<dyn Processor>,
+    /// The store the processor writes its state through to, checkpointed on every commit.
+    state_store: Option<StateStore>,
     /// This node's output channel manager, for forwarding data, writing metadata and writing port state.
     channel_manager: ChannelManager,
     /// The error manager, for reporting non-fatal errors.
@@ -41,7 +47,11 @@ impl ProcessorNode {
             panic!("Must pass in a node")
         };
         let node_handle = node.handle.clone();
-        let NodeKind::Processor(processor) = kind else {
+        let NodeKind::Processor {
+            processor,
+            state_store,
+        } = kind
+        else {
             panic!("Must pass in a processor node");
         };
 
@@ -63,6 +73,7 @@ impl ProcessorNode {
             node_handles,
             receivers,
             processor,
+            state_store,
             channel_manager,
             error_manager: dag.error_manager().clone(),
         }
@@ -105,6 +116,14 @@ impl ReceiverLoop for ProcessorNode {
         if let Err(e) = self.processor.commit(&epoch) {
             self.error_manager.report(e);
         }
+        if let Some(state_store) = &self.state_store {
+            state_store
+                .checkpoint(&epoch.common_info.source_states)
+                .map_err(|error| ExecutionError::StateStore {
+                    node: self.node_handle.clone(),
+                    error,
+                })?;
+        }
 
         self.channel_manager.send_commit(epoch)
     }
@@ -126,4 +145,43 @@ impl ReceiverLoop for ProcessorNode {
         self.channel_manager
             .send_snapshotting_done(connection_name, id)
     }
+
+    fn on_source_state_changed(&mut self, state: Vec<u8>) -> Result<(), ExecutionError> {
+        self.channel_manager.send_source_state_changed(state)
+    }
+
+    fn on_schema_change(&mut self, port: PortHandle, schema: Schema) -> Result<(), ExecutionError> {
+        let output_schemas = self
+            .processor
+            .on_schema_change(port, &schema)
+            .map_err(|error| ExecutionError::SchemaChangeNotApplied {
+                node: self.node_handle.clone(),
+                port,
+                error,
+            })?;
+        for (port, schema) in output_schemas {
+            self.channel_manager.send_schema_change(port, schema)?;
+        }
+        Ok(())
+    }
+
+    fn on_truncate(&mut self, port: PortHandle) -> Result<(), ExecutionError> {
+        let output_ports = self.processor.on_truncate(port).map_err(|error| {
+            ExecutionError::TruncateNotApplied {
+                node: self.node_handle.clone(),
+                port,
+                error,
+            }
+        })?;
+        for port in output_ports {
+            self.channel_manager.send_truncate(port)?;
+        }
+        self.processor
+            .after_truncate(port, &mut self.channel_manager)
+            .map_err(|error| ExecutionError::TruncateNotApplied {
+                node: self.node_handle.clone(),
+                port,
+                error,
+            })
+    }
 }
@@ -1,9 +1,15 @@
 use std::borrow::Cow;
 
 use crossbeam::channel::{Receiver, Select};
-use dozer_types::{log::debug, node::OpIdentifier, types::TableOperation};
+use dozer_types::{
+    log::debug,
+    node::OpIdentifier,
+    types::{Schema, TableOperation},
+};
 
-use crate::{epoch::Epoch, errors::ExecutionError, executor_operation::ExecutorOperation};
+use crate::{
+    epoch::Epoch, errors::ExecutionError, executor_operation::ExecutorOperation, node::PortHandle,
+};
 
 use super::name::Name;
 
@@ -31,6 +37,12 @@ pub trait ReceiverLoop: Name {
         connection_name: String,
         id: Option<OpIdentifier>,
     ) -> Result<(), ExecutionError>;
+    /// Responds to `SourceStateChanged`.
+    fn on_source_state_changed(&mut self, state: Vec<u8>) -> Result<(), ExecutionError>;
+    /// Responds to `SchemaChange` of input `port`.
+    fn on_schema_change(&mut self, port: PortHandle, schema: Schema) -> Result<(), ExecutionError>;
+    /// Responds to `Truncate` of input `port`.
+    fn on_truncate(&mut self, port: PortHandle) -> Result<(), ExecutionError>;
 
     /// The loop implementation, calls [`on_op`], [`on_commit`] and [`on_terminate`] at appropriate times.
     fn receiver_loop(mut self, initial_epoch_id: u64) -> Result<(), ExecutionError>
@@ -93,6 +105,15 @@ pub trait ReceiverLoop: Name {
                 } => {
                     self.on_snapshotting_done(connection_name, id)?;
                 }
+                ExecutorOperation::SourceStateChanged { state } => {
+                    self.on_source_state_changed(state)?;
+                }
+                ExecutorOperation::SchemaChange { port, schema } => {
+                    self.on_schema_change(port, schema)?;
+                }
+                ExecutorOperation::Truncate { port } => {
+                    self.on_truncate(port)?;
+                }
             }
         }
     }
@@ -113,7 +134,7 @@ mod tests {
     use crossbeam::channel::{unbounded, Sender};
     use dozer_types::{
         node::{NodeHandle, SourceState, SourceStates},
-        types::{Field, Operation, Record},
+        types::{Field, FieldDefinition, FieldType, Operation, Record, SourceDefinition},
     };
 
     use crate::DEFAULT_PORT_HANDLE;
@@ -126,6 +147,9 @@ mod tests {
         commits: Vec<Epoch>,
         snapshotting_started: Vec<String>,
         snapshotting_done: Vec<(String, Option<OpIdentifier>)>,
+        source_states: Vec<Vec<u8>>,
+        schema_changes: Vec<(PortHandle, Schema)>,
+        truncates: Vec<PortHandle>,
         num_terminations: usize,
     }
 
@@ -192,6 +216,25 @@ mod tests {
                 .push((connection_name, state));
             Ok(())
         }
+
+        fn on_source_state_changed(&mut self, state: Vec<u8>) -> Result<(), ExecutionError> {
+            self.state.borrow_mut().source_states.push(state);
+            Ok(())
+        }
+
+        fn on_schema_change(
+            &mut self,
+            port: PortHandle,
+            schema: Schema,
+        ) -> Result<(), ExecutionError> {
+            self.state.borrow_mut().schema_changes.push((port, schema));
+            Ok(())
+        }
+
+        fn on_truncate(&mut self, port: PortHandle) -> Result<(), ExecutionError> {
+            self.state.borrow_mut().truncates.push(port);
+            Ok(())
+        }
     }
 
     impl TestReceiverLoop {
@@ -208,6 +251,9 @@ mod tests {
                 commits: vec![],
                 snapshotting_started: vec![],
                 snapshotting_done: vec![],
+                source_states: vec![],
+                schema_changes: vec![],
+                truncates: vec![],
                 num_terminations: 0,
             }));
             (
@@ -248,6 +294,62 @@ mod tests {
         assert_eq!(snapshotting_done, vec![(connection_name, None)])
     }
 
+    #[test]
+    fn receiver_loop_forwards_source_state_changed() {
+        let (test_loop, senders, state) = TestReceiverLoop::new(2);
+        senders[1]
+            .send(ExecutorOperation::SourceStateChanged {
+                state: b"progress".to_vec(),
+            })
+            .unwrap();
+        senders[0].send(ExecutorOperation::Terminate).unwrap();
+        senders[1].send(ExecutorOperation::Terminate).unwrap();
+        test_loop.receiver_loop(0).unwrap();
+        assert_eq!(state.borrow().source_states, vec![b"progress".to_vec()]);
+    }
+
+    #[test]
+    fn receiver_loop_forwards_schema_change() {
+        let (test_loop, senders, state) = TestReceiverLoop::new(2);
+        let mut schema = Schema::default();
+        schema.field(
+            FieldDefinition::new(
+                "added".to_string(),
+                FieldType::Int,
+                true,
+                SourceDefinition::Dynamic,
+            ),
+            false,
+        );
+        senders[1]
+            .send(ExecutorOperation::SchemaChange {
+                port: DEFAULT_PORT_HANDLE,
+                schema: schema.clone(),
+            })
+            .unwrap();
+        senders[0].send(ExecutorOperation::Terminate).unwrap();
+        senders[1].send(ExecutorOperation::Terminate).unwrap();
+        test_loop.receiver_loop(0).unwrap();
+        assert_eq!(
+            state.borrow().schema_changes,
+            vec![(DEFAULT_PORT_HANDLE, schema)]
+        );
+    }
+
+    #[test]
+    fn receiver_loop_forwards_truncate() {
+        let (test_loop, senders, state) = TestReceiverLoop::new(2);
+        senders[0]
+            .send(ExecutorOperation::Truncate {
+                port: DEFAULT_PORT_HANDLE,
+            })
+            .unwrap();
+        senders[0].send(ExecutorOperation::Terminate).unwrap();
+        senders[1].send(ExecutorOperation::Terminate).unwrap();
+        test_loop.receiver_loop(0).unwrap();
+        assert_eq!(state.borrow().truncates, vec![DEFAULT_PORT_HANDLE]);
+    }
+
     #[test]
     fn receiver_loop_forwards_op() {
         let (test_loop, senders, state) = TestReceiverLoop::new(2);
@@ -14,7 +14,7 @@ use dozer_types::{
     log::debug,
     node::{NodeHandle, OpIdentifier},
     tracing::error,
-    types::{Operation, TableOperation},
+    types::{Operation, Schema, TableOperation},
 };
 use std::{
     borrow::Cow,
@@ -26,8 +26,13 @@ use std::{
 use tokio::sync::broadcast;
 
 use crate::{
-    builder_dag::NodeKind, epoch::Epoch, error_manager::ErrorManager, errors::ExecutionError,
-    event::Event, executor_operation::ExecutorOperation, node::Sink,
+    builder_dag::NodeKind,
+    epoch::Epoch,
+    error_manager::ErrorManager,
+    errors::ExecutionError,
+    event::Event,
+    executor_operation::ExecutorOperation,
+    node::{PortHandle, Sink},
 };
 
 use super::execution_dag::ExecutionDag;
@@ -101,6 +106,9 @@ pub struct SinkNode {
     receivers: Vec<Receiver<ExecutorOperation>>,
     /// The sink.
     sink: Box<dyn Sink>,
+    /// The op id the sink persisted, if its source resumes. Its source resumes from the smallest
+    /// checkpoint of its sinks, so the operations up to this one were already written.
+    last_checkpoint: Option<OpIdentifier>,
     /// The error manager, for reporting non-fatal errors.
     error_manager: Arc<ErrorManager>,
     /// The metrics labels.
@@ -134,7 +142,11 @@ impl SinkNode {
             panic!("Must pass in a node")
         };
         let node_handle = node.handle.clone();
-        let NodeKind::Sink(sink) = kind else {
+        let NodeKind::Sink {
+            sink,
+            last_checkpoint,
+        } = kind
+        else {
             panic!("Must pass in a sink node");
         };
 
@@ -177,6 +189,7 @@ impl SinkNode {
             node_handles,
             receivers,
             sink,
+            last_checkpoint,
             error_manager: dag.error_manager().clone(),
             labels: dag.labels().clone(),
             last_op_if_commit: None,
@@ -387,11 +400,26 @@ impl ReceiverLoop for SinkNode {
                     );
                     self.on_snapshotting_done(connection_name, id)?;
                 }
+                ExecutorOperation::SourceStateChanged { state } => {
+                    self.on_source_state_changed(state)?;
+                }
+                ExecutorOperation::SchemaChange { port, schema } => {
+                    self.on_schema_change(port, schema)?;
+                }
+                ExecutorOperation::Truncate { port } => {
+                    self.on_truncate(port)?;
+                }
             }
         }
     }
 
     fn on_op(&mut self, _index: usize, op: TableOperation) -> Result<(), ExecutionError> {
+        if let (Some(id), Some(checkpoint)) = (op.id, self.last_checkpoint) {
+            if id <= checkpoint {
+                return Ok(());
+            }
+            self.last_checkpoint = None;
+        }
         self.last_op_if_commit = None;
         let mut labels = self.labels.attrs();
 
@@ -505,4 +533,32 @@ impl ReceiverLoop for SinkNode {
 
         Ok(())
     }
+
+    fn on_source_state_changed(&mut self, state: Vec<u8>) -> Result<(), ExecutionError> {
+        if let Err(e) = self.sink.set_source_state(&state) {
+            self.error_manager.report(e);
+        }
+        Ok(())
+    }
+
+    fn on_schema_change(&mut self, port: PortHandle, schema: Schema) -> Result<(), ExecutionError> {
+        self.sink.on_schema_change(port, &schema).map_err(|error| {
+            ExecutionError::SchemaChangeNotApplied {
+                node: self.node_handle.clone(),
+                port,
+                error,
+            }
+        })
+    }
+
+    fn on_truncate(&mut self, port: PortHandle) -> Result<(), ExecutionError> {
+        self.last_op_if_commit = None;
+        self.sink
+            .on_truncate(port)
+            .map_err(|error| ExecutionError::TruncateNotApplied {
+                node: self.node_handle.clone(),
+                port,
+                error,
+            })
+    }
 }
@@ -1,8 +1,13 @@
-use std::{fmt::Debug, future::Future, pin::pin, sync::Arc, time::SystemTime};
+use std::{
+    collections::HashMap, fmt::Debug, future::Future, pin::pin, sync::Arc, time::SystemTime,
+};
 
 use daggy::petgraph::visit::IntoNodeIdentifiers;
 use dozer_types::{
-    log::debug, models::ingestion_types::TransactionInfo, node::OpIdentifier, types::TableOperation,
+    log::debug,
+    models::ingestion_types::TransactionInfo,
+    node::OpIdentifier,
+    types::{Schema, TableOperation},
 };
 use dozer_types::{models::ingestion_types::IngestionMessage, node::SourceState};
 use futures::{future::Either, StreamExt};
@@ -13,6 +18,7 @@ use tokio::{
 
 use crate::{
     builder_dag::NodeKind,
+    dag_schemas::SchemaChange,
     epoch::Epoch,
     errors::ExecutionError,
     executor_operation::ExecutorOperation,
@@ -91,12 +97,21 @@ impl<F: Future + Unpin> Node for SourceNode<F> {
                     };
                     let source = &mut self.sources[index];
                     match message {
-                        IngestionMessage::OperationEvent { op, id, .. } => {
+                        IngestionMessage::OperationEvent { mut op, id, .. } => {
                             source.state = SourceState::NonRestartable;
+                            if let Some(change) = source.schema_changes.get(&port) {
+                                op = change.map_operation(op);
+                            }
                             source
                                 .channel_manager
                                 .send_op(TableOperation { op, id, port })?;
                         }
+                        IngestionMessage::SchemaChange { schema, .. } => {
+                            source.on_schema_change(port, &schema)?;
+                        }
+                        IngestionMessage::Truncate { .. } => {
+                            source.channel_manager.send_truncate(port)?;
+                        }
                         IngestionMessage::TransactionInfo(info) => match info {
                             TransactionInfo::Commit { id, source_time } => {
                                 if let Some(id) = id {
@@ -138,6 +153,9 @@ impl<F: Future + Unpin> Node for SourceNode<F> {
                                     id,
                                 )?;
                             }
+                            TransactionInfo::SourceStateChanged { state } => {
+                                source.channel_manager.send_source_state_changed(state)?;
+                            }
                         },
                     }
                 }
@@ -150,6 +168,37 @@ impl<F: Future + Unpin> Node for SourceNode<F> {
 struct RunningSource {
     channel_manager: ChannelManager,
     state: SourceState,
+    /// The current schema of every connected output port.
+    schemas: HashMap<PortHandle, Schema>,
+    /// Ports whose schema has changed since the pipeline was built.
+    schema_changes: HashMap<PortHandle, SchemaChange>,
+}
+
+impl RunningSource {
+    fn on_schema_change(
+        &mut self,
+        port: PortHandle,
+        reported: &Schema,
+    ) -> Result<(), ExecutionError> {
+        let Some(current) = self.schemas.get_mut(&port) else {
+            // No downstream node is interested in this port.
+            return Ok(());
+        };
+        let change = SchemaChange::new(current, reported).map_err(|error| {
+            ExecutionError::IncompatibleSchemaChange {
+                node: self.channel_manager.owner().clone(),
+                port,
+                error,
+            }
+        })?;
+        if change.schema != *current {
+            *current = change.schema.clone();
+            self.channel_manager
+                .send_schema_change(port, change.schema.clone())?;
+        }
+        self.schema_changes.insert(port, change);
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -200,6 +249,7 @@ pub async fn create_source_node<F>(
         };
 
         let senders = dag.collect_senders(node_index);
+        let schemas = dag.collect_output_schemas(node_index);
         let record_writers = dag.collect_record_writers(node_index).await;
         let channel_manager = ChannelManager::new(
             node_handle,
@@ -210,6 +260,8 @@ pub async fn create_source_node<F>(
         sources.push(RunningSource {
             channel_manager,
             state: SourceState::NotStarted,
+            schemas,
+            schema_changes: HashMap::new(),
         });
 
         let (sender, receiver) = channel(options.channel_buffer_sz);
@@ -1,4 +1,7 @@
-use dozer_types::{node::OpIdentifier, types::TableOperation};
+use dozer_types::{
+    node::OpIdentifier,
+    types::{PortHandle, Schema, TableOperation},
+};
 
 use crate::epoch::Epoch;
 
@@ -18,4 +21,17 @@ pub enum ExecutorOperation {
         connection_name: String,
         id: Option<OpIdentifier>,
     },
+    /// The source has a new state to be persisted, see [`crate::node::Sink::set_source_state`].
+    SourceStateChanged {
+        state: Vec<u8>,
+    },
+    /// Operations on `port` that follow have `schema`.
+    SchemaChange {
+        port: PortHandle,
+        schema: Schema,
+    },
+    /// All records received on `port` so far are deleted.
+    Truncate {
+        port: PortHandle,
+    },
 }
@@ -9,7 +9,7 @@ use crate::record_store::RecordWriter;
 use crossbeam::channel::Sender;
 use dozer_types::log::debug;
 use dozer_types::node::{NodeHandle, OpIdentifier};
-use dozer_types::types::TableOperation;
+use dozer_types::types::{Schema, TableOperation};
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::sync::Arc;
@@ -39,6 +39,34 @@ impl SenderWithPortMapping {
         }
         Ok(())
     }
+
+    pub fn send_schema_change(
+        &self,
+        port: PortHandle,
+        schema: &Schema,
+    ) -> Result<(), ExecutionError> {
+        let Some(ports) = self.port_mapping.get(&port) else {
+            return Ok(());
+        };
+        for port in ports {
+            self.sender.send(ExecutorOperation::SchemaChange {
+                port: *port,
+                schema: schema.clone(),
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn send_truncate(&self, port: PortHandle) -> Result<(), ExecutionError> {
+        let Some(ports) = self.port_mapping.get(&port) else {
+            return Ok(());
+        };
+        for port in ports {
+            self.sender
+                .send(ExecutorOperation::Truncate { port: *port })?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -85,6 +113,32 @@ impl ChannelManager {
         Ok(())
     }
 
+    /// Notifies downstream nodes that operations on output `port` have `schema` from now on.
+    pub fn send_schema_change(
+        &mut self,
+        port: PortHandle,
+        schema: Schema,
+    ) -> Result<(), ExecutionError> {
+        if let Some(writer) = self.record_writers.get_mut(&port) {
+            writer.on_schema_change(&schema);
+        }
+        for sender in &self.senders {
+            sender.send_schema_change(port, &schema)?;
+        }
+        Ok(())
+    }
+
+    /// Notifies downstream nodes that all records sent on output `port` so far are deleted.
+    pub fn send_truncate(&mut self, port: PortHandle) -> Result<(), ExecutionError> {
+        if let Some(writer) = self.record_writers.get_mut(&port) {
+            writer.on_truncate();
+        }
+        for sender in &self.senders {
+            sender.send_truncate(port)?;
+        }
+        Ok(())
+    }
+
     pub fn send_terminate(&self) -> Result<(), ExecutionError> {
         self.send_non_op(ExecutorOperation::Terminate)
     }
@@ -104,6 +158,10 @@ impl ChannelManager {
         })
     }
 
+    pub fn send_source_state_changed(&self, state: Vec<u8>) -> Result<(), ExecutionError> {
+        self.send_non_op(ExecutorOperation::SourceStateChanged { state })
+    }
+
     pub fn send_commit(&mut self, epoch: Epoch) -> Result<(), ExecutionError> {
         debug!(
             "[{}] Checkpointing - {}: {:?}",
@@ -14,6 +14,7 @@ mod hash_map_to_vec;
 pub mod node;
 pub mod record_store;
 pub mod shutdown;
+pub mod state_store;
 pub use tokio;
 
 #[cfg(test)]
@@ -1,6 +1,8 @@
 use crate::channels::ProcessorChannelForwarder;
 use crate::epoch::Epoch;
+use crate::errors::{SchemaChangeError, TruncateUnsupported};
 use crate::event::EventHub;
+use crate::state_store::StateStore;
 
 use dozer_types::errors::internal::BoxedError;
 use dozer_types::models::ingestion_types::IngestionMessage;
@@ -93,6 +95,50 @@ pub trait Processor: Send + Sync + Debug {
         op: TableOperation,
         fw: &mut dyn ProcessorChannelForwarder,
     ) -> Result<(), BoxedError>;
+
+    /// Operations on input `port` have `schema` from now on.
+    ///
+    /// Returns the output ports whose schema changed as a result, with their new schema.
+    fn on_schema_change(
+        &mut self,
+        _port: PortHandle,
+        _schema: &Schema,
+    ) -> Result<Vec<(PortHandle, Schema)>, BoxedError> {
+        Err(SchemaChangeError::Unsupported.into())
+    }
+
+    /// All records received on input `port` so far are deleted.
+    ///
+    /// Returns the output ports whose records are all deleted as a result.
+    fn on_truncate(&mut self, _port: PortHandle) -> Result<Vec<PortHandle>, BoxedError> {
+        Err(TruncateUnsupported.into())
+    }
+
+    /// Sends the records that remain on the output ports [`Processor::on_truncate`] returned for input
+    /// `port`, after the truncate was sent on them. They're derived from the records of other inputs,
+    /// which the truncate didn't delete.
+    fn after_truncate(
+        &mut self,
+        _port: PortHandle,
+        _fw: &mut dyn ProcessorChannelForwarder,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    /// Whether the processor keeps state built from the operations it has processed.
+    ///
+    /// If the pipeline has a state directory, a stateful processor is restored from its [`StateStore`].
+    /// Sources that can't resume where the store was checkpointed start over.
+    fn is_stateful(&self) -> bool {
+        false
+    }
+
+    /// Loads the state in `store` and writes all state changes through to it from now on.
+    ///
+    /// The store is checkpointed after every [`Processor::commit`].
+    fn restore(&mut self, _store: StateStore) -> Result<(), BoxedError> {
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -125,6 +171,22 @@ pub trait Sink: Send + Debug {
     fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError>;
     fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError>;
 
+    /// Whether the sink persists the op id [`Sink::get_latest_op_id`] returns. Sinks that don't
+    /// never hold their sources back from resuming at the checkpoint of their other sinks.
+    fn persists_op_id(&self) -> bool {
+        true
+    }
+
+    /// Operations on input `port` have `schema` from now on.
+    fn on_schema_change(&mut self, _port: PortHandle, _schema: &Schema) -> Result<(), BoxedError> {
+        Err(SchemaChangeError::Unsupported.into())
+    }
+
+    /// All records received on input `port` so far are deleted.
+    fn on_truncate(&mut self, _port: PortHandle) -> Result<(), BoxedError> {
+        Err(TruncateUnsupported.into())
+    }
+
     fn preferred_batch_size(&self) -> Option<u64> {
         None
     }
@@ -2,6 +2,8 @@ use dozer_types::errors::types::DeserializationError;
 use dozer_types::thiserror::Error;
 use dozer_types::types::{Operation, Record, Schema};
 use std::collections::HashMap;
+
+use crate::dag_schemas::evolve_record;
 use std::fmt::{Debug, Formatter};
 
 #[derive(Debug, Error)]
@@ -12,6 +14,10 @@ pub enum RecordWriterError {
 
 pub trait RecordWriter: Send + Sync {
     fn write(&mut self, op: Operation) -> Result<Operation, RecordWriterError>;
+    /// Converts the written records to `schema`, which is the result of a [`SchemaChange`](crate::dag_schemas::SchemaChange).
+    fn on_schema_change(&mut self, schema: &Schema);
+    /// Forgets all the written records.
+    fn on_truncate(&mut self);
 }
 
 impl Debug for dyn RecordWriter {
@@ -84,4 +90,20 @@ impl RecordWriter for PrimaryKeyLookupRecordWriter {
             }
         }
     }
+
+    fn on_schema_change(&mut self, schema: &Schema) {
+        // The primary key fields keep their index, but their values may be widened.
+        self.schema = schema.clone();
+        self.index = std::mem::take(&mut self.index)
+            .into_values()
+            .map(|record| {
+                let record = evolve_record(record, &self.schema);
+                (record.get_key(&self.schema.primary_index), record)
+            })
+            .collect();
+    }
+
+    fn on_truncate(&mut self) {
+        self.index.clear();
+    }
 }
@@ -0,0 +1,543 @@
+//! Durable state of stateful processors.
+//!
+//! Every stateful processor gets its own store, an SQLite database in the pipeline's state directory.
+//! Processors write state changes through to the store as they process operations. The changes are
+//! only made durable when the processor node checkpoints the store at an [`Epoch`](crate::epoch::Epoch),
+//! together with the [`SourceStates`] of that epoch, so the stored state always matches the source
+//! positions it was built from.
+//!
+//! Sinks persist their progress independently of the processors, so a processor is usually a few
+//! epochs ahead of its sinks when the pipeline stops. The store keeps the previous values of the
+//! entries written since the last [`RETAINED_CHECKPOINTS`] checkpoints, so it can roll back to the
+//! checkpoint the sinks resume from.
+//!
+//! A store can also have a memory budget. Processors keep their state in memory up to the budget and
+//! drop the least recently used part beyond it, reading it back from the store when it's needed.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use dozer_types::bincode::{
+    self,
+    enc::{write::SizeWriter, EncoderImpl},
+    Decode, Encode,
+};
+use dozer_types::node::{NodeHandle, SourceStates};
+use dozer_types::parking_lot::Mutex;
+use dozer_types::thiserror::{self, Error};
+use rusqlite::{params, Connection, OptionalExtension};
+
+#[derive(Debug, Error)]
+pub enum StateStoreError {
+    #[error("Cannot create state directory {0:?}: {1}")]
+    CreateDir(PathBuf, #[source] std::io::Error),
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("Cannot encode state: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+    #[error("Cannot decode state: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+}
+
+/// A key-value store that processors write their state through to.
+///
+/// Entries are grouped by namespace and iterated in key order. Clones share the same database.
+#[derive(Debug, Clone)]
+pub struct StateStore {
+    connection: Arc<Mutex<Connection>>,
+    memory_budget: Option<usize>,
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS entries (
+    namespace TEXT NOT NULL,
+    key BLOB NOT NULL,
+    value BLOB NOT NULL,
+    PRIMARY KEY (namespace, key)
+) WITHOUT ROWID;
+CREATE TABLE IF NOT EXISTS checkpoints (
+    id INTEGER PRIMARY KEY,
+    source_states BLOB NOT NULL
+);
+CREATE TABLE IF NOT EXISTS undo (
+    checkpoint INTEGER NOT NULL,
+    namespace TEXT NOT NULL,
+    key BLOB NOT NULL,
+    value BLOB,
+    PRIMARY KEY (checkpoint, namespace, key)
+) WITHOUT ROWID;
+";
+
+/// How many checkpoints a store can roll back to.
+pub const RETAINED_CHECKPOINTS: i64 = 100;
+
+/// Records the value of an entry before its first write since the last checkpoint, if there's one.
+const SAVE_UNDO: &str = "
+INSERT OR IGNORE INTO undo (checkpoint, namespace, key, value)
+SELECT id, ?1, ?2, (SELECT value FROM entries WHERE namespace = ?1 AND key = ?2)
+FROM checkpoints ORDER BY id DESC LIMIT 1
+";
+
+impl StateStore {
+    /// Opens the store of processor `node` in `state_dir`, creating it if it doesn't exist.
+    pub fn open(state_dir: &Path, node: &NodeHandle) -> Result<Self, StateStoreError> {
+        std::fs::create_dir_all(state_dir)
+            .map_err(|e| StateStoreError::CreateDir(state_dir.to_path_buf(), e))?;
+        let file_name = match node.ns {
+            Some(ns) => format!("{ns}_{}.sqlite", node.id),
+            None => format!("{}.sqlite", node.id),
+        };
+        Self::new(Connection::open(state_dir.join(file_name))?)
+    }
+
+    /// A store that's lost when the last clone is dropped.
+    pub fn in_memory() -> Result<Self, StateStoreError> {
+        Self::new(Connection::open_in_memory()?)
+    }
+
+    /// A store on disk that's deleted when the last clone is dropped.
+    pub fn temporary() -> Result<Self, StateStoreError> {
+        Self::new(Connection::open("")?)
+    }
+
+    fn new(connection: Connection) -> Result<Self, StateStoreError> {
+        connection.execute_batch(SCHEMA)?;
+        // All writes until the next checkpoint go into this transaction.
+        connection.execute_batch("BEGIN")?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+            memory_budget: None,
+        })
+    }
+
+    /// Sets how many bytes of state the processor may keep in memory.
+    pub fn with_memory_budget(mut self, memory_budget: Option<usize>) -> Self {
+        self.memory_budget = memory_budget;
+        self
+    }
+
+    pub fn memory_budget(&self) -> Option<usize> {
+        self.memory_budget
+    }
+
+    pub fn put(
+        &self,
+        namespace: &str,
+        key: &impl Encode,
+        value: &impl Encode,
+    ) -> Result<(), StateStoreError> {
+        let key = encode(key)?;
+        let value = encode(value)?;
+        let connection = self.connection.lock();
+        connection
+            .prepare_cached(SAVE_UNDO)?
+            .execute(params![namespace, key])?;
+        connection
+            .prepare_cached(
+                "INSERT OR REPLACE INTO entries (namespace, key, value) VALUES (?1, ?2, ?3)",
+            )?
+            .execute(params![namespace, key, value])?;
+        Ok(())
+    }
+
+    pub fn get<V: Decode>(
+        &self,
+        namespace: &str,
+        key: &impl Encode,
+    ) -> Result<Option<V>, StateStoreError> {
+        let key = encode(key)?;
+        let value: Option<Vec<u8>> = self
+            .connection
+            .lock()
+            .prepare_cached("SELECT value FROM entries WHERE namespace = ?1 AND key = ?2")?
+            .query_row(params![namespace, key], |row| row.get(0))
+            .optional()?;
+        value.map(|value| decode(&value)).transpose()
+    }
+
+    pub fn delete(&self, namespace: &str, key: &impl Encode) -> Result<(), StateStoreError> {
+        let key = encode(key)?;
+        let connection = self.connection.lock();
+        connection
+            .prepare_cached(SAVE_UNDO)?
+            .execute(params![namespace, key])?;
+        connection
+            .prepare_cached("DELETE FROM entries WHERE namespace = ?1 AND key = ?2")?
+            .execute(params![namespace, key])?;
+        Ok(())
+    }
+
+    pub fn delete_namespace(&self, namespace: &str) -> Result<(), StateStoreError> {
+        let connection = self.connection.lock();
+        connection
+            .prepare_cached(
+                "INSERT OR IGNORE INTO undo (checkpoint, namespace, key, value)
+                SELECT (SELECT MAX(id) FROM checkpoints), namespace, key, value FROM entries
+                WHERE namespace = ?1 AND EXISTS (SELECT 1 FROM checkpoints)",
+            )?
+            .execute(params![namespace])?;
+        connection
+            .prepare_cached("DELETE FROM entries WHERE namespace = ?1")?
+            .execute(params![namespace])?;
+        Ok(())
+    }
+
+    /// All entries of `namespace`, ordered by encoded key.
+    pub fn entries<K: Decode, V: Decode>(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<(K, V)>, StateStoreError> {
+        let mut entries = vec![];
+        self.for_each_entry(namespace, |key, value| {
+            entries.push((key, value));
+            Ok::<_, StateStoreError>(())
+        })?;
+        Ok(entries)
+    }
+
+    /// Calls `f` on all entries of `namespace` in encoded key order, without loading them all at once.
+    ///
+    /// `f` must not use the store.
+    pub fn for_each_entry<K: Decode, V: Decode, E: From<StateStoreError>>(
+        &self,
+        namespace: &str,
+        mut f: impl FnMut(K, V) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let connection = self.connection.lock();
+        let mut statement = connection
+            .prepare_cached("SELECT key, value FROM entries WHERE namespace = ?1 ORDER BY key")
+            .map_err(StateStoreError::from)?;
+        let mut rows = statement
+            .query(params![namespace])
+            .map_err(StateStoreError::from)?;
+        while let Some(row) = rows.next().map_err(StateStoreError::from)? {
+            let (key, value) = decode_row(row)?;
+            f(key, value)?;
+        }
+        Ok(())
+    }
+
+    /// The entries of `namespace` whose key starts with the encoding of `prefix`, ordered by encoded key.
+    ///
+    /// As encodings are self-delimiting, these are the keys that are tuples starting with `prefix`.
+    pub fn entries_with_prefix<K: Decode, V: Decode>(
+        &self,
+        namespace: &str,
+        prefix: &impl Encode,
+    ) -> Result<Vec<(K, V)>, StateStoreError> {
+        let start = encode(prefix)?;
+        let connection = self.connection.lock();
+        let mut statement;
+        let mut rows = match prefix_end(&start) {
+            Some(end) => {
+                statement = connection.prepare_cached(
+                    "SELECT key, value FROM entries WHERE namespace = ?1 AND key >= ?2 AND key < ?3 ORDER BY key",
+                )?;
+                statement.query(params![namespace, start, end])?
+            }
+            None => {
+                statement = connection.prepare_cached(
+                    "SELECT key, value FROM entries WHERE namespace = ?1 AND key >= ?2 ORDER BY key",
+                )?;
+                statement.query(params![namespace, start])?
+            }
+        };
+        let mut entries = vec![];
+        while let Some(row) = rows.next()? {
+            entries.push(decode_row(row)?);
+        }
+        Ok(entries)
+    }
+
+    /// The entries of `namespace` whose key is less than the encoding of `end`, ordered by encoded key.
+    pub fn entries_before<K: Decode, V: Decode>(
+        &self,
+        namespace: &str,
+        end: &impl Encode,
+    ) -> Result<Vec<(K, V)>, StateStoreError> {
+        let end = encode(end)?;
+        let connection = self.connection.lock();
+        let mut statement = connection.prepare_cached(
+            "SELECT key, value FROM entries WHERE namespace = ?1 AND key < ?2 ORDER BY key",
+        )?;
+        let mut rows = statement.query(params![namespace, end])?;
+        let mut entries = vec![];
+        while let Some(row) = rows.next()? {
+            entries.push(decode_row(row)?);
+        }
+        Ok(entries)
+    }
+
+    /// Removes all entries and checkpoints.
+    pub fn clear(&self) -> Result<(), StateStoreError> {
+        self.connection.lock().execute_batch(
+            "DELETE FROM entries; DELETE FROM checkpoints; DELETE FROM undo; COMMIT; BEGIN",
+        )?;
+        Ok(())
+    }
+
+    /// Makes the writes so far durable, as the state of the processor at `source_states`.
+    ///
+    /// Checkpoints older than the last [`RETAINED_CHECKPOINTS`] can't be rolled back to anymore.
+    pub fn checkpoint(&self, source_states: &SourceStates) -> Result<(), StateStoreError> {
+        let source_states = encode(source_states)?;
+        let connection = self.connection.lock();
+        let id: i64 = connection
+            .prepare_cached("INSERT INTO checkpoints (source_states) VALUES (?1) RETURNING id")?
+            .query_row(params![source_states], |row| row.get(0))?;
+        let oldest = id - (RETAINED_CHECKPOINTS - 1);
+        connection
+            .prepare_cached("DELETE FROM checkpoints WHERE id < ?1")?
+            .execute(params![oldest])?;
+        connection
+            .prepare_cached("DELETE FROM undo WHERE checkpoint < ?1")?
+            .execute(params![oldest])?;
+        connection.execute_batch("COMMIT; BEGIN")?;
+        Ok(())
+    }
+
+    /// The checkpoints the store can roll back to, with their source states, the last one first.
+    ///
+    /// Writes after the last checkpoint are discarded when the store is reopened.
+    pub fn checkpoints(&self) -> Result<Vec<(i64, SourceStates)>, StateStoreError> {
+        let connection = self.connection.lock();
+        let mut statement = connection
+            .prepare_cached("SELECT id, source_states FROM checkpoints ORDER BY id DESC")?;
+        let mut rows = statement.query([])?;
+        let mut checkpoints = vec![];
+        while let Some(row) = rows.next()? {
+            let source_states: Vec<u8> = row.get(1)?;
+            checkpoints.push((row.get(0)?, decode(&source_states)?));
+        }
+        Ok(checkpoints)
+    }
+
+    /// Restores the entries to what they were at `checkpoint` and removes the later checkpoints.
+    pub fn roll_back(&self, checkpoint: i64) -> Result<(), StateStoreError> {
+        let connection = self.connection.lock();
+        // Applied from the last checkpoint back, so the value before the first write since
+        // `checkpoint` is restored last.
+        let mut undo = connection.prepare_cached(
+            "SELECT namespace, key, value FROM undo WHERE checkpoint >= ?1 ORDER BY checkpoint DESC",
+        )?;
+        let mut rows = undo.query(params![checkpoint])?;
+        while let Some(row) = rows.next()? {
+            let namespace: String = row.get(0)?;
+            let key: Vec<u8> = row.get(1)?;
+            match row.get::<_, Option<Vec<u8>>>(2)? {
+                Some(value) => connection
+                    .prepare_cached(
+                        "INSERT OR REPLACE INTO entries (namespace, key, value) VALUES (?1, ?2, ?3)",
+                    )?
+                    .execute(params![namespace, key, value])?,
+                None => connection
+                    .prepare_cached("DELETE FROM entries WHERE namespace = ?1 AND key = ?2")?
+                    .execute(params![namespace, key])?,
+            };
+        }
+        drop(rows);
+        drop(undo);
+        connection
+            .prepare_cached("DELETE FROM undo WHERE checkpoint >= ?1")?
+            .execute(params![checkpoint])?;
+        connection
+            .prepare_cached("DELETE FROM checkpoints WHERE id > ?1")?
+            .execute(params![checkpoint])?;
+        connection.execute_batch("COMMIT; BEGIN")?;
+        Ok(())
+    }
+}
+
+fn encode(value: &impl Encode) -> Result<Vec<u8>, StateStoreError> {
+    Ok(bincode::encode_to_vec(value, bincode::config::legacy())?)
+}
+
+fn decode<T: Decode>(bytes: &[u8]) -> Result<T, StateStoreError> {
+    Ok(bincode::decode_from_slice(bytes, bincode::config::legacy())?.0)
+}
+
+fn decode_row<K: Decode, V: Decode>(row: &rusqlite::Row) -> Result<(K, V), StateStoreError> {
+    let key: Vec<u8> = row.get(0)?;
+    let value: Vec<u8> = row.get(1)?;
+    Ok((decode(&key)?, decode(&value)?))
+}
+
+/// The smallest key greater than all keys starting with `prefix`, if there's one.
+///
+/// SQLite compares blobs with `memcmp`.
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
+
+/// The size of `value` in the store, which approximates the memory it takes.
+pub fn encoded_size(value: &impl Encode) -> Result<usize, StateStoreError> {
+    let mut encoder = EncoderImpl::new(SizeWriter::default(), bincode::config::legacy());
+    value.encode(&mut encoder)?;
+    Ok(encoder.into_writer().bytes_written)
+}
+
+#[cfg(test)]
+mod tests {
+    use dozer_types::node::{OpIdentifier, SourceState};
+
+    use super::*;
+
+    fn source_states(op_id: OpIdentifier) -> SourceStates {
+        [(
+            NodeHandle::new(None, "src".to_string()),
+            SourceState::Restartable(op_id),
+        )]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn test_entries_are_ordered_by_namespace_and_key() {
+        let store = StateStore::in_memory().unwrap();
+        store.put("a", &2u8, &"two".to_string()).unwrap();
+        store.put("a", &1u8, &"one".to_string()).unwrap();
+        store.put("b", &0u8, &"zero".to_string()).unwrap();
+        store.put("a", &2u8, &"deux".to_string()).unwrap();
+        assert_eq!(
+            store.entries::<u8, String>("a").unwrap(),
+            vec![(1, "one".to_string()), (2, "deux".to_string())]
+        );
+        assert_eq!(
+            store.get::<String>("b", &0u8).unwrap(),
+            Some("zero".to_string())
+        );
+
+        store.delete("a", &1u8).unwrap();
+        assert_eq!(store.get::<String>("a", &1u8).unwrap(), None);
+        store.delete_namespace("a").unwrap();
+        assert!(store.entries::<u8, String>("a").unwrap().is_empty());
+        assert_eq!(store.entries::<u8, String>("b").unwrap().len(), 1);
+        store.clear().unwrap();
+        assert!(store.entries::<u8, String>("b").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_entries_with_prefix() {
+        let store = StateStore::temporary().unwrap();
+        for (prefix, suffix) in [(1u8, 0u8), (1, 255), (2, 0), (255, 1), (255, 2)] {
+            store.put("a", &(prefix, suffix), &suffix).unwrap();
+        }
+        store.put("b", &(1u8, 1u8), &1u8).unwrap();
+        assert_eq!(
+            store
+                .entries_with_prefix::<(u8, u8), u8>("a", &1u8)
+                .unwrap(),
+            vec![((1, 0), 0), ((1, 255), 255)]
+        );
+        assert_eq!(
+            store
+                .entries_with_prefix::<(u8, u8), u8>("a", &255u8)
+                .unwrap(),
+            vec![((255, 1), 1), ((255, 2), 2)]
+        );
+        assert!(store
+            .entries_with_prefix::<(u8, u8), u8>("a", &3u8)
+            .unwrap()
+            .is_empty());
+        assert_eq!(encoded_size(&(1u8, 1u8)).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_entries_before() {
+        let store = StateStore::in_memory().unwrap();
+        for (prefix, suffix) in [(1u8, 0u8), (1, 255), (2, 0), (3, 1)] {
+            store.put("a", &(prefix, suffix), &suffix).unwrap();
+        }
+        assert_eq!(
+            store.entries_before::<(u8, u8), u8>("a", &2u8).unwrap(),
+            vec![((1, 0), 0), ((1, 255), 255)]
+        );
+        assert_eq!(
+            store
+                .entries_before::<(u8, u8), u8>("a", &(2u8, 1u8))
+                .unwrap(),
+            vec![((1, 0), 0), ((1, 255), 255), ((2, 0), 0)]
+        );
+    }
+
+    #[test]
+    fn test_reopen_restores_last_checkpoint() {
+        let dir = std::env::temp_dir().join(format!("dozer-state-store-{}", std::process::id()));
+        let node = NodeHandle::new(Some(1), "aggregation".to_string());
+
+        let store = StateStore::open(&dir, &node).unwrap();
+        assert!(store.checkpoints().unwrap().is_empty());
+        store.put("counts", &"a".to_string(), &1u64).unwrap();
+        store
+            .checkpoint(&source_states(OpIdentifier::new(1, 0)))
+            .unwrap();
+        store.put("counts", &"a".to_string(), &2u64).unwrap();
+        drop(store);
+
+        let store = StateStore::open(&dir, &node).unwrap();
+        let checkpoints = store.checkpoints().unwrap();
+        assert_eq!(checkpoints.len(), 1);
+        assert_eq!(checkpoints[0].1, source_states(OpIdentifier::new(1, 0)));
+        assert_eq!(
+            store.get::<u64>("counts", &"a".to_string()).unwrap(),
+            Some(1)
+        );
+        drop(store);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_roll_back_to_earlier_checkpoint() {
+        let store = StateStore::temporary().unwrap();
+        store.put("counts", &"a".to_string(), &1u64).unwrap();
+        store
+            .checkpoint(&source_states(OpIdentifier::new(1, 0)))
+            .unwrap();
+        store.put("counts", &"a".to_string(), &2u64).unwrap();
+        store.put("counts", &"b".to_string(), &1u64).unwrap();
+        store
+            .checkpoint(&source_states(OpIdentifier::new(2, 0)))
+            .unwrap();
+        store.put("counts", &"a".to_string(), &3u64).unwrap();
+        store.delete_namespace("counts").unwrap();
+        store
+            .checkpoint(&source_states(OpIdentifier::new(3, 0)))
+            .unwrap();
+
+        let checkpoints = store.checkpoints().unwrap();
+        assert_eq!(checkpoints.len(), 3);
+        assert_eq!(checkpoints[2].1, source_states(OpIdentifier::new(1, 0)));
+        store.roll_back(checkpoints[2].0).unwrap();
+        assert_eq!(
+            store.entries::<String, u64>("counts").unwrap(),
+            vec![("a".to_string(), 1)]
+        );
+        assert_eq!(store.checkpoints().unwrap(), vec![checkpoints[2].clone()]);
+    }
+
+    #[test]
+    fn test_old_checkpoints_are_dropped() {
+        let store = StateStore::in_memory().unwrap();
+        for op in 0..RETAINED_CHECKPOINTS as u64 + 10 {
+            store.put("counts", &(), &op).unwrap();
+            store
+                .checkpoint(&source_states(OpIdentifier::new(op, 0)))
+                .unwrap();
+        }
+        let checkpoints = store.checkpoints().unwrap();
+        assert_eq!(checkpoints.len(), RETAINED_CHECKPOINTS as usize);
+        assert_eq!(
+            checkpoints.last().unwrap().1,
+            source_states(OpIdentifier::new(10, 0))
+        );
+        store.roll_back(checkpoints.last().unwrap().0).unwrap();
+        assert_eq!(store.get::<u64>("counts", &()).unwrap(), Some(10));
+    }
+}
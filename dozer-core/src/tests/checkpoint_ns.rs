@@ -1,9 +1,14 @@
-use super::run_dag;
+use super::{create_test_runtime, run_dag};
+use crate::builder_dag::{BuilderDag, NodeKind};
+use crate::dag_schemas::DagSchemas;
+use crate::state_store::StateStore;
 use crate::tests::dag_base_run::NoopJoinProcessorFactory;
-use crate::tests::sinks::{CountingSinkFactory, COUNTING_SINK_INPUT_PORT};
+use crate::tests::processors::StatefulProcessorFactory;
+use crate::tests::sinks::{CheckpointedSinkFactory, CountingSinkFactory, COUNTING_SINK_INPUT_PORT};
 use crate::tests::sources::{GeneratorSourceFactory, GENERATOR_SOURCE_OUTPUT_PORT};
 use crate::{Dag, Endpoint, DEFAULT_PORT_HANDLE};
-use dozer_types::node::NodeHandle;
+use dozer_types::node::{NodeHandle, OpIdentifier, SourceState};
+use std::path::Path;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
@@ -70,3 +75,224 @@ fn test_checkpoint_consistency_ns() {
 
     run_dag(dag).unwrap();
 }
+
+/// Connects one source to `sinks`.
+fn dag_with_sinks(count: u64, running: bool, sinks: Vec<CheckpointedSinkFactory>) -> Dag {
+    let mut dag = Dag::new();
+    let source_handle = NodeHandle::new(None, "src".to_string());
+    dag.add_source(
+        source_handle.clone(),
+        Box::new(GeneratorSourceFactory::new(
+            count,
+            Arc::new(AtomicBool::new(running)),
+            false,
+        )),
+    );
+    for (i, sink) in sinks.into_iter().enumerate() {
+        let sink_handle = NodeHandle::new(None, format!("sink{i}"));
+        dag.add_sink(sink_handle.clone(), Box::new(sink));
+        dag.connect(
+            Endpoint::new(source_handle.clone(), GENERATOR_SOURCE_OUTPUT_PORT),
+            Endpoint::new(sink_handle, DEFAULT_PORT_HANDLE),
+        )
+        .unwrap();
+    }
+    dag
+}
+
+/// The checkpoint the source resumes from, and the checkpoints of the sinks.
+fn build_with_sinks(
+    sinks: Vec<CheckpointedSinkFactory>,
+) -> (Option<OpIdentifier>, Vec<Option<OpIdentifier>>) {
+    let dag = dag_with_sinks(1, true, sinks);
+    let builder_dag = create_test_runtime().block_on(async {
+        let dag_schemas = DagSchemas::new(dag).await.unwrap();
+        BuilderDag::new(dag_schemas, 10, None, None).await.unwrap()
+    });
+    let mut source_checkpoint = None;
+    let mut sink_checkpoints = vec![];
+    for node in builder_dag.graph().raw_nodes() {
+        match &node.weight.kind {
+            NodeKind::Source {
+                last_checkpoint, ..
+            } => source_checkpoint = *last_checkpoint,
+            NodeKind::Sink {
+                last_checkpoint, ..
+            } => sink_checkpoints.push(*last_checkpoint),
+            NodeKind::Processor { .. } => (),
+        }
+    }
+    (source_checkpoint, sink_checkpoints)
+}
+
+fn build_with_sink_checkpoints(checkpoints: &[Option<OpIdentifier>]) -> Option<OpIdentifier> {
+    build_with_sinks(
+        checkpoints
+            .iter()
+            .map(|checkpoint| CheckpointedSinkFactory::new(*checkpoint))
+            .collect(),
+    )
+    .0
+}
+
+#[test]
+fn test_source_resumes_from_min_sink_checkpoint() {
+    assert_eq!(
+        build_with_sink_checkpoints(&[
+            Some(OpIdentifier::new(5, 0)),
+            Some(OpIdentifier::new(3, 2))
+        ]),
+        Some(OpIdentifier::new(3, 2))
+    );
+    // A sink without checkpoint needs all the data.
+    assert_eq!(
+        build_with_sink_checkpoints(&[Some(OpIdentifier::new(5, 0)), None]),
+        None
+    );
+}
+
+#[test]
+fn test_sink_without_persisted_op_id_does_not_hold_source_back() {
+    assert_eq!(
+        build_with_sinks(vec![
+            CheckpointedSinkFactory::new(Some(OpIdentifier::new(5, 0))),
+            CheckpointedSinkFactory::without_persisted_op_id(),
+        ]),
+        (
+            Some(OpIdentifier::new(5, 0)),
+            vec![Some(OpIdentifier::new(5, 0)), None]
+        )
+    );
+    // The sinks get everything again if the source starts over.
+    assert_eq!(
+        build_with_sinks(vec![
+            CheckpointedSinkFactory::new(Some(OpIdentifier::new(5, 0))),
+            CheckpointedSinkFactory::new(None),
+        ]),
+        (None, vec![None, None])
+    );
+}
+
+#[test]
+fn test_sink_skips_operations_up_to_its_checkpoint() {
+    let behind = CheckpointedSinkFactory::new(Some(OpIdentifier::new(0, 1)));
+    let ahead = CheckpointedSinkFactory::new(Some(OpIdentifier::new(0, 3)));
+    let (behind_processed, ahead_processed) = (behind.processed(), ahead.processed());
+    // The source resumes after the checkpoint of the sink that is behind.
+    run_dag(dag_with_sinks(5, false, vec![behind, ahead])).unwrap();
+
+    let op_ids = |seqs: std::ops::Range<u64>| {
+        seqs.map(|seq| OpIdentifier::new(0, seq))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(*behind_processed.lock(), op_ids(2..7));
+    assert_eq!(*ahead_processed.lock(), op_ids(4..7));
+}
+
+/// Builds source -> stateful processor -> sink, with the processor state in `state_dir`.
+fn build_with_processor_state(
+    state_dir: &Path,
+    sink_checkpoint: Option<OpIdentifier>,
+) -> Option<OpIdentifier> {
+    let mut dag = Dag::new();
+    let source_handle = NodeHandle::new(None, "src".to_string());
+    let proc_handle = NodeHandle::new(None, "proc".to_string());
+    let sink_handle = NodeHandle::new(None, "sink".to_string());
+    dag.add_source(
+        source_handle.clone(),
+        Box::new(GeneratorSourceFactory::new(
+            1,
+            Arc::new(AtomicBool::new(true)),
+            false,
+        )),
+    );
+    dag.add_processor(proc_handle.clone(), Box::new(StatefulProcessorFactory));
+    dag.add_sink(
+        sink_handle.clone(),
+        Box::new(CheckpointedSinkFactory::new(sink_checkpoint)),
+    );
+    dag.connect(
+        Endpoint::new(source_handle, GENERATOR_SOURCE_OUTPUT_PORT),
+        Endpoint::new(proc_handle.clone(), DEFAULT_PORT_HANDLE),
+    )
+    .unwrap();
+    dag.connect(
+        Endpoint::new(proc_handle, DEFAULT_PORT_HANDLE),
+        Endpoint::new(sink_handle, DEFAULT_PORT_HANDLE),
+    )
+    .unwrap();
+
+    let builder_dag = create_test_runtime().block_on(async {
+        let dag_schemas = DagSchemas::new(dag).await.unwrap();
+        BuilderDag::new(dag_schemas, 10, Some(state_dir), None)
+            .await
+            .unwrap()
+    });
+    builder_dag
+        .graph()
+        .raw_nodes()
+        .iter()
+        .find_map(|node| match &node.weight.kind {
+            NodeKind::Source {
+                last_checkpoint, ..
+            } => Some(*last_checkpoint),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn test_source_resumes_from_processor_checkpoint() {
+    let state_dir =
+        std::env::temp_dir().join(format!("dozer-processor-state-{}", std::process::id()));
+    let proc_handle = NodeHandle::new(None, "proc".to_string());
+    let checkpoint = |op_id: OpIdentifier, count: u64| {
+        let store = StateStore::open(&state_dir, &proc_handle).unwrap();
+        store.put("count", &(), &count).unwrap();
+        store
+            .checkpoint(
+                &[(
+                    NodeHandle::new(None, "src".to_string()),
+                    SourceState::Restartable(op_id),
+                )]
+                .into_iter()
+                .collect(),
+            )
+            .unwrap();
+    };
+
+    // The processor has no state, so the source starts over.
+    assert_eq!(
+        build_with_processor_state(&state_dir, Some(OpIdentifier::new(5, 0))),
+        None
+    );
+
+    checkpoint(OpIdentifier::new(5, 0), 1);
+    assert_eq!(
+        build_with_processor_state(&state_dir, Some(OpIdentifier::new(5, 0))),
+        Some(OpIdentifier::new(5, 0))
+    );
+
+    // The sink is behind the processor, so the processor rolls back to the sink's checkpoint.
+    checkpoint(OpIdentifier::new(6, 0), 2);
+    assert_eq!(
+        build_with_processor_state(&state_dir, Some(OpIdentifier::new(5, 0))),
+        Some(OpIdentifier::new(5, 0))
+    );
+    let store = StateStore::open(&state_dir, &proc_handle).unwrap();
+    assert_eq!(store.checkpoints().unwrap().len(), 1);
+    assert_eq!(store.get::<u64>("count", &()).unwrap(), Some(1));
+    drop(store);
+
+    // The processor has no checkpoint where the sink resumes, so its state is discarded.
+    assert_eq!(
+        build_with_processor_state(&state_dir, Some(OpIdentifier::new(4, 0))),
+        None
+    );
+    let store = StateStore::open(&state_dir, &proc_handle).unwrap();
+    assert!(store.checkpoints().unwrap().is_empty());
+    assert_eq!(store.get::<u64>("count", &()).unwrap(), None);
+    drop(store);
+
+    std::fs::remove_dir_all(state_dir).unwrap();
+}
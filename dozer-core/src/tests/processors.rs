@@ -1,10 +1,17 @@
 use std::collections::HashMap;
 
-use dozer_types::{errors::internal::BoxedError, tonic::async_trait, types::Schema};
+use dozer_types::{
+    errors::internal::BoxedError,
+    tonic::async_trait,
+    types::{Schema, TableOperation},
+};
 
 use crate::{
+    channels::ProcessorChannelForwarder,
+    epoch::Epoch,
     event::EventHub,
     node::{PortHandle, Processor, ProcessorFactory},
+    state_store::StateStore,
     DEFAULT_PORT_HANDLE,
 };
 
@@ -92,3 +99,76 @@ impl ProcessorFactory for NoInputPortProcessorFactory {
         "NoInput".to_owned()
     }
 }
+
+#[derive(Debug)]
+pub struct StatefulProcessorFactory;
+
+#[async_trait]
+impl ProcessorFactory for StatefulProcessorFactory {
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_output_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    async fn get_output_schema(
+        &self,
+        _output_port: &PortHandle,
+        input_schemas: &HashMap<PortHandle, Schema>,
+    ) -> Result<Schema, BoxedError> {
+        Ok(input_schemas.get(&DEFAULT_PORT_HANDLE).unwrap().clone())
+    }
+
+    async fn build(
+        &self,
+        _input_schemas: HashMap<PortHandle, Schema>,
+        _output_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Processor>, BoxedError> {
+        Ok(Box::new(StatefulProcessor { state_store: None }))
+    }
+
+    fn type_name(&self) -> String {
+        "Stateful".to_owned()
+    }
+
+    fn id(&self) -> String {
+        "Stateful".to_owned()
+    }
+}
+
+/// Counts the operations it has processed in its state store.
+#[derive(Debug)]
+pub struct StatefulProcessor {
+    state_store: Option<StateStore>,
+}
+
+impl Processor for StatefulProcessor {
+    fn commit(&self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn process(
+        &mut self,
+        op: TableOperation,
+        fw: &mut dyn ProcessorChannelForwarder,
+    ) -> Result<(), BoxedError> {
+        if let Some(store) = &self.state_store {
+            let count = store.get::<u64>("count", &())?.unwrap_or(0);
+            store.put("count", &(), &(count + 1))?;
+        }
+        fw.send(op);
+        Ok(())
+    }
+
+    fn is_stateful(&self) -> bool {
+        true
+    }
+
+    fn restore(&mut self, store: StateStore) -> Result<(), BoxedError> {
+        self.state_store = Some(store);
+        Ok(())
+    }
+}
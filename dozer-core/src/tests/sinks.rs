@@ -7,6 +7,7 @@ use dozer_types::node::OpIdentifier;
 use dozer_types::types::{Schema, TableOperation};
 
 use dozer_types::log::debug;
+use dozer_types::parking_lot::Mutex;
 use std::collections::HashMap;
 
 use dozer_types::tonic::async_trait;
@@ -112,6 +113,116 @@ impl Sink for CountingSink {
     }
 }
 
+/// A sink that was restarted after persisting `latest_op_id`. It records the op ids it processes.
+#[derive(Debug)]
+pub(crate) struct CheckpointedSinkFactory {
+    latest_op_id: Option<OpIdentifier>,
+    persists_op_id: bool,
+    processed: Arc<Mutex<Vec<OpIdentifier>>>,
+}
+
+impl CheckpointedSinkFactory {
+    pub fn new(latest_op_id: Option<OpIdentifier>) -> Self {
+        Self {
+            latest_op_id,
+            persists_op_id: true,
+            processed: Default::default(),
+        }
+    }
+
+    /// A sink that doesn't persist op ids, like one that keeps its records in memory.
+    pub fn without_persisted_op_id() -> Self {
+        Self {
+            persists_op_id: false,
+            ..Self::new(None)
+        }
+    }
+
+    pub fn processed(&self) -> Arc<Mutex<Vec<OpIdentifier>>> {
+        self.processed.clone()
+    }
+}
+
+#[async_trait]
+impl SinkFactory for CheckpointedSinkFactory {
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_input_port_name(&self, _port: &PortHandle) -> String {
+        "checkpointed".to_string()
+    }
+
+    fn prepare(&self, _input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        _input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        Ok(Box::new(CheckpointedSink {
+            latest_op_id: self.latest_op_id,
+            persists_op_id: self.persists_op_id,
+            processed: self.processed.clone(),
+        }))
+    }
+
+    fn type_name(&self) -> String {
+        "checkpointed".to_string()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct CheckpointedSink {
+    latest_op_id: Option<OpIdentifier>,
+    persists_op_id: bool,
+    processed: Arc<Mutex<Vec<OpIdentifier>>>,
+}
+
+impl Sink for CheckpointedSink {
+    fn commit(&mut self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
+        self.processed.lock().extend(op.id);
+        Ok(())
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        _id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, _source_state: &[u8]) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(None)
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        Ok(self.latest_op_id)
+    }
+
+    fn persists_op_id(&self) -> bool {
+        self.persists_op_id
+    }
+}
+
 #[derive(Debug)]
 pub struct ConnectivityTestSinkFactory;
 
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use aerospike::{Bins, ClientPolicy, ScanPolicy, Value};
+use dozer_ingestion_connector::dozer_types::log::debug;
+
+use crate::AerospikeConnectorError;
+
+/// A record read by a scan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScannedRecord {
+    pub digest: Vec<u8>,
+    /// Only stored if the record was written with `send_key`.
+    pub user_key: Option<Value>,
+    pub bins: HashMap<String, Value>,
+}
+
+/// The subset of Aerospike operations used by the connector.
+///
+/// Abstracted so the connector can be tested against a mocked client.
+pub trait Client: Send + Sync + Debug {
+    /// Scans the records of a set, calling `f` on each until it returns `false`.
+    fn scan(
+        &self,
+        namespace: &str,
+        set: &str,
+        f: &mut dyn FnMut(ScannedRecord) -> bool,
+    ) -> Result<(), AerospikeConnectorError>;
+}
+
+pub struct AerospikeClient {
+    inner: aerospike::Client,
+    scan_policy: ScanPolicy,
+}
+
+impl Debug for AerospikeClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AerospikeClient").finish()
+    }
+}
+
+impl AerospikeClient {
+    pub fn new(hosts: &str) -> Result<Self, AerospikeConnectorError> {
+        debug!("Connecting to aerospike hosts {hosts}");
+        let inner = aerospike::Client::new(&ClientPolicy::default(), &hosts)
+            .map_err(|e| AerospikeConnectorError::Aerospike(e.to_string()))?;
+        Ok(Self {
+            inner,
+            scan_policy: ScanPolicy::default(),
+        })
+    }
+}
+
+impl Client for AerospikeClient {
+    fn scan(
+        &self,
+        namespace: &str,
+        set: &str,
+        f: &mut dyn FnMut(ScannedRecord) -> bool,
+    ) -> Result<(), AerospikeConnectorError> {
+        let records = self
+            .inner
+            .scan(&self.scan_policy, namespace, set, Bins::All)
+            .map_err(|e| AerospikeConnectorError::Aerospike(e.to_string()))?;
+        for record in &*records {
+            let record = record.map_err(|e| AerospikeConnectorError::Aerospike(e.to_string()))?;
+            let Some(key) = record.key else {
+                continue;
+            };
+            let record = ScannedRecord {
+                digest: key.digest.to_vec(),
+                user_key: key.user_key,
+                bins: record.bins,
+            };
+            if !f(record) {
+                records.close();
+                break;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use dozer_ingestion_connector::{
+    async_trait,
+    dozer_types::{
+        errors::internal::BoxedError, models::connection::AerospikeConnection, node::OpIdentifier,
+        types::FieldType,
+    },
+    tokio::{self, sync::mpsc::unbounded_channel},
+    utils::TableNotFound,
+    Connector, Ingestor, SourceSchema, SourceSchemaResult, TableIdentifier, TableInfo,
+};
+
+use crate::{
+    client::{AerospikeClient, Client},
+    conversion, esp,
+    replicator::{replicate, ReplicatedSet},
+    schema::{self, SetMapper},
+    AerospikeConnectorError,
+};
+
+/// Replicates the configured sets of a namespace.
+///
+/// The sets are scanned once, then kept up to date with the change notifications that XDR ships to the connector's
+/// HTTP server. Replication always restarts from a new scan.
+#[derive(Debug)]
+pub struct AerospikeConnector {
+    config: AerospikeConnection,
+    /// Connected on first use, so the connector can be created while the cluster is unreachable.
+    client: Option<Arc<dyn Client>>,
+}
+
+impl AerospikeConnector {
+    pub fn new(config: AerospikeConnection) -> Self {
+        Self {
+            config,
+            client: None,
+        }
+    }
+
+    pub fn with_client(config: AerospikeConnection, client: Arc<dyn Client>) -> Self {
+        Self {
+            config,
+            client: Some(client),
+        }
+    }
+
+    async fn client(&mut self) -> Result<Arc<dyn Client>, AerospikeConnectorError> {
+        if let Some(client) = &self.client {
+            return Ok(client.clone());
+        }
+        let hosts = self.config.hosts.clone();
+        let client: Arc<dyn Client> =
+            Arc::new(tokio::task::spawn_blocking(move || AerospikeClient::new(&hosts)).await??);
+        self.client = Some(client.clone());
+        Ok(client)
+    }
+
+    fn find_set(&self, table: &TableIdentifier) -> Result<(), TableNotFound> {
+        if table.schema.is_none() && self.config.sets.contains(&table.name) {
+            Ok(())
+        } else {
+            Err(TableNotFound {
+                schema: table.schema.clone(),
+                name: table.name.clone(),
+            })
+        }
+    }
+
+    /// The full schema of each set, from the configured schemas or inferred from the records.
+    async fn set_schemas(
+        &mut self,
+        sets: Vec<String>,
+    ) -> Result<HashMap<String, SourceSchema>, AerospikeConnectorError> {
+        if let Some(schemas) = &self.config.schemas {
+            let mut schemas = schema::parse_schemas(schemas)?;
+            return sets
+                .into_iter()
+                .map(|set| {
+                    let schema = schemas
+                        .remove(&set)
+                        .ok_or_else(|| AerospikeConnectorError::SchemaNotFound(set.clone()))?;
+                    Ok((set, schema))
+                })
+                .collect();
+        }
+
+        let client = self.client().await?;
+        let namespace = self.config.namespace.clone();
+        tokio::task::spawn_blocking(move || {
+            sets.into_iter()
+                .map(|set| {
+                    let schema = schema::infer_schema(client.as_ref(), &namespace, &set)?;
+                    let schema = SourceSchema::new(schema, Default::default());
+                    Ok((set, schema))
+                })
+                .collect()
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl Connector for AerospikeConnector {
+    fn types_mapping() -> Vec<(String, Option<FieldType>)>
+    where
+        Self: Sized,
+    {
+        conversion::types_mapping()
+    }
+
+    async fn validate_connection(&mut self) -> Result<(), BoxedError> {
+        if let Some(schemas) = &self.config.schemas {
+            schema::parse_schemas(schemas)?;
+        }
+        self.client().await?;
+        Ok(())
+    }
+
+    async fn list_tables(&mut self) -> Result<Vec<TableIdentifier>, BoxedError> {
+        Ok(self
+            .config
+            .sets
+            .iter()
+            .cloned()
+            .map(TableIdentifier::from_table_name)
+            .collect())
+    }
+
+    async fn validate_tables(&mut self, tables: &[TableIdentifier]) -> Result<(), BoxedError> {
+        for table in tables {
+            self.find_set(table)?;
+        }
+        Ok(())
+    }
+
+    async fn list_columns(
+        &mut self,
+        tables: Vec<TableIdentifier>,
+    ) -> Result<Vec<TableInfo>, BoxedError> {
+        for table in &tables {
+            self.find_set(table)?;
+        }
+        let mut schemas = self
+            .set_schemas(tables.iter().map(|table| table.name.clone()).collect())
+            .await?;
+        Ok(tables
+            .into_iter()
+            .map(|table| {
+                let column_names = schemas
+                    .remove(&table.name)
+                    .map(|schema| {
+                        schema
+                            .schema
+                            .fields
+                            .into_iter()
+                            .map(|field| field.name)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                TableInfo {
+                    schema: table.schema,
+                    name: table.name,
+                    column_names,
+                }
+            })
+            .collect())
+    }
+
+    async fn get_schemas(
+        &mut self,
+        table_infos: &[TableInfo],
+    ) -> Result<Vec<SourceSchemaResult>, BoxedError> {
+        let schemas = self
+            .set_schemas(table_infos.iter().map(|table| table.name.clone()).collect())
+            .await?;
+        Ok(table_infos
+            .iter()
+            .map(|table| {
+                let schema = schemas
+                    .get(&table.name)
+                    .ok_or_else(|| AerospikeConnectorError::SchemaNotFound(table.name.clone()))?;
+                Ok(schema::source_schema(table, &schema.schema)?)
+            })
+            .collect())
+    }
+
+    async fn serialize_state(&self) -> Result<Vec<u8>, BoxedError> {
+        Ok(vec![])
+    }
+
+    async fn start(
+        &mut self,
+        ingestor: &Ingestor,
+        tables: Vec<TableInfo>,
+        _last_checkpoint: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        let schemas = self.get_schemas(&tables).await?;
+        let sets = tables
+            .into_iter()
+            .zip(schemas)
+            .map(|(table, schema)| {
+                Ok(ReplicatedSet::new(
+                    table.name,
+                    SetMapper::new(schema?.schema),
+                ))
+            })
+            .collect::<Result<Vec<_>, BoxedError>>()?;
+
+        // Notifications received during the snapshot are queued until it's done.
+        let (sender, receiver) = unbounded_channel();
+        let client = self.client().await?;
+        let replication = &self.config.replication;
+        tokio::select! {
+            result = esp::serve(&replication.server_address, replication.server_port, sender) => result?,
+            result = replicate(client, ingestor, &self.config.namespace, sets, receiver) => result?,
+        }
+        Ok(())
+    }
+}
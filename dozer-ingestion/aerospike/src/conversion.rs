@@ -0,0 +1,258 @@
+use aerospike::Value;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use dozer_ingestion_connector::dozer_types::{
+    chrono::{DateTime, NaiveDate},
+    json_types::{json_from_str, serde_json_to_json_value, JsonValue},
+    ordered_float::OrderedFloat,
+    rust_decimal::Decimal,
+    serde_json,
+    types::{DozerDuration, DozerPoint, Field, FieldType, TimeUnit, DATE_FORMAT},
+};
+
+use crate::AerospikeConnectorError;
+
+pub fn types_mapping() -> Vec<(String, Option<FieldType>)> {
+    vec![
+        ("bool".to_string(), Some(FieldType::Boolean)),
+        ("int".to_string(), Some(FieldType::Int)),
+        ("float".to_string(), Some(FieldType::Float)),
+        ("str".to_string(), Some(FieldType::String)),
+        ("blob".to_string(), Some(FieldType::Binary)),
+        ("geojson".to_string(), Some(FieldType::Point)),
+        ("list".to_string(), Some(FieldType::Json)),
+        ("map".to_string(), Some(FieldType::Json)),
+        ("hll".to_string(), Some(FieldType::Binary)),
+    ]
+}
+
+/// The type of a bin inferred from one of its values, `None` for nil.
+///
+/// GeoJSON bins are points if the value is a point, JSON otherwise.
+pub fn infer_type(value: &Value) -> Option<FieldType> {
+    let typ = match value {
+        Value::Nil => return None,
+        Value::Bool(_) => FieldType::Boolean,
+        Value::Int(_) => FieldType::Int,
+        Value::UInt(_) => FieldType::UInt,
+        Value::Float(_) => FieldType::Float,
+        Value::String(_) => FieldType::String,
+        Value::Blob(_) | Value::HLL(_) => FieldType::Binary,
+        Value::GeoJSON(geojson) if parse_geojson_point(geojson).is_some() => FieldType::Point,
+        Value::GeoJSON(_) | Value::List(_) | Value::HashMap(_) | Value::OrderedMap(_) => {
+            FieldType::Json
+        }
+    };
+    Some(typ)
+}
+
+/// The type of a bin having values of both types. Integers widen to floats, other conflicts fall back to JSON.
+pub fn unify_types(left: FieldType, right: FieldType) -> FieldType {
+    match (left, right) {
+        (left, right) if left == right => left,
+        (FieldType::Int | FieldType::UInt, FieldType::Float)
+        | (FieldType::Float, FieldType::Int | FieldType::UInt) => FieldType::Float,
+        (FieldType::Int, FieldType::UInt) | (FieldType::UInt, FieldType::Int) => FieldType::Int,
+        _ => FieldType::Json,
+    }
+}
+
+pub fn value_to_field(value: &Value, typ: FieldType) -> Result<Field, AerospikeConnectorError> {
+    let error = || AerospikeConnectorError::ValueConversion {
+        value: value.to_string(),
+        typ,
+    };
+    if matches!(value, Value::Nil) {
+        return Ok(Field::Null);
+    }
+    let field = match (typ, value) {
+        (FieldType::UInt, Value::Int(v)) => Field::UInt(u64::try_from(*v).map_err(|_| error())?),
+        (FieldType::UInt, Value::UInt(v)) => Field::UInt(*v),
+        (FieldType::U128, Value::Int(v)) => Field::U128(u128::try_from(*v).map_err(|_| error())?),
+        (FieldType::U128, Value::UInt(v)) => Field::U128(*v as u128),
+        (FieldType::U128, Value::Blob(v)) => Field::U128(u128::from_be_bytes(
+            v.as_slice().try_into().map_err(|_| error())?,
+        )),
+        (FieldType::Int, Value::Int(v)) => Field::Int(*v),
+        (FieldType::Int, Value::UInt(v)) => Field::Int(i64::try_from(*v).map_err(|_| error())?),
+        (FieldType::Int8, Value::Int(v)) => Field::Int8(i8::try_from(*v).map_err(|_| error())?),
+        (FieldType::I128, Value::Int(v)) => Field::I128(*v as i128),
+        (FieldType::I128, Value::Blob(v)) => Field::I128(i128::from_be_bytes(
+            v.as_slice().try_into().map_err(|_| error())?,
+        )),
+        (FieldType::Float, Value::Float(v)) => Field::Float(OrderedFloat(f64::from(v))),
+        (FieldType::Float, Value::Int(v)) => Field::Float(OrderedFloat(*v as f64)),
+        (FieldType::Float, Value::UInt(v)) => Field::Float(OrderedFloat(*v as f64)),
+        (FieldType::Boolean, Value::Bool(v)) => Field::Boolean(*v),
+        (FieldType::Boolean, Value::Int(v)) => Field::Boolean(*v != 0),
+        (FieldType::String, Value::String(v)) => Field::String(v.clone()),
+        (FieldType::Text, Value::String(v)) => Field::Text(v.clone()),
+        (FieldType::Binary, Value::Blob(v) | Value::HLL(v)) => Field::Binary(v.clone()),
+        (FieldType::Decimal, Value::String(v)) => {
+            Field::Decimal(v.parse::<Decimal>().map_err(|_| error())?)
+        }
+        (FieldType::Decimal, Value::Int(v)) => Field::Decimal(Decimal::from(*v)),
+        (FieldType::Decimal, Value::Float(v)) => {
+            Field::Decimal(Decimal::try_from(f64::from(v)).map_err(|_| error())?)
+        }
+        (FieldType::Timestamp, Value::String(v)) => {
+            Field::Timestamp(DateTime::parse_from_rfc3339(v).map_err(|_| error())?)
+        }
+        // Integer timestamps are milliseconds since the epoch.
+        (FieldType::Timestamp, Value::Int(v)) => Field::Timestamp(
+            DateTime::from_timestamp_millis(*v)
+                .ok_or_else(error)?
+                .fixed_offset(),
+        ),
+        (FieldType::Date, Value::String(v)) => {
+            Field::Date(NaiveDate::parse_from_str(v, DATE_FORMAT).map_err(|_| error())?)
+        }
+        (FieldType::Json, Value::String(v)) => {
+            Field::Json(json_from_str(v).unwrap_or_else(|_| v.clone().into()))
+        }
+        (FieldType::Json, value) => Field::Json(value_to_json(value).ok_or_else(error)?),
+        (FieldType::Point, Value::GeoJSON(v)) => {
+            Field::Point(parse_geojson_point(v).ok_or_else(error)?)
+        }
+        (FieldType::Duration, Value::Int(v)) => Field::Duration(DozerDuration(
+            std::time::Duration::from_nanos(u64::try_from(*v).map_err(|_| error())?),
+            TimeUnit::Nanoseconds,
+        )),
+        _ => return Err(error()),
+    };
+    Ok(field)
+}
+
+fn value_to_json(value: &Value) -> Option<JsonValue> {
+    serde_json_to_json_value(value_to_serde_json(value)?).ok()
+}
+
+/// Blobs are base64 encoded and map keys are converted to strings.
+fn value_to_serde_json(value: &Value) -> Option<serde_json::Value> {
+    let json = match value {
+        Value::Nil => serde_json::Value::Null,
+        Value::Bool(v) => (*v).into(),
+        Value::Int(v) => (*v).into(),
+        Value::UInt(v) => (*v).into(),
+        Value::Float(v) => f64::from(v).into(),
+        Value::String(v) => v.clone().into(),
+        Value::Blob(v) | Value::HLL(v) => STANDARD.encode(v).into(),
+        Value::GeoJSON(v) => serde_json::from_str(v).ok()?,
+        Value::List(values) => values
+            .iter()
+            .map(value_to_serde_json)
+            .collect::<Option<Vec<_>>>()?
+            .into(),
+        Value::HashMap(entries) => map_to_serde_json(entries.iter())?,
+        Value::OrderedMap(entries) => map_to_serde_json(entries.iter().map(|(k, v)| (k, v)))?,
+    };
+    Some(json)
+}
+
+fn map_to_serde_json<'a>(
+    entries: impl Iterator<Item = (&'a Value, &'a Value)>,
+) -> Option<serde_json::Value> {
+    let mut map = serde_json::Map::new();
+    for (key, value) in entries {
+        let key = match key {
+            Value::String(key) => key.clone(),
+            key => key.to_string(),
+        };
+        map.insert(key, value_to_serde_json(value)?);
+    }
+    Some(map.into())
+}
+
+fn parse_geojson_point(geojson: &str) -> Option<DozerPoint> {
+    let geojson: serde_json::Value = serde_json::from_str(geojson).ok()?;
+    if geojson.get("type")?.as_str()? != "Point" {
+        return None;
+    }
+    let coordinates = geojson.get("coordinates")?.as_array()?;
+    let [x, y] = coordinates.as_slice() else {
+        return None;
+    };
+    Some(DozerPoint::from((x.as_f64()?, y.as_f64()?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use dozer_ingestion_connector::dozer_types::json_types::json_from_str;
+
+    use super::*;
+
+    #[test]
+    fn test_infer_type() {
+        assert_eq!(infer_type(&Value::Nil), None);
+        assert_eq!(infer_type(&Value::Int(1)), Some(FieldType::Int));
+        assert_eq!(infer_type(&Value::from(1.5)), Some(FieldType::Float));
+        assert_eq!(
+            infer_type(&Value::GeoJSON(
+                r#"{"type":"Point","coordinates":[1.0,2.0]}"#.to_string()
+            )),
+            Some(FieldType::Point)
+        );
+        assert_eq!(
+            infer_type(&Value::GeoJSON(
+                r#"{"type":"Polygon","coordinates":[]}"#.to_string()
+            )),
+            Some(FieldType::Json)
+        );
+        assert_eq!(
+            infer_type(&Value::List(vec![Value::Int(1)])),
+            Some(FieldType::Json)
+        );
+    }
+
+    #[test]
+    fn test_unify_types() {
+        assert_eq!(
+            unify_types(FieldType::Int, FieldType::Float),
+            FieldType::Float
+        );
+        assert_eq!(
+            unify_types(FieldType::String, FieldType::String),
+            FieldType::String
+        );
+        assert_eq!(
+            unify_types(FieldType::String, FieldType::Int),
+            FieldType::Json
+        );
+    }
+
+    #[test]
+    fn test_value_to_field() {
+        assert_eq!(
+            value_to_field(&Value::Int(3), FieldType::Float).unwrap(),
+            Field::Float(OrderedFloat(3.0))
+        );
+        assert_eq!(
+            value_to_field(&Value::Nil, FieldType::Int).unwrap(),
+            Field::Null
+        );
+        assert_eq!(
+            value_to_field(
+                &Value::GeoJSON(r#"{"type":"Point","coordinates":[1.0,2.0]}"#.to_string()),
+                FieldType::Point
+            )
+            .unwrap(),
+            Field::Point(DozerPoint::from((1.0, 2.0)))
+        );
+        assert!(value_to_field(&Value::String("a".to_string()), FieldType::Int).is_err());
+
+        let mut map = HashMap::new();
+        map.insert(
+            Value::String("a".to_string()),
+            Value::List(vec![Value::Int(1), Value::Blob(vec![0xff])]),
+        );
+        assert_eq!(
+            value_to_field(&Value::HashMap(map), FieldType::Json).unwrap(),
+            Field::Json(json_from_str(r#"{"a":[1,"/w=="]}"#).unwrap())
+        );
+        assert_eq!(
+            value_to_field(&Value::String("plain".to_string()), FieldType::Json).unwrap(),
+            Field::Json("plain".to_string().into())
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Change notifications sent by the Aerospike XDR connector for ESP (Event Stream Processing).
+//!
+//! Each record write or delete is posted as a JSON message, or an array of messages if batching is enabled:
+//!
+//! ```json
+//! {"msg": "write", "key": ["ns", "set", "<base64 digest>", "user key"], "gen": 2, "bins": [{"name": "a", "type": "int", "value": 1}]}
+//! ```
+
+use std::collections::HashMap;
+
+use actix_web::{web, App, HttpResponse, HttpServer};
+use aerospike::Value;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use dozer_ingestion_connector::{
+    dozer_types::{log::info, serde_json},
+    tokio::sync::mpsc::UnboundedSender,
+};
+
+use crate::AerospikeConnectorError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventKind {
+    /// The bins of the record after the write.
+    Write {
+        bins: HashMap<String, Value>,
+    },
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub namespace: String,
+    pub set: String,
+    pub digest: Vec<u8>,
+    pub user_key: Option<Value>,
+    pub kind: EventKind,
+}
+
+/// Parses a request body holding one message or an array of messages.
+pub fn parse_events(body: &[u8]) -> Result<Vec<Event>, AerospikeConnectorError> {
+    let body: serde_json::Value = serde_json::from_slice(body)
+        .map_err(|e| AerospikeConnectorError::InvalidEvent(e.to_string()))?;
+    match body {
+        serde_json::Value::Array(messages) => messages.iter().map(parse_event).collect(),
+        message => Ok(vec![parse_event(&message)?]),
+    }
+}
+
+fn parse_event(message: &serde_json::Value) -> Result<Event, AerospikeConnectorError> {
+    let invalid =
+        |reason: &str| AerospikeConnectorError::InvalidEvent(format!("{reason}: {message}"));
+
+    let key = message
+        .get("key")
+        .and_then(|key| key.as_array())
+        .ok_or_else(|| invalid("missing key"))?;
+    let (namespace, set, digest) = match key.as_slice() {
+        [namespace, set, digest, ..] => (
+            namespace
+                .as_str()
+                .ok_or_else(|| invalid("invalid namespace"))?,
+            set.as_str().ok_or_else(|| invalid("invalid set"))?,
+            digest
+                .as_str()
+                .and_then(|digest| STANDARD.decode(digest).ok())
+                .ok_or_else(|| invalid("invalid digest"))?,
+        ),
+        _ => return Err(invalid("invalid key")),
+    };
+    let user_key = match key.get(3) {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(key)) => Some(Value::String(key.clone())),
+        Some(serde_json::Value::Number(key)) => Some(
+            key.as_i64()
+                .map(Value::Int)
+                .ok_or_else(|| invalid("invalid user key"))?,
+        ),
+        Some(_) => return Err(invalid("invalid user key")),
+    };
+
+    let kind = match message.get("msg").and_then(|msg| msg.as_str()) {
+        Some("write") => {
+            let mut bins = HashMap::new();
+            for bin in message
+                .get("bins")
+                .and_then(|bins| bins.as_array())
+                .map_or(&[][..], |bins| bins.as_slice())
+            {
+                let name = bin
+                    .get("name")
+                    .and_then(|name| name.as_str())
+                    .ok_or_else(|| invalid("invalid bin name"))?;
+                let typ = bin.get("type").and_then(|typ| typ.as_str());
+                let value = bin.get("value").unwrap_or(&serde_json::Value::Null);
+                let value = bin_value(typ, value).ok_or_else(|| invalid("invalid bin value"))?;
+                bins.insert(name.to_string(), value);
+            }
+            EventKind::Write { bins }
+        }
+        Some("delete") => EventKind::Delete,
+        _ => return Err(invalid("unknown message type")),
+    };
+
+    Ok(Event {
+        namespace: namespace.to_string(),
+        set: set.to_string(),
+        digest,
+        user_key,
+        kind,
+    })
+}
+
+fn bin_value(typ: Option<&str>, value: &serde_json::Value) -> Option<Value> {
+    let value = match (typ, value) {
+        (_, serde_json::Value::Null) => Value::Nil,
+        (Some("blob" | "hll"), serde_json::Value::String(blob)) => {
+            Value::Blob(STANDARD.decode(blob).ok()?)
+        }
+        (Some("geojson"), serde_json::Value::String(geojson)) => Value::GeoJSON(geojson.clone()),
+        (Some("geojson"), geojson) => Value::GeoJSON(geojson.to_string()),
+        (_, value) => json_value(value)?,
+    };
+    Some(value)
+}
+
+fn json_value(value: &serde_json::Value) -> Option<Value> {
+    let value = match value {
+        serde_json::Value::Null => Value::Nil,
+        serde_json::Value::Bool(value) => Value::Bool(*value),
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(number) => Value::Int(number),
+            None => Value::from(number.as_f64()?),
+        },
+        serde_json::Value::String(value) => Value::String(value.clone()),
+        serde_json::Value::Array(values) => {
+            Value::List(values.iter().map(json_value).collect::<Option<Vec<_>>>()?)
+        }
+        serde_json::Value::Object(entries) => Value::HashMap(
+            entries
+                .iter()
+                .map(|(key, value)| Some((Value::String(key.clone()), json_value(value)?)))
+                .collect::<Option<HashMap<_, _>>>()?,
+        ),
+    };
+    Some(value)
+}
+
+async fn handle(
+    body: web::Bytes,
+    sender: web::Data<UnboundedSender<Event>>,
+) -> actix_web::Result<HttpResponse> {
+    let events = parse_events(&body).map_err(actix_web::error::ErrorBadRequest)?;
+    for event in events {
+        if sender.send(event).is_err() {
+            return Ok(HttpResponse::ServiceUnavailable().finish());
+        }
+    }
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Receives change notifications on any path, forwarding them to `sender`.
+pub async fn serve(
+    address: &str,
+    port: u32,
+    sender: UnboundedSender<Event>,
+) -> Result<(), AerospikeConnectorError> {
+    let sender = web::Data::new(sender);
+    let address = format!("{address}:{port}");
+    info!("Listening for Aerospike change notifications on {address}");
+    HttpServer::new(move || {
+        App::new()
+            .app_data(sender.clone())
+            .default_service(web::post().to(handle))
+    })
+    .bind(address)?
+    .run()
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_events() {
+        let digest = STANDARD.encode([1u8; 20]);
+        let write = format!(
+            r#"{{"msg":"write","key":["test","users","{digest}","alice"],"gen":2,"bins":[
+                {{"name":"age","type":"int","value":30}},
+                {{"name":"photo","type":"blob","value":"AQI="}},
+                {{"name":"tags","type":"list","value":["a",1.5]}}
+            ]}}"#
+        );
+        let events = parse_events(write.as_bytes()).unwrap();
+        let mut bins = HashMap::new();
+        bins.insert("age".to_string(), Value::Int(30));
+        bins.insert("photo".to_string(), Value::Blob(vec![1, 2]));
+        bins.insert(
+            "tags".to_string(),
+            Value::List(vec![Value::String("a".to_string()), Value::from(1.5)]),
+        );
+        assert_eq!(
+            events,
+            vec![Event {
+                namespace: "test".to_string(),
+                set: "users".to_string(),
+                digest: vec![1; 20],
+                user_key: Some(Value::String("alice".to_string())),
+                kind: EventKind::Write { bins },
+            }]
+        );
+
+        let delete = format!(
+            r#"[{{"msg":"delete","key":["test","users","{digest}"]}},{{"msg":"delete","key":["test","users","{digest}",7]}}]"#
+        );
+        let events = parse_events(delete.as_bytes()).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, EventKind::Delete);
+        assert_eq!(events[0].user_key, None);
+        assert_eq!(events[1].user_key, Some(Value::Int(7)));
+
+        assert!(parse_events(br#"{"msg":"truncate","key":["test","users","AA=="]}"#).is_err());
+        assert!(parse_events(br#"{"msg":"write","key":["test"]}"#).is_err());
+    }
+}
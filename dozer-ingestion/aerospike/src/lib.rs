@@ -0,0 +1,51 @@
+use dozer_ingestion_connector::{
+    dozer_types::{
+        serde_json,
+        thiserror::{self, Error},
+        types::FieldType,
+    },
+    schema_parser::SchemaParserError,
+    tokio::task::JoinError,
+};
+
+pub mod client;
+pub mod connector;
+mod conversion;
+mod esp;
+mod replicator;
+mod schema;
+#[cfg(test)]
+mod tests;
+
+#[derive(Error, Debug)]
+pub enum AerospikeConnectorError {
+    #[error("Aerospike error: {0}")]
+    Aerospike(String),
+
+    #[error(transparent)]
+    SchemaParser(#[from] SchemaParserError),
+
+    #[error("Invalid schemas: {0}")]
+    InvalidSchemas(#[source] serde_json::Error),
+
+    #[error("Set {0} is not in the configured schemas")]
+    SchemaNotFound(String),
+
+    #[error("Primary key of set {0} must only have the __digest and __key fields")]
+    InvalidPrimaryKey(String),
+
+    #[error("Column {column} not found in set {set}")]
+    ColumnNotFound { set: String, column: String },
+
+    #[error("Cannot convert Aerospike value {value} to {typ}")]
+    ValueConversion { value: String, typ: FieldType },
+
+    #[error("Invalid change notification: {0}")]
+    InvalidEvent(String),
+
+    #[error("Change notification server error: {0}")]
+    Server(#[from] std::io::Error),
+
+    #[error("Scan task failed: {0}")]
+    Join(#[from] JoinError),
+}
@@ -0,0 +1,192 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use dozer_ingestion_connector::{
+    dozer_types::{
+        log::warn,
+        models::ingestion_types::{IngestionMessage, TransactionInfo},
+        types::{Operation, Record},
+    },
+    tokio::{self, sync::mpsc::UnboundedReceiver},
+    Ingestor,
+};
+
+use crate::{
+    client::{Client, ScannedRecord},
+    esp::{Event, EventKind},
+    schema::SetMapper,
+    AerospikeConnectorError,
+};
+
+#[derive(Debug)]
+pub struct ReplicatedSet {
+    pub name: String,
+    pub mapper: SetMapper,
+    /// Digests of the records that have been inserted, to tell inserts from updates.
+    known: HashSet<Vec<u8>>,
+}
+
+impl ReplicatedSet {
+    pub fn new(name: String, mapper: SetMapper) -> Self {
+        Self {
+            name,
+            mapper,
+            known: HashSet::new(),
+        }
+    }
+}
+
+/// Scans the sets, then applies the change notifications received meanwhile and afterwards.
+///
+/// Returns when the ingestor is closed.
+pub async fn replicate(
+    client: Arc<dyn Client>,
+    ingestor: &Ingestor,
+    namespace: &str,
+    mut sets: Vec<ReplicatedSet>,
+    mut events: UnboundedReceiver<Event>,
+) -> Result<(), AerospikeConnectorError> {
+    if ingestor
+        .handle_message(IngestionMessage::TransactionInfo(
+            TransactionInfo::SnapshottingStarted,
+        ))
+        .await
+        .is_err()
+    {
+        return Ok(());
+    }
+    for (table_index, set) in sets.iter_mut().enumerate() {
+        let Some(known) = snapshot(
+            client.clone(),
+            ingestor.clone(),
+            namespace.to_string(),
+            table_index,
+            set.name.clone(),
+            set.mapper.clone(),
+        )
+        .await?
+        else {
+            return Ok(());
+        };
+        set.known = known;
+    }
+    if ingestor
+        .handle_message(IngestionMessage::TransactionInfo(
+            TransactionInfo::SnapshottingDone { id: None },
+        ))
+        .await
+        .is_err()
+    {
+        return Ok(());
+    }
+
+    while let Some(event) = events.recv().await {
+        if event.namespace != namespace {
+            continue;
+        }
+        let Some(table_index) = sets.iter().position(|set| set.name == event.set) else {
+            continue;
+        };
+        let Some(op) = apply(&mut sets[table_index], event)? else {
+            continue;
+        };
+        if ingestor
+            .handle_message(IngestionMessage::OperationEvent {
+                table_index,
+                op,
+                id: None,
+            })
+            .await
+            .is_err()
+        {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Inserts the records of a set, returning their digests, or `None` if the ingestor is closed.
+async fn snapshot(
+    client: Arc<dyn Client>,
+    ingestor: Ingestor,
+    namespace: String,
+    table_index: usize,
+    set: String,
+    mapper: SetMapper,
+) -> Result<Option<HashSet<Vec<u8>>>, AerospikeConnectorError> {
+    tokio::task::spawn_blocking(move || {
+        let mut known = HashSet::new();
+        let mut result = Ok(());
+        let mut closed = false;
+        client.scan(&namespace, &set, &mut |record| {
+            let new = match mapper.record(&record) {
+                Ok(values) => Record::new(values),
+                Err(e) => {
+                    result = Err(e);
+                    return false;
+                }
+            };
+            known.insert(record.digest);
+            closed = ingestor
+                .blocking_handle_message(IngestionMessage::OperationEvent {
+                    table_index,
+                    op: Operation::Insert { new },
+                    id: None,
+                })
+                .is_err();
+            !closed
+        })?;
+        result?;
+        Ok((!closed).then_some(known))
+    })
+    .await?
+}
+
+/// Maps a change notification to an operation.
+///
+/// Notifications can be received for records written while their set was scanned, so writes of scanned records are
+/// updates, and deletes of records that were never inserted are ignored.
+fn apply(
+    set: &mut ReplicatedSet,
+    event: Event,
+) -> Result<Option<Operation>, AerospikeConnectorError> {
+    let op = match event.kind {
+        EventKind::Write { bins } => {
+            let new = Record::new(set.mapper.record(&ScannedRecord {
+                digest: event.digest.clone(),
+                user_key: event.user_key.clone(),
+                bins,
+            })?);
+            if !set.mapper.has_primary_key() {
+                Operation::Insert { new }
+            } else if set.known.contains(&event.digest) {
+                let old = Record::new(
+                    set.mapper
+                        .key_record(&event.digest, event.user_key.as_ref())?,
+                );
+                Operation::Update { old, new }
+            } else {
+                set.known.insert(event.digest);
+                Operation::Insert { new }
+            }
+        }
+        EventKind::Delete => {
+            if !set.mapper.has_primary_key() {
+                warn!(
+                    "Ignoring delete from set {} which has no primary key",
+                    set.name
+                );
+                return Ok(None);
+            }
+            if !set.known.remove(&event.digest) {
+                return Ok(None);
+            }
+            let old = Record::new(
+                set.mapper
+                    .key_record(&event.digest, event.user_key.as_ref())?,
+            );
+            Operation::Delete { old }
+        }
+    };
+    Ok(Some(op))
+}
@@ -0,0 +1,190 @@
+use std::collections::{BTreeMap, HashMap};
+
+use aerospike::Value;
+use dozer_ingestion_connector::{
+    dozer_types::{
+        models::ingestion_types::ConfigSchemas,
+        serde_json,
+        types::{Field, FieldDefinition, FieldType, Schema, SourceDefinition},
+    },
+    schema_parser::SchemaParser,
+    utils::warn_dropped_primary_index,
+    CdcType, SourceSchema, TableInfo,
+};
+
+use crate::{
+    client::{Client, ScannedRecord},
+    conversion::{infer_type, unify_types, value_to_field},
+    AerospikeConnectorError,
+};
+
+/// The RIPEMD-160 digest identifying a record, always available.
+pub const DIGEST_FIELD: &str = "__digest";
+/// The user key of a record, only available if it was written with `send_key`.
+pub const KEY_FIELD: &str = "__key";
+
+/// Number of records scanned to infer the bin types of a set.
+const SAMPLE_SIZE: usize = 1000;
+
+/// Parses the user supplied schemas, keyed by set.
+pub fn parse_schemas(
+    schemas: &ConfigSchemas,
+) -> Result<HashMap<String, SourceSchema>, AerospikeConnectorError> {
+    let schemas: HashMap<String, SourceSchema> =
+        serde_json::from_str(&SchemaParser::parse_config(schemas)?)
+            .map_err(AerospikeConnectorError::InvalidSchemas)?;
+    for (set, schema) in &schemas {
+        let schema = &schema.schema;
+        if schema.primary_index.is_empty()
+            || schema.primary_index.iter().any(|index| {
+                schema.fields.get(*index).map_or(true, |field| {
+                    field.name != DIGEST_FIELD && field.name != KEY_FIELD
+                })
+            })
+        {
+            return Err(AerospikeConnectorError::InvalidPrimaryKey(set.clone()));
+        }
+    }
+    Ok(schemas)
+}
+
+/// Infers the schema of a set from a sample of its records.
+///
+/// The digest is the primary key. Bins are sorted by name and nullable, as records of a set can have different bins.
+pub fn infer_schema(
+    client: &dyn Client,
+    namespace: &str,
+    set: &str,
+) -> Result<Schema, AerospikeConnectorError> {
+    let mut key_type = None;
+    let mut bin_types = BTreeMap::new();
+    let mut count = 0;
+    client.scan(namespace, set, &mut |record| {
+        if let Some(typ) = record.user_key.as_ref().and_then(infer_type) {
+            key_type = Some(key_type.map_or(typ, |key_type| unify_types(key_type, typ)));
+        }
+        for (name, value) in &record.bins {
+            if let Some(typ) = infer_type(value) {
+                bin_types
+                    .entry(name.clone())
+                    .and_modify(|bin_type| *bin_type = unify_types(*bin_type, typ))
+                    .or_insert(typ);
+            }
+        }
+        count += 1;
+        count < SAMPLE_SIZE
+    })?;
+
+    let mut schema = Schema::new();
+    schema
+        .field(field(DIGEST_FIELD, FieldType::Binary, false), true)
+        .field(
+            field(KEY_FIELD, key_type.unwrap_or(FieldType::String), true),
+            false,
+        );
+    for (name, typ) in bin_types {
+        schema.field(field(&name, typ, true), false);
+    }
+    Ok(schema)
+}
+
+fn field(name: &str, typ: FieldType, nullable: bool) -> FieldDefinition {
+    FieldDefinition::new(name.to_string(), typ, nullable, SourceDefinition::Dynamic)
+}
+
+/// Keeps the columns of `table`, in its order.
+pub fn source_schema(
+    table: &TableInfo,
+    schema: &Schema,
+) -> Result<SourceSchema, AerospikeConnectorError> {
+    let fields = table
+        .column_names
+        .iter()
+        .map(|column| {
+            schema
+                .fields
+                .iter()
+                .find(|field| &field.name == column)
+                .cloned()
+                .ok_or_else(|| AerospikeConnectorError::ColumnNotFound {
+                    set: table.name.clone(),
+                    column: column.clone(),
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let primary_index = schema
+        .primary_index
+        .iter()
+        .map(|index| {
+            table
+                .column_names
+                .iter()
+                .position(|name| name == &schema.fields[*index].name)
+        })
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_else(|| {
+            warn_dropped_primary_index(&table.name);
+            vec![]
+        });
+    // Change notifications only carry the record key.
+    let cdc_type = if primary_index.is_empty() {
+        CdcType::Nothing
+    } else {
+        CdcType::OnlyPK
+    };
+    Ok(SourceSchema::new(
+        Schema {
+            fields,
+            primary_index,
+        },
+        cdc_type,
+    ))
+}
+
+/// Maps the records of a set to the fields of its source schema.
+#[derive(Debug, Clone)]
+pub struct SetMapper {
+    pub schema: Schema,
+}
+
+impl SetMapper {
+    pub fn new(schema: Schema) -> Self {
+        Self { schema }
+    }
+
+    pub fn has_primary_key(&self) -> bool {
+        !self.schema.primary_index.is_empty()
+    }
+
+    pub fn record(&self, record: &ScannedRecord) -> Result<Vec<Field>, AerospikeConnectorError> {
+        self.schema
+            .fields
+            .iter()
+            .map(|field| {
+                let value = match field.name.as_str() {
+                    DIGEST_FIELD => return Ok(Field::Binary(record.digest.clone())),
+                    KEY_FIELD => record.user_key.as_ref(),
+                    name => record.bins.get(name),
+                };
+                value.map_or(Ok(Field::Null), |value| value_to_field(value, field.typ))
+            })
+            .collect()
+    }
+
+    /// The record with only the primary key fields, as the old record of updates and deletes.
+    pub fn key_record(
+        &self,
+        digest: &[u8],
+        user_key: Option<&Value>,
+    ) -> Result<Vec<Field>, AerospikeConnectorError> {
+        let mut fields = vec![Field::Null; self.schema.fields.len()];
+        for index in &self.schema.primary_index {
+            let field = &self.schema.fields[*index];
+            fields[*index] = match field.name.as_str() {
+                DIGEST_FIELD => Field::Binary(digest.to_vec()),
+                _ => user_key.map_or(Ok(Field::Null), |value| value_to_field(value, field.typ))?,
+            };
+        }
+        Ok(fields)
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use aerospike::Value;
+use dozer_ingestion_connector::{
+    dozer_types::{
+        json_types::json_from_str,
+        models::{
+            connection::{AerospikeConnection, ReplicationSettings},
+            ingestion_types::{ConfigSchemas, IngestionMessage, TransactionInfo},
+        },
+        ordered_float::OrderedFloat,
+        types::{Field, FieldType, Operation, Record},
+    },
+    tokio::{self, sync::mpsc::unbounded_channel},
+    CdcType, Connector, Ingestor, TableIdentifier,
+};
+
+use crate::{
+    client::{Client, ScannedRecord},
+    connector::AerospikeConnector,
+    esp::{Event, EventKind},
+    replicator::{replicate, ReplicatedSet},
+    schema::SetMapper,
+    AerospikeConnectorError,
+};
+
+#[derive(Debug, Default)]
+struct MockClient {
+    sets: HashMap<String, Vec<ScannedRecord>>,
+}
+
+impl Client for MockClient {
+    fn scan(
+        &self,
+        _namespace: &str,
+        set: &str,
+        f: &mut dyn FnMut(ScannedRecord) -> bool,
+    ) -> Result<(), AerospikeConnectorError> {
+        for record in self.sets.get(set).into_iter().flatten() {
+            if !f(record.clone()) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn record(digest: u8, user_key: Option<&str>, bins: Vec<(&str, Value)>) -> ScannedRecord {
+    ScannedRecord {
+        digest: vec![digest; 20],
+        user_key: user_key.map(|key| Value::String(key.to_string())),
+        bins: bins
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    }
+}
+
+fn users() -> Vec<ScannedRecord> {
+    vec![
+        record(
+            1,
+            Some("alice"),
+            vec![
+                ("name", Value::String("Alice".to_string())),
+                ("age", Value::Int(30)),
+            ],
+        ),
+        record(
+            2,
+            None,
+            vec![
+                ("age", Value::from(41.5)),
+                ("tags", Value::List(vec![Value::Int(1)])),
+            ],
+        ),
+    ]
+}
+
+fn connector(schemas: Option<&str>) -> AerospikeConnector {
+    let mut sets = HashMap::new();
+    sets.insert("users".to_string(), users());
+    AerospikeConnector::with_client(
+        AerospikeConnection {
+            hosts: "localhost:3000".to_string(),
+            namespace: "test".to_string(),
+            sets: vec!["users".to_string()],
+            batching: false,
+            replication: ReplicationSettings::default(),
+            schemas: schemas.map(|schemas| ConfigSchemas::Inline(schemas.to_string())),
+        },
+        Arc::new(MockClient { sets }),
+    )
+}
+
+#[tokio::test]
+async fn test_inferred_schema() {
+    let mut connector = connector(None);
+    let (tables, schemas) = connector.list_all_schemas().await.unwrap();
+    assert_eq!(
+        tables[0].column_names,
+        vec!["__digest", "__key", "age", "name", "tags"]
+    );
+    let schema = &schemas[0];
+    assert_eq!(schema.cdc_type, CdcType::OnlyPK);
+    assert_eq!(schema.schema.primary_index, vec![0]);
+    assert_eq!(
+        schema
+            .schema
+            .fields
+            .iter()
+            .map(|field| (field.typ, field.nullable))
+            .collect::<Vec<_>>(),
+        vec![
+            (FieldType::Binary, false),
+            (FieldType::String, true),
+            (FieldType::Float, true),
+            (FieldType::String, true),
+            (FieldType::Json, true),
+        ]
+    );
+
+    assert!(connector
+        .validate_tables(&[TableIdentifier::from_table_name("orders".to_string())])
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_configured_schema() {
+    let schemas = r#"{
+        "users": {
+            "schema": {
+                "fields": [
+                    {"name": "__key", "typ": "String", "nullable": false},
+                    {"name": "age", "typ": "Float", "nullable": true}
+                ],
+                "primary_index": [0]
+            }
+        }
+    }"#;
+    let mut connector = connector(Some(schemas));
+    let (tables, schemas) = connector.list_all_schemas().await.unwrap();
+    assert_eq!(tables[0].column_names, vec!["__key", "age"]);
+    assert_eq!(schemas[0].schema.primary_index, vec![0]);
+
+    // Deletes only carry the record key.
+    let schemas = r#"{
+        "users": {
+            "schema": {
+                "fields": [{"name": "age", "typ": "Int", "nullable": false}],
+                "primary_index": [0]
+            }
+        }
+    }"#;
+    let mut connector = connector(Some(schemas));
+    assert!(matches!(
+        connector
+            .validate_connection()
+            .await
+            .unwrap_err()
+            .downcast_ref::<AerospikeConnectorError>(),
+        Some(AerospikeConnectorError::InvalidPrimaryKey(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_replicate() {
+    let mut connector = connector(None);
+    let (tables, schemas) = connector.list_all_schemas().await.unwrap();
+    let sets = vec![ReplicatedSet::new(
+        tables[0].name.clone(),
+        SetMapper::new(schemas[0].schema.clone()),
+    )];
+
+    let event = |digest: u8, set: &str, kind: EventKind| Event {
+        namespace: "test".to_string(),
+        set: set.to_string(),
+        digest: vec![digest; 20],
+        user_key: None,
+        kind,
+    };
+    let write = |age: i64| EventKind::Write {
+        bins: [("age".to_string(), Value::Int(age))].into_iter().collect(),
+    };
+    let (sender, receiver) = unbounded_channel();
+    for event in [
+        event(1, "users", write(31)),
+        event(3, "users", write(20)),
+        event(4, "users", EventKind::Delete),
+        event(2, "users", EventKind::Delete),
+        event(5, "orders", write(1)),
+    ] {
+        sender.send(event).unwrap();
+    }
+    drop(sender);
+
+    let (ingestor, mut iterator) = Ingestor::initialize_channel(Default::default());
+    let client = Arc::new(MockClient {
+        sets: [("users".to_string(), users())].into_iter().collect(),
+    });
+    replicate(client, &ingestor, "test", sets, receiver)
+        .await
+        .unwrap();
+    drop(ingestor);
+
+    let mut ops = vec![];
+    while let Some(message) = iterator.receiver.recv().await {
+        ops.push(message);
+    }
+
+    let key = |digest: u8| {
+        Record::new(vec![
+            Field::Binary(vec![digest; 20]),
+            Field::Null,
+            Field::Null,
+            Field::Null,
+            Field::Null,
+        ])
+    };
+    let insert = |table_index, new| IngestionMessage::OperationEvent {
+        table_index,
+        op: Operation::Insert { new },
+        id: None,
+    };
+    let op = |op| IngestionMessage::OperationEvent {
+        table_index: 0,
+        op,
+        id: None,
+    };
+    let age = |digest: u8, age: f64| {
+        Record::new(vec![
+            Field::Binary(vec![digest; 20]),
+            Field::Null,
+            Field::Float(OrderedFloat(age)),
+            Field::Null,
+            Field::Null,
+        ])
+    };
+    assert_eq!(
+        ops,
+        vec![
+            IngestionMessage::TransactionInfo(TransactionInfo::SnapshottingStarted),
+            insert(
+                0,
+                Record::new(vec![
+                    Field::Binary(vec![1; 20]),
+                    Field::String("alice".to_string()),
+                    Field::Float(OrderedFloat(30.0)),
+                    Field::String("Alice".to_string()),
+                    Field::Null,
+                ])
+            ),
+            insert(
+                0,
+                Record::new(vec![
+                    Field::Binary(vec![2; 20]),
+                    Field::Null,
+                    Field::Float(OrderedFloat(41.5)),
+                    Field::Null,
+                    Field::Json(json_from_str("[1]").unwrap()),
+                ])
+            ),
+            IngestionMessage::TransactionInfo(TransactionInfo::SnapshottingDone { id: None }),
+            op(Operation::Update {
+                old: key(1),
+                new: age(1, 31.0),
+            }),
+            insert(0, age(3, 20.0)),
+            op(Operation::Delete { old: key(2) }),
+        ]
+    );
+}
@@ -100,6 +100,9 @@ pub trait Connector: Send + Sync + Debug {
     }
 
     /// Serializes any state that's required to re-instantiate this connector. Should not be confused with `last_checkpoint`.
+    ///
+    /// This is called once before [`Connector::start`]. A running connector reports later changes of the state
+    /// with `TransactionInfo::SourceStateChanged`.
     async fn serialize_state(&self) -> Result<Vec<u8>, BoxedError>;
 
     /// Starts outputting data from `tables` to `ingestor`. This method should never return unless there is an unrecoverable error.
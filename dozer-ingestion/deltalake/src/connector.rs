@@ -1,9 +1,12 @@
-use crate::reader::DeltaLakeReader;
+use crate::reader::{op_id, DeltaLakeReader};
 use crate::schema_helper::SchemaHelper;
+use crate::state::{ConnectorState, ResumeError};
 use dozer_ingestion_connector::{
     async_trait,
     dozer_types::{
-        errors::internal::BoxedError, models::ingestion_types::DeltaLakeConfig, node::OpIdentifier,
+        errors::internal::BoxedError,
+        models::ingestion_types::{DeltaLakeConfig, IngestionMessage, TransactionInfo},
+        node::OpIdentifier,
         types::FieldType,
     },
     utils::{ListOrFilterColumns, TableNotFound},
@@ -13,11 +16,21 @@ use dozer_ingestion_connector::{
 #[derive(Debug)]
 pub struct DeltaLakeConnector {
     config: DeltaLakeConfig,
+    state: Vec<u8>,
 }
 
 impl DeltaLakeConnector {
     pub fn new(config: DeltaLakeConfig) -> Self {
-        Self { config }
+        Self::with_state(config, None)
+    }
+
+    /// Creates a connector that resumes from `state`, as returned by `serialize_state` or sent with
+    /// `TransactionInfo::SourceStateChanged`.
+    pub fn with_state(config: DeltaLakeConfig, state: Option<Vec<u8>>) -> Self {
+        Self {
+            config,
+            state: state.unwrap_or_default(),
+        }
     }
 }
 
@@ -110,7 +123,7 @@ impl Connector for DeltaLakeConnector {
     }
 
     async fn serialize_state(&self) -> Result<Vec<u8>, BoxedError> {
-        Ok(vec![])
+        Ok(self.state.clone())
     }
 
     async fn start(
@@ -119,8 +132,47 @@ impl Connector for DeltaLakeConnector {
         tables: Vec<TableInfo>,
         last_checkpoint: Option<OpIdentifier>,
     ) -> Result<(), BoxedError> {
-        assert!(last_checkpoint.is_none());
         let reader = DeltaLakeReader::new(self.config.clone());
-        reader.read(&tables, ingestor).await
+        let versions = match last_checkpoint {
+            None => {
+                if ingestor
+                    .handle_message(IngestionMessage::TransactionInfo(
+                        TransactionInfo::SnapshottingStarted,
+                    ))
+                    .await
+                    .is_err()
+                {
+                    // If receiving side is closed, we can stop
+                    return Ok(());
+                }
+                let versions = reader.read(&tables, ingestor).await?;
+                let state = ConnectorState::new(&tables, &versions).serialize();
+                for message in [
+                    TransactionInfo::SourceStateChanged { state },
+                    TransactionInfo::SnapshottingDone {
+                        id: versions
+                            .iter()
+                            .enumerate()
+                            .last()
+                            .map(|(table_index, version)| op_id(table_index, *version)),
+                    },
+                ] {
+                    if ingestor
+                        .handle_message(IngestionMessage::TransactionInfo(message))
+                        .await
+                        .is_err()
+                    {
+                        return Ok(());
+                    }
+                }
+                versions
+            }
+            // The checkpoint has the version of a single table, so the tables resume after the versions of
+            // the state.
+            Some(checkpoint) => ConnectorState::deserialize(&self.state)?
+                .ok_or(ResumeError::MissingState(checkpoint))?
+                .table_versions(&tables)?,
+        };
+        reader.read_changes(&tables, versions, ingestor).await
     }
 }
@@ -0,0 +1,322 @@
+//! Reads the changes of a table version from its commit in the `_delta_log`.
+//!
+//! A commit that writes change data files to `_change_data` has its changes read from them, as the
+//! change data feed requires. Otherwise, every row of the data files it removes is a delete and every
+//! row of the data files it adds is an insert.
+//!
+//! Partition columns are not stored in the data files, so their values are taken from the
+//! `partitionValues` of the file's action, as typed in the table schema.
+
+use std::collections::{HashMap, VecDeque};
+
+use deltalake::{
+    arrow::{
+        array::{ArrayRef, StringArray},
+        compute::cast,
+        datatypes::Schema as ArrowSchema,
+        record_batch::RecordBatch,
+    },
+    parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder,
+    ObjectStore, ObjectStoreError, Path,
+};
+use dozer_ingestion_connector::{
+    dozer_types::{
+        arrow_types::from_arrow::{map_schema_to_dozer, map_value_to_dozer_field},
+        errors::internal::BoxedError,
+        serde::Deserialize,
+        serde_json,
+        thiserror::{self, Error},
+        types::{Field, Operation, Record},
+    },
+    TableInfo,
+};
+
+const CHANGE_TYPE_COLUMN: &str = "_change_type";
+
+#[derive(Debug, Error)]
+pub enum ChangeDataError {
+    #[error("Unknown change type {0} in {1}")]
+    UnknownChangeType(String, Path),
+    #[error("Update postimage without preimage in {0}")]
+    MissingPreimage(Path),
+    #[error("Column {0} is neither in {1} nor a partition column")]
+    MissingColumn(String, Path),
+}
+
+/// A line of a commit file, holding one action.
+#[derive(Debug, Default, Deserialize)]
+#[serde(crate = "dozer_ingestion_connector::dozer_types::serde", default)]
+struct Action {
+    add: Option<FileAction>,
+    remove: Option<FileAction>,
+    cdc: Option<FileAction>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(
+    crate = "dozer_ingestion_connector::dozer_types::serde",
+    rename_all = "camelCase"
+)]
+struct FileAction {
+    /// Relative to the table root and URL encoded.
+    path: String,
+    #[serde(default)]
+    data_change: bool,
+    /// The values of the partition columns in the file, `None` being null.
+    #[serde(default)]
+    partition_values: HashMap<String, Option<String>>,
+}
+
+impl FileAction {
+    fn into_data_file(self) -> Result<DataFile, BoxedError> {
+        Ok(DataFile {
+            path: Path::from_url_path(self.path)?,
+            partition_values: self.partition_values,
+        })
+    }
+}
+
+/// A data file of a commit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataFile {
+    pub path: Path,
+    pub partition_values: HashMap<String, Option<String>>,
+}
+
+/// The files of a commit that hold its changes.
+#[derive(Debug, Default, PartialEq)]
+pub struct Commit {
+    pub added: Vec<DataFile>,
+    pub removed: Vec<DataFile>,
+    pub change_data: Vec<DataFile>,
+}
+
+impl Commit {
+    pub fn parse(content: &[u8]) -> Result<Self, BoxedError> {
+        let mut commit = Self::default();
+        for line in content.split(|byte| *byte == b'\n') {
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            let action: Action = serde_json::from_slice(line)?;
+            if let Some(add) = action.add.filter(|add| add.data_change) {
+                commit.added.push(add.into_data_file()?);
+            }
+            if let Some(remove) = action.remove.filter(|remove| remove.data_change) {
+                commit.removed.push(remove.into_data_file()?);
+            }
+            if let Some(cdc) = action.cdc {
+                commit.change_data.push(cdc.into_data_file()?);
+            }
+        }
+        Ok(commit)
+    }
+
+    /// Reads the commit of `version`, if it has been written.
+    pub async fn read(store: &dyn ObjectStore, version: i64) -> Result<Option<Self>, BoxedError> {
+        let path = Path::from(format!("_delta_log/{version:020}.json"));
+        match store.get(&path).await {
+            Ok(result) => Ok(Some(Self::parse(&result.bytes().await?)?)),
+            Err(ObjectStoreError::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The operations of the commit, on the columns of `table`, whose schema is `table_schema`.
+    pub async fn operations(
+        &self,
+        store: &dyn ObjectStore,
+        table: &TableInfo,
+        table_schema: &ArrowSchema,
+    ) -> Result<Vec<Operation>, BoxedError> {
+        let mut ops = vec![];
+        if !self.change_data.is_empty() {
+            let mut preimages = VecDeque::new();
+            for file in &self.change_data {
+                for (change_type, values) in
+                    read_rows(store, file, table, table_schema, true).await?
+                {
+                    let record = Record::new(values);
+                    match change_type.as_str() {
+                        "insert" => ops.push(Operation::Insert { new: record }),
+                        "delete" => ops.push(Operation::Delete { old: record }),
+                        "update_preimage" => preimages.push_back(record),
+                        "update_postimage" => {
+                            let old = preimages.pop_front().ok_or_else(|| {
+                                ChangeDataError::MissingPreimage(file.path.clone())
+                            })?;
+                            ops.push(Operation::Update { old, new: record });
+                        }
+                        _ => {
+                            return Err(ChangeDataError::UnknownChangeType(
+                                change_type,
+                                file.path.clone(),
+                            )
+                            .into())
+                        }
+                    }
+                }
+            }
+            return Ok(ops);
+        }
+
+        for file in &self.removed {
+            for (_, values) in read_rows(store, file, table, table_schema, false).await? {
+                ops.push(Operation::Delete {
+                    old: Record::new(values),
+                });
+            }
+        }
+        for file in &self.added {
+            for (_, values) in read_rows(store, file, table, table_schema, false).await? {
+                ops.push(Operation::Insert {
+                    new: Record::new(values),
+                });
+            }
+        }
+        Ok(ops)
+    }
+}
+
+/// Reads the rows of a parquet file, with their change type if `change_data` is set.
+async fn read_rows(
+    store: &dyn ObjectStore,
+    file: &DataFile,
+    table: &TableInfo,
+    table_schema: &ArrowSchema,
+    change_data: bool,
+) -> Result<Vec<(String, Vec<Field>)>, BoxedError> {
+    let bytes = store.get(&file.path).await?.bytes().await?;
+    let batches = ParquetRecordBatchReaderBuilder::try_new(bytes)?
+        .build()?
+        .collect::<Result<Vec<RecordBatch>, _>>()?;
+
+    let table_dozer_schema = map_schema_to_dozer(table_schema)?;
+    let mut rows = vec![];
+    for batch in batches {
+        let batch_schema = batch.schema();
+        let dozer_schema = map_schema_to_dozer(&batch_schema)?;
+        let columns = table
+            .column_names
+            .iter()
+            .map(|name| {
+                Ok(match batch_schema.index_of(name) {
+                    Ok(index) => (name.as_str(), batch.column(index).clone(), &dozer_schema),
+                    Err(_) => (
+                        name.as_str(),
+                        partition_column(file, table_schema, name, batch.num_rows())?,
+                        &table_dozer_schema,
+                    ),
+                })
+            })
+            .collect::<Result<Vec<_>, BoxedError>>()?;
+        let change_type_column = if change_data {
+            Some(batch.column(batch_schema.index_of(CHANGE_TYPE_COLUMN)?))
+        } else {
+            None
+        };
+
+        for row in 0..batch.num_rows() {
+            let values = columns
+                .iter()
+                .map(|(name, column, schema)| map_value_to_dozer_field(column, row, name, schema))
+                .collect::<Result<Vec<_>, _>>()?;
+            let change_type = match change_type_column {
+                Some(column) => {
+                    match map_value_to_dozer_field(column, row, CHANGE_TYPE_COLUMN, &dozer_schema)?
+                    {
+                        Field::String(change_type) => change_type,
+                        other => other.to_string(),
+                    }
+                }
+                None => String::new(),
+            };
+            rows.push((change_type, values));
+        }
+    }
+    Ok(rows)
+}
+
+/// A column of `num_rows` copies of the value of the partition column `name` in `file`, cast to
+/// the column's type in the table.
+fn partition_column(
+    file: &DataFile,
+    table_schema: &ArrowSchema,
+    name: &str,
+    num_rows: usize,
+) -> Result<ArrayRef, BoxedError> {
+    let Some(value) = file.partition_values.get(name) else {
+        return Err(ChangeDataError::MissingColumn(name.to_string(), file.path.clone()).into());
+    };
+    let values = StringArray::from(vec![value.clone(); num_rows]);
+    let data_type = table_schema.field_with_name(name)?.data_type();
+    Ok(cast(&values, data_type)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use deltalake::arrow::{
+        array::{Array, Int32Array},
+        datatypes::{DataType, Field as ArrowField},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_parse_commit() {
+        let content = br#"{"commitInfo":{"timestamp":1615043776199,"operation":"UPDATE"}}
+{"remove":{"path":"part-00001.snappy.parquet","dataChange":true}}
+{"add":{"path":"year%3D2024/part-00000.snappy.parquet","partitionValues":{"year":"2024"},"dataChange":true}}
+{"add":{"path":"part-00002.snappy.parquet","dataChange":false}}
+{"cdc":{"path":"_change_data/cdc-00000.snappy.parquet","partitionValues":{},"size":440,"dataChange":false}}
+"#;
+        assert_eq!(
+            Commit::parse(content).unwrap(),
+            Commit {
+                added: vec![DataFile {
+                    path: Path::from("year=2024/part-00000.snappy.parquet"),
+                    partition_values: HashMap::from([(
+                        "year".to_string(),
+                        Some("2024".to_string())
+                    )]),
+                }],
+                removed: vec![data_file("part-00001.snappy.parquet")],
+                change_data: vec![data_file("_change_data/cdc-00000.snappy.parquet")],
+            }
+        );
+    }
+
+    fn data_file(path: &str) -> DataFile {
+        DataFile {
+            path: Path::from(path),
+            partition_values: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_partition_column() {
+        let table_schema = ArrowSchema::new(vec![
+            ArrowField::new("year", DataType::Int32, true),
+            ArrowField::new("country", DataType::Utf8, true),
+        ]);
+        let file = DataFile {
+            path: Path::from("year=2024/country=__HIVE_DEFAULT_PARTITION__/part-00000.parquet"),
+            partition_values: HashMap::from([
+                ("year".to_string(), Some("2024".to_string())),
+                ("country".to_string(), None),
+            ]),
+        };
+
+        let year = partition_column(&file, &table_schema, "year", 2).unwrap();
+        let year = year.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(
+            year.iter().collect::<Vec<_>>(),
+            vec![Some(2024), Some(2024)]
+        );
+
+        let country = partition_column(&file, &table_schema, "country", 2).unwrap();
+        assert_eq!(country.null_count(), 2);
+
+        assert!(partition_column(&file, &table_schema, "city", 2).is_err());
+    }
+}
@@ -1,6 +1,8 @@
 mod connector;
+mod delta_log;
 mod reader;
 mod schema_helper;
+mod state;
 mod test;
 
 pub use connector::DeltaLakeConnector;
@@ -1,11 +1,12 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use deltalake::datafusion::prelude::SessionContext;
 use dozer_ingestion_connector::{
     dozer_types::{
         arrow_types::from_arrow::{map_schema_to_dozer, map_value_to_dozer_field},
         errors::internal::BoxedError,
-        models::ingestion_types::{DeltaLakeConfig, IngestionMessage},
+        models::ingestion_types::{DeltaLakeConfig, IngestionMessage, TransactionInfo},
+        node::OpIdentifier,
         types::{Operation, Record},
     },
     futures::StreamExt,
@@ -14,6 +15,11 @@ use dozer_ingestion_connector::{
     Ingestor, TableInfo,
 };
 
+use crate::{delta_log::Commit, schema_helper::arrow_schema, state::ConnectorState};
+
+/// How long to wait for a new version when all the tables are up to date.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct DeltaLakeReader {
     config: DeltaLakeConfig,
 }
@@ -23,11 +29,17 @@ impl DeltaLakeReader {
         Self { config }
     }
 
-    pub async fn read(&self, table: &[TableInfo], ingestor: &Ingestor) -> Result<(), BoxedError> {
+    /// Reads the latest version of every table, returning the versions.
+    pub async fn read(
+        &self,
+        table: &[TableInfo],
+        ingestor: &Ingestor,
+    ) -> Result<Vec<i64>, BoxedError> {
+        let mut versions = vec![];
         for (table_index, table) in table.iter().enumerate() {
-            self.read_impl(table_index, table, ingestor).await?;
+            versions.push(self.read_impl(table_index, table, ingestor).await?);
         }
-        Ok(())
+        Ok(versions)
     }
 
     async fn read_impl(
@@ -35,10 +47,11 @@ impl DeltaLakeReader {
         table_index: usize,
         table: &TableInfo,
         ingestor: &Ingestor,
-    ) -> Result<(), BoxedError> {
+    ) -> Result<i64, BoxedError> {
         let table_path = table_path(&self.config, &table.name)?;
         let ctx = SessionContext::new();
         let delta_table = deltalake::open_table(table_path).await?;
+        let version = delta_table.version();
         let cols: Vec<&str> = table.column_names.iter().map(|c| c.as_str()).collect();
         let data = ctx
             .read_table(Arc::new(delta_table))?
@@ -75,8 +88,87 @@ impl DeltaLakeReader {
                     .unwrap();
             }
         }
-        Ok(())
+        Ok(version)
     }
+
+    /// Polls the `_delta_log` of every table for the versions after `versions`, and sends their changes.
+    ///
+    /// Each version is committed with the id of [`op_id`], after the per-table versions are sent as the
+    /// source state to resume from.
+    pub async fn read_changes(
+        &self,
+        tables: &[TableInfo],
+        mut versions: Vec<i64>,
+        ingestor: &Ingestor,
+    ) -> Result<(), BoxedError> {
+        let mut stores = vec![];
+        let mut schemas = vec![];
+        for table in tables {
+            let delta_table = deltalake::open_table(table_path(&self.config, &table.name)?).await?;
+            stores.push(delta_table.object_store());
+            schemas.push(arrow_schema(delta_table)?);
+        }
+
+        loop {
+            let mut up_to_date = true;
+            for (table_index, table) in tables.iter().enumerate() {
+                let version = versions[table_index] + 1;
+                let Some(commit) = Commit::read(stores[table_index].as_ref(), version).await?
+                else {
+                    continue;
+                };
+                up_to_date = false;
+
+                let ops = commit
+                    .operations(stores[table_index].as_ref(), table, &schemas[table_index])
+                    .await?;
+                for op in ops {
+                    if ingestor
+                        .handle_message(IngestionMessage::OperationEvent {
+                            table_index,
+                            op,
+                            id: None,
+                        })
+                        .await
+                        .is_err()
+                    {
+                        // If receiving side is closed, we can stop
+                        return Ok(());
+                    }
+                }
+
+                versions[table_index] = version;
+                let state = ConnectorState::new(tables, &versions).serialize();
+                for message in [
+                    TransactionInfo::SourceStateChanged { state },
+                    TransactionInfo::Commit {
+                        id: Some(op_id(table_index, version)),
+                        source_time: None,
+                    },
+                ] {
+                    if ingestor
+                        .handle_message(IngestionMessage::TransactionInfo(message))
+                        .await
+                        .is_err()
+                    {
+                        return Ok(());
+                    }
+                }
+            }
+            if up_to_date {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// The id of `version` of the table at `table_index`, which is the Delta version with the table index as
+/// sequence number.
+///
+/// Versions of different tables are unrelated, so the connector resumes from the versions of its state
+/// rather than from the id.
+pub fn op_id(table_index: usize, version: i64) -> OpIdentifier {
+    OpIdentifier::new(version as u64, table_index as u64)
 }
 
 pub fn table_path(config: &DeltaLakeConfig, table_name: &str) -> Result<String, TableNotFound> {
@@ -1,4 +1,4 @@
-use deltalake::{arrow::datatypes::SchemaRef, datafusion::prelude::SessionContext};
+use deltalake::{arrow::datatypes::SchemaRef, datafusion::prelude::SessionContext, DeltaTable};
 use dozer_ingestion_connector::{
     dozer_types::{errors::internal::BoxedError, models::ingestion_types::DeltaLakeConfig},
     utils::ListOrFilterColumns,
@@ -34,12 +34,16 @@ impl SchemaHelper {
         table: &ListOrFilterColumns,
     ) -> Result<SourceSchema, BoxedError> {
         let table_path = table_path(&self.config, &table.name)?;
-        let ctx = SessionContext::new();
         let delta_table = deltalake::open_table(table_path).await?;
-        let arrow_schema: SchemaRef = (*ctx.read_table(Arc::new(delta_table))?.schema())
-            .clone()
-            .into();
-        let schema = map_schema(arrow_schema, table)?;
-        Ok(SourceSchema::new(schema, CdcType::Nothing))
+        let schema = map_schema(arrow_schema(delta_table)?, table)?;
+        Ok(SourceSchema::new(schema, CdcType::FullChanges))
     }
 }
+
+/// The arrow schema of a table, including its partition columns.
+pub fn arrow_schema(delta_table: DeltaTable) -> Result<SchemaRef, BoxedError> {
+    let ctx = SessionContext::new();
+    Ok((*ctx.read_table(Arc::new(delta_table))?.schema())
+        .clone()
+        .into())
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use dozer_ingestion_connector::{
+    dozer_types::{
+        node::OpIdentifier,
+        serde::{Deserialize, Serialize},
+        serde_json,
+        thiserror::{self, Error},
+    },
+    TableInfo,
+};
+
+#[derive(Debug, Error)]
+pub enum ResumeError {
+    #[error("No source state to resume from checkpoint {0:?}")]
+    MissingState(OpIdentifier),
+    #[error("No version of table {0} in the source state")]
+    MissingTable(String),
+}
+
+/// The state of a [`DeltaLakeConnector`](crate::DeltaLakeConnector), as returned by `serialize_state`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "dozer_ingestion_connector::dozer_types::serde")]
+pub struct ConnectorState {
+    /// The last ingested version of every table, keyed by table name.
+    pub versions: HashMap<String, i64>,
+}
+
+impl ConnectorState {
+    pub fn new(tables: &[TableInfo], versions: &[i64]) -> Self {
+        Self {
+            versions: tables
+                .iter()
+                .zip(versions)
+                .map(|(table, version)| (table.name.clone(), *version))
+                .collect(),
+        }
+    }
+
+    /// The versions of `tables`, failing if the state misses one of them.
+    pub fn table_versions(&self, tables: &[TableInfo]) -> Result<Vec<i64>, ResumeError> {
+        tables
+            .iter()
+            .map(|table| {
+                self.versions
+                    .get(&table.name)
+                    .copied()
+                    .ok_or_else(|| ResumeError::MissingTable(table.name.clone()))
+            })
+            .collect()
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("connector state must be serializable")
+    }
+
+    /// Deserializes a state, which is empty until the connector has ingested a snapshot.
+    pub fn deserialize(state: &[u8]) -> Result<Option<Self>, serde_json::Error> {
+        if state.is_empty() {
+            return Ok(None);
+        }
+        serde_json::from_slice(state).map(Some)
+    }
+}
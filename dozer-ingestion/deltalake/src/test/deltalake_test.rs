@@ -1,11 +1,24 @@
-use crate::DeltaLakeConnector;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{state::ConnectorState, DeltaLakeConnector};
+use deltalake::{
+    arrow::{
+        array::{ArrayRef, Int32Array, StringArray},
+        record_batch::RecordBatch,
+    },
+    parquet::arrow::ArrowWriter,
+};
 use dozer_ingestion_connector::{
     dozer_types::{
-        models::ingestion_types::{DeltaLakeConfig, DeltaTable, IngestionMessage},
-        types::{Field, FieldType, Operation, SourceDefinition},
+        models::ingestion_types::{DeltaLakeConfig, DeltaTable, IngestionMessage, TransactionInfo},
+        node::OpIdentifier,
+        types::{Field, FieldType, Operation, Record, SourceDefinition},
     },
-    test_util::create_runtime_and_spawn_connector_all_tables,
-    tokio, Connector,
+    test_util::{create_runtime_and_spawn_connector_all_tables, create_test_runtime},
+    tokio, Connector, IngestionIterator, Ingestor,
 };
 
 #[tokio::test]
@@ -48,14 +61,215 @@ fn read_deltalake() {
     let fields = vec![Field::Int(0), Field::Int(1), Field::Int(2), Field::Int(4)];
     let mut values = vec![];
     for message in iterator {
-        if let IngestionMessage::OperationEvent {
-            op: Operation::Insert { new },
-            ..
-        } = message
-        {
-            values.extend(new.values);
+        match message {
+            IngestionMessage::OperationEvent {
+                op: Operation::Insert { new },
+                ..
+            } => values.extend(new.values),
+            IngestionMessage::TransactionInfo(TransactionInfo::SnapshottingDone { id }) => {
+                assert_eq!(id, Some(OpIdentifier::new(1, 0)));
+                break;
+            }
+            _ => {}
         }
     }
     values.sort();
     assert_eq!(fields, values);
 }
+
+fn copy_dir(from: &Path, to: &Path) {
+    std::fs::create_dir_all(to).unwrap();
+    for entry in std::fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&entry.path(), &to.join(entry.file_name()));
+        } else {
+            std::fs::copy(entry.path(), to.join(entry.file_name())).unwrap();
+        }
+    }
+}
+
+/// A copy of the test table, to write new versions to.
+fn temp_table(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("dozer-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    copy_dir(Path::new("src/test/data/delta-0.8.0"), &path);
+    path
+}
+
+fn spawn_from_checkpoint(
+    mut connector: DeltaLakeConnector,
+    checkpoint: OpIdentifier,
+) -> IngestionIterator {
+    let runtime = create_test_runtime();
+    let tables = runtime.block_on(async {
+        let tables = connector.list_tables().await.unwrap();
+        connector.list_columns(tables).await.unwrap()
+    });
+    let (ingestor, iterator) = Ingestor::initialize_channel(Default::default());
+    runtime.clone().spawn_blocking(move || {
+        runtime.block_on(async move {
+            connector
+                .start(&ingestor, tables, Some(checkpoint))
+                .await
+                .unwrap()
+        })
+    });
+    iterator
+}
+
+fn next_op(iterator: &mut IngestionIterator) -> Operation {
+    match iterator.next() {
+        Some(IngestionMessage::OperationEvent { op, .. }) => op,
+        other => panic!("Unexpected message {other:?}"),
+    }
+}
+
+/// Expects the state update and commit of a version, returning the state.
+fn next_commit(iterator: &mut IngestionIterator, version: u64) -> ConnectorState {
+    let state = match iterator.next() {
+        Some(IngestionMessage::TransactionInfo(TransactionInfo::SourceStateChanged { state })) => {
+            ConnectorState::deserialize(&state).unwrap().unwrap()
+        }
+        other => panic!("Unexpected message {other:?}"),
+    };
+    match iterator.next() {
+        Some(IngestionMessage::TransactionInfo(TransactionInfo::Commit { id, .. })) => {
+            assert_eq!(id, Some(OpIdentifier::new(version, 0)))
+        }
+        other => panic!("Unexpected message {other:?}"),
+    }
+    state
+}
+
+fn value(value: i64) -> Record {
+    Record::new(vec![Field::Int(value)])
+}
+
+/// Writes a change data file and the commit of `version` that adds it.
+fn write_change_data(path: &Path, version: u64, changes: &[(i32, &str)]) {
+    let batch = RecordBatch::try_from_iter([
+        (
+            "value",
+            Arc::new(Int32Array::from_iter_values(
+                changes.iter().map(|(value, _)| *value),
+            )) as ArrayRef,
+        ),
+        (
+            "_change_type",
+            Arc::new(StringArray::from_iter_values(
+                changes.iter().map(|(_, change_type)| *change_type),
+            )) as ArrayRef,
+        ),
+    ])
+    .unwrap();
+    let file_name = format!("cdc-{version:05}.snappy.parquet");
+    let file = std::fs::File::create(path.join("_change_data").join(&file_name)).unwrap();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+
+    let commit = format!(
+        "{{\"commitInfo\":{{\"timestamp\":1615043786000,\"operation\":\"MERGE\"}}}}\n\
+         {{\"cdc\":{{\"path\":\"_change_data/{file_name}\",\"partitionValues\":{{}},\"size\":0,\"dataChange\":false}}}}\n"
+    );
+    // The commit is renamed into place, so the connector never reads it half written.
+    let log_path = path.join("_delta_log");
+    let temp_path = log_path.join(format!(".{version:020}.json.tmp"));
+    std::fs::write(&temp_path, commit).unwrap();
+    std::fs::rename(temp_path, log_path.join(format!("{version:020}.json"))).unwrap();
+}
+
+#[test]
+fn read_deltalake_changes() {
+    let path = temp_table("deltalake-changes");
+    let config = DeltaLakeConfig {
+        tables: vec![DeltaTable {
+            path: path.to_str().unwrap().to_string(),
+            name: "test_table".to_string(),
+        }],
+    };
+
+    // Version 1 rewrote the file of 2, 3 and 4 without 3, and has no change data files.
+    let state = ConnectorState {
+        versions: [("test_table".to_string(), 0)].into(),
+    };
+    let mut iterator = spawn_from_checkpoint(
+        DeltaLakeConnector::with_state(config, Some(state.serialize())),
+        OpIdentifier::new(0, 0),
+    );
+    for old in [2, 3, 4] {
+        assert_eq!(
+            next_op(&mut iterator),
+            Operation::Delete { old: value(old) }
+        );
+    }
+    for new in [2, 4] {
+        assert_eq!(
+            next_op(&mut iterator),
+            Operation::Insert { new: value(new) }
+        );
+    }
+    let state = next_commit(&mut iterator, 1);
+    assert_eq!(state.versions["test_table"], 1);
+
+    write_change_data(
+        &path,
+        2,
+        &[
+            (4, "update_preimage"),
+            (40, "update_postimage"),
+            (0, "delete"),
+            (5, "insert"),
+        ],
+    );
+    assert_eq!(
+        next_op(&mut iterator),
+        Operation::Update {
+            old: value(4),
+            new: value(40),
+        }
+    );
+    assert_eq!(next_op(&mut iterator), Operation::Delete { old: value(0) });
+    assert_eq!(next_op(&mut iterator), Operation::Insert { new: value(5) });
+    let state = next_commit(&mut iterator, 2);
+
+    // The tables resume from the versions of the state.
+    let connector = DeltaLakeConnector::with_state(
+        DeltaLakeConfig {
+            tables: vec![DeltaTable {
+                path: path.to_str().unwrap().to_string(),
+                name: "test_table".to_string(),
+            }],
+        },
+        Some(state.serialize()),
+    );
+    let mut iterator = spawn_from_checkpoint(connector, OpIdentifier::new(0, 0));
+    write_change_data(&path, 3, &[(5, "delete")]);
+    assert_eq!(next_op(&mut iterator), Operation::Delete { old: value(5) });
+    next_commit(&mut iterator, 3);
+
+    std::fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn resume_deltalake_without_state() {
+    let config = DeltaLakeConfig {
+        tables: vec![DeltaTable {
+            path: "src/test/data/delta-0.8.0".to_string(),
+            name: "test_table".to_string(),
+        }],
+    };
+    let runtime = create_test_runtime();
+    let (ingestor, _iterator) = Ingestor::initialize_channel(Default::default());
+    runtime.block_on(async {
+        let mut connector = DeltaLakeConnector::new(config);
+        let tables = connector.list_tables().await.unwrap();
+        let tables = connector.list_columns(tables).await.unwrap();
+        let error = connector
+            .start(&ingestor, tables, Some(OpIdentifier::new(1, 0)))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().starts_with("No source state"));
+    });
+}
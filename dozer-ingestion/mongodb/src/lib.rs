@@ -7,7 +7,10 @@ use dozer_ingestion_connector::{
         self,
         errors::{internal::BoxedError, types::DeserializationError},
         json_types::{serde_json_to_json_value, JsonValue},
-        models::ingestion_types::{IngestionMessage, TransactionInfo},
+        models::ingestion_types::{
+            default_mongodb_sample_size, IngestionMessage, MongodbConfig, MongodbTypedColumns,
+            TransactionInfo,
+        },
         node::OpIdentifier,
         thiserror::{self, Error},
         types::{Field, FieldDefinition, FieldType, Operation, Record, SourceDefinition},
@@ -22,8 +25,11 @@ use dozer_ingestion_connector::{
 use mongodb::{
     change_stream::event::ChangeStreamEvent,
     error::{CommandError, ErrorKind},
-    options::{ChangeStreamOptions, ClientOptions, ConnectionString},
+    options::{ChangeStreamOptions, ClientOptions, ConnectionString, FindOptions},
 };
+use schema::DocumentSchema;
+
+mod schema;
 
 pub use bson;
 pub use mongodb;
@@ -66,6 +72,15 @@ pub enum MongodbConnectorError {
     #[error("Collection should have pre- and post-images enabled. Collection: {0}")]
     NoPrePostImages(String),
 
+    #[error("Failed to sample the documents of collection {0}. {1}")]
+    SampleDocumentsError(String, #[source] mongodb::error::Error),
+
+    #[error("Column {1} not found in collection {0}")]
+    ColumnNotFound(String, String),
+
+    #[error("The _id column must be selected. Collection: {0}")]
+    IdColumnNotSelected(String),
+
     #[error("Missing permissions: {}", .0.iter().map(|(table, permissions)| format!("{table}: [{}]", permissions.join(", "))).collect::<Vec<_>>().join(", "))]
     MissingPermissions(Vec<(String, Vec<String>)>),
 }
@@ -75,6 +90,10 @@ use MongodbConnectorError::*;
 #[derive(Debug)]
 pub struct MongodbConnector {
     conn_string: String,
+    typed_columns: Option<MongodbTypedColumns>,
+    /// The typed columns returned by `get_schemas`, keyed by collection. `start` flattens documents into them rather
+    /// than inferring them again, so the records match the schemas even if the documents changed in between.
+    schemas: HashMap<String, DocumentSchema>,
 }
 
 #[derive(Default, Clone, Copy)]
@@ -126,6 +145,7 @@ async fn snapshot_collection(
     db: &mongodb::Database,
     collection: &str,
     table_idx: usize,
+    schema: Option<&DocumentSchema>,
     tx: Sender<Result<(usize, Operation), MongodbConnectorError>>,
 ) -> Result<Timestamp, MongodbConnectorError> {
     let mut session = start_session(client).await?;
@@ -141,12 +161,8 @@ async fn snapshot_collection(
         .stream(&mut session)
         .map(|doc| {
             let document = doc.map_err(SnapshotReadError)?;
-            let id = document_id(&document)?;
-            let v: JsonValue =
-                serde_json_to_json_value(Bson::Document(document).into_relaxed_extjson())
-                    .expect("Could not deserialize bson into json");
             Ok(Operation::Insert {
-                new: Record::new(vec![Field::Json(id), Field::Json(v)]),
+                new: Record::new(document_fields(document, schema)?),
             })
         })
         .for_each(|op| async {
@@ -156,25 +172,39 @@ async fn snapshot_collection(
     Ok(timestamp)
 }
 
-struct ChangeEventData {
-    id: Field,
-    fields: Vec<Field>,
+/// The values of a document, as a single JSON column without `schema`.
+fn document_fields(
+    document: Document,
+    schema: Option<&DocumentSchema>,
+) -> Result<Vec<Field>, MongodbConnectorError> {
+    match schema {
+        Some(schema) => schema.flatten(&document),
+        None => {
+            let id = document_id(&document)?;
+            let json = serde_json_to_json_value(Bson::Document(document).into_relaxed_extjson())
+                .map_err(ReplicationDataError)?;
+            Ok(vec![Field::Json(id), Field::Json(json)])
+        }
+    }
+}
+
+/// The values of a record that only has the `_id` of a document.
+fn key_fields(id: JsonValue, schema: Option<&DocumentSchema>) -> Vec<Field> {
+    match schema {
+        Some(schema) => schema.key_fields(Field::Json(id)),
+        None => vec![Field::Json(id), Field::Null],
+    }
 }
 
 fn change_event_fields(
     event: &ChangeStreamEvent<Document>,
-) -> Result<ChangeEventData, MongodbConnectorError> {
-    let id = change_event_id(event)?;
+    schema: Option<&DocumentSchema>,
+) -> Result<Vec<Field>, MongodbConnectorError> {
     let doc = event
         .full_document
         .as_ref()
         .expect("No full document on change stream event");
-    let serde_json = Bson::from(doc).into_relaxed_extjson();
-    let json = serde_json_to_json_value(serde_json).map_err(ReplicationDataError)?;
-    Ok(ChangeEventData {
-        id: Field::Json(id.clone()),
-        fields: vec![Field::Json(id), Field::Json(json)],
-    })
+    document_fields(doc.clone(), schema)
 }
 
 fn change_event_id(
@@ -203,6 +233,7 @@ async fn replicate_collection(
     collection: &str,
     start_at: Timestamp,
     table_idx: usize,
+    schema: Option<&DocumentSchema>,
     tx: Sender<Result<(usize, Operation), MongodbConnectorError>>,
 ) -> Result<(), MongodbConnectorError> {
     let collection: mongodb::Collection<Document> = db.collection(collection);
@@ -221,24 +252,21 @@ async fn replicate_collection(
         .map_err(ReplicationError)
         .and_then(|event| async move {
             match event.operation_type {
-                mongodb::change_stream::event::OperationType::Insert => {
-                    let data = change_event_fields(&event)?;
-                    Ok(Operation::Insert {
-                        new: Record::new(data.fields),
-                    })
-                }
+                mongodb::change_stream::event::OperationType::Insert => Ok(Operation::Insert {
+                    new: Record::new(change_event_fields(&event, schema)?),
+                }),
                 mongodb::change_stream::event::OperationType::Update
                 | mongodb::change_stream::event::OperationType::Replace => {
-                    let data = change_event_fields(&event)?;
+                    let id = change_event_id(&event)?;
                     Ok(Operation::Update {
-                        old: Record::new(vec![data.id, Field::Null]),
-                        new: Record::new(data.fields),
+                        old: Record::new(key_fields(id, schema)),
+                        new: Record::new(change_event_fields(&event, schema)?),
                     })
                 }
                 mongodb::change_stream::event::OperationType::Delete => {
                     let id = change_event_id(&event)?;
                     Ok(Operation::Delete {
-                        old: Record::new(vec![Field::Json(id), Field::Null]),
+                        old: Record::new(key_fields(id, schema)),
                     })
                 }
                 mongodb::change_stream::event::OperationType::Drop
@@ -263,11 +291,13 @@ struct ServerInfo {
 }
 
 impl MongodbConnector {
-    pub fn new(connection_string: String) -> Result<Self, MongodbConnectorError> {
-        let _ = ConnectionString::parse(&connection_string)
+    pub fn new(config: MongodbConfig) -> Result<Self, MongodbConnectorError> {
+        let _ = ConnectionString::parse(&config.connection_string)
             .map_err(MongodbConnectorError::ParseConnectionString);
         Ok(Self {
-            conn_string: connection_string,
+            conn_string: config.connection_string,
+            typed_columns: config.typed_columns,
+            schemas: HashMap::new(),
         })
     }
 
@@ -331,6 +361,48 @@ impl MongodbConnector {
         Ok(server_info)
     }
 
+    /// Infers the columns of a collection from its `$jsonSchema` validator, or from a sample of its documents.
+    async fn document_schema(
+        &self,
+        database: &mongodb::Database,
+        collection: &str,
+        typed_columns: &MongodbTypedColumns,
+    ) -> Result<DocumentSchema, MongodbConnectorError> {
+        if typed_columns.use_validator.unwrap_or(true) {
+            let validator = database
+                .list_collections(Some(doc! {"name": collection}), None)
+                .await
+                .map_err(ListTablesError)?
+                .try_next()
+                .await
+                .map_err(ListTablesError)?
+                .and_then(|collection_info| collection_info.options.validator);
+            if let Some(schema) = validator.as_ref().and_then(DocumentSchema::from_validator) {
+                return Ok(schema);
+            }
+        }
+
+        // Sorting by `_id` samples the same documents as long as the oldest ones are not changed.
+        let options = FindOptions::builder()
+            .sort(doc! {"_id": 1})
+            .limit(
+                typed_columns
+                    .sample_size
+                    .unwrap_or_else(default_mongodb_sample_size) as i64,
+            )
+            .build();
+        let sample_error = |e| SampleDocumentsError(collection.to_owned(), e);
+        let documents: Vec<Document> = database
+            .collection::<Document>(collection)
+            .find(None, options)
+            .await
+            .map_err(sample_error)?
+            .try_collect()
+            .await
+            .map_err(sample_error)?;
+        Ok(DocumentSchema::infer(&documents))
+    }
+
     async fn validate_table_privileges(
         &self,
         database: &mongodb::Database,
@@ -475,6 +547,23 @@ impl Connector for MongodbConnector {
         &mut self,
         tables: Vec<TableIdentifier>,
     ) -> Result<Vec<TableInfo>, BoxedError> {
+        if let Some(typed_columns) = &self.typed_columns {
+            let client = self.client().await?;
+            let database = self.database(&client);
+            let mut table_infos = vec![];
+            for table in tables {
+                let schema = self
+                    .document_schema(&database, &table.name, typed_columns)
+                    .await?;
+                table_infos.push(TableInfo {
+                    schema: None,
+                    name: table.name,
+                    column_names: schema.column_names(),
+                });
+            }
+            return Ok(table_infos);
+        }
+
         Ok(tables
             .into_iter()
             .map(|table| TableInfo {
@@ -489,7 +578,29 @@ impl Connector for MongodbConnector {
         &mut self,
         table_infos: &[TableInfo],
     ) -> Result<Vec<SourceSchemaResult>, BoxedError> {
-        let _ = self.client().await?;
+        let client = self.client().await?;
+        if let Some(typed_columns) = &self.typed_columns {
+            let database = self.database(&client);
+            let mut schemas = vec![];
+            for table_info in table_infos {
+                let schema = self
+                    .document_schema(&database, &table_info.name, typed_columns)
+                    .await
+                    .and_then(|schema| schema.select(&table_info.name, &table_info.column_names));
+                schemas.push(schema);
+            }
+            return Ok(table_infos
+                .iter()
+                .zip(schemas)
+                .map(|(table_info, schema)| {
+                    let schema = schema?;
+                    let source_schema = SourceSchema::new(schema.schema(), CdcType::OnlyPK);
+                    self.schemas.insert(table_info.name.clone(), schema);
+                    Ok(source_schema)
+                })
+                .collect());
+        }
+
         Ok(table_infos
             .iter()
             .map(|_table_info| {
@@ -607,12 +718,35 @@ impl Connector for MongodbConnector {
         let client = self.client().await?;
         let database = self.database(&client);
 
+        let mut schemas = vec![];
+        for table in &tables {
+            // Fields of paths that are not in the schema, or that can't be converted to its types, are kept in the
+            // overflow column.
+            let schema = match (&self.typed_columns, self.schemas.get(&table.name)) {
+                (Some(_), Some(schema)) => Some(schema.clone()),
+                (Some(typed_columns), None) => Some(
+                    self.document_schema(&database, &table.name, typed_columns)
+                        .await?
+                        .select(&table.name, &table.column_names)?,
+                ),
+                (None, _) => None,
+            };
+            schemas.push(schema);
+        }
+
         let (tx, mut rx) = channel::<Result<(usize, Operation), MongodbConnectorError>>(100);
 
         let snapshots = FuturesUnordered::new();
         for (idx, table) in tables.iter().enumerate() {
-            let fut = snapshot_collection(&client, &database, &table.name, idx, tx.clone())
-                .map_ok(move |timestamp| (idx, timestamp));
+            let fut = snapshot_collection(
+                &client,
+                &database,
+                &table.name,
+                idx,
+                schemas[idx].as_ref(),
+                tx.clone(),
+            )
+            .map_ok(move |timestamp| (idx, timestamp));
             snapshots.push(fut);
         }
         drop(tx);
@@ -671,6 +805,7 @@ impl Connector for MongodbConnector {
                 &tables[table_idx].name,
                 timestamp,
                 table_idx,
+                schemas[table_idx].as_ref(),
                 tx,
             ));
         }
@@ -0,0 +1,457 @@
+//! Flattens documents into typed columns, inferred from a sample of documents or a `$jsonSchema` validator.
+
+use std::collections::BTreeMap;
+
+use bson::{Bson, Document};
+use dozer_ingestion_connector::dozer_types::{
+    chrono::{TimeZone, Utc},
+    json_types::serde_json_to_json_value,
+    ordered_float::OrderedFloat,
+    rust_decimal::Decimal,
+    types::{Field, FieldDefinition, FieldType, Schema, SourceDefinition},
+};
+
+use crate::{document_id, MongodbConnectorError};
+
+pub const ID_COLUMN: &str = "_id";
+pub const OVERFLOW_COLUMN: &str = "_overflow";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Column {
+    Id,
+    /// A field of the document, at a dotted path.
+    Path {
+        path: String,
+        typ: FieldType,
+    },
+    /// The fields that are not mapped to a column, as a JSON document.
+    Overflow,
+}
+
+impl Column {
+    fn name(&self) -> &str {
+        match self {
+            Column::Id => ID_COLUMN,
+            Column::Path { path, .. } => path,
+            Column::Overflow => OVERFLOW_COLUMN,
+        }
+    }
+}
+
+/// The columns that the documents of a collection are flattened into.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentSchema {
+    columns: Vec<Column>,
+}
+
+impl DocumentSchema {
+    fn new(paths: BTreeMap<String, FieldType>) -> Self {
+        let mut columns = vec![Column::Id];
+        columns.extend(
+            paths
+                .into_iter()
+                .map(|(path, typ)| Column::Path { path, typ }),
+        );
+        columns.push(Column::Overflow);
+        Self { columns }
+    }
+
+    /// Infers a column for every path that holds a value other than a document in `documents`.
+    ///
+    /// A path with values of different types is a `Json` column, except for numbers which are widened.
+    pub fn infer<'a>(documents: impl IntoIterator<Item = &'a Document>) -> Self {
+        let mut paths = BTreeMap::new();
+        for document in documents {
+            infer_paths(document, "", &mut paths);
+        }
+        Self::new(
+            paths
+                .into_iter()
+                .map(|(path, typ)| (path, typ.unwrap_or(FieldType::Json)))
+                .collect(),
+        )
+    }
+
+    /// The columns of the `properties` of the `$jsonSchema` in `validator`, if it has one.
+    pub fn from_validator(validator: &Document) -> Option<Self> {
+        let json_schema = validator.get_document("$jsonSchema").ok()?;
+        let mut paths = BTreeMap::new();
+        json_schema_paths(json_schema, "", &mut paths);
+        Some(Self::new(paths))
+    }
+
+    pub fn column_names(&self) -> Vec<String> {
+        self.columns
+            .iter()
+            .map(|column| column.name().to_owned())
+            .collect()
+    }
+
+    /// Keeps the columns in `column_names`, in that order. Fields of the dropped columns are kept in the overflow column.
+    pub fn select(
+        &self,
+        collection: &str,
+        column_names: &[String],
+    ) -> Result<Self, MongodbConnectorError> {
+        if !column_names.iter().any(|name| name == ID_COLUMN) {
+            return Err(MongodbConnectorError::IdColumnNotSelected(
+                collection.to_owned(),
+            ));
+        }
+        let columns = column_names
+            .iter()
+            .map(|name| {
+                self.columns
+                    .iter()
+                    .find(|column| column.name() == name)
+                    .cloned()
+                    .ok_or_else(|| {
+                        MongodbConnectorError::ColumnNotFound(collection.to_owned(), name.clone())
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { columns })
+    }
+
+    pub fn schema(&self) -> Schema {
+        let mut schema = Schema::default();
+        for column in &self.columns {
+            let (typ, nullable) = match column {
+                Column::Id => (FieldType::Json, false),
+                Column::Path { typ, .. } => (*typ, true),
+                Column::Overflow => (FieldType::Json, true),
+            };
+            schema.field(
+                FieldDefinition::new(
+                    column.name().to_owned(),
+                    typ,
+                    nullable,
+                    SourceDefinition::Dynamic,
+                ),
+                *column == Column::Id,
+            );
+        }
+        schema
+    }
+
+    /// The values of the columns in `document`.
+    ///
+    /// A value that can't be converted to the type of its column is `Null`, and kept in the overflow column.
+    pub fn flatten(&self, document: &Document) -> Result<Vec<Field>, MongodbConnectorError> {
+        let mut overflow = document.clone();
+        overflow.remove(ID_COLUMN);
+        let mut fields = Vec::with_capacity(self.columns.len());
+        for column in &self.columns {
+            let field = match column {
+                Column::Id => Field::Json(document_id(document)?),
+                Column::Path { path, typ } => match get_path(document, path) {
+                    None => Field::Null,
+                    Some(value) => match convert(value, *typ) {
+                        Some(field) => {
+                            remove_path(&mut overflow, path);
+                            field
+                        }
+                        None => Field::Null,
+                    },
+                },
+                // Filled once all the other columns have been mapped.
+                Column::Overflow => Field::Null,
+            };
+            fields.push(field);
+        }
+
+        if let Some(index) = self
+            .columns
+            .iter()
+            .position(|column| *column == Column::Overflow)
+        {
+            if !overflow.is_empty() {
+                fields[index] = convert(&Bson::Document(overflow), FieldType::Json)
+                    .expect("Documents are always convertible to JSON");
+            }
+        }
+        Ok(fields)
+    }
+
+    /// A record that only has the `_id` of a document, for updates and deletes.
+    pub fn key_fields(&self, id: Field) -> Vec<Field> {
+        self.columns
+            .iter()
+            .map(|column| match column {
+                Column::Id => id.clone(),
+                _ => Field::Null,
+            })
+            .collect()
+    }
+}
+
+/// Adds the paths of `document` to `paths`, with `None` for a path that has only held nulls so far.
+fn infer_paths(document: &Document, prefix: &str, paths: &mut BTreeMap<String, Option<FieldType>>) {
+    for (key, value) in document {
+        if prefix.is_empty() && key == ID_COLUMN {
+            continue;
+        }
+        let path = format!("{prefix}{key}");
+        if let Bson::Document(nested) = value {
+            infer_paths(nested, &format!("{path}."), paths);
+            continue;
+        }
+        let typ = bson_type(value);
+        let entry = paths.entry(path).or_insert(typ);
+        *entry = match (*entry, typ) {
+            (Some(current), Some(typ)) => Some(widen(current, typ)),
+            (current, typ) => current.or(typ),
+        };
+    }
+}
+
+fn bson_type(value: &Bson) -> Option<FieldType> {
+    Some(match value {
+        Bson::Null | Bson::Undefined => return None,
+        Bson::Double(_) => FieldType::Float,
+        Bson::Int32(_) | Bson::Int64(_) => FieldType::Int,
+        Bson::Decimal128(_) => FieldType::Decimal,
+        Bson::String(_) | Bson::Symbol(_) | Bson::ObjectId(_) => FieldType::String,
+        Bson::Boolean(_) => FieldType::Boolean,
+        Bson::DateTime(_) => FieldType::Timestamp,
+        Bson::Binary(_) => FieldType::Binary,
+        _ => FieldType::Json,
+    })
+}
+
+fn widen(left: FieldType, right: FieldType) -> FieldType {
+    use FieldType::{Decimal, Float, Int};
+    match (left, right) {
+        (left, right) if left == right => left,
+        (Int, Float) | (Float, Int) => Float,
+        (Int | Float, Decimal) | (Decimal, Int | Float) => Decimal,
+        _ => FieldType::Json,
+    }
+}
+
+fn json_schema_paths(schema: &Document, prefix: &str, paths: &mut BTreeMap<String, FieldType>) {
+    let Ok(properties) = schema.get_document("properties") else {
+        return;
+    };
+    for (key, property) in properties {
+        if prefix.is_empty() && key == ID_COLUMN {
+            continue;
+        }
+        let Some(property) = property.as_document() else {
+            continue;
+        };
+        let path = format!("{prefix}{key}");
+        let typ = match json_schema_type(property) {
+            Some("object") if property.contains_key("properties") => {
+                json_schema_paths(property, &format!("{path}."), paths);
+                continue;
+            }
+            Some("double" | "number") => FieldType::Float,
+            Some("int" | "long" | "integer") => FieldType::Int,
+            Some("decimal") => FieldType::Decimal,
+            Some("string" | "objectId" | "symbol") => FieldType::String,
+            Some("bool" | "boolean") => FieldType::Boolean,
+            Some("date") => FieldType::Timestamp,
+            Some("binData") => FieldType::Binary,
+            _ => FieldType::Json,
+        };
+        paths.insert(path, typ);
+    }
+}
+
+/// The `bsonType` or `type` of a property, ignoring `null`. `None` if it allows several types.
+fn json_schema_type(property: &Document) -> Option<&str> {
+    let typ = property.get("bsonType").or_else(|| property.get("type"))?;
+    match typ {
+        Bson::String(typ) => Some(typ),
+        Bson::Array(types) => {
+            let mut types = types
+                .iter()
+                .filter_map(Bson::as_str)
+                .filter(|typ| *typ != "null");
+            match (types.next(), types.next()) {
+                (Some(typ), None) => Some(typ),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn get_path<'a>(document: &'a Document, path: &str) -> Option<&'a Bson> {
+    match path.split_once('.') {
+        Some((key, rest)) => get_path(document.get_document(key).ok()?, rest),
+        None => document.get(path),
+    }
+}
+
+/// Removes the value at `path`, and the nested documents that it leaves empty.
+fn remove_path(document: &mut Document, path: &str) {
+    match path.split_once('.') {
+        Some((key, rest)) => {
+            if let Ok(nested) = document.get_document_mut(key) {
+                remove_path(nested, rest);
+                if nested.is_empty() {
+                    document.remove(key);
+                }
+            }
+        }
+        None => {
+            document.remove(path);
+        }
+    }
+}
+
+fn convert(value: &Bson, typ: FieldType) -> Option<Field> {
+    Some(match (value, typ) {
+        (Bson::Null | Bson::Undefined, _) => Field::Null,
+        (value, FieldType::Json) => {
+            Field::Json(serde_json_to_json_value(value.clone().into_relaxed_extjson()).ok()?)
+        }
+        (Bson::Int32(value), FieldType::Int) => Field::Int(*value as i64),
+        (Bson::Int64(value), FieldType::Int) => Field::Int(*value),
+        (Bson::Double(value), FieldType::Float) => Field::Float(OrderedFloat(*value)),
+        (Bson::Int32(value), FieldType::Float) => Field::Float(OrderedFloat(*value as f64)),
+        (Bson::Int64(value), FieldType::Float) => Field::Float(OrderedFloat(*value as f64)),
+        (Bson::Decimal128(_), FieldType::Decimal) => {
+            // The extended JSON of a decimal is its string representation.
+            let json = value.clone().into_relaxed_extjson();
+            Field::Decimal(json.get("$numberDecimal")?.as_str()?.parse().ok()?)
+        }
+        (Bson::Int32(value), FieldType::Decimal) => Field::Decimal(Decimal::from(*value)),
+        (Bson::Int64(value), FieldType::Decimal) => Field::Decimal(Decimal::from(*value)),
+        (Bson::Double(value), FieldType::Decimal) => {
+            Field::Decimal(Decimal::try_from(*value).ok()?)
+        }
+        (Bson::String(value) | Bson::Symbol(value), FieldType::String) => {
+            Field::String(value.clone())
+        }
+        (Bson::ObjectId(value), FieldType::String) => Field::String(value.to_hex()),
+        (Bson::Boolean(value), FieldType::Boolean) => Field::Boolean(*value),
+        (Bson::DateTime(value), FieldType::Timestamp) => Field::Timestamp(
+            Utc.timestamp_millis_opt(value.timestamp_millis())
+                .single()?
+                .into(),
+        ),
+        (Bson::Binary(value), FieldType::Binary) => Field::Binary(value.bytes.clone()),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::{doc, oid::ObjectId};
+    use dozer_ingestion_connector::dozer_types::json_types::json;
+
+    use super::*;
+
+    fn path(path: &str, typ: FieldType) -> Column {
+        Column::Path {
+            path: path.to_owned(),
+            typ,
+        }
+    }
+
+    #[test]
+    fn test_infer_from_sample() {
+        let documents = [
+            doc! { "_id": 1, "name": "alice", "age": 30, "address": { "city": "Paris", "zip": "75001" } },
+            doc! { "_id": 2, "name": "bob", "age": 31.5, "tags": ["a"], "nickname": null },
+            doc! { "_id": 3, "name": 3, "address": { "city": "Oslo" }, "joined": bson::DateTime::from_millis(0) },
+        ];
+        let schema = DocumentSchema::infer(&documents);
+        assert_eq!(
+            schema.columns,
+            vec![
+                Column::Id,
+                path("address.city", FieldType::String),
+                path("address.zip", FieldType::String),
+                path("age", FieldType::Float),
+                path("joined", FieldType::Timestamp),
+                path("name", FieldType::Json),
+                path("nickname", FieldType::Json),
+                path("tags", FieldType::Json),
+                Column::Overflow,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_infer_from_validator() {
+        let validator = doc! {
+            "$jsonSchema": {
+                "bsonType": "object",
+                "properties": {
+                    "_id": { "bsonType": "objectId" },
+                    "name": { "bsonType": "string" },
+                    "age": { "bsonType": ["int", "null"] },
+                    "score": { "bsonType": ["int", "double"] },
+                    "address": {
+                        "bsonType": "object",
+                        "properties": { "city": { "bsonType": "string" } },
+                    },
+                    "extra": { "bsonType": "object" },
+                },
+            },
+        };
+        let schema = DocumentSchema::from_validator(&validator).unwrap();
+        assert_eq!(
+            schema.columns,
+            vec![
+                Column::Id,
+                path("address.city", FieldType::String),
+                path("age", FieldType::Int),
+                path("extra", FieldType::Json),
+                path("name", FieldType::String),
+                path("score", FieldType::Json),
+                Column::Overflow,
+            ]
+        );
+        assert_eq!(DocumentSchema::from_validator(&doc! {}), None);
+    }
+
+    #[test]
+    fn test_flatten() {
+        let id = ObjectId::new();
+        let schema = DocumentSchema::new(BTreeMap::from([
+            ("address.city".to_owned(), FieldType::String),
+            ("age".to_owned(), FieldType::Int),
+            ("id".to_owned(), FieldType::String),
+        ]));
+        let document = doc! {
+            "_id": 1,
+            "id": id,
+            "age": "unknown",
+            "address": { "city": "Paris", "zip": "75001" },
+            "extra": true,
+        };
+        assert_eq!(
+            schema.flatten(&document).unwrap(),
+            vec![
+                Field::Json(json!(1)),
+                Field::String("Paris".to_owned()),
+                Field::Null,
+                Field::String(id.to_hex()),
+                Field::Json(json!({
+                    "age": "unknown",
+                    "address": { "zip": "75001" },
+                    "extra": true,
+                })),
+            ]
+        );
+
+        let schema = schema
+            .select("users", &["age".to_owned(), "_id".to_owned()])
+            .unwrap();
+        assert_eq!(schema.schema().primary_index, vec![1]);
+        assert_eq!(
+            schema.flatten(&doc! { "_id": 2, "age": 30 }).unwrap(),
+            vec![Field::Int(30), Field::Json(json!(2))]
+        );
+        assert_eq!(
+            schema.key_fields(Field::Json(json!(2))),
+            vec![Field::Null, Field::Json(json!(2))]
+        );
+        assert!(schema.select("users", &["age".to_owned()]).is_err());
+    }
+}
@@ -1,9 +1,11 @@
 use dozer_ingestion_connector::dozer_types::models::ingestion_types::{
-    LocalStorage, S3Storage, Table, TableConfig,
+    AzureStorage, GcsStorage, LocalStorage, S3Storage, Table, TableConfig,
 };
 use object_store::aws::{AmazonS3, AmazonS3Builder};
+use object_store::azure::{AzureConfigKey, MicrosoftAzure, MicrosoftAzureBuilder};
+use object_store::gcp::{GoogleCloudStorage, GoogleCloudStorageBuilder};
 use object_store::local::LocalFileSystem;
-use object_store::{BackoffConfig, ObjectStore, RetryConfig};
+use object_store::{BackoffConfig, ClientOptions, ObjectStore, RetryConfig};
 use std::fmt::Debug;
 use url::Url;
 
@@ -35,6 +37,24 @@ pub trait DozerObjectStore: Clone + Send + Sync + Debug + 'static {
     fn tables(&self) -> &[Table];
 }
 
+/// Retries requests to remote stores indefinitely.
+fn retry_config() -> RetryConfig {
+    RetryConfig {
+        backoff: BackoffConfig::default(),
+        max_retries: usize::max_value(),
+        retry_timeout: std::time::Duration::from_secs(u64::MAX),
+    }
+}
+
+fn table_folder(table: &Table) -> String {
+    match &table.config {
+        TableConfig::CSV(csv_config) => csv_config.path.clone(),
+        TableConfig::Parquet(parquet_config) => parquet_config.path.clone(),
+        TableConfig::Json(json_config) => json_config.path.clone(),
+        TableConfig::Avro(avro_config) => avro_config.path.clone(),
+    }
+}
+
 pub struct DozerObjectStoreParams<T: ObjectStore> {
     pub url: Url,
     pub object_store: T,
@@ -58,24 +78,15 @@ impl DozerObjectStore for S3Storage {
     ) -> Result<DozerObjectStoreParams<Self::ObjectStore>, ObjectStoreConnectorError> {
         let details = &self.details;
 
-        let retry_config = RetryConfig {
-            backoff: BackoffConfig::default(),
-            max_retries: usize::max_value(),
-            retry_timeout: std::time::Duration::from_secs(u64::MAX),
-        };
-
         let object_store = AmazonS3Builder::new()
             .with_bucket_name(&details.bucket_name)
             .with_region(&details.region)
             .with_access_key_id(&details.access_key_id)
             .with_secret_access_key(&details.secret_access_key)
-            .with_retry(retry_config)
+            .with_retry(retry_config())
             .build()?;
 
-        let folder = match &table.config {
-            TableConfig::CSV(csv_config) => csv_config.path.clone(),
-            TableConfig::Parquet(parquet_config) => parquet_config.path.clone(),
-        };
+        let folder = table_folder(table);
 
         Ok(DozerObjectStoreParams {
             url: Url::parse(&format!("s3://{}", details.bucket_name)).expect("Must be valid url"),
@@ -106,10 +117,7 @@ impl DozerObjectStore for LocalStorage {
 
         let object_store = LocalFileSystem::new_with_prefix(path)?;
 
-        let folder = match &table.config {
-            TableConfig::CSV(csv_config) => csv_config.path.clone(),
-            TableConfig::Parquet(parquet_config) => parquet_config.path.clone(),
-        };
+        let folder = table_folder(table);
 
         Ok(DozerObjectStoreParams {
             url: Url::parse(&format!("local://{}", path)).expect("Must be valid url"),
@@ -128,3 +136,89 @@ impl DozerObjectStore for LocalStorage {
         &self.tables
     }
 }
+
+impl DozerObjectStore for GcsStorage {
+    type ObjectStore = GoogleCloudStorage;
+
+    fn store_params(
+        &self,
+        table: &Table,
+    ) -> Result<DozerObjectStoreParams<Self::ObjectStore>, ObjectStoreConnectorError> {
+        let details = &self.details;
+
+        let mut builder = GoogleCloudStorageBuilder::new()
+            .with_bucket_name(&details.bucket_name)
+            .with_client_options(
+                ClientOptions::new().with_allow_http(details.allow_http.unwrap_or(false)),
+            )
+            .with_retry(retry_config());
+        if let Some(key) = &details.service_account_key {
+            builder = builder.with_service_account_key(key);
+        }
+        if let Some(path) = &details.service_account_path {
+            builder = builder.with_service_account_path(path);
+        }
+        let object_store = builder.build()?;
+
+        let folder = table_folder(table);
+
+        Ok(DozerObjectStoreParams {
+            url: Url::parse(&format!("gs://{}", details.bucket_name)).expect("Must be valid url"),
+            object_store,
+            table_path: format!("gs://{}/{folder}/", details.bucket_name),
+            folder,
+            data_fusion_table: table.clone(),
+
+            aws_region: None,
+            aws_access_key_id: None,
+            aws_secret_access_key: None,
+        })
+    }
+
+    fn tables(&self) -> &[Table] {
+        &self.tables
+    }
+}
+
+impl DozerObjectStore for AzureStorage {
+    type ObjectStore = MicrosoftAzure;
+
+    fn store_params(
+        &self,
+        table: &Table,
+    ) -> Result<DozerObjectStoreParams<Self::ObjectStore>, ObjectStoreConnectorError> {
+        let details = &self.details;
+
+        let mut builder = MicrosoftAzureBuilder::new()
+            .with_account(&details.account_name)
+            .with_container_name(&details.container_name)
+            .with_use_emulator(details.use_emulator.unwrap_or(false))
+            .with_retry(retry_config());
+        if let Some(access_key) = &details.access_key {
+            builder = builder.with_access_key(access_key);
+        }
+        if let Some(sas_token) = &details.sas_token {
+            builder = builder.with_config(AzureConfigKey::SasKey, sas_token);
+        }
+        let object_store = builder.build()?;
+
+        let folder = table_folder(table);
+
+        Ok(DozerObjectStoreParams {
+            url: Url::parse(&format!("az://{}", details.container_name))
+                .expect("Must be valid url"),
+            object_store,
+            table_path: format!("az://{}/{folder}/", details.container_name),
+            folder,
+            data_fusion_table: table.clone(),
+
+            aws_region: None,
+            aws_access_key_id: None,
+            aws_secret_access_key: None,
+        })
+    }
+
+    fn tables(&self) -> &[Table] {
+        &self.tables
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use dozer_ingestion_connector::dozer_types::errors::internal::BoxedError;
 use dozer_ingestion_connector::dozer_types::log::error;
 use dozer_ingestion_connector::dozer_types::models::ingestion_types::{
@@ -6,7 +8,7 @@ use dozer_ingestion_connector::dozer_types::models::ingestion_types::{
 use dozer_ingestion_connector::dozer_types::node::OpIdentifier;
 use dozer_ingestion_connector::dozer_types::types::FieldType;
 use dozer_ingestion_connector::futures::future::try_join_all;
-use dozer_ingestion_connector::tokio::sync::mpsc::channel;
+use dozer_ingestion_connector::tokio::sync::{mpsc::channel, Mutex};
 use dozer_ingestion_connector::tokio::task::JoinSet;
 use dozer_ingestion_connector::utils::{ListOrFilterColumns, TableNotFound};
 use dozer_ingestion_connector::{
@@ -14,6 +16,7 @@ use dozer_ingestion_connector::{
 };
 
 use crate::adapters::DozerObjectStore;
+use crate::state::ConnectorState;
 use crate::table::ObjectStoreTable;
 use crate::{schema_mapper, ObjectStoreConnectorError};
 
@@ -22,11 +25,27 @@ use super::connection::validator::validate_connection;
 #[derive(Debug)]
 pub struct ObjectStoreConnector<T: Clone> {
     config: T,
+    state: Option<ConnectorState>,
 }
 
 impl<T: DozerObjectStore + 'static> ObjectStoreConnector<T> {
     pub fn new(config: T) -> Self {
-        Self { config }
+        Self {
+            config,
+            state: None,
+        }
+    }
+
+    /// Creates a connector that resumes from `state`, as returned by `serialize_state`.
+    pub fn with_state(
+        config: T,
+        state: Option<Vec<u8>>,
+    ) -> Result<Self, ObjectStoreConnectorError> {
+        let state = match state {
+            Some(state) => ConnectorState::deserialize(&state)?,
+            None => None,
+        };
+        Ok(Self { config, state })
     }
 }
 
@@ -96,7 +115,10 @@ impl<T: DozerObjectStore> Connector for ObjectStoreConnector<T> {
     }
 
     async fn serialize_state(&self) -> Result<Vec<u8>, BoxedError> {
-        Ok(vec![])
+        Ok(self
+            .state
+            .as_ref()
+            .map_or_else(Vec::new, ConnectorState::serialize))
     }
 
     async fn start(
@@ -139,6 +161,7 @@ impl<T: DozerObjectStore> Connector for ObjectStoreConnector<T> {
             .await
             .unwrap();
 
+        let state = Arc::new(Mutex::new(self.state.clone().unwrap_or_default()));
         let mut handles = vec![];
 
         for (table_index, table_info) in tables.iter().enumerate() {
@@ -158,11 +181,24 @@ impl<T: DozerObjectStore> Connector for ObjectStoreConnector<T> {
                     );
                     let table_info = table_info.clone();
                     let sender = sender.clone();
+                    let change_tracking = table_config.change_tracking.clone();
+                    let state = state.clone();
                     handles.push(tokio::spawn(async move {
-                        table
-                            .snapshot(table_index, &table_info, sender)
-                            .await
-                            .unwrap()
+                        match change_tracking {
+                            Some(change_tracking) => table
+                                .sync_changes(
+                                    table_index,
+                                    &table_info,
+                                    &sender,
+                                    &change_tracking,
+                                    &state,
+                                    None,
+                                )
+                                .await
+                                .map(|schema| (Default::default(), schema)),
+                            None => table.snapshot(table_index, &table_info, sender).await,
+                        }
+                        .unwrap()
                     }));
                     found = true;
                     break;
@@ -206,14 +242,35 @@ impl<T: DozerObjectStore> Connector for ObjectStoreConnector<T> {
 
             for table in self.config.tables() {
                 if table_info.name == table.name {
-                    let (state, schema) = updated_state[table_index].clone();
-                    let table =
-                        ObjectStoreTable::new(table.config.clone(), self.config.clone(), state);
+                    let (update_state, schema) = updated_state[table_index].clone();
+                    let change_tracking = table.change_tracking.clone();
+                    let table = ObjectStoreTable::new(
+                        table.config.clone(),
+                        self.config.clone(),
+                        update_state,
+                    );
                     let sender = sender.clone();
+                    let state = state.clone();
                     joinset.spawn(async move {
-                        table
-                            .watch(table_index, &table_info, sender, schema.as_ref())
-                            .await
+                        match change_tracking {
+                            Some(change_tracking) => {
+                                table
+                                    .watch_changes(
+                                        table_index,
+                                        &table_info,
+                                        sender,
+                                        &change_tracking,
+                                        &state,
+                                        schema,
+                                    )
+                                    .await
+                            }
+                            None => {
+                                table
+                                    .watch(table_index, &table_info, sender, schema.as_ref())
+                                    .await
+                            }
+                        }
                     });
                     break;
                 }
@@ -1,5 +1,5 @@
 use datafusion::datasource::{
-    file_format::{csv::CsvFormat, parquet::ParquetFormat},
+    file_format::{avro::AvroFormat, csv::CsvFormat, json::JsonFormat, parquet::ParquetFormat},
     listing::ListingOptions,
 };
 use dozer_ingestion_connector::dozer_types::models::ingestion_types::{Table, TableConfig};
@@ -22,6 +22,14 @@ pub fn map_listing_options(
                     .with_file_extension(parquet.extension.clone()),
             )
         }
+        TableConfig::Json(json) => {
+            let format = JsonFormat::default();
+            Ok(ListingOptions::new(Arc::new(format)).with_file_extension(json.extension.clone()))
+        }
+        TableConfig::Avro(avro) => {
+            let format = AvroFormat;
+            Ok(ListingOptions::new(Arc::new(format)).with_file_extension(avro.extension.clone()))
+        }
     }
 }
 
@@ -1,6 +1,7 @@
 use datafusion::{datasource::listing::ListingTableUrl, error::DataFusionError};
 use dozer_ingestion_connector::dozer_types::{
     arrow_types::errors::FromArrowError,
+    bincode,
     thiserror::{self, Error},
 };
 
@@ -10,6 +11,7 @@ pub mod connector;
 mod helper;
 mod schema_helper;
 pub mod schema_mapper;
+mod state;
 mod table;
 mod table_reader;
 pub(crate) mod table_watcher;
@@ -41,6 +43,9 @@ pub enum ObjectStoreConnectorError {
 
     #[error("Failed to receive message on data read channel")]
     RecvError,
+
+    #[error("Failed to deserialize connector state: {0}")]
+    StateDeserialization(#[source] bincode::error::DecodeError),
 }
 
 #[derive(Error, Debug, PartialEq)]
@@ -77,6 +82,9 @@ pub enum ObjectStoreObjectError {
 
     #[error("Listing path {0} error: {1}")]
     ListingPathError(String, #[source] DataFusionError),
+
+    #[error("Primary key column {0} not found")]
+    PrimaryKeyColumnNotFound(String),
 }
 
 #[derive(Error, Debug)]
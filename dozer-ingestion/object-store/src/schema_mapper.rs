@@ -1,5 +1,7 @@
 use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::datasource::file_format::avro::AvroFormat;
 use datafusion::datasource::file_format::csv::CsvFormat;
+use datafusion::datasource::file_format::json::JsonFormat;
 use datafusion::datasource::file_format::parquet::ParquetFormat;
 use datafusion::datasource::listing::{ListingOptions, ListingTableUrl};
 use datafusion::prelude::SessionContext;
@@ -66,6 +68,18 @@ async fn get_table_schema(
 
             get_object_schema(table, config, listing_options).await
         }
+        TableConfig::Json(table_config) => {
+            let format = JsonFormat::default();
+            let listing_options = ListingOptions::new(Arc::new(format))
+                .with_file_extension(table_config.extension.clone());
+            get_object_schema(table, config, listing_options).await
+        }
+        TableConfig::Avro(table_config) => {
+            let format = AvroFormat;
+            let listing_options = ListingOptions::new(Arc::new(format))
+                .with_file_extension(table_config.extension.clone());
+            get_object_schema(table, config, listing_options).await
+        }
     }
 }
 
@@ -95,7 +109,26 @@ async fn get_object_schema(
             ObjectStoreConnectorError::InternalDataFusionError(e)
         })?;
 
-    let schema = map_schema(resolved_schema, table)?;
+    let mut schema = map_schema(resolved_schema, table)?;
+
+    let cdc_type = match &params.data_fusion_table.change_tracking {
+        Some(change_tracking) => {
+            schema.primary_index = change_tracking
+                .primary_key
+                .iter()
+                .map(|column| {
+                    schema
+                        .get_field_index(column)
+                        .map(|(index, _)| index)
+                        .map_err(|_| {
+                            ObjectStoreObjectError::PrimaryKeyColumnNotFound(column.clone())
+                        })
+                })
+                .collect::<Result<_, _>>()?;
+            CdcType::FullChanges
+        }
+        None => CdcType::Nothing,
+    };
 
-    Ok(SourceSchema::new(schema, CdcType::Nothing))
+    Ok(SourceSchema::new(schema, cdc_type))
 }
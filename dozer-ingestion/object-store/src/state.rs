@@ -0,0 +1,185 @@
+use std::collections::{HashMap, HashSet};
+
+use dozer_ingestion_connector::dozer_types::{
+    bincode::{
+        self,
+        de::Decoder,
+        enc::Encoder,
+        error::{DecodeError, EncodeError},
+        Decode, Encode,
+    },
+    types::{Field, Operation, Record},
+};
+
+use crate::ObjectStoreConnectorError;
+
+/// The state of an [`ObjectStoreConnector`](crate::connector::ObjectStoreConnector), as returned by `serialize_state`.
+///
+/// It's the manifest of the tables with change tracking, keyed by table name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectorState {
+    pub tables: HashMap<String, TableManifest>,
+}
+
+/// The ingested files of a table, keyed by object path.
+pub type TableManifest = HashMap<String, FileManifest>;
+
+/// An ingested file and its rows, to diff the file against when it's modified or deleted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileManifest {
+    /// Milliseconds since the epoch.
+    pub last_modified: i64,
+    pub rows: Vec<Vec<Field>>,
+}
+
+impl Encode for FileManifest {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.last_modified.encode(encoder)?;
+        self.rows.encode(encoder)
+    }
+}
+
+impl Decode for FileManifest {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Ok(Self {
+            last_modified: Decode::decode(decoder)?,
+            rows: Decode::decode(decoder)?,
+        })
+    }
+}
+
+impl ConnectorState {
+    pub fn serialize(&self) -> Vec<u8> {
+        bincode::encode_to_vec(&self.tables, bincode::config::legacy())
+            .expect("connector state must be serializable")
+    }
+
+    /// Deserializes a state, which is empty if no table has change tracking.
+    pub fn deserialize(state: &[u8]) -> Result<Option<Self>, ObjectStoreConnectorError> {
+        if state.is_empty() {
+            return Ok(None);
+        }
+        let (tables, _) = bincode::decode_from_slice(state, bincode::config::legacy())
+            .map_err(ObjectStoreConnectorError::StateDeserialization)?;
+        Ok(Some(Self { tables }))
+    }
+}
+
+/// The operations that turn the `old` rows of a file into the `new` ones.
+///
+/// Rows are matched by their values at the `key` indexes, or by position if there's no key.
+pub fn diff_rows(old: &[Vec<Field>], new: &[Vec<Field>], key: Option<&[usize]>) -> Vec<Operation> {
+    let mut ops = vec![];
+    match key {
+        None => {
+            for (index, new_row) in new.iter().enumerate() {
+                match old.get(index) {
+                    Some(old_row) if old_row == new_row => {}
+                    Some(old_row) => ops.push(update(old_row, new_row)),
+                    None => ops.push(insert(new_row)),
+                }
+            }
+            ops.extend(old.iter().skip(new.len()).map(|row| delete(row)));
+        }
+        Some(key) => {
+            let key_of = |row: &[Field]| key.iter().map(|index| row[*index].clone()).collect();
+            let mut old_by_key = old
+                .iter()
+                .map(|row| (key_of(row), row))
+                .collect::<HashMap<Vec<Field>, _>>();
+            let mut new_keys = HashSet::new();
+            for new_row in new {
+                let new_key = key_of(new_row);
+                match old_by_key.remove(&new_key) {
+                    Some(old_row) if old_row == new_row => {}
+                    Some(old_row) => ops.push(update(old_row, new_row)),
+                    None => ops.push(insert(new_row)),
+                }
+                new_keys.insert(new_key);
+            }
+            ops.extend(
+                old.iter()
+                    .filter(|row| !new_keys.contains(&key_of(row)))
+                    .map(|row| delete(row)),
+            );
+        }
+    }
+    ops
+}
+
+pub fn insert(row: &[Field]) -> Operation {
+    Operation::Insert {
+        new: Record::new(row.to_vec()),
+    }
+}
+
+pub fn delete(row: &[Field]) -> Operation {
+    Operation::Delete {
+        old: Record::new(row.to_vec()),
+    }
+}
+
+fn update(old: &[Field], new: &[Field]) -> Operation {
+    Operation::Update {
+        old: Record::new(old.to_vec()),
+        new: Record::new(new.to_vec()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: i64, name: &str) -> Vec<Field> {
+        vec![Field::Int(id), Field::String(name.to_string())]
+    }
+
+    #[test]
+    fn test_diff_rows_by_position() {
+        let old = vec![row(1, "a"), row(2, "b"), row(3, "c")];
+        let new = vec![row(1, "a"), row(2, "x")];
+        assert_eq!(
+            diff_rows(&old, &new, None),
+            vec![update(&row(2, "b"), &row(2, "x")), delete(&row(3, "c"))]
+        );
+        assert_eq!(
+            diff_rows(&new, &old, None),
+            vec![update(&row(2, "x"), &row(2, "b")), insert(&row(3, "c"))]
+        );
+    }
+
+    #[test]
+    fn test_diff_rows_by_key() {
+        let old = vec![row(1, "a"), row(2, "b"), row(3, "c")];
+        let new = vec![row(3, "c"), row(4, "d"), row(1, "x")];
+        assert_eq!(
+            diff_rows(&old, &new, Some(&[0])),
+            vec![
+                insert(&row(4, "d")),
+                update(&row(1, "a"), &row(1, "x")),
+                delete(&row(2, "b")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_state_roundtrip() {
+        let state = ConnectorState {
+            tables: HashMap::from([(
+                "trips".to_string(),
+                HashMap::from([(
+                    "trips/2024-01.csv".to_string(),
+                    FileManifest {
+                        last_modified: 1_700_000_000_000,
+                        rows: vec![row(1, "a"), vec![Field::Null, Field::Float(1.5.into())]],
+                    },
+                )]),
+            )]),
+        };
+        assert_eq!(
+            ConnectorState::deserialize(&state.serialize()).unwrap(),
+            Some(state)
+        );
+        assert_eq!(ConnectorState::deserialize(&[]).unwrap(), None);
+    }
+}
@@ -1,14 +1,25 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
 use datafusion::{common::DFSchema, datasource::listing::ListingTableUrl, prelude::SessionContext};
 use dozer_ingestion_connector::{
     dozer_types::{
         chrono::{DateTime, Utc},
         log::info,
-        models::ingestion_types::{self, CsvConfig, IngestionMessage, ParquetConfig},
+        models::ingestion_types::{
+            self, AvroConfig, ChangeTracking, CsvConfig, IngestionMessage, JsonConfig,
+            ParquetConfig, TransactionInfo,
+        },
+        types::Operation,
     },
     futures::StreamExt,
-    tokio::{self, sync::mpsc::Sender},
+    tokio::{
+        self,
+        sync::{mpsc::Sender, Mutex},
+    },
     TableInfo,
 };
 use object_store::path::Path;
@@ -17,6 +28,7 @@ use object_store::ObjectStore;
 use crate::{
     adapters::DozerObjectStore,
     helper::{is_marker_file_exist, map_listing_options},
+    state::{delete, diff_rows, ConnectorState, FileManifest},
     table_reader,
     table_watcher::FileInfo,
     ObjectStoreConnectorError, ObjectStoreObjectError,
@@ -330,6 +342,251 @@ impl<C: TableConfig, O: DozerObjectStore> ObjectStoreTable<C, O> {
     }
 }
 
+impl<C: TableConfig, O: DozerObjectStore> ObjectStoreTable<C, O> {
+    /// Diffs the files of the table against its manifest in `state`, sending the rows of new files as inserts,
+    /// and the changed rows of modified and deleted files as updates and deletes.
+    ///
+    /// The deletes of all the files are sent before their inserts and updates, so a row that moved between files is
+    /// deleted before it's inserted again. The state is only locked to read and write the table's manifest, not while
+    /// sending.
+    ///
+    /// Sends the updated state if any file changed. Returns the schema of the files read, or `schema` if none was read.
+    pub async fn sync_changes(
+        &self,
+        table_index: usize,
+        table_info: &TableInfo,
+        sender: &Sender<Result<Option<IngestionMessage>, ObjectStoreConnectorError>>,
+        change_tracking: &ChangeTracking,
+        state: &Mutex<ConnectorState>,
+        mut schema: Option<DFSchema>,
+    ) -> Result<Option<DFSchema>, ObjectStoreConnectorError> {
+        let params = self.store.table_params(&table_info.name)?;
+        let store = Arc::new(params.object_store);
+
+        let listing_options = map_listing_options(&params.data_fusion_table)
+            .map_err(ObjectStoreConnectorError::DataFusionStorageObjectError)?;
+
+        let ctx = SessionContext::new();
+
+        ctx.runtime_env()
+            .register_object_store(&params.url, store.clone());
+
+        let mut files = HashMap::new();
+        let mut marker_files = HashSet::new();
+        let mut stream = store.list(Some(&Path::from(params.folder.clone())));
+        while let Some(item) = stream.next().await {
+            let object = item?;
+            let file_path = object.location.to_string();
+            if file_path.ends_with(self.table_config.extension()) {
+                files.insert(file_path, object.last_modified.timestamp_millis());
+            } else if let Some(marker_extension) = self.table_config.marker_extension() {
+                if file_path.ends_with(marker_extension) {
+                    marker_files.insert(file_stem(&file_path).to_string());
+                }
+            }
+        }
+        // A file without marker is not complete yet.
+        if self.table_config.marker_extension().is_some() {
+            files.retain(|file_path, _| marker_files.contains(file_stem(file_path)));
+        }
+
+        let mut manifest = state
+            .lock()
+            .await
+            .tables
+            .get(&table_info.name)
+            .cloned()
+            .unwrap_or_default();
+        let mut changed = false;
+        let mut deletes = vec![];
+        let mut changes = vec![];
+
+        let deleted_files = manifest
+            .keys()
+            .filter(|file_path| !files.contains_key(*file_path))
+            .cloned()
+            .collect::<Vec<_>>();
+        for file_path in deleted_files {
+            info!("Source Object has been deleted: {file_path:?}");
+            let file = manifest
+                .remove(&file_path)
+                .expect("file must be in manifest");
+            deletes.extend(file.rows.iter().map(|row| delete(row)));
+            changed = true;
+        }
+
+        let mut files = files.into_iter().collect::<Vec<_>>();
+        files.sort_by_key(|(_, last_modified)| *last_modified);
+        for (file_path, last_modified) in files {
+            let old_rows = match manifest.get(&file_path) {
+                Some(file) if file.last_modified >= last_modified => continue,
+                Some(file) => {
+                    info!("Source Object has been modified: {file_path:?}");
+                    file.rows.as_slice()
+                }
+                None => {
+                    info!("Source Object has been added: {file_path:?}");
+                    &[]
+                }
+            };
+
+            let file_url = file_url(&params.table_path, &file_path);
+            let file_path_url = ListingTableUrl::parse(&file_url).map_err(|e| {
+                ObjectStoreConnectorError::DataFusionStorageObjectError(
+                    ObjectStoreObjectError::ListingPathParsingError(file_url.clone(), e),
+                )
+            })?;
+            let (file_schema, rows) = match table_reader::read_rows(
+                &ctx,
+                &file_path_url,
+                listing_options.clone(),
+                table_info,
+                schema.as_ref(),
+            )
+            .await
+            {
+                Ok(result) => result,
+                Err(e) => {
+                    sender
+                        .send(Err(e))
+                        .await
+                        .map_err(|_| ObjectStoreConnectorError::SendError)?;
+                    continue;
+                }
+            };
+
+            let key = if change_tracking.primary_key.is_empty() {
+                None
+            } else {
+                Some(primary_key_indexes(
+                    &file_schema,
+                    &change_tracking.primary_key,
+                )?)
+            };
+            for op in diff_rows(old_rows, &rows, key.as_deref()) {
+                match op {
+                    Operation::Delete { .. } => deletes.push(op),
+                    _ => changes.push(op),
+                }
+            }
+            manifest.insert(
+                file_path,
+                FileManifest {
+                    last_modified,
+                    rows,
+                },
+            );
+            schema.get_or_insert(file_schema);
+            changed = true;
+        }
+
+        if changed {
+            for op in deletes.into_iter().chain(changes) {
+                send_op(sender, table_index, op).await?;
+            }
+            let state = {
+                let mut state = state.lock().await;
+                state.tables.insert(table_info.name.clone(), manifest);
+                state.serialize()
+            };
+            sender
+                .send(Ok(Some(IngestionMessage::TransactionInfo(
+                    TransactionInfo::SourceStateChanged { state },
+                ))))
+                .await
+                .map_err(|_| ObjectStoreConnectorError::SendError)?;
+            sender
+                .send(Ok(Some(IngestionMessage::TransactionInfo(
+                    TransactionInfo::Commit {
+                        id: None,
+                        source_time: None,
+                    },
+                ))))
+                .await
+                .map_err(|_| ObjectStoreConnectorError::SendError)?;
+        }
+        Ok(schema)
+    }
+
+    /// Calls [`Self::sync_changes`] periodically.
+    pub async fn watch_changes(
+        &self,
+        table_index: usize,
+        table_info: &TableInfo,
+        sender: Sender<Result<Option<IngestionMessage>, ObjectStoreConnectorError>>,
+        change_tracking: &ChangeTracking,
+        state: &Mutex<ConnectorState>,
+        mut schema: Option<DFSchema>,
+    ) -> Result<(), ObjectStoreConnectorError> {
+        loop {
+            const WATCHER_INTERVAL: Duration = Duration::from_secs(1);
+            tokio::time::sleep(WATCHER_INTERVAL).await;
+
+            schema = self
+                .sync_changes(
+                    table_index,
+                    table_info,
+                    &sender,
+                    change_tracking,
+                    state,
+                    schema,
+                )
+                .await?;
+        }
+    }
+}
+
+async fn send_op(
+    sender: &Sender<Result<Option<IngestionMessage>, ObjectStoreConnectorError>>,
+    table_index: usize,
+    op: Operation,
+) -> Result<(), ObjectStoreConnectorError> {
+    sender
+        .send(Ok(Some(IngestionMessage::OperationEvent {
+            table_index,
+            op,
+            id: None,
+        })))
+        .await
+        .map_err(|_| ObjectStoreConnectorError::SendError)
+}
+
+/// The path of a file without its extension.
+fn file_stem(file_path: &str) -> &str {
+    file_path
+        .rsplit_once('.')
+        .map_or(file_path, |(stem, _)| stem)
+}
+
+/// The url of the object at `file_path`, relative to the table folder at `table_path`.
+fn file_url(table_path: &str, file_path: &str) -> String {
+    let path = std::path::Path::new(file_path);
+    let relative_path = path
+        .strip_prefix(
+            path.components()
+                .next()
+                .expect("object path can't be empty"),
+        )
+        .expect("object path must start with its first component");
+    table_path.to_string() + relative_path.to_str().expect("object path must be utf-8")
+}
+
+fn primary_key_indexes(
+    schema: &DFSchema,
+    primary_key: &[String],
+) -> Result<Vec<usize>, ObjectStoreObjectError> {
+    primary_key
+        .iter()
+        .map(|column| {
+            schema
+                .fields()
+                .iter()
+                .position(|field| field.name() == column)
+                .ok_or_else(|| ObjectStoreObjectError::PrimaryKeyColumnNotFound(column.clone()))
+        })
+        .collect()
+}
+
 impl TableConfig for CsvConfig {
     fn path(&self) -> &str {
         &self.path
@@ -358,11 +615,41 @@ impl TableConfig for ParquetConfig {
     }
 }
 
+impl TableConfig for JsonConfig {
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    fn marker_extension(&self) -> Option<&str> {
+        self.marker_extension.as_deref()
+    }
+}
+
+impl TableConfig for AvroConfig {
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    fn marker_extension(&self) -> Option<&str> {
+        self.marker_extension.as_deref()
+    }
+}
+
 impl TableConfig for ingestion_types::TableConfig {
     fn path(&self) -> &str {
         match self {
             ingestion_types::TableConfig::CSV(csv_config) => csv_config.path(),
             ingestion_types::TableConfig::Parquet(parquet_config) => parquet_config.path(),
+            ingestion_types::TableConfig::Json(json_config) => json_config.path(),
+            ingestion_types::TableConfig::Avro(avro_config) => avro_config.path(),
         }
     }
 
@@ -370,6 +657,8 @@ impl TableConfig for ingestion_types::TableConfig {
         match self {
             ingestion_types::TableConfig::CSV(csv_config) => csv_config.extension(),
             ingestion_types::TableConfig::Parquet(parquet_config) => parquet_config.extension(),
+            ingestion_types::TableConfig::Json(json_config) => json_config.extension(),
+            ingestion_types::TableConfig::Avro(avro_config) => avro_config.extension(),
         }
     }
 
@@ -379,6 +668,8 @@ impl TableConfig for ingestion_types::TableConfig {
             ingestion_types::TableConfig::Parquet(parquet_config) => {
                 parquet_config.marker_extension()
             }
+            ingestion_types::TableConfig::Json(json_config) => json_config.marker_extension(),
+            ingestion_types::TableConfig::Avro(avro_config) => avro_config.marker_extension(),
         }
     }
 }
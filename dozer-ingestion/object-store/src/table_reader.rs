@@ -1,14 +1,16 @@
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::common::DFSchema;
 use datafusion::datasource::listing::{
     ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
 };
-use datafusion::prelude::SessionContext;
+use datafusion::prelude::{DataFrame, SessionContext};
 use dozer_ingestion_connector::dozer_types::arrow_types::from_arrow::{
     map_schema_to_dozer, map_value_to_dozer_field,
 };
 use dozer_ingestion_connector::dozer_types::log::error;
 use dozer_ingestion_connector::dozer_types::models::ingestion_types::IngestionMessage;
-use dozer_ingestion_connector::dozer_types::types::{Operation, Record};
+use dozer_ingestion_connector::dozer_types::types::{Field, Operation, Record};
 use dozer_ingestion_connector::futures::StreamExt;
 use dozer_ingestion_connector::tokio::sync::mpsc::Sender;
 use dozer_ingestion_connector::{tokio, TableInfo};
@@ -16,17 +18,16 @@ use std::sync::Arc;
 
 use crate::{ObjectStoreConnectorError, ObjectStoreTableReaderError};
 
-pub async fn read(
-    table_index: usize,
-    ctx: SessionContext,
-    table_path: ListingTableUrl,
+/// Selects the columns of `table` from the files at `table_path`, which must have `schema` if it's known.
+async fn dataframe(
+    ctx: &SessionContext,
+    table_path: &ListingTableUrl,
     listing_options: ListingOptions,
     table: &TableInfo,
-    sender: Sender<Result<Option<IngestionMessage>, ObjectStoreConnectorError>>,
     schema: Option<&DFSchema>,
-) -> Result<DFSchema, ObjectStoreConnectorError> {
+) -> Result<(DataFrame, SchemaRef), ObjectStoreConnectorError> {
     let resolved_schema = listing_options
-        .infer_schema(&ctx.state(), &table_path)
+        .infer_schema(&ctx.state(), table_path)
         .await
         .map_err(ObjectStoreConnectorError::InternalDataFusionError)?;
 
@@ -60,14 +61,56 @@ pub async fn read(
             )
         })?;
 
-    let this_schema = dataframe.schema().to_owned();
     if let Some(schema) = schema {
-        if schema != &this_schema {
+        if schema != dataframe.schema() {
             return Err(ObjectStoreConnectorError::TableReaderError(
-                ObjectStoreTableReaderError::ConflictingSchema(table_path),
+                ObjectStoreTableReaderError::ConflictingSchema(table_path.clone()),
             ));
         }
     }
+    Ok((dataframe, resolved_schema))
+}
+
+fn batch_rows(
+    batch: &RecordBatch,
+    resolved_schema: &SchemaRef,
+) -> Result<Vec<Vec<Field>>, ObjectStoreConnectorError> {
+    let batch_schema = batch.schema();
+    let dozer_schema = map_schema_to_dozer(batch_schema.as_ref())?;
+
+    let mut rows = Vec::with_capacity(batch.num_rows());
+    for row in 0..batch.num_rows() {
+        let fields = batch
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(col, column)| {
+                map_value_to_dozer_field(
+                    column,
+                    row,
+                    resolved_schema.field(col).name(),
+                    &dozer_schema,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.push(fields);
+    }
+    Ok(rows)
+}
+
+pub async fn read(
+    table_index: usize,
+    ctx: SessionContext,
+    table_path: ListingTableUrl,
+    listing_options: ListingOptions,
+    table: &TableInfo,
+    sender: Sender<Result<Option<IngestionMessage>, ObjectStoreConnectorError>>,
+    schema: Option<&DFSchema>,
+) -> Result<DFSchema, ObjectStoreConnectorError> {
+    let (dataframe, resolved_schema) =
+        dataframe(&ctx, &table_path, listing_options, table, schema).await?;
+
+    let this_schema = dataframe.schema().to_owned();
     let data = dataframe.execute_stream().await.map_err(|e| {
         ObjectStoreConnectorError::TableReaderError(
             ObjectStoreTableReaderError::StreamExecutionError(e),
@@ -85,24 +128,7 @@ pub async fn read(
             }
         };
 
-        let batch_schema = batch.schema();
-        let dozer_schema = map_schema_to_dozer(batch_schema.as_ref())?;
-
-        for row in 0..batch.num_rows() {
-            let fields = batch
-                .columns()
-                .iter()
-                .enumerate()
-                .map(|(col, column)| {
-                    map_value_to_dozer_field(
-                        column,
-                        row,
-                        resolved_schema.field(col).name(),
-                        &dozer_schema,
-                    )
-                })
-                .collect::<Result<Vec<_>, _>>()?;
-
+        for fields in batch_rows(&batch, &resolved_schema)? {
             let evt = Operation::Insert {
                 new: Record {
                     values: fields,
@@ -128,3 +154,30 @@ pub async fn read(
 
     Ok(this_schema.to_owned())
 }
+
+/// Reads all the rows of the files at `table_path`.
+///
+/// Unlike [`read`], fails on a record batch that can't be read, as the rows would be incomplete.
+pub async fn read_rows(
+    ctx: &SessionContext,
+    table_path: &ListingTableUrl,
+    listing_options: ListingOptions,
+    table: &TableInfo,
+    schema: Option<&DFSchema>,
+) -> Result<(DFSchema, Vec<Vec<Field>>), ObjectStoreConnectorError> {
+    let (dataframe, resolved_schema) =
+        dataframe(ctx, table_path, listing_options, table, schema).await?;
+
+    let this_schema = dataframe.schema().to_owned();
+    let batches = dataframe.collect().await.map_err(|e| {
+        ObjectStoreConnectorError::TableReaderError(
+            ObjectStoreTableReaderError::StreamExecutionError(e),
+        )
+    })?;
+
+    let mut rows = vec![];
+    for batch in &batches {
+        rows.extend(batch_rows(batch, &resolved_schema)?);
+    }
+    Ok((this_schema, rows))
+}
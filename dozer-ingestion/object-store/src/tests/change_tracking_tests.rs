@@ -0,0 +1,187 @@
+use std::path::{Path, PathBuf};
+
+use dozer_ingestion_connector::{
+    dozer_types::{
+        models::ingestion_types::{
+            ChangeTracking, CsvConfig, IngestionMessage, LocalDetails, LocalStorage, Table,
+            TableConfig, TransactionInfo,
+        },
+        types::{Field, Operation, Record},
+    },
+    test_util::create_runtime_and_spawn_connector_all_tables,
+    IngestionIterator,
+};
+
+use crate::{connector::ObjectStoreConnector, state::ConnectorState};
+
+/// Replaces the file atomically, so the watcher never reads it half written.
+fn write_file(dir: &Path, name: &str, content: &str) {
+    let temp_path = dir.join(format!("{name}.tmp"));
+    std::fs::write(&temp_path, content).unwrap();
+    std::fs::rename(temp_path, dir.join(name)).unwrap();
+}
+
+fn tracked_storage(path: &Path, primary_key: Vec<String>) -> LocalStorage {
+    LocalStorage {
+        details: LocalDetails {
+            path: path.to_str().unwrap().to_string(),
+        },
+        tables: vec![Table {
+            config: TableConfig::CSV(CsvConfig {
+                path: "tracked".to_string(),
+                extension: ".csv".to_string(),
+                marker_extension: None,
+            }),
+            name: "tracked".to_string(),
+            change_tracking: Some(ChangeTracking { primary_key }),
+        }],
+    }
+}
+
+fn row(id: i64, name: &str) -> Record {
+    Record::new(vec![Field::Int(id), Field::String(name.to_string())])
+}
+
+fn next_op(iterator: &mut IngestionIterator) -> Operation {
+    match iterator.next() {
+        Some(IngestionMessage::OperationEvent { op, .. }) => op,
+        other => panic!("Unexpected message {other:?}"),
+    }
+}
+
+/// Expects the state update and commit that follow the operations of a sync, returning the state.
+fn next_state(iterator: &mut IngestionIterator) -> ConnectorState {
+    let state = match iterator.next() {
+        Some(IngestionMessage::TransactionInfo(TransactionInfo::SourceStateChanged { state })) => {
+            ConnectorState::deserialize(&state).unwrap().unwrap()
+        }
+        other => panic!("Unexpected message {other:?}"),
+    };
+    assert!(matches!(
+        iterator.next(),
+        Some(IngestionMessage::TransactionInfo(
+            TransactionInfo::Commit { .. }
+        ))
+    ));
+    state
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("dozer-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(path.join("tracked")).unwrap();
+    path
+}
+
+#[test]
+fn test_change_tracking_by_primary_key() {
+    let path = temp_dir("change-tracking-by-primary-key");
+    let table_dir = path.join("tracked");
+    write_file(
+        &table_dir,
+        "users.csv",
+        "id,name\n1,alice\n2,bob\n3,carol\n",
+    );
+
+    let connector = ObjectStoreConnector::new(tracked_storage(&path, vec!["id".to_string()]));
+    let (mut iterator, _) = create_runtime_and_spawn_connector_all_tables(connector);
+
+    assert!(matches!(
+        iterator.next(),
+        Some(IngestionMessage::TransactionInfo(
+            TransactionInfo::SnapshottingStarted
+        ))
+    ));
+    for (id, name) in [(1, "alice"), (2, "bob"), (3, "carol")] {
+        assert_eq!(
+            next_op(&mut iterator),
+            Operation::Insert { new: row(id, name) }
+        );
+    }
+    let state = next_state(&mut iterator);
+    assert_eq!(state.tables["tracked"]["tracked/users.csv"].rows.len(), 3);
+    assert!(matches!(
+        iterator.next(),
+        Some(IngestionMessage::TransactionInfo(
+            TransactionInfo::SnapshottingDone { .. }
+        ))
+    ));
+    assert!(matches!(
+        iterator.next(),
+        Some(IngestionMessage::TransactionInfo(
+            TransactionInfo::Commit { .. }
+        ))
+    ));
+
+    // Rewrite the file: update a row, delete a row and insert a row.
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    write_file(
+        &table_dir,
+        "users.csv",
+        "id,name\n3,carol\n1,alicia\n4,dave\n",
+    );
+    assert_eq!(
+        next_op(&mut iterator),
+        Operation::Update {
+            old: row(1, "alice"),
+            new: row(1, "alicia"),
+        }
+    );
+    assert_eq!(
+        next_op(&mut iterator),
+        Operation::Insert {
+            new: row(4, "dave")
+        }
+    );
+    assert_eq!(
+        next_op(&mut iterator),
+        Operation::Delete { old: row(2, "bob") }
+    );
+    next_state(&mut iterator);
+
+    // Delete the file.
+    std::fs::remove_file(table_dir.join("users.csv")).unwrap();
+    for (id, name) in [(3, "carol"), (1, "alicia"), (4, "dave")] {
+        assert_eq!(
+            next_op(&mut iterator),
+            Operation::Delete { old: row(id, name) }
+        );
+    }
+    let state = next_state(&mut iterator);
+    assert!(state.tables["tracked"].is_empty());
+
+    std::fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn test_change_tracking_resumes_from_state() {
+    let path = temp_dir("change-tracking-resumes-from-state");
+    let table_dir = path.join("tracked");
+    write_file(&table_dir, "users.csv", "id,name\n1,alice\n2,bob\n");
+
+    let connector = ObjectStoreConnector::new(tracked_storage(&path, vec![]));
+    let (mut iterator, _) = create_runtime_and_spawn_connector_all_tables(connector);
+    iterator.next();
+    next_op(&mut iterator);
+    next_op(&mut iterator);
+    let state = next_state(&mut iterator);
+
+    // Rows are matched by position without primary key.
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    write_file(&table_dir, "users.csv", "id,name\n1,alice\n2,bobby\n");
+    let connector =
+        ObjectStoreConnector::with_state(tracked_storage(&path, vec![]), Some(state.serialize()))
+            .unwrap();
+    let (mut iterator, _) = create_runtime_and_spawn_connector_all_tables(connector);
+    iterator.next();
+    assert_eq!(
+        next_op(&mut iterator),
+        Operation::Update {
+            old: row(2, "bob"),
+            new: row(2, "bobby"),
+        }
+    );
+    next_state(&mut iterator);
+
+    std::fs::remove_dir_all(path).unwrap();
+}
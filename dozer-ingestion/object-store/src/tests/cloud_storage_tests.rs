@@ -0,0 +1,146 @@
+//! The emulator tests expect an empty `dozer-test` bucket in fake-gcs-server and `dozer-test` container in Azurite:
+//!
+//! ```sh
+//! docker run -d -p 4443:4443 fsouza/fake-gcs-server -scheme http
+//! curl -X POST http://localhost:4443/storage/v1/b -H 'Content-Type: application/json' -d '{"name": "dozer-test"}'
+//! docker run -d -p 10000:10000 mcr.microsoft.com/azure-storage/azurite azurite-blob --blobHost 0.0.0.0
+//! az storage container create -n dozer-test --connection-string 'UseDevelopmentStorage=true'
+//! ```
+
+use dozer_ingestion_connector::{
+    dozer_types::{
+        models::ingestion_types::{
+            AzureDetails, AzureStorage, CsvConfig, GcsDetails, GcsStorage, IngestionMessage, Table,
+            TableConfig, TransactionInfo,
+        },
+        types::Operation,
+    },
+    test_util::create_runtime_and_spawn_connector_all_tables,
+    tokio,
+};
+use object_store::{path::Path, ObjectStore};
+
+use crate::{adapters::DozerObjectStore, connector::ObjectStoreConnector};
+
+const BUCKET: &str = "dozer-test";
+
+/// A service account key that points to fake-gcs-server.
+const FAKE_GCS_SERVICE_ACCOUNT_KEY: &str = r#"{"gcs_base_url": "http://localhost:4443", "disable_oauth": true, "client_email": "", "private_key": "", "private_key_id": ""}"#;
+
+fn csv_tables() -> Vec<Table> {
+    vec![Table {
+        config: TableConfig::CSV(CsvConfig {
+            path: "all_types_csv".to_string(),
+            extension: "csv".to_string(),
+            marker_extension: None,
+        }),
+        name: "all_types_csv".to_string(),
+        change_tracking: None,
+    }]
+}
+
+fn gcs_storage() -> GcsStorage {
+    GcsStorage {
+        details: GcsDetails {
+            bucket_name: BUCKET.to_string(),
+            service_account_key: Some(FAKE_GCS_SERVICE_ACCOUNT_KEY.to_string()),
+            service_account_path: None,
+            allow_http: Some(true),
+        },
+        tables: csv_tables(),
+    }
+}
+
+fn azure_storage() -> AzureStorage {
+    AzureStorage {
+        details: AzureDetails {
+            account_name: "devstoreaccount1".to_string(),
+            container_name: BUCKET.to_string(),
+            access_key: None,
+            sas_token: None,
+            use_emulator: Some(true),
+        },
+        tables: csv_tables(),
+    }
+}
+
+#[test]
+fn test_gcs_store_params() {
+    let params = gcs_storage().table_params("all_types_csv").unwrap();
+    assert_eq!(params.url.as_str(), "gs://dozer-test");
+    assert_eq!(params.table_path, "gs://dozer-test/all_types_csv/");
+    assert_eq!(params.folder, "all_types_csv");
+}
+
+#[test]
+fn test_azure_store_params() {
+    let params = azure_storage().table_params("all_types_csv").unwrap();
+    assert_eq!(params.url.as_str(), "az://dozer-test");
+    assert_eq!(params.table_path, "az://dozer-test/all_types_csv/");
+    assert_eq!(params.folder, "all_types_csv");
+}
+
+/// Uploads the local `all_types_csv` files to the table's folder in `config`'s store.
+fn upload_csv_files(config: &impl DozerObjectStore) {
+    let params = config.table_params("all_types_csv").unwrap();
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        for name in ["sample.csv", "new_sample.csv"] {
+            let data = std::fs::read(format!("src/tests/files/all_types_csv/{name}")).unwrap();
+            params
+                .object_store
+                .put(
+                    &Path::from(format!("{}/{name}", params.folder)),
+                    data.into(),
+                )
+                .await
+                .unwrap();
+        }
+    });
+}
+
+fn assert_csv_snapshot<T: DozerObjectStore>(config: T) {
+    let connector = ObjectStoreConnector::new(config);
+
+    let (mut iterator, _) = create_runtime_and_spawn_connector_all_tables(connector);
+
+    let row = iterator.next();
+    if let Some(IngestionMessage::TransactionInfo(TransactionInfo::SnapshottingStarted)) = row {
+    } else {
+        panic!("Unexpected message");
+    }
+
+    for _ in 0..20 {
+        let row = iterator.next();
+        if let Some(IngestionMessage::OperationEvent {
+            op: Operation::Insert { new },
+            ..
+        }) = row
+        {
+            assert_eq!(new.values.len(), 10);
+        } else {
+            panic!("Unexpected message");
+        }
+    }
+
+    let row = iterator.next();
+    if let Some(IngestionMessage::TransactionInfo(TransactionInfo::SnapshottingDone { .. })) = row {
+    } else {
+        panic!("Unexpected message");
+    }
+}
+
+#[test]
+#[ignore]
+fn test_gcs_csv_read() {
+    let config = gcs_storage();
+    upload_csv_files(&config);
+    assert_csv_snapshot(config);
+}
+
+#[test]
+#[ignore]
+fn test_azure_csv_read() {
+    let config = azure_storage();
+    upload_csv_files(&config);
+    assert_csv_snapshot(config);
+}
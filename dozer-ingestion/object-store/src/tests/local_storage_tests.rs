@@ -370,3 +370,134 @@ fn test_csv_read_no_marker() {
         panic!("Unexpected message");
     }
 }
+
+#[tokio::test]
+async fn test_get_schema_of_json() {
+    let local_storage = get_local_storage_config("json", "");
+
+    let mut connector = ObjectStoreConnector::new(local_storage);
+    let (_, schemas) = connector.list_all_schemas().await.unwrap();
+    let schema = schemas.first().unwrap();
+
+    let fields = schema.schema.fields.clone();
+    assert_eq!(fields.first().unwrap().typ, FieldType::Int);
+    assert_eq!(fields.get(1).unwrap().typ, FieldType::String);
+    assert_eq!(fields.get(2).unwrap().typ, FieldType::String);
+    assert_eq!(fields.get(3).unwrap().typ, FieldType::Int);
+    assert_eq!(fields.get(4).unwrap().typ, FieldType::Float);
+    assert_eq!(fields.get(5).unwrap().typ, FieldType::Float);
+    assert_eq!(fields.get(6).unwrap().typ, FieldType::Float);
+    assert_eq!(fields.get(7).unwrap().typ, FieldType::String);
+    assert_eq!(fields.get(8).unwrap().typ, FieldType::String);
+    assert_eq!(fields.get(9).unwrap().typ, FieldType::Float);
+}
+
+#[tokio::test]
+async fn test_get_schema_of_avro() {
+    let local_storage = get_local_storage_config("avro", "");
+
+    let mut connector = ObjectStoreConnector::new(local_storage);
+    let (_, schemas) = connector.list_all_schemas().await.unwrap();
+    let schema = schemas.first().unwrap();
+
+    let fields = schema.schema.fields.clone();
+    assert_eq!(fields.first().unwrap().typ, FieldType::Int);
+    assert_eq!(fields.get(1).unwrap().typ, FieldType::String);
+    assert_eq!(fields.get(2).unwrap().typ, FieldType::String);
+    assert_eq!(fields.get(3).unwrap().typ, FieldType::Int);
+    assert_eq!(fields.get(4).unwrap().typ, FieldType::Float);
+    assert_eq!(fields.get(5).unwrap().typ, FieldType::Float);
+    assert_eq!(fields.get(6).unwrap().typ, FieldType::Float);
+    assert_eq!(fields.get(7).unwrap().typ, FieldType::String);
+    assert_eq!(fields.get(8).unwrap().typ, FieldType::String);
+    assert_eq!(fields.get(9).unwrap().typ, FieldType::Float);
+    assert!(fields.get(9).unwrap().nullable);
+}
+
+/// Reads the snapshot of the 10 sample rows that the JSON and Avro fixtures share.
+fn read_sample_rows(typ: &str, prefix: &str) {
+    let local_storage = get_local_storage_config(typ, prefix);
+
+    let connector = ObjectStoreConnector::new(local_storage);
+
+    let (mut iterator, _) = create_runtime_and_spawn_connector_all_tables(connector);
+
+    let row = iterator.next();
+    if let Some(IngestionMessage::TransactionInfo(TransactionInfo::SnapshottingStarted)) = row {
+    } else {
+        panic!("Unexpected message");
+    }
+
+    for _ in 0..10 {
+        let row = iterator.next();
+        if let Some(IngestionMessage::OperationEvent {
+            op: Operation::Insert { new },
+            ..
+        }) = row
+        {
+            let values = new.values;
+
+            test_type_conversion!(values, 0, Field::Int(_));
+            test_type_conversion!(values, 1, Field::String(_));
+            test_type_conversion!(values, 2, Field::String(_));
+            test_type_conversion!(values, 3, Field::Int(_));
+            test_type_conversion!(values, 4, Field::Float(_));
+            test_type_conversion!(values, 5, Field::Float(_));
+            test_type_conversion!(values, 6, Field::Float(_));
+            test_type_conversion!(values, 7, Field::String(_));
+            test_type_conversion!(values, 8, Field::String(_));
+
+            if values[0] == Field::Int(2) {
+                test_type_conversion!(values, 9, Field::Float(_));
+            } else {
+                test_type_conversion!(values, 9, Field::Null);
+            }
+        } else {
+            panic!("Unexpected message");
+        }
+    }
+
+    let row = iterator.next();
+    if let Some(IngestionMessage::TransactionInfo(TransactionInfo::SnapshottingDone { .. })) = row {
+    } else {
+        panic!("Unexpected message");
+    }
+}
+
+#[test]
+fn test_json_read() {
+    read_sample_rows("json", "");
+}
+
+#[test]
+fn test_json_read_marker() {
+    read_sample_rows("json", "marker");
+}
+
+#[test]
+fn test_json_read_no_marker() {
+    let local_storage = get_local_storage_config("json", "no_marker");
+
+    let connector = ObjectStoreConnector::new(local_storage);
+
+    let (mut iterator, _) = create_runtime_and_spawn_connector_all_tables(connector);
+
+    let row = iterator.next();
+    if let Some(IngestionMessage::TransactionInfo(TransactionInfo::SnapshottingStarted)) = row {
+    } else {
+        panic!("Unexpected message");
+    }
+
+    // No data to be snapshotted
+
+    let row = iterator.next();
+    if let Some(IngestionMessage::TransactionInfo(TransactionInfo::SnapshottingDone { .. })) = row {
+    } else {
+        panic!("Unexpected message");
+    }
+}
+
+#[test]
+fn test_avro_read() {
+    read_sample_rows("avro", "");
+}
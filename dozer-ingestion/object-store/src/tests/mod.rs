@@ -1,2 +1,4 @@
+mod change_tracking_tests;
+mod cloud_storage_tests;
 mod local_storage_tests;
 mod test_utils;
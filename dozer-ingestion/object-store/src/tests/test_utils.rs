@@ -1,5 +1,6 @@
 use dozer_ingestion_connector::dozer_types::models::ingestion_types::{
-    CsvConfig, LocalDetails, LocalStorage, ParquetConfig, Table, TableConfig,
+    AvroConfig, CsvConfig, JsonConfig, LocalDetails, LocalStorage, ParquetConfig, Table,
+    TableConfig,
 };
 use std::path::PathBuf;
 
@@ -18,6 +19,7 @@ pub fn get_local_storage_config(typ: &str, prefix: &str) -> LocalStorage {
                         marker_extension: None,
                     }),
                     name: format!("all_types_{typ}"),
+                    change_tracking: None,
                 }],
             },
             &_ => LocalStorage {
@@ -31,6 +33,7 @@ pub fn get_local_storage_config(typ: &str, prefix: &str) -> LocalStorage {
                         marker_extension: Some(String::from(".marker")),
                     }),
                     name: format!("{prefix}_{typ}"),
+                    change_tracking: None,
                 }],
             },
         },
@@ -46,6 +49,7 @@ pub fn get_local_storage_config(typ: &str, prefix: &str) -> LocalStorage {
                         marker_extension: None,
                     }),
                     name: format!("all_types_{typ}"),
+                    change_tracking: None,
                 }],
             },
             &_ => LocalStorage {
@@ -59,6 +63,67 @@ pub fn get_local_storage_config(typ: &str, prefix: &str) -> LocalStorage {
                         marker_extension: Some(String::from(".marker")),
                     }),
                     name: format!("{prefix}_{typ}"),
+                    change_tracking: None,
+                }],
+            },
+        },
+        "json" => match prefix {
+            "" => LocalStorage {
+                details: LocalDetails {
+                    path: p.to_str().unwrap().to_string(),
+                },
+                tables: vec![Table {
+                    config: TableConfig::Json(JsonConfig {
+                        extension: typ.to_string(),
+                        path: format!("all_types_{typ}"),
+                        marker_extension: None,
+                    }),
+                    name: format!("all_types_{typ}"),
+                    change_tracking: None,
+                }],
+            },
+            &_ => LocalStorage {
+                details: LocalDetails {
+                    path: p.to_str().unwrap().to_string(),
+                },
+                tables: vec![Table {
+                    config: TableConfig::Json(JsonConfig {
+                        extension: typ.to_string(),
+                        path: format!("{prefix}_{typ}"),
+                        marker_extension: Some(String::from(".marker")),
+                    }),
+                    name: format!("{prefix}_{typ}"),
+                    change_tracking: None,
+                }],
+            },
+        },
+        "avro" => match prefix {
+            "" => LocalStorage {
+                details: LocalDetails {
+                    path: p.to_str().unwrap().to_string(),
+                },
+                tables: vec![Table {
+                    config: TableConfig::Avro(AvroConfig {
+                        extension: typ.to_string(),
+                        path: format!("all_types_{typ}"),
+                        marker_extension: None,
+                    }),
+                    name: format!("all_types_{typ}"),
+                    change_tracking: None,
+                }],
+            },
+            &_ => LocalStorage {
+                details: LocalDetails {
+                    path: p.to_str().unwrap().to_string(),
+                },
+                tables: vec![Table {
+                    config: TableConfig::Avro(AvroConfig {
+                        extension: typ.to_string(),
+                        path: format!("{prefix}_{typ}"),
+                        marker_extension: Some(String::from(".marker")),
+                    }),
+                    name: format!("{prefix}_{typ}"),
+                    change_tracking: None,
                 }],
             },
         },
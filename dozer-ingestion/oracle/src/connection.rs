@@ -0,0 +1,56 @@
+use dozer_ingestion_connector::dozer_types::{log::debug, models::ingestion_types::OracleConfig};
+use oracle::Connection;
+
+use crate::OracleConnectorError;
+
+/// Formats of the dates, timestamps and numbers LogMiner writes in `SQL_REDO`, parsed by `conversion::parse_text`.
+const SESSION_SETUP: &[&str] = &[
+    "ALTER SESSION SET NLS_DATE_FORMAT = 'YYYY-MM-DD HH24:MI:SS'",
+    "ALTER SESSION SET NLS_TIMESTAMP_FORMAT = 'YYYY-MM-DD HH24:MI:SS.FF9'",
+    "ALTER SESSION SET NLS_TIMESTAMP_TZ_FORMAT = 'YYYY-MM-DD HH24:MI:SS.FF9 TZH:TZM'",
+    "ALTER SESSION SET NLS_NUMERIC_CHARACTERS = '.,'",
+];
+
+/// Connects to the database identified by `sid`, which is the container database if `pdb` is set.
+///
+/// LogMiner must run in the root container.
+pub fn connect_root(config: &OracleConfig) -> Result<Connection, OracleConnectorError> {
+    let connect_string = format!(
+        "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCP)(HOST={})(PORT={}))(CONNECT_DATA=(SID={})))",
+        config.host, config.port, config.sid
+    );
+    debug!("Connecting to oracle at {connect_string}");
+    let connection = Connection::connect(&config.user, &config.password, connect_string)?;
+    for statement in SESSION_SETUP {
+        connection.execute(statement, &[])?;
+    }
+    Ok(connection)
+}
+
+/// Connects to the database holding the tables, switching to `pdb` if set.
+pub fn connect(config: &OracleConfig) -> Result<Connection, OracleConnectorError> {
+    let connection = connect_root(config)?;
+    if let Some(pdb) = &config.pdb {
+        connection.execute(&format!("ALTER SESSION SET CONTAINER = {pdb}"), &[])?;
+    }
+    Ok(connection)
+}
+
+pub fn current_scn(connection: &Connection) -> Result<u64, OracleConnectorError> {
+    Ok(connection.query_row_as::<u64>("SELECT CURRENT_SCN FROM V$DATABASE", &[])?)
+}
+
+/// The start SCN of the oldest open transaction, if any.
+pub fn oldest_transaction_scn(
+    connection: &Connection,
+) -> Result<Option<u64>, OracleConnectorError> {
+    Ok(connection.query_row_as::<Option<u64>>("SELECT MIN(START_SCN) FROM V$TRANSACTION", &[])?)
+}
+
+pub fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+pub fn quote_literal(literal: &str) -> String {
+    format!("'{}'", literal.replace('\'', "''"))
+}
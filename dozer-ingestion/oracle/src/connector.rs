@@ -0,0 +1,250 @@
+use std::time::Duration;
+
+use dozer_ingestion_connector::{
+    async_trait,
+    dozer_types::{
+        errors::internal::BoxedError,
+        log::info,
+        models::ingestion_types::{
+            IngestionMessage, OracleConfig, OracleReplicator, TransactionInfo,
+        },
+        node::OpIdentifier,
+        types::FieldType,
+    },
+    tokio,
+    utils::TableNotFound,
+    Connector, Ingestor, SourceSchemaResult, TableIdentifier, TableInfo,
+};
+use oracle::Connection;
+
+use crate::{
+    connection::{connect, connect_root, current_scn, oldest_transaction_scn},
+    conversion,
+    logminer::{LogMiner, Position},
+    schema::{self, ReplicatedTable},
+    snapshot::{send, snapshot},
+    OracleConnectorError,
+};
+
+const DEFAULT_BATCH_SIZE: usize = 100_000;
+
+#[derive(Debug)]
+pub struct OracleConnector {
+    config: OracleConfig,
+}
+
+impl OracleConnector {
+    pub fn new(config: OracleConfig) -> Self {
+        Self { config }
+    }
+
+    /// Tables without schema belong to the connecting user.
+    fn owner(&self, schema: Option<&String>) -> String {
+        schema
+            .cloned()
+            .unwrap_or_else(|| self.config.user.to_uppercase())
+    }
+
+    /// Runs `f` on a new connection to the database holding the tables, on a blocking thread.
+    async fn with_connection<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Connection) -> Result<T, OracleConnectorError> + Send + 'static,
+    ) -> Result<T, OracleConnectorError> {
+        let config = self.config.clone();
+        tokio::task::spawn_blocking(move || f(&connect(&config)?)).await?
+    }
+}
+
+#[async_trait]
+impl Connector for OracleConnector {
+    fn types_mapping() -> Vec<(String, Option<FieldType>)>
+    where
+        Self: Sized,
+    {
+        conversion::types_mapping()
+    }
+
+    async fn validate_connection(&mut self) -> Result<(), BoxedError> {
+        if self.config.replicator == OracleReplicator::DozerLogReader {
+            return Err(OracleConnectorError::UnsupportedReplicator.into());
+        }
+        self.with_connection(|_| Ok(())).await?;
+        Ok(())
+    }
+
+    async fn list_tables(&mut self) -> Result<Vec<TableIdentifier>, BoxedError> {
+        let schemas = self.config.schemas.clone();
+        let tables = self
+            .with_connection(move |connection| schema::list_tables(connection, &schemas))
+            .await?;
+        Ok(tables)
+    }
+
+    async fn validate_tables(&mut self, tables: &[TableIdentifier]) -> Result<(), BoxedError> {
+        let tables = tables
+            .iter()
+            .map(|table| (self.owner(table.schema.as_ref()), table.name.clone()))
+            .collect::<Vec<_>>();
+        self.with_connection(move |connection| {
+            for (owner, table) in &tables {
+                if schema::table_columns(connection, owner, table)?.is_empty() {
+                    return Err(TableNotFound {
+                        schema: Some(owner.clone()),
+                        name: table.clone(),
+                    }
+                    .into());
+                }
+            }
+            schema::validate_supplemental_logging(connection, &tables)
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn list_columns(
+        &mut self,
+        tables: Vec<TableIdentifier>,
+    ) -> Result<Vec<TableInfo>, BoxedError> {
+        let tables = tables
+            .into_iter()
+            .map(|table| (self.owner(table.schema.as_ref()), table))
+            .collect::<Vec<_>>();
+        let table_infos = self
+            .with_connection(move |connection| {
+                tables
+                    .into_iter()
+                    .map(|(owner, table)| {
+                        let columns = schema::table_columns(connection, &owner, &table.name)?;
+                        if columns.is_empty() {
+                            return Err(TableNotFound {
+                                schema: table.schema,
+                                name: table.name,
+                            }
+                            .into());
+                        }
+                        Ok(TableInfo {
+                            schema: table.schema,
+                            name: table.name,
+                            column_names: columns.into_iter().map(|column| column.name).collect(),
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .await?;
+        Ok(table_infos)
+    }
+
+    async fn get_schemas(
+        &mut self,
+        table_infos: &[TableInfo],
+    ) -> Result<Vec<SourceSchemaResult>, BoxedError> {
+        let tables = table_infos
+            .iter()
+            .map(|table| (self.owner(table.schema.as_ref()), table.clone()))
+            .collect::<Vec<_>>();
+        let schemas = self
+            .with_connection(move |connection| {
+                Ok(tables
+                    .iter()
+                    .map(|(owner, table)| {
+                        schema::source_schema(connection, owner, table).map_err(Into::into)
+                    })
+                    .collect())
+            })
+            .await?;
+        Ok(schemas)
+    }
+
+    async fn serialize_state(&self) -> Result<Vec<u8>, BoxedError> {
+        Ok(vec![])
+    }
+
+    async fn start(
+        &mut self,
+        ingestor: &Ingestor,
+        tables: Vec<TableInfo>,
+        last_checkpoint: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        let OracleReplicator::LogMiner {
+            poll_interval_in_milliseconds,
+        } = self.config.replicator
+        else {
+            return Err(OracleConnectorError::UnsupportedReplicator.into());
+        };
+        let tables = tables
+            .into_iter()
+            .map(|table| (self.owner(table.schema.as_ref()), table))
+            .collect();
+        let config = self.config.clone();
+        let ingestor = ingestor.clone();
+        tokio::task::spawn_blocking(move || {
+            replicate(
+                &config,
+                &ingestor,
+                tables,
+                last_checkpoint,
+                Duration::from_millis(poll_interval_in_milliseconds),
+            )
+        })
+        .await
+        .map_err(OracleConnectorError::from)??;
+        Ok(())
+    }
+}
+
+/// Snapshots the tables unless resuming from `last_checkpoint`, then mines their changes.
+fn replicate(
+    config: &OracleConfig,
+    ingestor: &Ingestor,
+    tables: Vec<(String, TableInfo)>,
+    last_checkpoint: Option<OpIdentifier>,
+    poll_interval: Duration,
+) -> Result<(), OracleConnectorError> {
+    let connection = connect(config)?;
+    let tables = tables
+        .into_iter()
+        .map(|(owner, table)| ReplicatedTable::new(&connection, owner, &table))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let checkpoint = match last_checkpoint {
+        Some(id) => Position::from(id),
+        None => {
+            if !send(
+                ingestor,
+                IngestionMessage::TransactionInfo(TransactionInfo::SnapshottingStarted),
+            ) {
+                return Ok(());
+            }
+            // Transactions open at the snapshot SCN are mined from their start, and emitted if they commit after it.
+            let scn = current_scn(&connection)?;
+            let oldest_transaction_scn = oldest_transaction_scn(&connection)?;
+            let batch_size = config.batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
+            if !snapshot(&connection, ingestor, &tables, scn, batch_size)? {
+                return Ok(());
+            }
+            let position = Position::snapshot(scn, oldest_transaction_scn);
+            if !send(
+                ingestor,
+                IngestionMessage::TransactionInfo(TransactionInfo::SnapshottingDone {
+                    id: Some(position.into()),
+                }),
+            ) {
+                return Ok(());
+            }
+            position
+        }
+    };
+    drop(connection);
+
+    let start_scn = checkpoint.start_scn();
+    info!("Starting LogMiner at SCN {start_scn}, after {checkpoint:?}");
+    let connection = connect_root(config)?;
+    LogMiner::new(
+        &connection,
+        ingestor,
+        config.pdb.as_deref(),
+        &tables,
+        checkpoint,
+    )
+    .run(start_scn, poll_interval)
+}
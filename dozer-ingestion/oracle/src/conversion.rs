@@ -0,0 +1,334 @@
+use std::time::Duration;
+
+use dozer_ingestion_connector::dozer_types::{
+    chrono::{DateTime, FixedOffset, NaiveDateTime, Offset, Utc},
+    json_types::json_from_str,
+    ordered_float::OrderedFloat,
+    rust_decimal::Decimal,
+    types::{DozerDuration, Field, FieldType, TimeUnit},
+};
+use oracle::{sql_type::IntervalDS, Row};
+
+use crate::{redo::Value, OracleConnectorError};
+
+/// Parses the session's `NLS_TIMESTAMP_TZ_FORMAT`.
+const TIMESTAMP_TZ_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f %:z";
+/// Parses the session's `NLS_TIMESTAMP_FORMAT` and `NLS_DATE_FORMAT`.
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+pub fn types_mapping() -> Vec<(String, Option<FieldType>)> {
+    vec![
+        ("NUMBER".into(), Some(FieldType::Decimal)),
+        ("FLOAT".into(), Some(FieldType::Float)),
+        ("BINARY_FLOAT".into(), Some(FieldType::Float)),
+        ("BINARY_DOUBLE".into(), Some(FieldType::Float)),
+        ("CHAR".into(), Some(FieldType::String)),
+        ("NCHAR".into(), Some(FieldType::String)),
+        ("VARCHAR2".into(), Some(FieldType::String)),
+        ("NVARCHAR2".into(), Some(FieldType::String)),
+        ("CLOB".into(), Some(FieldType::Text)),
+        ("NCLOB".into(), Some(FieldType::Text)),
+        ("LONG".into(), Some(FieldType::Text)),
+        ("RAW".into(), Some(FieldType::Binary)),
+        ("LONG RAW".into(), Some(FieldType::Binary)),
+        ("BLOB".into(), Some(FieldType::Binary)),
+        ("DATE".into(), Some(FieldType::Timestamp)),
+        ("TIMESTAMP".into(), Some(FieldType::Timestamp)),
+        (
+            "TIMESTAMP WITH TIME ZONE".into(),
+            Some(FieldType::Timestamp),
+        ),
+        (
+            "TIMESTAMP WITH LOCAL TIME ZONE".into(),
+            Some(FieldType::Timestamp),
+        ),
+        ("INTERVAL DAY TO SECOND".into(), Some(FieldType::Duration)),
+        ("JSON".into(), Some(FieldType::Json)),
+        ("INTERVAL YEAR TO MONTH".into(), None),
+        ("ROWID".into(), None),
+        ("UROWID".into(), None),
+        ("BFILE".into(), None),
+        ("XMLTYPE".into(), None),
+        ("SDO_GEOMETRY".into(), None),
+    ]
+}
+
+/// Maps a `DATA_TYPE` of `ALL_TAB_COLUMNS`.
+///
+/// Integer `NUMBER`s are mapped to `Int` if they fit, and `I128` otherwise.
+pub fn map_type(data_type: &str, precision: Option<u32>, scale: Option<i32>) -> Option<FieldType> {
+    let typ = match remove_precision(data_type).as_str() {
+        "NUMBER" => match (precision, scale) {
+            (Some(precision), Some(0)) if precision <= 18 => FieldType::Int,
+            (_, Some(0)) => FieldType::I128,
+            _ => FieldType::Decimal,
+        },
+        "FLOAT" | "BINARY_FLOAT" | "BINARY_DOUBLE" => FieldType::Float,
+        "CHAR" | "NCHAR" | "VARCHAR2" | "NVARCHAR2" => FieldType::String,
+        "CLOB" | "NCLOB" | "LONG" => FieldType::Text,
+        "RAW" | "LONG RAW" | "BLOB" => FieldType::Binary,
+        "DATE" | "TIMESTAMP" | "TIMESTAMP WITH TIME ZONE" | "TIMESTAMP WITH LOCAL TIME ZONE" => {
+            FieldType::Timestamp
+        }
+        "INTERVAL DAY TO SECOND" => FieldType::Duration,
+        "JSON" => FieldType::Json,
+        _ => return None,
+    };
+    Some(typ)
+}
+
+/// `TIMESTAMP(6) WITH TIME ZONE` becomes `TIMESTAMP WITH TIME ZONE`.
+fn remove_precision(data_type: &str) -> String {
+    let mut result = String::with_capacity(data_type.len());
+    let mut depth = 0;
+    for c in data_type.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if depth == 0 => result.push(c),
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Reads a column of a snapshot row.
+pub fn field_from_row(
+    row: &Row,
+    index: usize,
+    typ: FieldType,
+) -> Result<Field, OracleConnectorError> {
+    let field = match typ {
+        FieldType::Int => row.get::<_, Option<i64>>(index)?.map(Field::Int),
+        FieldType::Float => row
+            .get::<_, Option<f64>>(index)?
+            .map(|value| Field::Float(OrderedFloat(value))),
+        FieldType::Binary => row.get::<_, Option<Vec<u8>>>(index)?.map(Field::Binary),
+        FieldType::Timestamp => row
+            .get::<_, Option<DateTime<FixedOffset>>>(index)?
+            .map(Field::Timestamp),
+        FieldType::Duration => row
+            .get::<_, Option<IntervalDS>>(index)?
+            .map(|interval| interval_to_field(&interval))
+            .transpose()?,
+        // Numbers are read as text so no precision is lost.
+        _ => row
+            .get::<_, Option<String>>(index)?
+            .map(|text| parse_text(&text, typ))
+            .transpose()?,
+    };
+    Ok(field.unwrap_or(Field::Null))
+}
+
+fn interval_to_field(interval: &IntervalDS) -> Result<Field, OracleConnectorError> {
+    let parts = [
+        interval.days(),
+        interval.hours(),
+        interval.minutes(),
+        interval.seconds(),
+        interval.nanoseconds(),
+    ];
+    if parts.iter().any(|part| *part < 0) {
+        return Err(OracleConnectorError::InvalidValue {
+            typ: FieldType::Duration,
+            value: interval.to_string(),
+        });
+    }
+    let [days, hours, minutes, seconds, nanoseconds] = parts.map(|part| part as u64);
+    let duration = Duration::new(
+        days * 86400 + hours * 3600 + minutes * 60 + seconds,
+        nanoseconds as u32,
+    );
+    Ok(Field::Duration(DozerDuration(
+        duration,
+        TimeUnit::Nanoseconds,
+    )))
+}
+
+/// Converts a value of a redo statement.
+pub fn field_from_redo(value: &Value, typ: FieldType) -> Result<Field, OracleConnectorError> {
+    match value {
+        Value::Null => Ok(Field::Null),
+        Value::Literal(text) => parse_text(text, typ),
+        Value::Function(name, arguments) => match (name.as_str(), arguments.as_slice()) {
+            (
+                "HEXTORAW" | "TO_DATE" | "TO_TIMESTAMP" | "TO_TIMESTAMP_TZ" | "TO_DSINTERVAL"
+                | "TO_NUMBER" | "TO_BINARY_FLOAT" | "TO_BINARY_DOUBLE",
+                [value, ..],
+            ) => field_from_redo(value, typ),
+            // LogMiner logs the content of LOBs separately from the row, so it's not replicated.
+            ("EMPTY_CLOB" | "EMPTY_BLOB", []) => parse_text("", typ),
+            _ => Err(OracleConnectorError::UnsupportedRedoFunction(name.clone())),
+        },
+    }
+}
+
+/// Parses a value in the text format of the session, see `connection::SESSION_SETUP`.
+pub fn parse_text(text: &str, typ: FieldType) -> Result<Field, OracleConnectorError> {
+    let invalid = || OracleConnectorError::InvalidValue {
+        typ,
+        value: text.to_string(),
+    };
+    let field = match typ {
+        FieldType::Int => Field::Int(text.parse().map_err(|_| invalid())?),
+        FieldType::I128 => Field::I128(text.parse().map_err(|_| invalid())?),
+        FieldType::Decimal => Field::Decimal(
+            Decimal::from_str_exact(text)
+                .or_else(|_| Decimal::from_scientific(text))
+                .map_err(|_| invalid())?,
+        ),
+        FieldType::Float => Field::Float(OrderedFloat(text.parse().map_err(|_| invalid())?)),
+        FieldType::String => Field::String(text.to_string()),
+        FieldType::Text => Field::Text(text.to_string()),
+        FieldType::Binary => Field::Binary(decode_hex(text).ok_or_else(invalid)?),
+        FieldType::Timestamp => Field::Timestamp(parse_timestamp(text).ok_or_else(invalid)?),
+        FieldType::Duration => Field::Duration(DozerDuration(
+            parse_interval(text).ok_or_else(invalid)?,
+            TimeUnit::Nanoseconds,
+        )),
+        FieldType::Json => Field::Json(json_from_str(text).map_err(|_| invalid())?),
+        FieldType::UInt
+        | FieldType::U128
+        | FieldType::Int8
+        | FieldType::Boolean
+        | FieldType::Date
+        | FieldType::Point => return Err(invalid()),
+    };
+    Ok(field)
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+/// Timestamps without time zone are taken as UTC.
+fn parse_timestamp(text: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_str(text, TIMESTAMP_TZ_FORMAT)
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(text, TIMESTAMP_FORMAT)
+                .ok()
+                .map(|timestamp| DateTime::from_naive_utc_and_offset(timestamp, Utc.fix()))
+        })
+}
+
+/// Parses an `INTERVAL DAY TO SECOND` like `+01 02:03:04.500000`. Negative intervals are not supported.
+fn parse_interval(text: &str) -> Option<Duration> {
+    let text = text.strip_prefix('+').unwrap_or(text);
+    let (days, time) = text.split_once(' ')?;
+    let mut parts = time.splitn(3, ':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds = parts.next()?;
+    let (seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+    if fraction.len() > 9 {
+        return None;
+    }
+    let seconds = days.parse::<u64>().ok()? * 86400
+        + hours * 3600
+        + minutes * 60
+        + seconds.parse::<u64>().ok()?;
+    let nanoseconds = format!("{fraction:0<9}").parse().ok()?;
+    Some(Duration::new(seconds, nanoseconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_type() {
+        assert_eq!(map_type("NUMBER", Some(10), Some(0)), Some(FieldType::Int));
+        assert_eq!(map_type("NUMBER", None, Some(0)), Some(FieldType::I128));
+        assert_eq!(
+            map_type("NUMBER", Some(10), Some(2)),
+            Some(FieldType::Decimal)
+        );
+        assert_eq!(map_type("NUMBER", None, None), Some(FieldType::Decimal));
+        assert_eq!(
+            map_type("TIMESTAMP(6) WITH TIME ZONE", None, Some(6)),
+            Some(FieldType::Timestamp)
+        );
+        assert_eq!(
+            map_type("INTERVAL DAY(2) TO SECOND(6)", None, Some(6)),
+            Some(FieldType::Duration)
+        );
+        assert_eq!(map_type("SDO_GEOMETRY", None, None), None);
+    }
+
+    #[test]
+    fn test_field_from_redo() {
+        let literal = |text: &str| Value::Literal(text.to_string());
+        let function =
+            |name: &str, text: &str| Value::Function(name.to_string(), vec![literal(text)]);
+
+        assert_eq!(
+            field_from_redo(&literal("42"), FieldType::Int).unwrap(),
+            Field::Int(42)
+        );
+        assert_eq!(
+            field_from_redo(&literal("1.5E+3"), FieldType::Decimal).unwrap(),
+            Field::Decimal(Decimal::new(1500, 0))
+        );
+        assert_eq!(
+            field_from_redo(&function("HEXTORAW", "00ff"), FieldType::Binary).unwrap(),
+            Field::Binary(vec![0, 255])
+        );
+        assert_eq!(
+            field_from_redo(
+                &function("TO_DATE", "2024-01-02 03:04:05"),
+                FieldType::Timestamp
+            )
+            .unwrap(),
+            Field::Timestamp(DateTime::parse_from_rfc3339("2024-01-02T03:04:05Z").unwrap())
+        );
+        assert_eq!(
+            field_from_redo(
+                &function("TO_TIMESTAMP_TZ", "2024-01-02 03:04:05.500000000 +02:00"),
+                FieldType::Timestamp
+            )
+            .unwrap(),
+            Field::Timestamp(DateTime::parse_from_rfc3339("2024-01-02T03:04:05.5+02:00").unwrap())
+        );
+        assert_eq!(
+            field_from_redo(
+                &function("TO_DSINTERVAL", "+01 02:03:04.5"),
+                FieldType::Duration
+            )
+            .unwrap(),
+            Field::Duration(DozerDuration(
+                Duration::new(93784, 500_000_000),
+                TimeUnit::Nanoseconds
+            ))
+        );
+        assert_eq!(
+            field_from_redo(&literal("{\"a\": 1}"), FieldType::Json).unwrap(),
+            Field::Json(json_from_str("{\"a\": 1}").unwrap())
+        );
+        assert_eq!(
+            field_from_redo(
+                &Value::Function("EMPTY_CLOB".to_string(), vec![]),
+                FieldType::Text
+            )
+            .unwrap(),
+            Field::Text(String::new())
+        );
+        assert_eq!(
+            field_from_redo(&Value::Null, FieldType::String).unwrap(),
+            Field::Null
+        );
+        assert!(field_from_redo(&literal("abc"), FieldType::Int).is_err());
+        assert!(field_from_redo(
+            &function("TO_DSINTERVAL", "-01 00:00:00"),
+            FieldType::Duration
+        )
+        .is_err());
+        assert!(field_from_redo(&function("SYS_GUID", ""), FieldType::String).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+use dozer_ingestion_connector::{
+    dozer_types::{
+        thiserror::{self, Error},
+        types::FieldType,
+    },
+    tokio::task::JoinError,
+    utils::TableNotFound,
+};
+
+mod connection;
+pub mod connector;
+mod conversion;
+mod logminer;
+mod redo;
+mod schema;
+mod snapshot;
+
+#[derive(Error, Debug)]
+pub enum OracleConnectorError {
+    #[error("Oracle error: {0}")]
+    Oracle(#[from] oracle::Error),
+
+    #[error(transparent)]
+    TableNotFound(#[from] TableNotFound),
+
+    #[error("Column {column} not found in table {table}")]
+    ColumnNotFound { table: String, column: String },
+
+    #[error("The DozerLogReader replicator is not supported, use LogMiner")]
+    UnsupportedReplicator,
+
+    #[error("Column {column} of table {table} has unsupported type {data_type}")]
+    UnsupportedColumnType {
+        table: String,
+        column: String,
+        data_type: String,
+    },
+
+    #[error("Invalid {typ} value {value:?}")]
+    InvalidValue { typ: FieldType, value: String },
+
+    #[error("Failed to parse redo SQL {sql:?}: {reason}")]
+    ParseRedo { sql: String, reason: String },
+
+    #[error("Unsupported function {0} in redo SQL")]
+    UnsupportedRedoFunction(String),
+
+    #[error("Minimal supplemental logging is not enabled. Run `ALTER DATABASE ADD SUPPLEMENTAL LOG DATA`")]
+    MinimalSupplementalLoggingDisabled,
+
+    #[error("Supplemental logging of all columns is not enabled for table {0}. Run `ALTER TABLE {0} ADD SUPPLEMENTAL LOG DATA (ALL) COLUMNS`")]
+    AllColumnsSupplementalLoggingDisabled(String),
+
+    #[error("Replication task failed: {0}")]
+    Join(#[from] JoinError),
+}
@@ -0,0 +1,462 @@
+//! Reads committed changes of the replicated tables with LogMiner.
+//!
+//! Changes are mined from the redo logs as they are written, including those of uncommitted transactions, and
+//! buffered per transaction until its commit. Each operation is identified by the commit SCN of its transaction and
+//! its index among the operations committed at that SCN.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use dozer_ingestion_connector::{
+    dozer_types::{
+        log::{debug, warn},
+        models::ingestion_types::{IngestionMessage, TransactionInfo},
+        node::OpIdentifier,
+        types::{Field, Operation, Record},
+    },
+    Ingestor,
+};
+use oracle::Connection;
+
+use crate::{
+    connection::{current_scn, quote_literal},
+    conversion::field_from_redo,
+    redo::{self, Columns, Redo},
+    schema::ReplicatedTable,
+    snapshot::send,
+    OracleConnectorError,
+};
+
+const OPERATION_INSERT: u32 = 1;
+const OPERATION_DELETE: u32 = 2;
+const OPERATION_UPDATE: u32 = 3;
+const OPERATION_COMMIT: u32 = 7;
+const OPERATION_ROLLBACK: u32 = 36;
+
+/// A position in the stream of committed operations.
+///
+/// Encoded in an `OpIdentifier` as the commit SCN, followed by the index and the lookback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub commit_scn: u64,
+    /// The index of the operation among the operations committed at `commit_scn`.
+    pub index: u32,
+    /// How far before `commit_scn` mining must start to see all changes of the transactions that were open at the
+    /// time of the commit.
+    pub lookback: u32,
+}
+
+impl Position {
+    /// The position after all commits at or before `scn`.
+    pub fn snapshot(scn: u64, oldest_transaction_scn: Option<u64>) -> Self {
+        Self {
+            commit_scn: scn,
+            index: u32::MAX,
+            lookback: lookback(scn, oldest_transaction_scn.unwrap_or(scn)),
+        }
+    }
+
+    /// The SCN mining starts from to resume after this position.
+    pub fn start_scn(&self) -> u64 {
+        self.commit_scn.saturating_sub(self.lookback as u64)
+    }
+
+    pub fn is_after(&self, other: &Position) -> bool {
+        (self.commit_scn, self.index) > (other.commit_scn, other.index)
+    }
+}
+
+impl From<Position> for OpIdentifier {
+    fn from(position: Position) -> Self {
+        OpIdentifier::new(
+            position.commit_scn,
+            (position.index as u64) << 32 | position.lookback as u64,
+        )
+    }
+}
+
+impl From<OpIdentifier> for Position {
+    fn from(id: OpIdentifier) -> Self {
+        Self {
+            commit_scn: id.txid,
+            index: (id.seq_in_tx >> 32) as u32,
+            lookback: id.seq_in_tx as u32,
+        }
+    }
+}
+
+fn lookback(commit_scn: u64, start_scn: u64) -> u32 {
+    u32::try_from(commit_scn.saturating_sub(start_scn)).unwrap_or_else(|_| {
+        warn!(
+            "Transaction started at SCN {start_scn} is too old to be resumed from SCN {commit_scn}"
+        );
+        u32::MAX
+    })
+}
+
+#[derive(Debug)]
+struct BufferedOperation {
+    table_index: usize,
+    row_id: Option<String>,
+    op: Operation,
+}
+
+#[derive(Debug)]
+struct Transaction {
+    /// The SCN of the first change to a replicated table.
+    start_scn: u64,
+    operations: Vec<BufferedOperation>,
+}
+
+#[derive(Debug)]
+struct LogMinerRow {
+    scn: u64,
+    xid: String,
+    operation_code: u32,
+    owner: Option<String>,
+    table: Option<String>,
+    row_id: Option<String>,
+    rollback: bool,
+    sql_redo: String,
+}
+
+#[derive(Debug)]
+pub struct LogMiner<'a> {
+    /// A connection to the root container.
+    connection: &'a Connection,
+    ingestor: &'a Ingestor,
+    pdb: Option<&'a str>,
+    tables: &'a [ReplicatedTable],
+    transactions: HashMap<String, Transaction>,
+    /// Operations at or before this position have been sent.
+    checkpoint: Position,
+    /// The last commit SCN and the index of its next operation.
+    next_index: (u64, u32),
+}
+
+impl<'a> LogMiner<'a> {
+    pub fn new(
+        connection: &'a Connection,
+        ingestor: &'a Ingestor,
+        pdb: Option<&'a str>,
+        tables: &'a [ReplicatedTable],
+        checkpoint: Position,
+    ) -> Self {
+        Self {
+            connection,
+            ingestor,
+            pdb,
+            tables,
+            transactions: HashMap::new(),
+            checkpoint,
+            next_index: (checkpoint.commit_scn, 0),
+        }
+    }
+
+    /// Mines from `start_scn` until the ingestor is closed.
+    pub fn run(
+        mut self,
+        start_scn: u64,
+        poll_interval: Duration,
+    ) -> Result<(), OracleConnectorError> {
+        let mut from = start_scn;
+        let mut observed_scn = current_scn(self.connection)?;
+        loop {
+            std::thread::sleep(poll_interval);
+            // Changes of uncommitted transactions may still be in the log buffer at the current SCN,
+            // so mining stays one poll behind, by when LGWR has written them.
+            let to = observed_scn;
+            observed_scn = current_scn(self.connection)?;
+            if to < from {
+                continue;
+            }
+            debug!("Mining SCN {from} to {to}");
+            if !self.mine(from, to)? {
+                return Ok(());
+            }
+            from = to + 1;
+        }
+    }
+
+    /// Mines the changes between `from` and `to` inclusive. Returns `false` if the ingestor is closed.
+    fn mine(&mut self, from: u64, to: u64) -> Result<bool, OracleConnectorError> {
+        self.add_log_files(from, to)?;
+        self.connection.execute(
+            "BEGIN DBMS_LOGMNR.START_LOGMNR(STARTSCN => :1, ENDSCN => :2, \
+            OPTIONS => DBMS_LOGMNR.DICT_FROM_ONLINE_CATALOG); END;",
+            &[&from, &to],
+        )?;
+        let result = self.read_contents();
+        self.connection
+            .execute("BEGIN DBMS_LOGMNR.END_LOGMNR; END;", &[])?;
+        result
+    }
+
+    /// Adds the online and archived logs that contain changes between `from` and `to`.
+    fn add_log_files(&self, from: u64, to: u64) -> Result<(), OracleConnectorError> {
+        let files = self
+            .connection
+            .query_as_named::<String>(
+                "SELECT MIN(f.MEMBER) FROM V$LOG l JOIN V$LOGFILE f ON l.GROUP# = f.GROUP# \
+                WHERE l.NEXT_CHANGE# > :from_scn AND l.FIRST_CHANGE# <= :to_scn \
+                GROUP BY l.THREAD#, l.SEQUENCE# \
+                UNION ALL \
+                SELECT MIN(a.NAME) FROM V$ARCHIVED_LOG a \
+                WHERE a.NEXT_CHANGE# > :from_scn AND a.FIRST_CHANGE# <= :to_scn \
+                AND a.STATUS = 'A' AND a.NAME IS NOT NULL \
+                AND (a.THREAD#, a.SEQUENCE#) NOT IN (SELECT THREAD#, SEQUENCE# FROM V$LOG) \
+                GROUP BY a.THREAD#, a.SEQUENCE#",
+                &[("from_scn", &from), ("to_scn", &to)],
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (index, file) in files.iter().enumerate() {
+            let option = if index == 0 { "NEW" } else { "ADDFILE" };
+            self.connection.execute(
+                &format!(
+                    "BEGIN DBMS_LOGMNR.ADD_LOGFILE(LOGFILENAME => :1, OPTIONS => DBMS_LOGMNR.{option}); END;"
+                ),
+                &[file],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn contents_query(&self) -> String {
+        let mut owners = self
+            .tables
+            .iter()
+            .map(|table| quote_literal(&table.owner))
+            .collect::<Vec<_>>();
+        owners.sort();
+        owners.dedup();
+        let mut dml_filter = format!(
+            "OPERATION_CODE IN ({OPERATION_INSERT}, {OPERATION_DELETE}, {OPERATION_UPDATE}) AND SEG_OWNER IN ({})",
+            owners.join(", ")
+        );
+        if let Some(pdb) = self.pdb {
+            dml_filter.push_str(&format!(
+                " AND SRC_CON_NAME = {}",
+                quote_literal(&pdb.to_uppercase())
+            ));
+        }
+        format!(
+            "SELECT SCN, RAWTOHEX(XID), OPERATION_CODE, SEG_OWNER, TABLE_NAME, ROW_ID, ROLLBACK, CSF, SQL_REDO \
+            FROM V$LOGMNR_CONTENTS \
+            WHERE OPERATION_CODE IN ({OPERATION_COMMIT}, {OPERATION_ROLLBACK}) OR ({dml_filter})"
+        )
+    }
+
+    fn read_contents(&mut self) -> Result<bool, OracleConnectorError> {
+        // Copied out of `self`, so the rows don't borrow `self`.
+        let connection = self.connection;
+        let rows = connection.query_as::<(
+            u64,
+            String,
+            u32,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            u32,
+            u32,
+            Option<String>,
+        )>(&self.contents_query(), &[])?;
+
+        // A statement longer than 4000 bytes spans rows, all but the last with `CSF` set.
+        let mut sql_redo = String::new();
+        for row in rows {
+            let (scn, xid, operation_code, owner, table, row_id, rollback, csf, sql) = row?;
+            sql_redo.push_str(sql.as_deref().unwrap_or_default());
+            if csf == 1 {
+                continue;
+            }
+            let row = LogMinerRow {
+                scn,
+                xid,
+                operation_code,
+                owner,
+                table,
+                row_id,
+                rollback: rollback == 1,
+                sql_redo: std::mem::take(&mut sql_redo),
+            };
+            if !self.process(row)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn process(&mut self, row: LogMinerRow) -> Result<bool, OracleConnectorError> {
+        match row.operation_code {
+            OPERATION_COMMIT => return self.commit(&row.xid, row.scn),
+            OPERATION_ROLLBACK => {
+                self.transactions.remove(&row.xid);
+            }
+            _ => self.change(row)?,
+        }
+        Ok(true)
+    }
+
+    fn change(&mut self, row: LogMinerRow) -> Result<(), OracleConnectorError> {
+        let Some(table_index) = self.tables.iter().position(|table| {
+            Some(&table.owner) == row.owner.as_ref() && Some(&table.name) == row.table.as_ref()
+        }) else {
+            return Ok(());
+        };
+
+        // A statement rolled back to a savepoint is undone by a row with `ROLLBACK` set.
+        if row.rollback {
+            if let Some(transaction) = self.transactions.get_mut(&row.xid) {
+                if let Some(index) = transaction
+                    .operations
+                    .iter()
+                    .rposition(|operation| operation.row_id == row.row_id)
+                {
+                    transaction.operations.remove(index);
+                }
+            }
+            return Ok(());
+        }
+
+        let op = self.operation(&self.tables[table_index], &row.sql_redo)?;
+        self.transactions
+            .entry(row.xid)
+            .or_insert_with(|| Transaction {
+                start_scn: row.scn,
+                operations: vec![],
+            })
+            .operations
+            .push(BufferedOperation {
+                table_index,
+                row_id: row.row_id,
+                op,
+            });
+        Ok(())
+    }
+
+    fn operation(
+        &self,
+        table: &ReplicatedTable,
+        sql_redo: &str,
+    ) -> Result<Operation, OracleConnectorError> {
+        let redo = redo::parse(sql_redo).map_err(|reason| OracleConnectorError::ParseRedo {
+            sql: sql_redo.to_string(),
+            reason,
+        })?;
+        let nulls = vec![Field::Null; table.columns.len()];
+        let op = match redo {
+            Redo::Insert { new } => Operation::Insert {
+                new: Record::new(fields(table, nulls, &new)?),
+            },
+            Redo::Update { set, old } => {
+                let old = fields(table, nulls, &old)?;
+                let new = fields(table, old.clone(), &set)?;
+                Operation::Update {
+                    old: Record::new(old),
+                    new: Record::new(new),
+                }
+            }
+            Redo::Delete { old } => Operation::Delete {
+                old: Record::new(fields(table, nulls, &old)?),
+            },
+        };
+        Ok(op)
+    }
+
+    fn commit(&mut self, xid: &str, commit_scn: u64) -> Result<bool, OracleConnectorError> {
+        let Some(transaction) = self.transactions.remove(xid) else {
+            return Ok(true);
+        };
+        let start_scn = self
+            .transactions
+            .values()
+            .map(|transaction| transaction.start_scn)
+            .fold(transaction.start_scn, u64::min);
+        let lookback = lookback(commit_scn, start_scn);
+        if self.next_index.0 != commit_scn {
+            self.next_index = (commit_scn, 0);
+        }
+
+        let mut last_position = None;
+        for BufferedOperation {
+            table_index, op, ..
+        } in transaction.operations
+        {
+            let position = Position {
+                commit_scn,
+                index: self.next_index.1,
+                lookback,
+            };
+            self.next_index.1 += 1;
+            if !position.is_after(&self.checkpoint) {
+                continue;
+            }
+            let message = IngestionMessage::OperationEvent {
+                table_index,
+                op,
+                id: Some(position.into()),
+            };
+            if !send(self.ingestor, message) {
+                return Ok(false);
+            }
+            last_position = Some(position);
+        }
+
+        let Some(position) = last_position else {
+            return Ok(true);
+        };
+        self.checkpoint = position;
+        Ok(send(
+            self.ingestor,
+            IngestionMessage::TransactionInfo(TransactionInfo::Commit {
+                id: Some(position.into()),
+                source_time: None,
+            }),
+        ))
+    }
+}
+
+/// Sets the replicated columns among `columns` on `fields`. Other columns are ignored.
+fn fields(
+    table: &ReplicatedTable,
+    mut fields: Vec<Field>,
+    columns: &Columns,
+) -> Result<Vec<Field>, OracleConnectorError> {
+    for (column, value) in columns {
+        if let Some(index) = table.column_index(column) {
+            fields[index] = field_from_redo(value, table.columns[index].1)?;
+        }
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position() {
+        let position = Position {
+            commit_scn: 1000,
+            index: 3,
+            lookback: 100,
+        };
+        assert_eq!(Position::from(OpIdentifier::from(position)), position);
+        assert_eq!(position.start_scn(), 900);
+
+        let snapshot = Position::snapshot(1000, Some(950));
+        assert_eq!(Position::from(OpIdentifier::from(snapshot)), snapshot);
+        assert_eq!(snapshot.start_scn(), 950);
+        assert!(snapshot.is_after(&position));
+        assert!(!position.is_after(&snapshot));
+
+        // Op ids are ordered like positions, whatever the lookback.
+        let next = Position {
+            commit_scn: 1000,
+            index: 4,
+            lookback: 0,
+        };
+        assert!(next.is_after(&position));
+        assert!(OpIdentifier::from(next) > OpIdentifier::from(position));
+    }
+}
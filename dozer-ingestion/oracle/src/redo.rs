@@ -0,0 +1,379 @@
+//! Parser of the `SQL_REDO` column of `V$LOGMNR_CONTENTS`.
+//!
+//! LogMiner reconstructs every change as a statement on a single row, like:
+//!
+//! ```text
+//! insert into "HR"."EMPLOYEES"("ID","NAME") values ('1','John');
+//! update "HR"."EMPLOYEES" set "NAME" = 'Jane' where "ID" = '1' and "NAME" = 'John' and ROWID = 'AAAR3sAAEAAAACXAAA';
+//! delete from "HR"."EMPLOYEES" where "ID" = '1' and "NAME" = 'Jane' and ROWID = 'AAAR3sAAEAAAACXAAA';
+//! ```
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    /// A string or number literal, without quotes.
+    Literal(String),
+    /// A function call, like `TO_DATE('2024-01-01 00:00:00', 'YYYY-MM-DD HH24:MI:SS')`. The name is upper case.
+    Function(String, Vec<Value>),
+}
+
+/// Column names and their values.
+pub type Columns = Vec<(String, Value)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Redo {
+    Insert {
+        new: Columns,
+    },
+    /// `old` holds the columns of the `WHERE` clause, which is every column with supplemental logging of all columns.
+    /// `set` only holds the changed columns.
+    Update {
+        set: Columns,
+        old: Columns,
+    },
+    Delete {
+        old: Columns,
+    },
+}
+
+pub fn parse(sql: &str) -> Result<Redo, String> {
+    let mut parser = Parser {
+        tokens: tokenize(sql)?,
+        position: 0,
+    };
+    let redo = match parser.next() {
+        Some(Token::Word(word)) if word.eq_ignore_ascii_case("insert") => {
+            parser.keyword("into")?;
+            parser.table()?;
+            parser.symbol('(')?;
+            let columns = parser.list(Parser::identifier)?;
+            parser.keyword("values")?;
+            parser.symbol('(')?;
+            let values = parser.list(Parser::value)?;
+            if columns.len() != values.len() {
+                return Err(format!(
+                    "{} columns but {} values",
+                    columns.len(),
+                    values.len()
+                ));
+            }
+            Redo::Insert {
+                new: columns.into_iter().zip(values).collect(),
+            }
+        }
+        Some(Token::Word(word)) if word.eq_ignore_ascii_case("update") => {
+            parser.table()?;
+            parser.keyword("set")?;
+            let set = parser.assignments()?;
+            let old = if parser.is_keyword("where") {
+                parser.next();
+                parser.conditions()?
+            } else {
+                vec![]
+            };
+            Redo::Update { set, old }
+        }
+        Some(Token::Word(word)) if word.eq_ignore_ascii_case("delete") => {
+            parser.keyword("from")?;
+            parser.table()?;
+            parser.keyword("where")?;
+            Redo::Delete {
+                old: parser.conditions()?,
+            }
+        }
+        _ => return Err("not an insert, update or delete".to_string()),
+    };
+    if parser.peek() == Some(&Token::Symbol(';')) {
+        parser.next();
+    }
+    match parser.next() {
+        None => Ok(redo),
+        Some(token) => Err(format!("unexpected {token:?}")),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A quoted identifier.
+    Identifier(String),
+    /// A keyword, function name or number.
+    Word(String),
+    String(String),
+    Symbol(char),
+}
+
+fn tokenize(sql: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '"' => Token::Identifier(quoted(&mut chars, '"')?),
+            '\'' => Token::String(quoted(&mut chars, '\'')?),
+            '(' | ')' | ',' | '=' | '.' | ';' => Token::Symbol(c),
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut number = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    let exponent_sign = matches!(c, '+' | '-') && number.ends_with(['e', 'E']);
+                    if !(c.is_ascii_alphanumeric() || c == '.' || exponent_sign) {
+                        break;
+                    }
+                    number.push(c);
+                    chars.next();
+                }
+                Token::Word(number)
+            }
+            c if c.is_alphabetic() => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || matches!(c, '_' | '$' | '#')) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                Token::Word(word)
+            }
+            c => return Err(format!("unexpected character {c:?}")),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Reads up to the closing `quote`. A doubled quote stands for the quote itself.
+fn quoted(chars: &mut Peekable<Chars>, quote: char) -> Result<String, String> {
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            Some(c) if c == quote => {
+                if chars.peek() == Some(&quote) {
+                    text.push(quote);
+                    chars.next();
+                } else {
+                    return Ok(text);
+                }
+            }
+            Some(c) => text.push(c),
+            None => return Err(format!("unterminated {quote}")),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => Ok(()),
+            token => Err(format!("expected {keyword}, got {token:?}")),
+        }
+    }
+
+    fn symbol(&mut self, symbol: char) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Symbol(c)) if c == symbol => Ok(()),
+            token => Err(format!("expected {symbol}, got {token:?}")),
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Identifier(identifier)) => Ok(identifier),
+            token => Err(format!("expected identifier, got {token:?}")),
+        }
+    }
+
+    /// The table name, which is known from the other columns of `V$LOGMNR_CONTENTS`.
+    fn table(&mut self) -> Result<(), String> {
+        self.identifier()?;
+        if self.peek() == Some(&Token::Symbol('.')) {
+            self.next();
+            self.identifier()?;
+        }
+        Ok(())
+    }
+
+    /// Items separated by commas, up to the closing parenthesis.
+    fn list<T>(&mut self, item: fn(&mut Self) -> Result<T, String>) -> Result<Vec<T>, String> {
+        let mut items = vec![];
+        loop {
+            items.push(item(self)?);
+            match self.next() {
+                Some(Token::Symbol(',')) => continue,
+                Some(Token::Symbol(')')) => return Ok(items),
+                token => return Err(format!("expected , or ), got {token:?}")),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.next() {
+            Some(Token::String(string)) => Ok(Value::Literal(string)),
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("null") => Ok(Value::Null),
+            Some(Token::Word(word)) if self.peek() == Some(&Token::Symbol('(')) => {
+                self.next();
+                let arguments = if self.peek() == Some(&Token::Symbol(')')) {
+                    self.next();
+                    vec![]
+                } else {
+                    self.list(Self::value)?
+                };
+                Ok(Value::Function(word.to_uppercase(), arguments))
+            }
+            Some(Token::Word(word))
+                if word.starts_with(|c: char| c.is_ascii_digit() || c == '-') =>
+            {
+                Ok(Value::Literal(word))
+            }
+            token => Err(format!("expected value, got {token:?}")),
+        }
+    }
+
+    fn assignments(&mut self) -> Result<Columns, String> {
+        let mut columns = vec![];
+        loop {
+            let column = self.identifier()?;
+            self.symbol('=')?;
+            columns.push((column, self.value()?));
+            if self.peek() != Some(&Token::Symbol(',')) {
+                return Ok(columns);
+            }
+            self.next();
+        }
+    }
+
+    fn conditions(&mut self) -> Result<Columns, String> {
+        let mut columns = vec![];
+        loop {
+            match self.next() {
+                Some(Token::Identifier(column)) => {
+                    if self.is_keyword("is") {
+                        self.next();
+                        self.keyword("null")?;
+                        columns.push((column, Value::Null));
+                    } else {
+                        self.symbol('=')?;
+                        columns.push((column, self.value()?));
+                    }
+                }
+                Some(Token::Word(word)) if word.eq_ignore_ascii_case("rowid") => {
+                    self.symbol('=')?;
+                    self.value()?;
+                }
+                token => return Err(format!("expected condition, got {token:?}")),
+            }
+            if !self.is_keyword("and") {
+                return Ok(columns);
+            }
+            self.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(value: &str) -> Value {
+        Value::Literal(value.to_string())
+    }
+
+    #[test]
+    fn test_parse_insert() {
+        let redo = parse(
+            r#"insert into "HR"."EMPLOYEES"("ID","NAME","HIRED","PHOTO","NOTE") values ('1','O''Brien',TO_DATE('2024-01-02 03:04:05', 'YYYY-MM-DD HH24:MI:SS'),HEXTORAW('00ff'),NULL);"#,
+        )
+        .unwrap();
+        assert_eq!(
+            redo,
+            Redo::Insert {
+                new: vec![
+                    ("ID".to_string(), literal("1")),
+                    ("NAME".to_string(), literal("O'Brien")),
+                    (
+                        "HIRED".to_string(),
+                        Value::Function(
+                            "TO_DATE".to_string(),
+                            vec![
+                                literal("2024-01-02 03:04:05"),
+                                literal("YYYY-MM-DD HH24:MI:SS")
+                            ]
+                        )
+                    ),
+                    (
+                        "PHOTO".to_string(),
+                        Value::Function("HEXTORAW".to_string(), vec![literal("00ff")])
+                    ),
+                    ("NOTE".to_string(), Value::Null),
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_update_and_delete() {
+        let redo = parse(
+            r#"update "HR"."EMPLOYEES" set "NAME" = 'Jane', "SALARY" = -1.5E+3 where "ID" = '1' and "NAME" = 'John' and "SALARY" IS NULL and ROWID = 'AAAR3sAAEAAAACXAAA';"#,
+        )
+        .unwrap();
+        assert_eq!(
+            redo,
+            Redo::Update {
+                set: vec![
+                    ("NAME".to_string(), literal("Jane")),
+                    ("SALARY".to_string(), literal("-1.5E+3")),
+                ],
+                old: vec![
+                    ("ID".to_string(), literal("1")),
+                    ("NAME".to_string(), literal("John")),
+                    ("SALARY".to_string(), Value::Null),
+                ]
+            }
+        );
+
+        let redo = parse(
+            r#"delete from "HR"."EMPLOYEES" where "ID" = '1' and "NOTE" = EMPTY_CLOB() and ROWID = 'AAAR3sAAEAAAACXAAA';"#,
+        )
+        .unwrap();
+        assert_eq!(
+            redo,
+            Redo::Delete {
+                old: vec![
+                    ("ID".to_string(), literal("1")),
+                    (
+                        "NOTE".to_string(),
+                        Value::Function("EMPTY_CLOB".to_string(), vec![])
+                    ),
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("Unsupported").is_err());
+        assert!(parse(r#"insert into "HR"."EMPLOYEES"("ID") values ('1','2');"#).is_err());
+        assert!(parse(r#"delete from "HR"."EMPLOYEES" where "ID" = '1"#).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use dozer_ingestion_connector::{
+    dozer_types::types::{FieldDefinition, FieldType, Schema, SourceDefinition},
+    utils::{warn_dropped_primary_index, TableNotFound},
+    CdcType, SourceSchema, TableIdentifier, TableInfo,
+};
+use oracle::Connection;
+
+use crate::{
+    connection::{quote_identifier, quote_literal},
+    conversion::map_type,
+    OracleConnectorError,
+};
+
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub name: String,
+    pub data_type: String,
+    pub precision: Option<u32>,
+    pub scale: Option<i32>,
+    pub nullable: bool,
+}
+
+/// A table being replicated, with the types of its replicated columns.
+#[derive(Debug, Clone)]
+pub struct ReplicatedTable {
+    pub owner: String,
+    pub name: String,
+    pub columns: Vec<(String, FieldType)>,
+}
+
+impl ReplicatedTable {
+    pub fn new(
+        connection: &Connection,
+        owner: String,
+        table: &TableInfo,
+    ) -> Result<Self, OracleConnectorError> {
+        let columns = table_columns(connection, &owner, &table.name)?;
+        let columns = field_types(&owner, table, &columns)?;
+        Ok(Self {
+            owner,
+            name: table.name.clone(),
+            columns,
+        })
+    }
+
+    pub fn qualified_name(&self) -> String {
+        format!(
+            "{}.{}",
+            quote_identifier(&self.owner),
+            quote_identifier(&self.name)
+        )
+    }
+
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|(column, _)| column == name)
+    }
+}
+
+/// Lists the tables of `schemas`, or of all schemas that are not maintained by Oracle if empty.
+pub fn list_tables(
+    connection: &Connection,
+    schemas: &[String],
+) -> Result<Vec<TableIdentifier>, OracleConnectorError> {
+    let sql = if schemas.is_empty() {
+        "SELECT t.OWNER, t.TABLE_NAME FROM ALL_TABLES t JOIN ALL_USERS u ON t.OWNER = u.USERNAME \
+        WHERE u.ORACLE_MAINTAINED = 'N' ORDER BY t.OWNER, t.TABLE_NAME"
+            .to_string()
+    } else {
+        format!(
+            "SELECT OWNER, TABLE_NAME FROM ALL_TABLES WHERE OWNER IN ({}) ORDER BY OWNER, TABLE_NAME",
+            schemas
+                .iter()
+                .map(|schema| quote_literal(schema))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    let tables = connection
+        .query_as::<(String, String)>(&sql, &[])?
+        .map(|row| row.map(|(owner, name)| TableIdentifier::new(Some(owner), name)))
+        .collect::<Result<_, _>>()?;
+    Ok(tables)
+}
+
+/// The columns of a table in definition order, empty if the table doesn't exist.
+pub fn table_columns(
+    connection: &Connection,
+    owner: &str,
+    table: &str,
+) -> Result<Vec<Column>, OracleConnectorError> {
+    let columns = connection
+        .query_as::<(String, String, Option<u32>, Option<i32>, String)>(
+            "SELECT COLUMN_NAME, DATA_TYPE, DATA_PRECISION, DATA_SCALE, NULLABLE \
+            FROM ALL_TAB_COLUMNS WHERE OWNER = :1 AND TABLE_NAME = :2 ORDER BY COLUMN_ID",
+            &[&owner, &table],
+        )?
+        .map(|row| {
+            row.map(|(name, data_type, precision, scale, nullable)| Column {
+                name,
+                data_type,
+                precision,
+                scale,
+                nullable: nullable == "Y",
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(columns)
+}
+
+fn primary_key(
+    connection: &Connection,
+    owner: &str,
+    table: &str,
+) -> Result<Vec<String>, OracleConnectorError> {
+    let columns = connection
+        .query_as::<String>(
+            "SELECT cc.COLUMN_NAME FROM ALL_CONSTRAINTS c JOIN ALL_CONS_COLUMNS cc \
+            ON c.OWNER = cc.OWNER AND c.CONSTRAINT_NAME = cc.CONSTRAINT_NAME \
+            WHERE c.CONSTRAINT_TYPE = 'P' AND c.OWNER = :1 AND c.TABLE_NAME = :2 \
+            ORDER BY cc.POSITION",
+            &[&owner, &table],
+        )?
+        .collect::<Result<_, _>>()?;
+    Ok(columns)
+}
+
+/// Resolves the requested columns of `table` from all its `columns`.
+fn requested_columns<'a>(
+    owner: &str,
+    table: &TableInfo,
+    columns: &'a [Column],
+) -> Result<Vec<&'a Column>, OracleConnectorError> {
+    if columns.is_empty() {
+        return Err(OracleConnectorError::TableNotFound(TableNotFound {
+            schema: Some(owner.to_string()),
+            name: table.name.clone(),
+        }));
+    }
+    table
+        .column_names
+        .iter()
+        .map(|name| {
+            columns
+                .iter()
+                .find(|column| &column.name == name)
+                .ok_or_else(|| OracleConnectorError::ColumnNotFound {
+                    table: table.name.clone(),
+                    column: name.clone(),
+                })
+        })
+        .collect()
+}
+
+fn field_types(
+    owner: &str,
+    table: &TableInfo,
+    columns: &[Column],
+) -> Result<Vec<(String, FieldType)>, OracleConnectorError> {
+    requested_columns(owner, table, columns)?
+        .into_iter()
+        .map(|column| {
+            let typ =
+                map_type(&column.data_type, column.precision, column.scale).ok_or_else(|| {
+                    OracleConnectorError::UnsupportedColumnType {
+                        table: table.name.clone(),
+                        column: column.name.clone(),
+                        data_type: column.data_type.clone(),
+                    }
+                })?;
+            Ok((column.name.clone(), typ))
+        })
+        .collect()
+}
+
+pub fn source_schema(
+    connection: &Connection,
+    owner: &str,
+    table: &TableInfo,
+) -> Result<SourceSchema, OracleConnectorError> {
+    let columns = table_columns(connection, owner, &table.name)?;
+    let nullable = requested_columns(owner, table, &columns)?
+        .into_iter()
+        .map(|column| column.nullable);
+    let fields = field_types(owner, table, &columns)?
+        .into_iter()
+        .zip(nullable)
+        .map(|((name, typ), nullable)| FieldDefinition {
+            name,
+            typ,
+            nullable,
+            source: SourceDefinition::Dynamic,
+            description: None,
+        })
+        .collect();
+
+    let primary_index = primary_key(connection, owner, &table.name)?
+        .iter()
+        .map(|column| table.column_names.iter().position(|name| name == column))
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_else(|| {
+            warn_dropped_primary_index(&table.name);
+            vec![]
+        });
+
+    Ok(SourceSchema::new(
+        Schema {
+            fields,
+            primary_index,
+        },
+        CdcType::FullChanges,
+    ))
+}
+
+/// LogMiner needs minimal supplemental logging, and supplemental logging of all columns to read whole old records.
+pub fn validate_supplemental_logging(
+    connection: &Connection,
+    tables: &[(String, String)],
+) -> Result<(), OracleConnectorError> {
+    let (minimal, all) = connection.query_row_as::<(String, String)>(
+        "SELECT SUPPLEMENTAL_LOG_DATA_MIN, SUPPLEMENTAL_LOG_DATA_ALL FROM V$DATABASE",
+        &[],
+    )?;
+    if minimal == "NO" {
+        return Err(OracleConnectorError::MinimalSupplementalLoggingDisabled);
+    }
+    if all == "YES" {
+        return Ok(());
+    }
+    for (owner, table) in tables {
+        let count = connection.query_row_as::<u64>(
+            "SELECT COUNT(*) FROM ALL_LOG_GROUPS \
+            WHERE OWNER = :1 AND TABLE_NAME = :2 AND LOG_GROUP_TYPE = 'ALL COLUMN LOGGING'",
+            &[owner, table],
+        )?;
+        if count == 0 {
+            return Err(OracleConnectorError::AllColumnsSupplementalLoggingDisabled(
+                format!("{}.{}", quote_identifier(owner), quote_identifier(table)),
+            ));
+        }
+    }
+    Ok(())
+}
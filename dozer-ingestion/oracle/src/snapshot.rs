@@ -0,0 +1,70 @@
+use dozer_ingestion_connector::{
+    dozer_types::{
+        log::info,
+        models::ingestion_types::IngestionMessage,
+        types::{Operation, Record},
+    },
+    Ingestor,
+};
+use oracle::Connection;
+
+use crate::{
+    connection::quote_identifier, conversion::field_from_row, schema::ReplicatedTable,
+    OracleConnectorError,
+};
+
+/// Sends a message from a blocking thread. Returns `false` if the ingestor is closed, which stops the replication.
+pub fn send(ingestor: &Ingestor, message: IngestionMessage) -> bool {
+    ingestor.blocking_handle_message(message).is_ok()
+}
+
+/// Reads the tables as they were at `scn`, fetching `batch_size` rows per round trip.
+///
+/// Returns `false` if the ingestor is closed.
+pub fn snapshot(
+    connection: &Connection,
+    ingestor: &Ingestor,
+    tables: &[ReplicatedTable],
+    scn: u64,
+    batch_size: usize,
+) -> Result<bool, OracleConnectorError> {
+    for (table_index, table) in tables.iter().enumerate() {
+        let sql = format!(
+            "SELECT {} FROM {} AS OF SCN :1",
+            table
+                .columns
+                .iter()
+                .map(|(name, _)| quote_identifier(name))
+                .collect::<Vec<_>>()
+                .join(", "),
+            table.qualified_name()
+        );
+        let mut statement = connection
+            .statement(&sql)
+            .fetch_array_size(batch_size as u32)
+            .build()?;
+        let mut count = 0;
+        for row in statement.query(&[&scn])? {
+            let row = row?;
+            let values = table
+                .columns
+                .iter()
+                .enumerate()
+                .map(|(index, (_, typ))| field_from_row(&row, index, *typ))
+                .collect::<Result<Vec<_>, _>>()?;
+            let message = IngestionMessage::OperationEvent {
+                table_index,
+                op: Operation::Insert {
+                    new: Record::new(values),
+                },
+                id: None,
+            };
+            if !send(ingestor, message) {
+                return Ok(false);
+            }
+            count += 1;
+        }
+        info!("Snapshotted {count} rows of {}", table.qualified_name());
+    }
+    Ok(true)
+}
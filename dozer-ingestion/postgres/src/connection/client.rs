@@ -99,6 +99,16 @@ impl Client {
         Ok(Box::pin(row_stream))
     }
 
+    /// Runs `query` on this connection, so that it reads in the snapshot of the current transaction.
+    ///
+    /// Unlike [`Client::query_raw`], the query is not resumed on a new connection after a network failure.
+    pub async fn query_raw_in_transaction(
+        &self,
+        query: &str,
+    ) -> Result<tokio_postgres::RowStream, tokio_postgres::Error> {
+        self.inner.query_raw(query, Vec::<String>::new()).await
+    }
+
     pub async fn copy_both_simple<T>(
         &mut self,
         query: &str,
@@ -15,6 +15,7 @@ use crate::{
     connection::validator::validate_connection,
     iterator::PostgresIterator,
     schema::helper::{SchemaHelper, DEFAULT_SCHEMA_NAME},
+    state::{ConnectorState, SnapshotProgress},
     PostgresConnectorError,
 };
 
@@ -27,6 +28,8 @@ pub struct PostgresConfig {
     pub config: Config,
     pub schema: Option<String>,
     pub batch_size: usize,
+    pub snapshot_chunk_size: u64,
+    pub snapshot_parallelism: usize,
 }
 
 #[derive(Debug)]
@@ -38,6 +41,9 @@ pub struct PostgresConnector {
     schema_helper: SchemaHelper,
     pub schema: Option<String>,
     batch_size: usize,
+    snapshot_chunk_size: u64,
+    snapshot_parallelism: usize,
+    snapshot_progress: Option<SnapshotProgress>,
 }
 
 #[derive(Debug)]
@@ -61,19 +67,22 @@ impl PostgresConnector {
         // conn_str - replication_conn_config
         // conn_str_plain- conn_config
 
-        let slot_name = state
-            .map(String::from_utf8)
+        let state = state
+            .map(ConnectorState::deserialize)
             .transpose()?
-            .unwrap_or_else(|| get_slot_name(&config.name));
+            .unwrap_or_else(|| ConnectorState::new(get_slot_name(&config.name)));
 
         Ok(PostgresConnector {
             name: config.name,
-            slot_name,
+            slot_name: state.slot_name,
             conn_config: config.config,
             replication_conn_config,
             schema_helper: helper,
             schema: config.schema,
             batch_size: config.batch_size,
+            snapshot_chunk_size: config.snapshot_chunk_size,
+            snapshot_parallelism: config.snapshot_parallelism,
+            snapshot_progress: state.snapshot,
         })
     }
 }
@@ -164,7 +173,11 @@ impl Connector for PostgresConnector {
     }
 
     async fn serialize_state(&self) -> Result<Vec<u8>, BoxedError> {
-        Ok(self.slot_name.as_bytes().to_vec())
+        Ok(ConnectorState {
+            slot_name: self.slot_name.clone(),
+            snapshot: self.snapshot_progress.clone(),
+        }
+        .serialize())
     }
 
     async fn start(
@@ -175,15 +188,10 @@ impl Connector for PostgresConnector {
     ) -> Result<(), BoxedError> {
         let lsn = last_checkpoint.map(|checkpoint| checkpoint.txid.into());
 
-        if lsn.is_none() {
-            let client = helper::connect(self.replication_conn_config.clone()).await?;
-            let table_identifiers = tables
-                .iter()
-                .map(|table| TableIdentifier::new(table.schema.clone(), table.name.clone()))
-                .collect::<Vec<_>>();
-            create_publication(client, &self.name, Some(&table_identifiers)).await?;
-        }
-
+        let table_identifiers = tables
+            .iter()
+            .map(|table| TableIdentifier::new(table.schema.clone(), table.name.clone()))
+            .collect::<Vec<_>>();
         let tables = tables
             .into_iter()
             .map(|table| ListOrFilterColumns {
@@ -192,16 +200,32 @@ impl Connector for PostgresConnector {
                 columns: Some(table.column_names),
             })
             .collect::<Vec<_>>();
+        let tables = self.schema_helper.get_tables(Some(&tables)).await?;
+
+        // A resumed snapshot replicates from the slot of the interrupted one,
+        // which needs the publication it was created with.
+        let resumes_snapshot = self
+            .snapshot_progress
+            .as_ref()
+            .is_some_and(|progress| progress.matches(&tables));
+        if lsn.is_none() && !resumes_snapshot {
+            let client = helper::connect(self.replication_conn_config.clone()).await?;
+            create_publication(client, &self.name, Some(&table_identifiers)).await?;
+        }
+
         let iterator = PostgresIterator::new(
             self.name.clone(),
             get_publication_name(&self.name),
             self.slot_name.clone(),
-            self.schema_helper.get_tables(Some(&tables)).await?,
+            tables,
             self.replication_conn_config.clone(),
             ingestor,
             self.conn_config.clone(),
             self.schema.clone(),
             self.batch_size,
+            self.snapshot_chunk_size,
+            self.snapshot_parallelism,
+            self.snapshot_progress.clone(),
         );
         iterator.start(lsn).await.map_err(Into::into)
     }
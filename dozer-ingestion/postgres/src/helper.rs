@@ -3,16 +3,21 @@ use dozer_ingestion_connector::dozer_types::{
     chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Offset, Utc},
     errors::types::TypeError,
     geo::Point as GeoPoint,
-    json_types::{parse_json_slice, serde_json_to_json_value, JsonArray, JsonValue},
+    json_types::{
+        field_to_json_value, json_from_str, parse_json_slice, serde_json_to_json_value, JsonArray,
+        JsonValue,
+    },
     ordered_float::OrderedFloat,
     rust_decimal, serde_json,
     types::*,
 };
-use postgres_types::{FromSql, Type, WasNull};
+use postgres_types::{FromSql, Kind, Type, WasNull};
 use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::Decimal;
 use std::error::Error;
+use std::iter::Peekable;
 use std::num::ParseIntError;
+use std::str::{Chars, FromStr};
 
 use dozer_ingestion_connector::dozer_types::chrono::{LocalResult, NaiveTime};
 use std::vec;
@@ -172,9 +177,87 @@ pub fn postgres_type_to_field(
                 .parse::<DozerPoint>()
                 .map_err(|_| PostgresSchemaError::PointParseError)?,
         )),
-        _ => Err(PostgresSchemaError::ColumnTypeNotSupported(
-            column_type.name().to_string(),
-        )),
+        _ => match column_type.kind() {
+            Kind::Enum(_) => Ok(Field::String(String::from_utf8(v.to_vec())?)),
+            Kind::Array(element) => Ok(Field::Json(parse_array(
+                &String::from_utf8(v.to_vec())?,
+                element,
+            )?)),
+            _ => Err(PostgresSchemaError::ColumnTypeNotSupported(
+                column_type.name().to_string(),
+            )),
+        },
+    })
+}
+
+/// Parses the text representation of an array, like `{1,2,NULL}` or `{{"a b",c},{d,e}}`.
+fn parse_array(text: &str, element: &Type) -> Result<JsonValue, PostgresSchemaError> {
+    // Arrays whose lower bound isn't 1 are prefixed with their dimensions, like `[0:1]={a,b}`.
+    let array = match text.strip_prefix('[') {
+        Some(_) => text.split_once('=').map_or(text, |(_, array)| array),
+        None => text,
+    };
+    let mut chars = array.chars().peekable();
+    let value = parse_array_items(&mut chars, element)
+        .ok_or_else(|| PostgresSchemaError::ArrayParseError(text.to_string()))?;
+    if chars.next().is_some() {
+        return Err(PostgresSchemaError::ArrayParseError(text.to_string()));
+    }
+    Ok(value)
+}
+
+fn parse_array_items(chars: &mut Peekable<Chars>, element: &Type) -> Option<JsonValue> {
+    if chars.next() != Some('{') {
+        return None;
+    }
+    let mut items = JsonArray::new();
+    if chars.next_if_eq(&'}').is_some() {
+        return Some(items.into());
+    }
+    loop {
+        let item = match chars.peek()? {
+            '{' => parse_array_items(chars, element)?,
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => value.push(chars.next()?),
+                        c => value.push(c),
+                    }
+                }
+                parse_array_element(&value, element)?
+            }
+            _ => {
+                let mut value = String::new();
+                while let Some(c) = chars.next_if(|c| *c != ',' && *c != '}') {
+                    value.push(c);
+                }
+                if value == "NULL" {
+                    JsonValue::NULL
+                } else {
+                    parse_array_element(&value, element)?
+                }
+            }
+        };
+        items.push(item);
+        match chars.next()? {
+            ',' => continue,
+            '}' => return Some(items.into()),
+            _ => return None,
+        }
+    }
+}
+
+fn parse_array_element(text: &str, element: &Type) -> Option<JsonValue> {
+    Some(match element {
+        &Type::BOOL => (text == "t").into(),
+        &Type::INT2 | &Type::INT4 | &Type::INT8 => text.parse::<i64>().ok()?.into(),
+        &Type::FLOAT4 | &Type::FLOAT8 => text.parse::<f64>().ok()?.into(),
+        &Type::NUMERIC => field_to_json_value(Field::Decimal(Decimal::from_str(text).ok()?)),
+        &Type::JSON | &Type::JSONB => json_from_str(text).ok()?,
+        _ => text.into(),
     })
 }
 
@@ -199,9 +282,13 @@ pub fn postgres_type_to_dozer_type(column_type: Type) -> Result<FieldType, Postg
         | Type::BPCHAR_ARRAY => Ok(FieldType::Json),
         Type::DATE => Ok(FieldType::Date),
         Type::POINT => Ok(FieldType::Point),
-        _ => Err(PostgresSchemaError::ColumnTypeNotSupported(
-            column_type.name().to_string(),
-        )),
+        _ => match column_type.kind() {
+            Kind::Enum(_) => Ok(FieldType::String),
+            Kind::Array(_) => Ok(FieldType::Json),
+            _ => Err(PostgresSchemaError::ColumnTypeNotSupported(
+                column_type.name().to_string(),
+            )),
+        },
     }
 }
 
@@ -288,6 +375,79 @@ fn convert_uuid(row: &Row, idx: usize) -> Result<Field, PostgresSchemaError> {
     value.map_or_else(handle_error, |val| Ok(Field::from(val.to_string())))
 }
 
+/// The label of an enum value, which is its binary representation too.
+struct EnumLabel(String);
+
+impl<'a> FromSql<'a> for EnumLabel {
+    fn from_sql(_ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(Self(std::str::from_utf8(raw)?.to_string()))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        matches!(ty.kind(), Kind::Enum(_))
+    }
+}
+
+/// An array element, as JSON.
+struct JsonElement(JsonValue);
+
+impl<'a> FromSql<'a> for JsonElement {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let value = match ty {
+            &Type::BOOL => bool::from_sql(ty, raw)?.into(),
+            &Type::INT2 => i64::from(i16::from_sql(ty, raw)?).into(),
+            &Type::INT4 => i64::from(i32::from_sql(ty, raw)?).into(),
+            &Type::INT8 => i64::from_sql(ty, raw)?.into(),
+            &Type::FLOAT4 => f64::from(f32::from_sql(ty, raw)?).into(),
+            &Type::FLOAT8 => f64::from_sql(ty, raw)?.into(),
+            &Type::NUMERIC => field_to_json_value(Field::Decimal(Decimal::from_sql(ty, raw)?)),
+            &Type::JSON | &Type::JSONB => {
+                serde_json_to_json_value(serde_json::Value::from_sql(ty, raw)?)?
+            }
+            _ if EnumLabel::accepts(ty) => EnumLabel::from_sql(ty, raw)?.0.into(),
+            _ => String::from_sql(ty, raw)?.into(),
+        };
+        Ok(Self(value))
+    }
+
+    fn from_sql_null(_ty: &Type) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(Self(JsonValue::NULL))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        matches!(
+            ty,
+            &Type::BOOL
+                | &Type::INT2
+                | &Type::INT4
+                | &Type::INT8
+                | &Type::FLOAT4
+                | &Type::FLOAT8
+                | &Type::NUMERIC
+                | &Type::JSON
+                | &Type::JSONB
+        ) || EnumLabel::accepts(ty)
+            || <String as FromSql>::accepts(ty)
+    }
+}
+
+fn convert_enum(row: &Row, idx: usize) -> Result<Field, PostgresSchemaError> {
+    let value: Result<EnumLabel, _> = row.try_get(idx);
+    value.map_or_else(handle_error, |val| Ok(Field::String(val.0)))
+}
+
+/// Converts a one-dimensional array.
+fn convert_array(row: &Row, idx: usize) -> Result<Field, PostgresSchemaError> {
+    let value: Result<Vec<JsonElement>, _> = row.try_get(idx);
+    value.map_or_else(handle_error, |val| {
+        let array = val
+            .into_iter()
+            .map(|element| element.0)
+            .collect::<JsonArray>();
+        Ok(Field::Json(array.into()))
+    })
+}
+
 type ConversionFn = fn(&Row, usize) -> Result<Field, PostgresSchemaError>;
 
 pub fn get_conversion_fn(col_type: &Type) -> Result<ConversionFn, PostgresSchemaError> {
@@ -314,6 +474,8 @@ pub fn get_conversion_fn(col_type: &Type) -> Result<ConversionFn, PostgresSchema
         &Type::POINT => Ok(convert_point),
         // &Type::UUID => convert_row_value_to_field!(row, idx, Uuid),
         &Type::UUID => Ok(convert_uuid),
+        _ if EnumLabel::accepts(col_type) => Ok(convert_enum),
+        _ if matches!(col_type.kind(), Kind::Array(_)) => Ok(convert_array),
         _ => {
             if col_type.schema() == "pg_catalog" {
                 Err(PostgresSchemaError::ColumnTypeNotSupported(
@@ -502,6 +664,54 @@ mod tests {
             Type::POINT,
             Field::Point(DozerPoint::from((1.234, 2.456)))
         );
+
+        test_conversion!("happy", mood_type(), Field::String("happy".to_string()));
+        test_conversion!(
+            "{1,NULL,3}",
+            Type::INT4_ARRAY,
+            Field::Json(json!([1, null, 3]))
+        );
+        test_conversion!(
+            r#"{happy,"so \"so\"",NULL}"#,
+            Type::new(
+                "_mood".to_string(),
+                16391,
+                Kind::Array(mood_type()),
+                "public".to_string()
+            ),
+            Field::Json(json!(["happy", "so \"so\"", null]))
+        );
+    }
+
+    fn mood_type() -> Type {
+        Type::new(
+            "mood".to_string(),
+            16390,
+            Kind::Enum(vec!["sad".to_string(), "happy".to_string()]),
+            "public".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_parse_array() {
+        assert_eq!(
+            parse_array("{}", &Type::TEXT).unwrap(),
+            JsonValue::from(JsonArray::new())
+        );
+        assert_eq!(
+            parse_array(r#"{{a,"b,c"},{"NULL",NULL}}"#, &Type::TEXT).unwrap(),
+            json!([["a", "b,c"], ["NULL", null]])
+        );
+        assert_eq!(
+            parse_array("{t,f}", &Type::BOOL).unwrap(),
+            json!([true, false])
+        );
+        assert_eq!(
+            parse_array("[0:1]={1.5,2}", &Type::FLOAT8).unwrap(),
+            json!([1.5, 2.0])
+        );
+        assert!(parse_array("{1,2", &Type::INT4).is_err());
+        assert!(parse_array("{1,x}", &Type::INT4).is_err());
     }
 
     #[test]
@@ -521,6 +731,8 @@ mod tests {
         test_type_mapping!(Type::JSON_ARRAY, FieldType::Json);
         test_type_mapping!(Type::BOOL, FieldType::Boolean);
         test_type_mapping!(Type::POINT, FieldType::Point);
+        test_type_mapping!(mood_type(), FieldType::String);
+        test_type_mapping!(Type::INT4_ARRAY, FieldType::Json);
     }
 
     #[test]
@@ -1,7 +1,7 @@
 use std::str::FromStr;
 use std::sync::Arc;
 
-use dozer_ingestion_connector::dozer_types::log::debug;
+use dozer_ingestion_connector::dozer_types::log::{debug, warn};
 use dozer_ingestion_connector::utils::ListOrFilterColumns;
 use dozer_ingestion_connector::Ingestor;
 use postgres_types::PgLsn;
@@ -11,6 +11,7 @@ use crate::connector::REPLICATION_SLOT_PREFIX;
 use crate::replication_slot_helper::ReplicationSlotHelper;
 use crate::replicator::CDCHandler;
 use crate::snapshotter::PostgresSnapshotter;
+use crate::state::{SnapshotProgress, TableProgress};
 use crate::PostgresConnectorError;
 
 use super::schema::helper::PostgresTableInfo;
@@ -24,6 +25,10 @@ pub struct Details {
     conn_config: tokio_postgres::Config,
     schema: Option<String>,
     batch_size: usize,
+    snapshot_chunk_size: u64,
+    snapshot_parallelism: usize,
+    /// Progress of an interrupted snapshot.
+    snapshot_progress: Option<SnapshotProgress>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -50,6 +55,9 @@ impl<'a> PostgresIterator<'a> {
         conn_config: tokio_postgres::Config,
         schema: Option<String>,
         batch_size: usize,
+        snapshot_chunk_size: u64,
+        snapshot_parallelism: usize,
+        snapshot_progress: Option<SnapshotProgress>,
     ) -> Self {
         let details = Arc::new(Details {
             name,
@@ -60,6 +68,9 @@ impl<'a> PostgresIterator<'a> {
             conn_config,
             schema,
             batch_size,
+            snapshot_chunk_size,
+            snapshot_parallelism,
+            snapshot_progress,
         });
         PostgresIterator { details, ingestor }
     }
@@ -107,7 +118,6 @@ impl<'a> PostgresIteratorHandler<'a> {
         let mut client = helper::connect(replication_conn_config).await?;
 
         // TODO: Handle cases:
-        // - When snapshot replication is not completed
         // - When there is gap between available lsn (in case when slot dropped and new created) and last lsn
         // - When publication tables changes
 
@@ -119,38 +129,105 @@ impl<'a> PostgresIteratorHandler<'a> {
         )
         .await?;
 
+        // Tables copied in a newer snapshot than the slot's skip the changes that are part of it.
+        let mut snapshot_lsns = match &details.snapshot_progress {
+            Some(progress) if progress.matches(&details.tables) => {
+                progress.tables.iter().map(TableProgress::lsn).collect()
+            }
+            _ => vec![None; details.tables.len()],
+        };
+
         if self.lsn.is_none() {
-            debug!("\nCreating Slot....");
             let slot_exist =
                 ReplicationSlotHelper::replication_slot_exists(&mut client, &details.slot_name)
                     .await?;
 
-            if slot_exist {
-                // We dont have lsn, so we need to drop replication slot and start from scratch
-                ReplicationSlotHelper::drop_replication_slot(&mut client, &details.slot_name)
-                    .await
-                    .map_err(PostgresConnectorError::InvalidQueryError)?;
-            }
+            // The slot retains the WAL from the snapshot's LSN on, so an interrupted snapshot can be resumed
+            // as long as the slot exists. The incomplete tables are copied again in a new snapshot, see
+            // `PostgresSnapshotter`.
+            let progress = match details.snapshot_progress.clone() {
+                Some(progress) if slot_exist && progress.matches(&details.tables) => Some(progress),
+                Some(_) => {
+                    warn!(
+                        "[{}] Cannot resume snapshot, starting from scratch",
+                        details.name
+                    );
+                    None
+                }
+                None => None,
+            };
 
-            client
-                .simple_query("BEGIN READ ONLY ISOLATION LEVEL REPEATABLE READ;")
-                .await
-                .map_err(|_e| {
-                    debug!("failed to begin txn for replication");
-                    PostgresConnectorError::BeginReplication
-                })?;
+            let (exported_snapshot, snapshot_lsn, remaining) = match progress {
+                Some(progress) => {
+                    debug!("\nResuming snapshot...");
+                    self.lsn = Some(progress.lsn());
 
-            let replication_slot_lsn =
-                ReplicationSlotHelper::create_replication_slot(&mut client, &details.slot_name)
+                    client
+                        .simple_query("BEGIN READ ONLY ISOLATION LEVEL REPEATABLE READ;")
+                        .await
+                        .map_err(|_e| {
+                            debug!("failed to begin txn for replication");
+                            PostgresConnectorError::BeginReplication
+                        })?;
+                    // Only the snapshot of the temporary slot is used, consistent with its LSN.
+                    let snapshot_lsn = ReplicationSlotHelper::create_temporary_replication_slot(
+                        &mut client,
+                        &resume_slot_name(&details.slot_name),
+                    )
                     .await?;
-            if let Some(lsn) = replication_slot_lsn {
-                self.lsn = PgLsn::from_str(&lsn).map_or_else(
-                    |_| Err(PostgresConnectorError::LsnParseError(lsn.to_string())),
-                    |lsn| Ok(Some(lsn)),
-                )?;
-            } else {
-                return Err(PostgresConnectorError::LsnNotReturnedFromReplicationSlot);
-            }
+                    let snapshot = ReplicationSlotHelper::export_snapshot(&mut client).await?;
+
+                    for (lsn, table) in snapshot_lsns.iter_mut().zip(&progress.tables) {
+                        if !table.complete {
+                            *lsn = Some(snapshot_lsn);
+                        }
+                    }
+                    (Some(snapshot), snapshot_lsn, Some(progress.tables))
+                }
+                None => {
+                    debug!("\nCreating Slot....");
+                    if slot_exist {
+                        // We dont have lsn, so we need to drop replication slot and start from scratch
+                        ReplicationSlotHelper::drop_replication_slot(
+                            &mut client,
+                            &details.slot_name,
+                        )
+                        .await
+                        .map_err(PostgresConnectorError::InvalidQueryError)?;
+                    }
+
+                    client
+                        .simple_query("BEGIN READ ONLY ISOLATION LEVEL REPEATABLE READ;")
+                        .await
+                        .map_err(|_e| {
+                            debug!("failed to begin txn for replication");
+                            PostgresConnectorError::BeginReplication
+                        })?;
+
+                    let replication_slot_lsn = ReplicationSlotHelper::create_replication_slot(
+                        &mut client,
+                        &details.slot_name,
+                    )
+                    .await?;
+                    if let Some(lsn) = replication_slot_lsn {
+                        self.lsn = PgLsn::from_str(&lsn).map_or_else(
+                            |_| Err(PostgresConnectorError::LsnParseError(lsn.to_string())),
+                            |lsn| Ok(Some(lsn)),
+                        )?;
+                    } else {
+                        return Err(PostgresConnectorError::LsnNotReturnedFromReplicationSlot);
+                    }
+
+                    // The snapshot workers read in the snapshot of the slot.
+                    let snapshot = ReplicationSlotHelper::export_snapshot(&mut client).await?;
+                    snapshot_lsns = vec![None; details.tables.len()];
+                    (
+                        Some(snapshot),
+                        self.lsn.ok_or(PostgresConnectorError::LSNNotStoredError)?,
+                        None,
+                    )
+                }
+            };
 
             self.state = ReplicationState::SnapshotInProgress;
 
@@ -162,6 +239,11 @@ impl<'a> PostgresIteratorHandler<'a> {
                 ingestor: self.ingestor,
                 schema: details.schema.clone(),
                 batch_size: details.batch_size,
+                chunk_size: details.snapshot_chunk_size,
+                parallelism: details.snapshot_parallelism,
+                slot_name: details.slot_name.clone(),
+                lsn: self.lsn.ok_or(PostgresConnectorError::LSNNotStoredError)?,
+                snapshot_lsn,
             };
             let tables = details
                 .tables
@@ -172,7 +254,9 @@ impl<'a> PostgresIteratorHandler<'a> {
                     schema: Some(table_info.schema.clone()),
                 })
                 .collect::<Vec<_>>();
-            snapshotter.sync_tables(&tables).await?;
+            snapshotter
+                .sync_tables(&tables, exported_snapshot.as_deref(), remaining)
+                .await?;
 
             debug!("\nInitialized with tables: {:?}", details.tables);
 
@@ -180,15 +264,26 @@ impl<'a> PostgresIteratorHandler<'a> {
                 debug!("failed to commit txn for replication");
                 PostgresConnectorError::CommitReplication
             })?;
+            if remaining.is_some() {
+                ReplicationSlotHelper::drop_replication_slot(
+                    &mut client,
+                    &resume_slot_name(&details.slot_name),
+                )
+                .await
+                .map_err(PostgresConnectorError::InvalidQueryError)?;
+            }
         }
 
         self.state = ReplicationState::Replicating;
 
         /*  ####################        Replicating         ######################  */
-        self.replicate().await
+        self.replicate(snapshot_lsns).await
     }
 
-    async fn replicate(&self) -> Result<(), PostgresConnectorError> {
+    async fn replicate(
+        &self,
+        snapshot_lsns: Vec<Option<PgLsn>>,
+    ) -> Result<(), PostgresConnectorError> {
         let lsn = self
             .lsn
             .as_ref()
@@ -199,9 +294,12 @@ impl<'a> PostgresIteratorHandler<'a> {
         let tables = self.details.tables.clone();
         let mut replicator = CDCHandler {
             replication_conn_config: self.details.replication_conn_config.clone(),
+            conn_config: self.details.conn_config.clone(),
             ingestor: self.ingestor,
             start_lsn: *lsn,
+            snapshot_lsns,
             begin_lsn: 0,
+            final_lsn: 0,
             offset_lsn: 0,
             publication_name,
             slot_name,
@@ -211,3 +309,8 @@ impl<'a> PostgresIteratorHandler<'a> {
         replicator.start(tables).await
     }
 }
+
+/// The name of the temporary slot whose snapshot a resumed snapshot is read in.
+fn resume_slot_name(slot_name: &str) -> String {
+    format!("{slot_name}_resume")
+}
@@ -4,6 +4,7 @@ use std::string::FromUtf8Error;
 use dozer_ingestion_connector::dozer_types::{
     chrono,
     errors::types::{DeserializationError, TypeError},
+    serde_json,
     thiserror::{self, Error},
 };
 use tokio_postgres::config::SslMode;
@@ -16,6 +17,7 @@ mod replication_slot_helper;
 pub mod replicator;
 mod schema;
 pub mod snapshotter;
+pub mod state;
 #[cfg(test)]
 pub mod test_utils;
 #[cfg(test)]
@@ -35,6 +37,9 @@ pub enum PostgresConnectorError {
     #[error("Failed to convert slot name from state. Error: {0}")]
     StringReadError(#[from] FromUtf8Error),
 
+    #[error("Failed to deserialize connector state: {0}")]
+    StateDeserialization(#[source] serde_json::Error),
+
     #[error("Query failed in connector: {0}")]
     InvalidQueryError(#[source] tokio_postgres::Error),
 
@@ -134,14 +139,6 @@ pub enum PostgresConnectorError {
         column_index: usize,
     },
 
-    #[error("Column type changed in table {table_index} column {column_name} from {old_type} to {new_type}")]
-    ColumnTypeChanged {
-        table_index: usize,
-        column_name: String,
-        old_type: postgres_types::Type,
-        new_type: postgres_types::Type,
-    },
-
     #[error("Unexpected query message")]
     UnexpectedQueryMessageError,
 }
@@ -178,6 +175,9 @@ pub enum PostgresSchemaError {
     #[error("Point parse failed")]
     PointParseError,
 
+    #[error("Array parse failed: {0}")]
+    ArrayParseError(String),
+
     #[error("Unsupported replication type - '{0}'")]
     UnsupportedReplicationType(String),
 
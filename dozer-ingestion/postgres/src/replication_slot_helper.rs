@@ -2,6 +2,8 @@ use crate::PostgresConnectorError;
 
 use super::connection::client::Client;
 use dozer_ingestion_connector::dozer_types::log::debug;
+use postgres_types::PgLsn;
+use std::str::FromStr;
 use tokio_postgres::{Error, SimpleQueryMessage};
 
 pub struct ReplicationSlotHelper {}
@@ -44,6 +46,48 @@ impl ReplicationSlotHelper {
         }
     }
 
+    /// Creates a slot that's dropped at the end of the session, for its snapshot only.
+    ///
+    /// Like [`Self::create_replication_slot`], it must be the first statement of a repeatable read transaction,
+    /// which then reads in the snapshot of the slot's consistent point.
+    pub async fn create_temporary_replication_slot(
+        client: &mut Client,
+        slot_name: &str,
+    ) -> Result<PgLsn, PostgresConnectorError> {
+        let query = format!(
+            r#"CREATE_REPLICATION_SLOT {slot_name:?} TEMPORARY LOGICAL "pgoutput" USE_SNAPSHOT"#
+        );
+
+        let slot_query_row = client.simple_query(&query).await.map_err(|e| {
+            debug!("failed to create temporary replication slot {}", slot_name);
+            PostgresConnectorError::CreateSlotError(slot_name.to_string(), e)
+        })?;
+
+        let Some(SimpleQueryMessage::Row(row)) = slot_query_row.first() else {
+            return Err(PostgresConnectorError::UnexpectedQueryMessageError);
+        };
+        let lsn = row
+            .get("consistent_point")
+            .ok_or(PostgresConnectorError::LsnNotReturnedFromReplicationSlot)?;
+        PgLsn::from_str(lsn).map_err(|_| PostgresConnectorError::LsnParseError(lsn.to_string()))
+    }
+
+    /// Exports the snapshot of the current transaction, which other connections can import until it ends.
+    pub async fn export_snapshot(client: &mut Client) -> Result<String, PostgresConnectorError> {
+        let rows = client
+            .simple_query("SELECT pg_export_snapshot();")
+            .await
+            .map_err(PostgresConnectorError::InvalidQueryError)?;
+
+        if let Some(SimpleQueryMessage::Row(row)) = rows.first() {
+            row.get(0)
+                .map(ToString::to_string)
+                .ok_or(PostgresConnectorError::UnexpectedQueryMessageError)
+        } else {
+            Err(PostgresConnectorError::UnexpectedQueryMessageError)
+        }
+    }
+
     pub async fn replication_slot_exists(
         client: &mut Client,
         slot_name: &str,
@@ -29,11 +29,18 @@ pub struct CDCHandler<'a> {
     pub ingestor: &'a Ingestor,
 
     pub replication_conn_config: tokio_postgres::Config,
+    /// A regular connection, to read the kind of the user-defined types of `Type` messages.
+    pub conn_config: tokio_postgres::Config,
     pub publication_name: String,
     pub slot_name: String,
 
     pub start_lsn: PgLsn,
+    /// Per table, the consistent point of the snapshot it was copied in if it's not the slot's.
+    /// Transactions committed before it are part of the copy.
+    pub snapshot_lsns: Vec<Option<PgLsn>>,
     pub begin_lsn: Lsn,
+    /// The commit LSN of the current transaction.
+    pub final_lsn: Lsn,
     pub offset_lsn: Lsn,
     pub last_commit_lsn: Lsn,
 }
@@ -105,6 +112,27 @@ impl<'a> CDCHandler<'a> {
         }
     }
 
+    /// Reads the `pg_type.typtype` and `pg_type.typcategory` of a type.
+    ///
+    /// `Type` messages are only sent before the first `Relation` message using the type, so this
+    /// connects when needed.
+    async fn read_type_kind(
+        &self,
+        oid: u32,
+    ) -> Result<(Option<char>, Option<char>), PostgresConnectorError> {
+        let mut client = helper::connect(self.conn_config.clone()).await?;
+        let row = client
+            .query_one(
+                "SELECT typtype, typcategory FROM pg_type WHERE oid = $1",
+                &[&oid],
+            )
+            .await
+            .map_err(PostgresConnectorError::InvalidQueryError)?;
+        let type_type: i8 = row.get(0);
+        let category: i8 = row.get(1);
+        Ok((Some(type_type as u8 as char), Some(category as u8 as char)))
+    }
+
     pub async fn handle_replication_message(
         &mut self,
         message: Option<Result<ReplicationMessage<LogicalReplicationMessage>, Error>>,
@@ -113,7 +141,11 @@ impl<'a> CDCHandler<'a> {
         match message {
             Some(Ok(XLogData(body))) => {
                 let lsn = body.wal_start();
-                let message = mapper.handle_message(body)?;
+                if let LogicalReplicationMessage::Type(typ) = body.data() {
+                    let (type_type, category) = self.read_type_kind(typ.id()).await?;
+                    mapper.ingest_type(typ, type_type, category)?;
+                }
+                let message = mapper.handle_message(body.data())?;
 
                 match message {
                     Some(MappedReplicationMessage::Commit(lsn)) => {
@@ -132,11 +164,13 @@ impl<'a> CDCHandler<'a> {
                             return Ok(());
                         }
                     }
-                    Some(MappedReplicationMessage::Begin) => {
+                    Some(MappedReplicationMessage::Begin(final_lsn)) => {
                         self.begin_lsn = lsn;
+                        self.final_lsn = final_lsn;
                     }
                     Some(MappedReplicationMessage::Operation { table_index, op }) => {
                         if self.begin_lsn != self.offset_lsn
+                            && !self.is_in_snapshot(table_index)
                             && self
                                 .ingestor
                                 .handle_message(IngestionMessage::OperationEvent {
@@ -151,6 +185,40 @@ impl<'a> CDCHandler<'a> {
                             return Ok(());
                         }
                     }
+                    Some(MappedReplicationMessage::SchemaChange {
+                        table_index,
+                        schema,
+                    }) => {
+                        if self
+                            .ingestor
+                            .handle_message(IngestionMessage::SchemaChange {
+                                table_index,
+                                schema,
+                            })
+                            .await
+                            .is_err()
+                        {
+                            return Ok(());
+                        }
+                    }
+                    Some(MappedReplicationMessage::Truncate { table_indexes }) => {
+                        if self.begin_lsn == self.offset_lsn {
+                            return Ok(());
+                        }
+                        for table_index in table_indexes {
+                            if self.is_in_snapshot(table_index) {
+                                continue;
+                            }
+                            if self
+                                .ingestor
+                                .handle_message(IngestionMessage::Truncate { table_index })
+                                .await
+                                .is_err()
+                            {
+                                return Ok(());
+                            }
+                        }
+                    }
                     None => {}
                 }
 
@@ -164,6 +232,15 @@ impl<'a> CDCHandler<'a> {
             None => Err(PostgresConnectorError::ReplicationStreamEndError),
         }
     }
+
+    /// Whether the current transaction is already part of the snapshot of the table.
+    fn is_in_snapshot(&self, table_index: usize) -> bool {
+        self.snapshot_lsns
+            .get(table_index)
+            .copied()
+            .flatten()
+            .is_some_and(|lsn| self.final_lsn < u64::from(lsn))
+    }
 }
 
 pub struct LogicalReplicationStream {
@@ -5,7 +5,7 @@ use dozer_ingestion_connector::{
     utils::ListOrFilterColumns,
     CdcType, SourceSchema,
 };
-use postgres_types::Type;
+use postgres_types::{Kind, Type};
 use tokio_postgres::Row;
 
 use crate::{
@@ -354,16 +354,22 @@ impl SchemaHelper {
         let is_column_used_in_index: bool = row.get(3);
         let replication_type_int: i8 = row.get(5);
         let type_oid: u32 = row.get(6);
-
-        // TODO: workaround - in case of custom enum
-        let typ = if type_oid == 28862 {
-            FieldType::String
-        } else {
-            let oid_typ = Type::from_oid(type_oid);
-            oid_typ.map_or_else(
-                || Err(PostgresSchemaError::InvalidColumnType(column_name.clone())),
-                postgres_type_to_dozer_type,
-            )?
+        let type_name: Option<String> = row.get(9);
+        let type_type: Option<i8> = row.get(10);
+        let type_category: Option<i8> = row.get(11);
+
+        let typ = match Type::from_oid(type_oid) {
+            Some(typ) => postgres_type_to_dozer_type(typ)?,
+            None => custom_type_to_dozer_type(
+                type_type.map(|typ| typ as u8 as char),
+                type_category.map(|category| category as u8 as char),
+            )
+            .ok_or_else(|| {
+                type_name.map_or_else(
+                    || PostgresSchemaError::InvalidColumnType(column_name.clone()),
+                    PostgresSchemaError::CustomTypeNotSupported,
+                )
+            })?,
         };
 
         let replication_type =
@@ -383,6 +389,26 @@ impl SchemaHelper {
 
 pub const DEFAULT_SCHEMA_NAME: &str = "public";
 
+/// The kind of a user-defined type by its `pg_type.typtype` and `pg_type.typcategory`, if it's supported.
+///
+/// Arrays are read as arrays of text, and enums as their label.
+pub(crate) fn custom_type_kind(typ: Option<char>, category: Option<char>) -> Option<Kind> {
+    match (typ, category) {
+        (_, Some('A')) => Some(Kind::Array(Type::TEXT)),
+        (Some('e'), _) => Some(Kind::Enum(vec![])),
+        _ => None,
+    }
+}
+
+/// Maps a user-defined type by its `pg_type.typtype` and `pg_type.typcategory`.
+fn custom_type_to_dozer_type(typ: Option<char>, category: Option<char>) -> Option<FieldType> {
+    match custom_type_kind(typ, category)? {
+        Kind::Array(_) => Some(FieldType::Json),
+        Kind::Enum(_) => Some(FieldType::String),
+        _ => None,
+    }
+}
+
 fn find_table(
     table_columns_map: &HashMap<SchemaTableIdentifier, (u32, Vec<String>)>,
     schema_name: Option<&str>,
@@ -419,7 +445,10 @@ SELECT table_info.table_name,
        pc.relreplident,
        pt.oid                                                           AS type_oid,
        t.table_type,
-       t.table_schema
+       t.table_schema,
+       pt.typname,
+       pt.typtype,
+       pt.typcategory
 FROM information_schema.columns table_info
          LEFT JOIN information_schema.tables t ON t.table_name = table_info.table_name AND t.table_schema = table_info.table_schema
          LEFT JOIN pg_namespace ns ON t.table_schema = ns.nspname
@@ -433,6 +462,8 @@ FROM information_schema.columns table_info
                  AND pa.attnum > 0
                  AND pa.attname = table_info.column_name
 WHERE :tables_name_condition AND ns.nspname not in ('information_schema', 'pg_catalog')
+      -- Generated columns are not replicated.
+      and table_info.is_generated = 'NEVER'
       and ns.nspname not like 'pg_toast%'
       and ns.nspname not like 'pg_temp_%'
 ORDER BY table_info.table_schema,
@@ -1,3 +1,4 @@
+use dozer_ingestion_connector::dozer_types::types::FieldType;
 use dozer_ingestion_connector::tokio;
 use dozer_ingestion_connector::utils::ListOrFilterColumns;
 use rand::Rng;
@@ -174,3 +175,60 @@ async fn test_connector_view_cannot_be_used() {
 
     client.drop_schema(&schema).await;
 }
+
+#[tokio::test]
+#[ignore]
+#[serial]
+async fn test_connector_get_schema_with_custom_types() {
+    let config = load_test_connection_config().await;
+    let mut client = TestPostgresClient::new(&config).await;
+
+    let mut rng = rand::thread_rng();
+
+    let schema = format!("schema_helper_test_{}", rng.gen::<u32>());
+    let table_name = format!("products_test_{}", rng.gen::<u32>());
+
+    client.create_schema(&schema).await;
+    client
+        .execute_query(&format!(
+            "CREATE TYPE {schema}.mood AS ENUM ('sad', 'ok', 'happy')"
+        ))
+        .await;
+    client
+        .execute_query(&format!(
+            "CREATE TABLE {schema}.{table_name}
+            (
+                id          SERIAL PRIMARY KEY,
+                mood        {schema}.mood,
+                moods       {schema}.mood[],
+                scores      INT[],
+                score_count INT GENERATED ALWAYS AS (cardinality(scores)) STORED
+            )"
+        ))
+        .await;
+
+    let schema_helper = SchemaHelper::new(client.postgres_config.clone(), None);
+    let table_info = ListOrFilterColumns {
+        name: table_name,
+        schema: Some(schema.clone()),
+        columns: None,
+    };
+    let result = schema_helper.get_schemas(&[table_info]).await.unwrap();
+
+    // The generated column is left out, as it's not replicated.
+    let fields = &result[0].as_ref().unwrap().schema.fields;
+    assert_eq!(
+        fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.typ))
+            .collect::<Vec<_>>(),
+        vec![
+            ("id", FieldType::Int),
+            ("mood", FieldType::String),
+            ("moods", FieldType::Json),
+            ("scores", FieldType::Json),
+        ]
+    );
+
+    client.drop_schema(&schema).await;
+}
@@ -1,7 +1,11 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
 use dozer_ingestion_connector::{
     dozer_types::{
         models::ingestion_types::{IngestionMessage, TransactionInfo},
-        types::{Operation, Schema},
+        parking_lot::Mutex,
+        types::{Operation, Record, Schema},
     },
     futures::StreamExt,
     tokio::{
@@ -12,21 +16,77 @@ use dozer_ingestion_connector::{
     utils::ListOrFilterColumns,
     Ingestor, SourceSchema,
 };
+use postgres_types::PgLsn;
+use tokio_postgres::Row;
 
 use crate::{
-    connection::helper as connection_helper, helper::get_conversion_fn,
-    schema::helper::SchemaHelper, PostgresConnectorError,
+    connection::{client::Client, helper as connection_helper},
+    helper::get_conversion_fn,
+    schema::helper::{SchemaHelper, DEFAULT_SCHEMA_NAME},
+    state::{ConnectorState, SnapshotProgress, TableProgress},
+    PostgresConnectorError,
 };
 
 use super::helper;
 
+/// Copies the initial snapshot of the replicated tables.
+///
+/// Tables with a primary key are split into chunks of `chunk_size` rows by key range, and up to `parallelism` chunks
+/// of the same or different tables are copied concurrently. The snapshot progress is reported after every completely
+/// copied table, so that it can be resumed.
+///
+/// A resumed snapshot keeps the completely copied tables, and restarts the others from their first row. Their
+/// remaining chunks would be read later than the slot's snapshot, so they'd already contain some of the changes
+/// replicated from the slot, and not others. These tables are truncated and copied again in a newer snapshot instead,
+/// and their changes are replicated from its consistent point.
 pub struct PostgresSnapshotter<'a> {
     pub conn_config: tokio_postgres::Config,
     pub ingestor: &'a Ingestor,
     pub schema: Option<String>,
     pub batch_size: usize,
+    pub chunk_size: u64,
+    pub parallelism: usize,
+    /// The replication slot the snapshot is consistent with, reported with the progress.
+    pub slot_name: String,
+    pub lsn: PgLsn,
+    /// The consistent point of the snapshot the tables are read in, after `lsn` if the snapshot is resumed.
+    pub snapshot_lsn: PgLsn,
+}
+
+struct SnapshotTable {
+    schema_name: String,
+    table_name: String,
+    schema: Schema,
+    /// The primary key columns, empty if there's no primary key.
+    key: Vec<String>,
+}
+
+/// A range of primary keys. A key is the text representation of its columns.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct KeyRange {
+    /// Exclusive lower bound, `None` for the start of the table.
+    after: Option<Vec<String>>,
+    /// Inclusive upper bound, `None` for the end of the table.
+    until: Option<Vec<String>>,
+}
+
+struct Chunk {
+    table_index: usize,
+    range: KeyRange,
 }
 
+enum ChunkMessage {
+    Rows {
+        table_index: usize,
+        rows: Vec<Record>,
+    },
+    Done {
+        table_index: usize,
+    },
+}
+
+type ChunkSender = Sender<Result<ChunkMessage, PostgresConnectorError>>;
+
 impl<'a> PostgresSnapshotter<'a> {
     pub async fn get_tables(
         &self,
@@ -36,107 +96,71 @@ impl<'a> PostgresSnapshotter<'a> {
         helper.get_schemas(tables).await
     }
 
-    pub async fn sync_table(
-        schema: Schema,
-        schema_name: String,
-        table_name: String,
-        table_index: usize,
-        conn_config: tokio_postgres::Config,
-        batch_size: usize,
-        sender: Sender<Result<(usize, Operation), PostgresConnectorError>>,
-    ) -> Result<(), PostgresConnectorError> {
-        let mut client_plain = connection_helper::connect(conn_config).await?;
-
-        let column_str: Vec<String> = schema
-            .fields
-            .iter()
-            .map(|f| format!("\"{0}\"", f.name))
-            .collect();
-
-        let column_str = column_str.join(",");
-        let query = format!(r#"select {column_str} from "{schema_name}"."{table_name}""#);
-        let stmt = client_plain
-            .prepare(&query)
-            .await
-            .map_err(PostgresConnectorError::InvalidQueryError)?;
-        let columns = stmt.columns();
-        let conversions: Vec<_> = columns
-            .iter()
-            .map(|col| get_conversion_fn(col.type_()))
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let empty_vec: Vec<String> = Vec::new();
-        let row_stream = client_plain
-            .query_raw(query, empty_vec)
-            .await
-            .map_err(PostgresConnectorError::InvalidQueryError)?;
-        tokio::pin!(row_stream);
-
-        let mut batch = Vec::with_capacity(batch_size);
-
-        while let Some(msg) = row_stream.next().await {
-            match msg {
-                Ok(msg) => {
-                    let record = helper::map_row_to_record(&msg, &conversions)
-                        .map_err(PostgresConnectorError::PostgresSchemaError)?;
-                    batch.push(record);
-
-                    if batch.len() == batch_size {
-                        let Ok(_) = sender
-                            .send(Ok((table_index, Operation::BatchInsert { new: batch })))
-                            .await
-                        else {
-                            // If we can't send, the parent task has quit. There is
-                            // no use in going on, but if there was an error, it was
-                            // handled by the parent.
-                            return Ok(());
-                        };
-                        batch = Vec::with_capacity(batch_size);
-                    }
-                }
-                Err(e) => return Err(PostgresConnectorError::SyncWithSnapshotError(e.to_string())),
-            }
-        }
-
-        if !batch.is_empty() {
-            let _ = sender
-                .send(Ok((table_index, Operation::BatchInsert { new: batch })))
-                .await;
-        }
-
-        Ok(())
-    }
-
+    /// Copies `tables`, or only the tables that `remaining` doesn't report as complete if the snapshot is resumed.
+    ///
+    /// Tables are read in `exported_snapshot` if given. Otherwise, every chunk is read in its own snapshot.
     pub async fn sync_tables(
         &self,
         tables: &[ListOrFilterColumns],
+        exported_snapshot: Option<&str>,
+        remaining: Option<Vec<TableProgress>>,
     ) -> Result<(), PostgresConnectorError> {
         let schemas = self.get_tables(tables).await?;
-
+        let mut snapshot_tables = vec![];
+        for (schema, table) in schemas.into_iter().zip(tables) {
+            let schema = schema?.schema;
+            snapshot_tables.push(SnapshotTable {
+                schema_name: table
+                    .schema
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_SCHEMA_NAME.to_string()),
+                table_name: table.name.clone(),
+                key: schema
+                    .primary_index
+                    .iter()
+                    .map(|index| schema.fields[*index].name.clone())
+                    .collect(),
+                schema,
+            });
+        }
+        let truncated = remaining
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, table)| !table.complete)
+            .map(|(table_index, _)| table_index)
+            .collect::<Vec<_>>();
+        let (mut progress, ranges) = self
+            .plan_chunks(&snapshot_tables, exported_snapshot, remaining)
+            .await?;
+        let mut remaining_chunks = ranges.iter().map(Vec::len).collect::<Vec<_>>();
+
+        let queue = interleave_chunks(ranges);
+        let num_workers = self.parallelism.max(1).min(queue.len());
+        let queue = Arc::new(Mutex::new(queue));
+        let snapshot_tables = Arc::new(snapshot_tables);
         let (tx, mut rx) = channel(16);
 
         let mut joinset = JoinSet::new();
-        for (table_index, (schema, table)) in schemas.into_iter().zip(tables).enumerate() {
-            let schema = schema?;
-            let schema = schema.schema;
-            let schema_name = table.schema.clone().unwrap_or("public".to_string());
-            let table_name = table.name.clone();
+        for _ in 0..num_workers {
+            let tables = snapshot_tables.clone();
+            let queue = queue.clone();
             let conn_config = self.conn_config.clone();
+            let exported_snapshot = exported_snapshot.map(ToString::to_string);
             let batch_size = self.batch_size;
             let sender = tx.clone();
             joinset.spawn(async move {
-                if let Err(e) = Self::sync_table(
-                    schema,
-                    schema_name,
-                    table_name,
-                    table_index,
+                if let Err(e) = Self::copy_chunks(
+                    tables,
+                    queue,
                     conn_config,
+                    exported_snapshot,
                     batch_size,
                     sender.clone(),
                 )
                 .await
                 {
-                    sender.send(Err(e)).await.unwrap();
+                    let _ = sender.send(Err(e)).await;
                 }
             });
         }
@@ -156,23 +180,55 @@ impl<'a> PostgresSnapshotter<'a> {
             return Ok(());
         }
 
-        while let Some(message) = rx.recv().await {
-            let (table_index, evt) = message?;
+        // Drop the rows copied before the snapshot was interrupted.
+        for table_index in truncated {
             if self
                 .ingestor
-                .handle_message(IngestionMessage::OperationEvent {
-                    table_index,
-                    op: evt,
-                    id: None,
-                })
+                .handle_message(IngestionMessage::Truncate { table_index })
                 .await
                 .is_err()
             {
-                // If receiving side is closed, we can stop
                 return Ok(());
             }
         }
 
+        while let Some(message) = rx.recv().await {
+            let messages = match message? {
+                ChunkMessage::Rows { table_index, rows } => {
+                    vec![IngestionMessage::OperationEvent {
+                        table_index,
+                        op: Operation::BatchInsert { new: rows },
+                        id: None,
+                    }]
+                }
+                ChunkMessage::Done { table_index } => {
+                    remaining_chunks[table_index] -= 1;
+                    if remaining_chunks[table_index] == 0 {
+                        progress[table_index].complete = true;
+                        // The rows sent so far are persisted together with the state on commit.
+                        let state = self.state(progress.clone());
+                        vec![
+                            IngestionMessage::TransactionInfo(
+                                TransactionInfo::SourceStateChanged { state },
+                            ),
+                            IngestionMessage::TransactionInfo(TransactionInfo::Commit {
+                                id: None,
+                                source_time: None,
+                            }),
+                        ]
+                    } else {
+                        vec![]
+                    }
+                }
+            };
+            for message in messages {
+                if self.ingestor.handle_message(message).await.is_err() {
+                    // If receiving side is closed, we can stop
+                    return Ok(());
+                }
+            }
+        }
+
         if self
             .ingestor
             .handle_message(IngestionMessage::TransactionInfo(
@@ -189,22 +245,426 @@ impl<'a> PostgresSnapshotter<'a> {
         // Otherwise, they will be aborted when the joinset is dropped
         Ok(())
     }
+
+    /// Splits every table with a primary key into ranges of `chunk_size` keys. Returns the progress of the tables,
+    /// and their ranges to copy.
+    ///
+    /// If the snapshot is resumed, the tables that `remaining` reports as complete are kept as they are.
+    async fn plan_chunks(
+        &self,
+        tables: &[SnapshotTable],
+        exported_snapshot: Option<&str>,
+        remaining: Option<Vec<TableProgress>>,
+    ) -> Result<(Vec<TableProgress>, Vec<Vec<KeyRange>>), PostgresConnectorError> {
+        let client = connect_in_snapshot(self.conn_config.clone(), exported_snapshot).await?;
+        let resumed = remaining.is_some();
+        let mut remaining = remaining.into_iter().flatten();
+        let mut progress = vec![];
+        let mut table_ranges = vec![];
+        for table in tables {
+            if let Some(complete) = remaining.next().filter(|table| table.complete) {
+                progress.push(complete);
+                table_ranges.push(vec![]);
+                continue;
+            }
+            let ranges = if table.key.is_empty() {
+                vec![KeyRange::default()]
+            } else {
+                let rows = client
+                    .query_raw_in_transaction(&boundaries_query(table, self.chunk_size.max(1)))
+                    .await
+                    .map_err(PostgresConnectorError::InvalidQueryError)?;
+                tokio::pin!(rows);
+                let mut boundaries = vec![];
+                while let Some(row) = rows.next().await {
+                    let row = row.map_err(PostgresConnectorError::InvalidQueryError)?;
+                    boundaries.push(key_text(&row));
+                }
+                ranges_from_boundaries(boundaries)
+            };
+            progress.push(TableProgress {
+                schema: table.schema_name.clone(),
+                name: table.table_name.clone(),
+                complete: false,
+                lsn: resumed.then_some(self.snapshot_lsn.into()),
+            });
+            table_ranges.push(ranges);
+        }
+        Ok((progress, table_ranges))
+    }
+
+    fn state(&self, tables: Vec<TableProgress>) -> Vec<u8> {
+        ConnectorState {
+            slot_name: self.slot_name.clone(),
+            snapshot: Some(SnapshotProgress {
+                lsn: self.lsn.into(),
+                tables,
+            }),
+        }
+        .serialize()
+    }
+
+    async fn copy_chunks(
+        tables: Arc<Vec<SnapshotTable>>,
+        queue: Arc<Mutex<VecDeque<Chunk>>>,
+        conn_config: tokio_postgres::Config,
+        exported_snapshot: Option<String>,
+        batch_size: usize,
+        sender: ChunkSender,
+    ) -> Result<(), PostgresConnectorError> {
+        let client = connect_in_snapshot(conn_config, exported_snapshot.as_deref()).await?;
+        loop {
+            let chunk = queue.lock().pop_front();
+            let Some(chunk) = chunk else {
+                return Ok(());
+            };
+            let table = &tables[chunk.table_index];
+            if !Self::copy_chunk(&client, table, chunk, batch_size, &sender).await? {
+                // If we can't send, the parent task has quit. There is
+                // no use in going on, but if there was an error, it was
+                // handled by the parent.
+                return Ok(());
+            }
+        }
+    }
+
+    /// Sends the rows of `chunk` in batches. Returns `false` if the receiver is gone.
+    async fn copy_chunk(
+        client: &Client,
+        table: &SnapshotTable,
+        chunk: Chunk,
+        batch_size: usize,
+        sender: &ChunkSender,
+    ) -> Result<bool, PostgresConnectorError> {
+        let row_stream = client
+            .query_raw_in_transaction(&chunk_query(table, &chunk.range))
+            .await
+            .map_err(PostgresConnectorError::InvalidQueryError)?;
+        tokio::pin!(row_stream);
+
+        let mut conversions = vec![];
+        let mut batch = Vec::with_capacity(batch_size);
+        loop {
+            let row = row_stream
+                .next()
+                .await
+                .transpose()
+                .map_err(|e| PostgresConnectorError::SyncWithSnapshotError(e.to_string()))?;
+            if let Some(row) = &row {
+                if conversions.is_empty() {
+                    conversions = row
+                        .columns()
+                        .iter()
+                        .map(|column| get_conversion_fn(column.type_()))
+                        .collect::<Result<Vec<_>, _>>()?;
+                }
+                batch.push(
+                    helper::map_row_to_record(row, &conversions)
+                        .map_err(PostgresConnectorError::PostgresSchemaError)?,
+                );
+            }
+            let is_done = row.is_none();
+
+            if batch.len() == batch_size || (is_done && !batch.is_empty()) {
+                let rows = std::mem::replace(&mut batch, Vec::with_capacity(batch_size));
+                let message = ChunkMessage::Rows {
+                    table_index: chunk.table_index,
+                    rows,
+                };
+                if sender.send(Ok(message)).await.is_err() {
+                    return Ok(false);
+                }
+            }
+            if is_done {
+                let message = ChunkMessage::Done {
+                    table_index: chunk.table_index,
+                };
+                return Ok(sender.send(Ok(message)).await.is_ok());
+            }
+        }
+    }
+}
+
+/// Connects, and imports `exported_snapshot` into a read only transaction if given.
+async fn connect_in_snapshot(
+    conn_config: tokio_postgres::Config,
+    exported_snapshot: Option<&str>,
+) -> Result<Client, PostgresConnectorError> {
+    let mut client = connection_helper::connect(conn_config).await?;
+    if let Some(snapshot) = exported_snapshot {
+        client
+            .simple_query(&format!(
+                "BEGIN READ ONLY ISOLATION LEVEL REPEATABLE READ; SET TRANSACTION SNAPSHOT {}",
+                quote_literal(snapshot)
+            ))
+            .await
+            .map_err(PostgresConnectorError::InvalidQueryError)?;
+    }
+    Ok(client)
+}
+
+/// Orders the chunks of the tables' `ranges` so that workers start on different tables.
+fn interleave_chunks(ranges: Vec<Vec<KeyRange>>) -> VecDeque<Chunk> {
+    let mut ranges = ranges
+        .into_iter()
+        .map(Vec::into_iter)
+        .enumerate()
+        .collect::<Vec<_>>();
+    let mut chunks = VecDeque::new();
+    while !ranges.is_empty() {
+        ranges.retain_mut(|(table_index, ranges)| match ranges.next() {
+            Some(range) => {
+                chunks.push_back(Chunk {
+                    table_index: *table_index,
+                    range,
+                });
+                true
+            }
+            None => false,
+        });
+    }
+    chunks
+}
+
+/// Splits the key space at `boundaries`, which are sorted.
+fn ranges_from_boundaries(boundaries: Vec<Vec<String>>) -> Vec<KeyRange> {
+    let mut ranges = vec![];
+    let mut after = None;
+    for boundary in boundaries {
+        ranges.push(KeyRange {
+            after: after.take(),
+            until: Some(boundary.clone()),
+        });
+        after = Some(boundary);
+    }
+    ranges.push(KeyRange { after, until: None });
+    ranges
+}
+
+/// The text representation of the key columns, which are the columns of `row`.
+fn key_text(row: &Row) -> Vec<String> {
+    (0..row.len()).map(|index| row.get(index)).collect()
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn table_name(table: &SnapshotTable) -> String {
+    format!(
+        "{}.{}",
+        quote_ident(&table.schema_name),
+        quote_ident(&table.table_name)
+    )
+}
+
+/// Selects every `chunk_size`th key, in key order.
+fn boundaries_query(table: &SnapshotTable, chunk_size: u64) -> String {
+    let key = table
+        .key
+        .iter()
+        .map(|column| quote_ident(column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let key_text = table
+        .key
+        .iter()
+        .map(|column| format!("{}::text", quote_ident(column)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "SELECT {key_text} FROM (SELECT {key}, row_number() OVER (ORDER BY {key}) AS dozer_row_number FROM {}) AS numbered WHERE dozer_row_number % {chunk_size} = 0 ORDER BY dozer_row_number",
+        table_name(table)
+    )
+}
+
+/// Selects the rows of `range`.
+///
+/// Keys are compared as row values, and the bounds are untyped literals that take the type of the key columns.
+fn chunk_query(table: &SnapshotTable, range: &KeyRange) -> String {
+    let columns = table
+        .schema
+        .fields
+        .iter()
+        .map(|field| quote_ident(&field.name))
+        .collect::<Vec<_>>();
+    if table.key.is_empty() {
+        return format!("SELECT {} FROM {}", columns.join(", "), table_name(table));
+    }
+
+    let key = table
+        .key
+        .iter()
+        .map(|column| quote_ident(column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let bound = |values: &[String]| {
+        values
+            .iter()
+            .map(|value| quote_literal(value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut conditions = vec![];
+    if let Some(after) = &range.after {
+        conditions.push(format!("({key}) > ({})", bound(after)));
+    }
+    if let Some(until) = &range.until {
+        conditions.push(format!("({key}) <= ({})", bound(until)));
+    }
+
+    let mut query = format!("SELECT {} FROM {}", columns.join(", "), table_name(table));
+    if !conditions.is_empty() {
+        query.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+    }
+    query
 }
 
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
 
-    use dozer_ingestion_connector::{tokio, utils::ListOrFilterColumns, IngestionConfig, Ingestor};
+    use dozer_ingestion_connector::{
+        dozer_types::{
+            models::ingestion_types::{IngestionMessage, TransactionInfo},
+            types::{FieldDefinition, FieldType, Operation, Schema, SourceDefinition},
+        },
+        tokio,
+        utils::ListOrFilterColumns,
+        IngestionConfig, Ingestor,
+    };
+    use postgres_types::PgLsn;
     use rand::Rng;
     use serial_test::serial;
 
     use crate::{
-        connection::helper::map_connection_config, test_utils::load_test_connection_config,
-        tests::client::TestPostgresClient,
+        connection::helper::map_connection_config, state::ConnectorState,
+        test_utils::load_test_connection_config, tests::client::TestPostgresClient,
+    };
+
+    use super::{
+        boundaries_query, chunk_query, interleave_chunks, ranges_from_boundaries, KeyRange,
+        PostgresSnapshotter, SnapshotTable,
     };
 
-    use super::PostgresSnapshotter;
+    fn key(values: &[&str]) -> Option<Vec<String>> {
+        Some(values.iter().map(ToString::to_string).collect())
+    }
+
+    fn orders_table() -> SnapshotTable {
+        let mut schema = Schema::default();
+        for name in ["region", "id", "note"] {
+            schema.field(
+                FieldDefinition::new(
+                    name.to_string(),
+                    FieldType::String,
+                    false,
+                    SourceDefinition::Dynamic,
+                ),
+                name != "note",
+            );
+        }
+        SnapshotTable {
+            schema_name: "public".to_string(),
+            table_name: "orders".to_string(),
+            schema,
+            key: vec!["region".to_string(), "id".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_ranges_from_boundaries() {
+        assert_eq!(ranges_from_boundaries(vec![]), vec![KeyRange::default()]);
+        assert_eq!(
+            ranges_from_boundaries(vec![vec!["10".to_string()], vec!["20".to_string()]]),
+            vec![
+                KeyRange {
+                    after: None,
+                    until: key(&["10"]),
+                },
+                KeyRange {
+                    after: key(&["10"]),
+                    until: key(&["20"]),
+                },
+                KeyRange {
+                    after: key(&["20"]),
+                    until: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chunk_query() {
+        let table = orders_table();
+        assert_eq!(
+            chunk_query(&table, &KeyRange::default()),
+            r#"SELECT "region", "id", "note" FROM "public"."orders""#
+        );
+        assert_eq!(
+            chunk_query(
+                &table,
+                &KeyRange {
+                    after: key(&["eu", "7"]),
+                    until: key(&["us's", "3"]),
+                }
+            ),
+            r#"SELECT "region", "id", "note" FROM "public"."orders" WHERE ("region", "id") > ('eu', '7') AND ("region", "id") <= ('us''s', '3')"#
+        );
+
+        let table = SnapshotTable {
+            key: vec![],
+            ..orders_table()
+        };
+        assert_eq!(
+            chunk_query(&table, &KeyRange::default()),
+            r#"SELECT "region", "id", "note" FROM "public"."orders""#
+        );
+    }
+
+    #[test]
+    fn test_boundaries_query() {
+        assert_eq!(
+            boundaries_query(&orders_table(), 1000),
+            r#"SELECT "region"::text, "id"::text FROM (SELECT "region", "id", row_number() OVER (ORDER BY "region", "id") AS dozer_row_number FROM "public"."orders") AS numbered WHERE dozer_row_number % 1000 = 0 ORDER BY dozer_row_number"#
+        );
+    }
+
+    #[test]
+    fn test_interleave_chunks() {
+        let ranges = |until: &[&str]| {
+            until
+                .iter()
+                .map(|until| KeyRange {
+                    after: None,
+                    until: key(&[until]),
+                })
+                .collect::<Vec<_>>()
+        };
+        let chunks = interleave_chunks(vec![
+            ranges(&["a0", "a1", "a2"]),
+            ranges(&["b0"]),
+            ranges(&["c0", "c1"]),
+        ]);
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|chunk| (chunk.table_index, chunk.range.until.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, key(&["a0"])),
+                (1, key(&["b0"])),
+                (2, key(&["c0"])),
+                (0, key(&["a1"])),
+                (2, key(&["c1"])),
+                (0, key(&["a2"])),
+            ]
+        );
+    }
 
     #[tokio::test]
     #[ignore]
@@ -236,9 +696,17 @@ mod tests {
             ingestor: &ingestor,
             schema: None,
             batch_size: 1000,
+            chunk_size: 1000,
+            parallelism: 2,
+            slot_name: "test_slot".to_string(),
+            lsn: PgLsn::from(0),
+            snapshot_lsn: PgLsn::from(0),
         };
 
-        snapshotter.sync_tables(&input_tables).await.unwrap();
+        snapshotter
+            .sync_tables(&input_tables, None, None)
+            .await
+            .unwrap();
 
         let mut i = 0;
         while i < 2 {
@@ -284,9 +752,14 @@ mod tests {
             ingestor: &ingestor,
             schema: None,
             batch_size: 1000,
+            chunk_size: 1000,
+            parallelism: 2,
+            slot_name: "test_slot".to_string(),
+            lsn: PgLsn::from(0),
+            snapshot_lsn: PgLsn::from(0),
         };
 
-        let actual = snapshotter.sync_tables(&input_tables).await;
+        let actual = snapshotter.sync_tables(&input_tables, None, None).await;
 
         assert!(actual.is_err());
     }
@@ -316,10 +789,128 @@ mod tests {
             ingestor: &ingestor,
             schema: None,
             batch_size: 1000,
+            chunk_size: 1000,
+            parallelism: 2,
+            slot_name: "test_slot".to_string(),
+            lsn: PgLsn::from(0),
+            snapshot_lsn: PgLsn::from(0),
         };
 
-        let actual = snapshotter.sync_tables(&input_tables).await;
+        let actual = snapshotter.sync_tables(&input_tables, None, None).await;
 
         assert!(actual.is_err());
     }
+
+    fn resumable_snapshotter<'a>(
+        conn_config: &tokio_postgres::Config,
+        ingestor: &'a Ingestor,
+    ) -> PostgresSnapshotter<'a> {
+        PostgresSnapshotter {
+            conn_config: conn_config.clone(),
+            ingestor,
+            schema: None,
+            batch_size: 1000,
+            chunk_size: 2,
+            parallelism: 1,
+            slot_name: "test_slot".to_string(),
+            lsn: PgLsn::from(0),
+            snapshot_lsn: PgLsn::from(0),
+        }
+    }
+
+    #[tokio::test]
+    #[ignore]
+    #[serial]
+    async fn test_connector_snapshotter_resumes_from_progress() {
+        let config = load_test_connection_config().await;
+
+        let mut test_client = TestPostgresClient::new(&config).await;
+
+        let mut rng = rand::thread_rng();
+        let table_name = format!("test_table_{}", rng.gen::<u32>());
+
+        test_client.create_simple_table("public", &table_name).await;
+        test_client.insert_rows(&table_name, 5, None).await;
+
+        let conn_config = map_connection_config(&config).unwrap();
+
+        let input_tables = vec![ListOrFilterColumns {
+            name: table_name,
+            schema: Some("public".to_string()),
+            columns: None,
+        }];
+
+        let (ingestor, mut iterator) = Ingestor::initialize_channel(IngestionConfig::default());
+        resumable_snapshotter(&conn_config, &ingestor)
+            .sync_tables(&input_tables, None, None)
+            .await
+            .unwrap();
+        drop(ingestor);
+        let mut states = vec![];
+        let mut num_rows = 0;
+        while let Some(message) = iterator.next_timeout(Duration::from_secs(1)).await {
+            match message {
+                IngestionMessage::OperationEvent {
+                    op: Operation::BatchInsert { new },
+                    ..
+                } => num_rows += new.len(),
+                IngestionMessage::TransactionInfo(TransactionInfo::SourceStateChanged {
+                    state,
+                }) => states.push(ConnectorState::deserialize(state).unwrap()),
+                _ => (),
+            }
+        }
+        assert_eq!(num_rows, 5);
+        // The progress is saved once the table is copied, in chunks of 2, 2 and 1 rows.
+        assert_eq!(states.len(), 1);
+        let mut progress = states[0].snapshot.clone().unwrap();
+        assert!(progress.tables[0].complete);
+
+        // A complete table isn't copied again.
+        let (ingestor, mut iterator) = Ingestor::initialize_channel(IngestionConfig::default());
+        resumable_snapshotter(&conn_config, &ingestor)
+            .sync_tables(&input_tables, None, Some(progress.tables.clone()))
+            .await
+            .unwrap();
+        drop(ingestor);
+        while let Some(message) = iterator.next_timeout(Duration::from_secs(1)).await {
+            assert!(!matches!(
+                message,
+                IngestionMessage::OperationEvent { .. } | IngestionMessage::Truncate { .. }
+            ));
+        }
+
+        // A partially copied table is copied again from the start.
+        progress.tables[0].complete = false;
+        let (ingestor, mut iterator) = Ingestor::initialize_channel(IngestionConfig::default());
+        resumable_snapshotter(&conn_config, &ingestor)
+            .sync_tables(&input_tables, None, Some(progress.tables))
+            .await
+            .unwrap();
+        drop(ingestor);
+        let mut num_rows = 0;
+        let mut truncated = false;
+        let mut last_state = None;
+        while let Some(message) = iterator.next_timeout(Duration::from_secs(1)).await {
+            match message {
+                IngestionMessage::OperationEvent {
+                    op: Operation::BatchInsert { new },
+                    ..
+                } => num_rows += new.len(),
+                IngestionMessage::Truncate { table_index: 0 } => {
+                    assert_eq!(num_rows, 0);
+                    truncated = true;
+                }
+                IngestionMessage::TransactionInfo(TransactionInfo::SourceStateChanged {
+                    state,
+                }) => last_state = Some(ConnectorState::deserialize(state).unwrap()),
+                _ => (),
+            }
+        }
+        assert!(truncated);
+        assert_eq!(num_rows, 5);
+        let table = &last_state.unwrap().snapshot.unwrap().tables[0];
+        assert!(table.complete);
+        assert_eq!(table.lsn, Some(0));
+    }
 }
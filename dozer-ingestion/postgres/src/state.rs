@@ -0,0 +1,119 @@
+use dozer_ingestion_connector::dozer_types::{
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+use postgres_types::PgLsn;
+
+use crate::{schema::helper::PostgresTableInfo, PostgresConnectorError};
+
+/// The state of a [`PostgresConnector`](crate::connector::PostgresConnector), as returned by `serialize_state`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "dozer_ingestion_connector::dozer_types::serde")]
+pub struct ConnectorState {
+    pub slot_name: String,
+    /// Progress of the initial snapshot, once a table of it has been copied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<SnapshotProgress>,
+}
+
+impl ConnectorState {
+    pub fn new(slot_name: String) -> Self {
+        Self {
+            slot_name,
+            snapshot: None,
+        }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("connector state must be serializable")
+    }
+
+    pub fn deserialize(state: Vec<u8>) -> Result<Self, PostgresConnectorError> {
+        if state.first() == Some(&b'{') {
+            serde_json::from_slice(&state).map_err(PostgresConnectorError::StateDeserialization)
+        } else {
+            // The state used to be the slot name only.
+            Ok(Self::new(String::from_utf8(state)?))
+        }
+    }
+}
+
+/// An initial snapshot taken at the consistent point of the replication slot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "dozer_ingestion_connector::dozer_types::serde")]
+pub struct SnapshotProgress {
+    /// The consistent point of the slot, where replication starts once the snapshot is done.
+    pub lsn: u64,
+    /// The snapshotted tables, in the order of the connector's tables.
+    pub tables: Vec<TableProgress>,
+}
+
+impl SnapshotProgress {
+    pub fn lsn(&self) -> PgLsn {
+        self.lsn.into()
+    }
+
+    /// Whether this is the progress of a snapshot of `tables`.
+    pub fn matches(&self, tables: &[PostgresTableInfo]) -> bool {
+        self.tables.len() == tables.len()
+            && self.tables.iter().zip(tables).all(|(progress, table)| {
+                progress.schema == table.schema && progress.name == table.name
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "dozer_ingestion_connector::dozer_types::serde")]
+pub struct TableProgress {
+    pub schema: String,
+    pub name: String,
+    /// Whether the table is completely copied. A partially copied table is copied again from the start
+    /// when the snapshot is resumed, as the chunks copied so far can't be reconciled with a newer snapshot.
+    pub complete: bool,
+    /// The consistent point of the snapshot the table is copied in, if it's not the slot's.
+    ///
+    /// That's the case when the copy restarted in a newer snapshot. Changes committed before it are part of the copy,
+    /// so they aren't replicated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lsn: Option<u64>,
+}
+
+impl TableProgress {
+    pub fn lsn(&self) -> Option<PgLsn> {
+        self.lsn.map(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_roundtrip() {
+        let state = ConnectorState {
+            slot_name: "dozer_slot_test_abcdefg".to_string(),
+            snapshot: Some(SnapshotProgress {
+                lsn: 42,
+                tables: vec![TableProgress {
+                    schema: "public".to_string(),
+                    name: "users".to_string(),
+                    complete: false,
+                    lsn: Some(84),
+                }],
+            }),
+        };
+        assert_eq!(
+            ConnectorState::deserialize(state.serialize()).unwrap(),
+            state
+        );
+    }
+
+    #[test]
+    fn test_state_from_slot_name() {
+        let state = ConnectorState::deserialize(b"dozer_slot_test_abcdefg".to_vec()).unwrap();
+        assert_eq!(
+            state,
+            ConnectorState::new("dozer_slot_test_abcdefg".to_string())
+        );
+    }
+}
@@ -28,6 +28,8 @@ mod tests {
             config: conn_config.clone(),
             schema: None,
             batch_size: 1000,
+            snapshot_chunk_size: 1000,
+            snapshot_parallelism: 1,
         };
 
         let connector = PostgresConnector::new(postgres_config, None).unwrap();
@@ -83,6 +85,8 @@ mod tests {
             config: conn_config.clone(),
             schema: None,
             batch_size: 1000,
+            snapshot_chunk_size: 1000,
+            snapshot_parallelism: 1,
         };
 
         let connector = PostgresConnector::new(postgres_config, None).unwrap();
@@ -1,26 +1,61 @@
-use dozer_ingestion_connector::dozer_types::types::{Field, Operation, Record};
+use dozer_ingestion_connector::dozer_types::types::{
+    Field, FieldDefinition, Operation, Record, Schema, SourceDefinition,
+};
 use postgres_protocol::message::backend::LogicalReplicationMessage::{
-    Begin, Commit, Delete, Insert, Relation, Update,
+    Begin, Commit, Delete, Insert, Relation, Truncate, Update,
 };
 use postgres_protocol::message::backend::{
-    LogicalReplicationMessage, RelationBody, ReplicaIdentity, TupleData, UpdateBody, XLogDataBody,
+    LogicalReplicationMessage, RelationBody, ReplicaIdentity, TupleData, TypeBody, UpdateBody,
 };
 use postgres_protocol::Lsn;
 use postgres_types::Type;
-use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 
 use crate::{
     helper::{self, postgres_type_to_dozer_type},
+    schema::helper::custom_type_kind,
     PostgresConnectorError, PostgresSchemaError,
 };
 
 #[derive(Debug)]
 pub struct Table {
     columns: Vec<TableColumn>,
+    /// Names of all the columns of the relation, including the ones that are not replicated.
+    all_columns: Vec<String>,
     replica_identity: ReplicaIdentity,
 }
 
+impl Table {
+    fn has_same_columns(&self, other: &Table) -> bool {
+        self.columns.len() == other.columns.len()
+            && self
+                .columns
+                .iter()
+                .zip(&other.columns)
+                .all(|(column, other)| column.name == other.name && column.r#type == other.r#type)
+    }
+
+    /// The schema of the replicated columns.
+    ///
+    /// `Relation` messages don't say whether a column is nullable, so only key columns are reported as not null.
+    fn schema(&self) -> Result<Schema, PostgresConnectorError> {
+        let mut schema = Schema::default();
+        for column in &self.columns {
+            let is_key = column.flags == 1;
+            schema.field(
+                FieldDefinition::new(
+                    column.name.clone(),
+                    postgres_type_to_dozer_type(column.r#type.clone())?,
+                    !is_key,
+                    SourceDefinition::Dynamic,
+                ),
+                is_key,
+            );
+        }
+        Ok(schema)
+    }
+}
+
 #[derive(Debug)]
 pub struct TableColumn {
     pub name: String,
@@ -31,9 +66,11 @@ pub struct TableColumn {
 
 #[derive(Debug, Clone)]
 pub enum MappedReplicationMessage {
-    Begin,
+    Begin(Lsn),
     Commit(Lsn),
     Operation { table_index: usize, op: Operation },
+    SchemaChange { table_index: usize, schema: Schema },
+    Truncate { table_indexes: Vec<usize> },
 }
 
 #[derive(Debug, Default)]
@@ -42,6 +79,8 @@ pub struct XlogMapper {
     relations_map: HashMap<u32, Table>,
     /// Relation id to (table index, column names).
     tables_columns: HashMap<u32, (usize, Vec<String>)>,
+    /// User-defined types from replication `Type` messages, by oid.
+    custom_types: HashMap<u32, Type>,
 }
 
 impl XlogMapper {
@@ -49,22 +88,26 @@ impl XlogMapper {
         XlogMapper {
             relations_map: HashMap::<u32, Table>::new(),
             tables_columns,
+            custom_types: HashMap::new(),
         }
     }
 
     pub fn handle_message(
         &mut self,
-        message: XLogDataBody<LogicalReplicationMessage>,
+        message: &LogicalReplicationMessage,
     ) -> Result<Option<MappedReplicationMessage>, PostgresConnectorError> {
-        match &message.data() {
+        match message {
             Relation(relation) => {
-                self.ingest_schema(relation)?;
+                return self.ingest_schema(relation);
             }
+            // Mapped by `ingest_type`, with the kind of the type read by the caller.
+            LogicalReplicationMessage::Type(_) => {}
             Commit(commit) => {
                 return Ok(Some(MappedReplicationMessage::Commit(commit.end_lsn())));
             }
-            Begin(_begin) => {
-                return Ok(Some(MappedReplicationMessage::Begin));
+            Begin(begin) => {
+                // Carries the commit LSN of the transaction.
+                return Ok(Some(MappedReplicationMessage::Begin(begin.final_lsn())));
             }
             Insert(insert) => {
                 let Some(table_columns) = self.tables_columns.get(&insert.rel_id()) else {
@@ -129,45 +172,105 @@ impl XlogMapper {
                     op: event,
                 }));
             }
+            Truncate(truncate) => {
+                let table_indexes = truncate
+                    .rel_ids()
+                    .iter()
+                    .filter_map(|rel_id| self.tables_columns.get(rel_id))
+                    .map(|(table_index, _)| *table_index)
+                    .collect::<Vec<_>>();
+                if !table_indexes.is_empty() {
+                    return Ok(Some(MappedReplicationMessage::Truncate { table_indexes }));
+                }
+            }
             _ => {}
         }
 
         Ok(None)
     }
 
-    fn ingest_schema(&mut self, relation: &RelationBody) -> Result<(), PostgresConnectorError> {
-        let rel_id = relation.rel_id();
-        let Some((table_index, wanted_columns)) = self.tables_columns.get(&rel_id) else {
+    /// Postgres sends a `Type` message before the first `Relation` message that uses a user-defined type.
+    ///
+    /// The message doesn't say what kind of type it is, so the caller reads its `pg_type.typtype` and
+    /// `pg_type.typcategory`. Types are mapped as in the schema, and relations using an unsupported
+    /// type fail.
+    pub fn ingest_type(
+        &mut self,
+        typ: &TypeBody,
+        type_type: Option<char>,
+        category: Option<char>,
+    ) -> Result<(), PostgresConnectorError> {
+        let name = typ
+            .name()
+            .map_err(|_| PostgresSchemaError::InvalidColumnType(typ.id().to_string()))?;
+        let namespace = typ
+            .namespace()
+            .map_err(|_| PostgresSchemaError::InvalidColumnType(name.to_string()))?;
+        let Some(kind) = custom_type_kind(type_type, category) else {
             return Ok(());
         };
+        self.custom_types.insert(
+            typ.id(),
+            Type::new(name.to_string(), typ.id(), kind, namespace.to_string()),
+        );
+        Ok(())
+    }
 
-        let mut columns = vec![];
+    fn ingest_schema(
+        &mut self,
+        relation: &RelationBody,
+    ) -> Result<Option<MappedReplicationMessage>, PostgresConnectorError> {
+        let rel_id = relation.rel_id();
+        let Some((table_index, wanted_columns)) = self.tables_columns.get_mut(&rel_id) else {
+            return Ok(None);
+        };
+        let table_index = *table_index;
+
+        let mut all_columns = vec![];
         for (column_index, column) in relation.columns().iter().enumerate() {
             let column_name =
                 column
                     .name()
                     .map_err(|_| PostgresConnectorError::NonUtf8ColumnName {
-                        table_index: *table_index,
+                        table_index,
                         column_index,
                     })?;
+            all_columns.push(column_name.to_string());
+        }
 
+        let existing = self.relations_map.get(&rel_id);
+        // An empty list replicates all columns anyway.
+        if let Some(existing) = existing.filter(|_| !wanted_columns.is_empty()) {
+            // A table that replicated all its columns, as when configured without `columns`, also
+            // replicates the columns added upstream. Otherwise only the configured columns are kept.
+            // Dropped columns are forgotten.
+            let replicates_all_columns = existing
+                .all_columns
+                .iter()
+                .all(|column_name| wanted_columns.contains(column_name));
+            wanted_columns.retain(|wanted| all_columns.contains(wanted));
+            if replicates_all_columns {
+                for column_name in &all_columns {
+                    if !existing.all_columns.contains(column_name) {
+                        wanted_columns.push(column_name.clone());
+                    }
+                }
+            }
+        }
+
+        let mut columns = vec![];
+        for (column_index, column) in relation.columns().iter().enumerate() {
+            let column_name = &all_columns[column_index];
             if !wanted_columns.is_empty()
-                && !wanted_columns
-                    .iter()
-                    .any(|column| column.as_str() == column_name)
+                && !wanted_columns.iter().any(|column| column == column_name)
             {
                 continue;
             }
 
-            // TODO: workaround - in case of custom enum
             let type_oid = column.type_id() as u32;
-            let typ = if type_oid == 28862 {
-                Type::VARCHAR
-            } else {
-                Type::from_oid(type_oid).ok_or_else(|| {
-                    PostgresSchemaError::InvalidColumnType(column_name.to_string())
-                })?
-            };
+            let typ = Type::from_oid(type_oid)
+                .or_else(|| self.custom_types.get(&type_oid).cloned())
+                .ok_or_else(|| PostgresSchemaError::InvalidColumnType(column_name.to_string()))?;
 
             columns.push(TableColumn {
                 name: column_name.to_string(),
@@ -194,6 +297,7 @@ impl XlogMapper {
 
         let table = Table {
             columns,
+            all_columns,
             replica_identity,
         };
 
@@ -201,28 +305,20 @@ impl XlogMapper {
             postgres_type_to_dozer_type(c.r#type.clone())?;
         }
 
-        match self.relations_map.entry(rel_id) {
-            Entry::Occupied(mut entry) => {
-                // Check if type has changed.
-                for (existing_column, column) in entry.get().columns.iter().zip(&table.columns) {
-                    if existing_column.r#type != column.r#type {
-                        return Err(PostgresConnectorError::ColumnTypeChanged {
-                            table_index: *table_index,
-                            column_name: existing_column.name.clone(),
-                            old_type: existing_column.r#type.clone(),
-                            new_type: column.r#type.clone(),
-                        });
-                    }
-                }
-
-                entry.insert(table);
-            }
-            Entry::Vacant(entry) => {
-                entry.insert(table);
+        // Postgres sends a `Relation` message before the first change of a table in every replication session,
+        // and again after its definition changed. Only the latter is a schema change.
+        let message = match existing {
+            Some(existing) if !existing.has_same_columns(&table) => {
+                Some(MappedReplicationMessage::SchemaChange {
+                    table_index,
+                    schema: table.schema()?,
+                })
             }
-        }
+            _ => None,
+        };
+        self.relations_map.insert(rel_id, table);
 
-        Ok(())
+        Ok(message)
     }
 
     fn convert_values_to_fields(
@@ -269,3 +365,325 @@ impl XlogMapper {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use dozer_ingestion_connector::dozer_types::bytes::{BufMut, BytesMut};
+    use dozer_ingestion_connector::dozer_types::json_types::json;
+    use dozer_ingestion_connector::dozer_types::types::FieldType;
+    use postgres_types::Kind;
+
+    use super::*;
+
+    const REL_ID: u32 = 16384;
+
+    /// Encodes a `Relation` message with columns of (flags, name, type).
+    fn relation(columns: &[(i8, &str, Type)]) -> LogicalReplicationMessage {
+        let mut buf = BytesMut::new();
+        buf.put_u8(b'R');
+        buf.put_u32(REL_ID);
+        buf.put_slice(b"public\0users\0");
+        buf.put_u8(b'd');
+        buf.put_i16(columns.len() as i16);
+        for (flags, name, typ) in columns {
+            buf.put_i8(*flags);
+            buf.put_slice(name.as_bytes());
+            buf.put_u8(0);
+            buf.put_u32(typ.oid());
+            buf.put_i32(-1);
+        }
+        LogicalReplicationMessage::parse(&buf.freeze()).unwrap()
+    }
+
+    /// Encodes an `Insert` message, `None` being null.
+    fn insert(values: &[Option<&str>]) -> LogicalReplicationMessage {
+        let mut buf = BytesMut::new();
+        buf.put_u8(b'I');
+        buf.put_u32(REL_ID);
+        buf.put_u8(b'N');
+        buf.put_i16(values.len() as i16);
+        for value in values {
+            match value {
+                Some(value) => {
+                    buf.put_u8(b't');
+                    buf.put_i32(value.len() as i32);
+                    buf.put_slice(value.as_bytes());
+                }
+                None => buf.put_u8(b'n'),
+            }
+        }
+        LogicalReplicationMessage::parse(&buf.freeze()).unwrap()
+    }
+
+    fn mapper() -> XlogMapper {
+        XlogMapper::new(
+            [(REL_ID, (0, vec!["id".to_string(), "name".to_string()]))]
+                .into_iter()
+                .collect(),
+        )
+    }
+
+    fn inserted(message: Option<MappedReplicationMessage>) -> Vec<Field> {
+        match message {
+            Some(MappedReplicationMessage::Operation {
+                op: Operation::Insert { new },
+                ..
+            }) => new.values,
+            message => panic!("expected insert, got {message:?}"),
+        }
+    }
+
+    fn schema_change(message: Option<MappedReplicationMessage>) -> Schema {
+        match message {
+            Some(MappedReplicationMessage::SchemaChange {
+                table_index: 0,
+                schema,
+            }) => schema,
+            message => panic!("expected schema change, got {message:?}"),
+        }
+    }
+
+    fn field_names(schema: &Schema) -> Vec<&str> {
+        schema
+            .fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_first_relation_is_not_a_schema_change() {
+        let mut mapper = mapper();
+        let message = relation(&[(1, "id", Type::INT4), (0, "name", Type::TEXT)]);
+        assert!(mapper.handle_message(&message).unwrap().is_none());
+        // The same definition is sent again in every replication session.
+        assert!(mapper.handle_message(&message).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_added_column() {
+        let mut mapper = mapper();
+        mapper
+            .handle_message(&relation(&[(1, "id", Type::INT4), (0, "name", Type::TEXT)]))
+            .unwrap();
+
+        let schema = schema_change(
+            mapper
+                .handle_message(&relation(&[
+                    (1, "id", Type::INT4),
+                    (0, "name", Type::TEXT),
+                    (0, "email", Type::VARCHAR),
+                ]))
+                .unwrap(),
+        );
+        assert_eq!(field_names(&schema), vec!["id", "name", "email"]);
+        assert_eq!(schema.primary_index, vec![0]);
+        assert!(!schema.fields[0].nullable);
+        assert!(schema.fields[2].nullable);
+
+        let values = inserted(
+            mapper
+                .handle_message(&insert(&[Some("1"), Some("alice"), None]))
+                .unwrap(),
+        );
+        assert_eq!(
+            values,
+            vec![
+                Field::Int(1),
+                Field::String("alice".to_string()),
+                Field::Null
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dropped_column() {
+        let mut mapper = mapper();
+        mapper
+            .handle_message(&relation(&[(1, "id", Type::INT4), (0, "name", Type::TEXT)]))
+            .unwrap();
+
+        let schema = schema_change(
+            mapper
+                .handle_message(&relation(&[(1, "id", Type::INT4)]))
+                .unwrap(),
+        );
+        assert_eq!(field_names(&schema), vec!["id"]);
+
+        let values = inserted(mapper.handle_message(&insert(&[Some("2")])).unwrap());
+        assert_eq!(values, vec![Field::Int(2)]);
+    }
+
+    #[test]
+    fn test_changed_column_type() {
+        let mut mapper = mapper();
+        mapper
+            .handle_message(&relation(&[(1, "id", Type::INT4), (0, "name", Type::TEXT)]))
+            .unwrap();
+
+        let schema = schema_change(
+            mapper
+                .handle_message(&relation(&[
+                    (1, "id", Type::NUMERIC),
+                    (0, "name", Type::TEXT),
+                ]))
+                .unwrap(),
+        );
+        assert_eq!(schema.fields[0].typ, FieldType::Decimal);
+
+        let values = inserted(
+            mapper
+                .handle_message(&insert(&[Some("1.5"), Some("alice")]))
+                .unwrap(),
+        );
+        assert_eq!(values[0], Field::Decimal("1.5".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_dropped_unreplicated_column() {
+        let mut mapper = mapper();
+        mapper
+            .handle_message(&relation(&[
+                (1, "id", Type::INT4),
+                (0, "name", Type::TEXT),
+                (0, "secret", Type::TEXT),
+            ]))
+            .unwrap();
+
+        // A column that was not replicated doesn't become replicated when another one is dropped.
+        assert!(mapper
+            .handle_message(&relation(&[(1, "id", Type::INT4), (0, "name", Type::TEXT)]))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_added_column_of_configured_columns() {
+        let mut mapper = mapper();
+        mapper
+            .handle_message(&relation(&[
+                (1, "id", Type::INT4),
+                (0, "name", Type::TEXT),
+                (0, "secret", Type::TEXT),
+            ]))
+            .unwrap();
+
+        // Only the configured columns are replicated, so an added column isn't.
+        assert!(mapper
+            .handle_message(&relation(&[
+                (1, "id", Type::INT4),
+                (0, "name", Type::TEXT),
+                (0, "secret", Type::TEXT),
+                (0, "email", Type::VARCHAR),
+            ]))
+            .unwrap()
+            .is_none());
+    }
+
+    /// Encodes a `Type` message.
+    fn custom_type(oid: u32, name: &str) -> TypeBody {
+        let mut buf = BytesMut::new();
+        buf.put_u8(b'Y');
+        buf.put_u32(oid);
+        buf.put_slice(b"public\0");
+        buf.put_slice(name.as_bytes());
+        buf.put_u8(0);
+        let LogicalReplicationMessage::Type(typ) =
+            LogicalReplicationMessage::parse(&buf.freeze()).unwrap()
+        else {
+            unreachable!()
+        };
+        typ
+    }
+
+    /// Encodes a `Truncate` message.
+    fn truncate(rel_ids: &[u32]) -> LogicalReplicationMessage {
+        let mut buf = BytesMut::new();
+        buf.put_u8(b'T');
+        buf.put_u32(rel_ids.len() as u32);
+        buf.put_i8(0);
+        for rel_id in rel_ids {
+            buf.put_u32(*rel_id);
+        }
+        LogicalReplicationMessage::parse(&buf.freeze()).unwrap()
+    }
+
+    #[test]
+    fn test_custom_types() {
+        let mut mapper = mapper();
+        let mood = Type::new(
+            "mood".to_string(),
+            16390,
+            Kind::Simple,
+            "public".to_string(),
+        );
+        let moods = Type::new(
+            "_mood".to_string(),
+            16391,
+            Kind::Simple,
+            "public".to_string(),
+        );
+
+        // Unknown until the `Type` messages are received.
+        assert!(mapper
+            .handle_message(&relation(&[(1, "id", mood.clone())]))
+            .is_err());
+
+        mapper
+            .ingest_type(&custom_type(16390, "mood"), Some('e'), Some('E'))
+            .unwrap();
+        mapper
+            .ingest_type(&custom_type(16391, "_mood"), Some('b'), Some('A'))
+            .unwrap();
+        // Composite types aren't supported, even if their name starts with an underscore.
+        let composite = Type::new(
+            "_point3".to_string(),
+            16392,
+            Kind::Simple,
+            "public".to_string(),
+        );
+        mapper
+            .ingest_type(&custom_type(16392, "_point3"), Some('c'), Some('C'))
+            .unwrap();
+        assert!(mapper
+            .handle_message(&relation(&[(1, "id", composite)]))
+            .is_err());
+
+        mapper
+            .handle_message(&relation(&[(1, "id", mood), (0, "name", moods)]))
+            .unwrap();
+
+        let values = inserted(
+            mapper
+                .handle_message(&insert(&[Some("happy"), Some(r#"{sad,"so so"}"#)]))
+                .unwrap(),
+        );
+        assert_eq!(
+            values,
+            vec![
+                Field::String("happy".to_string()),
+                Field::Json(json!(["sad", "so so"]))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut mapper = mapper();
+        mapper
+            .handle_message(&relation(&[(1, "id", Type::INT4), (0, "name", Type::TEXT)]))
+            .unwrap();
+
+        // Relations that aren't replicated are ignored.
+        match mapper.handle_message(&truncate(&[16400, REL_ID])).unwrap() {
+            Some(MappedReplicationMessage::Truncate { table_indexes }) => {
+                assert_eq!(table_indexes, vec![0])
+            }
+            message => panic!("expected truncate, got {message:?}"),
+        }
+        assert!(mapper
+            .handle_message(&truncate(&[16400]))
+            .unwrap()
+            .is_none());
+    }
+}
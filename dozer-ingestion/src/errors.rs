@@ -15,6 +15,10 @@ pub enum ConnectorError {
     #[error("postgres config error: {0}")]
     PostgresConfig(#[from] dozer_ingestion_postgres::PostgresConnectorError),
 
+    #[cfg(feature = "datafusion")]
+    #[error("object store config error: {0}")]
+    ObjectStoreConfig(#[from] dozer_ingestion_object_store::ObjectStoreConnectorError),
+
     #[error("snowflake feature is not enabled")]
     SnowflakeFeatureNotEnabled,
 
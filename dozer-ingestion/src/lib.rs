@@ -1,3 +1,4 @@
+use dozer_ingestion_aerospike::connector::AerospikeConnector;
 #[cfg(feature = "ethereum")]
 use dozer_ingestion_connector::dozer_types::models::ingestion_types::EthProviderConfig;
 use dozer_ingestion_connector::dozer_types::{
@@ -23,6 +24,7 @@ use dozer_ingestion_mongodb::MongodbConnector;
 use dozer_ingestion_mysql::connector::{mysql_connection_opts_from_url, MySQLConnector};
 #[cfg(feature = "datafusion")]
 use dozer_ingestion_object_store::connector::ObjectStoreConnector;
+use dozer_ingestion_oracle::connector::OracleConnector;
 use dozer_ingestion_postgres::{
     connection::helper::map_connection_config,
     connector::{PostgresConfig, PostgresConnector},
@@ -38,6 +40,8 @@ pub mod errors;
 pub use dozer_ingestion_connector::*;
 
 const DEFAULT_POSTGRES_SNAPSHOT_BATCH_SIZE: u32 = 100_000;
+const DEFAULT_POSTGRES_SNAPSHOT_CHUNK_SIZE: u64 = 1_000_000;
+const DEFAULT_POSTGRES_SNAPSHOT_PARALLELISM: u32 = 4;
 
 #[allow(unused_variables)]
 pub fn get_connector(
@@ -55,6 +59,13 @@ pub fn get_connector(
                 config,
                 schema: c.schema,
                 batch_size: c.batch_size.unwrap_or(DEFAULT_POSTGRES_SNAPSHOT_BATCH_SIZE) as usize,
+                snapshot_chunk_size: c
+                    .snapshot_chunk_size
+                    .unwrap_or(DEFAULT_POSTGRES_SNAPSHOT_CHUNK_SIZE),
+                snapshot_parallelism: c
+                    .snapshot_parallelism
+                    .unwrap_or(DEFAULT_POSTGRES_SNAPSHOT_PARALLELISM)
+                    as usize,
             };
 
             if let Some(dbname) = postgres_config.config.get_dbname() {
@@ -111,27 +122,38 @@ pub fn get_connector(
         #[cfg(not(feature = "kafka"))]
         ConnectionConfig::Kafka(_) => Err(ConnectorError::KafkaFeatureNotEnabled),
         #[cfg(feature = "datafusion")]
-        ConnectionConfig::S3Storage(object_store_config) => {
-            Ok(Box::new(ObjectStoreConnector::new(object_store_config)))
-        }
+        ConnectionConfig::S3Storage(object_store_config) => Ok(Box::new(
+            ObjectStoreConnector::with_state(object_store_config, state)?,
+        )),
         #[cfg(feature = "datafusion")]
-        ConnectionConfig::LocalStorage(object_store_config) => {
-            Ok(Box::new(ObjectStoreConnector::new(object_store_config)))
-        }
+        ConnectionConfig::LocalStorage(object_store_config) => Ok(Box::new(
+            ObjectStoreConnector::with_state(object_store_config, state)?,
+        )),
         #[cfg(feature = "datafusion")]
-        ConnectionConfig::DeltaLake(delta_lake_config) => {
-            Ok(Box::new(DeltaLakeConnector::new(delta_lake_config)))
-        }
+        ConnectionConfig::GcsStorage(object_store_config) => Ok(Box::new(
+            ObjectStoreConnector::with_state(object_store_config, state)?,
+        )),
+        #[cfg(feature = "datafusion")]
+        ConnectionConfig::AzureStorage(object_store_config) => Ok(Box::new(
+            ObjectStoreConnector::with_state(object_store_config, state)?,
+        )),
+        #[cfg(feature = "datafusion")]
+        ConnectionConfig::DeltaLake(delta_lake_config) => Ok(Box::new(
+            DeltaLakeConnector::with_state(delta_lake_config, state),
+        )),
         #[cfg(not(feature = "datafusion"))]
         ConnectionConfig::DeltaLake(_) => Err(ConnectorError::DatafusionFeatureNotEnabled),
         #[cfg(not(feature = "datafusion"))]
         ConnectionConfig::LocalStorage(_) => Err(ConnectorError::DatafusionFeatureNotEnabled),
         #[cfg(not(feature = "datafusion"))]
         ConnectionConfig::S3Storage(_) => Err(ConnectorError::DatafusionFeatureNotEnabled),
+        #[cfg(not(feature = "datafusion"))]
+        ConnectionConfig::GcsStorage(_) => Err(ConnectorError::DatafusionFeatureNotEnabled),
+        #[cfg(not(feature = "datafusion"))]
+        ConnectionConfig::AzureStorage(_) => Err(ConnectorError::DatafusionFeatureNotEnabled),
         #[cfg(feature = "mongodb")]
         ConnectionConfig::MongoDB(mongodb_config) => {
-            let connection_string = mongodb_config.connection_string;
-            Ok(Box::new(MongodbConnector::new(connection_string)?))
+            Ok(Box::new(MongodbConnector::new(mongodb_config)?))
         }
         #[cfg(not(feature = "mongodb"))]
         ConnectionConfig::MongoDB(_) => Err(ConnectorError::MongodbFeatureNotEnabled),
@@ -153,10 +175,12 @@ pub fn get_connector(
             runtime,
             javascript_config,
         ))),
-        ConnectionConfig::Aerospike(_) => {
-            Err(ConnectorError::FeatureNotEnabled("Aerospike".to_string()))
+        ConnectionConfig::Aerospike(aerospike_config) => {
+            Ok(Box::new(AerospikeConnector::new(aerospike_config)))
+        }
+        ConnectionConfig::Oracle(oracle_config) => {
+            Ok(Box::new(OracleConnector::new(oracle_config)))
         }
-        ConnectionConfig::Oracle(_) => Err(ConnectorError::FeatureNotEnabled("Oracle".to_string())),
     }
 }
 
@@ -171,6 +195,8 @@ pub fn get_connector_info_table(connection: &Connection) -> Option<Table> {
         ConnectionConfig::Kafka(config) => Some(config.convert_to_table()),
         ConnectionConfig::S3Storage(config) => Some(config.convert_to_table()),
         ConnectionConfig::LocalStorage(config) => Some(config.convert_to_table()),
+        ConnectionConfig::GcsStorage(config) => Some(config.convert_to_table()),
+        ConnectionConfig::AzureStorage(config) => Some(config.convert_to_table()),
         _ => None,
     }
 }
@@ -1,4 +1,4 @@
-use dozer_ingestion_connector::async_trait;
+use dozer_ingestion_connector::{async_trait, dozer_types::models::ingestion_types::MongodbConfig};
 use dozer_ingestion_mongodb::{
     bson::{self, doc},
     mongodb::{
@@ -105,7 +105,11 @@ async fn create_mongodb_server() -> (mongodb::Database, MongodbConnectorTest, Mo
 
     let client = mongodb::Client::with_options(connection_options.clone()).unwrap();
     let db = client.default_database().unwrap();
-    let connector = MongodbConnector::new(connection_string).unwrap();
+    let connector = MongodbConnector::new(MongodbConfig {
+        connection_string,
+        typed_columns: None,
+    })
+    .unwrap();
     let test = MongodbConnectorTest {
         _cleanup: cleanup,
         _temp_dir: temp_dir,
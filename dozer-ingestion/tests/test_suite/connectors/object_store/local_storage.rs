@@ -96,6 +96,7 @@ fn create_connector(
                 marker_extension: None,
             }),
             name: table_name,
+            change_tracking: None,
         }],
     };
     let connector = ObjectStoreConnector::new(local_storage);
@@ -137,6 +137,8 @@ async fn create_postgres_server() -> (Client, PostgresConnectorTest, PostgresCon
             config: config.clone(),
             schema: None,
             batch_size: 1000,
+            snapshot_chunk_size: 1000,
+            snapshot_parallelism: 1,
         },
         None,
     )
@@ -0,0 +1,79 @@
+use aerospike::Value;
+use dozer_types::indexmap::IndexMap;
+
+use crate::client::{BinMap, Client, RecordKey};
+use crate::errors::AerospikeSinkError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Write {
+    Put(Vec<(String, Value)>),
+    Delete,
+}
+
+/// Writes accumulated between two flushes, keeping only the last write of every record.
+///
+/// Reads go through the batch first, so denormalization lookups observe the writes
+/// that are not yet flushed.
+#[derive(Debug, Default)]
+pub(crate) struct Batch {
+    writes: IndexMap<RecordKey, Write>,
+}
+
+impl Batch {
+    pub fn put(&mut self, key: RecordKey, bins: Vec<(String, Value)>) {
+        self.writes.insert(key, Write::Put(bins));
+    }
+
+    pub fn delete(&mut self, key: RecordKey) {
+        self.writes.insert(key, Write::Delete);
+    }
+
+    pub fn read(
+        &self,
+        client: &dyn Client,
+        key: &RecordKey,
+    ) -> Result<Option<BinMap>, AerospikeSinkError> {
+        match self.writes.get(key) {
+            Some(Write::Put(bins)) => Ok(Some(
+                bins.iter()
+                    .filter(|(_, value)| !matches!(value, Value::Nil))
+                    .cloned()
+                    .collect(),
+            )),
+            Some(Write::Delete) => Ok(None),
+            None => client.get(key),
+        }
+    }
+
+    /// Writes all pending records to `client`, spread over `n_threads` threads.
+    pub fn flush(
+        &mut self,
+        client: &dyn Client,
+        n_threads: usize,
+    ) -> Result<(), AerospikeSinkError> {
+        let writes = self.writes.drain(..).collect::<Vec<_>>();
+        if writes.is_empty() {
+            return Ok(());
+        }
+        let chunk_size = writes.len().div_ceil(n_threads.max(1));
+        std::thread::scope(|scope| {
+            let handles = writes
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || write_all(client, chunk)))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .try_for_each(|handle| handle.join().expect("aerospike writer panicked"))
+        })
+    }
+}
+
+fn write_all(client: &dyn Client, writes: &[(RecordKey, Write)]) -> Result<(), AerospikeSinkError> {
+    for (key, write) in writes {
+        match write {
+            Write::Put(bins) => client.put(key, bins)?,
+            Write::Delete => client.delete(key)?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use aerospike::{Bin, ClientPolicy, ErrorKind, Key, ReadPolicy, ResultCode, Value, WritePolicy};
+use dozer_types::log::debug;
+
+use crate::errors::AerospikeSinkError;
+
+/// Identifies an Aerospike record.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RecordKey {
+    pub namespace: String,
+    pub set: String,
+    pub key: Value,
+}
+
+impl RecordKey {
+    pub fn new(namespace: impl Into<String>, set: impl Into<String>, key: Value) -> Self {
+        Self {
+            namespace: namespace.into(),
+            set: set.into(),
+            key,
+        }
+    }
+}
+
+pub type BinMap = HashMap<String, Value>;
+
+/// The subset of Aerospike operations used by the sink.
+///
+/// Abstracted so the sink can be exercised against an in-process store.
+pub trait Client: Send + Sync + Debug {
+    /// Writes `bins` to the record, creating it if it doesn't exist. A `Value::Nil` bin removes that bin.
+    fn put(&self, key: &RecordKey, bins: &[(String, Value)]) -> Result<(), AerospikeSinkError>;
+    /// Reads all bins of the record, or `None` if it doesn't exist.
+    fn get(&self, key: &RecordKey) -> Result<Option<BinMap>, AerospikeSinkError>;
+    /// Deletes the record. Deleting a non-existing record is not an error.
+    fn delete(&self, key: &RecordKey) -> Result<(), AerospikeSinkError>;
+    /// Deletes all records of the set written before the call.
+    fn truncate(&self, namespace: &str, set: &str) -> Result<(), AerospikeSinkError>;
+}
+
+pub struct AerospikeClient {
+    inner: aerospike::Client,
+    write_policy: WritePolicy,
+    read_policy: ReadPolicy,
+}
+
+impl Debug for AerospikeClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AerospikeClient").finish()
+    }
+}
+
+impl AerospikeClient {
+    pub fn new(hosts: &str) -> Result<Self, AerospikeSinkError> {
+        debug!("Connecting to aerospike hosts {hosts}");
+        let inner = aerospike::Client::new(&ClientPolicy::default(), &hosts)
+            .map_err(|e| AerospikeSinkError::Aerospike(e.to_string()))?;
+        Ok(Self {
+            inner,
+            write_policy: WritePolicy::default(),
+            read_policy: ReadPolicy::default(),
+        })
+    }
+}
+
+fn to_aerospike_key(key: &RecordKey) -> Result<Key, AerospikeSinkError> {
+    Key::new(key.namespace.as_str(), key.set.as_str(), key.key.clone())
+        .map_err(|e| AerospikeSinkError::Aerospike(e.to_string()))
+}
+
+impl Client for AerospikeClient {
+    fn put(&self, key: &RecordKey, bins: &[(String, Value)]) -> Result<(), AerospikeSinkError> {
+        let key = to_aerospike_key(key)?;
+        let bins = bins
+            .iter()
+            .map(|(name, value)| Bin::new(name, value.clone()))
+            .collect::<Vec<_>>();
+        self.inner
+            .put(&self.write_policy, &key, &bins)
+            .map_err(|e| AerospikeSinkError::Aerospike(e.to_string()))
+    }
+
+    fn get(&self, key: &RecordKey) -> Result<Option<BinMap>, AerospikeSinkError> {
+        let key = to_aerospike_key(key)?;
+        match self
+            .inner
+            .get(&self.read_policy, &key, aerospike::Bins::All)
+        {
+            Ok(record) => Ok(Some(record.bins)),
+            Err(aerospike::Error(ErrorKind::ServerError(ResultCode::KeyNotFoundError), _)) => {
+                Ok(None)
+            }
+            Err(e) => Err(AerospikeSinkError::Aerospike(e.to_string())),
+        }
+    }
+
+    fn delete(&self, key: &RecordKey) -> Result<(), AerospikeSinkError> {
+        let key = to_aerospike_key(key)?;
+        self.inner
+            .delete(&self.write_policy, &key)
+            .map(|_existed| ())
+            .map_err(|e| AerospikeSinkError::Aerospike(e.to_string()))
+    }
+
+    fn truncate(&self, namespace: &str, set: &str) -> Result<(), AerospikeSinkError> {
+        // A zero cutoff is the current time of the server.
+        self.inner
+            .truncate(namespace, set, 0)
+            .map_err(|e| AerospikeSinkError::Aerospike(e.to_string()))
+    }
+}
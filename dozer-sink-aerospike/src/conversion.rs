@@ -0,0 +1,120 @@
+use aerospike::Value;
+use dozer_types::chrono::{DateTime, NaiveDate};
+use dozer_types::json_types::{json_from_str, json_to_string};
+use dozer_types::ordered_float::OrderedFloat;
+use dozer_types::rust_decimal::Decimal;
+use dozer_types::types::{DozerDuration, DozerPoint, Field, FieldType, TimeUnit, DATE_FORMAT};
+
+use crate::errors::AerospikeSinkError;
+
+/// Maps a dozer field to the Aerospike value stored in its bin.
+///
+/// Types without a native Aerospike counterpart are stored as strings (decimal, timestamp, date, json),
+/// big-endian blobs (128-bit integers) or GeoJSON (point).
+pub fn field_to_value(field: &Field) -> Result<Value, AerospikeSinkError> {
+    let value = match field {
+        Field::UInt(v) => Value::Int(
+            i64::try_from(*v).map_err(|_| AerospikeSinkError::IntegerOverflow(field.clone()))?,
+        ),
+        Field::U128(v) => Value::Blob(v.to_be_bytes().to_vec()),
+        Field::Int(v) => Value::Int(*v),
+        Field::Int8(v) => Value::Int(*v as i64),
+        Field::I128(v) => Value::Blob(v.to_be_bytes().to_vec()),
+        Field::Float(v) => Value::from(v.0),
+        Field::Boolean(v) => Value::Bool(*v),
+        Field::String(v) | Field::Text(v) => Value::String(v.clone()),
+        Field::Binary(v) => Value::Blob(v.clone()),
+        Field::Decimal(v) => Value::String(v.to_string()),
+        Field::Timestamp(v) => Value::String(v.to_rfc3339()),
+        Field::Date(v) => Value::String(v.format(DATE_FORMAT).to_string()),
+        Field::Json(v) => Value::String(json_to_string(v)),
+        Field::Point(DozerPoint(p)) => Value::GeoJSON(format!(
+            r#"{{"type":"Point","coordinates":[{},{}]}}"#,
+            p.x().0,
+            p.y().0
+        )),
+        Field::Duration(DozerDuration(d, _)) => Value::Int(
+            i64::try_from(d.as_nanos())
+                .map_err(|_| AerospikeSinkError::IntegerOverflow(field.clone()))?,
+        ),
+        Field::Null => Value::Nil,
+    };
+    Ok(value)
+}
+
+/// Inverse of [`field_to_value`].
+pub fn value_to_field(value: &Value, typ: FieldType) -> Result<Field, AerospikeSinkError> {
+    let error = || AerospikeSinkError::ValueConversion {
+        value: value.to_string(),
+        typ,
+    };
+    if matches!(value, Value::Nil) {
+        return Ok(Field::Null);
+    }
+    let field = match (typ, value) {
+        (FieldType::UInt, Value::Int(v)) => Field::UInt(u64::try_from(*v).map_err(|_| error())?),
+        (FieldType::U128, Value::Blob(v)) => Field::U128(u128::from_be_bytes(
+            v.as_slice().try_into().map_err(|_| error())?,
+        )),
+        (FieldType::Int, Value::Int(v)) => Field::Int(*v),
+        (FieldType::Int8, Value::Int(v)) => Field::Int8(i8::try_from(*v).map_err(|_| error())?),
+        (FieldType::I128, Value::Blob(v)) => Field::I128(i128::from_be_bytes(
+            v.as_slice().try_into().map_err(|_| error())?,
+        )),
+        (FieldType::Float, Value::Float(v)) => Field::Float(OrderedFloat(f64::from(v))),
+        (FieldType::Float, Value::Int(v)) => Field::Float(OrderedFloat(*v as f64)),
+        (FieldType::Boolean, Value::Bool(v)) => Field::Boolean(*v),
+        (FieldType::Boolean, Value::Int(v)) => Field::Boolean(*v != 0),
+        (FieldType::String, Value::String(v)) => Field::String(v.clone()),
+        (FieldType::Text, Value::String(v)) => Field::Text(v.clone()),
+        (FieldType::Binary, Value::Blob(v)) => Field::Binary(v.clone()),
+        (FieldType::Decimal, Value::String(v)) => {
+            Field::Decimal(v.parse::<Decimal>().map_err(|_| error())?)
+        }
+        (FieldType::Timestamp, Value::String(v)) => {
+            Field::Timestamp(DateTime::parse_from_rfc3339(v).map_err(|_| error())?)
+        }
+        (FieldType::Date, Value::String(v)) => {
+            Field::Date(NaiveDate::parse_from_str(v, DATE_FORMAT).map_err(|_| error())?)
+        }
+        (FieldType::Json, Value::String(v)) => Field::Json(json_from_str(v).map_err(|_| error())?),
+        (FieldType::Point, Value::GeoJSON(v)) => {
+            Field::Point(parse_geojson_point(v).ok_or_else(error)?)
+        }
+        (FieldType::Duration, Value::Int(v)) => Field::Duration(DozerDuration(
+            std::time::Duration::from_nanos(u64::try_from(*v).map_err(|_| error())?),
+            TimeUnit::Nanoseconds,
+        )),
+        _ => return Err(error()),
+    };
+    Ok(field)
+}
+
+fn parse_geojson_point(geojson: &str) -> Option<DozerPoint> {
+    let start = geojson.find('[')?;
+    let end = geojson[start..].find(']')? + start;
+    let mut coordinates = geojson[start + 1..end].split(',');
+    let x = coordinates.next()?.trim().parse::<f64>().ok()?;
+    let y = coordinates.next()?.trim().parse::<f64>().ok()?;
+    Some(DozerPoint::from((x, y)))
+}
+
+/// Builds the user key of a record from its key fields.
+///
+/// A single integer or string field is used as is, so the records can be read by key from other clients.
+/// Other keys are encoded into a blob.
+pub fn key_value(fields: &[&Field]) -> Result<Value, AerospikeSinkError> {
+    if let [field] = fields {
+        match field {
+            Field::UInt(_) | Field::Int(_) | Field::Int8(_) | Field::String(_) | Field::Text(_) => {
+                return field_to_value(field);
+            }
+            _ => (),
+        }
+    }
+    let mut key = vec![];
+    for field in fields {
+        key.extend(field.encode());
+    }
+    Ok(Value::Blob(key))
+}
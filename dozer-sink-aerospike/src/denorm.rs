@@ -0,0 +1,472 @@
+use aerospike::Value;
+use dozer_core::errors::SchemaChangeError;
+use dozer_types::models::sink::{AerospikeSinkTable, DenormKey};
+use dozer_types::types::{Field, FieldType, Schema};
+
+use crate::batch::Batch;
+use crate::client::{Client, RecordKey};
+use crate::conversion::{field_to_value, key_value, value_to_field};
+use crate::errors::AerospikeSinkError;
+
+/// A table written by the sink, resolved against its input schema.
+#[derive(Debug)]
+pub(crate) struct SinkTable {
+    pub namespace: String,
+    pub set_name: String,
+    pub bin_names: Vec<String>,
+    pub key_index: Vec<usize>,
+    pub denormalization: Option<Denormalization>,
+}
+
+/// Looks up columns of another set by a key taken from the written record.
+#[derive(Debug)]
+struct Lookup {
+    namespace: String,
+    set: String,
+    key_index: Vec<usize>,
+    /// Bin to read from the looked up record and its dozer type.
+    columns: Vec<(String, FieldType)>,
+}
+
+/// Columns looked up in other sets, read when a record is written.
+///
+/// Looked up records aren't tracked back to the records that reference them, so a change to a
+/// looked up record only shows in records written after it.
+#[derive(Debug)]
+pub(crate) struct Denormalization {
+    /// The number of fields of the table the denormalization was resolved with.
+    /// Fields appended to the table later aren't denormalized.
+    num_fields: usize,
+    lookups: Vec<Lookup>,
+    /// Bins of the looked up columns, in lookup order.
+    bin_names: Vec<String>,
+    target: Option<DenormTarget>,
+}
+
+/// The set that denormalized records are written to.
+#[derive(Debug)]
+struct DenormTarget {
+    namespace: String,
+    set: String,
+    /// Key columns, indexing into the denormalized record.
+    key_index: Vec<usize>,
+    /// If set, all rows with the same key are stored in one record as lists, one list per bin.
+    /// Rows are identified by the fields at these indexes.
+    aggregate_row_key: Option<Vec<usize>>,
+}
+
+fn resolve_columns(
+    table: &str,
+    schema: &Schema,
+    columns: &[String],
+) -> Result<Vec<usize>, AerospikeSinkError> {
+    columns
+        .iter()
+        .map(|column| {
+            schema
+                .get_field_index(column)
+                .map(|(index, _)| index)
+                .map_err(|_| AerospikeSinkError::ColumnNotFound {
+                    table: table.to_string(),
+                    column: column.clone(),
+                })
+        })
+        .collect()
+}
+
+/// Resolves the sink config of every table against the input schemas, ordered by input port.
+pub(crate) fn build_tables(
+    tables: &[AerospikeSinkTable],
+    schemas: &[Schema],
+) -> Result<Vec<SinkTable>, AerospikeSinkError> {
+    let mut key_indexes = vec![];
+    for (table, schema) in tables.iter().zip(schemas) {
+        let key_index = if table.primary_key.is_empty() {
+            schema.primary_index.clone()
+        } else {
+            resolve_columns(&table.source_table_name, schema, &table.primary_key)?
+        };
+        if key_index.is_empty() {
+            return Err(AerospikeSinkError::MissingPrimaryKey(
+                table.source_table_name.clone(),
+            ));
+        }
+        key_indexes.push(key_index);
+    }
+
+    let mut result = vec![];
+    for ((table, schema), key_index) in tables.iter().zip(schemas).zip(&key_indexes) {
+        let bin_names = schema
+            .fields
+            .iter()
+            .map(|field| field.name.clone())
+            .collect::<Vec<_>>();
+
+        if table.aggregate_by_pk && table.write_denormalized_to.is_none() {
+            return Err(AerospikeSinkError::AggregateWithoutTarget(
+                table.source_table_name.clone(),
+            ));
+        }
+
+        let denormalization = if table.denormalize.is_empty()
+            && table.write_denormalized_to.is_none()
+        {
+            None
+        } else {
+            let mut lookups = vec![];
+            let mut denorm_bin_names = vec![];
+            for denorm in &table.denormalize {
+                let source = tables
+                    .iter()
+                    .position(|t| {
+                        t.namespace == denorm.from_namespace && t.set_name == denorm.from_set
+                    })
+                    .ok_or_else(|| AerospikeSinkError::DenormalizationSourceNotFound {
+                        namespace: denorm.from_namespace.clone(),
+                        set: denorm.from_set.clone(),
+                    })?;
+                let key_columns = match &denorm.key {
+                    DenormKey::Simple(column) => vec![column.clone()],
+                    DenormKey::Composite(columns) => columns.clone(),
+                };
+                if key_columns.len() != key_indexes[source].len() {
+                    return Err(AerospikeSinkError::DenormalizationKeyMismatch {
+                        namespace: denorm.from_namespace.clone(),
+                        set: denorm.from_set.clone(),
+                        expected: key_indexes[source].len(),
+                        actual: key_columns.len(),
+                    });
+                }
+                let key_index = resolve_columns(&table.source_table_name, schema, &key_columns)?;
+
+                let source_table = &tables[source];
+                let source_schema = &schemas[source];
+                let mut columns = vec![];
+                for column in &denorm.columns {
+                    let (src, dst) = column.to_src_dst();
+                    let (_, field) = source_schema.get_field_index(src).map_err(|_| {
+                        AerospikeSinkError::ColumnNotFound {
+                            table: source_table.source_table_name.clone(),
+                            column: src.to_string(),
+                        }
+                    })?;
+                    columns.push((src.to_string(), field.typ));
+                    denorm_bin_names.push(dst.to_string());
+                }
+                lookups.push(Lookup {
+                    namespace: denorm.from_namespace.clone(),
+                    set: denorm.from_set.clone(),
+                    key_index,
+                    columns,
+                });
+            }
+
+            let target = match &table.write_denormalized_to {
+                Some(target) => {
+                    let all_bin_names = bin_names
+                        .iter()
+                        .chain(&denorm_bin_names)
+                        .collect::<Vec<_>>();
+                    let target_key_index = if target.primary_key.is_empty() {
+                        key_index.clone()
+                    } else {
+                        target
+                            .primary_key
+                            .iter()
+                            .map(|column| {
+                                all_bin_names
+                                    .iter()
+                                    .position(|name| *name == column)
+                                    .ok_or_else(|| AerospikeSinkError::ColumnNotFound {
+                                        table: format!("{}.{}", target.namespace, target.set),
+                                        column: column.clone(),
+                                    })
+                            })
+                            .collect::<Result<Vec<_>, _>>()?
+                    };
+                    Some(DenormTarget {
+                        namespace: target.namespace.clone(),
+                        set: target.set.clone(),
+                        key_index: target_key_index,
+                        aggregate_row_key: table.aggregate_by_pk.then(|| key_index.clone()),
+                    })
+                }
+                None => None,
+            };
+
+            Some(Denormalization {
+                num_fields: bin_names.len(),
+                lookups,
+                bin_names: denorm_bin_names,
+                target,
+            })
+        };
+
+        result.push(SinkTable {
+            namespace: table.namespace.clone(),
+            set_name: table.set_name.clone(),
+            bin_names,
+            key_index: key_index.clone(),
+            denormalization,
+        });
+    }
+    Ok(result)
+}
+
+fn record_key(
+    namespace: &str,
+    set: &str,
+    values: &[Field],
+    key_index: &[usize],
+) -> Result<RecordKey, AerospikeSinkError> {
+    let fields = key_index.iter().map(|i| &values[*i]).collect::<Vec<_>>();
+    Ok(RecordKey::new(namespace, set, key_value(&fields)?))
+}
+
+fn to_bins(names: &[String], values: &[Field]) -> Result<Vec<(String, Value)>, AerospikeSinkError> {
+    names
+        .iter()
+        .zip(values)
+        .map(|(name, value)| Ok((name.clone(), field_to_value(value)?)))
+        .collect()
+}
+
+impl SinkTable {
+    /// Writes the fields appended to the input schema to bins of the same name.
+    pub fn append_fields(&mut self, schema: &Schema) -> Result<(), SchemaChangeError> {
+        if schema.fields.len() < self.bin_names.len()
+            || self
+                .bin_names
+                .iter()
+                .zip(&schema.fields)
+                .any(|(name, field)| *name != field.name)
+        {
+            return Err(SchemaChangeError::Unsupported);
+        }
+        let appended = &schema.fields[self.bin_names.len()..];
+        self.bin_names
+            .extend(appended.iter().map(|field| field.name.clone()));
+        Ok(())
+    }
+
+    pub fn insert(
+        &self,
+        batch: &mut Batch,
+        client: &dyn Client,
+        values: &[Field],
+    ) -> Result<(), AerospikeSinkError> {
+        let key = record_key(&self.namespace, &self.set_name, values, &self.key_index)?;
+        let mut bins = to_bins(&self.bin_names, values)?;
+        if let Some(denorm) = &self.denormalization {
+            let table_values = &values[..denorm.num_fields];
+            let denormalized = denorm.denormalize(batch, client, table_values)?;
+            match &denorm.target {
+                Some(target) => target.insert(
+                    batch,
+                    client,
+                    &self.bin_names[..denorm.num_fields],
+                    denorm,
+                    &denormalized,
+                )?,
+                None => bins.extend(to_bins(
+                    &denorm.bin_names,
+                    &denormalized[table_values.len()..],
+                )?),
+            }
+        }
+        batch.put(key, bins);
+        Ok(())
+    }
+
+    /// Deletes all records of the set, and the denormalized records if they have their own set.
+    pub fn truncate(&self, client: &dyn Client) -> Result<(), AerospikeSinkError> {
+        client.truncate(&self.namespace, &self.set_name)?;
+        if let Some(target) = self
+            .denormalization
+            .as_ref()
+            .and_then(|denorm| denorm.target.as_ref())
+        {
+            client.truncate(&target.namespace, &target.set)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete(
+        &self,
+        batch: &mut Batch,
+        client: &dyn Client,
+        values: &[Field],
+    ) -> Result<(), AerospikeSinkError> {
+        batch.delete(record_key(
+            &self.namespace,
+            &self.set_name,
+            values,
+            &self.key_index,
+        )?);
+        if let Some(denorm) = &self.denormalization {
+            if let Some(target) = &denorm.target {
+                let denormalized =
+                    denorm.denormalize(batch, client, &values[..denorm.num_fields])?;
+                target.delete(
+                    batch,
+                    client,
+                    &self.bin_names[..denorm.num_fields],
+                    denorm,
+                    &denormalized,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Denormalization {
+    /// Returns `values` followed by the looked up columns.
+    fn denormalize(
+        &self,
+        batch: &Batch,
+        client: &dyn Client,
+        values: &[Field],
+    ) -> Result<Vec<Field>, AerospikeSinkError> {
+        let mut result = values.to_vec();
+        for lookup in &self.lookups {
+            let key_fields = lookup
+                .key_index
+                .iter()
+                .map(|i| &values[*i])
+                .collect::<Vec<_>>();
+            let bins = if key_fields.iter().any(|field| **field == Field::Null) {
+                None
+            } else {
+                let key = RecordKey::new(&lookup.namespace, &lookup.set, key_value(&key_fields)?);
+                batch.read(client, &key)?
+            };
+            for (bin, typ) in &lookup.columns {
+                let field = match bins.as_ref().and_then(|bins| bins.get(bin)) {
+                    Some(value) => value_to_field(value, *typ)?,
+                    None => Field::Null,
+                };
+                result.push(field);
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl DenormTarget {
+    fn bin_names<'a>(
+        table_bin_names: &'a [String],
+        denorm: &'a Denormalization,
+    ) -> impl Iterator<Item = &'a String> {
+        table_bin_names.iter().chain(&denorm.bin_names)
+    }
+
+    fn insert(
+        &self,
+        batch: &mut Batch,
+        client: &dyn Client,
+        table_bin_names: &[String],
+        denorm: &Denormalization,
+        values: &[Field],
+    ) -> Result<(), AerospikeSinkError> {
+        let key = record_key(&self.namespace, &self.set, values, &self.key_index)?;
+        let row = values
+            .iter()
+            .map(field_to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        let bin_names = Self::bin_names(table_bin_names, denorm);
+        let Some(row_key) = &self.aggregate_row_key else {
+            batch.put(key, bin_names.cloned().zip(row).collect());
+            return Ok(());
+        };
+
+        let mut lists = self.read_lists(batch, client, &key, table_bin_names, denorm)?;
+        match find_row(&lists, row_key, &row) {
+            // Replacing instead of appending keeps replays after a restart idempotent.
+            Some(position) => {
+                for (list, value) in lists.iter_mut().zip(row) {
+                    list[position] = value;
+                }
+            }
+            None => {
+                for (list, value) in lists.iter_mut().zip(row) {
+                    list.push(value);
+                }
+            }
+        }
+        batch.put(
+            key,
+            bin_names
+                .cloned()
+                .zip(lists.into_iter().map(Value::List))
+                .collect(),
+        );
+        Ok(())
+    }
+
+    fn delete(
+        &self,
+        batch: &mut Batch,
+        client: &dyn Client,
+        table_bin_names: &[String],
+        denorm: &Denormalization,
+        values: &[Field],
+    ) -> Result<(), AerospikeSinkError> {
+        let key = record_key(&self.namespace, &self.set, values, &self.key_index)?;
+        let Some(row_key) = &self.aggregate_row_key else {
+            batch.delete(key);
+            return Ok(());
+        };
+
+        let row = values
+            .iter()
+            .map(field_to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut lists = self.read_lists(batch, client, &key, table_bin_names, denorm)?;
+        if let Some(position) = find_row(&lists, row_key, &row) {
+            for list in lists.iter_mut() {
+                list.remove(position);
+            }
+        }
+        if lists.iter().all(Vec::is_empty) {
+            batch.delete(key);
+        } else {
+            let bin_names = Self::bin_names(table_bin_names, denorm);
+            batch.put(
+                key,
+                bin_names
+                    .cloned()
+                    .zip(lists.into_iter().map(Value::List))
+                    .collect(),
+            );
+        }
+        Ok(())
+    }
+
+    fn read_lists(
+        &self,
+        batch: &Batch,
+        client: &dyn Client,
+        key: &RecordKey,
+        table_bin_names: &[String],
+        denorm: &Denormalization,
+    ) -> Result<Vec<Vec<Value>>, AerospikeSinkError> {
+        let mut bins = batch.read(client, key)?.unwrap_or_default();
+        Self::bin_names(table_bin_names, denorm)
+            .map(|name| match bins.remove(name) {
+                Some(Value::List(list)) => Ok(list),
+                None => Ok(vec![]),
+                Some(_) => Err(AerospikeSinkError::NotAList(name.clone())),
+            })
+            .collect()
+    }
+}
+
+fn find_row(lists: &[Vec<Value>], row_key: &[usize], row: &[Value]) -> Option<usize> {
+    let len = lists.first().map_or(0, Vec::len);
+    (0..len).find(|position| {
+        row_key
+            .iter()
+            .all(|index| lists[*index].get(*position) == Some(&row[*index]))
+    })
+}
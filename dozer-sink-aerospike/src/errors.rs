@@ -0,0 +1,42 @@
+use dozer_types::{
+    thiserror::{self, Error},
+    types::{Field, FieldType},
+};
+
+#[derive(Error, Debug)]
+pub enum AerospikeSinkError {
+    #[error("Aerospike error: {0}")]
+    Aerospike(String),
+
+    #[error("Table {0} has no primary key. Set `primary_key` in the sink config or define one in the source")]
+    MissingPrimaryKey(String),
+
+    #[error("Column {column} not found in table {table}")]
+    ColumnNotFound { table: String, column: String },
+
+    #[error("Denormalization source {namespace}.{set} is not written by this sink")]
+    DenormalizationSourceNotFound { namespace: String, set: String },
+
+    #[error("Denormalization key of {namespace}.{set} has {actual} columns, but the key of that set has {expected}")]
+    DenormalizationKeyMismatch {
+        namespace: String,
+        set: String,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("`aggregate_by_pk` requires `write_denormalized_to` to be set for table {0}")]
+    AggregateWithoutTarget(String),
+
+    #[error("Value {0} does not fit in an Aerospike integer")]
+    IntegerOverflow(Field),
+
+    #[error("Cannot convert Aerospike value {value} to {typ}")]
+    ValueConversion { value: String, typ: FieldType },
+
+    #[error("Bin {0} of an aggregated record is not a list")]
+    NotAList(String),
+
+    #[error("Received operation on unknown port {0}")]
+    UnknownPort(u16),
+}
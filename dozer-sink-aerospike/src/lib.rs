@@ -0,0 +1,9 @@
+mod batch;
+pub mod client;
+pub mod conversion;
+mod denorm;
+pub mod errors;
+mod sink;
+pub use sink::AerospikeSinkFactory;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use aerospike::Value;
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{PortHandle, Sink, SinkFactory};
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::log::debug;
+use dozer_types::models::connection::AerospikeConnection;
+use dozer_types::models::sink::AerospikeSinkConfig;
+use dozer_types::node::OpIdentifier;
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Operation, Schema, TableOperation};
+
+use crate::batch::Batch;
+use crate::client::{AerospikeClient, Client, RecordKey};
+use crate::denorm::{build_tables, SinkTable};
+use crate::errors::AerospikeSinkError;
+
+pub const DEFAULT_METADATA_SET: &str = "__replication_metadata";
+const META_TXID_BIN: &str = "txid";
+const META_SEQ_IN_TX_BIN: &str = "seq_in_tx";
+const META_SOURCE_STATE_BIN: &str = "source_state";
+
+#[derive(Debug)]
+pub struct AerospikeSinkFactory {
+    connection: AerospikeConnection,
+    config: AerospikeSinkConfig,
+}
+
+impl AerospikeSinkFactory {
+    pub fn new(connection: AerospikeConnection, config: AerospikeSinkConfig) -> Self {
+        Self { connection, config }
+    }
+
+    fn ordered_schemas(&self, mut input_schemas: HashMap<PortHandle, Schema>) -> Vec<Schema> {
+        self.get_input_ports()
+            .iter()
+            .map(|port| {
+                input_schemas
+                    .remove(port)
+                    .expect("schema must be provided for every input port")
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl SinkFactory for AerospikeSinkFactory {
+    fn type_name(&self) -> String {
+        "aerospike".to_string()
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        (0..self.config.tables.len() as PortHandle).collect()
+    }
+
+    fn get_input_port_name(&self, port: &PortHandle) -> String {
+        self.config.tables[*port as usize].source_table_name.clone()
+    }
+
+    fn prepare(&self, input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        build_tables(&self.config.tables, &self.ordered_schemas(input_schemas))?;
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        let client = AerospikeClient::new(&self.connection.hosts)?;
+        let sink = AerospikeSink::new(
+            &self.config,
+            Arc::new(client),
+            &self.ordered_schemas(input_schemas),
+        )?;
+        Ok(Box::new(sink))
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct AerospikeSink {
+    client: Arc<dyn Client>,
+    tables: Vec<SinkTable>,
+    batch: Batch,
+    n_threads: usize,
+    max_batch_duration_ms: Option<u64>,
+    preferred_batch_size: Option<u64>,
+    metadata_key: RecordKey,
+    latest_op_id: Option<OpIdentifier>,
+    source_state: Option<Vec<u8>>,
+}
+
+impl AerospikeSink {
+    pub fn new(
+        config: &AerospikeSinkConfig,
+        client: Arc<dyn Client>,
+        schemas: &[Schema],
+    ) -> Result<Self, AerospikeSinkError> {
+        let tables = build_tables(&config.tables, schemas)?;
+
+        // The written sets identify the sink, so its checkpoint survives restarts and renames.
+        let metadata_key = config
+            .tables
+            .iter()
+            .map(|table| format!("{}.{}", table.namespace, table.set_name))
+            .collect::<Vec<_>>()
+            .join(",");
+        let metadata_key = RecordKey::new(
+            &config.metadata_namespace,
+            config
+                .metadata_set
+                .as_deref()
+                .unwrap_or(DEFAULT_METADATA_SET),
+            Value::String(metadata_key),
+        );
+
+        Ok(Self {
+            client,
+            tables,
+            batch: Batch::default(),
+            n_threads: config.n_threads.map_or(1, |n| n.get()),
+            max_batch_duration_ms: config.max_batch_duration_ms,
+            preferred_batch_size: config.preferred_batch_size,
+            metadata_key,
+            latest_op_id: None,
+            source_state: None,
+        })
+    }
+
+    fn write_metadata(&self) -> Result<(), AerospikeSinkError> {
+        let mut bins = vec![];
+        if let Some(op_id) = self.latest_op_id {
+            bins.push((
+                META_TXID_BIN.to_string(),
+                Value::Blob(op_id.txid.to_be_bytes().to_vec()),
+            ));
+            bins.push((
+                META_SEQ_IN_TX_BIN.to_string(),
+                Value::Blob(op_id.seq_in_tx.to_be_bytes().to_vec()),
+            ));
+        }
+        if let Some(state) = &self.source_state {
+            bins.push((
+                META_SOURCE_STATE_BIN.to_string(),
+                Value::Blob(state.clone()),
+            ));
+        }
+        if bins.is_empty() {
+            return Ok(());
+        }
+        debug!("[Sink] Writing checkpoint {:?}", self.latest_op_id);
+        self.client.put(&self.metadata_key, &bins)
+    }
+
+    fn read_metadata_bin(&self, bin: &str) -> Result<Option<Vec<u8>>, AerospikeSinkError> {
+        let bins = self.client.get(&self.metadata_key)?;
+        Ok(bins
+            .and_then(|mut bins| bins.remove(bin))
+            .and_then(|value| match value {
+                Value::Blob(bytes) => Some(bytes),
+                _ => None,
+            }))
+    }
+
+    fn read_u64_bin(&self, bin: &str) -> Result<Option<u64>, AerospikeSinkError> {
+        Ok(self
+            .read_metadata_bin(bin)?
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_be_bytes))
+    }
+}
+
+impl Sink for AerospikeSink {
+    fn commit(&mut self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
+        let table = self
+            .tables
+            .get(op.port as usize)
+            .ok_or(AerospikeSinkError::UnknownPort(op.port))?;
+        let client = self.client.as_ref();
+        match op.op {
+            Operation::Insert { new } => table.insert(&mut self.batch, client, &new.values)?,
+            Operation::Delete { old } => table.delete(&mut self.batch, client, &old.values)?,
+            Operation::Update { old, new } => {
+                table.delete(&mut self.batch, client, &old.values)?;
+                table.insert(&mut self.batch, client, &new.values)?;
+            }
+            Operation::BatchInsert { new } => {
+                for record in new {
+                    table.insert(&mut self.batch, client, &record.values)?;
+                }
+            }
+        }
+        if let Some(id) = op.id {
+            self.latest_op_id = Some(id);
+        }
+        Ok(())
+    }
+
+    fn on_schema_change(&mut self, port: PortHandle, schema: &Schema) -> Result<(), BoxedError> {
+        let table = self
+            .tables
+            .get_mut(port as usize)
+            .ok_or(AerospikeSinkError::UnknownPort(port))?;
+        table.append_fields(schema)?;
+        Ok(())
+    }
+
+    fn on_truncate(&mut self, port: PortHandle) -> Result<(), BoxedError> {
+        let table = self
+            .tables
+            .get(port as usize)
+            .ok_or(AerospikeSinkError::UnknownPort(port))?;
+        // Writes after the truncate must survive it, so the pending ones are written before.
+        self.batch.flush(self.client.as_ref(), self.n_threads)?;
+        table.truncate(self.client.as_ref())?;
+        Ok(())
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        if id.is_some() {
+            self.latest_op_id = id;
+        }
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, source_state: &[u8]) -> Result<(), BoxedError> {
+        self.source_state = Some(source_state.to_vec());
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(self.read_metadata_bin(META_SOURCE_STATE_BIN)?)
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        let txid = self.read_u64_bin(META_TXID_BIN)?;
+        let seq_in_tx = self.read_u64_bin(META_SEQ_IN_TX_BIN)?;
+        Ok(txid
+            .zip(seq_in_tx)
+            .map(|(txid, seq_in_tx)| OpIdentifier::new(txid, seq_in_tx)))
+    }
+
+    fn preferred_batch_size(&self) -> Option<u64> {
+        self.preferred_batch_size
+    }
+
+    fn max_batch_duration_ms(&self) -> Option<u64> {
+        self.max_batch_duration_ms
+    }
+
+    fn flush_batch(&mut self) -> Result<(), BoxedError> {
+        self.batch.flush(self.client.as_ref(), self.n_threads)?;
+        // Data is written first: a crash in between replays the last batch, which is idempotent.
+        self.write_metadata()?;
+        Ok(())
+    }
+
+    fn supports_batching(&self) -> bool {
+        true
+    }
+}
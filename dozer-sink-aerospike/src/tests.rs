@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use aerospike::Value;
+use dozer_core::node::Sink;
+use dozer_types::models::sink::{
+    AerospikeDenormalizations, AerospikeSet, AerospikeSinkConfig, AerospikeSinkTable, DenormColumn,
+    DenormKey,
+};
+use dozer_types::node::OpIdentifier;
+use dozer_types::types::{
+    Field, FieldDefinition, FieldType, Operation, Record, Schema, SourceDefinition, TableOperation,
+};
+
+use crate::client::{BinMap, Client, RecordKey};
+use crate::errors::AerospikeSinkError;
+use crate::sink::AerospikeSink;
+
+#[derive(Debug, Default)]
+struct MockClient {
+    records: Mutex<HashMap<RecordKey, BinMap>>,
+}
+
+impl Client for MockClient {
+    fn put(&self, key: &RecordKey, bins: &[(String, Value)]) -> Result<(), AerospikeSinkError> {
+        let mut records = self.records.lock().unwrap();
+        let record = records.entry(key.clone()).or_default();
+        for (name, value) in bins {
+            if matches!(value, Value::Nil) {
+                record.remove(name);
+            } else {
+                record.insert(name.clone(), value.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn get(&self, key: &RecordKey) -> Result<Option<BinMap>, AerospikeSinkError> {
+        Ok(self.records.lock().unwrap().get(key).cloned())
+    }
+
+    fn delete(&self, key: &RecordKey) -> Result<(), AerospikeSinkError> {
+        self.records.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    fn truncate(&self, namespace: &str, set: &str) -> Result<(), AerospikeSinkError> {
+        self.records
+            .lock()
+            .unwrap()
+            .retain(|key, _| key.namespace != namespace || key.set != set);
+        Ok(())
+    }
+}
+
+impl MockClient {
+    fn record(&self, set: &str, key: Value) -> Option<BinMap> {
+        self.records
+            .lock()
+            .unwrap()
+            .get(&RecordKey::new("test", set, key))
+            .cloned()
+    }
+}
+
+fn field(name: &str, typ: FieldType) -> FieldDefinition {
+    FieldDefinition::new(name.to_string(), typ, true, SourceDefinition::Dynamic)
+}
+
+fn customers_schema() -> Schema {
+    Schema {
+        fields: vec![
+            field("id", FieldType::Int),
+            field("name", FieldType::String),
+        ],
+        primary_index: vec![0],
+    }
+}
+
+fn orders_schema() -> Schema {
+    Schema {
+        fields: vec![
+            field("order_id", FieldType::Int),
+            field("customer_id", FieldType::Int),
+            field("amount", FieldType::Float),
+        ],
+        primary_index: vec![0],
+    }
+}
+
+fn table(source: &str, set: &str) -> AerospikeSinkTable {
+    AerospikeSinkTable {
+        source_table_name: source.to_string(),
+        namespace: "test".to_string(),
+        set_name: set.to_string(),
+        denormalize: vec![],
+        write_denormalized_to: None,
+        primary_key: vec![],
+        aggregate_by_pk: false,
+    }
+}
+
+fn config(tables: Vec<AerospikeSinkTable>) -> AerospikeSinkConfig {
+    AerospikeSinkConfig {
+        connection: "aerospike".to_string(),
+        n_threads: None,
+        tables,
+        max_batch_duration_ms: None,
+        preferred_batch_size: None,
+        metadata_namespace: "test".to_string(),
+        metadata_set: None,
+    }
+}
+
+fn orders_with_customer(aggregate_by_pk: bool) -> AerospikeSinkConfig {
+    let mut orders = table("orders", "orders");
+    orders.denormalize = vec![AerospikeDenormalizations {
+        from_namespace: "test".to_string(),
+        from_set: "customers".to_string(),
+        key: DenormKey::Simple("customer_id".to_string()),
+        columns: vec![DenormColumn::Renamed {
+            source: "name".to_string(),
+            destination: "customer_name".to_string(),
+        }],
+    }];
+    orders.write_denormalized_to = Some(AerospikeSet {
+        namespace: "test".to_string(),
+        set: "orders_denorm".to_string(),
+        primary_key: if aggregate_by_pk {
+            vec!["customer_id".to_string()]
+        } else {
+            vec![]
+        },
+    });
+    orders.aggregate_by_pk = aggregate_by_pk;
+    config(vec![table("customers", "customers"), orders])
+}
+
+fn insert(port: u16, values: Vec<Field>) -> TableOperation {
+    TableOperation::without_id(
+        Operation::Insert {
+            new: Record::new(values),
+        },
+        port,
+    )
+}
+
+fn sink(config: &AerospikeSinkConfig, client: Arc<MockClient>) -> AerospikeSink {
+    let schemas = [customers_schema(), orders_schema()];
+    AerospikeSink::new(config, client, &schemas[..config.tables.len()]).unwrap()
+}
+
+#[test]
+fn test_writes_are_deferred_until_flush() {
+    let client = Arc::new(MockClient::default());
+    let mut sink = sink(
+        &config(vec![table("customers", "customers")]),
+        client.clone(),
+    );
+
+    sink.process(insert(
+        0,
+        vec![Field::Int(1), Field::String("alice".into())],
+    ))
+    .unwrap();
+    assert!(client.record("customers", Value::Int(1)).is_none());
+
+    sink.flush_batch().unwrap();
+    let record = client.record("customers", Value::Int(1)).unwrap();
+    assert_eq!(record["name"], Value::String("alice".into()));
+
+    sink.process(TableOperation::without_id(
+        Operation::Update {
+            old: Record::new(vec![Field::Int(1), Field::String("alice".into())]),
+            new: Record::new(vec![Field::Int(1), Field::Null]),
+        },
+        0,
+    ))
+    .unwrap();
+    sink.flush_batch().unwrap();
+    let record = client.record("customers", Value::Int(1)).unwrap();
+    assert!(!record.contains_key("name"));
+
+    sink.process(TableOperation::without_id(
+        Operation::Delete {
+            old: Record::new(vec![Field::Int(1), Field::Null]),
+        },
+        0,
+    ))
+    .unwrap();
+    sink.flush_batch().unwrap();
+    assert!(client.record("customers", Value::Int(1)).is_none());
+}
+
+#[test]
+fn test_denormalization_reads_unflushed_writes() {
+    let client = Arc::new(MockClient::default());
+    let mut sink = sink(&orders_with_customer(false), client.clone());
+
+    sink.process(insert(0, vec![Field::Int(7), Field::String("bob".into())]))
+        .unwrap();
+    sink.process(insert(
+        1,
+        vec![Field::Int(100), Field::Int(7), Field::Float(10.0.into())],
+    ))
+    .unwrap();
+    sink.process(insert(
+        1,
+        vec![Field::Int(101), Field::Int(8), Field::Float(5.0.into())],
+    ))
+    .unwrap();
+    sink.flush_batch().unwrap();
+
+    let order = client.record("orders", Value::Int(100)).unwrap();
+    assert!(!order.contains_key("customer_name"));
+    let denormalized = client.record("orders_denorm", Value::Int(100)).unwrap();
+    assert_eq!(denormalized["customer_name"], Value::String("bob".into()));
+    assert_eq!(denormalized["amount"], Value::from(10.0));
+    // Missing lookups produce nulls.
+    let denormalized = client.record("orders_denorm", Value::Int(101)).unwrap();
+    assert!(!denormalized.contains_key("customer_name"));
+}
+
+#[test]
+fn test_aggregate_by_pk() {
+    let client = Arc::new(MockClient::default());
+    let mut sink = sink(&orders_with_customer(true), client.clone());
+
+    sink.process(insert(0, vec![Field::Int(7), Field::String("bob".into())]))
+        .unwrap();
+    for order_id in [100, 101] {
+        sink.process(insert(
+            1,
+            vec![
+                Field::Int(order_id),
+                Field::Int(7),
+                Field::Float(1.0.into()),
+            ],
+        ))
+        .unwrap();
+    }
+    sink.flush_batch().unwrap();
+
+    let aggregated = client.record("orders_denorm", Value::Int(7)).unwrap();
+    assert_eq!(
+        aggregated["order_id"],
+        Value::List(vec![Value::Int(100), Value::Int(101)])
+    );
+
+    // Replaying an insert doesn't duplicate the row.
+    sink.process(insert(
+        1,
+        vec![Field::Int(101), Field::Int(7), Field::Float(2.0.into())],
+    ))
+    .unwrap();
+    sink.process(TableOperation::without_id(
+        Operation::Delete {
+            old: Record::new(vec![
+                Field::Int(100),
+                Field::Int(7),
+                Field::Float(1.0.into()),
+            ]),
+        },
+        1,
+    ))
+    .unwrap();
+    sink.flush_batch().unwrap();
+    let aggregated = client.record("orders_denorm", Value::Int(7)).unwrap();
+    assert_eq!(aggregated["order_id"], Value::List(vec![Value::Int(101)]));
+    assert_eq!(aggregated["amount"], Value::List(vec![Value::from(2.0)]));
+}
+
+#[test]
+fn test_appended_field() {
+    let client = Arc::new(MockClient::default());
+    let mut sink = sink(&orders_with_customer(false), client.clone());
+
+    let mut schema = orders_schema();
+    schema.fields.push(field("note", FieldType::String));
+    sink.on_schema_change(1, &schema).unwrap();
+    sink.process(insert(
+        1,
+        vec![
+            Field::Int(100),
+            Field::Int(7),
+            Field::Float(10.0.into()),
+            Field::String("gift".into()),
+        ],
+    ))
+    .unwrap();
+    sink.flush_batch().unwrap();
+
+    let order = client.record("orders", Value::Int(100)).unwrap();
+    assert_eq!(order["note"], Value::String("gift".into()));
+    // Denormalized records keep the configured columns.
+    let denormalized = client.record("orders_denorm", Value::Int(100)).unwrap();
+    assert!(!denormalized.contains_key("note"));
+
+    schema.fields.swap(2, 3);
+    assert!(sink.on_schema_change(1, &schema).is_err());
+}
+
+#[test]
+fn test_truncate() {
+    let client = Arc::new(MockClient::default());
+    let mut sink = sink(&orders_with_customer(false), client.clone());
+
+    sink.process(insert(0, vec![Field::Int(7), Field::String("bob".into())]))
+        .unwrap();
+    sink.process(insert(
+        1,
+        vec![Field::Int(100), Field::Int(7), Field::Float(10.0.into())],
+    ))
+    .unwrap();
+    sink.on_truncate(1).unwrap();
+    sink.process(insert(
+        1,
+        vec![Field::Int(101), Field::Int(7), Field::Float(5.0.into())],
+    ))
+    .unwrap();
+    sink.flush_batch().unwrap();
+
+    assert!(client.record("customers", Value::Int(7)).is_some());
+    assert!(client.record("orders", Value::Int(100)).is_none());
+    assert!(client.record("orders_denorm", Value::Int(100)).is_none());
+    assert!(client.record("orders", Value::Int(101)).is_some());
+    assert!(client.record("orders_denorm", Value::Int(101)).is_some());
+}
+
+#[test]
+fn test_checkpoint_is_persisted_on_flush() {
+    let client = Arc::new(MockClient::default());
+    let config = config(vec![table("customers", "customers")]);
+    let mut sink = sink(&config, client.clone());
+    assert_eq!(sink.get_latest_op_id().unwrap(), None);
+
+    sink.set_source_state(b"state").unwrap();
+    let mut op = insert(0, vec![Field::Int(1), Field::String("alice".into())]);
+    op.id = Some(OpIdentifier::new(42, 3));
+    sink.process(op).unwrap();
+    assert_eq!(sink.get_latest_op_id().unwrap(), None);
+    sink.flush_batch().unwrap();
+
+    let mut restarted = self::sink(&config, client);
+    assert_eq!(
+        restarted.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(42, 3))
+    );
+    assert_eq!(
+        restarted.get_source_state().unwrap(),
+        Some(b"state".to_vec())
+    );
+}
+
+#[test]
+fn test_invalid_configs() {
+    let mut schema = customers_schema();
+    schema.primary_index.clear();
+    let result = AerospikeSink::new(
+        &config(vec![table("customers", "customers")]),
+        Arc::new(MockClient::default()),
+        &[schema],
+    );
+    assert!(matches!(
+        result,
+        Err(AerospikeSinkError::MissingPrimaryKey(_))
+    ));
+
+    let mut config = orders_with_customer(false);
+    config.tables.remove(0);
+    let result = AerospikeSink::new(&config, Arc::new(MockClient::default()), &[orders_schema()]);
+    assert!(matches!(
+        result,
+        Err(AerospikeSinkError::DenormalizationSourceNotFound { .. })
+    ));
+}
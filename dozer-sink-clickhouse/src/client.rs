@@ -57,6 +57,13 @@ impl ClickhouseClient {
         Ok(())
     }
 
+    pub async fn execute(&self, query: &str) -> Result<(), QueryError> {
+        let mut client = self.pool.get_handle().await?;
+        debug!("{query}");
+        client.execute(query).await?;
+        Ok(())
+    }
+
     pub async fn create_table(
         &self,
         datasource_name: &str,
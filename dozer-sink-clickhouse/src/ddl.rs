@@ -1,7 +1,10 @@
 use dozer_types::models::sink::ClickhouseTableOptions;
-use dozer_types::types::FieldDefinition;
+use dozer_types::types::{Field, FieldDefinition};
 
+use crate::engine::TableEngine;
+use crate::errors::QueryError;
 use crate::schema::map_field_to_type;
+use crate::types::field_to_sql_literal;
 
 const DEFAULT_TABLE_ENGINE: &str = "MergeTree()";
 
@@ -14,11 +17,8 @@ pub fn get_create_table_query(
         .as_ref()
         .and_then(|c| c.engine.clone())
         .unwrap_or_else(|| DEFAULT_TABLE_ENGINE.to_string());
-    let engine_name = if engine == "CollapsingMergeTree" {
-        "CollapsingMergeTree(sign)".to_string()
-    } else {
-        engine.to_owned()
-    };
+    let table_engine = TableEngine::new(&engine, table_options.as_ref());
+    let engine_name = table_engine.engine_definition(&engine);
     let mut parts = fields
         .iter()
         .map(|field| {
@@ -26,9 +26,7 @@ pub fn get_create_table_query(
             format!("{} {}", field.name, typ)
         })
         .collect::<Vec<_>>();
-    if engine == "CollapsingMergeTree" {
-        parts.push("sign Int8".to_string());
-    }
+    parts.extend(table_engine.extra_column_definitions());
 
     parts.push(
         table_options
@@ -77,3 +75,66 @@ pub fn get_create_table_query(
             ",
     )
 }
+
+/// Adds a column for `field`, unless the table already has it.
+pub fn get_add_column_query(
+    table_name: &str,
+    field: &FieldDefinition,
+    cluster: Option<&str>,
+) -> String {
+    let cluster = cluster.map_or("".to_string(), |cluster| format!(" ON CLUSTER {cluster}"));
+    format!(
+        "ALTER TABLE {table_name}{cluster} ADD COLUMN IF NOT EXISTS {} {}",
+        field.name,
+        map_field_to_type(field)
+    )
+}
+
+/// Changes the type of the column of `field`, which may only have become nullable or wider.
+pub fn get_modify_column_query(
+    table_name: &str,
+    field: &FieldDefinition,
+    cluster: Option<&str>,
+) -> String {
+    let cluster = cluster.map_or("".to_string(), |cluster| format!(" ON CLUSTER {cluster}"));
+    format!(
+        "ALTER TABLE {table_name}{cluster} MODIFY COLUMN {} {}",
+        field.name,
+        map_field_to_type(field)
+    )
+}
+
+/// Removes all rows of the table.
+pub fn get_truncate_query(table_name: &str, cluster: Option<&str>) -> String {
+    let cluster = cluster.map_or("".to_string(), |cluster| format!(" ON CLUSTER {cluster}"));
+    format!("TRUNCATE TABLE IF EXISTS {table_name}{cluster}")
+}
+
+/// Lightweight delete of the rows with the given primary keys.
+pub fn get_delete_query(
+    table_name: &str,
+    key_columns: &[&str],
+    keys: &[Vec<Field>],
+) -> Result<String, QueryError> {
+    let tuple = |values: Vec<String>| {
+        if values.len() == 1 {
+            values.into_iter().next().expect("checked length")
+        } else {
+            format!("({})", values.join(", "))
+        }
+    };
+    let keys = keys
+        .iter()
+        .map(|key| {
+            key.iter()
+                .map(field_to_sql_literal)
+                .collect::<Result<Vec<_>, _>>()
+                .map(tuple)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let columns = tuple(key_columns.iter().map(|c| c.to_string()).collect());
+    Ok(format!(
+        "DELETE FROM {table_name} WHERE {columns} IN ({})",
+        keys.join(", ")
+    ))
+}
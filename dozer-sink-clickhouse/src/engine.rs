@@ -0,0 +1,106 @@
+use dozer_types::models::sink::ClickhouseTableOptions;
+use dozer_types::types::{FieldDefinition, FieldType, SourceDefinition};
+
+pub const SIGN_COLUMN: &str = "sign";
+pub const DEFAULT_VERSION_COLUMN: &str = "_version";
+pub const DEFAULT_IS_DELETED_COLUMN: &str = "_is_deleted";
+
+/// How updates and deletes are written, depending on the table engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableEngine {
+    /// Rows are cancelled by inserting them again with `sign = -1`.
+    Collapsing,
+    /// Rows are replaced by inserting them with a higher version,
+    /// and deleted by inserting them with the deleted marker set.
+    Replacing {
+        version_column: String,
+        is_deleted_column: String,
+    },
+    /// Rows are deleted with lightweight `DELETE`.
+    MergeTree,
+    /// Any other engine, only supporting inserts.
+    Other,
+}
+
+impl TableEngine {
+    /// Classifies `engine`, given either as a name (`ReplicatedReplacingMergeTree`)
+    /// or as a full definition (`ReplacingMergeTree(ver)`).
+    pub fn new(engine: &str, options: Option<&ClickhouseTableOptions>) -> Self {
+        let name = engine.split('(').next().unwrap_or_default().trim();
+        match name.strip_prefix("Replicated").unwrap_or(name) {
+            "CollapsingMergeTree" => TableEngine::Collapsing,
+            "ReplacingMergeTree" => TableEngine::Replacing {
+                version_column: options
+                    .and_then(|options| options.version_column.clone())
+                    .unwrap_or_else(|| DEFAULT_VERSION_COLUMN.to_string()),
+                is_deleted_column: options
+                    .and_then(|options| options.is_deleted_column.clone())
+                    .unwrap_or_else(|| DEFAULT_IS_DELETED_COLUMN.to_string()),
+            },
+            "MergeTree" => TableEngine::MergeTree,
+            _ => TableEngine::Other,
+        }
+    }
+
+    /// Whether writing operations again leaves the table as it was after writing them once.
+    /// Only then the sink writes exactly once, as a batch written before a crash, but not
+    /// checkpointed, is written again on restart.
+    pub fn is_idempotent(&self) -> bool {
+        matches!(
+            self,
+            TableEngine::Collapsing | TableEngine::Replacing { .. }
+        )
+    }
+
+    /// Columns written by the sink in addition to the fields of the input schema.
+    pub fn extra_fields(&self, source: SourceDefinition) -> Vec<FieldDefinition> {
+        let field = |name: &str, typ| FieldDefinition {
+            name: name.to_string(),
+            typ,
+            nullable: false,
+            description: None,
+            source: source.clone(),
+        };
+        match self {
+            TableEngine::Collapsing => vec![field(SIGN_COLUMN, FieldType::Int8)],
+            TableEngine::Replacing {
+                version_column,
+                is_deleted_column,
+            } => vec![
+                field(version_column, FieldType::UInt),
+                field(is_deleted_column, FieldType::Boolean),
+            ],
+            TableEngine::MergeTree | TableEngine::Other => vec![],
+        }
+    }
+
+    /// Column definitions of [`TableEngine::extra_fields`], as they are created in `CREATE TABLE`.
+    pub fn extra_column_definitions(&self) -> Vec<String> {
+        match self {
+            TableEngine::Collapsing => vec![format!("{SIGN_COLUMN} Int8")],
+            TableEngine::Replacing {
+                version_column,
+                is_deleted_column,
+            } => vec![
+                format!("{version_column} UInt64"),
+                format!("{is_deleted_column} Bool"),
+            ],
+            TableEngine::MergeTree | TableEngine::Other => vec![],
+        }
+    }
+
+    /// Adds the engine parameters to `engine` if it has none.
+    pub fn engine_definition(&self, engine: &str) -> String {
+        if engine.contains('(') {
+            return engine.to_string();
+        }
+        match self {
+            TableEngine::Collapsing => format!("{engine}({SIGN_COLUMN})"),
+            TableEngine::Replacing {
+                version_column,
+                is_deleted_column,
+            } => format!("{engine}({version_column}, {is_deleted_column})"),
+            TableEngine::MergeTree | TableEngine::Other => engine.to_string(),
+        }
+    }
+}
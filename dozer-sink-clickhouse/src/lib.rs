@@ -1,5 +1,6 @@
 pub mod client;
 pub mod ddl;
+pub mod engine;
 pub mod errors;
 pub mod schema;
 mod sink;
@@ -1,15 +1,38 @@
-use dozer_types::types::{FieldDefinition, FieldType, Schema, SourceDefinition};
+use dozer_types::node::OpIdentifier;
+use dozer_types::types::{Field, FieldDefinition, FieldType, Schema, SourceDefinition};
+
+use crate::client::ClickhouseClient;
+use crate::errors::QueryError;
 
 // Replication Metadata Constants
 pub const REPLICA_METADATA_TABLE: &str = "__dozer_replication_metadata";
 pub const META_TABLE_COL: &str = "table";
 pub const META_TXN_ID_COL: &str = "txn_id";
+pub const META_SEQ_IN_TX_COL: &str = "seq_in_tx";
+pub const META_SOURCE_STATE_COL: &str = "source_state";
 
 pub struct ReplicationMetadata {
     pub schema: Schema,
     pub table_name: String,
 }
 
+/// The checkpoint of a sink table, as last written by [`ReplicationMetadata::write`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub op_id: Option<OpIdentifier>,
+    pub source_state: Option<Vec<u8>>,
+}
+
+fn metadata_field(name: &str, typ: FieldType, nullable: bool) -> FieldDefinition {
+    FieldDefinition {
+        name: name.to_owned(),
+        typ,
+        nullable,
+        source: SourceDefinition::Dynamic,
+        description: None,
+    }
+}
+
 impl ReplicationMetadata {
     pub fn schema(&self) -> &Schema {
         &self.schema
@@ -19,31 +42,121 @@ impl ReplicationMetadata {
         vec![META_TABLE_COL.to_string()]
     }
 
+    /// The table is created with explicit DDL, as it has no engine columns like the sink tables.
+    /// Without a version column, `ReplacingMergeTree` keeps the last inserted row of each table.
+    pub fn create_table_query(&self, cluster: Option<&str>) -> String {
+        let cluster = cluster.map_or("".to_string(), |cluster| format!(" ON CLUSTER {cluster}"));
+        format!(
+            "CREATE TABLE IF NOT EXISTS {}{cluster} (
+                {META_TABLE_COL} String,
+                {META_TXN_ID_COL} Nullable(UInt64),
+                {META_SEQ_IN_TX_COL} Nullable(UInt64),
+                {META_SOURCE_STATE_COL} String,
+                PRIMARY KEY ({META_TABLE_COL})
+            )
+            ENGINE = ReplacingMergeTree
+            ORDER BY ({META_TABLE_COL})
+            PARTITION BY ({META_TABLE_COL})",
+            self.table_name
+        )
+    }
+
+    /// Brings a table created by an older version, with only the table and transaction id columns,
+    /// to the current columns. It does nothing on a current table.
+    pub fn upgrade_table_query(&self, cluster: Option<&str>) -> String {
+        let cluster = cluster.map_or("".to_string(), |cluster| format!(" ON CLUSTER {cluster}"));
+        format!(
+            "ALTER TABLE {}{cluster} \
+             MODIFY COLUMN {META_TXN_ID_COL} Nullable(UInt64), \
+             ADD COLUMN IF NOT EXISTS {META_SEQ_IN_TX_COL} Nullable(UInt64), \
+             ADD COLUMN IF NOT EXISTS {META_SOURCE_STATE_COL} String",
+            self.table_name
+        )
+    }
+
     pub fn get_metadata() -> ReplicationMetadata {
         ReplicationMetadata {
             table_name: REPLICA_METADATA_TABLE.to_string(),
             schema: Schema::new()
                 .field(
-                    FieldDefinition {
-                        name: META_TABLE_COL.to_owned(),
-                        typ: FieldType::String,
-                        nullable: false,
-                        source: SourceDefinition::Dynamic,
-                        description: None,
-                    },
+                    metadata_field(META_TABLE_COL, FieldType::String, false),
                     true,
                 )
                 .field(
-                    FieldDefinition {
-                        name: META_TXN_ID_COL.to_owned(),
-                        typ: FieldType::UInt,
-                        nullable: false,
-                        source: SourceDefinition::Dynamic,
-                        description: None,
-                    },
+                    metadata_field(META_TXN_ID_COL, FieldType::UInt, true),
+                    false,
+                )
+                .field(
+                    metadata_field(META_SEQ_IN_TX_COL, FieldType::UInt, true),
+                    false,
+                )
+                .field(
+                    metadata_field(META_SOURCE_STATE_COL, FieldType::Binary, false),
                     false,
                 )
                 .clone(),
         }
     }
+
+    /// Replaces the checkpoint of `sink_table_name`.
+    ///
+    /// The table is a `ReplacingMergeTree` ordered by table name, so the last inserted row wins.
+    pub async fn write(
+        &self,
+        client: &ClickhouseClient,
+        sink_table_name: &str,
+        checkpoint: &Checkpoint,
+    ) -> Result<(), QueryError> {
+        let (txn_id, seq_in_tx) = match checkpoint.op_id {
+            Some(op_id) => (Field::UInt(op_id.txid), Field::UInt(op_id.seq_in_tx)),
+            None => (Field::Null, Field::Null),
+        };
+        client
+            .insert(
+                &self.table_name,
+                &self.schema.fields,
+                vec![
+                    Field::String(sink_table_name.to_string()),
+                    txn_id,
+                    seq_in_tx,
+                    Field::Binary(checkpoint.source_state.clone().unwrap_or_default()),
+                ],
+                None,
+            )
+            .await
+    }
+
+    pub async fn read(
+        &self,
+        client: &ClickhouseClient,
+        sink_table_name: &str,
+    ) -> Result<Checkpoint, QueryError> {
+        let query = format!(
+            "SELECT \"{META_TABLE_COL}\", \"{META_TXN_ID_COL}\", \"{META_SEQ_IN_TX_COL}\", \"{META_SOURCE_STATE_COL}\" \
+             FROM {} FINAL WHERE \"{META_TABLE_COL}\" = '{}'",
+            self.table_name,
+            sink_table_name.replace('\\', "\\\\").replace('\'', "\\'")
+        );
+        let result = client
+            .fetch_all(&query, self.schema.fields.clone(), None)
+            .await?;
+        let Some(row) = result.rows.into_iter().next() else {
+            return Ok(Checkpoint::default());
+        };
+
+        let op_id = match (&row[1], &row[2]) {
+            (Field::UInt(txid), Field::UInt(seq_in_tx)) => {
+                Some(OpIdentifier::new(*txid, *seq_in_tx))
+            }
+            _ => None,
+        };
+        let source_state = match &row[3] {
+            Field::Binary(state) if !state.is_empty() => Some(state.clone()),
+            _ => None,
+        };
+        Ok(Checkpoint {
+            op_id,
+            source_state,
+        })
+    }
 }
@@ -3,7 +3,6 @@ use crate::errors::ClickhouseSinkError::{self, SinkTableDoesNotExist};
 use clickhouse_rs::types::Complex;
 use clickhouse_rs::{Block, ClientHandle};
 use dozer_types::log::warn;
-use dozer_types::models::sink::ClickhouseSinkConfig;
 use dozer_types::serde::{Deserialize, Serialize};
 use dozer_types::types::{FieldDefinition, FieldType, Schema};
 
@@ -36,16 +35,16 @@ pub struct ClickhouseSchema {}
 impl ClickhouseSchema {
     pub async fn get_clickhouse_table(
         client: ClickhouseClient,
-        config: &ClickhouseSinkConfig,
+        sink_table_name: &str,
     ) -> Result<ClickhouseTable, ClickhouseSinkError> {
         let mut client = client.get_client_handle().await?;
-        let query = format!("DESCRIBE TABLE {}", config.sink_table_name);
+        let query = format!("DESCRIBE TABLE {}", sink_table_name);
         let block: Block<Complex> = client.query(&query).fetch_all().await?;
 
         if block.row_count() == 0 {
             Err(SinkTableDoesNotExist)
         } else {
-            Self::fetch_sink_table_info(client, &config.sink_table_name).await
+            Self::fetch_sink_table_info(client, sink_table_name).await
         }
     }
 
@@ -1,27 +1,37 @@
+use dozer_core::dag_schemas::field_changes;
 use dozer_core::epoch::Epoch;
 use dozer_core::event::EventHub;
 use dozer_core::node::{PortHandle, Sink, SinkFactory};
 use dozer_core::tokio::runtime::Runtime;
-use dozer_core::DEFAULT_PORT_HANDLE;
 use dozer_types::errors::internal::BoxedError;
 
-use dozer_types::log::debug;
-use dozer_types::models::sink::{ClickhouseSinkConfig, ClickhouseTableOptions};
+use dozer_types::log::{debug, warn};
+use dozer_types::models::sink::{ClickhouseSinkConfig, ClickhouseSinkTableConfig};
 use dozer_types::node::OpIdentifier;
 
 use crate::client::ClickhouseClient;
-use crate::errors::ClickhouseSinkError;
-use crate::metadata::{
-    ReplicationMetadata, META_TABLE_COL, META_TXN_ID_COL, REPLICA_METADATA_TABLE,
+use crate::ddl::{
+    get_add_column_query, get_delete_query, get_modify_column_query, get_truncate_query,
 };
-use crate::schema::{ClickhouseSchema, ClickhouseTable};
+use crate::engine::TableEngine;
+use crate::errors::ClickhouseSinkError;
+use crate::metadata::{Checkpoint, ReplicationMetadata};
+use crate::schema::{map_field_to_type, ClickhouseSchema, ClickhouseTable};
 use dozer_types::tonic::async_trait;
-use dozer_types::types::{Field, FieldDefinition, Operation, Schema, TableOperation};
+use dozer_types::types::{Field, Operation, Schema, TableOperation};
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const BATCH_SIZE: u64 = 100;
 
-const BATCH_SIZE: usize = 100;
+#[derive(Debug)]
+enum BatchOp {
+    Insert(Vec<Field>),
+    /// Lightweight delete by primary key.
+    Delete(Vec<Field>),
+}
 
 #[derive(Debug)]
 pub struct ClickhouseSinkFactory {
@@ -34,36 +44,57 @@ impl ClickhouseSinkFactory {
         Self { config, runtime }
     }
 
-    pub async fn create_replication_metadata_table(&self) -> Result<(), BoxedError> {
-        let client = ClickhouseClient::new(self.config.clone());
+    pub async fn create_replication_metadata_table(
+        &self,
+        client: &ClickhouseClient,
+    ) -> Result<(), BoxedError> {
         let repl_metadata = ReplicationMetadata::get_metadata();
-
-        let primary_keys = repl_metadata.get_primary_keys();
-        let partition_by = format!("({})", primary_keys.join(","));
-        let create_table_options = ClickhouseTableOptions {
-            engine: Some("ReplacingMergeTree".to_string()),
-            primary_keys: Some(repl_metadata.get_primary_keys()),
-            partition_by: Some(partition_by),
-            // Replaced using this key
-            order_by: Some(repl_metadata.get_primary_keys()),
-            cluster: self
-                .config
+        let cluster = self.config.tables.iter().find_map(|table| {
+            table
                 .create_table_options
                 .as_ref()
-                .and_then(|o| o.cluster.clone()),
-            sample_by: None,
-        };
+                .and_then(|o| o.cluster.as_deref())
+        });
         client
-            .create_table(
-                &repl_metadata.table_name,
-                &repl_metadata.schema.fields,
-                Some(create_table_options),
-                None,
-            )
+            .execute(&repl_metadata.create_table_query(cluster))
+            .await?;
+        client
+            .execute(&repl_metadata.upgrade_table_query(cluster))
             .await?;
 
         Ok(())
     }
+
+    async fn build_table(
+        &self,
+        client: &ClickhouseClient,
+        config: &ClickhouseSinkTableConfig,
+        schema: Schema,
+    ) -> Result<SinkTable, BoxedError> {
+        // Create Sink Table
+        if config.create_table_options.is_some() {
+            client
+                .create_table(
+                    &config.sink_table_name,
+                    &schema.fields,
+                    config.create_table_options.clone(),
+                    None,
+                )
+                .await?;
+        }
+        let table =
+            ClickhouseSchema::get_clickhouse_table(client.clone(), &config.sink_table_name).await?;
+
+        ClickhouseSchema::compare_with_dozer_schema(client.clone(), &schema, &table).await?;
+        let table = SinkTable::new(config, schema, &table);
+        if !table.engine.is_idempotent() {
+            warn!(
+                "Table {} isn't a ReplacingMergeTree or CollapsingMergeTree table, so records written before a crash, but after the last checkpoint, are written again on restart",
+                table.name
+            );
+        }
+        Ok(table)
+    }
 }
 
 #[async_trait]
@@ -73,15 +104,15 @@ impl SinkFactory for ClickhouseSinkFactory {
     }
 
     fn get_input_ports(&self) -> Vec<PortHandle> {
-        vec![DEFAULT_PORT_HANDLE]
+        (0..self.config.tables.len() as PortHandle).collect()
     }
 
-    fn get_input_port_name(&self, _port: &PortHandle) -> String {
-        self.config.source_table_name.clone()
+    fn get_input_port_name(&self, port: &PortHandle) -> String {
+        self.config.tables[*port as usize].source_table_name.clone()
     }
 
     fn prepare(&self, input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
-        debug_assert!(input_schemas.len() == 1);
+        debug_assert!(input_schemas.len() == self.config.tables.len());
         Ok(())
     }
 
@@ -90,130 +121,301 @@ impl SinkFactory for ClickhouseSinkFactory {
         mut input_schemas: HashMap<PortHandle, Schema>,
         _event_hub: EventHub,
     ) -> Result<Box<dyn Sink>, BoxedError> {
-        let schema = input_schemas.remove(&DEFAULT_PORT_HANDLE).unwrap();
-
+        // All tables share the connection pool.
         let client = ClickhouseClient::new(self.config.clone());
 
-        let config = &self.config;
+        self.create_replication_metadata_table(&client).await?;
 
-        // Create Sink Table
-        self.create_replication_metadata_table().await?;
-
-        // Create Sink Table
-        if self.config.create_table_options.is_some() {
-            client
-                .create_table(
-                    &config.sink_table_name,
-                    &schema.fields,
-                    self.config.create_table_options.clone(),
-                    None,
-                )
-                .await?;
+        let mut tables = Vec::with_capacity(self.config.tables.len());
+        for (port, table_config) in self.config.tables.iter().enumerate() {
+            let schema = input_schemas.remove(&(port as PortHandle)).unwrap();
+            tables.push(self.build_table(&client, table_config, schema).await?);
         }
-        let table = ClickhouseSchema::get_clickhouse_table(client.clone(), &self.config).await?;
 
-        ClickhouseSchema::compare_with_dozer_schema(client.clone(), &schema, &table).await?;
-        let sink = ClickhouseSink::new(
-            client,
-            self.config.clone(),
-            schema,
-            self.runtime.clone(),
-            table,
-        );
+        let mut sink = ClickhouseSink::new(client, self.runtime.clone(), tables);
+        // The checkpoint is read here, as the sink's sync getters run inside the async pipeline build.
+        sink.load_checkpoint().await?;
 
         Ok(Box::new(sink))
     }
 }
 
+/// Returns a version greater than all previous ones, also across restarts.
+fn next_version(last_version: &mut u64) -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos() as u64);
+    *last_version = now.max(*last_version + 1);
+    *last_version
+}
+
+#[derive(Debug)]
+pub(crate) struct SinkTable {
+    name: String,
+    /// The input schema, followed by the columns of the engine.
+    schema: Schema,
+    /// The number of fields of the input schema.
+    num_fields: usize,
+    engine: TableEngine,
+    cluster: Option<String>,
+    batch: Vec<BatchOp>,
+}
+
+impl SinkTable {
+    fn new(config: &ClickhouseSinkTableConfig, schema: Schema, table: &ClickhouseTable) -> Self {
+        let mut schema = schema;
+        let num_fields = schema.fields.len();
+
+        let engine = TableEngine::new(&table.engine, config.create_table_options.as_ref());
+        if !schema.fields.is_empty() {
+            // get source from any field in schema
+            let source = schema.fields[0].source.clone();
+            schema.fields.extend(engine.extra_fields(source));
+        }
+        Self {
+            name: config.sink_table_name.clone(),
+            schema,
+            num_fields,
+            engine,
+            cluster: config
+                .create_table_options
+                .as_ref()
+                .and_then(|options| options.cluster.clone()),
+            batch: Vec::new(),
+        }
+    }
+
+    /// Modifies the columns of the fields that became nullable or were widened, and adds columns
+    /// for the fields appended to the input schema.
+    async fn alter_table(
+        &mut self,
+        client: &ClickhouseClient,
+        schema: &Schema,
+    ) -> Result<(), BoxedError> {
+        let changes = field_changes(&self.schema.fields[..self.num_fields], &schema.fields)?;
+        if changes.altered.is_empty() && changes.appended.is_empty() {
+            return Ok(());
+        }
+        // The rows in the batch don't have the new column types and columns.
+        self.flush(client).await?;
+        for &index in &changes.altered {
+            let (current, field) = (&self.schema.fields[index], &schema.fields[index]);
+            if map_field_to_type(current) != map_field_to_type(field) {
+                let query = get_modify_column_query(&self.name, field, self.cluster.as_deref());
+                client.execute(&query).await?;
+            }
+            self.schema.fields[index] = field.clone();
+        }
+        for field in changes.appended {
+            let query = get_add_column_query(&self.name, field, self.cluster.as_deref());
+            client.execute(&query).await?;
+        }
+
+        let engine_fields = self.schema.fields.split_off(self.num_fields);
+        self.schema.fields.extend_from_slice(changes.appended);
+        self.schema.fields.extend(engine_fields);
+        self.num_fields = schema.fields.len();
+        Ok(())
+    }
+
+    /// Removes all rows of the table. The rows in the batch would be removed anyway.
+    async fn truncate(&mut self, client: &ClickhouseClient) -> Result<(), BoxedError> {
+        self.batch.clear();
+        let query = get_truncate_query(&self.name, self.cluster.as_deref());
+        client.execute(&query).await?;
+        Ok(())
+    }
+
+    fn insert_values(&mut self, mut values: Vec<Field>, last_version: &mut u64) {
+        match &self.engine {
+            TableEngine::Collapsing => values.push(Field::Int8(1)),
+            TableEngine::Replacing { .. } => {
+                values.push(Field::UInt(next_version(last_version)));
+                values.push(Field::Boolean(false));
+            }
+            TableEngine::MergeTree | TableEngine::Other => (),
+        }
+        // add values to batch instead of inserting immediately
+        self.batch.push(BatchOp::Insert(values));
+    }
+
+    fn delete_values(
+        &mut self,
+        mut values: Vec<Field>,
+        last_version: &mut u64,
+    ) -> Result<(), ClickhouseSinkError> {
+        match &self.engine {
+            TableEngine::Collapsing => values.push(Field::Int8(-1)),
+            TableEngine::Replacing { .. } => {
+                values.push(Field::UInt(next_version(last_version)));
+                values.push(Field::Boolean(true));
+            }
+            TableEngine::MergeTree => {
+                let key = self.primary_key(&values)?;
+                self.batch.push(BatchOp::Delete(key));
+                return Ok(());
+            }
+            TableEngine::Other => return Err(ClickhouseSinkError::UnsupportedOperation),
+        }
+        self.batch.push(BatchOp::Insert(values));
+        Ok(())
+    }
+
+    fn update_values(
+        &mut self,
+        old: Vec<Field>,
+        new: Vec<Field>,
+        last_version: &mut u64,
+    ) -> Result<(), ClickhouseSinkError> {
+        // A newer version replaces the old row, unless the primary key changed.
+        if matches!(self.engine, TableEngine::Replacing { .. })
+            && self.primary_key(&old)? == self.primary_key(&new)?
+        {
+            self.insert_values(new, last_version);
+            return Ok(());
+        }
+        self.delete_values(old, last_version)?;
+        self.insert_values(new, last_version);
+        Ok(())
+    }
+
+    fn primary_key(&self, values: &[Field]) -> Result<Vec<Field>, ClickhouseSinkError> {
+        if self.schema.primary_index.is_empty() {
+            return Err(ClickhouseSinkError::PrimaryKeyNotFound);
+        }
+        Ok(self
+            .schema
+            .primary_index
+            .iter()
+            .map(|i| values[*i].clone())
+            .collect())
+    }
+
+    /// Writes the batch. Consecutive inserts and deletes are grouped, keeping the order between groups.
+    async fn flush(&mut self, client: &ClickhouseClient) -> Result<(), BoxedError> {
+        let mut batch = std::mem::take(&mut self.batch).into_iter().peekable();
+        let key_columns = self
+            .schema
+            .primary_index
+            .iter()
+            .map(|i| self.schema.fields[*i].name.as_str())
+            .collect::<Vec<_>>();
+        while let Some(op) = batch.next() {
+            match op {
+                BatchOp::Insert(row) => {
+                    let mut rows = vec![row];
+                    while let Some(BatchOp::Insert(_)) = batch.peek() {
+                        let Some(BatchOp::Insert(row)) = batch.next() else {
+                            unreachable!()
+                        };
+                        rows.push(row);
+                    }
+                    client
+                        .insert_multi(&self.name, &self.schema.fields, rows, None)
+                        .await?;
+                }
+                BatchOp::Delete(key) => {
+                    let mut keys = vec![key];
+                    while let Some(BatchOp::Delete(_)) = batch.peek() {
+                        let Some(BatchOp::Delete(key)) = batch.next() else {
+                            unreachable!()
+                        };
+                        keys.push(key);
+                    }
+                    let query = get_delete_query(&self.name, &key_columns, &keys)?;
+                    client.execute(&query).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 pub(crate) struct ClickhouseSink {
     pub(crate) client: ClickhouseClient,
     pub(crate) runtime: Arc<Runtime>,
-    pub(crate) schema: Schema,
-    pub(crate) sink_table_name: String,
-    pub(crate) table: ClickhouseTable,
-    batch: Vec<Vec<Field>>,
+    pub(crate) tables: Vec<SinkTable>,
+    /// Key of the checkpoint row, shared by all tables of the sink.
+    checkpoint_key: String,
+    last_version: u64,
     metadata: ReplicationMetadata,
-    latest_txid: Option<u64>,
+    latest_op_id: Option<OpIdentifier>,
+    source_state: Option<Vec<u8>>,
+    /// The last checkpoint written to ClickHouse.
+    checkpoint: Checkpoint,
 }
 
 impl Debug for ClickhouseSink {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ClickhouseSink")
-            .field("sink_table_name", &self.sink_table_name)
-            .field("table", &self.table)
-            .field("schema", &self.schema)
+            .field("tables", &self.tables)
             .finish()
     }
 }
 
 impl ClickhouseSink {
-    pub fn new(
+    pub(crate) fn new(
         client: ClickhouseClient,
-        config: ClickhouseSinkConfig,
-        schema: Schema,
         runtime: Arc<Runtime>,
-        table: ClickhouseTable,
+        tables: Vec<SinkTable>,
     ) -> Self {
-        let mut schema = schema.clone();
-
-        if table.engine == "CollapsingMergeTree" && !schema.fields.is_empty() {
-            // get source from any field in schema
-            let source = schema.fields[0].source.clone();
-            schema.fields.push(FieldDefinition {
-                name: "sign".to_string(),
-                typ: dozer_types::types::FieldType::Int8,
-                nullable: false,
-                description: None,
-                source,
-            });
-        }
+        // A single table keeps using its name, as before sinks had several tables.
+        let checkpoint_key = tables
+            .iter()
+            .map(|table| table.name.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
         Self {
             client,
             runtime,
-            schema,
-            sink_table_name: config.sink_table_name,
-            table,
-            batch: Vec::new(),
-            latest_txid: None,
+            tables,
+            checkpoint_key,
+            last_version: 0,
+            latest_op_id: None,
+            source_state: None,
+            checkpoint: Checkpoint::default(),
             metadata: ReplicationMetadata::get_metadata(),
         }
     }
 
-    pub async fn insert_metadata(&self) -> Result<(), BoxedError> {
+    pub(crate) async fn load_checkpoint(&mut self) -> Result<(), BoxedError> {
+        self.checkpoint = self
+            .metadata
+            .read(&self.client, &self.checkpoint_key)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn insert_metadata(&mut self) -> Result<(), BoxedError> {
         debug!(
             "[Sink] Inserting metadata record {:?} {}",
-            self.latest_txid,
-            self.sink_table_name.clone()
+            self.latest_op_id, self.checkpoint_key
         );
-        if let Some(txid) = self.latest_txid {
-            self.client
-                .insert(
-                    REPLICA_METADATA_TABLE,
-                    &self.metadata.schema.fields,
-                    vec![
-                        Field::String(self.sink_table_name.clone()),
-                        Field::UInt(txid),
-                    ],
-                    None,
-                )
-                .await?;
+        if self.latest_op_id.is_none() && self.source_state.is_none() {
+            return Ok(());
         }
+        let checkpoint = Checkpoint {
+            op_id: self.latest_op_id,
+            source_state: self.source_state.clone(),
+        };
+        self.metadata
+            .write(&self.client, &self.checkpoint_key, &checkpoint)
+            .await?;
+        self.checkpoint = checkpoint;
         Ok(())
     }
 
-    fn insert_values(&mut self, values: &[Field]) -> Result<(), BoxedError> {
-        // add values to batch instead of inserting immediately
-        self.batch.push(values.to_vec());
-        Ok(())
-    }
-
+    /// Inserts the batches of all tables, then one checkpoint for the sink.
+    ///
+    /// ClickHouse can't write both atomically: if the sink stops in between, the batches are
+    /// replayed on restart. That only leaves the tables as they were with the engines that are
+    /// [`TableEngine::is_idempotent`]. Other tables get the replayed records again.
     fn commit_batch(&mut self) -> Result<(), BoxedError> {
-        let batch = std::mem::take(&mut self.batch);
-        self.runtime.block_on(async {
-            //Insert batch
-            self.client
-                .insert_multi(&self.sink_table_name, &self.schema.fields, batch, None)
-                .await?;
+        let runtime = self.runtime.clone();
+        runtime.block_on(async {
+            for table in &mut self.tables {
+                table.flush(&self.client).await?;
+            }
 
             self.insert_metadata().await?;
             Ok::<(), BoxedError>(())
@@ -221,28 +423,6 @@ impl ClickhouseSink {
 
         Ok(())
     }
-
-    fn _get_latest_op(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
-        let op = self.runtime.block_on(async {
-            let mut client = self.client.get_client_handle().await?;
-            let table_name = self.sink_table_name.clone();
-            let query = format!("SELECT \"{META_TXN_ID_COL}\" FROM \"{REPLICA_METADATA_TABLE}\" WHERE \"{META_TABLE_COL}\" = '\"{table_name}\"' ORDER BY \"{META_TXN_ID_COL}\" LIMIT 1");
-            let block = client
-                .query(query)
-                .fetch_all()
-                .await?;
-
-            let row = block.rows().next();
-            match row {
-                Some(row) => {
-                    let txid: u64 = row.get(META_TXN_ID_COL)?;
-                    Ok::<Option<dozer_types::node::OpIdentifier>, BoxedError>(Some(OpIdentifier { txid, seq_in_tx: 0 }))
-                },
-                None => Ok::<Option<dozer_types::node::OpIdentifier>, BoxedError>(None),
-            }
-        })?;
-        Ok(op)
-    }
 }
 
 impl Sink for ClickhouseSink {
@@ -256,56 +436,38 @@ impl Sink for ClickhouseSink {
     }
 
     fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
-        self.latest_txid = op.id.map(|id| id.txid);
+        if let Some(id) = op.id {
+            self.latest_op_id = Some(id);
+        }
+        let table = &mut self.tables[op.port as usize];
+        let version = &mut self.last_version;
         match op.op {
-            Operation::Insert { new } => {
-                if self.table.engine == "CollapsingMergeTree" {
-                    let mut values = new.values;
-                    values.push(Field::Int8(1));
-                    self.insert_values(&values)?;
-                } else {
-                    self.insert_values(&new.values)?;
-                }
-
-                if self.batch.len() > BATCH_SIZE - 1 {
-                    self.commit_batch()?;
-                }
-            }
-            Operation::Delete { old } => {
-                if self.table.engine != "CollapsingMergeTree" {
-                    return Err(BoxedError::from(ClickhouseSinkError::UnsupportedOperation));
-                }
-                let mut values = old.values;
-                values.push(Field::Int(-1));
-                self.insert_values(&values)?;
-            }
-            Operation::Update { new, old } => {
-                if self.table.engine != "CollapsingMergeTree" {
-                    return Err(BoxedError::from(ClickhouseSinkError::UnsupportedOperation));
-                }
-                let mut values = old.values;
-                values.push(Field::Int8(-1));
-                self.insert_values(&values)?;
-
-                let mut values = new.values;
-                values.push(Field::Int8(1));
-                self.insert_values(&values)?;
+            Operation::Insert { new } => table.insert_values(new.values, version),
+            Operation::Delete { old } => table.delete_values(old.values, version)?,
+            Operation::Update { old, new } => {
+                table.update_values(old.values, new.values, version)?
             }
             Operation::BatchInsert { new } => {
                 for record in new {
-                    let mut values = record.values;
-                    if self.table.engine == "CollapsingMergeTree" {
-                        values.push(Field::Int8(1));
-                    }
-                    self.insert_values(&values)?;
+                    table.insert_values(record.values, version);
                 }
-                self.commit_batch()?;
             }
         }
 
         Ok(())
     }
 
+    fn on_schema_change(&mut self, port: PortHandle, schema: &Schema) -> Result<(), BoxedError> {
+        let table = &mut self.tables[port as usize];
+        self.runtime
+            .block_on(table.alter_table(&self.client, schema))
+    }
+
+    fn on_truncate(&mut self, port: PortHandle) -> Result<(), BoxedError> {
+        let table = &mut self.tables[port as usize];
+        self.runtime.block_on(table.truncate(&self.client))
+    }
+
     fn on_source_snapshotting_started(
         &mut self,
         _connection_name: String,
@@ -318,21 +480,31 @@ impl Sink for ClickhouseSink {
         _connection_name: String,
         id: Option<OpIdentifier>,
     ) -> Result<(), BoxedError> {
-        self.latest_txid = id.map(|opid| opid.txid);
+        if id.is_some() {
+            self.latest_op_id = id;
+        }
         self.commit_batch()?;
         Ok(())
     }
 
-    fn set_source_state(&mut self, _source_state: &[u8]) -> Result<(), BoxedError> {
+    fn set_source_state(&mut self, source_state: &[u8]) -> Result<(), BoxedError> {
+        self.source_state = Some(source_state.to_vec());
         Ok(())
     }
 
     fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
-        Ok(None)
+        Ok(self.checkpoint.source_state.clone())
     }
 
     fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
-        // self.get_latest_op()
-        Ok(None)
+        Ok(self.checkpoint.op_id)
+    }
+
+    fn preferred_batch_size(&self) -> Option<u64> {
+        Some(BATCH_SIZE)
+    }
+
+    fn supports_batching(&self) -> bool {
+        true
     }
 }
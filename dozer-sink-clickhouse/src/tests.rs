@@ -1,9 +1,26 @@
 use crate::client::ClickhouseClient;
+use crate::ddl::{
+    get_add_column_query, get_create_table_query, get_delete_query, get_modify_column_query,
+    get_truncate_query,
+};
+use crate::engine::{TableEngine, DEFAULT_IS_DELETED_COLUMN, DEFAULT_VERSION_COLUMN};
+use crate::metadata::{ReplicationMetadata, REPLICA_METADATA_TABLE};
 use crate::schema::ClickhouseSchema;
+use crate::ClickhouseSinkFactory;
 use clickhouse_rs::types::Query;
+use dozer_core::event::EventHub;
+use dozer_core::node::{PortHandle, Sink, SinkFactory};
 use dozer_core::tokio;
-use dozer_types::models::sink::ClickhouseSinkConfig;
-use dozer_types::types::{FieldDefinition, FieldType, Schema};
+use dozer_core::tokio::runtime::Runtime;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_types::models::sink::{
+    ClickhouseSinkConfig, ClickhouseSinkTableConfig, ClickhouseTableOptions,
+};
+use dozer_types::node::OpIdentifier;
+use dozer_types::types::{
+    Field, FieldDefinition, FieldType, Operation, Record, Schema, SourceDefinition, TableOperation,
+};
+use std::sync::Arc;
 
 fn get_client() -> ClickhouseClient {
     ClickhouseClient::new(get_sink_config())
@@ -11,20 +28,26 @@ fn get_client() -> ClickhouseClient {
 
 fn get_sink_config() -> ClickhouseSinkConfig {
     ClickhouseSinkConfig {
-        source_table_name: "source_table".to_string(),
-        sink_table_name: "sink_table".to_string(),
         scheme: "tcp".to_string(),
-        create_table_options: None,
         user: "default".to_string(),
         password: None,
         database: "default".to_string(),
         host: "localhost".to_string(),
         port: 9000,
         options: vec![],
+        tables: vec![get_table_config("source_table", "sink_table")],
+    }
+}
+
+fn get_table_config(source_table_name: &str, sink_table_name: &str) -> ClickhouseSinkTableConfig {
+    ClickhouseSinkTableConfig {
+        source_table_name: source_table_name.to_string(),
+        sink_table_name: sink_table_name.to_string(),
+        create_table_options: None,
     }
 }
 
-fn _get_dozer_schema() -> Schema {
+fn get_dozer_schema() -> Schema {
     Schema {
         fields: vec![
             FieldDefinition {
@@ -63,12 +86,13 @@ async fn create_table(table_name: &str) {
 #[ignore]
 async fn test_get_clickhouse_table() {
     let client = get_client();
-    let sink_config = get_sink_config();
-    create_table(&sink_config.sink_table_name).await;
-    let clickhouse_table = ClickhouseSchema::get_clickhouse_table(client, &sink_config)
-        .await
-        .unwrap();
-    assert_eq!(clickhouse_table.name, sink_config.sink_table_name);
+    let table_config = get_table_config("source_table", "sink_table");
+    create_table(&table_config.sink_table_name).await;
+    let clickhouse_table =
+        ClickhouseSchema::get_clickhouse_table(client, &table_config.sink_table_name)
+            .await
+            .unwrap();
+    assert_eq!(clickhouse_table.name, table_config.sink_table_name);
 }
 
 use clickhouse_rs::{Block, Pool};
@@ -103,3 +127,388 @@ async fn clickhouse_test() -> Result<(), Box<dyn Error>> {
     client.insert(table, block).await?;
     Ok(())
 }
+
+fn build_sink(runtime: &Arc<Runtime>, config: ClickhouseSinkConfig) -> Box<dyn Sink> {
+    let factory = ClickhouseSinkFactory::new(config, runtime.clone());
+    let schemas = factory
+        .get_input_ports()
+        .into_iter()
+        .map(|port| (port, get_dozer_schema()))
+        .collect();
+    runtime
+        .block_on(factory.build(schemas, EventHub::new(10)))
+        .unwrap()
+}
+
+fn insert(id: u64, txid: u64) -> TableOperation {
+    insert_into(DEFAULT_PORT_HANDLE, id, txid)
+}
+
+fn insert_into(port: PortHandle, id: u64, txid: u64) -> TableOperation {
+    TableOperation {
+        id: Some(OpIdentifier::new(txid, 0)),
+        op: Operation::Insert {
+            new: Record::new(vec![Field::UInt(id), Field::String(format!("data_{id}"))]),
+        },
+        port,
+    }
+}
+
+fn get_merge_tree_options() -> ClickhouseTableOptions {
+    ClickhouseTableOptions {
+        engine: Some("MergeTree".to_string()),
+        primary_keys: Some(vec!["id".to_string()]),
+        partition_by: None,
+        sample_by: None,
+        order_by: Some(vec!["id".to_string()]),
+        cluster: None,
+        version_column: None,
+        is_deleted_column: None,
+    }
+}
+
+#[test]
+#[ignore]
+fn test_resume_from_checkpoint() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let mut config = get_sink_config();
+    config.tables = vec![get_table_config("source_table", "resume_table")];
+    config.tables[0].create_table_options = Some(get_merge_tree_options());
+    runtime.block_on(async {
+        let client = get_client();
+        client.drop_table("resume_table").await.unwrap();
+        client.drop_table(REPLICA_METADATA_TABLE).await.unwrap();
+    });
+
+    let mut sink = build_sink(&runtime, config.clone());
+    assert_eq!(sink.get_latest_op_id().unwrap(), None);
+    assert_eq!(sink.get_source_state().unwrap(), None);
+    sink.set_source_state(b"state_1").unwrap();
+    sink.process(insert(1, 1)).unwrap();
+    sink.flush_batch().unwrap();
+    sink.set_source_state(b"state_2").unwrap();
+    sink.process(insert(2, 2)).unwrap();
+    // The sink is killed before flushing the second batch.
+    drop(sink);
+
+    let mut sink = build_sink(&runtime, config.clone());
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(1, 0))
+    );
+    assert_eq!(sink.get_source_state().unwrap(), Some(b"state_1".to_vec()));
+    sink.set_source_state(b"state_2").unwrap();
+    sink.process(insert(2, 2)).unwrap();
+    sink.flush_batch().unwrap();
+    drop(sink);
+
+    let mut sink = build_sink(&runtime, config);
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(2, 0))
+    );
+    assert_eq!(sink.get_source_state().unwrap(), Some(b"state_2".to_vec()));
+}
+
+#[test]
+fn test_input_port_per_table() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let mut config = get_sink_config();
+    config.tables = vec![
+        get_table_config("users", "users_sink"),
+        get_table_config("orders", "orders_sink"),
+    ];
+    let factory = ClickhouseSinkFactory::new(config, runtime);
+    let ports = factory.get_input_ports();
+    assert_eq!(ports, vec![0, 1]);
+    assert_eq!(factory.get_input_port_name(&ports[0]), "users");
+    assert_eq!(factory.get_input_port_name(&ports[1]), "orders");
+}
+
+#[test]
+#[ignore]
+fn test_multiple_tables_share_checkpoint() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let mut config = get_sink_config();
+    config.tables = vec![
+        get_table_config("users", "users_sink"),
+        get_table_config("orders", "orders_sink"),
+    ];
+    for table in &mut config.tables {
+        table.create_table_options = Some(get_merge_tree_options());
+    }
+    runtime.block_on(async {
+        let client = get_client();
+        client.drop_table("users_sink").await.unwrap();
+        client.drop_table("orders_sink").await.unwrap();
+        client.drop_table(REPLICA_METADATA_TABLE).await.unwrap();
+    });
+
+    let mut sink = build_sink(&runtime, config.clone());
+    sink.process(insert_into(0, 1, 1)).unwrap();
+    sink.process(insert_into(1, 1, 2)).unwrap();
+    sink.flush_batch().unwrap();
+    drop(sink);
+
+    let rows = runtime.block_on(async {
+        let client = get_client();
+        let mut rows = vec![];
+        for table in ["users_sink", "orders_sink"] {
+            let result = client
+                .fetch_all(
+                    &format!("SELECT id, data FROM {table}"),
+                    get_dozer_schema().fields,
+                    None,
+                )
+                .await
+                .unwrap();
+            rows.extend(result.rows);
+        }
+        rows
+    });
+    assert_eq!(rows.len(), 2);
+
+    let mut sink = build_sink(&runtime, config);
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(2, 0))
+    );
+}
+
+#[test]
+#[ignore]
+fn test_schema_change() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let mut config = get_sink_config();
+    config.tables = vec![get_table_config("source_table", "schema_change_table")];
+    config.tables[0].create_table_options = Some(get_merge_tree_options());
+    runtime.block_on(async {
+        let client = get_client();
+        client.drop_table("schema_change_table").await.unwrap();
+        client.drop_table(REPLICA_METADATA_TABLE).await.unwrap();
+    });
+
+    let mut schema = get_dozer_schema();
+    schema.field(
+        FieldDefinition::new(
+            "count".to_string(),
+            FieldType::Int8,
+            false,
+            SourceDefinition::Dynamic,
+        ),
+        false,
+    );
+    let factory = ClickhouseSinkFactory::new(config, runtime.clone());
+    let mut sink = runtime
+        .block_on(factory.build(
+            [(DEFAULT_PORT_HANDLE, schema.clone())].into(),
+            EventHub::new(10),
+        ))
+        .unwrap();
+    let insert = |values, txid| TableOperation {
+        id: Some(OpIdentifier::new(txid, 0)),
+        op: Operation::Insert {
+            new: Record::new(values),
+        },
+        port: DEFAULT_PORT_HANDLE,
+    };
+
+    sink.process(insert(
+        vec![
+            Field::UInt(1),
+            Field::String("a".to_string()),
+            Field::Int8(1),
+        ],
+        1,
+    ))
+    .unwrap();
+    // `count` is widened, and `data` is dropped from the source, so it becomes nullable.
+    let mut evolved = schema.clone();
+    evolved.fields[1].nullable = true;
+    evolved.fields[2].typ = FieldType::Int;
+    sink.on_schema_change(DEFAULT_PORT_HANDLE, &evolved)
+        .unwrap();
+    sink.process(insert(
+        vec![Field::UInt(2), Field::Null, Field::Int(300)],
+        2,
+    ))
+    .unwrap();
+    sink.flush_batch().unwrap();
+
+    let rows = runtime.block_on(async {
+        get_client()
+            .fetch_all(
+                "SELECT id, data, count FROM schema_change_table ORDER BY id",
+                evolved.fields.clone(),
+                None,
+            )
+            .await
+            .unwrap()
+            .rows
+    });
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                Field::UInt(1),
+                Field::String("a".to_string()),
+                Field::Int(1)
+            ],
+            vec![Field::UInt(2), Field::Null, Field::Int(300)],
+        ]
+    );
+}
+
+#[test]
+fn test_table_engine() {
+    assert_eq!(
+        TableEngine::new("CollapsingMergeTree", None),
+        TableEngine::Collapsing
+    );
+    assert_eq!(
+        TableEngine::new("MergeTree()", None),
+        TableEngine::MergeTree
+    );
+    assert_eq!(TableEngine::new("Memory", None), TableEngine::Other);
+    assert!(TableEngine::Collapsing.is_idempotent());
+    assert!(!TableEngine::MergeTree.is_idempotent());
+    assert!(!TableEngine::Other.is_idempotent());
+    assert_eq!(
+        TableEngine::new("ReplicatedReplacingMergeTree('/path', '{replica}')", None),
+        TableEngine::Replacing {
+            version_column: DEFAULT_VERSION_COLUMN.to_string(),
+            is_deleted_column: DEFAULT_IS_DELETED_COLUMN.to_string(),
+        }
+    );
+    let options = ClickhouseTableOptions {
+        engine: Some("ReplacingMergeTree".to_string()),
+        primary_keys: None,
+        partition_by: None,
+        sample_by: None,
+        order_by: None,
+        cluster: None,
+        version_column: Some("ver".to_string()),
+        is_deleted_column: None,
+    };
+    let engine = TableEngine::new("ReplacingMergeTree", Some(&options));
+    assert_eq!(
+        engine,
+        TableEngine::Replacing {
+            version_column: "ver".to_string(),
+            is_deleted_column: DEFAULT_IS_DELETED_COLUMN.to_string(),
+        }
+    );
+    assert!(engine.is_idempotent());
+}
+
+#[test]
+fn test_create_table_query_adds_engine_columns() {
+    let options = ClickhouseTableOptions {
+        engine: Some("ReplacingMergeTree".to_string()),
+        primary_keys: Some(vec!["id".to_string()]),
+        partition_by: None,
+        sample_by: None,
+        order_by: Some(vec!["id".to_string()]),
+        cluster: None,
+        version_column: None,
+        is_deleted_column: None,
+    };
+    let query = get_create_table_query("t", &get_dozer_schema().fields, Some(options.clone()));
+    assert!(query.contains("_version UInt64"));
+    assert!(query.contains("_is_deleted Bool"));
+    assert!(query.contains("ENGINE = ReplacingMergeTree(_version, _is_deleted)"));
+
+    let options = ClickhouseTableOptions {
+        engine: Some("CollapsingMergeTree".to_string()),
+        ..options
+    };
+    let query = get_create_table_query("t", &get_dozer_schema().fields, Some(options));
+    assert!(query.contains("sign Int8"));
+    assert!(query.contains("ENGINE = CollapsingMergeTree(sign)"));
+}
+
+#[test]
+fn test_delete_query() {
+    assert_eq!(
+        get_delete_query("t", &["id"], &[vec![Field::UInt(1)], vec![Field::UInt(2)]]).unwrap(),
+        "DELETE FROM t WHERE id IN (1, 2)"
+    );
+    assert_eq!(
+        get_delete_query(
+            "t",
+            &["id", "data"],
+            &[vec![Field::Int(-1), Field::String("it's".to_string())]]
+        )
+        .unwrap(),
+        r"DELETE FROM t WHERE (id, data) IN ((-1, 'it\'s'))"
+    );
+}
+
+#[test]
+fn test_add_column_query() {
+    let field = FieldDefinition::new(
+        "email".to_string(),
+        FieldType::String,
+        true,
+        SourceDefinition::Dynamic,
+    );
+    assert_eq!(
+        get_add_column_query("t", &field, None),
+        "ALTER TABLE t ADD COLUMN IF NOT EXISTS email Nullable(String)"
+    );
+    assert_eq!(
+        get_add_column_query("t", &field, Some("c")),
+        "ALTER TABLE t ON CLUSTER c ADD COLUMN IF NOT EXISTS email Nullable(String)"
+    );
+}
+
+#[test]
+fn test_modify_column_query() {
+    let field = FieldDefinition::new(
+        "count".to_string(),
+        FieldType::Int,
+        true,
+        SourceDefinition::Dynamic,
+    );
+    assert_eq!(
+        get_modify_column_query("t", &field, None),
+        "ALTER TABLE t MODIFY COLUMN count Nullable(Int64)"
+    );
+    assert_eq!(
+        get_modify_column_query("t", &field, Some("c")),
+        "ALTER TABLE t ON CLUSTER c MODIFY COLUMN count Nullable(Int64)"
+    );
+}
+
+#[test]
+fn test_truncate_query() {
+    assert_eq!(get_truncate_query("t", None), "TRUNCATE TABLE IF EXISTS t");
+    assert_eq!(
+        get_truncate_query("t", Some("c")),
+        "TRUNCATE TABLE IF EXISTS t ON CLUSTER c"
+    );
+}
+
+#[test]
+fn test_metadata_table_has_no_engine_columns() {
+    let query = ReplicationMetadata::get_metadata().create_table_query(Some("c"));
+    assert!(query.starts_with(&format!(
+        "CREATE TABLE IF NOT EXISTS {REPLICA_METADATA_TABLE} ON CLUSTER c ("
+    )));
+    assert!(query.contains("source_state String"));
+    assert!(query.contains("ENGINE = ReplacingMergeTree\n"));
+    assert!(!query.contains("_version"));
+    assert!(!query.contains("_is_deleted"));
+}
+
+#[test]
+fn test_metadata_table_upgrade_query() {
+    assert_eq!(
+        ReplicationMetadata::get_metadata().upgrade_table_query(None),
+        format!(
+            "ALTER TABLE {REPLICA_METADATA_TABLE} MODIFY COLUMN txn_id Nullable(UInt64), \
+             ADD COLUMN IF NOT EXISTS seq_in_tx Nullable(UInt64), \
+             ADD COLUMN IF NOT EXISTS source_state String"
+        )
+    );
+}
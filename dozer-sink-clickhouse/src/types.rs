@@ -269,6 +269,46 @@ fn add_last_column_to_block(
     }
 }
 
+fn quote_string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Formats a key field as a ClickHouse literal, to be used in `WHERE` clauses.
+pub fn field_to_sql_literal(field: &Field) -> Result<String, QueryError> {
+    let literal = match field {
+        Field::UInt(value) => value.to_string(),
+        Field::U128(value) => value.to_string(),
+        Field::Int(value) => value.to_string(),
+        Field::Int8(value) => value.to_string(),
+        Field::I128(value) => value.to_string(),
+        Field::Float(value) => value.to_string(),
+        Field::Boolean(value) => value.to_string(),
+        Field::Decimal(value) => value.to_string(),
+        Field::String(value) | Field::Text(value) => quote_string(value),
+        Field::Binary(value) => format!(
+            "unhex('{}')",
+            value.iter().map(|b| format!("{b:02x}")).collect::<String>()
+        ),
+        Field::Timestamp(value) => format!(
+            "toDateTime64({}, 3, 'UTC')",
+            quote_string(
+                &value
+                    .with_timezone(&UTC)
+                    .format("%Y-%m-%d %H:%M:%S%.3f")
+                    .to_string()
+            )
+        ),
+        Field::Date(value) => format!("toDate({})", quote_string(&value.to_string())),
+        Field::Null => "NULL".to_string(),
+        other => {
+            return Err(QueryError::UnsupportedFieldType(
+                other.ty().expect("not null"),
+            ))
+        }
+    };
+    Ok(literal)
+}
+
 pub async fn insert_multi(
     mut client: ClientHandle,
     table_name: &str,
@@ -0,0 +1,288 @@
+//! Debezium change events as Avro, registered in a Confluent schema registry.
+//!
+//! The schemas are the ones the Debezium `AvroConverter` derives from the Kafka Connect types
+//! of [`crate::debezium`].
+
+use std::collections::HashSet;
+
+use apache_avro::types::Value as AvroValue;
+use apache_avro::Schema as AvroSchema;
+use dozer_types::json_types::json_to_string;
+use dozer_types::serde_json::{json, Value};
+use dozer_types::types::{DozerDuration, DozerPoint, Field, FieldDefinition, FieldType, Schema};
+use schema_registry_converter::async_impl::schema_registry::{post_schema, SrSettings};
+use schema_registry_converter::schema_registry_common::{SchemaType, SuppliedSchema};
+
+use crate::debezium::{
+    connect_type, duration_micros, epoch_days, unscaled_bytes, Envelope, POINT,
+    VARIABLE_SCALE_DECIMAL,
+};
+use crate::errors::KafkaSinkError;
+
+/// Magic byte of the Confluent wire format, followed by the schema id.
+const MAGIC_BYTE: u8 = 0;
+
+/// Avro names only allow letters, digits and underscores, with dots between namespace parts.
+fn namespace(topic: &str) -> String {
+    topic.replace(|c: char| !c.is_ascii_alphanumeric() && c != '.', "_")
+}
+
+fn nullable(typ: Value) -> Value {
+    json!(["null", typ])
+}
+
+/// Schemas of the records of a message. Named types can only be defined once, then are referenced by name.
+#[derive(Default)]
+struct SchemaBuilder {
+    defined: HashSet<String>,
+}
+
+impl SchemaBuilder {
+    fn record(&mut self, full_name: &str, fields: impl FnOnce(&mut Self) -> Vec<Value>) -> Value {
+        if !self.defined.insert(full_name.to_string()) {
+            return json!(full_name);
+        }
+        let (namespace, name) = full_name.rsplit_once('.').unwrap_or(("", full_name));
+        json!({
+            "type": "record",
+            "name": name,
+            "namespace": namespace,
+            "fields": fields(self),
+            "connect.name": full_name,
+        })
+    }
+
+    fn field_type(&mut self, typ: FieldType) -> Value {
+        let (connect_type, name) = connect_type(typ);
+        match typ {
+            FieldType::Decimal => self.record(VARIABLE_SCALE_DECIMAL, |_| {
+                vec![
+                    json!({ "name": "scale", "type": "int" }),
+                    json!({ "name": "value", "type": "bytes" }),
+                ]
+            }),
+            FieldType::Point => self.record(POINT, |_| {
+                vec![
+                    json!({ "name": "x", "type": "double" }),
+                    json!({ "name": "y", "type": "double" }),
+                ]
+            }),
+            _ => {
+                let avro_type = match connect_type {
+                    "int8" | "int32" => "int",
+                    "int64" => "long",
+                    other => other,
+                };
+                match name {
+                    Some(name) => json!({ "type": avro_type, "connect.name": name }),
+                    None => json!(avro_type),
+                }
+            }
+        }
+    }
+
+    fn field(&mut self, field: &FieldDefinition) -> Value {
+        let typ = self.field_type(field.typ);
+        if field.nullable {
+            json!({ "name": field.name, "type": nullable(typ), "default": null })
+        } else {
+            json!({ "name": field.name, "type": typ })
+        }
+    }
+
+    fn fields<'a>(&mut self, fields: impl IntoIterator<Item = &'a FieldDefinition>) -> Vec<Value> {
+        fields.into_iter().map(|field| self.field(field)).collect()
+    }
+}
+
+/// Avro schema of the message key, `None` if there is no primary key.
+pub fn key_schema(topic: &str, schema: &Schema) -> Option<Value> {
+    if schema.primary_index.is_empty() {
+        return None;
+    }
+    let fields = schema.primary_index.iter().map(|i| &schema.fields[*i]);
+    Some(
+        SchemaBuilder::default().record(&format!("{}.Key", namespace(topic)), |builder| {
+            builder.fields(fields)
+        }),
+    )
+}
+
+/// Avro schema of the change event envelope.
+pub fn envelope_schema(topic: &str, schema: &Schema) -> Value {
+    let namespace = namespace(topic);
+    let value_name = format!("{namespace}.Value");
+    SchemaBuilder::default().record(&format!("{namespace}.Envelope"), |builder| {
+        let before = builder.record(&value_name, |builder| builder.fields(&schema.fields));
+        let after = builder.record(&value_name, |_| unreachable!("defined by before"));
+        vec![
+            json!({ "name": "before", "type": nullable(before), "default": null }),
+            json!({ "name": "after", "type": nullable(after), "default": null }),
+            json!({ "name": "op", "type": "string" }),
+            json!({ "name": "ts_ms", "type": nullable(json!("long")), "default": null }),
+        ]
+    })
+}
+
+/// Converts a field to the Avro value of its type in [`envelope_schema`], ignoring nullability.
+pub fn avro_value(field: &Field) -> Result<AvroValue, KafkaSinkError> {
+    let value = match field {
+        Field::UInt(v) => AvroValue::Long(
+            i64::try_from(*v).map_err(|_| KafkaSinkError::IntegerOverflow(field.clone()))?,
+        ),
+        Field::U128(v) => AvroValue::String(v.to_string()),
+        Field::Int(v) => AvroValue::Long(*v),
+        Field::Int8(v) => AvroValue::Int(*v as i32),
+        Field::I128(v) => AvroValue::String(v.to_string()),
+        Field::Float(v) => AvroValue::Double(v.0),
+        Field::Boolean(v) => AvroValue::Boolean(*v),
+        Field::String(v) | Field::Text(v) => AvroValue::String(v.clone()),
+        Field::Binary(v) => AvroValue::Bytes(v.clone()),
+        Field::Decimal(v) => AvroValue::Record(vec![
+            ("scale".to_string(), AvroValue::Int(v.scale() as i32)),
+            ("value".to_string(), AvroValue::Bytes(unscaled_bytes(v))),
+        ]),
+        Field::Timestamp(v) => AvroValue::Long(v.timestamp_micros()),
+        Field::Date(v) => AvroValue::Int(epoch_days(v)),
+        Field::Json(v) => AvroValue::String(json_to_string(v)),
+        Field::Point(DozerPoint(p)) => AvroValue::Record(vec![
+            ("x".to_string(), AvroValue::Double(p.x().0)),
+            ("y".to_string(), AvroValue::Double(p.y().0)),
+        ]),
+        Field::Duration(DozerDuration(v, _)) => AvroValue::Long(duration_micros(field, v)?),
+        Field::Null => AvroValue::Null,
+    };
+    Ok(value)
+}
+
+fn union(value: Option<AvroValue>) -> AvroValue {
+    match value {
+        None | Some(AvroValue::Null) => AvroValue::Union(0, Box::new(AvroValue::Null)),
+        Some(value) => AvroValue::Union(1, Box::new(value)),
+    }
+}
+
+fn avro_record<'a>(
+    fields: impl Iterator<Item = (&'a FieldDefinition, &'a Field)>,
+) -> Result<AvroValue, KafkaSinkError> {
+    let mut record = vec![];
+    for (definition, field) in fields {
+        let value = avro_value(field)?;
+        let value = if definition.nullable {
+            union(Some(value))
+        } else {
+            value
+        };
+        record.push((definition.name.clone(), value));
+    }
+    Ok(AvroValue::Record(record))
+}
+
+#[derive(Debug, Clone)]
+struct RegisteredSchema {
+    id: u32,
+    schema: AvroSchema,
+}
+
+impl RegisteredSchema {
+    async fn register(
+        sr_settings: &SrSettings,
+        subject: String,
+        schema: Value,
+    ) -> Result<Self, KafkaSinkError> {
+        let avro_schema = AvroSchema::parse(&schema)?;
+        let registered = post_schema(
+            sr_settings,
+            subject,
+            SuppliedSchema {
+                name: None,
+                schema_type: SchemaType::Avro,
+                schema: schema.to_string(),
+                references: vec![],
+            },
+        )
+        .await?;
+        Ok(Self {
+            id: registered.id,
+            schema: avro_schema,
+        })
+    }
+
+    /// Encodes `value` in the Confluent wire format.
+    fn encode(&self, value: AvroValue) -> Result<Vec<u8>, KafkaSinkError> {
+        let mut bytes = vec![MAGIC_BYTE];
+        bytes.extend_from_slice(&self.id.to_be_bytes());
+        bytes.extend(apache_avro::to_avro_datum(&self.schema, value)?);
+        Ok(bytes)
+    }
+}
+
+/// Encodes keys and envelopes as Avro, with schemas registered under the `TopicNameStrategy` subjects.
+#[derive(Debug, Clone)]
+pub struct AvroEncoder {
+    schema: Schema,
+    key_schema: Option<RegisteredSchema>,
+    envelope_schema: RegisteredSchema,
+}
+
+impl AvroEncoder {
+    pub async fn register(
+        schema_registry_url: &str,
+        topic: &str,
+        schema: Schema,
+    ) -> Result<Self, KafkaSinkError> {
+        let sr_settings = SrSettings::new(schema_registry_url.to_string());
+        let key_schema = match key_schema(topic, &schema) {
+            Some(key_schema) => Some(
+                RegisteredSchema::register(&sr_settings, format!("{topic}-key"), key_schema)
+                    .await?,
+            ),
+            None => None,
+        };
+        let envelope_schema = RegisteredSchema::register(
+            &sr_settings,
+            format!("{topic}-value"),
+            envelope_schema(topic, &schema),
+        )
+        .await?;
+        Ok(Self {
+            schema,
+            key_schema,
+            envelope_schema,
+        })
+    }
+
+    pub fn encode_key(&self, key: &[Field]) -> Result<Vec<u8>, KafkaSinkError> {
+        let key_schema = self
+            .key_schema
+            .as_ref()
+            .expect("keys are only encoded with a primary key");
+        let fields = self
+            .schema
+            .primary_index
+            .iter()
+            .map(|i| &self.schema.fields[*i]);
+        key_schema.encode(avro_record(fields.zip(key))?)
+    }
+
+    pub fn encode_envelope(&self, envelope: &Envelope) -> Result<Vec<u8>, KafkaSinkError> {
+        let record = |values: Option<&[Field]>| {
+            values
+                .map(|values| avro_record(self.schema.fields.iter().zip(values)))
+                .transpose()
+        };
+        let value = AvroValue::Record(vec![
+            ("before".to_string(), union(record(envelope.before)?)),
+            ("after".to_string(), union(record(envelope.after)?)),
+            (
+                "op".to_string(),
+                AvroValue::String(envelope.op.code().to_string()),
+            ),
+            (
+                "ts_ms".to_string(),
+                union(Some(AvroValue::Long(envelope.ts_ms))),
+            ),
+        ]);
+        self.envelope_schema.encode(value)
+    }
+}
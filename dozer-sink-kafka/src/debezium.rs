@@ -0,0 +1,243 @@
+//! Debezium change events, with the schema of the Kafka Connect `JsonConverter`.
+//!
+//! Types follow the Debezium Postgres connector, so the events can be read back by the Kafka connector.
+
+use std::time::Duration;
+
+use base64::{engine, Engine};
+use dozer_types::chrono::{NaiveDate, NaiveDateTime};
+use dozer_types::json_types::json_to_string;
+use dozer_types::rust_decimal::Decimal;
+use dozer_types::serde_json::{json, Map, Value};
+use dozer_types::types::{DozerDuration, DozerPoint, Field, FieldDefinition, FieldType, Schema};
+
+use crate::errors::KafkaSinkError;
+
+pub const DATE: &str = "io.debezium.time.Date";
+pub const MICRO_TIMESTAMP: &str = "io.debezium.time.MicroTimestamp";
+pub const MICRO_DURATION: &str = "io.debezium.time.MicroDuration";
+pub const JSON: &str = "io.debezium.data.Json";
+pub const VARIABLE_SCALE_DECIMAL: &str = "io.debezium.data.VariableScaleDecimal";
+pub const POINT: &str = "io.debezium.data.geometry.Point";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebeziumOp {
+    Create,
+    Update,
+    Delete,
+    Truncate,
+}
+
+impl DebeziumOp {
+    pub fn code(&self) -> &'static str {
+        match self {
+            DebeziumOp::Create => "c",
+            DebeziumOp::Update => "u",
+            DebeziumOp::Delete => "d",
+            DebeziumOp::Truncate => "t",
+        }
+    }
+}
+
+/// A change of one record. `before` and `after` hold all the fields of the schema, and are both
+/// `None` for a truncate.
+#[derive(Debug, Clone, Copy)]
+pub struct Envelope<'a> {
+    pub op: DebeziumOp,
+    pub before: Option<&'a [Field]>,
+    pub after: Option<&'a [Field]>,
+    pub ts_ms: i64,
+}
+
+/// The Kafka Connect type of a field type, with the Debezium semantic type name if any.
+///
+/// Structs (`Decimal`, `Point`) have no primitive type.
+pub fn connect_type(typ: FieldType) -> (&'static str, Option<&'static str>) {
+    match typ {
+        FieldType::UInt | FieldType::Int => ("int64", None),
+        FieldType::Int8 => ("int8", None),
+        FieldType::U128 | FieldType::I128 => ("string", None),
+        FieldType::Float => ("double", None),
+        FieldType::Boolean => ("boolean", None),
+        FieldType::String | FieldType::Text => ("string", None),
+        FieldType::Binary => ("bytes", None),
+        FieldType::Decimal => ("struct", Some(VARIABLE_SCALE_DECIMAL)),
+        FieldType::Timestamp => ("int64", Some(MICRO_TIMESTAMP)),
+        FieldType::Date => ("int32", Some(DATE)),
+        FieldType::Json => ("string", Some(JSON)),
+        FieldType::Point => ("struct", Some(POINT)),
+        FieldType::Duration => ("int64", Some(MICRO_DURATION)),
+    }
+}
+
+fn primitive_schema(typ: &str, optional: bool, field: &str) -> Value {
+    json!({ "type": typ, "optional": optional, "field": field })
+}
+
+fn field_schema(field: &FieldDefinition) -> Value {
+    let (typ, name) = connect_type(field.typ);
+    let mut schema = primitive_schema(typ, field.nullable, &field.name);
+    if let Some(name) = name {
+        schema["name"] = json!(name);
+    }
+    match field.typ {
+        FieldType::Decimal => {
+            schema["fields"] = json!([
+                primitive_schema("int32", false, "scale"),
+                primitive_schema("bytes", false, "value"),
+            ]);
+        }
+        FieldType::Point => {
+            schema["fields"] = json!([
+                primitive_schema("double", false, "x"),
+                primitive_schema("double", false, "y"),
+            ]);
+        }
+        _ => (),
+    }
+    schema
+}
+
+fn struct_schema(fields: &[&FieldDefinition], name: String, optional: bool) -> Value {
+    json!({
+        "type": "struct",
+        "optional": optional,
+        "name": name,
+        "fields": fields.iter().map(|field| field_schema(field)).collect::<Vec<_>>(),
+    })
+}
+
+/// Schema of the message key, made of the primary key fields.
+pub fn key_schema(topic: &str, schema: &Schema) -> Value {
+    let fields = schema
+        .primary_index
+        .iter()
+        .map(|i| &schema.fields[*i])
+        .collect::<Vec<_>>();
+    struct_schema(&fields, format!("{topic}.Key"), false)
+}
+
+/// Schema of the message value, the change event envelope.
+pub fn envelope_schema(topic: &str, schema: &Schema) -> Value {
+    let fields = schema.fields.iter().collect::<Vec<_>>();
+    let mut before = struct_schema(&fields, format!("{topic}.Value"), true);
+    before["field"] = json!("before");
+    let mut after = before.clone();
+    after["field"] = json!("after");
+    json!({
+        "type": "struct",
+        "optional": false,
+        "name": format!("{topic}.Envelope"),
+        "fields": [
+            before,
+            after,
+            primitive_schema("string", false, "op"),
+            primitive_schema("int64", true, "ts_ms"),
+        ],
+    })
+}
+
+/// The unscaled value of a decimal, as minimal big endian two's complement bytes.
+pub fn unscaled_bytes(decimal: &Decimal) -> Vec<u8> {
+    let bytes = decimal.mantissa().to_be_bytes();
+    let sign_byte = if decimal.mantissa() < 0 { 0xff } else { 0 };
+    // Keep one sign byte if the next byte doesn't carry the sign.
+    let start = bytes
+        .windows(2)
+        .position(|pair| pair[0] != sign_byte || (pair[1] & 0x80) != (sign_byte & 0x80))
+        .unwrap_or(bytes.len() - 1);
+    bytes[start..].to_vec()
+}
+
+pub fn epoch_days(date: &NaiveDate) -> i32 {
+    date.signed_duration_since(NaiveDateTime::UNIX_EPOCH.date())
+        .num_days() as i32
+}
+
+pub fn duration_micros(field: &Field, duration: &Duration) -> Result<i64, KafkaSinkError> {
+    i64::try_from(duration.as_micros()).map_err(|_| KafkaSinkError::IntegerOverflow(field.clone()))
+}
+
+/// Converts a field to its `JsonConverter` representation.
+pub fn json_value(field: &Field) -> Result<Value, KafkaSinkError> {
+    let value = match field {
+        Field::UInt(v) => {
+            json!(i64::try_from(*v).map_err(|_| KafkaSinkError::IntegerOverflow(field.clone()))?)
+        }
+        Field::U128(v) => json!(v.to_string()),
+        Field::Int(v) => json!(v),
+        Field::Int8(v) => json!(v),
+        Field::I128(v) => json!(v.to_string()),
+        Field::Float(v) => json!(v.0),
+        Field::Boolean(v) => json!(v),
+        Field::String(v) | Field::Text(v) => json!(v),
+        Field::Binary(v) => json!(engine::general_purpose::STANDARD.encode(v)),
+        Field::Decimal(v) => json!({
+            "scale": v.scale(),
+            "value": engine::general_purpose::STANDARD.encode(unscaled_bytes(v)),
+        }),
+        Field::Timestamp(v) => json!(v.timestamp_micros()),
+        Field::Date(v) => json!(epoch_days(v)),
+        Field::Json(v) => json!(json_to_string(v)),
+        Field::Point(DozerPoint(p)) => json!({ "x": p.x().0, "y": p.y().0 }),
+        Field::Duration(DozerDuration(v, _)) => json!(duration_micros(field, v)?),
+        Field::Null => Value::Null,
+    };
+    Ok(value)
+}
+
+fn json_struct<'a>(
+    fields: impl Iterator<Item = (&'a FieldDefinition, &'a Field)>,
+) -> Result<Value, KafkaSinkError> {
+    let mut object = Map::new();
+    for (definition, field) in fields {
+        object.insert(definition.name.clone(), json_value(field)?);
+    }
+    Ok(Value::Object(object))
+}
+
+/// Encodes keys and envelopes as `JsonConverter` messages with embedded schemas.
+#[derive(Debug, Clone)]
+pub struct JsonEncoder {
+    schema: Schema,
+    key_schema: Value,
+    envelope_schema: Value,
+}
+
+impl JsonEncoder {
+    pub fn new(topic: &str, schema: Schema) -> Self {
+        Self {
+            key_schema: key_schema(topic, &schema),
+            envelope_schema: envelope_schema(topic, &schema),
+            schema,
+        }
+    }
+
+    pub fn encode_key(&self, key: &[Field]) -> Result<Vec<u8>, KafkaSinkError> {
+        let payload = json_struct(
+            self.schema
+                .primary_index
+                .iter()
+                .map(|i| &self.schema.fields[*i])
+                .zip(key),
+        )?;
+        let message = json!({ "schema": self.key_schema, "payload": payload });
+        Ok(dozer_types::serde_json::to_vec(&message)?)
+    }
+
+    pub fn encode_envelope(&self, envelope: &Envelope) -> Result<Vec<u8>, KafkaSinkError> {
+        let record = |values: Option<&[Field]>| {
+            values.map_or(Ok(Value::Null), |values| {
+                json_struct(self.schema.fields.iter().zip(values))
+            })
+        };
+        let payload = json!({
+            "before": record(envelope.before)?,
+            "after": record(envelope.after)?,
+            "op": envelope.op.code(),
+            "ts_ms": envelope.ts_ms,
+        });
+        let message = json!({ "schema": self.envelope_schema, "payload": payload });
+        Ok(dozer_types::serde_json::to_vec(&message)?)
+    }
+}
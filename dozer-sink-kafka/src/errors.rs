@@ -0,0 +1,37 @@
+use dozer_types::{
+    serde_json,
+    thiserror::{self, Error},
+    types::Field,
+};
+use rdkafka::types::RDKafkaErrorCode;
+use schema_registry_converter::error::SRCError;
+
+#[derive(Error, Debug)]
+pub enum KafkaSinkError {
+    #[error("Kafka error: {0}")]
+    Kafka(#[from] rdkafka::error::KafkaError),
+
+    #[error("JSON encode error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Avro error: {0}")]
+    Avro(#[from] apache_avro::Error),
+
+    #[error("Schema registry error: {0}")]
+    SchemaRegistry(#[from] SRCError),
+
+    #[error("Value {0} does not fit in a 64 bit integer")]
+    IntegerOverflow(Field),
+
+    #[error("Failed to create topic {topic}: {code}")]
+    CreateTopic {
+        topic: String,
+        code: RDKafkaErrorCode,
+    },
+
+    #[error("Timed out reading checkpoints from topic {0}")]
+    CheckpointTimeout(String),
+
+    #[error("Invalid checkpoint source state: {0}")]
+    InvalidSourceState(#[from] base64::DecodeError),
+}
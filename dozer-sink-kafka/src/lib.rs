@@ -0,0 +1,7 @@
+pub mod avro;
+pub mod debezium;
+pub mod errors;
+mod sink;
+pub use sink::KafkaSinkFactory;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,471 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use base64::{engine, Engine};
+use dozer_core::dag_schemas::field_changes;
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{PortHandle, Sink, SinkFactory};
+use dozer_core::tokio::runtime::Runtime;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::log::{debug, info, warn};
+use dozer_types::models::ingestion_types::KafkaConfig;
+use dozer_types::models::sink::KafkaSinkConfig;
+use dozer_types::node::OpIdentifier;
+use dozer_types::serde::{Deserialize, Serialize};
+use dozer_types::serde_json;
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Field, Operation, Schema, TableOperation};
+use rdkafka::admin::{AdminClient, AdminOptions, NewTopic, TopicReplication};
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::error::KafkaError;
+use rdkafka::message::Message;
+use rdkafka::producer::{BaseRecord, DefaultProducerContext, Producer, ThreadedProducer};
+use rdkafka::types::RDKafkaErrorCode;
+use rdkafka::{Offset, TopicPartitionList};
+
+use crate::avro::AvroEncoder;
+use crate::debezium::{DebeziumOp, Envelope, JsonEncoder};
+use crate::errors::KafkaSinkError;
+
+/// Compacted topic holding the latest checkpoint of every sink, keyed by sink topic.
+pub const METADATA_TOPIC: &str = "__dozer_replication_metadata";
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+const QUEUE_FULL_BACKOFF: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub struct KafkaSinkFactory {
+    connection: KafkaConfig,
+    config: KafkaSinkConfig,
+    runtime: Arc<Runtime>,
+}
+
+impl KafkaSinkFactory {
+    pub fn new(connection: KafkaConfig, config: KafkaSinkConfig, runtime: Arc<Runtime>) -> Self {
+        Self {
+            connection,
+            config,
+            runtime,
+        }
+    }
+}
+
+/// Creates the metadata topic if it doesn't exist.
+async fn create_metadata_topic(broker: &str) -> Result<(), KafkaSinkError> {
+    let admin: AdminClient<_> = ClientConfig::new()
+        .set("bootstrap.servers", broker)
+        .create()?;
+    let topic = NewTopic::new(METADATA_TOPIC, 1, TopicReplication::Fixed(-1))
+        .set("cleanup.policy", "compact");
+    for result in admin.create_topics(&[topic], &AdminOptions::new()).await? {
+        match result {
+            Ok(_) => info!("Created topic {METADATA_TOPIC}"),
+            Err((_, RDKafkaErrorCode::TopicAlreadyExists)) => (),
+            Err((topic, code)) => return Err(KafkaSinkError::CreateTopic { topic, code }),
+        }
+    }
+    Ok(())
+}
+
+fn create_producer(
+    broker: &str,
+    transactional_id: &str,
+) -> Result<ThreadedProducer<DefaultProducerContext>, KafkaSinkError> {
+    let producer: ThreadedProducer<_> = ClientConfig::new()
+        .set("bootstrap.servers", broker)
+        .set("transactional.id", transactional_id)
+        .set("enable.idempotence", "true")
+        .create()?;
+    // Fences off older producers with the same transactional id, aborting their open transaction.
+    producer.init_transactions(TIMEOUT)?;
+    producer.begin_transaction()?;
+    Ok(producer)
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Encoder {
+    Json(JsonEncoder),
+    Avro(AvroEncoder),
+}
+
+impl Encoder {
+    fn encode_key(&self, key: &[Field]) -> Result<Vec<u8>, KafkaSinkError> {
+        match self {
+            Encoder::Json(encoder) => encoder.encode_key(key),
+            Encoder::Avro(encoder) => encoder.encode_key(key),
+        }
+    }
+
+    fn encode_envelope(&self, envelope: &Envelope) -> Result<Vec<u8>, KafkaSinkError> {
+        match self {
+            Encoder::Json(encoder) => encoder.encode_envelope(envelope),
+            Encoder::Avro(encoder) => encoder.encode_envelope(envelope),
+        }
+    }
+}
+
+#[async_trait]
+impl SinkFactory for KafkaSinkFactory {
+    fn type_name(&self) -> String {
+        "kafka".to_string()
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_input_port_name(&self, _port: &PortHandle) -> String {
+        self.config.table_name.clone()
+    }
+
+    fn prepare(&self, input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        debug_assert!(input_schemas.len() == 1);
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        mut input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        let schema = input_schemas.remove(&DEFAULT_PORT_HANDLE).unwrap();
+        let topic = &self.config.topic;
+        let encoder = match &self.connection.schema_registry_url {
+            Some(url) => Encoder::Avro(AvroEncoder::register(url, topic, schema.clone()).await?),
+            None => Encoder::Json(JsonEncoder::new(topic, schema.clone())),
+        };
+
+        create_metadata_topic(&self.connection.broker).await?;
+        let transactional_id = self
+            .config
+            .transactional_id
+            .clone()
+            .unwrap_or_else(|| format!("dozer-{topic}"));
+        let producer = create_producer(&self.connection.broker, &transactional_id)?;
+        // The checkpoint is read here, as the sink's sync getters run inside the async pipeline build.
+        let checkpoint = {
+            let broker = self.connection.broker.clone();
+            let topic = topic.clone();
+            dozer_core::tokio::task::spawn_blocking(move || read_checkpoint(&broker, &topic))
+                .await??
+        };
+
+        Ok(Box::new(KafkaSink {
+            producer,
+            runtime: self.runtime.clone(),
+            broker: self.connection.broker.clone(),
+            schema_registry_url: self.connection.schema_registry_url.clone(),
+            topic: topic.clone(),
+            schema,
+            encoder,
+            latest_op_id: None,
+            source_state: None,
+            checkpoint,
+        }))
+    }
+}
+
+/// The value of a message in the metadata topic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "dozer_types::serde")]
+pub(crate) struct Checkpoint {
+    pub op_id: Option<OpIdentifier>,
+    /// Base64 encoded.
+    pub source_state: Option<String>,
+}
+
+/// Reads the latest committed checkpoint of `topic` from the metadata topic.
+pub(crate) fn read_checkpoint(
+    broker: &str,
+    topic: &str,
+) -> Result<Option<Checkpoint>, KafkaSinkError> {
+    let consumer: BaseConsumer = ClientConfig::new()
+        .set("bootstrap.servers", broker)
+        .set("group.id", format!("dozer-{topic}-checkpoint"))
+        .set("enable.auto.commit", "false")
+        .set("enable.partition.eof", "true")
+        .set("isolation.level", "read_committed")
+        .create()?;
+    let mut partitions = TopicPartitionList::new();
+    partitions.add_partition_offset(METADATA_TOPIC, 0, Offset::Beginning)?;
+    consumer.assign(&partitions)?;
+
+    let deadline = Instant::now() + TIMEOUT;
+    let mut checkpoint = None;
+    loop {
+        match consumer.poll(deadline.saturating_duration_since(Instant::now())) {
+            None => return Err(KafkaSinkError::CheckpointTimeout(topic.to_string())),
+            Some(Err(KafkaError::PartitionEOF(_))) => return Ok(checkpoint),
+            Some(Err(e)) => return Err(e.into()),
+            Some(Ok(message)) => {
+                if message.key() == Some(topic.as_bytes()) {
+                    checkpoint = message.payload().map(serde_json::from_slice).transpose()?;
+                }
+            }
+        }
+    }
+}
+
+pub(crate) struct KafkaSink {
+    producer: ThreadedProducer<DefaultProducerContext>,
+    runtime: Arc<Runtime>,
+    broker: String,
+    schema_registry_url: Option<String>,
+    topic: String,
+    schema: Schema,
+    encoder: Encoder,
+    latest_op_id: Option<OpIdentifier>,
+    source_state: Option<Vec<u8>>,
+    /// The checkpoint the sink resumes from, read when it's built.
+    checkpoint: Option<Checkpoint>,
+}
+
+impl Debug for KafkaSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KafkaSink")
+            .field("broker", &self.broker)
+            .field("topic", &self.topic)
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
+
+impl KafkaSink {
+    fn key(&self, values: &[Field]) -> Option<Vec<Field>> {
+        if self.schema.primary_index.is_empty() {
+            return None;
+        }
+        Some(
+            self.schema
+                .primary_index
+                .iter()
+                .map(|i| values[*i].clone())
+                .collect(),
+        )
+    }
+
+    fn send(
+        &self,
+        topic: &str,
+        key: Option<&[u8]>,
+        payload: Option<&[u8]>,
+    ) -> Result<(), KafkaSinkError> {
+        let mut record = BaseRecord::<[u8], [u8]>::to(topic);
+        if let Some(key) = key {
+            record = record.key(key);
+        }
+        if let Some(payload) = payload {
+            record = record.payload(payload);
+        }
+        loop {
+            match self.producer.send(record) {
+                Ok(()) => return Ok(()),
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), returned)) => {
+                    std::thread::sleep(QUEUE_FULL_BACKOFF);
+                    record = returned;
+                }
+                Err((e, _)) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Sends a change event, followed by a tombstone for deletes so the key can be compacted away.
+    fn send_event(
+        &self,
+        op: DebeziumOp,
+        before: Option<&[Field]>,
+        after: Option<&[Field]>,
+    ) -> Result<(), KafkaSinkError> {
+        let key = self
+            .key(after.or(before).expect("an event has a before or an after"))
+            .map(|key| self.encoder.encode_key(&key))
+            .transpose()?;
+        let payload = self.encoder.encode_envelope(&Envelope {
+            op,
+            before,
+            after,
+            ts_ms: now_ms(),
+        })?;
+        self.send(&self.topic, key.as_deref(), Some(&payload))?;
+        if op == DebeziumOp::Delete && key.is_some() {
+            self.send(&self.topic, key.as_deref(), None)?;
+        }
+        Ok(())
+    }
+
+    /// Sends a truncate event. Like Debezium's, it has no key, so it doesn't delete the keys of the
+    /// topic when it's compacted.
+    fn send_truncate(&self) -> Result<(), KafkaSinkError> {
+        let payload = self.encoder.encode_envelope(&Envelope {
+            op: DebeziumOp::Truncate,
+            before: None,
+            after: None,
+            ts_ms: now_ms(),
+        })?;
+        self.send(&self.topic, None, Some(&payload))
+    }
+
+    /// Sends the checkpoint and commits it with the events of the transaction.
+    ///
+    /// If that fails, the transaction is aborted and a new one begins, unless the error is fatal to
+    /// the producer. The events of the aborted transaction are lost, but so is its checkpoint, so the
+    /// sink resumes from before them.
+    fn commit_transaction(&mut self) -> Result<(), KafkaSinkError> {
+        if let Err(e) = self.try_commit_transaction() {
+            let fatal =
+                matches!(&e, KafkaSinkError::Kafka(KafkaError::Transaction(e)) if e.is_fatal());
+            if !fatal {
+                warn!("Aborting transaction of {}: {e}", self.topic);
+                self.producer.abort_transaction(TIMEOUT)?;
+                self.producer.begin_transaction()?;
+            }
+            return Err(e);
+        }
+        self.producer.begin_transaction()?;
+        Ok(())
+    }
+
+    fn try_commit_transaction(&mut self) -> Result<(), KafkaSinkError> {
+        let checkpoint = Checkpoint {
+            op_id: self.latest_op_id,
+            source_state: self
+                .source_state
+                .as_ref()
+                .map(|state| engine::general_purpose::STANDARD.encode(state)),
+        };
+        debug!(
+            "[Sink] Writing checkpoint {:?} of {}",
+            self.latest_op_id, self.topic
+        );
+        let payload = serde_json::to_vec(&checkpoint)?;
+        self.send(METADATA_TOPIC, Some(self.topic.as_bytes()), Some(&payload))?;
+        loop {
+            match self.producer.commit_transaction(TIMEOUT) {
+                Ok(()) => break,
+                Err(KafkaError::Transaction(e)) if e.is_retriable() => {
+                    warn!("Retrying commit of {}: {e}", self.topic)
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Sink for KafkaSink {
+    fn commit(&mut self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        self.commit_transaction()?;
+        Ok(())
+    }
+
+    fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
+        match &op.op {
+            Operation::Insert { new } => {
+                self.send_event(DebeziumOp::Create, None, Some(&new.values))?
+            }
+            Operation::Delete { old } => {
+                self.send_event(DebeziumOp::Delete, Some(&old.values), None)?
+            }
+            Operation::Update { old, new } => {
+                if self.key(&old.values) == self.key(&new.values) {
+                    self.send_event(DebeziumOp::Update, Some(&old.values), Some(&new.values))?;
+                } else {
+                    // Like Debezium, a key change is a delete of the old key and a create of the new one.
+                    self.send_event(DebeziumOp::Delete, Some(&old.values), None)?;
+                    self.send_event(DebeziumOp::Create, None, Some(&new.values))?;
+                }
+            }
+            Operation::BatchInsert { new } => {
+                for record in new {
+                    self.send_event(DebeziumOp::Create, None, Some(&record.values))?;
+                }
+            }
+        }
+        if let Some(id) = op.id {
+            self.latest_op_id = Some(id);
+        }
+        Ok(())
+    }
+
+    fn on_schema_change(&mut self, _port: PortHandle, schema: &Schema) -> Result<(), BoxedError> {
+        let changes = field_changes(&self.schema.fields, &schema.fields)?;
+        if changes.altered.is_empty() && changes.appended.is_empty() {
+            return Ok(());
+        }
+        // Messages carry their schema, or its id in the registry. Appended fields are nullable,
+        // with a null default, and Avro resolves values written before a field became nullable
+        // or was widened, so the new schema stays compatible for consumers. The registry rejects
+        // widened types that Avro doesn't promote.
+        self.encoder = match &self.schema_registry_url {
+            Some(url) => Encoder::Avro(self.runtime.block_on(AvroEncoder::register(
+                url,
+                &self.topic,
+                schema.clone(),
+            ))?),
+            None => Encoder::Json(JsonEncoder::new(&self.topic, schema.clone())),
+        };
+        self.schema = schema.clone();
+        Ok(())
+    }
+
+    fn on_truncate(&mut self, _port: PortHandle) -> Result<(), BoxedError> {
+        self.send_truncate()?;
+        Ok(())
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        if id.is_some() {
+            self.latest_op_id = id;
+        }
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, source_state: &[u8]) -> Result<(), BoxedError> {
+        self.source_state = Some(source_state.to_vec());
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        let Some(source_state) = self
+            .checkpoint
+            .as_ref()
+            .and_then(|checkpoint| checkpoint.source_state.as_ref())
+        else {
+            return Ok(None);
+        };
+        Ok(Some(
+            engine::general_purpose::STANDARD
+                .decode(source_state)
+                .map_err(KafkaSinkError::from)?,
+        ))
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        Ok(self
+            .checkpoint
+            .as_ref()
+            .and_then(|checkpoint| checkpoint.op_id))
+    }
+}
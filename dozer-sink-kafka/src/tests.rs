@@ -0,0 +1,364 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use apache_avro::types::Value as AvroValue;
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{Sink, SinkFactory};
+use dozer_core::tokio::runtime::Runtime;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_types::models::ingestion_types::KafkaConfig;
+use dozer_types::models::sink::KafkaSinkConfig;
+use dozer_types::node::OpIdentifier;
+use dozer_types::rust_decimal::Decimal;
+use dozer_types::serde_json::{self, json, Value};
+use dozer_types::types::{
+    Field, FieldDefinition, FieldType, Operation, Record, Schema, TableOperation,
+};
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::error::KafkaError;
+use rdkafka::message::Message;
+use rdkafka::mocking::MockCluster;
+use rdkafka::{Offset, TopicPartitionList};
+
+use crate::avro;
+use crate::debezium::{unscaled_bytes, DebeziumOp, Envelope, JsonEncoder};
+use crate::KafkaSinkFactory;
+
+fn field(name: &str, typ: FieldType, nullable: bool) -> FieldDefinition {
+    FieldDefinition {
+        name: name.to_string(),
+        typ,
+        nullable,
+        source: Default::default(),
+        description: None,
+    }
+}
+
+fn get_schema() -> Schema {
+    Schema {
+        fields: vec![
+            field("id", FieldType::UInt, false),
+            field("name", FieldType::String, true),
+            field("price", FieldType::Decimal, true),
+            field("discount", FieldType::Decimal, true),
+        ],
+        primary_index: vec![0],
+    }
+}
+
+fn get_values(id: u64) -> Vec<Field> {
+    vec![
+        Field::UInt(id),
+        Field::String(format!("name_{id}")),
+        Field::Decimal(Decimal::new(-12345, 2)),
+        Field::Null,
+    ]
+}
+
+#[test]
+fn test_unscaled_bytes() {
+    assert_eq!(unscaled_bytes(&Decimal::new(0, 0)), vec![0]);
+    assert_eq!(unscaled_bytes(&Decimal::new(1, 2)), vec![1]);
+    assert_eq!(unscaled_bytes(&Decimal::new(128, 0)), vec![0, 0x80]);
+    assert_eq!(unscaled_bytes(&Decimal::new(-1, 0)), vec![0xff]);
+    assert_eq!(unscaled_bytes(&Decimal::new(-129, 0)), vec![0xff, 0x7f]);
+}
+
+#[test]
+fn test_json_envelope() {
+    let encoder = JsonEncoder::new("users", get_schema());
+    let values = get_values(1);
+
+    let key: Value = serde_json::from_slice(&encoder.encode_key(&values[..1]).unwrap()).unwrap();
+    assert_eq!(key["schema"]["name"], json!("users.Key"));
+    assert_eq!(key["payload"], json!({ "id": 1 }));
+
+    let envelope = Envelope {
+        op: DebeziumOp::Create,
+        before: None,
+        after: Some(&values),
+        ts_ms: 1000,
+    };
+    let message: Value =
+        serde_json::from_slice(&encoder.encode_envelope(&envelope).unwrap()).unwrap();
+    assert_eq!(message["schema"]["name"], json!("users.Envelope"));
+    assert_eq!(
+        message["schema"]["fields"][1]["fields"][2]["name"],
+        json!("io.debezium.data.VariableScaleDecimal")
+    );
+    assert_eq!(
+        message["payload"],
+        json!({
+            "before": null,
+            "after": {
+                "id": 1,
+                "name": "name_1",
+                "price": { "scale": 2, "value": "z8c=" },
+                "discount": null,
+            },
+            "op": "c",
+            "ts_ms": 1000,
+        })
+    );
+}
+
+#[test]
+fn test_json_truncate_envelope() {
+    let encoder = JsonEncoder::new("users", get_schema());
+    let envelope = Envelope {
+        op: DebeziumOp::Truncate,
+        before: None,
+        after: None,
+        ts_ms: 1000,
+    };
+    let message: Value =
+        serde_json::from_slice(&encoder.encode_envelope(&envelope).unwrap()).unwrap();
+    assert_eq!(
+        message["payload"],
+        json!({ "before": null, "after": null, "op": "t", "ts_ms": 1000 })
+    );
+}
+
+#[test]
+fn test_avro_envelope() {
+    let schema = get_schema();
+    let avro_schema =
+        apache_avro::Schema::parse(&avro::envelope_schema("my-topic", &schema)).unwrap();
+    let values = get_values(1);
+    let value = AvroValue::Record(vec![
+        (
+            "before".to_string(),
+            AvroValue::Union(0, Box::new(AvroValue::Null)),
+        ),
+        (
+            "after".to_string(),
+            AvroValue::Union(
+                1,
+                Box::new(AvroValue::Record(vec![
+                    ("id".to_string(), avro::avro_value(&values[0]).unwrap()),
+                    (
+                        "name".to_string(),
+                        AvroValue::Union(1, Box::new(avro::avro_value(&values[1]).unwrap())),
+                    ),
+                    (
+                        "price".to_string(),
+                        AvroValue::Union(1, Box::new(avro::avro_value(&values[2]).unwrap())),
+                    ),
+                    (
+                        "discount".to_string(),
+                        AvroValue::Union(0, Box::new(AvroValue::Null)),
+                    ),
+                ])),
+            ),
+        ),
+        ("op".to_string(), AvroValue::String("c".to_string())),
+        (
+            "ts_ms".to_string(),
+            AvroValue::Union(1, Box::new(AvroValue::Long(1000))),
+        ),
+    ]);
+    let datum = apache_avro::to_avro_datum(&avro_schema, value.clone()).unwrap();
+    let decoded = apache_avro::from_avro_datum(&avro_schema, &mut datum.as_slice(), None).unwrap();
+    assert_eq!(decoded, value);
+
+    let key_schema = avro::key_schema("my-topic", &schema).unwrap();
+    assert_eq!(key_schema["name"], json!("Key"));
+    assert_eq!(key_schema["namespace"], json!("my_topic"));
+    assert!(avro::key_schema(
+        "my-topic",
+        &Schema {
+            primary_index: vec![],
+            ..schema
+        }
+    )
+    .is_none());
+}
+
+#[test]
+fn test_avro_schema_change() {
+    let mut schema = get_schema();
+    schema.field(field("count", FieldType::Int8, false), false);
+    let writer = apache_avro::Schema::parse(&avro::envelope_schema("my-topic", &schema)).unwrap();
+    // `count` is widened, and is dropped from the source, so it becomes nullable too.
+    let mut evolved = schema.clone();
+    evolved.fields[4] = field("count", FieldType::Int, true);
+    let reader = apache_avro::Schema::parse(&avro::envelope_schema("my-topic", &evolved)).unwrap();
+
+    let value = |count| {
+        AvroValue::Record(vec![
+            (
+                "before".to_string(),
+                AvroValue::Union(0, Box::new(AvroValue::Null)),
+            ),
+            (
+                "after".to_string(),
+                AvroValue::Union(
+                    1,
+                    Box::new(AvroValue::Record(vec![
+                        ("id".to_string(), AvroValue::Long(1)),
+                        (
+                            "name".to_string(),
+                            AvroValue::Union(0, Box::new(AvroValue::Null)),
+                        ),
+                        (
+                            "price".to_string(),
+                            AvroValue::Union(0, Box::new(AvroValue::Null)),
+                        ),
+                        (
+                            "discount".to_string(),
+                            AvroValue::Union(0, Box::new(AvroValue::Null)),
+                        ),
+                        ("count".to_string(), count),
+                    ])),
+                ),
+            ),
+            ("op".to_string(), AvroValue::String("c".to_string())),
+            (
+                "ts_ms".to_string(),
+                AvroValue::Union(1, Box::new(AvroValue::Long(1000))),
+            ),
+        ])
+    };
+    let datum = apache_avro::to_avro_datum(&writer, value(AvroValue::Int(1))).unwrap();
+    let decoded =
+        apache_avro::from_avro_datum(&writer, &mut datum.as_slice(), Some(&reader)).unwrap();
+    assert_eq!(
+        decoded,
+        value(AvroValue::Union(1, Box::new(AvroValue::Long(1))))
+    );
+}
+
+fn build_sink(runtime: &Arc<Runtime>, broker: &str) -> Box<dyn Sink> {
+    build_sink_with_schema(runtime, broker, get_schema())
+}
+
+fn build_sink_with_schema(runtime: &Arc<Runtime>, broker: &str, schema: Schema) -> Box<dyn Sink> {
+    let factory = KafkaSinkFactory::new(
+        KafkaConfig {
+            broker: broker.to_string(),
+            schema_registry_url: None,
+        },
+        KafkaSinkConfig {
+            connection: "kafka".to_string(),
+            table_name: "users".to_string(),
+            topic: "users".to_string(),
+            transactional_id: None,
+        },
+        runtime.clone(),
+    );
+    runtime
+        .block_on(factory.build(
+            [(DEFAULT_PORT_HANDLE, schema)].into_iter().collect(),
+            EventHub::new(10),
+        ))
+        .unwrap()
+}
+
+fn insert(id: u64) -> TableOperation {
+    TableOperation {
+        id: Some(OpIdentifier::new(id, 0)),
+        op: Operation::Insert {
+            new: Record::new(get_values(id)),
+        },
+        port: DEFAULT_PORT_HANDLE,
+    }
+}
+
+fn epoch(id: u64) -> Epoch {
+    Epoch::new(id, Default::default(), SystemTime::now())
+}
+
+/// Reads the committed messages of partition 0 of `topic`.
+fn read_committed(broker: &str, topic: &str) -> Vec<Value> {
+    let consumer: BaseConsumer = ClientConfig::new()
+        .set("bootstrap.servers", broker)
+        .set("group.id", "test")
+        .set("enable.partition.eof", "true")
+        .set("isolation.level", "read_committed")
+        .create()
+        .unwrap();
+    let mut partitions = TopicPartitionList::new();
+    partitions
+        .add_partition_offset(topic, 0, Offset::Beginning)
+        .unwrap();
+    consumer.assign(&partitions).unwrap();
+    let mut messages = vec![];
+    loop {
+        match consumer.poll(Duration::from_secs(10)).unwrap() {
+            Err(KafkaError::PartitionEOF(_)) => return messages,
+            message => {
+                let payload = message.unwrap().payload().unwrap().to_vec();
+                messages.push(serde_json::from_slice(&payload).unwrap());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_transactional_produce_and_resume() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let cluster = MockCluster::new(3).unwrap();
+    cluster.create_topic("users", 1, 3).unwrap();
+    let broker = cluster.bootstrap_servers();
+
+    let mut sink = build_sink(&runtime, &broker);
+    assert_eq!(sink.get_latest_op_id().unwrap(), None);
+    sink.process(insert(1)).unwrap();
+    sink.set_source_state(b"state_1").unwrap();
+    sink.commit(&epoch(0)).unwrap();
+    // The second epoch is never committed.
+    sink.process(insert(2)).unwrap();
+    drop(sink);
+
+    let mut sink = build_sink(&runtime, &broker);
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(1, 0))
+    );
+    assert_eq!(sink.get_source_state().unwrap(), Some(b"state_1".to_vec()));
+
+    let messages = read_committed(&broker, "users");
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["payload"]["after"]["id"], json!(1));
+}
+
+#[test]
+fn test_schema_change() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let cluster = MockCluster::new(3).unwrap();
+    cluster.create_topic("users", 1, 3).unwrap();
+    let broker = cluster.bootstrap_servers();
+
+    let mut schema = get_schema();
+    schema.field(field("count", FieldType::Int8, false), false);
+    let mut sink = build_sink_with_schema(&runtime, &broker, schema.clone());
+    let insert = |id, count| TableOperation {
+        id: Some(OpIdentifier::new(id, 0)),
+        op: Operation::Insert {
+            new: Record::new([get_values(id), vec![count]].concat()),
+        },
+        port: DEFAULT_PORT_HANDLE,
+    };
+    sink.process(insert(1, Field::Int8(1))).unwrap();
+    // `count` is widened, and is dropped from the source, so it becomes nullable too.
+    let mut evolved = schema;
+    evolved.fields[4] = field("count", FieldType::Int, true);
+    sink.on_schema_change(DEFAULT_PORT_HANDLE, &evolved)
+        .unwrap();
+    sink.process(insert(2, Field::Null)).unwrap();
+    sink.commit(&epoch(0)).unwrap();
+
+    let messages = read_committed(&broker, "users");
+    assert_eq!(messages.len(), 2);
+    assert_eq!(
+        messages[0]["schema"]["fields"][1]["fields"][4],
+        json!({ "type": "int8", "optional": false, "field": "count" })
+    );
+    assert_eq!(
+        messages[1]["schema"]["fields"][1]["fields"][4],
+        json!({ "type": "int64", "optional": true, "field": "count" })
+    );
+    assert_eq!(messages[1]["payload"]["after"]["count"], json!(null));
+}
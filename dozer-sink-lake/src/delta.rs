@@ -0,0 +1,379 @@
+//! A writer of the Delta Lake transaction log, limited to what an append-only sink needs.
+//!
+//! Every commit adds Parquet files written by the sink, or removes all files of a truncated table,
+//! and carries the sink checkpoint in its `commitInfo`.
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use dozer_types::arrow::compute::{cast_with_options, CastOptions};
+use dozer_types::arrow::datatypes::{DataType, Schema as ArrowSchema, SchemaRef, TimeUnit};
+use dozer_types::arrow::record_batch::RecordBatch;
+use dozer_types::bytes::Bytes;
+use dozer_types::node::OpIdentifier;
+use dozer_types::serde::{Deserialize, Serialize};
+use dozer_types::serde_json::{self, json, Value};
+use object_store::path::Path;
+use object_store::{ObjectStore, PutMode};
+
+use crate::errors::LakeSinkError;
+
+pub const DELTA_LOG_DIR: &str = "_delta_log";
+const CHECKPOINT_KEY: &str = "dozerCheckpoint";
+
+/// Delta decimals are at most 128 bits. Dozer decimals have at most 28 significant digits.
+const DECIMAL_PRECISION: u8 = 38;
+const DECIMAL_SCALE: i8 = 18;
+
+/// The type a column of the given Arrow type is written as in a Delta table.
+pub fn delta_data_type(typ: &DataType) -> DataType {
+    match typ {
+        DataType::UInt64 | DataType::Duration(_) => DataType::Int64,
+        DataType::Decimal256(_, _) => DataType::Decimal128(DECIMAL_PRECISION, DECIMAL_SCALE),
+        DataType::Timestamp(_, _) => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        DataType::Date64 => DataType::Date32,
+        other => other.clone(),
+    }
+}
+
+/// The name of a type returned by [`delta_data_type`] in the table schema.
+pub fn delta_type_name(typ: &DataType) -> Result<String, LakeSinkError> {
+    let name = match typ {
+        DataType::Int64 => "long".to_string(),
+        DataType::Float64 => "double".to_string(),
+        DataType::Boolean => "boolean".to_string(),
+        DataType::Utf8 | DataType::LargeUtf8 => "string".to_string(),
+        DataType::Binary => "binary".to_string(),
+        DataType::Decimal128(precision, scale) => format!("decimal({precision},{scale})"),
+        DataType::Timestamp(_, _) => "timestamp".to_string(),
+        DataType::Date32 => "date".to_string(),
+        other => return Err(LakeSinkError::UnsupportedDeltaType(other.clone())),
+    };
+    Ok(name)
+}
+
+/// Maps the Arrow schema of the records to the schema of the files of a Delta table.
+pub fn delta_arrow_schema(schema: &ArrowSchema) -> ArrowSchema {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            field
+                .as_ref()
+                .clone()
+                .with_data_type(delta_data_type(field.data_type()))
+        })
+        .collect::<Vec<_>>();
+    ArrowSchema::new_with_metadata(fields, schema.metadata().clone())
+}
+
+/// The `schemaString` of the table metadata, for a schema returned by [`delta_arrow_schema`].
+pub fn delta_schema_string(schema: &ArrowSchema) -> Result<String, LakeSinkError> {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            Ok(json!({
+                "name": field.name(),
+                "type": delta_type_name(field.data_type())?,
+                "nullable": field.is_nullable(),
+                "metadata": {},
+            }))
+        })
+        .collect::<Result<Vec<_>, LakeSinkError>>()?;
+    Ok(json!({ "type": "struct", "fields": fields }).to_string())
+}
+
+/// Casts a batch to a schema returned by [`delta_arrow_schema`], failing on values that don't fit.
+pub fn cast_batch(batch: &RecordBatch, schema: SchemaRef) -> Result<RecordBatch, LakeSinkError> {
+    let options = CastOptions {
+        safe: false,
+        ..Default::default()
+    };
+    let columns = batch
+        .columns()
+        .iter()
+        .zip(schema.fields())
+        .map(|(column, field)| cast_with_options(column, field.data_type(), &options))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// The sink state as of a commit. The source state is base64 encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(crate = "dozer_types::serde")]
+pub struct Checkpoint {
+    pub op_id: Option<OpIdentifier>,
+    pub source_state: Option<String>,
+}
+
+/// A Parquet file to add to the table, with its path relative to the table root.
+#[derive(Debug, Clone)]
+pub struct AddFile {
+    pub path: String,
+    pub size: usize,
+}
+
+/// The operation of a commit, recorded in its `commitInfo`.
+struct Operation {
+    name: &'static str,
+    parameters: Value,
+    is_blind_append: bool,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as u64)
+}
+
+#[derive(Debug)]
+pub struct DeltaLog {
+    store: Arc<dyn ObjectStore>,
+    log_dir: Path,
+    schema_string: String,
+    /// Whether the schema changed since the latest commit.
+    schema_changed: bool,
+    /// The latest committed version, `None` if the table doesn't exist yet.
+    version: Option<i64>,
+}
+
+impl DeltaLog {
+    /// Opens the log of the table at `table_path`, which is created by the first commit.
+    pub async fn open(
+        store: Arc<dyn ObjectStore>,
+        table_path: &Path,
+        schema: &ArrowSchema,
+    ) -> Result<Self, LakeSinkError> {
+        let log_dir = table_path.child(DELTA_LOG_DIR);
+        let objects = match store.list_with_delimiter(Some(&log_dir)).await {
+            Ok(list) => list.objects,
+            Err(object_store::Error::NotFound { .. }) => vec![],
+            Err(e) => return Err(e.into()),
+        };
+        let version = objects
+            .iter()
+            .filter_map(|object| object.location.filename()?.strip_suffix(".json"))
+            .filter_map(|version| version.parse::<i64>().ok())
+            .max();
+        Ok(Self {
+            store,
+            log_dir,
+            schema_string: delta_schema_string(schema)?,
+            schema_changed: false,
+            version,
+        })
+    }
+
+    /// Changes the schema of the table with the next commit.
+    pub fn set_schema(&mut self, schema: &ArrowSchema) -> Result<(), LakeSinkError> {
+        self.schema_string = delta_schema_string(schema)?;
+        self.schema_changed = true;
+        Ok(())
+    }
+
+    /// The id of the table, from the metadata of its first commit, if it's still there.
+    async fn table_id(&self) -> Result<Option<String>, LakeSinkError> {
+        let bytes = match self.store.get(&self.commit_path(0)).await {
+            Ok(result) => result.bytes().await?,
+            Err(object_store::Error::NotFound { .. }) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        for line in bytes.split(|byte| *byte == b'\n') {
+            let action: Value = serde_json::from_slice(line)?;
+            if let Some(id) = action.pointer("/metaData/id").and_then(Value::as_str) {
+                return Ok(Some(id.to_string()));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn version(&self) -> Option<i64> {
+        self.version
+    }
+
+    fn commit_path(&self, version: i64) -> Path {
+        self.log_dir.child(format!("{version:020}.json"))
+    }
+
+    /// Reads the checkpoint of the latest commit.
+    pub async fn latest_checkpoint(&self) -> Result<Option<Checkpoint>, LakeSinkError> {
+        let Some(version) = self.version else {
+            return Ok(None);
+        };
+        let bytes = self
+            .store
+            .get(&self.commit_path(version))
+            .await?
+            .bytes()
+            .await?;
+        for line in bytes.split(|byte| *byte == b'\n') {
+            let action: Value = serde_json::from_slice(line)?;
+            if let Some(checkpoint) = action.get("commitInfo").and_then(|c| c.get(CHECKPOINT_KEY)) {
+                return Ok(Some(serde_json::from_value(checkpoint.clone())?));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Commits `files` with `checkpoint` as the next version, creating the table on the first commit.
+    ///
+    /// A changed schema replaces the metadata of the table, keeping its id.
+    pub async fn commit(
+        &mut self,
+        files: &[AddFile],
+        checkpoint: &Checkpoint,
+    ) -> Result<i64, LakeSinkError> {
+        let now = now_ms();
+        let actions = files
+            .iter()
+            .map(|file| {
+                json!({
+                    "add": {
+                        "path": file.path,
+                        "partitionValues": {},
+                        "size": file.size,
+                        "modificationTime": now,
+                        "dataChange": true,
+                    }
+                })
+            })
+            .collect();
+        let operation = Operation {
+            name: "WRITE",
+            parameters: json!({ "mode": "Append" }),
+            is_blind_append: true,
+        };
+        self.commit_actions(actions, operation, checkpoint).await
+    }
+
+    /// Commits the removal of all files of the table with `checkpoint` as the next version, if the
+    /// table exists.
+    pub async fn truncate(
+        &mut self,
+        checkpoint: &Checkpoint,
+    ) -> Result<Option<i64>, LakeSinkError> {
+        if self.version.is_none() {
+            return Ok(None);
+        }
+        let now = now_ms();
+        let actions = self
+            .active_files()
+            .await?
+            .into_iter()
+            .map(|path| {
+                json!({
+                    "remove": {
+                        "path": path,
+                        "deletionTimestamp": now,
+                        "dataChange": true,
+                    }
+                })
+            })
+            .collect();
+        let operation = Operation {
+            name: "TRUNCATE",
+            parameters: json!({}),
+            is_blind_append: false,
+        };
+        self.commit_actions(actions, operation, checkpoint)
+            .await
+            .map(Some)
+    }
+
+    /// The files of the table as of the latest version, by replaying the log from its first commit.
+    async fn active_files(&self) -> Result<BTreeSet<String>, LakeSinkError> {
+        let mut files = BTreeSet::new();
+        let Some(latest) = self.version else {
+            return Ok(files);
+        };
+        for version in 0..=latest {
+            let bytes = self
+                .store
+                .get(&self.commit_path(version))
+                .await?
+                .bytes()
+                .await?;
+            for line in bytes.split(|byte| *byte == b'\n') {
+                let action: Value = serde_json::from_slice(line)?;
+                if let Some(path) = action.pointer("/add/path").and_then(Value::as_str) {
+                    files.insert(path.to_string());
+                }
+                if let Some(path) = action.pointer("/remove/path").and_then(Value::as_str) {
+                    files.remove(path);
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Commits `file_actions` as the next version of the table.
+    async fn commit_actions(
+        &mut self,
+        file_actions: Vec<Value>,
+        operation: Operation,
+        checkpoint: &Checkpoint,
+    ) -> Result<i64, LakeSinkError> {
+        let version = self.version.map_or(0, |version| version + 1);
+        let now = now_ms();
+        let mut actions = vec![];
+        if version == 0 {
+            actions.push(json!({
+                "protocol": { "minReaderVersion": 1, "minWriterVersion": 2 }
+            }));
+        }
+        if version == 0 || self.schema_changed {
+            let id = match version {
+                0 => None,
+                _ => self.table_id().await?,
+            };
+            actions.push(json!({
+                "metaData": {
+                    "id": id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+                    "format": { "provider": "parquet", "options": {} },
+                    "schemaString": self.schema_string,
+                    "partitionColumns": [],
+                    "configuration": {},
+                    "createdTime": now,
+                }
+            }));
+        }
+        actions.extend(file_actions);
+        actions.push(json!({
+            "commitInfo": {
+                "timestamp": now,
+                "operation": operation.name,
+                "operationParameters": operation.parameters,
+                "isBlindAppend": operation.is_blind_append,
+                "engineInfo": "dozer",
+                CHECKPOINT_KEY: checkpoint,
+            }
+        }));
+        let body = actions
+            .iter()
+            .map(Value::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let path = self.commit_path(version);
+        match self
+            .store
+            .put_opts(&path, Bytes::from(body.clone()), PutMode::Create.into())
+            .await
+        {
+            Ok(_) => (),
+            Err(object_store::Error::AlreadyExists { .. }) => {
+                return Err(LakeSinkError::ConcurrentCommit(version))
+            }
+            // S3 can't create an object atomically without an external lock, so there Dozer must be the table's only writer.
+            Err(object_store::Error::NotImplemented) => {
+                self.store.put(&path, Bytes::from(body)).await?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+        self.version = Some(version);
+        self.schema_changed = false;
+        Ok(version)
+    }
+}
@@ -0,0 +1,43 @@
+use dozer_types::{
+    arrow::{datatypes::DataType, error::ArrowError},
+    serde_json,
+    thiserror::{self, Error},
+};
+use parquet::errors::ParquetError;
+
+#[derive(Error, Debug)]
+pub enum LakeSinkError {
+    #[error("Object store error: {0}")]
+    ObjectStore(#[from] object_store::Error),
+
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] ArrowError),
+
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] ParquetError),
+
+    #[error("Delta log JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Failed to create directory {0}: {1}")]
+    CreateDirectory(String, #[source] std::io::Error),
+
+    #[error("The lake sink only appends records, {0} operations are not supported")]
+    UnsupportedOperation(&'static str),
+
+    #[error("Arrow type {0} has no Delta Lake equivalent")]
+    UnsupportedDeltaType(DataType),
+
+    #[error("Delta Lake column {column} can't change from {from} to {to}, the table doesn't support type widening")]
+    ColumnTypeChange {
+        column: String,
+        from: String,
+        to: String,
+    },
+
+    #[error("Delta log version {0} was committed by another writer")]
+    ConcurrentCommit(i64),
+
+    #[error("Invalid checkpoint source state: {0}")]
+    InvalidSourceState(#[from] base64::DecodeError),
+}
@@ -0,0 +1,7 @@
+pub mod delta;
+pub mod errors;
+mod sink;
+pub use sink::LakeSinkFactory;
+pub mod storage;
+#[cfg(test)]
+mod tests;
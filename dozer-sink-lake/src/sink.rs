@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use base64::{engine, Engine};
+use dozer_core::dag_schemas::field_changes;
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{PortHandle, Sink, SinkFactory};
+use dozer_core::tokio::runtime::Runtime;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_types::arrow::datatypes::SchemaRef;
+use dozer_types::arrow_types::to_arrow::{map_record_to_arrow, map_to_arrow_schema};
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::log::debug;
+use dozer_types::models::sink::{
+    default_lake_max_file_age_secs, default_lake_max_file_size_bytes, LakeSinkConfig,
+};
+use dozer_types::node::OpIdentifier;
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Operation, Record, Schema, TableOperation};
+use object_store::path::Path;
+use object_store::ObjectStore;
+use parquet::arrow::ArrowWriter;
+
+use crate::delta::{
+    cast_batch, delta_arrow_schema, delta_type_name, AddFile, Checkpoint, DeltaLog,
+};
+use crate::errors::LakeSinkError;
+use crate::storage::Storage;
+
+/// The name of a file written by the sink is the prefix, a UUID, then the suffix.
+const FILE_PREFIX: &str = "part-";
+const FILE_SUFFIX: &str = ".parquet";
+
+#[derive(Debug)]
+pub struct LakeSinkFactory {
+    storage: Storage,
+    config: LakeSinkConfig,
+    runtime: Arc<Runtime>,
+}
+
+impl LakeSinkFactory {
+    pub fn new(storage: Storage, config: LakeSinkConfig, runtime: Arc<Runtime>) -> Self {
+        Self {
+            storage,
+            config,
+            runtime,
+        }
+    }
+}
+
+#[async_trait]
+impl SinkFactory for LakeSinkFactory {
+    fn type_name(&self) -> String {
+        "lake".to_string()
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_input_port_name(&self, _port: &PortHandle) -> String {
+        self.config.table_name.clone()
+    }
+
+    fn prepare(&self, input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        debug_assert!(input_schemas.len() == 1);
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        mut input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        let schema = input_schemas.remove(&DEFAULT_PORT_HANDLE).unwrap();
+        let arrow_schema = map_to_arrow_schema(&schema).map_err(LakeSinkError::from)?;
+        let store = self.storage.object_store()?;
+        let table_path = Path::from(self.config.path.as_str());
+
+        let (file_schema, delta, checkpoint) = if self.config.delta {
+            let file_schema = delta_arrow_schema(&arrow_schema);
+            let delta = DeltaLog::open(store.clone(), &table_path, &file_schema).await?;
+            // Resolved here, as the sink's sync getters run inside the async pipeline build.
+            let checkpoint = delta.latest_checkpoint().await?;
+            (Arc::new(file_schema), Some(delta), checkpoint)
+        } else {
+            (Arc::new(arrow_schema), None, None)
+        };
+
+        Ok(Box::new(LakeSink {
+            runtime: self.runtime.clone(),
+            store,
+            table_path,
+            schema,
+            file_schema,
+            delta,
+            max_file_size: self
+                .config
+                .max_file_size_bytes
+                .unwrap_or_else(default_lake_max_file_size_bytes),
+            max_file_age: Duration::from_secs(
+                self.config
+                    .max_file_age_secs
+                    .unwrap_or_else(default_lake_max_file_age_secs),
+            ),
+            file: None,
+            latest_op_id: None,
+            source_state: None,
+            checkpoint,
+        }))
+    }
+}
+
+/// The Parquet file being written, kept in memory until it's rolled.
+struct OpenFile {
+    writer: ArrowWriter<Vec<u8>>,
+    opened_at: Instant,
+}
+
+impl OpenFile {
+    fn size(&self) -> u64 {
+        (self.writer.bytes_written() + self.writer.in_progress_size()) as u64
+    }
+}
+
+pub(crate) struct LakeSink {
+    runtime: Arc<Runtime>,
+    store: Arc<dyn ObjectStore>,
+    table_path: Path,
+    schema: Schema,
+    file_schema: SchemaRef,
+    delta: Option<DeltaLog>,
+    max_file_size: u64,
+    max_file_age: Duration,
+    file: Option<OpenFile>,
+    latest_op_id: Option<OpIdentifier>,
+    source_state: Option<Vec<u8>>,
+    /// The checkpoint of the latest Delta commit.
+    checkpoint: Option<Checkpoint>,
+}
+
+impl Debug for LakeSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LakeSink")
+            .field("table_path", &self.table_path)
+            .field("schema", &self.schema)
+            .field("delta", &self.delta.is_some())
+            .finish()
+    }
+}
+
+impl LakeSink {
+    fn write(&mut self, record: Record) -> Result<(), LakeSinkError> {
+        let batch = map_record_to_arrow(record, &self.schema)?;
+        let batch = if self.delta.is_some() {
+            cast_batch(&batch, self.file_schema.clone())?
+        } else {
+            batch
+        };
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert(OpenFile {
+                writer: ArrowWriter::try_new(vec![], self.file_schema.clone(), None)?,
+                opened_at: Instant::now(),
+            }),
+        };
+        file.writer.write(&batch)?;
+        Ok(())
+    }
+
+    fn should_roll(&self) -> bool {
+        self.file.as_ref().is_some_and(|file| {
+            file.size() >= self.max_file_size || file.opened_at.elapsed() >= self.max_file_age
+        })
+    }
+
+    /// Uploads the open file, then commits it to the Delta log if any.
+    async fn roll(&mut self) -> Result<(), LakeSinkError> {
+        let Some(file) = self.file.take() else {
+            return Ok(());
+        };
+        let bytes = file.writer.into_inner()?;
+        let name = format!("{FILE_PREFIX}{}{FILE_SUFFIX}", uuid::Uuid::new_v4());
+        let size = bytes.len();
+        debug!(
+            "[Sink] Writing {name} ({size} bytes) to {}",
+            self.table_path
+        );
+        self.store
+            .put(&self.table_path.child(name.as_str()), bytes.into())
+            .await?;
+
+        let checkpoint = self.current_checkpoint();
+        if let Some(delta) = &mut self.delta {
+            let version = delta
+                .commit(&[AddFile { path: name, size }], &checkpoint)
+                .await?;
+            debug!("[Sink] Committed version {version} of {}", self.table_path);
+            self.checkpoint = Some(checkpoint);
+        }
+        Ok(())
+    }
+
+    /// Removes all files of the table. The records of the open file would be removed anyway.
+    ///
+    /// Without a Delta log, the Parquet files the sink wrote to the table path are deleted.
+    async fn truncate(&mut self) -> Result<(), LakeSinkError> {
+        self.file = None;
+        let checkpoint = self.current_checkpoint();
+        let Some(delta) = &mut self.delta else {
+            let objects = match self.store.list_with_delimiter(Some(&self.table_path)).await {
+                Ok(list) => list.objects,
+                Err(object_store::Error::NotFound { .. }) => vec![],
+                Err(e) => return Err(e.into()),
+            };
+            for object in objects {
+                if object.location.filename().is_some_and(|name| {
+                    name.starts_with(FILE_PREFIX) && name.ends_with(FILE_SUFFIX)
+                }) {
+                    self.store.delete(&object.location).await?;
+                }
+            }
+            return Ok(());
+        };
+        if let Some(version) = delta.truncate(&checkpoint).await? {
+            debug!("[Sink] Truncated {} in version {version}", self.table_path);
+            self.checkpoint = Some(checkpoint);
+        }
+        Ok(())
+    }
+
+    fn current_checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            op_id: self.latest_op_id,
+            source_state: self
+                .source_state
+                .as_ref()
+                .map(|state| engine::general_purpose::STANDARD.encode(state)),
+        }
+    }
+}
+
+impl Sink for LakeSink {
+    fn commit(&mut self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        if self.should_roll() {
+            let runtime = self.runtime.clone();
+            runtime.block_on(self.roll())?;
+        }
+        Ok(())
+    }
+
+    fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
+        match op.op {
+            Operation::Insert { new } => self.write(new)?,
+            Operation::BatchInsert { new } => {
+                for record in new {
+                    self.write(record)?;
+                }
+            }
+            Operation::Update { .. } => {
+                return Err(LakeSinkError::UnsupportedOperation("update").into())
+            }
+            Operation::Delete { .. } => {
+                return Err(LakeSinkError::UnsupportedOperation("delete").into())
+            }
+        }
+        if let Some(id) = op.id {
+            self.latest_op_id = Some(id);
+        }
+        Ok(())
+    }
+
+    fn on_schema_change(&mut self, _port: PortHandle, schema: &Schema) -> Result<(), BoxedError> {
+        let changes = field_changes(&self.schema.fields, &schema.fields)?;
+        if changes.altered.is_empty() && changes.appended.is_empty() {
+            return Ok(());
+        }
+        let arrow_schema = map_to_arrow_schema(schema).map_err(LakeSinkError::from)?;
+        let file_schema = match &self.delta {
+            Some(_) => delta_arrow_schema(&arrow_schema),
+            None => arrow_schema,
+        };
+        // Columns of a Delta table can become nullable, but keep the type of the files written so far.
+        if self.delta.is_some() {
+            for &index in &changes.altered {
+                let from = delta_type_name(self.file_schema.field(index).data_type())?;
+                let to = delta_type_name(file_schema.field(index).data_type())?;
+                if from != to {
+                    return Err(LakeSinkError::ColumnTypeChange {
+                        column: schema.fields[index].name.clone(),
+                        from,
+                        to,
+                    }
+                    .into());
+                }
+            }
+        }
+
+        // A Parquet file has a single schema, so the records written so far go to their own file.
+        let runtime = self.runtime.clone();
+        runtime.block_on(self.roll())?;
+        if let Some(delta) = &mut self.delta {
+            delta.set_schema(&file_schema)?;
+        }
+        self.file_schema = Arc::new(file_schema);
+        self.schema = schema.clone();
+        Ok(())
+    }
+
+    fn on_truncate(&mut self, _port: PortHandle) -> Result<(), BoxedError> {
+        let runtime = self.runtime.clone();
+        runtime.block_on(self.truncate())?;
+        Ok(())
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        if id.is_some() {
+            self.latest_op_id = id;
+        }
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, source_state: &[u8]) -> Result<(), BoxedError> {
+        self.source_state = Some(source_state.to_vec());
+        Ok(())
+    }
+
+    /// Without a Delta log, written files carry no checkpoint and the sink can't resume.
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        let Some(source_state) = self
+            .checkpoint
+            .as_ref()
+            .and_then(|checkpoint| checkpoint.source_state.as_ref())
+        else {
+            return Ok(None);
+        };
+        Ok(Some(
+            engine::general_purpose::STANDARD
+                .decode(source_state)
+                .map_err(LakeSinkError::from)?,
+        ))
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        Ok(self
+            .checkpoint
+            .as_ref()
+            .and_then(|checkpoint| checkpoint.op_id))
+    }
+}
@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use dozer_types::models::ingestion_types::{LocalDetails, S3Details};
+use object_store::aws::AmazonS3Builder;
+use object_store::local::LocalFileSystem;
+use object_store::ObjectStore;
+
+use crate::errors::LakeSinkError;
+
+/// Where the lake sink writes, taken from a `LocalStorage` or `S3Storage` connection.
+#[derive(Debug, Clone)]
+pub enum Storage {
+    Local(LocalDetails),
+    S3(S3Details),
+}
+
+impl Storage {
+    /// The object store, with paths relative to the local path or the bucket root.
+    pub fn object_store(&self) -> Result<Arc<dyn ObjectStore>, LakeSinkError> {
+        match self {
+            Storage::Local(details) => {
+                std::fs::create_dir_all(&details.path)
+                    .map_err(|e| LakeSinkError::CreateDirectory(details.path.clone(), e))?;
+                Ok(Arc::new(LocalFileSystem::new_with_prefix(&details.path)?))
+            }
+            Storage::S3(details) => Ok(Arc::new(
+                AmazonS3Builder::new()
+                    .with_bucket_name(&details.bucket_name)
+                    .with_region(&details.region)
+                    .with_access_key_id(&details.access_key_id)
+                    .with_secret_access_key(&details.secret_access_key)
+                    .build()?,
+            )),
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{Sink, SinkFactory};
+use dozer_core::tokio::runtime::Runtime;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_types::arrow::datatypes::{DataType, TimeUnit};
+use dozer_types::arrow_types::to_arrow::map_to_arrow_schema;
+use dozer_types::models::ingestion_types::LocalDetails;
+use dozer_types::models::sink::LakeSinkConfig;
+use dozer_types::node::OpIdentifier;
+use dozer_types::rust_decimal::Decimal;
+use dozer_types::serde_json::{self, json, Value};
+use dozer_types::types::{
+    Field, FieldDefinition, FieldType, Operation, Record, Schema, TableOperation,
+};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use tempfile::TempDir;
+
+use crate::delta::{delta_arrow_schema, delta_schema_string, DELTA_LOG_DIR};
+use crate::storage::Storage;
+use crate::LakeSinkFactory;
+
+fn field(name: &str, typ: FieldType) -> FieldDefinition {
+    FieldDefinition {
+        name: name.to_string(),
+        typ,
+        nullable: true,
+        source: Default::default(),
+        description: None,
+    }
+}
+
+fn get_schema() -> Schema {
+    Schema {
+        fields: vec![
+            field("id", FieldType::UInt),
+            field("price", FieldType::Decimal),
+            field("created_at", FieldType::Timestamp),
+        ],
+        primary_index: vec![0],
+    }
+}
+
+#[test]
+fn test_delta_schema() {
+    let schema = delta_arrow_schema(&map_to_arrow_schema(&get_schema()).unwrap());
+    assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+    assert_eq!(schema.field(1).data_type(), &DataType::Decimal128(38, 18));
+    assert_eq!(
+        schema.field(2).data_type(),
+        &DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+    );
+
+    let schema_string: Value =
+        serde_json::from_str(&delta_schema_string(&schema).unwrap()).unwrap();
+    assert_eq!(
+        schema_string,
+        json!({
+            "type": "struct",
+            "fields": [
+                { "name": "id", "type": "long", "nullable": true, "metadata": {} },
+                { "name": "price", "type": "decimal(38,18)", "nullable": true, "metadata": {} },
+                { "name": "created_at", "type": "timestamp", "nullable": true, "metadata": {} },
+            ],
+        })
+    );
+}
+
+fn build_sink(runtime: &Arc<Runtime>, dir: &TempDir, delta: bool) -> Box<dyn Sink> {
+    build_sink_with_schema(runtime, dir, delta, get_schema())
+}
+
+fn build_sink_with_schema(
+    runtime: &Arc<Runtime>,
+    dir: &TempDir,
+    delta: bool,
+    schema: Schema,
+) -> Box<dyn Sink> {
+    let factory = LakeSinkFactory::new(
+        Storage::Local(LocalDetails {
+            path: dir.path().to_str().unwrap().to_string(),
+        }),
+        LakeSinkConfig {
+            connection: "local".to_string(),
+            table_name: "trips".to_string(),
+            path: "trips".to_string(),
+            // Every epoch rolls a file.
+            max_file_size_bytes: Some(0),
+            max_file_age_secs: None,
+            delta,
+        },
+        runtime.clone(),
+    );
+    runtime
+        .block_on(factory.build(
+            [(DEFAULT_PORT_HANDLE, schema)].into_iter().collect(),
+            EventHub::new(10),
+        ))
+        .unwrap()
+}
+
+fn insert(id: u64) -> TableOperation {
+    TableOperation {
+        id: Some(OpIdentifier::new(id, 0)),
+        op: Operation::Insert {
+            new: Record::new(vec![
+                Field::UInt(id),
+                Field::Decimal(Decimal::new(1234, 2)),
+                Field::Null,
+            ]),
+        },
+        port: DEFAULT_PORT_HANDLE,
+    }
+}
+
+fn epoch(id: u64) -> Epoch {
+    Epoch::new(id, Default::default(), SystemTime::now())
+}
+
+fn count_rows(dir: &TempDir) -> usize {
+    std::fs::read_dir(dir.path().join("trips"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "parquet")
+        })
+        .map(|path| {
+            ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+                .unwrap()
+                .build()
+                .unwrap()
+                .map(|batch| batch.unwrap().num_rows())
+                .sum::<usize>()
+        })
+        .sum()
+}
+
+#[test]
+fn test_roll_files_at_epochs() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let dir = TempDir::new().unwrap();
+    let mut sink = build_sink(&runtime, &dir, false);
+    sink.process(insert(1)).unwrap();
+    sink.process(insert(2)).unwrap();
+    sink.commit(&epoch(0)).unwrap();
+    // Nothing to roll.
+    sink.commit(&epoch(1)).unwrap();
+    sink.process(insert(3)).unwrap();
+    sink.commit(&epoch(2)).unwrap();
+
+    assert_eq!(count_rows(&dir), 3);
+    assert!(!dir.path().join("trips").join(DELTA_LOG_DIR).exists());
+    assert_eq!(sink.get_latest_op_id().unwrap(), None);
+    assert!(sink
+        .process(TableOperation {
+            id: None,
+            op: Operation::Delete {
+                old: Record::new(vec![Field::UInt(1), Field::Null, Field::Null]),
+            },
+            port: DEFAULT_PORT_HANDLE,
+        })
+        .is_err());
+}
+
+#[test]
+fn test_delta_commits_and_resume() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let dir = TempDir::new().unwrap();
+    let mut sink = build_sink(&runtime, &dir, true);
+    assert_eq!(sink.get_latest_op_id().unwrap(), None);
+    sink.process(insert(1)).unwrap();
+    sink.set_source_state(b"state_1").unwrap();
+    sink.commit(&epoch(0)).unwrap();
+    sink.process(insert(2)).unwrap();
+    sink.set_source_state(b"state_2").unwrap();
+    sink.commit(&epoch(1)).unwrap();
+    // Not rolled before the sink stops.
+    sink.process(insert(3)).unwrap();
+    drop(sink);
+
+    let log_dir = dir.path().join("trips").join(DELTA_LOG_DIR);
+    let first_commit = std::fs::read_to_string(log_dir.join("00000000000000000000.json")).unwrap();
+    let actions = first_commit
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert!(actions[0].get("protocol").is_some());
+    assert!(actions[1].get("metaData").is_some());
+    assert!(actions[2].get("add").is_some());
+    assert!(actions[3].get("commitInfo").is_some());
+    assert!(log_dir.join("00000000000000000001.json").exists());
+    assert_eq!(count_rows(&dir), 2);
+
+    let mut sink = build_sink(&runtime, &dir, true);
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(2, 0))
+    );
+    assert_eq!(sink.get_source_state().unwrap(), Some(b"state_2".to_vec()));
+    sink.process(insert(3)).unwrap();
+    sink.commit(&epoch(2)).unwrap();
+    assert!(log_dir.join("00000000000000000002.json").exists());
+    assert_eq!(count_rows(&dir), 3);
+}
+
+fn read_actions(log_dir: &std::path::Path, version: u64) -> Vec<Value> {
+    std::fs::read_to_string(log_dir.join(format!("{version:020}.json")))
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_delta_appended_field() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let dir = TempDir::new().unwrap();
+    let mut sink = build_sink(&runtime, &dir, true);
+    sink.process(insert(1)).unwrap();
+
+    let mut schema = get_schema();
+    schema.fields.push(field("note", FieldType::String));
+    sink.on_schema_change(DEFAULT_PORT_HANDLE, &schema).unwrap();
+    let mut op = insert(2);
+    let Operation::Insert { new } = &mut op.op else {
+        unreachable!()
+    };
+    new.values.push(Field::String("late".to_string()));
+    sink.process(op).unwrap();
+    sink.commit(&epoch(0)).unwrap();
+
+    // The records before the change were written with the previous schema.
+    let log_dir = dir.path().join("trips").join(DELTA_LOG_DIR);
+    let first_commit = read_actions(&log_dir, 0);
+    let second_commit = read_actions(&log_dir, 1);
+    let metadata = second_commit[0].get("metaData").unwrap();
+    assert_eq!(metadata["id"], first_commit[1]["metaData"]["id"]);
+    let schema_string: Value =
+        serde_json::from_str(metadata["schemaString"].as_str().unwrap()).unwrap();
+    assert_eq!(schema_string["fields"][3]["name"], json!("note"));
+    assert!(second_commit[1].get("add").is_some());
+    assert_eq!(count_rows(&dir), 2);
+}
+
+#[test]
+fn test_delta_altered_fields() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let dir = TempDir::new().unwrap();
+    let mut schema = get_schema();
+    schema.fields[1].nullable = false;
+    let mut sink = build_sink_with_schema(&runtime, &dir, true, schema.clone());
+    sink.process(insert(1)).unwrap();
+
+    // `price` is dropped from the source, so it becomes nullable.
+    schema.fields[1].nullable = true;
+    sink.on_schema_change(DEFAULT_PORT_HANDLE, &schema).unwrap();
+    let mut op = insert(2);
+    let Operation::Insert { new } = &mut op.op else {
+        unreachable!()
+    };
+    new.values[1] = Field::Null;
+    sink.process(op).unwrap();
+    sink.commit(&epoch(0)).unwrap();
+
+    let log_dir = dir.path().join("trips").join(DELTA_LOG_DIR);
+    let metadata = read_actions(&log_dir, 1)[0]
+        .get("metaData")
+        .cloned()
+        .unwrap();
+    let schema_string: Value =
+        serde_json::from_str(metadata["schemaString"].as_str().unwrap()).unwrap();
+    assert_eq!(schema_string["fields"][1]["nullable"], json!(true));
+    assert_eq!(count_rows(&dir), 2);
+
+    // The files written so far keep the column type.
+    schema.fields[0].typ = FieldType::Decimal;
+    assert!(sink.on_schema_change(DEFAULT_PORT_HANDLE, &schema).is_err());
+}
+
+#[test]
+fn test_widened_field_without_delta() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let dir = TempDir::new().unwrap();
+    let mut sink = build_sink(&runtime, &dir, false);
+    sink.process(insert(1)).unwrap();
+
+    // Every file has its own schema, so the new files have the wider type.
+    let mut schema = get_schema();
+    schema.fields[0].typ = FieldType::Decimal;
+    sink.on_schema_change(DEFAULT_PORT_HANDLE, &schema).unwrap();
+    let mut op = insert(2);
+    let Operation::Insert { new } = &mut op.op else {
+        unreachable!()
+    };
+    new.values[0] = Field::Decimal(Decimal::new(2, 0));
+    sink.process(op).unwrap();
+    sink.commit(&epoch(0)).unwrap();
+    assert_eq!(count_rows(&dir), 2);
+}
+
+#[test]
+fn test_delta_truncate() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let dir = TempDir::new().unwrap();
+    let mut sink = build_sink(&runtime, &dir, true);
+    sink.process(insert(1)).unwrap();
+    sink.commit(&epoch(0)).unwrap();
+    sink.process(insert(2)).unwrap();
+    sink.commit(&epoch(1)).unwrap();
+    // Not rolled before the truncate, so it's never written.
+    sink.process(insert(3)).unwrap();
+    sink.on_truncate(DEFAULT_PORT_HANDLE).unwrap();
+
+    let log_dir = dir.path().join("trips").join(DELTA_LOG_DIR);
+    let paths = |actions: &[Value], kind: &str| {
+        actions
+            .iter()
+            .filter_map(|action| action[kind]["path"].as_str().map(str::to_string))
+            .collect::<BTreeSet<_>>()
+    };
+    let mut added = paths(&read_actions(&log_dir, 0), "add");
+    added.extend(paths(&read_actions(&log_dir, 1), "add"));
+    assert_eq!(added.len(), 2);
+    let truncate = read_actions(&log_dir, 2);
+    assert_eq!(paths(&truncate, "remove"), added);
+    assert_eq!(
+        truncate.last().unwrap()["commitInfo"]["operation"],
+        json!("TRUNCATE")
+    );
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(3, 0))
+    );
+}
+
+#[test]
+fn test_truncate_without_delta() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let dir = TempDir::new().unwrap();
+    let mut sink = build_sink(&runtime, &dir, false);
+    sink.process(insert(1)).unwrap();
+    sink.commit(&epoch(0)).unwrap();
+    assert_eq!(count_rows(&dir), 1);
+
+    sink.on_truncate(DEFAULT_PORT_HANDLE).unwrap();
+    assert_eq!(count_rows(&dir), 0);
+}
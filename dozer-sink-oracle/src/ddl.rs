@@ -0,0 +1,158 @@
+use std::fmt::Display;
+
+use dozer_types::types::{FieldDefinition, Schema};
+
+use crate::types::column_type;
+
+pub const METADATA_TABLE: &str = "__DOZER_REPLICATION_METADATA";
+pub const META_TABLE_COL: &str = "TABLE_NAME";
+pub const META_TXID_COL: &str = "TXID";
+pub const META_SEQ_IN_TX_COL: &str = "SEQ_IN_TX";
+
+/// A table qualified by its owner. Both parts are quoted, so they are case sensitive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableName {
+    pub owner: String,
+    pub name: String,
+}
+
+impl TableName {
+    pub fn new(owner: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            owner: owner.into(),
+            name: name.into(),
+        }
+    }
+}
+
+impl Display for TableName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", quote(&self.owner), quote(&self.name))
+    }
+}
+
+pub fn quote(identifier: &str) -> String {
+    format!("\"{identifier}\"")
+}
+
+fn quoted_columns<'a>(schema: &'a Schema, index: &'a [usize]) -> impl Iterator<Item = String> + 'a {
+    index.iter().map(|i| quote(&schema.fields[*i].name))
+}
+
+fn column_definition(field: &FieldDefinition) -> String {
+    format!(
+        "{} {}{}",
+        quote(&field.name),
+        column_type(field.typ),
+        if field.nullable { "" } else { " NOT NULL" }
+    )
+}
+
+pub fn create_table(table: &TableName, schema: &Schema, key_index: &[usize]) -> String {
+    let mut columns = schema
+        .fields
+        .iter()
+        .map(column_definition)
+        .collect::<Vec<_>>();
+    columns.push(format!(
+        "UNIQUE ({})",
+        quoted_columns(schema, key_index)
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+    format!("CREATE TABLE {table} ({})", columns.join(", "))
+}
+
+pub fn add_column(table: &TableName, field: &FieldDefinition) -> String {
+    format!("ALTER TABLE {table} ADD ({})", column_definition(field))
+}
+
+/// Changes the column of `field` to its column type, and makes it nullable if `drop_not_null`.
+pub fn modify_column(table: &TableName, field: &FieldDefinition, drop_not_null: bool) -> String {
+    format!(
+        "ALTER TABLE {table} MODIFY ({} {}{})",
+        quote(&field.name),
+        column_type(field.typ),
+        if drop_not_null { " NULL" } else { "" }
+    )
+}
+
+/// Upserts one record, bound as `:1..:n` in schema order.
+pub fn merge(table: &TableName, schema: &Schema, key_index: &[usize]) -> String {
+    let source = schema
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| format!(":{} {}", i + 1, quote(&field.name)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let on = quoted_columns(schema, key_index)
+        .map(|column| format!("D.{column} = S.{column}"))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let update = (0..schema.fields.len())
+        .filter(|i| !key_index.contains(i))
+        .map(|i| {
+            let column = quote(&schema.fields[i].name);
+            format!("D.{column} = S.{column}")
+        })
+        .collect::<Vec<_>>();
+    let all_columns = (0..schema.fields.len()).collect::<Vec<_>>();
+    let insert_columns = quoted_columns(schema, &all_columns).collect::<Vec<_>>();
+    let insert_values = insert_columns
+        .iter()
+        .map(|column| format!("S.{column}"))
+        .collect::<Vec<_>>();
+
+    let mut sql = format!("MERGE INTO {table} D USING (SELECT {source} FROM DUAL) S ON ({on})");
+    // Oracle rejects an empty SET list, and there is nothing to update if all columns are in the key.
+    if !update.is_empty() {
+        sql += &format!(" WHEN MATCHED THEN UPDATE SET {}", update.join(", "));
+    }
+    sql += &format!(
+        " WHEN NOT MATCHED THEN INSERT ({}) VALUES ({})",
+        insert_columns.join(", "),
+        insert_values.join(", ")
+    );
+    sql
+}
+
+/// Deletes one record, with the key bound as `:1..:n` in key order.
+pub fn delete(table: &TableName, schema: &Schema, key_index: &[usize]) -> String {
+    let condition = quoted_columns(schema, key_index)
+        .enumerate()
+        .map(|(i, column)| format!("{column} = :{}", i + 1))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    format!("DELETE FROM {table} WHERE {condition}")
+}
+
+pub fn truncate(table: &TableName) -> String {
+    format!("TRUNCATE TABLE {table}")
+}
+
+pub fn create_metadata_table(owner: &str) -> String {
+    format!(
+        "CREATE TABLE {} ({META_TABLE_COL} VARCHAR2(261) PRIMARY KEY, {META_TXID_COL} NUMBER(20) NOT NULL, {META_SEQ_IN_TX_COL} NUMBER(20) NOT NULL)",
+        TableName::new(owner, METADATA_TABLE)
+    )
+}
+
+/// Stores the op id of a table, bound as `:1` (table), `:2` (txid), `:3` (seq_in_tx).
+pub fn merge_metadata(owner: &str) -> String {
+    format!(
+        "MERGE INTO {} D USING (SELECT :1 {META_TABLE_COL}, :2 {META_TXID_COL}, :3 {META_SEQ_IN_TX_COL} FROM DUAL) S \
+         ON (D.{META_TABLE_COL} = S.{META_TABLE_COL}) \
+         WHEN MATCHED THEN UPDATE SET D.{META_TXID_COL} = S.{META_TXID_COL}, D.{META_SEQ_IN_TX_COL} = S.{META_SEQ_IN_TX_COL} \
+         WHEN NOT MATCHED THEN INSERT ({META_TABLE_COL}, {META_TXID_COL}, {META_SEQ_IN_TX_COL}) \
+         VALUES (S.{META_TABLE_COL}, S.{META_TXID_COL}, S.{META_SEQ_IN_TX_COL})",
+        TableName::new(owner, METADATA_TABLE)
+    )
+}
+
+pub fn select_metadata(owner: &str) -> String {
+    format!(
+        "SELECT {META_TXID_COL}, {META_SEQ_IN_TX_COL} FROM {} WHERE {META_TABLE_COL} = :1",
+        TableName::new(owner, METADATA_TABLE)
+    )
+}
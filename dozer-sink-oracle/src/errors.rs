@@ -0,0 +1,27 @@
+use dozer_types::{
+    thiserror::{self, Error},
+    types::Field,
+};
+
+#[derive(Error, Debug)]
+pub enum OracleSinkError {
+    #[error("Oracle error: {0}")]
+    Oracle(#[from] oracle::Error),
+
+    #[error("Table {0} has no unique key. Set `unique_key` in the sink config or define a primary key in the source")]
+    MissingUniqueKey(String),
+
+    #[error("Column {column} not found in table {table}")]
+    ColumnNotFound { table: String, column: String },
+
+    #[error("Column {column} of table {table} can't change from {from} to {to} while it has rows, only NUMBER columns can be widened")]
+    ColumnTypeChange {
+        table: String,
+        column: String,
+        from: &'static str,
+        to: &'static str,
+    },
+
+    #[error("Value {0} does not fit in an Oracle NUMBER(20)")]
+    IntegerOverflow(Field),
+}
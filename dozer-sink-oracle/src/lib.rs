@@ -0,0 +1,7 @@
+pub mod ddl;
+pub mod errors;
+mod sink;
+pub use sink::OracleSinkFactory;
+#[cfg(test)]
+mod tests;
+pub mod types;
@@ -0,0 +1,436 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use dozer_core::dag_schemas::{evolve_values, field_changes, widen_field};
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{PortHandle, Sink, SinkFactory};
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::indexmap::IndexMap;
+use dozer_types::log::{debug, info};
+use dozer_types::models::ingestion_types::OracleConfig;
+use dozer_types::models::sink::OracleSinkConfig;
+use dozer_types::node::OpIdentifier;
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Field, Operation, Schema, TableOperation};
+use oracle::sql_type::ToSql;
+use oracle::Connection;
+
+use crate::ddl::{self, TableName, METADATA_TABLE};
+use crate::errors::OracleSinkError;
+use crate::types::{bind_value, column_type};
+
+const BATCH_SIZE: u64 = 1000;
+
+#[derive(Debug)]
+pub struct OracleSinkFactory {
+    connection: OracleConfig,
+    config: OracleSinkConfig,
+}
+
+impl OracleSinkFactory {
+    pub fn new(connection: OracleConfig, config: OracleSinkConfig) -> Self {
+        Self { connection, config }
+    }
+}
+
+pub fn connect(config: &OracleConfig) -> Result<Connection, OracleSinkError> {
+    let connect_string = match &config.pdb {
+        Some(pdb) => format!("//{}:{}/{}", config.host, config.port, pdb),
+        None => format!(
+            "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCP)(HOST={})(PORT={}))(CONNECT_DATA=(SID={})))",
+            config.host, config.port, config.sid
+        ),
+    };
+    debug!("Connecting to oracle at {connect_string}");
+    Ok(Connection::connect(
+        &config.user,
+        &config.password,
+        connect_string,
+    )?)
+}
+
+/// Resolves the columns that records are merged on: `unique_key` if set, otherwise the primary key.
+pub(crate) fn key_index(
+    config: &OracleSinkConfig,
+    schema: &Schema,
+) -> Result<Vec<usize>, OracleSinkError> {
+    let key_index = if config.unique_key.is_empty() {
+        schema.primary_index.clone()
+    } else {
+        config
+            .unique_key
+            .iter()
+            .map(|column| {
+                schema
+                    .get_field_index(column)
+                    .map(|(index, _)| index)
+                    .map_err(|_| OracleSinkError::ColumnNotFound {
+                        table: config.table_name.clone(),
+                        column: column.clone(),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    if key_index.is_empty() {
+        return Err(OracleSinkError::MissingUniqueKey(config.table_name.clone()));
+    }
+    Ok(key_index)
+}
+
+fn table_exists(conn: &Connection, table: &TableName) -> Result<bool, OracleSinkError> {
+    let count = conn.query_row_as::<u64>(
+        "SELECT COUNT(*) FROM ALL_TABLES WHERE OWNER = :1 AND TABLE_NAME = :2",
+        &[&table.owner, &table.name],
+    )?;
+    Ok(count > 0)
+}
+
+/// The columns of the table, and whether they're nullable.
+fn table_columns(
+    conn: &Connection,
+    table: &TableName,
+) -> Result<HashMap<String, bool>, OracleSinkError> {
+    let columns = conn
+        .query_as::<(String, String)>(
+            "SELECT COLUMN_NAME, NULLABLE FROM ALL_TAB_COLUMNS WHERE OWNER = :1 AND TABLE_NAME = :2",
+            &[&table.owner, &table.name],
+        )?
+        .map(|column| column.map(|(name, nullable)| (name, nullable == "Y")))
+        .collect::<Result<_, _>>()?;
+    Ok(columns)
+}
+
+/// Creates the sink table if it doesn't exist, or checks that it has a column for every field.
+fn create_or_validate_table(
+    conn: &Connection,
+    table: &TableName,
+    schema: &Schema,
+    key_index: &[usize],
+) -> Result<(), OracleSinkError> {
+    if !table_exists(conn, table)? {
+        let sql = ddl::create_table(table, schema, key_index);
+        info!("Creating sink table: {sql}");
+        conn.execute(&sql, &[])?;
+        return Ok(());
+    }
+
+    let columns = table_columns(conn, table)?;
+    for field in &schema.fields {
+        if !columns.contains_key(&field.name) {
+            return Err(OracleSinkError::ColumnNotFound {
+                table: table.to_string(),
+                column: field.name.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl SinkFactory for OracleSinkFactory {
+    fn type_name(&self) -> String {
+        "oracle".to_string()
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_input_port_name(&self, _port: &PortHandle) -> String {
+        self.config.table_name.clone()
+    }
+
+    fn prepare(&self, input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        debug_assert!(input_schemas.len() == 1);
+        let schema = &input_schemas[&DEFAULT_PORT_HANDLE];
+        key_index(&self.config, schema)?;
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        mut input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        let schema = input_schemas.remove(&DEFAULT_PORT_HANDLE).unwrap();
+        let key_index = key_index(&self.config, &schema)?;
+
+        let conn = connect(&self.connection)?;
+        let owner = match &self.config.owner {
+            Some(owner) => owner.clone(),
+            None => conn.query_row_as::<String>("SELECT USER FROM DUAL", &[])?,
+        };
+        let table = TableName::new(owner.clone(), self.config.table_name.clone());
+
+        create_or_validate_table(&conn, &table, &schema, &key_index)?;
+        if !table_exists(&conn, &TableName::new(owner.clone(), METADATA_TABLE))? {
+            conn.execute(&ddl::create_metadata_table(&owner), &[])?;
+        }
+
+        Ok(Box::new(OracleSink::new(conn, table, schema, key_index)))
+    }
+}
+
+pub(crate) struct OracleSink {
+    conn: Connection,
+    table: TableName,
+    schema: Schema,
+    key_index: Vec<usize>,
+    merge_sql: String,
+    delete_sql: String,
+    merge_metadata_sql: String,
+    select_metadata_sql: String,
+    /// Pending writes by key, `None` being a delete. Only the last write of a key is kept.
+    batch: IndexMap<Vec<Field>, Option<Vec<Field>>>,
+    latest_op_id: Option<OpIdentifier>,
+}
+
+impl Debug for OracleSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OracleSink")
+            .field("table", &self.table)
+            .field("schema", &self.schema)
+            .field("key_index", &self.key_index)
+            .finish()
+    }
+}
+
+impl OracleSink {
+    pub fn new(conn: Connection, table: TableName, schema: Schema, key_index: Vec<usize>) -> Self {
+        Self {
+            merge_sql: ddl::merge(&table, &schema, &key_index),
+            delete_sql: ddl::delete(&table, &schema, &key_index),
+            merge_metadata_sql: ddl::merge_metadata(&table.owner),
+            select_metadata_sql: ddl::select_metadata(&table.owner),
+            conn,
+            table,
+            schema,
+            key_index,
+            batch: IndexMap::new(),
+            latest_op_id: None,
+        }
+    }
+
+    fn key(&self, values: &[Field]) -> Vec<Field> {
+        self.key_index.iter().map(|i| values[*i].clone()).collect()
+    }
+
+    fn upsert(&mut self, values: Vec<Field>) {
+        self.batch.insert(self.key(&values), Some(values));
+    }
+
+    fn delete(&mut self, values: &[Field]) {
+        self.batch.insert(self.key(values), None);
+    }
+
+    /// Executes `sql` once per row, binding the fields of every row as the `columns` of the schema.
+    fn execute_batch(
+        &self,
+        sql: &str,
+        columns: &[usize],
+        rows: &[&[Field]],
+    ) -> Result<(), OracleSinkError> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let mut batch = self.conn.batch(sql, rows.len()).build()?;
+        for values in rows {
+            let params = values
+                .iter()
+                .zip(columns)
+                .map(|(value, column)| bind_value(value, self.schema.fields[*column].typ))
+                .collect::<Result<Vec<_>, _>>()?;
+            let params = params.iter().map(Box::as_ref).collect::<Vec<&dyn ToSql>>();
+            batch.append_row(&params)?;
+        }
+        batch.execute()?;
+        Ok(())
+    }
+
+    fn write_batch(&self) -> Result<(), OracleSinkError> {
+        let mut deletes = vec![];
+        let mut upserts = vec![];
+        for (key, values) in &self.batch {
+            match values {
+                Some(values) => upserts.push(values.as_slice()),
+                None => deletes.push(key.as_slice()),
+            }
+        }
+        // Keys are unique within a batch, so deletes and upserts don't depend on each other's order.
+        self.execute_batch(&self.delete_sql, &self.key_index, &deletes)?;
+        let all_columns = (0..self.schema.fields.len()).collect::<Vec<_>>();
+        self.execute_batch(&self.merge_sql, &all_columns, &upserts)?;
+
+        if let Some(op_id) = self.latest_op_id {
+            debug!("[Sink] Writing checkpoint {op_id:?} of {}", self.table);
+            self.conn.execute(
+                &self.merge_metadata_sql,
+                &[&self.table.to_string(), &op_id.txid, &op_id.seq_in_tx],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Modifies the columns of the fields that became nullable or were widened, adds columns for
+    /// the fields appended to the schema, and writes them from now on.
+    fn alter_table(&mut self, schema: &Schema) -> Result<(), BoxedError> {
+        let changes = field_changes(&self.schema.fields, &schema.fields)?;
+        if changes.altered.is_empty() && changes.appended.is_empty() {
+            return Ok(());
+        }
+        // Oracle has no `ADD COLUMN IF NOT EXISTS`, and fails to make a nullable column nullable.
+        // The pending writes are only executed when the batch is flushed, so the implicit commit
+        // of the DDL doesn't commit them.
+        let columns = table_columns(&self.conn, &self.table)?;
+        for &index in &changes.altered {
+            let field = &schema.fields[index];
+            let from = column_type(self.schema.fields[index].typ);
+            let to = column_type(field.typ);
+            // Other types can only change while the column is empty.
+            if from != to && !(from.starts_with("NUMBER") && to.starts_with("NUMBER")) {
+                return Err(OracleSinkError::ColumnTypeChange {
+                    table: self.table.to_string(),
+                    column: field.name.clone(),
+                    from,
+                    to,
+                }
+                .into());
+            }
+            let drop_not_null = field.nullable && columns.get(&field.name) == Some(&false);
+            if from != to || drop_not_null {
+                self.conn
+                    .execute(&ddl::modify_column(&self.table, field, drop_not_null), &[])?;
+            }
+        }
+        for field in changes.appended {
+            if !columns.contains_key(&field.name) {
+                self.conn
+                    .execute(&ddl::add_column(&self.table, field), &[])?;
+            }
+        }
+
+        // Pending rows are written with the new column types and columns too.
+        self.batch = std::mem::take(&mut self.batch)
+            .into_iter()
+            .map(|(mut key, mut values)| {
+                for (value, &index) in key.iter_mut().zip(&self.key_index) {
+                    *value = widen_field(
+                        std::mem::replace(value, Field::Null),
+                        schema.fields[index].typ,
+                    );
+                }
+                if let Some(values) = &mut values {
+                    evolve_values(values, &schema.fields);
+                }
+                (key, values)
+            })
+            .collect();
+        self.merge_sql = ddl::merge(&self.table, schema, &self.key_index);
+        self.schema = schema.clone();
+        Ok(())
+    }
+
+    /// Writes the pending records and the latest op id in one transaction.
+    fn commit_batch(&mut self) -> Result<(), OracleSinkError> {
+        if let Err(e) = self.write_batch() {
+            self.conn.rollback()?;
+            return Err(e);
+        }
+        self.conn.commit()?;
+        self.batch.clear();
+        Ok(())
+    }
+}
+
+impl Sink for OracleSink {
+    fn commit(&mut self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
+        match op.op {
+            Operation::Insert { new } => self.upsert(new.values),
+            Operation::Delete { old } => self.delete(&old.values),
+            Operation::Update { old, new } => {
+                let old_key = self.key(&old.values);
+                if old_key != self.key(&new.values) {
+                    self.batch.insert(old_key, None);
+                }
+                self.upsert(new.values);
+            }
+            Operation::BatchInsert { new } => {
+                for record in new {
+                    self.upsert(record.values);
+                }
+            }
+        }
+        if let Some(id) = op.id {
+            self.latest_op_id = Some(id);
+        }
+        Ok(())
+    }
+
+    fn on_schema_change(&mut self, _port: PortHandle, schema: &Schema) -> Result<(), BoxedError> {
+        self.alter_table(schema)
+    }
+
+    fn on_truncate(&mut self, _port: PortHandle) -> Result<(), BoxedError> {
+        // Pending writes would be deleted anyway. They're only executed when the batch is flushed, so
+        // the implicit commit of the DDL doesn't commit them.
+        self.batch.clear();
+        self.conn.execute(&ddl::truncate(&self.table), &[])?;
+        Ok(())
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        if id.is_some() {
+            self.latest_op_id = id;
+        }
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, _source_state: &[u8]) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(None)
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        match self
+            .conn
+            .query_row_as::<(u64, u64)>(&self.select_metadata_sql, &[&self.table.to_string()])
+        {
+            Ok((txid, seq_in_tx)) => Ok(Some(OpIdentifier::new(txid, seq_in_tx))),
+            Err(oracle::Error::NoDataFound) => Ok(None),
+            Err(e) => Err(OracleSinkError::from(e).into()),
+        }
+    }
+
+    fn preferred_batch_size(&self) -> Option<u64> {
+        Some(BATCH_SIZE)
+    }
+
+    fn flush_batch(&mut self) -> Result<(), BoxedError> {
+        self.commit_batch()?;
+        Ok(())
+    }
+
+    fn supports_batching(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,247 @@
+use dozer_core::node::Sink;
+use dozer_types::models::ingestion_types::{OracleConfig, OracleReplicator};
+use dozer_types::models::sink::OracleSinkConfig;
+use dozer_types::node::OpIdentifier;
+use dozer_types::types::{
+    Field, FieldDefinition, FieldType, Operation, Record, Schema, SourceDefinition, TableOperation,
+};
+
+use crate::ddl::{self, TableName};
+use crate::errors::OracleSinkError;
+use crate::sink::{connect, key_index, OracleSink};
+
+fn get_schema() -> Schema {
+    Schema {
+        fields: vec![
+            FieldDefinition::new(
+                "id".to_string(),
+                FieldType::Int,
+                false,
+                SourceDefinition::Dynamic,
+            ),
+            FieldDefinition::new(
+                "name".to_string(),
+                FieldType::String,
+                true,
+                SourceDefinition::Dynamic,
+            ),
+        ],
+        primary_index: vec![0],
+    }
+}
+
+fn get_sink_config(unique_key: Vec<String>) -> OracleSinkConfig {
+    OracleSinkConfig {
+        connection: "oracle".to_string(),
+        table_name: "sink_table".to_string(),
+        unique_key,
+        owner: None,
+    }
+}
+
+#[test]
+fn test_key_index() {
+    let schema = get_schema();
+    assert_eq!(
+        key_index(&get_sink_config(vec![]), &schema).unwrap(),
+        vec![0]
+    );
+    assert_eq!(
+        key_index(&get_sink_config(vec!["name".to_string()]), &schema).unwrap(),
+        vec![1]
+    );
+    assert!(matches!(
+        key_index(&get_sink_config(vec!["missing".to_string()]), &schema),
+        Err(OracleSinkError::ColumnNotFound { .. })
+    ));
+
+    let mut schema = schema;
+    schema.primary_index.clear();
+    assert!(matches!(
+        key_index(&get_sink_config(vec![]), &schema),
+        Err(OracleSinkError::MissingUniqueKey(_))
+    ));
+}
+
+#[test]
+fn test_ddl() {
+    let table = TableName::new("DOZER", "sink_table");
+    let schema = get_schema();
+    assert_eq!(
+        ddl::create_table(&table, &schema, &[0]),
+        r#"CREATE TABLE "DOZER"."sink_table" ("id" NUMBER(19) NOT NULL, "name" VARCHAR2(4000), UNIQUE ("id"))"#
+    );
+    assert_eq!(
+        ddl::merge(&table, &schema, &[0]),
+        r#"MERGE INTO "DOZER"."sink_table" D USING (SELECT :1 "id", :2 "name" FROM DUAL) S ON (D."id" = S."id") WHEN MATCHED THEN UPDATE SET D."name" = S."name" WHEN NOT MATCHED THEN INSERT ("id", "name") VALUES (S."id", S."name")"#
+    );
+    assert_eq!(
+        ddl::merge(&table, &schema, &[0, 1]),
+        r#"MERGE INTO "DOZER"."sink_table" D USING (SELECT :1 "id", :2 "name" FROM DUAL) S ON (D."id" = S."id" AND D."name" = S."name") WHEN NOT MATCHED THEN INSERT ("id", "name") VALUES (S."id", S."name")"#
+    );
+    assert_eq!(
+        ddl::delete(&table, &schema, &[1, 0]),
+        r#"DELETE FROM "DOZER"."sink_table" WHERE "name" = :1 AND "id" = :2"#
+    );
+    assert_eq!(
+        ddl::add_column(&table, &schema.fields[1]),
+        r#"ALTER TABLE "DOZER"."sink_table" ADD ("name" VARCHAR2(4000))"#
+    );
+    let mut widened = schema.fields[0].clone();
+    widened.typ = FieldType::I128;
+    assert_eq!(
+        ddl::modify_column(&table, &widened, false),
+        r#"ALTER TABLE "DOZER"."sink_table" MODIFY ("id" NUMBER(39))"#
+    );
+    assert_eq!(
+        ddl::modify_column(&table, &widened, true),
+        r#"ALTER TABLE "DOZER"."sink_table" MODIFY ("id" NUMBER(39) NULL)"#
+    );
+    assert_eq!(
+        ddl::truncate(&table),
+        r#"TRUNCATE TABLE "DOZER"."sink_table""#
+    );
+}
+
+fn get_connection_config() -> OracleConfig {
+    OracleConfig {
+        user: "DOZER".to_string(),
+        password: "123".to_string(),
+        host: "localhost".to_string(),
+        port: 1521,
+        sid: "ORCLCDB".to_string(),
+        pdb: Some("ORCLPDB1".to_string()),
+        schemas: vec![],
+        batch_size: None,
+        replicator: OracleReplicator::DozerLogReader,
+    }
+}
+
+fn insert(id: i64, name: &str, txid: u64) -> TableOperation {
+    TableOperation {
+        id: Some(OpIdentifier::new(txid, 0)),
+        op: Operation::Insert {
+            new: Record::new(vec![Field::Int(id), Field::String(name.to_string())]),
+        },
+        port: 0,
+    }
+}
+
+#[test]
+#[ignore]
+fn test_merge_and_resume() {
+    let config = get_connection_config();
+    let table = TableName::new("DOZER", "sink_table");
+    let conn = connect(&config).unwrap();
+    let _ = conn.execute(&format!("DROP TABLE {table}"), &[]);
+    let _ = conn.execute(
+        &format!(
+            "DROP TABLE {}",
+            TableName::new("DOZER", ddl::METADATA_TABLE)
+        ),
+        &[],
+    );
+    let schema = get_schema();
+    conn.execute(&ddl::create_table(&table, &schema, &[0]), &[])
+        .unwrap();
+    conn.execute(&ddl::create_metadata_table("DOZER"), &[])
+        .unwrap();
+
+    let mut sink = OracleSink::new(conn, table.clone(), schema.clone(), vec![0]);
+    sink.process(insert(1, "a", 1)).unwrap();
+    sink.process(insert(2, "b", 1)).unwrap();
+    sink.flush_batch().unwrap();
+    // Replaying an insert updates the existing row.
+    sink.process(insert(1, "c", 2)).unwrap();
+    sink.process(TableOperation {
+        id: Some(OpIdentifier::new(3, 0)),
+        op: Operation::Delete {
+            old: Record::new(vec![Field::Int(2), Field::Null]),
+        },
+        port: 0,
+    })
+    .unwrap();
+    sink.flush_batch().unwrap();
+
+    let conn = connect(&config).unwrap();
+    let rows = conn
+        .query_as::<(i64, String)>(&format!(r#"SELECT "id", "name" FROM {table}"#), &[])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![(1, "c".to_string())]);
+
+    let mut sink = OracleSink::new(conn, table, schema, vec![0]);
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(3, 0))
+    );
+}
+
+#[test]
+#[ignore]
+fn test_schema_change() {
+    let config = get_connection_config();
+    let table = TableName::new("DOZER", "sink_schema_change");
+    let conn = connect(&config).unwrap();
+    let _ = conn.execute(&format!("DROP TABLE {table}"), &[]);
+    let mut schema = get_schema();
+    schema.field(
+        FieldDefinition::new(
+            "count".to_string(),
+            FieldType::Int8,
+            false,
+            SourceDefinition::Dynamic,
+        ),
+        false,
+    );
+    conn.execute(&ddl::create_table(&table, &schema, &[0]), &[])
+        .unwrap();
+    let _ = conn.execute(&ddl::create_metadata_table("DOZER"), &[]);
+
+    let mut sink = OracleSink::new(conn, table.clone(), schema.clone(), vec![0]);
+    let insert = |values, txid| TableOperation {
+        id: Some(OpIdentifier::new(txid, 0)),
+        op: Operation::Insert {
+            new: Record::new(values),
+        },
+        port: 0,
+    };
+    // The pending row is written with the widened type.
+    sink.process(insert(
+        vec![
+            Field::Int(1),
+            Field::String("a".to_string()),
+            Field::Int8(1),
+        ],
+        1,
+    ))
+    .unwrap();
+    // `count` is widened, and is dropped from the source, so it becomes nullable too.
+    let mut evolved = schema.clone();
+    evolved.fields[2].typ = FieldType::Int;
+    evolved.fields[2].nullable = true;
+    sink.on_schema_change(0, &evolved).unwrap();
+    sink.process(insert(
+        vec![Field::Int(2), Field::String("b".to_string()), Field::Null],
+        2,
+    ))
+    .unwrap();
+    sink.flush_batch().unwrap();
+
+    let conn = connect(&config).unwrap();
+    let rows = conn
+        .query_as::<(i64, Option<i64>)>(
+            &format!(r#"SELECT "id", "count" FROM {table} ORDER BY "id""#),
+            &[],
+        )
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![(1, Some(1)), (2, None)]);
+
+    // Oracle can't change other column types while the column has rows.
+    let mut text = evolved.clone();
+    text.fields[1].typ = FieldType::Text;
+    assert!(sink.on_schema_change(0, &text).is_err());
+}
@@ -0,0 +1,93 @@
+use dozer_types::chrono::{DateTime, FixedOffset, NaiveDate};
+use dozer_types::json_types::json_to_string;
+use dozer_types::types::{DozerDuration, DozerPoint, Field, FieldType};
+use oracle::sql_type::{OracleType, ToSql};
+use oracle::{Connection, SqlValue};
+
+use crate::errors::OracleSinkError;
+
+/// Returns the Oracle column type used to store a dozer field type.
+///
+/// Durations are stored as nanoseconds and points as WKT, since their native Oracle
+/// counterparts (`INTERVAL`, `SDO_GEOMETRY`) cannot hold every value or need extra options.
+pub fn column_type(typ: FieldType) -> &'static str {
+    match typ {
+        FieldType::UInt => "NUMBER(20)",
+        FieldType::U128 => "NUMBER(39)",
+        FieldType::Int => "NUMBER(19)",
+        FieldType::Int8 => "NUMBER(3)",
+        FieldType::I128 => "NUMBER(39)",
+        FieldType::Float => "BINARY_DOUBLE",
+        FieldType::Boolean => "NUMBER(1)",
+        FieldType::String => "VARCHAR2(4000)",
+        FieldType::Text => "CLOB",
+        FieldType::Binary => "BLOB",
+        FieldType::Decimal => "NUMBER",
+        FieldType::Timestamp => "TIMESTAMP(9) WITH TIME ZONE",
+        FieldType::Date => "DATE",
+        FieldType::Json => "CLOB",
+        FieldType::Point => "VARCHAR2(100)",
+        FieldType::Duration => "NUMBER(20)",
+    }
+}
+
+/// A number bound by its decimal representation, so no precision is lost on the way.
+#[derive(Debug)]
+pub struct OracleNumber(pub Option<String>);
+
+impl ToSql for OracleNumber {
+    fn oratype(&self, _conn: &Connection) -> oracle::Result<OracleType> {
+        Ok(OracleType::Number(0, 0))
+    }
+
+    fn to_sql(&self, val: &mut SqlValue) -> oracle::Result<()> {
+        val.set(&self.0)
+    }
+}
+
+fn number(value: Option<String>) -> Box<dyn ToSql> {
+    Box::new(OracleNumber(value))
+}
+
+/// Converts a field to a bind parameter of the column type of `typ`.
+///
+/// `typ` is needed to bind `Field::Null` with the type of its column.
+pub fn bind_value(field: &Field, typ: FieldType) -> Result<Box<dyn ToSql>, OracleSinkError> {
+    let value: Box<dyn ToSql> = match field {
+        Field::UInt(v) => Box::new(*v),
+        Field::U128(v) => number(Some(v.to_string())),
+        Field::Int(v) => Box::new(*v),
+        Field::Int8(v) => Box::new(*v as i64),
+        Field::I128(v) => number(Some(v.to_string())),
+        Field::Float(v) => Box::new(v.0),
+        Field::Boolean(v) => Box::new(*v as i64),
+        Field::String(v) | Field::Text(v) => Box::new(v.clone()),
+        Field::Binary(v) => Box::new(v.clone()),
+        Field::Decimal(v) => number(Some(v.to_string())),
+        Field::Timestamp(v) => Box::new(*v),
+        Field::Date(v) => Box::new(*v),
+        Field::Json(v) => Box::new(json_to_string(v)),
+        Field::Point(DozerPoint(p)) => Box::new(format!("POINT ({} {})", p.x().0, p.y().0)),
+        Field::Duration(DozerDuration(d, _)) => Box::new(
+            u64::try_from(d.as_nanos())
+                .map_err(|_| OracleSinkError::IntegerOverflow(field.clone()))?,
+        ),
+        Field::Null => null(typ),
+    };
+    Ok(value)
+}
+
+fn null(typ: FieldType) -> Box<dyn ToSql> {
+    match typ {
+        FieldType::UInt | FieldType::Duration => Box::new(None::<u64>),
+        FieldType::U128 | FieldType::I128 | FieldType::Decimal => number(None),
+        FieldType::Int | FieldType::Int8 | FieldType::Boolean => Box::new(None::<i64>),
+        FieldType::Float => Box::new(None::<f64>),
+        FieldType::String | FieldType::Text | FieldType::Json | FieldType::Point => {
+            Box::new(None::<String>)
+        }
+        FieldType::Binary => Box::new(None::<Vec<u8>>),
+        FieldType::Timestamp => Box::new(None::<DateTime<FixedOffset>>),
+        FieldType::Date => Box::new(None::<NaiveDate>),
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt::Display;
+
+use dozer_types::types::{FieldDefinition, Schema};
+
+use crate::types::column_type;
+
+pub const METADATA_TABLE: &str = "__dozer_replication_metadata";
+pub const META_TABLE_COL: &str = "table_name";
+pub const META_TXID_COL: &str = "txid";
+pub const META_SEQ_IN_TX_COL: &str = "seq_in_tx";
+pub const META_SOURCE_STATE_COL: &str = "source_state";
+
+/// A table qualified by its schema. Both parts are quoted, so they are case sensitive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableName {
+    pub schema: String,
+    pub name: String,
+}
+
+impl TableName {
+    pub fn new(schema: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            schema: schema.into(),
+            name: name.into(),
+        }
+    }
+}
+
+impl Display for TableName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", quote(&self.schema), quote(&self.name))
+    }
+}
+
+pub fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn quoted_columns<'a>(schema: &'a Schema, index: &'a [usize]) -> impl Iterator<Item = String> + 'a {
+    index.iter().map(|i| quote(&schema.fields[*i].name))
+}
+
+fn all_columns(schema: &Schema) -> String {
+    schema
+        .fields
+        .iter()
+        .map(|field| quote(&field.name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn column_definition(field: &FieldDefinition) -> String {
+    format!(
+        "{} {}{}",
+        quote(&field.name),
+        column_type(field.typ),
+        if field.nullable { "" } else { " NOT NULL" }
+    )
+}
+
+pub fn create_table(table: &TableName, schema: &Schema, key_index: &[usize]) -> String {
+    let mut columns = schema
+        .fields
+        .iter()
+        .map(column_definition)
+        .collect::<Vec<_>>();
+    if !key_index.is_empty() {
+        columns.push(format!(
+            "PRIMARY KEY ({})",
+            quoted_columns(schema, key_index)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    format!("CREATE TABLE {table} ({})", columns.join(", "))
+}
+
+/// Inserts one record, bound as `$1..$n` in schema order.
+///
+/// With a key, an existing record with the same key is updated instead.
+pub fn insert(table: &TableName, schema: &Schema, key_index: &[usize]) -> String {
+    let values = (1..=schema.fields.len())
+        .map(|i| format!("${i}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut sql = format!(
+        "INSERT INTO {table} ({}) VALUES ({values})",
+        all_columns(schema)
+    );
+    if key_index.is_empty() {
+        return sql;
+    }
+
+    sql += &format!(
+        " ON CONFLICT ({})",
+        quoted_columns(schema, key_index)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let update = (0..schema.fields.len())
+        .filter(|i| !key_index.contains(i))
+        .map(|i| {
+            let column = quote(&schema.fields[i].name);
+            format!("{column} = EXCLUDED.{column}")
+        })
+        .collect::<Vec<_>>();
+    if update.is_empty() {
+        sql += " DO NOTHING";
+    } else {
+        sql += &format!(" DO UPDATE SET {}", update.join(", "));
+    }
+    sql
+}
+
+/// Deletes one record, with the key bound as `$1..$n` in key order.
+pub fn delete(table: &TableName, schema: &Schema, key_index: &[usize]) -> String {
+    let condition = quoted_columns(schema, key_index)
+        .enumerate()
+        .map(|(i, column)| format!("{column} = ${}", i + 1))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    format!("DELETE FROM {table} WHERE {condition}")
+}
+
+/// Adds a column for `field`, unless the table already has it.
+pub fn add_column(table: &TableName, field: &FieldDefinition) -> String {
+    format!(
+        "ALTER TABLE {table} ADD COLUMN IF NOT EXISTS {}",
+        column_definition(field)
+    )
+}
+
+/// Changes the column of `current` to `field`, which may only be nullable or have a wider type.
+///
+/// `None` if the column doesn't change, as both types have the same column type.
+pub fn alter_column(
+    table: &TableName,
+    current: &FieldDefinition,
+    field: &FieldDefinition,
+) -> Option<String> {
+    let column = quote(&field.name);
+    let mut changes = vec![];
+    if column_type(current.typ) != column_type(field.typ) {
+        changes.push(format!(
+            "ALTER COLUMN {column} TYPE {}",
+            column_type(field.typ)
+        ));
+    }
+    if field.nullable {
+        changes.push(format!("ALTER COLUMN {column} DROP NOT NULL"));
+    }
+    (!changes.is_empty()).then(|| format!("ALTER TABLE {table} {}", changes.join(", ")))
+}
+
+pub fn truncate(table: &TableName) -> String {
+    format!("TRUNCATE {table}")
+}
+
+pub fn copy(table: &TableName, schema: &Schema) -> String {
+    format!("COPY {table} ({}) FROM STDIN BINARY", all_columns(schema))
+}
+
+pub fn create_metadata_table(schema: &str) -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (\
+         {META_TABLE_COL} TEXT PRIMARY KEY, \
+         {META_TXID_COL} NUMERIC(20, 0), \
+         {META_SEQ_IN_TX_COL} NUMERIC(20, 0), \
+         {META_SOURCE_STATE_COL} BYTEA)",
+        TableName::new(schema, METADATA_TABLE)
+    )
+}
+
+/// Stores the checkpoint of a table, bound as `$1` (table), `$2` (txid), `$3` (seq_in_tx)
+/// and `$4` (source state).
+pub fn upsert_metadata(schema: &str) -> String {
+    format!(
+        "INSERT INTO {} ({META_TABLE_COL}, {META_TXID_COL}, {META_SEQ_IN_TX_COL}, {META_SOURCE_STATE_COL}) \
+         VALUES ($1, $2, $3, $4) \
+         ON CONFLICT ({META_TABLE_COL}) DO UPDATE SET \
+         {META_TXID_COL} = EXCLUDED.{META_TXID_COL}, \
+         {META_SEQ_IN_TX_COL} = EXCLUDED.{META_SEQ_IN_TX_COL}, \
+         {META_SOURCE_STATE_COL} = EXCLUDED.{META_SOURCE_STATE_COL}",
+        TableName::new(schema, METADATA_TABLE)
+    )
+}
+
+pub fn select_metadata(schema: &str) -> String {
+    format!(
+        "SELECT {META_TXID_COL}, {META_SEQ_IN_TX_COL}, {META_SOURCE_STATE_COL} FROM {} WHERE {META_TABLE_COL} = $1",
+        TableName::new(schema, METADATA_TABLE)
+    )
+}
@@ -0,0 +1,22 @@
+use dozer_types::{
+    errors::types::DeserializationError,
+    thiserror::{self, Error},
+};
+
+#[derive(Error, Debug)]
+pub enum PostgresSinkError {
+    #[error("Postgres error: {0}")]
+    Postgres(#[from] tokio_postgres::Error),
+
+    #[error("Invalid connection config: {0}")]
+    InvalidConnection(#[from] DeserializationError),
+
+    #[error("The Postgres sink doesn't support TLS, set `sslmode` to `disable` or `prefer`")]
+    TlsNotSupported,
+
+    #[error("Table {0} has no unique key. Set `unique_key` in the sink config or define a primary key in the source")]
+    MissingUniqueKey(String),
+
+    #[error("Column {column} not found in table {table}")]
+    ColumnNotFound { table: String, column: String },
+}
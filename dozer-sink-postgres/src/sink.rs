@@ -0,0 +1,550 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::pin::pin;
+use std::sync::Arc;
+
+use dozer_core::dag_schemas::{evolve_values, field_changes, widen_field};
+use dozer_core::epoch::Epoch;
+use dozer_core::event::EventHub;
+use dozer_core::node::{PortHandle, Sink, SinkFactory};
+use dozer_core::tokio::runtime::Runtime;
+use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_types::errors::internal::BoxedError;
+use dozer_types::log::{debug, error, info};
+use dozer_types::models::connection::PostgresConfig;
+use dozer_types::models::sink::PostgresSinkConfig;
+use dozer_types::node::OpIdentifier;
+use dozer_types::rust_decimal::prelude::ToPrimitive;
+use dozer_types::rust_decimal::Decimal;
+use dozer_types::tonic::async_trait;
+use dozer_types::types::{Field, Operation, Schema, TableOperation};
+use tokio_postgres::binary_copy::BinaryCopyInWriter;
+use tokio_postgres::config::SslMode;
+use tokio_postgres::types::Type;
+use tokio_postgres::{Client, NoTls, Statement};
+
+use crate::ddl::{self, TableName};
+use crate::errors::PostgresSinkError;
+use crate::types::{param_refs, params, pg_type, PgField};
+
+const BATCH_SIZE: u64 = 1000;
+const DEFAULT_SCHEMA: &str = "public";
+
+#[derive(Debug)]
+pub struct PostgresSinkFactory {
+    connection: PostgresConfig,
+    config: PostgresSinkConfig,
+    runtime: Arc<Runtime>,
+}
+
+impl PostgresSinkFactory {
+    pub fn new(
+        connection: PostgresConfig,
+        config: PostgresSinkConfig,
+        runtime: Arc<Runtime>,
+    ) -> Self {
+        Self {
+            connection,
+            config,
+            runtime,
+        }
+    }
+}
+
+/// Connects to Postgres, driving the connection on `runtime`.
+pub async fn connect(
+    config: &PostgresConfig,
+    runtime: &Runtime,
+) -> Result<Client, PostgresSinkError> {
+    let replenished = config.replenish()?;
+    if matches!(replenished.sslmode, SslMode::Require) {
+        return Err(PostgresSinkError::TlsNotSupported);
+    }
+    let mut pg_config = tokio_postgres::Config::new();
+    pg_config
+        .host(&replenished.host)
+        .port(replenished.port as u16)
+        .user(&replenished.user)
+        .dbname(&replenished.database)
+        .password(&replenished.password)
+        .ssl_mode(replenished.sslmode);
+    debug!(
+        "Connecting to postgres at {}:{}",
+        replenished.host, replenished.port
+    );
+    let (client, connection) = pg_config.connect(NoTls).await?;
+    runtime.spawn(async move {
+        if let Err(e) = connection.await {
+            error!("Postgres sink connection error: {e}");
+        }
+    });
+    Ok(client)
+}
+
+/// Resolves the columns that records are upserted on: `unique_key` if set, otherwise the primary key.
+///
+/// Without a key, records can only be inserted.
+pub(crate) fn key_index(
+    config: &PostgresSinkConfig,
+    schema: &Schema,
+) -> Result<Vec<usize>, PostgresSinkError> {
+    if config.unique_key.is_empty() {
+        return Ok(schema.primary_index.clone());
+    }
+    config
+        .unique_key
+        .iter()
+        .map(|column| {
+            schema
+                .get_field_index(column)
+                .map(|(index, _)| index)
+                .map_err(|_| PostgresSinkError::ColumnNotFound {
+                    table: config.table_name.clone(),
+                    column: column.clone(),
+                })
+        })
+        .collect()
+}
+
+async fn table_columns(
+    client: &Client,
+    table: &TableName,
+) -> Result<HashSet<String>, PostgresSinkError> {
+    let rows = client
+        .query(
+            "SELECT column_name FROM information_schema.columns WHERE table_schema = $1 AND table_name = $2",
+            &[&table.schema, &table.name],
+        )
+        .await?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+/// Creates the sink table if it doesn't exist, or checks that it has a column for every field.
+async fn create_or_validate_table(
+    client: &Client,
+    table: &TableName,
+    schema: &Schema,
+    key_index: &[usize],
+) -> Result<(), PostgresSinkError> {
+    let columns = table_columns(client, table).await?;
+    if columns.is_empty() {
+        let sql = ddl::create_table(table, schema, key_index);
+        info!("Creating sink table: {sql}");
+        client.batch_execute(&sql).await?;
+        return Ok(());
+    }
+
+    for field in &schema.fields {
+        if !columns.contains(&field.name) {
+            return Err(PostgresSinkError::ColumnNotFound {
+                table: table.to_string(),
+                column: field.name.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl SinkFactory for PostgresSinkFactory {
+    fn type_name(&self) -> String {
+        "postgres".to_string()
+    }
+
+    fn get_input_ports(&self) -> Vec<PortHandle> {
+        vec![DEFAULT_PORT_HANDLE]
+    }
+
+    fn get_input_port_name(&self, _port: &PortHandle) -> String {
+        self.config.table_name.clone()
+    }
+
+    fn prepare(&self, input_schemas: HashMap<PortHandle, Schema>) -> Result<(), BoxedError> {
+        debug_assert!(input_schemas.len() == 1);
+        let schema = &input_schemas[&DEFAULT_PORT_HANDLE];
+        key_index(&self.config, schema)?;
+        Ok(())
+    }
+
+    async fn build(
+        &self,
+        mut input_schemas: HashMap<PortHandle, Schema>,
+        _event_hub: EventHub,
+    ) -> Result<Box<dyn Sink>, BoxedError> {
+        let schema = input_schemas.remove(&DEFAULT_PORT_HANDLE).unwrap();
+        let key_index = key_index(&self.config, &schema)?;
+
+        let client = connect(&self.connection, &self.runtime).await?;
+        let table_schema = self
+            .config
+            .schema
+            .clone()
+            .or_else(|| self.connection.schema.clone())
+            .unwrap_or_else(|| DEFAULT_SCHEMA.to_string());
+        let table = TableName::new(table_schema, self.config.table_name.clone());
+
+        create_or_validate_table(&client, &table, &schema, &key_index).await?;
+        client
+            .batch_execute(&ddl::create_metadata_table(&table.schema))
+            .await?;
+
+        let sink =
+            PostgresSink::new(client, self.runtime.clone(), table, schema, key_index).await?;
+        Ok(Box::new(sink))
+    }
+}
+
+#[derive(Debug)]
+enum BatchOp {
+    /// Rows of consecutive batch inserts, written with `COPY`.
+    Copy(Vec<Vec<Field>>),
+    Insert(Vec<Field>),
+    Delete(Vec<Field>),
+    /// Deletes all rows, in the same transaction as the rest of the batch.
+    Truncate,
+}
+
+struct Statements {
+    insert: Statement,
+    delete: Option<Statement>,
+    upsert_metadata: Statement,
+    select_metadata: Statement,
+}
+
+impl Statements {
+    async fn prepare(
+        client: &Client,
+        table: &TableName,
+        schema: &Schema,
+        key_index: &[usize],
+    ) -> Result<Self, PostgresSinkError> {
+        let delete = if key_index.is_empty() {
+            None
+        } else {
+            Some(
+                client
+                    .prepare(&ddl::delete(table, schema, key_index))
+                    .await?,
+            )
+        };
+        Ok(Self {
+            insert: client
+                .prepare(&ddl::insert(table, schema, key_index))
+                .await?,
+            delete,
+            upsert_metadata: client.prepare(&ddl::upsert_metadata(&table.schema)).await?,
+            select_metadata: client.prepare(&ddl::select_metadata(&table.schema)).await?,
+        })
+    }
+}
+
+pub(crate) struct PostgresSink {
+    client: Client,
+    runtime: Arc<Runtime>,
+    table: TableName,
+    schema: Schema,
+    key_index: Vec<usize>,
+    statements: Statements,
+    copy_sql: String,
+    column_types: Vec<Type>,
+    /// Pending writes, applied in order.
+    batch: Vec<BatchOp>,
+    latest_op_id: Option<OpIdentifier>,
+    source_state: Option<Vec<u8>>,
+    /// The last committed op id and source state.
+    checkpoint: (Option<OpIdentifier>, Option<Vec<u8>>),
+}
+
+impl Debug for PostgresSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PostgresSink")
+            .field("table", &self.table)
+            .field("schema", &self.schema)
+            .field("key_index", &self.key_index)
+            .finish()
+    }
+}
+
+impl PostgresSink {
+    pub async fn new(
+        client: Client,
+        runtime: Arc<Runtime>,
+        table: TableName,
+        schema: Schema,
+        key_index: Vec<usize>,
+    ) -> Result<Self, PostgresSinkError> {
+        let mut sink = Self {
+            statements: Statements::prepare(&client, &table, &schema, &key_index).await?,
+            copy_sql: ddl::copy(&table, &schema),
+            column_types: schema
+                .fields
+                .iter()
+                .map(|field| pg_type(field.typ))
+                .collect(),
+            client,
+            runtime,
+            table,
+            schema,
+            key_index,
+            batch: vec![],
+            latest_op_id: None,
+            source_state: None,
+            checkpoint: (None, None),
+        };
+        // The sync getters run inside the async pipeline build, so the checkpoint is read here.
+        sink.checkpoint = sink.read_checkpoint().await?;
+        Ok(sink)
+    }
+
+    fn key(&self, values: &[Field]) -> Result<Vec<Field>, PostgresSinkError> {
+        if self.key_index.is_empty() {
+            return Err(PostgresSinkError::MissingUniqueKey(self.table.to_string()));
+        }
+        Ok(self.key_index.iter().map(|i| values[*i].clone()).collect())
+    }
+
+    fn copy(&mut self, rows: impl IntoIterator<Item = Vec<Field>>) {
+        if let Some(BatchOp::Copy(batch)) = self.batch.last_mut() {
+            batch.extend(rows);
+        } else {
+            self.batch.push(BatchOp::Copy(rows.into_iter().collect()));
+        }
+    }
+
+    async fn copy_rows(&self, rows: &[Vec<Field>]) -> Result<(), PostgresSinkError> {
+        let sink = self.client.copy_in(&self.copy_sql).await?;
+        let mut writer = pin!(BinaryCopyInWriter::new(sink, &self.column_types));
+        for row in rows {
+            let params = params(row);
+            writer.as_mut().write(&param_refs(&params)).await?;
+        }
+        writer.finish().await?;
+        Ok(())
+    }
+
+    async fn write_batch(&self) -> Result<(), PostgresSinkError> {
+        for op in &self.batch {
+            match op {
+                BatchOp::Copy(rows) => self.copy_rows(rows).await?,
+                BatchOp::Insert(values) => {
+                    let params = params(values);
+                    self.client
+                        .execute(&self.statements.insert, &param_refs(&params))
+                        .await?;
+                }
+                BatchOp::Delete(key) => {
+                    let delete = self
+                        .statements
+                        .delete
+                        .as_ref()
+                        .expect("deletes are only batched with a key");
+                    let params = params(key);
+                    self.client.execute(delete, &param_refs(&params)).await?;
+                }
+                BatchOp::Truncate => {
+                    self.client
+                        .batch_execute(&ddl::truncate(&self.table))
+                        .await?;
+                }
+            }
+        }
+
+        if self.latest_op_id.is_some() || self.source_state.is_some() {
+            debug!(
+                "[Sink] Writing checkpoint {:?} of {}",
+                self.latest_op_id, self.table
+            );
+            let (txid, seq_in_tx) = match self.latest_op_id {
+                Some(op_id) => (Field::UInt(op_id.txid), Field::UInt(op_id.seq_in_tx)),
+                None => (Field::Null, Field::Null),
+            };
+            self.client
+                .execute(
+                    &self.statements.upsert_metadata,
+                    &[
+                        &self.table.to_string(),
+                        &PgField(&txid),
+                        &PgField(&seq_in_tx),
+                        &self.source_state,
+                    ],
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Writes the pending records and the checkpoint in one transaction.
+    async fn commit_batch(&mut self) -> Result<(), PostgresSinkError> {
+        self.client.batch_execute("BEGIN").await?;
+        if let Err(e) = self.write_batch().await {
+            self.client.batch_execute("ROLLBACK").await?;
+            return Err(e);
+        }
+        self.client.batch_execute("COMMIT").await?;
+        self.batch.clear();
+        if self.latest_op_id.is_some() || self.source_state.is_some() {
+            self.checkpoint = (self.latest_op_id, self.source_state.clone());
+        }
+        Ok(())
+    }
+
+    /// Alters the columns of the fields that became nullable or were widened, adds columns for
+    /// the fields appended to the schema, and writes them from now on.
+    async fn alter_table(&mut self, schema: &Schema) -> Result<(), BoxedError> {
+        let changes = field_changes(&self.schema.fields, &schema.fields)?;
+        if changes.altered.is_empty() && changes.appended.is_empty() {
+            return Ok(());
+        }
+        for &index in &changes.altered {
+            if let Some(sql) = ddl::alter_column(
+                &self.table,
+                &self.schema.fields[index],
+                &schema.fields[index],
+            ) {
+                self.client.batch_execute(&sql).await?;
+            }
+        }
+        for field in changes.appended {
+            self.client
+                .batch_execute(&ddl::add_column(&self.table, field))
+                .await?;
+        }
+
+        // Pending rows are written with the new column types and columns too.
+        for op in &mut self.batch {
+            match op {
+                BatchOp::Copy(rows) => {
+                    for row in rows {
+                        evolve_values(row, &schema.fields);
+                    }
+                }
+                BatchOp::Insert(values) => evolve_values(values, &schema.fields),
+                BatchOp::Delete(key) => {
+                    for (value, &index) in key.iter_mut().zip(&self.key_index) {
+                        *value = widen_field(
+                            std::mem::replace(value, Field::Null),
+                            schema.fields[index].typ,
+                        );
+                    }
+                }
+                BatchOp::Truncate => (),
+            }
+        }
+        self.statements =
+            Statements::prepare(&self.client, &self.table, schema, &self.key_index).await?;
+        self.copy_sql = ddl::copy(&self.table, schema);
+        self.column_types = schema
+            .fields
+            .iter()
+            .map(|field| pg_type(field.typ))
+            .collect();
+        self.schema = schema.clone();
+        Ok(())
+    }
+
+    async fn read_checkpoint(
+        &self,
+    ) -> Result<(Option<OpIdentifier>, Option<Vec<u8>>), PostgresSinkError> {
+        let Some(row) = self
+            .client
+            .query_opt(&self.statements.select_metadata, &[&self.table.to_string()])
+            .await?
+        else {
+            return Ok((None, None));
+        };
+        let txid: Option<Decimal> = row.get(0);
+        let seq_in_tx: Option<Decimal> = row.get(1);
+        let op_id = txid
+            .and_then(|txid| txid.to_u64())
+            .zip(seq_in_tx.and_then(|seq_in_tx| seq_in_tx.to_u64()))
+            .map(|(txid, seq_in_tx)| OpIdentifier::new(txid, seq_in_tx));
+        Ok((op_id, row.get(2)))
+    }
+}
+
+impl Sink for PostgresSink {
+    fn commit(&mut self, _epoch_details: &Epoch) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn process(&mut self, op: TableOperation) -> Result<(), BoxedError> {
+        match op.op {
+            Operation::Insert { new } => self.batch.push(BatchOp::Insert(new.values)),
+            Operation::Delete { old } => {
+                let key = self.key(&old.values)?;
+                self.batch.push(BatchOp::Delete(key));
+            }
+            Operation::Update { old, new } => {
+                let old_key = self.key(&old.values)?;
+                if old_key != self.key(&new.values)? {
+                    self.batch.push(BatchOp::Delete(old_key));
+                }
+                self.batch.push(BatchOp::Insert(new.values));
+            }
+            Operation::BatchInsert { new } => {
+                self.copy(new.into_iter().map(|record| record.values));
+            }
+        }
+        if let Some(id) = op.id {
+            self.latest_op_id = Some(id);
+        }
+        Ok(())
+    }
+
+    fn on_source_snapshotting_started(
+        &mut self,
+        _connection_name: String,
+    ) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn on_source_snapshotting_done(
+        &mut self,
+        _connection_name: String,
+        id: Option<OpIdentifier>,
+    ) -> Result<(), BoxedError> {
+        if id.is_some() {
+            self.latest_op_id = id;
+        }
+        Ok(())
+    }
+
+    fn set_source_state(&mut self, source_state: &[u8]) -> Result<(), BoxedError> {
+        self.source_state = Some(source_state.to_vec());
+        Ok(())
+    }
+
+    fn get_source_state(&mut self) -> Result<Option<Vec<u8>>, BoxedError> {
+        Ok(self.checkpoint.1.clone())
+    }
+
+    fn get_latest_op_id(&mut self) -> Result<Option<OpIdentifier>, BoxedError> {
+        Ok(self.checkpoint.0)
+    }
+
+    fn on_schema_change(&mut self, _port: PortHandle, schema: &Schema) -> Result<(), BoxedError> {
+        let runtime = self.runtime.clone();
+        runtime.block_on(self.alter_table(schema))
+    }
+
+    fn on_truncate(&mut self, _port: PortHandle) -> Result<(), BoxedError> {
+        // Pending writes would be deleted anyway.
+        self.batch.clear();
+        self.batch.push(BatchOp::Truncate);
+        Ok(())
+    }
+
+    fn preferred_batch_size(&self) -> Option<u64> {
+        Some(BATCH_SIZE)
+    }
+
+    fn flush_batch(&mut self) -> Result<(), BoxedError> {
+        let runtime = self.runtime.clone();
+        runtime.block_on(self.commit_batch())?;
+        Ok(())
+    }
+
+    fn supports_batching(&self) -> bool {
+        true
+    }
+}
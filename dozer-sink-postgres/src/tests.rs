@@ -0,0 +1,324 @@
+use std::sync::Arc;
+
+use dozer_core::node::Sink;
+use dozer_core::tokio::runtime::Runtime;
+use dozer_types::models::connection::PostgresConfig;
+use dozer_types::models::sink::PostgresSinkConfig;
+use dozer_types::node::OpIdentifier;
+use dozer_types::types::{
+    Field, FieldDefinition, FieldType, Operation, Record, Schema, SourceDefinition, TableOperation,
+};
+
+use crate::ddl::{self, TableName};
+use crate::errors::PostgresSinkError;
+use crate::sink::{connect, key_index, PostgresSink};
+
+fn get_schema() -> Schema {
+    Schema {
+        fields: vec![
+            FieldDefinition::new(
+                "id".to_string(),
+                FieldType::Int,
+                false,
+                SourceDefinition::Dynamic,
+            ),
+            FieldDefinition::new(
+                "name".to_string(),
+                FieldType::String,
+                true,
+                SourceDefinition::Dynamic,
+            ),
+        ],
+        primary_index: vec![0],
+    }
+}
+
+fn get_sink_config(unique_key: Vec<String>) -> PostgresSinkConfig {
+    PostgresSinkConfig {
+        connection: "postgres".to_string(),
+        table_name: "sink_table".to_string(),
+        unique_key,
+        schema: None,
+    }
+}
+
+#[test]
+fn test_key_index() {
+    let schema = get_schema();
+    assert_eq!(
+        key_index(&get_sink_config(vec![]), &schema).unwrap(),
+        vec![0]
+    );
+    assert_eq!(
+        key_index(&get_sink_config(vec!["name".to_string()]), &schema).unwrap(),
+        vec![1]
+    );
+    assert!(matches!(
+        key_index(&get_sink_config(vec!["missing".to_string()]), &schema),
+        Err(PostgresSinkError::ColumnNotFound { .. })
+    ));
+
+    let mut schema = schema;
+    schema.primary_index.clear();
+    assert!(key_index(&get_sink_config(vec![]), &schema)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_ddl() {
+    let table = TableName::new("public", "sink_table");
+    let schema = get_schema();
+    assert_eq!(
+        ddl::create_table(&table, &schema, &[0]),
+        r#"CREATE TABLE "public"."sink_table" ("id" BIGINT NOT NULL, "name" TEXT, PRIMARY KEY ("id"))"#
+    );
+    assert_eq!(
+        ddl::insert(&table, &schema, &[0]),
+        r#"INSERT INTO "public"."sink_table" ("id", "name") VALUES ($1, $2) ON CONFLICT ("id") DO UPDATE SET "name" = EXCLUDED."name""#
+    );
+    assert_eq!(
+        ddl::insert(&table, &schema, &[0, 1]),
+        r#"INSERT INTO "public"."sink_table" ("id", "name") VALUES ($1, $2) ON CONFLICT ("id", "name") DO NOTHING"#
+    );
+    assert_eq!(
+        ddl::insert(&table, &schema, &[]),
+        r#"INSERT INTO "public"."sink_table" ("id", "name") VALUES ($1, $2)"#
+    );
+    assert_eq!(
+        ddl::delete(&table, &schema, &[1, 0]),
+        r#"DELETE FROM "public"."sink_table" WHERE "name" = $1 AND "id" = $2"#
+    );
+    assert_eq!(
+        ddl::add_column(&table, &schema.fields[1]),
+        r#"ALTER TABLE "public"."sink_table" ADD COLUMN IF NOT EXISTS "name" TEXT"#
+    );
+    let mut widened = schema.fields[0].clone();
+    widened.typ = FieldType::I128;
+    assert_eq!(
+        ddl::alter_column(&table, &schema.fields[0], &widened).unwrap(),
+        r#"ALTER TABLE "public"."sink_table" ALTER COLUMN "id" TYPE NUMERIC(39, 0)"#
+    );
+    widened.nullable = true;
+    assert_eq!(
+        ddl::alter_column(&table, &schema.fields[0], &widened).unwrap(),
+        r#"ALTER TABLE "public"."sink_table" ALTER COLUMN "id" TYPE NUMERIC(39, 0), ALTER COLUMN "id" DROP NOT NULL"#
+    );
+    let mut text = schema.fields[1].clone();
+    text.typ = FieldType::Text;
+    assert_eq!(ddl::alter_column(&table, &schema.fields[1], &text), None);
+    assert_eq!(ddl::truncate(&table), r#"TRUNCATE "public"."sink_table""#);
+    assert_eq!(
+        ddl::copy(&table, &schema),
+        r#"COPY "public"."sink_table" ("id", "name") FROM STDIN BINARY"#
+    );
+    assert_eq!(ddl::quote(r#"a"b"#), r#""a""b""#);
+}
+
+fn get_connection_config() -> PostgresConfig {
+    PostgresConfig {
+        user: Some("postgres".to_string()),
+        password: Some("postgres".to_string()),
+        host: Some("localhost".to_string()),
+        port: Some(5432),
+        database: Some("postgres".to_string()),
+        sslmode: Some("disable".to_string()),
+        ..Default::default()
+    }
+}
+
+fn record(id: i64, name: &str) -> Record {
+    Record::new(vec![Field::Int(id), Field::String(name.to_string())])
+}
+
+fn op(op: Operation, txid: u64) -> TableOperation {
+    TableOperation {
+        id: Some(OpIdentifier::new(txid, 0)),
+        op,
+        port: 0,
+    }
+}
+
+#[test]
+#[ignore]
+fn test_upsert_and_resume() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let config = get_connection_config();
+    let table = TableName::new("public", "sink_table");
+    let schema = get_schema();
+    let new_sink = || {
+        runtime.block_on(async {
+            let client = connect(&config, &runtime).await.unwrap();
+            PostgresSink::new(
+                client,
+                runtime.clone(),
+                table.clone(),
+                schema.clone(),
+                vec![0],
+            )
+            .await
+            .unwrap()
+        })
+    };
+
+    runtime.block_on(async {
+        let client = connect(&config, &runtime).await.unwrap();
+        client
+            .batch_execute(&format!(
+                "DROP TABLE IF EXISTS {table}; DROP TABLE IF EXISTS {}; {}; {}",
+                TableName::new("public", ddl::METADATA_TABLE),
+                ddl::create_table(&table, &schema, &[0]),
+                ddl::create_metadata_table("public"),
+            ))
+            .await
+            .unwrap();
+    });
+
+    let mut sink = new_sink();
+    sink.process(op(
+        Operation::BatchInsert {
+            new: vec![record(1, "a"), record(2, "b")],
+        },
+        1,
+    ))
+    .unwrap();
+    sink.flush_batch().unwrap();
+    // Replaying an insert updates the existing row.
+    sink.process(op(
+        Operation::Insert {
+            new: record(1, "c"),
+        },
+        2,
+    ))
+    .unwrap();
+    sink.process(op(
+        Operation::Delete {
+            old: record(2, "b"),
+        },
+        3,
+    ))
+    .unwrap();
+    sink.set_source_state(b"state").unwrap();
+    sink.flush_batch().unwrap();
+
+    let rows = runtime.block_on(async {
+        let client = connect(&config, &runtime).await.unwrap();
+        client
+            .query(&format!(r#"SELECT "id", "name" FROM {table}"#), &[])
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| (row.get::<_, i64>(0), row.get::<_, String>(1)))
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(rows, vec![(1, "c".to_string())]);
+
+    let mut sink = new_sink();
+    assert_eq!(
+        sink.get_latest_op_id().unwrap(),
+        Some(OpIdentifier::new(3, 0))
+    );
+    assert_eq!(sink.get_source_state().unwrap(), Some(b"state".to_vec()));
+}
+
+#[test]
+#[ignore]
+fn test_schema_change() {
+    let runtime = Arc::new(Runtime::new().unwrap());
+    let config = get_connection_config();
+    let table = TableName::new("public", "sink_schema_change");
+    let mut schema = get_schema();
+    schema
+        .field(
+            FieldDefinition::new(
+                "count".to_string(),
+                FieldType::Int8,
+                false,
+                SourceDefinition::Dynamic,
+            ),
+            false,
+        )
+        .field(
+            FieldDefinition::new(
+                "flag".to_string(),
+                FieldType::Boolean,
+                false,
+                SourceDefinition::Dynamic,
+            ),
+            false,
+        );
+
+    let mut sink = runtime.block_on(async {
+        let client = connect(&config, &runtime).await.unwrap();
+        client
+            .batch_execute(&format!(
+                "DROP TABLE IF EXISTS {table}; {}; {}",
+                ddl::create_table(&table, &schema, &[0]),
+                ddl::create_metadata_table("public"),
+            ))
+            .await
+            .unwrap();
+        PostgresSink::new(
+            client,
+            runtime.clone(),
+            table.clone(),
+            schema.clone(),
+            vec![0],
+        )
+        .await
+        .unwrap()
+    });
+
+    // The pending row is written with the widened type.
+    sink.process(op(
+        Operation::Insert {
+            new: Record::new(vec![
+                Field::Int(1),
+                Field::String("a".to_string()),
+                Field::Int8(1),
+                Field::Boolean(true),
+            ]),
+        },
+        1,
+    ))
+    .unwrap();
+    // `count` is widened, and `flag` is dropped from the source, so it becomes nullable.
+    let mut evolved = schema.clone();
+    evolved.fields[2].typ = FieldType::Int;
+    evolved.fields[3].nullable = true;
+    sink.on_schema_change(0, &evolved).unwrap();
+    sink.process(op(
+        Operation::Insert {
+            new: Record::new(vec![
+                Field::Int(2),
+                Field::String("b".to_string()),
+                Field::Int(300),
+                Field::Null,
+            ]),
+        },
+        2,
+    ))
+    .unwrap();
+    sink.flush_batch().unwrap();
+
+    let rows = runtime.block_on(async {
+        let client = connect(&config, &runtime).await.unwrap();
+        client
+            .query(
+                &format!(r#"SELECT "id", "count", "flag" FROM {table} ORDER BY "id""#),
+                &[],
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| {
+                (
+                    row.get::<_, i64>(0),
+                    row.get::<_, i64>(1),
+                    row.get::<_, Option<bool>>(2),
+                )
+            })
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(rows, vec![(1, 1, Some(true)), (2, 300, None)]);
+}
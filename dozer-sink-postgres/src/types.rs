@@ -0,0 +1,115 @@
+use std::error::Error;
+use std::fmt::Display;
+
+use dozer_types::bytes::{BufMut, BytesMut};
+use dozer_types::geo::Point;
+use dozer_types::rust_decimal::Decimal;
+use dozer_types::serde_json;
+use dozer_types::types::{DozerDuration, DozerPoint, Field, FieldType};
+use tokio_postgres::types::{to_sql_checked, IsNull, ToSql, Type};
+
+/// Returns the Postgres column type used to store a dozer field type.
+pub fn column_type(typ: FieldType) -> &'static str {
+    match typ {
+        FieldType::UInt => "NUMERIC(20, 0)",
+        FieldType::U128 => "NUMERIC(39, 0)",
+        FieldType::Int => "BIGINT",
+        FieldType::Int8 => "SMALLINT",
+        FieldType::I128 => "NUMERIC(39, 0)",
+        FieldType::Float => "DOUBLE PRECISION",
+        FieldType::Boolean => "BOOLEAN",
+        FieldType::String => "TEXT",
+        FieldType::Text => "TEXT",
+        FieldType::Binary => "BYTEA",
+        FieldType::Decimal => "NUMERIC",
+        FieldType::Timestamp => "TIMESTAMPTZ",
+        FieldType::Date => "DATE",
+        FieldType::Json => "JSONB",
+        FieldType::Point => "POINT",
+        FieldType::Duration => "INTERVAL",
+    }
+}
+
+/// The wire type of [`column_type`], used to encode `COPY` rows.
+pub fn pg_type(typ: FieldType) -> Type {
+    match typ {
+        FieldType::UInt | FieldType::U128 | FieldType::I128 | FieldType::Decimal => Type::NUMERIC,
+        FieldType::Int => Type::INT8,
+        FieldType::Int8 => Type::INT2,
+        FieldType::Float => Type::FLOAT8,
+        FieldType::Boolean => Type::BOOL,
+        FieldType::String | FieldType::Text => Type::TEXT,
+        FieldType::Binary => Type::BYTEA,
+        FieldType::Timestamp => Type::TIMESTAMPTZ,
+        FieldType::Date => Type::DATE,
+        FieldType::Json => Type::JSONB,
+        FieldType::Point => Type::POINT,
+        FieldType::Duration => Type::INTERVAL,
+    }
+}
+
+/// Binds a field as a parameter of the column type of its field type.
+///
+/// Any parameter type is accepted, so that `Field::Null` can be bound to every column.
+#[derive(Debug)]
+pub struct PgField<'a>(pub &'a Field);
+
+fn numeric(
+    value: impl TryInto<i128> + Display + Copy,
+) -> Result<Decimal, Box<dyn Error + Sync + Send>> {
+    value
+        .try_into()
+        .ok()
+        .and_then(|value| Decimal::try_from_i128_with_scale(value, 0).ok())
+        .ok_or_else(|| format!("{value} does not fit in a Postgres NUMERIC").into())
+}
+
+impl ToSql for PgField<'_> {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        match self.0 {
+            Field::UInt(v) => Decimal::from(*v).to_sql(ty, out),
+            Field::U128(v) => numeric(*v)?.to_sql(ty, out),
+            Field::Int(v) => v.to_sql(ty, out),
+            Field::Int8(v) => (*v as i16).to_sql(ty, out),
+            Field::I128(v) => numeric(*v)?.to_sql(ty, out),
+            Field::Float(v) => v.0.to_sql(ty, out),
+            Field::Boolean(v) => v.to_sql(ty, out),
+            Field::String(v) | Field::Text(v) => v.as_str().to_sql(ty, out),
+            Field::Binary(v) => v.as_slice().to_sql(ty, out),
+            Field::Decimal(v) => v.to_sql(ty, out),
+            Field::Timestamp(v) => v.to_sql(ty, out),
+            Field::Date(v) => v.to_sql(ty, out),
+            Field::Json(v) => serde_json::to_value(v)?.to_sql(ty, out),
+            Field::Point(DozerPoint(p)) => Point::new(p.x().0, p.y().0).to_sql(ty, out),
+            Field::Duration(DozerDuration(d, _)) => {
+                // An interval is sent as microseconds, days and months.
+                out.put_i64(i64::try_from(d.as_micros())?);
+                out.put_i32(0);
+                out.put_i32(0);
+                Ok(IsNull::No)
+            }
+            Field::Null => Ok(IsNull::Yes),
+        }
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
+
+pub fn params(values: &[Field]) -> Vec<PgField<'_>> {
+    values.iter().map(PgField).collect()
+}
+
+pub fn param_refs<'a>(params: &'a [PgField<'a>]) -> Vec<&'a (dyn ToSql + Sync)> {
+    params
+        .iter()
+        .map(|param| param as &(dyn ToSql + Sync))
+        .collect()
+}
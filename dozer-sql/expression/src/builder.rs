@@ -193,6 +193,22 @@ impl ExpressionBuilder {
                 self.parse_sql_isnull_operator(parse_aggregations, &true, expr, schema, udfs)
                     .await
             }
+            SqlExpr::Between {
+                expr,
+                negated,
+                low,
+                high,
+            } => {
+                self.parse_sql_between_operator(
+                    parse_aggregations,
+                    expr,
+                    *negated,
+                    (low, high),
+                    schema,
+                    udfs,
+                )
+                .await
+            }
             _ => Err(Error::UnsupportedExpression(expression.clone())),
         }
     }
@@ -1023,6 +1039,50 @@ impl ExpressionBuilder {
         Ok(in_list_expression)
     }
 
+    /// `expr BETWEEN low AND high` is `expr >= low AND expr <= high`.
+    async fn parse_sql_between_operator(
+        &mut self,
+        parse_aggregations: bool,
+        expr: &Expr,
+        negated: bool,
+        (low, high): (&Expr, &Expr),
+        schema: &Schema,
+        udfs: &[UdfConfig],
+    ) -> Result<Expression, Error> {
+        let expr = self
+            .parse_sql_expression(parse_aggregations, expr, schema, udfs)
+            .await?;
+        let low = self
+            .parse_sql_expression(parse_aggregations, low, schema, udfs)
+            .await?;
+        let high = self
+            .parse_sql_expression(parse_aggregations, high, schema, udfs)
+            .await?;
+
+        let between_expression = Expression::BinaryOperator {
+            left: Box::new(Expression::BinaryOperator {
+                left: Box::new(expr.clone()),
+                operator: BinaryOperatorType::Gte,
+                right: Box::new(low),
+            }),
+            operator: BinaryOperatorType::And,
+            right: Box::new(Expression::BinaryOperator {
+                left: Box::new(expr),
+                operator: BinaryOperatorType::Lte,
+                right: Box::new(high),
+            }),
+        };
+
+        if negated {
+            Ok(Expression::UnaryOperator {
+                operator: UnaryOperatorType::Not,
+                arg: Box::new(between_expression),
+            })
+        } else {
+            Ok(between_expression)
+        }
+    }
+
     async fn parse_sql_isnull_operator(
         &mut self,
         parse_aggregations: bool,
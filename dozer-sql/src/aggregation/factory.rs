@@ -122,6 +122,9 @@ impl ProcessorFactory for AggregationProcessorFactory {
             Box::new(ProjectionProcessor::new(
                 input_schema.clone(),
                 planner.projection_output,
+                self.projection.clone(),
+                self.udfs.clone(),
+                self.runtime.clone(),
             )?)
         } else {
             Box::new(AggregationProcessor::new(
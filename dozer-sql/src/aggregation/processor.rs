@@ -3,8 +3,11 @@
 use crate::aggregation::aggregator::Aggregator;
 use crate::errors::PipelineError;
 use crate::utils::record_hashtable_key::{get_record_hash, RecordKey};
+use crate::utils::spill::SpillTracker;
 use dozer_core::channels::ProcessorChannelForwarder;
-use dozer_core::node::Processor;
+use dozer_core::errors::SchemaChangeError;
+use dozer_core::node::{PortHandle, Processor};
+use dozer_core::state_store::{encoded_size, StateStore};
 use dozer_core::DEFAULT_PORT_HANDLE;
 use dozer_sql_expression::execution::Expression;
 use dozer_types::bincode;
@@ -19,6 +22,7 @@ use crate::aggregation::aggregator::{
 use dozer_core::epoch::Epoch;
 
 const DEFAULT_SEGMENT_KEY: &str = "DOZER_DEFAULT_SEGMENT_KEY";
+const STATES_NAMESPACE: &str = "states";
 
 #[derive(Debug, bincode::Encode, bincode::Decode)]
 struct AggregationState {
@@ -59,6 +63,9 @@ pub struct AggregationProcessor {
     default_segment_key: RecordKey,
     having_eval_schema: Schema,
     accurate_keys: bool,
+    state_store: Option<StateStore>,
+    /// Tracks the segment states in memory, if the store has a memory budget.
+    spill_tracker: Option<SpillTracker<RecordKey>>,
 }
 
 enum AggregatorOperation {
@@ -119,9 +126,59 @@ impl AggregationProcessor {
                 primary_index: vec![],
             },
             accurate_keys,
+            state_store: None,
+            spill_tracker: None,
         })
     }
 
+    /// Writes the state of segment `key` through to the state store, if there's one.
+    fn write_through(&mut self, key: &RecordKey) -> Result<(), PipelineError> {
+        let Some(store) = &self.state_store else {
+            return Ok(());
+        };
+        match self.states.get(key) {
+            Some(state) => {
+                store.put(STATES_NAMESPACE, key, state)?;
+                if let Some(spill_tracker) = &mut self.spill_tracker {
+                    spill_tracker.set(key, encoded_size(state)?);
+                }
+            }
+            None => {
+                store.delete(STATES_NAMESPACE, key)?;
+                if let Some(spill_tracker) = &mut self.spill_tracker {
+                    spill_tracker.remove(key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the state of segment `key` back from the state store, if it has been spilled.
+    fn load(&mut self, key: &RecordKey) -> Result<(), PipelineError> {
+        let (Some(spill_tracker), Some(store)) = (&mut self.spill_tracker, &self.state_store)
+        else {
+            return Ok(());
+        };
+        if self.states.contains_key(key) {
+            spill_tracker.touch(key);
+        } else if spill_tracker.has_spilled() {
+            if let Some(state) = store.get::<AggregationState>(STATES_NAMESPACE, key)? {
+                spill_tracker.set(key, encoded_size(&state)?);
+                self.states.insert(key.clone(), state);
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops the least recently used segment states from memory until they're within the memory budget.
+    fn spill(&mut self) {
+        if let Some(spill_tracker) = &mut self.spill_tracker {
+            while let Some(key) = spill_tracker.pop_over_budget() {
+                self.states.remove(&key);
+            }
+        }
+    }
+
     fn calc_and_fill_measures(
         curr_state: &mut AggregationState,
         deleted_record: Option<&Record>,
@@ -185,13 +242,13 @@ impl AggregationProcessor {
         let mut out_rec_insert: Vec<Field> = Vec::with_capacity(self.measures.len());
 
         let key = if !self.dimensions.is_empty() {
-            Some(self.get_key(old)?)
+            self.get_key(old)?
         } else {
-            None
+            self.default_segment_key.clone()
         };
-        let key = key.as_ref().unwrap_or(&self.default_segment_key);
+        self.load(&key)?;
 
-        let curr_state_opt = self.states.get_mut(key);
+        let curr_state_opt = self.states.get_mut(&key);
         assert!(
             curr_state_opt.is_some(),
             "Unable to find aggregator state during DELETE operation"
@@ -229,7 +286,7 @@ impl AggregationProcessor {
             };
 
         let res = if curr_state.count == 1 {
-            self.states.remove(key);
+            self.states.remove(&key);
             if out_rec_delete_having_satisfied {
                 vec![Operation::Delete {
                     old: Self::build_projection(
@@ -256,6 +313,7 @@ impl AggregationProcessor {
                 &self.aggregation_schema,
             )?
         };
+        self.write_through(&key)?;
 
         Ok(res)
     }
@@ -269,11 +327,15 @@ impl AggregationProcessor {
         } else {
             self.default_segment_key.clone()
         };
+        self.load(&key)?;
 
-        let curr_state = self.states.entry(key).or_insert(AggregationState::new(
-            &self.measures_types,
-            &self.measures_return_types,
-        ));
+        let curr_state = self
+            .states
+            .entry(key.clone())
+            .or_insert(AggregationState::new(
+                &self.measures_types,
+                &self.measures_return_types,
+            ));
 
         let new_values = Self::calc_and_fill_measures(
             curr_state,
@@ -332,6 +394,7 @@ impl AggregationProcessor {
 
         curr_state.count += 1;
         curr_state.values = Some(new_values);
+        self.write_through(&key)?;
 
         Ok(res)
     }
@@ -420,6 +483,7 @@ impl AggregationProcessor {
         let mut out_rec_delete: Vec<Field> = Vec::with_capacity(self.measures.len());
         let mut out_rec_insert: Vec<Field> = Vec::with_capacity(self.measures.len());
 
+        self.load(&key)?;
         let curr_state_opt = self.states.get_mut(&key);
         assert!(
             curr_state_opt.is_some(),
@@ -495,6 +559,7 @@ impl AggregationProcessor {
         };
 
         curr_state.values = Some(new_values);
+        self.write_through(&key)?;
         Ok(res)
     }
 
@@ -519,9 +584,9 @@ impl AggregationProcessor {
     }
 
     pub fn aggregate(&mut self, mut op: Operation) -> Result<Vec<Operation>, PipelineError> {
-        match op {
-            Operation::Insert { ref mut new } => Ok(self.agg_insert(new)?),
-            Operation::Delete { ref mut old } => Ok(self.agg_delete(old)?),
+        let ops = match op {
+            Operation::Insert { ref mut new } => self.agg_insert(new)?,
+            Operation::Delete { ref mut old } => self.agg_delete(old)?,
             Operation::Update {
                 ref mut old,
                 ref mut new,
@@ -536,12 +601,12 @@ impl AggregationProcessor {
                 };
 
                 if old_record_hash == new_record_hash {
-                    Ok(self.agg_update(old, new, old_record_hash)?)
+                    self.agg_update(old, new, old_record_hash)?
                 } else {
                     let mut r = Vec::with_capacity(2);
                     r.extend(self.agg_delete(old)?);
                     r.extend(self.agg_insert(new)?);
-                    Ok(r)
+                    r
                 }
             }
             Operation::BatchInsert { new } => {
@@ -549,9 +614,11 @@ impl AggregationProcessor {
                 for record in new {
                     result.extend(self.aggregate(Operation::Insert { new: record })?);
                 }
-                Ok(result)
+                result
             }
-        }
+        };
+        self.spill();
+        Ok(ops)
     }
 
     fn get_key(&mut self, record: &Record) -> Result<RecordKey, PipelineError> {
@@ -577,10 +644,83 @@ impl Processor for AggregationProcessor {
         op: TableOperation,
         fw: &mut dyn ProcessorChannelForwarder,
     ) -> Result<(), BoxedError> {
-        let ops = self.aggregate(op.op)?;
+        let mut op = op.op;
+        // Fields appended to the input aren't aggregated, and they'd shift the measures
+        // evaluated after the input fields.
+        let num_fields = self.input_schema.fields.len();
+        match &mut op {
+            Operation::Insert { new } => new.values.truncate(num_fields),
+            Operation::Delete { old } => old.values.truncate(num_fields),
+            Operation::Update { old, new } => {
+                old.values.truncate(num_fields);
+                new.values.truncate(num_fields);
+            }
+            Operation::BatchInsert { new } => {
+                for record in new {
+                    record.values.truncate(num_fields);
+                }
+            }
+        }
+        let ops = self.aggregate(op)?;
         for output_op in ops {
             fw.send(TableOperation::without_id(output_op, DEFAULT_PORT_HANDLE));
         }
         Ok(())
     }
+
+    fn on_schema_change(
+        &mut self,
+        _port: PortHandle,
+        schema: &Schema,
+    ) -> Result<Vec<(PortHandle, Schema)>, BoxedError> {
+        // The aggregation keeps evaluating the fields it was planned with, which keep their index.
+        // Only nullability may have been widened, which the aggregators already handle.
+        let num_fields = self.input_schema.fields.len();
+        if schema.fields.len() < num_fields
+            || self
+                .input_schema
+                .fields
+                .iter()
+                .zip(&schema.fields)
+                .any(|(current, new)| {
+                    current.name != new.name
+                        || current.source != new.source
+                        || current.typ != new.typ
+                })
+        {
+            return Err(SchemaChangeError::Unsupported.into());
+        }
+        // Appended fields are neither grouped nor projected, so the output schema doesn't change.
+        Ok(vec![])
+    }
+
+    fn on_truncate(&mut self, _port: PortHandle) -> Result<Vec<PortHandle>, BoxedError> {
+        // Every output record is derived from the input, so all of them are gone too.
+        self.states.clear();
+        if let Some(store) = &self.state_store {
+            store.delete_namespace(STATES_NAMESPACE)?;
+        }
+        if let Some(spill_tracker) = &mut self.spill_tracker {
+            spill_tracker.clear();
+        }
+        Ok(vec![DEFAULT_PORT_HANDLE])
+    }
+
+    fn is_stateful(&self) -> bool {
+        true
+    }
+
+    fn restore(&mut self, store: StateStore) -> Result<(), BoxedError> {
+        // With a memory budget, segment states are read back as they're used.
+        match store.memory_budget() {
+            Some(memory_budget) => {
+                let mut spill_tracker = SpillTracker::new(memory_budget);
+                spill_tracker.mark_spilled();
+                self.spill_tracker = Some(spill_tracker);
+            }
+            None => self.states = store.entries(STATES_NAMESPACE)?.into_iter().collect(),
+        }
+        self.state_store = Some(store);
+        Ok(())
+    }
 }
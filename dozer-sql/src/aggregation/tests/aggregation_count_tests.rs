@@ -5,6 +5,8 @@ use crate::aggregation::tests::aggregation_tests_utils::{
     FIELD_3_INT, FIELD_50_FLOAT, FIELD_50_INT, FIELD_NULL, ITALY, SINGAPORE,
 };
 use crate::output;
+use dozer_core::node::Processor;
+use dozer_core::state_store::StateStore;
 use dozer_core::DEFAULT_PORT_HANDLE;
 use dozer_types::types::FieldType::{Date, Decimal, Duration, Float, Int, Timestamp};
 use dozer_types::types::{Operation, Record};
@@ -38,6 +40,50 @@ fn test_count_star() {
     assert_eq!(out, exp);
 }
 
+#[test]
+fn test_count_after_truncate() {
+    let schema = init_input_schema(Float, "COUNT");
+    let mut processor = init_processor(
+        "SELECT Country, COUNT(Salary) FROM Users GROUP BY Country",
+        HashMap::from([(DEFAULT_PORT_HANDLE, schema)]),
+    )
+    .unwrap();
+
+    output!(processor, insert_field(ITALY, FIELD_100_FLOAT));
+    output!(processor, insert_field(ITALY, FIELD_100_FLOAT));
+    assert_eq!(
+        processor.on_truncate(DEFAULT_PORT_HANDLE).unwrap(),
+        vec![DEFAULT_PORT_HANDLE]
+    );
+
+    // The segment starts over.
+    let out = output!(processor, insert_field(ITALY, FIELD_100_FLOAT));
+    assert_eq!(out, vec![insert_exp(ITALY, FIELD_1_INT)]);
+}
+
+#[test]
+fn test_count_with_spilled_segments() {
+    let schema = init_input_schema(Float, "COUNT");
+    let mut processor = init_processor(
+        "SELECT Country, COUNT(Salary) FROM Users GROUP BY Country",
+        HashMap::from([(DEFAULT_PORT_HANDLE, schema)]),
+    )
+    .unwrap();
+    // Every segment is spilled after each operation.
+    let store = StateStore::temporary().unwrap().with_memory_budget(Some(1));
+    processor.restore(store).unwrap();
+
+    output!(processor, insert_field(ITALY, FIELD_100_FLOAT));
+    output!(processor, insert_field(SINGAPORE, FIELD_100_FLOAT));
+    let out = output!(processor, insert_field(ITALY, FIELD_100_FLOAT));
+    assert_eq!(
+        out,
+        vec![update_exp(ITALY, ITALY, FIELD_1_INT, FIELD_2_INT)]
+    );
+    let out = output!(processor, delete_field(SINGAPORE, FIELD_100_FLOAT));
+    assert_eq!(out, vec![delete_exp(SINGAPORE, FIELD_1_INT)]);
+}
+
 #[test]
 fn test_count_aggregation_float() {
     let schema = init_input_schema(Float, "COUNT");
@@ -6,7 +6,7 @@ use dozer_core::app::AppPipeline;
 use dozer_core::node::PortHandle;
 use dozer_core::DEFAULT_PORT_HANDLE;
 use dozer_sql_expression::builder::{ExpressionBuilder, NameOrAlias};
-use dozer_sql_expression::sqlparser::ast::{SetOperator, SetQuantifier, TableFactor};
+use dozer_sql_expression::sqlparser::ast::TableFactor;
 use dozer_types::models::udf_config::UdfConfig;
 
 use dozer_sql_expression::sqlparser::{
@@ -21,6 +21,7 @@ use tokio::runtime::Runtime;
 
 use super::errors::UnsupportedSqlError;
 
+use super::product::set::operator::SetOperation;
 use super::product::set::set_factory::SetProcessorFactory;
 
 #[derive(Debug, Clone)]
@@ -210,21 +211,21 @@ fn query_to_pipeline(
             set_quantifier,
             left,
             right,
-        } => match op {
-            SetOperator::Union => {
-                set_to_pipeline(
-                    table_info,
-                    left,
-                    right,
-                    set_quantifier,
-                    pipeline,
-                    query_ctx,
-                    pipeline_idx,
-                    is_top_select,
-                )?;
-            }
-            _ => return Err(PipelineError::InvalidOperator(op.to_string())),
-        },
+        } => {
+            set_to_pipeline(
+                table_info,
+                left,
+                right,
+                SetOperation {
+                    op,
+                    quantifier: set_quantifier,
+                },
+                pipeline,
+                query_ctx,
+                pipeline_idx,
+                is_top_select,
+            )?;
+        }
         _ => {
             return Err(PipelineError::UnsupportedSqlError(
                 UnsupportedSqlError::GenericError("Unsupported query body structure".to_string()),
@@ -365,7 +366,7 @@ fn set_to_pipeline(
     table_info: TableInfo,
     left_select: Box<SetExpr>,
     right_select: Box<SetExpr>,
-    set_quantifier: SetQuantifier,
+    set_operation: SetOperation,
     pipeline: &mut AppPipeline,
     query_ctx: &mut QueryContext,
     pipeline_idx: usize,
@@ -392,7 +393,7 @@ fn set_to_pipeline(
             is_top_select,
         )?,
         SetExpr::SetOperation {
-            op: _,
+            op,
             set_quantifier,
             left,
             right,
@@ -400,7 +401,10 @@ fn set_to_pipeline(
             left_table_info,
             left,
             right,
-            set_quantifier,
+            SetOperation {
+                op,
+                quantifier: set_quantifier,
+            },
             pipeline,
             query_ctx,
             pipeline_idx,
@@ -423,7 +427,7 @@ fn set_to_pipeline(
             is_top_select,
         )?,
         SetExpr::SetOperation {
-            op: _,
+            op,
             set_quantifier,
             left,
             right,
@@ -431,7 +435,10 @@ fn set_to_pipeline(
             right_table_info,
             left,
             right,
-            set_quantifier,
+            SetOperation {
+                op,
+                quantifier: set_quantifier,
+            },
             pipeline,
             query_ctx,
             pipeline_idx,
@@ -462,7 +469,7 @@ fn set_to_pipeline(
 
     let set_proc_fac = SetProcessorFactory::new(
         gen_set_name.clone(),
-        set_quantifier,
+        set_operation,
         pipeline
             .flags()
             .enable_probabilistic_optimizations
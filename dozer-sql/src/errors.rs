@@ -1,6 +1,7 @@
 #![allow(clippy::enum_variant_names)]
 
 use dozer_core::node::PortHandle;
+use dozer_core::state_store::StateStoreError;
 use dozer_types::chrono::RoundingError;
 use dozer_types::errors::internal::BoxedError;
 use dozer_types::errors::types::{DeserializationError, TypeError};
@@ -93,25 +94,18 @@ pub enum PipelineError {
     #[error("Invalid JOIN: {0}")]
     InvalidJoin(String),
 
-    #[error("The JOIN clause is not supported. In this version only INNER, LEFT and RIGHT OUTER JOINs are supported")]
+    #[error("The JOIN clause is not supported. In this version only INNER, LEFT OUTER, RIGHT OUTER and FULL OUTER JOINs are supported")]
     UnsupportedJoinType,
 
-    #[error(
-        "Unsupported JOIN constraint, only ON is allowed as the JOIN constraint using \'=\' and \'AND\' operators"
-    )]
+    #[error("Unsupported JOIN constraint, only ON is allowed as the JOIN constraint")]
     UnsupportedJoinConstraintType,
 
-    #[error("Unsupported JOIN constraint {0} only comparison of fields with \'=\' and \'AND\' operators are allowed in the JOIN ON constraint")]
+    #[error("Unsupported JOIN constraint {0}, the fields compared in the JOIN ON constraint must each belong to one of the joined tables")]
     UnsupportedJoinConstraint(String),
 
     #[error("Invalid JOIN constraint on: {0}")]
     InvalidJoinConstraint(String),
 
-    #[error(
-        "Unsupported JOIN constraint operator {0}, only \'=\' and \'AND\' operators are allowed in the JOIN ON constraint"
-    )]
-    UnsupportedJoinConstraintOperator(String),
-
     #[error("Currently JOIN supports two level of namespacing. For example, `source.field_name` is valid, but `connection.source.field_name` is not.")]
     NameSpaceTooLong(String),
 
@@ -123,6 +117,9 @@ pub enum PipelineError {
 
     #[error("Duplicated Processor name: {0}")]
     ProcessorAlreadyExists(String),
+
+    #[error("State store: {0}")]
+    StateStore(#[from] StateStoreError),
 }
 
 #[derive(Error, Debug)]
@@ -159,6 +156,8 @@ pub enum SetError {
     HistoryUnavailable(u16),
     #[error("Deserialization error: {0}")]
     Deserialization(#[from] DeserializationError),
+    #[error("{0} can't truncate one input, as it doesn't keep the records of the other input to send them again")]
+    TruncateUnsupported(String),
 }
 
 #[derive(Error, Debug)]
@@ -171,15 +170,9 @@ pub enum JoinError {
     AmbiguousField(String),
     #[error("Invalid Field specified in join : {0}")]
     InvalidFieldSpecified(String),
-    #[error("Unsupported Join constraint {0} only comparison of fields with \'=\' and \'AND\' operators are allowed in the JOIN ON constraint")]
+    #[error("Unsupported Join constraint {0}, the fields compared in the JOIN ON constraint must each belong to one of the joined tables")]
     UnsupportedJoinConstraint(String),
-    #[error(
-        "Unsupported Join constraint operator {0}, only \'=\' and \'AND\' operators are allowed in the JOIN ON constraint"
-    )]
-    UnsupportedJoinConstraintOperator(String),
-    #[error(
-        "Unsupported Join constraint, only ON is allowed as the JOIN constraint using \'=\' and \'AND\' operators"
-    )]
+    #[error("Unsupported Join constraint, only ON is allowed as the JOIN constraint")]
     UnsupportedJoinConstraintType,
     #[error("Unsupported Join type")]
     UnsupportedJoinType,
@@ -192,6 +185,12 @@ pub enum JoinError {
 
     #[error("Deserialization error: {0}")]
     Deserialization(#[from] DeserializationError),
+
+    #[error("State store error: {0}")]
+    StateStore(#[from] StateStoreError),
+
+    #[error("Expression error in the JOIN ON constraint: {0}")]
+    Expression(#[from] dozer_sql_expression::error::Error),
 }
 
 #[derive(Error, Debug)]
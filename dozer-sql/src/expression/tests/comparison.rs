@@ -173,3 +173,40 @@ fn test_comparison_logical_date() {
     );
     assert_eq!(f, Field::Boolean(true));
 }
+
+#[test]
+fn test_comparison_between() {
+    let record = vec![Field::Int(124)];
+    let schema = Schema::default()
+        .field(
+            FieldDefinition::new(
+                String::from("id"),
+                FieldType::Int,
+                false,
+                SourceDefinition::Dynamic,
+            ),
+            false,
+        )
+        .clone();
+
+    let f = run_fct(
+        "SELECT id BETWEEN 100 AND 124 FROM users",
+        schema.clone(),
+        record.clone(),
+    );
+    assert_eq!(f, Field::Boolean(true));
+
+    let f = run_fct(
+        "SELECT id BETWEEN 125 AND 200 FROM users",
+        schema.clone(),
+        record.clone(),
+    );
+    assert_eq!(f, Field::Boolean(false));
+
+    let f = run_fct(
+        "SELECT id NOT BETWEEN 125 AND 200 FROM users",
+        schema,
+        record,
+    );
+    assert_eq!(f, Field::Boolean(true));
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use dozer_core::{
     event::EventHub,
@@ -14,17 +14,20 @@ use dozer_sql_expression::{
 };
 
 use dozer_types::{
+    chrono,
     errors::internal::BoxedError,
+    models::udf_config::UdfConfig,
     tonic::async_trait,
-    types::{FieldDefinition, Schema},
+    types::{Field, FieldDefinition, FieldType, Record, Schema},
 };
+use tokio::runtime::Runtime;
 
 use crate::errors::JoinError;
 use crate::errors::PipelineError;
 use dozer_sql_expression::builder::extend_schema_source_def;
 
 use super::{
-    operator::{JoinOperator, JoinType},
+    operator::{IntervalJoin, JoinBranch, JoinOperator, JoinPredicate, JoinType},
     processor::ProductProcessor,
 };
 
@@ -38,6 +41,8 @@ pub struct JoinProcessorFactory {
     right: Option<NameOrAlias>,
     join_operator: SqlJoinOperator,
     enable_probabilistic_optimizations: bool,
+    udfs: Vec<UdfConfig>,
+    runtime: Arc<Runtime>,
 }
 
 impl JoinProcessorFactory {
@@ -47,6 +52,8 @@ impl JoinProcessorFactory {
         right: Option<NameOrAlias>,
         join_operator: SqlJoinOperator,
         enable_probabilistic_optimizations: bool,
+        udfs: Vec<UdfConfig>,
+        runtime: Arc<Runtime>,
     ) -> Self {
         Self {
             id,
@@ -54,6 +61,8 @@ impl JoinProcessorFactory {
             right,
             join_operator,
             enable_probabilistic_optimizations,
+            udfs,
+            runtime,
         }
     }
 }
@@ -117,6 +126,7 @@ impl ProcessorFactory for JoinProcessorFactory {
             SqlJoinOperator::Inner(constraint) => (JoinType::Inner, constraint),
             SqlJoinOperator::LeftOuter(constraint) => (JoinType::LeftOuter, constraint),
             SqlJoinOperator::RightOuter(constraint) => (JoinType::RightOuter, constraint),
+            SqlJoinOperator::FullOuter(constraint) => (JoinType::FullOuter, constraint),
             _ => return Err(PipelineError::JoinError(JoinError::UnsupportedJoinType).into()),
         };
 
@@ -149,19 +159,48 @@ impl ProcessorFactory for JoinProcessorFactory {
             right_schema = extend_schema_source_def(&right_schema, right_table_name);
         }
 
-        let (left_join_key_indexes, right_join_key_indexes) =
+        let (left_join_key_indexes, right_join_key_indexes, conjuncts) =
             parse_join_constraint(expression, &left_schema, &right_schema)?;
 
+        let interval =
+            parse_interval_join(&conjuncts, &left_schema, &right_schema, &self.runtime).await?;
+
+        // The conjuncts that are not key equalities are evaluated on the joined records.
+        let residual = conjuncts
+            .into_iter()
+            .reduce(|left, right| SqlExpr::BinaryOp {
+                left: Box::new(left),
+                op: BinaryOperator::And,
+                right: Box::new(right),
+            });
+        let output_schema = append_schema(&left_schema, &right_schema);
+        let predicate = match residual {
+            Some(expression) => {
+                let expression =
+                    ExpressionBuilder::new(output_schema.fields.len(), self.runtime.clone())
+                        .build(false, &expression, &output_schema, &self.udfs)
+                        .await
+                        .map_err(JoinError::Expression)?;
+                Some(JoinPredicate::new(expression, output_schema.clone()))
+            }
+            None => None,
+        };
+
         let join_operator = JoinOperator::new(
             join_type,
             (left_join_key_indexes, right_join_key_indexes),
             (&left_schema, &right_schema),
+            predicate,
+            interval,
             self.enable_probabilistic_optimizations,
         )?;
 
         Ok(Box::new(ProductProcessor::new(
             self.id.clone(),
             join_operator,
+            (self.left.clone(), self.right.clone()),
+            (left_schema, right_schema),
+            output_schema,
         )))
     }
 }
@@ -190,76 +229,178 @@ fn append_schema(left_schema: &Schema, right_schema: &Schema) -> Schema {
     output_schema
 }
 
+/// Splits the ON constraint into the join keys, from the equalities of a left field and a right field,
+/// and the other conjuncts.
 fn parse_join_constraint(
-    expression: &dozer_sql_expression::sqlparser::ast::Expr,
+    expression: &SqlExpr,
     left_join_table: &Schema,
     right_join_table: &Schema,
-) -> Result<(Vec<usize>, Vec<usize>), JoinError> {
+) -> Result<(Vec<usize>, Vec<usize>, Vec<SqlExpr>), JoinError> {
     match expression {
         SqlExpr::BinaryOp {
-            ref left,
-            op,
-            ref right,
-        } => match op {
-            BinaryOperator::And => {
-                let (mut left_keys, mut right_keys) =
-                    parse_join_constraint(left, left_join_table, right_join_table)?;
-
-                let (mut left_keys_from_right, mut right_keys_from_right) =
-                    parse_join_constraint(right, left_join_table, right_join_table)?;
-                left_keys.append(&mut left_keys_from_right);
-                right_keys.append(&mut right_keys_from_right);
-
-                Ok((left_keys, right_keys))
-            }
-            BinaryOperator::Eq => {
-                let mut left_key_indexes = vec![];
-                let mut right_key_indexes = vec![];
-
-                let (left_arr, right_arr) =
-                    parse_join_eq_expression(left, left_join_table, right_join_table)?;
-                left_key_indexes.extend(left_arr);
-                right_key_indexes.extend(right_arr);
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            let (mut left_keys, mut right_keys, mut conjuncts) =
+                parse_join_constraint(left, left_join_table, right_join_table)?;
 
-                let (left_arr, right_arr) =
-                    parse_join_eq_expression(right, left_join_table, right_join_table)?;
-                left_key_indexes.extend(left_arr);
-                right_key_indexes.extend(right_arr);
+            let (mut left_keys_from_right, mut right_keys_from_right, mut conjuncts_from_right) =
+                parse_join_constraint(right, left_join_table, right_join_table)?;
+            left_keys.append(&mut left_keys_from_right);
+            right_keys.append(&mut right_keys_from_right);
+            conjuncts.append(&mut conjuncts_from_right);
 
-                Ok((left_key_indexes, right_key_indexes))
+            Ok((left_keys, right_keys, conjuncts))
+        }
+        SqlExpr::BinaryOp {
+            left,
+            op: BinaryOperator::Eq,
+            right,
+        } => match (
+            parse_join_eq_expression(left, left_join_table, right_join_table)?,
+            parse_join_eq_expression(right, left_join_table, right_join_table)?,
+        ) {
+            (Some((JoinBranch::Left, left_key)), Some((JoinBranch::Right, right_key)))
+            | (Some((JoinBranch::Right, right_key)), Some((JoinBranch::Left, left_key))) => {
+                Ok((vec![left_key], vec![right_key], vec![]))
             }
-            _ => Err(JoinError::UnsupportedJoinConstraintOperator(op.to_string())),
+            _ => Ok((vec![], vec![], vec![expression.clone()])),
         },
-        _ => Err(JoinError::UnsupportedJoinConstraint(expression.to_string())),
+        SqlExpr::Nested(expression) => {
+            parse_join_constraint(expression, left_join_table, right_join_table)
+        }
+        _ => Ok((vec![], vec![], vec![expression.clone()])),
     }
 }
 
+/// The side and index of the field, if `expr` is a field.
 fn parse_join_eq_expression(
     expr: &SqlExpr,
     left_join_table: &Schema,
     right_join_table: &Schema,
-) -> Result<(Vec<usize>, Vec<usize>), JoinError> {
-    let mut left_key_indexes = vec![];
-    let mut right_key_indexes = vec![];
-    let (left_keys, right_keys) = match expr.clone() {
-        SqlExpr::Identifier(ident) => parse_identifier(&[ident], left_join_table, right_join_table),
+) -> Result<Option<(JoinBranch, usize)>, JoinError> {
+    let (left_key, right_key) = match expr {
+        SqlExpr::Identifier(ident) => {
+            parse_identifier(&[ident.clone()], left_join_table, right_join_table)
+        }
         SqlExpr::CompoundIdentifier(ident) => {
-            parse_identifier(&ident, left_join_table, right_join_table)
+            parse_identifier(ident, left_join_table, right_join_table)
+        }
+        _ => return Ok(None),
+    }?;
+
+    match (left_key, right_key) {
+        (Some(left_key), None) => Ok(Some((JoinBranch::Left, left_key))),
+        (None, Some(right_key)) => Ok(Some((JoinBranch::Right, right_key))),
+        _ => Err(JoinError::UnsupportedJoinConstraint(expr.to_string())),
+    }
+}
+
+/// Finds a conjunct `a.time BETWEEN b.time [± INTERVAL] AND b.time [± INTERVAL]`, where `a` and `b`
+/// are the two sides of the join and the times are timestamps, which makes the join an interval join.
+async fn parse_interval_join(
+    conjuncts: &[SqlExpr],
+    left_join_table: &Schema,
+    right_join_table: &Schema,
+    runtime: &Arc<Runtime>,
+) -> Result<Option<IntervalJoin>, BoxedError> {
+    for conjunct in conjuncts {
+        let SqlExpr::Between {
+            expr,
+            negated: false,
+            low,
+            high,
+        } = conjunct
+        else {
+            continue;
+        };
+        let Some(time) = parse_time_field(expr, left_join_table, right_join_table) else {
+            continue;
+        };
+        let (Some((low_time, low_offset)), Some((high_time, high_offset))) = (
+            parse_time_offset(low, left_join_table, right_join_table, runtime).await?,
+            parse_time_offset(high, left_join_table, right_join_table, runtime).await?,
+        ) else {
+            continue;
+        };
+        if low_time != high_time || low_time.0 == time.0 || low_offset > high_offset {
+            continue;
         }
+
+        let interval = match time.0 {
+            JoinBranch::Left => IntervalJoin {
+                left_time_index: time.1,
+                right_time_index: low_time.1,
+                lower: low_offset,
+                upper: high_offset,
+            },
+            JoinBranch::Right => IntervalJoin {
+                left_time_index: low_time.1,
+                right_time_index: time.1,
+                lower: -high_offset,
+                upper: -low_offset,
+            },
+        };
+        return Ok(Some(interval));
+    }
+    Ok(None)
+}
+
+/// Parses `time`, `time + INTERVAL ...` or `time - INTERVAL ...` into the time field and the offset.
+async fn parse_time_offset(
+    expr: &SqlExpr,
+    left_join_table: &Schema,
+    right_join_table: &Schema,
+    runtime: &Arc<Runtime>,
+) -> Result<Option<((JoinBranch, usize), chrono::Duration)>, BoxedError> {
+    let mut expr = expr;
+    while let SqlExpr::Nested(nested) = expr {
+        expr = &**nested;
+    }
+    let (time, interval, op) = match expr {
+        SqlExpr::BinaryOp {
+            left,
+            op: op @ (BinaryOperator::Plus | BinaryOperator::Minus),
+            right,
+        } if matches!(**right, SqlExpr::Interval(_)) => (left, right, op),
         _ => {
-            return Err(JoinError::UnsupportedJoinConstraint(
-                expr.clone().to_string(),
-            ))
+            return Ok(parse_time_field(expr, left_join_table, right_join_table)
+                .map(|time| (time, chrono::Duration::zero())))
         }
-    }?;
+    };
+    let Some(time) = parse_time_field(time, left_join_table, right_join_table) else {
+        return Ok(None);
+    };
 
-    match (left_keys, right_keys) {
-        (Some(left_key), None) => left_key_indexes.push(left_key),
-        (None, Some(right_key)) => right_key_indexes.push(right_key),
-        _ => return Err(JoinError::UnsupportedJoinConstraint("".to_string())),
+    let schema = Schema::default();
+    let mut interval = ExpressionBuilder::new(0, runtime.clone())
+        .build(false, interval, &schema, &[])
+        .await?;
+    let Field::Duration(duration) = interval.evaluate(&Record::new(vec![]), &schema)? else {
+        return Ok(None);
+    };
+    let offset = chrono::Duration::from_std(duration.0)?;
+    match op {
+        BinaryOperator::Minus => Ok(Some((time, -offset))),
+        _ => Ok(Some((time, offset))),
     }
+}
 
-    Ok((left_key_indexes, right_key_indexes))
+/// The side and index of the field, if `expr` is a timestamp field.
+fn parse_time_field(
+    expr: &SqlExpr,
+    left_join_table: &Schema,
+    right_join_table: &Schema,
+) -> Option<(JoinBranch, usize)> {
+    let (branch, index) = parse_join_eq_expression(expr, left_join_table, right_join_table)
+        .ok()
+        .flatten()?;
+    let schema = match branch {
+        JoinBranch::Left => left_join_table,
+        JoinBranch::Right => right_join_table,
+    };
+    (schema.fields[index].typ == FieldType::Timestamp).then_some((branch, index))
 }
 
 fn parse_identifier(
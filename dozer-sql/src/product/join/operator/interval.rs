@@ -0,0 +1,73 @@
+use dozer_types::{
+    chrono::Duration,
+    types::{Field, Record, Timestamp},
+};
+
+use super::JoinBranch;
+
+/// A join whose ON constraint bounds the time of a left record to a range around the time of a right
+/// record: `right.time + lower <= left.time <= right.time + upper`.
+///
+/// Records arrive roughly in time order, so once one side's time is past the range of a record of
+/// the other side, that record can only match late records. If the record has a lifetime, from a
+/// TTL on its table, it's evicted then as at the end of its lifetime, which bounds the state of the
+/// join. Records without a lifetime are kept, so late records still match them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalJoin {
+    pub left_time_index: usize,
+    pub right_time_index: usize,
+    pub lower: Duration,
+    pub upper: Duration,
+}
+
+impl IntervalJoin {
+    /// How long after its own time a record of `branch` is kept.
+    ///
+    /// Records are evicted at the first instant that can't match them, one past the bound.
+    pub fn retention(&self, branch: JoinBranch) -> Duration {
+        let bound = match branch {
+            JoinBranch::Left => -self.lower,
+            JoinBranch::Right => self.upper,
+        };
+        bound + Duration::nanoseconds(1)
+    }
+
+    pub fn time_index(&self, branch: JoinBranch) -> usize {
+        match branch {
+            JoinBranch::Left => self.left_time_index,
+            JoinBranch::Right => self.right_time_index,
+        }
+    }
+
+    /// The time of a record of `branch`, if it's not null.
+    pub fn time(&self, branch: JoinBranch, record: &Record) -> Option<Timestamp> {
+        match record.values.get(self.time_index(branch)) {
+            Some(Field::Timestamp(time)) => Some(*time),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention() {
+        // left.time BETWEEN right.time - INTERVAL '5' MINUTE AND right.time + INTERVAL '1' MINUTE
+        let interval = IntervalJoin {
+            left_time_index: 0,
+            right_time_index: 0,
+            lower: Duration::minutes(-5),
+            upper: Duration::minutes(1),
+        };
+        assert_eq!(
+            interval.retention(JoinBranch::Left),
+            Duration::minutes(5) + Duration::nanoseconds(1)
+        );
+        assert_eq!(
+            interval.retention(JoinBranch::Right),
+            Duration::minutes(1) + Duration::nanoseconds(1)
+        );
+    }
+}
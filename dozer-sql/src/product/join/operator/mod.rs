@@ -1,9 +1,13 @@
-use dozer_types::types::{Record, Schema, Timestamp};
+use dozer_core::state_store::StateStore;
+use dozer_sql_expression::execution::Expression;
+use dozer_types::types::{Field, Record, Schema, Timestamp};
 
 use crate::errors::JoinError;
 
 use self::table::{JoinKey, JoinTable};
 
+pub use self::interval::IntervalJoin;
+
 use super::JoinResult;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -17,6 +21,7 @@ pub enum JoinType {
     Inner,
     LeftOuter,
     RightOuter,
+    FullOuter,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -25,6 +30,66 @@ pub enum JoinAction {
     Delete,
 }
 
+/// The part of the ON constraint that's not an equality of the join keys. It's evaluated on the
+/// joined records of a key, which only match if it's true.
+#[derive(Debug, Clone)]
+pub struct JoinPredicate {
+    expression: Expression,
+    schema: Schema,
+}
+
+impl JoinPredicate {
+    /// `schema` is the schema of the joined records.
+    pub fn new(expression: Expression, schema: Schema) -> Self {
+        Self { expression, schema }
+    }
+
+    fn is_satisfied(&mut self, join_record: &Record) -> JoinResult<bool> {
+        Ok(self.expression.evaluate(join_record, &self.schema)? == Field::Boolean(true))
+    }
+}
+
+/// Where the fields of both sides are in the joined records.
+///
+/// The fields the join was planned with come first, the left ones then the right ones. Fields
+/// appended to either side since follow in the order they were appended, so the joined records
+/// keep their layout when a side's schema changes. Fields a record doesn't have are null.
+#[derive(Debug, Clone)]
+struct JoinLayout {
+    left_len: usize,
+    right_len: usize,
+    appended: Vec<(JoinBranch, usize)>,
+}
+
+impl JoinLayout {
+    fn output_index(&self, branch: JoinBranch, index: usize) -> Option<usize> {
+        match branch {
+            JoinBranch::Left if index < self.left_len => Some(index),
+            JoinBranch::Right if index < self.right_len => Some(self.left_len + index),
+            _ => self
+                .appended
+                .iter()
+                .position(|appended| *appended == (branch, index))
+                .map(|position| self.left_len + self.right_len + position),
+        }
+    }
+
+    fn join(&self, left: &Record, right: &Record) -> Record {
+        let field = |record: &Record, index: usize| {
+            record.values.get(index).cloned().unwrap_or(Field::Null)
+        };
+        let mut values = Vec::with_capacity(self.left_len + self.right_len + self.appended.len());
+        values.extend((0..self.left_len).map(|index| field(left, index)));
+        values.extend((0..self.right_len).map(|index| field(right, index)));
+        values.extend(self.appended.iter().map(|(branch, index)| match branch {
+            JoinBranch::Left => field(left, *index),
+            JoinBranch::Right => field(right, *index),
+        }));
+        Record::new(values)
+    }
+}
+
+mod interval;
 mod table;
 
 #[derive(Debug, Clone)]
@@ -33,6 +98,11 @@ pub struct JoinOperator {
 
     left: JoinTable,
     right: JoinTable,
+
+    predicate: Option<JoinPredicate>,
+    interval: Option<IntervalJoin>,
+
+    layout: JoinLayout,
 }
 
 impl JoinOperator {
@@ -40,77 +110,127 @@ impl JoinOperator {
         join_type: JoinType,
         (left_join_key_indexes, right_join_key_indexes): (Vec<usize>, Vec<usize>),
         (left_schema, right_schema): (&Schema, &Schema),
+        predicate: Option<JoinPredicate>,
+        interval: Option<IntervalJoin>,
         enable_probabilistic_optimizations: bool,
     ) -> Result<Self, JoinError> {
         let accurate_keys = !enable_probabilistic_optimizations;
-        let left = JoinTable::new(left_schema, left_join_key_indexes, accurate_keys)?;
-        let right = JoinTable::new(right_schema, right_join_key_indexes, accurate_keys)?;
+        let mut left = JoinTable::new(left_schema, left_join_key_indexes, accurate_keys)?;
+        let mut right = JoinTable::new(right_schema, right_join_key_indexes, accurate_keys)?;
+        if let Some(interval) = &interval {
+            left = left.with_retention(
+                interval.time_index(JoinBranch::Left),
+                interval.retention(JoinBranch::Left),
+            );
+            right = right.with_retention(
+                interval.time_index(JoinBranch::Right),
+                interval.retention(JoinBranch::Right),
+            );
+        }
         Ok(Self {
             join_type,
             left,
             right,
+            predicate,
+            interval,
+            layout: JoinLayout {
+                left_len: left_schema.fields.len(),
+                right_len: right_schema.fields.len(),
+                appended: vec![],
+            },
         })
     }
 
+    /// The index in the joined records of field `index` of `branch`, if they have it.
+    pub fn output_index(&self, branch: JoinBranch, index: usize) -> Option<usize> {
+        self.layout.output_index(branch, index)
+    }
+
+    /// Appends field `index` of `branch` to the joined records.
+    pub fn append_field(&mut self, branch: JoinBranch, index: usize) {
+        self.layout.appended.push((branch, index));
+    }
+
     fn inner_join(
-        &self,
+        &mut self,
         action: JoinAction,
         join_key: &JoinKey,
         record: &Record,
         record_branch: JoinBranch,
         default_if_no_match: bool,
-    ) -> Vec<(JoinAction, Record)> {
+    ) -> JoinResult<Vec<(JoinAction, Record)>> {
         let table = match record_branch {
             JoinBranch::Left => &self.right,
             JoinBranch::Right => &self.left,
         };
-        let join_records = create_join_records_fn(record, record_branch);
+        let join_records = create_join_records_fn(&self.layout, record, record_branch);
 
-        table
-            .get_matching_records(join_key, default_if_no_match)
-            .map(|matching_record| (action, join_records(matching_record)))
-            .collect()
+        let mut output_records = vec![];
+        for matching_record in table.get_matching_records(join_key, false) {
+            let join_record = join_records(matching_record);
+            if is_match(&mut self.predicate, &join_record)? {
+                output_records.push((action, join_record));
+            }
+        }
+        if default_if_no_match && output_records.is_empty() {
+            output_records.push((action, join_records(table.default_record())));
+        }
+        Ok(output_records)
     }
 
     fn outer_join(
-        &self,
+        &mut self,
         action: JoinAction,
         join_key: &JoinKey,
         record: &Record,
         record_branch: JoinBranch,
-    ) -> Vec<(JoinAction, Record)> {
+    ) -> JoinResult<Vec<(JoinAction, Record)>> {
         let (table_to_match, table_of_record) = match record_branch {
             JoinBranch::Left => (&self.right, &self.left),
             JoinBranch::Right => (&self.left, &self.right),
         };
-        let join_records = create_join_records_fn(record, record_branch);
-        let default_join_records =
-            create_join_records_fn(table_of_record.default_record(), record_branch);
-
-        // We need to query from the table where this record is from:
-        // - For JoinAction::Insert, did this join key exist before this insert? If not, we need to remove the default record.
-        // - For JoinAction::Delete, does this join key exist after this delete? If not, we need to insert the default record.
-        let need_to_act_on_default_record = match action {
-            JoinAction::Insert => {
-                // Because this record is already inserted, the join key didn't exist before this insert iif the matching count is now 1.
-                table_of_record
-                    .get_matching_records(join_key, false)
-                    .take(2)
-                    .count()
-                    == 1
-            }
-            JoinAction::Delete => {
-                table_of_record
-                    .get_matching_records(join_key, false)
-                    .take(1)
-                    .count()
-                    == 0
-            }
-        };
+        let join_records = create_join_records_fn(&self.layout, record, record_branch);
+        let default_join_records = create_join_records_fn(
+            &self.layout,
+            table_of_record.default_record(),
+            record_branch,
+        );
 
         let mut output_records = vec![];
         for matching_record in table_to_match.get_matching_records(join_key, false) {
             let join_record = join_records(matching_record);
+            if !is_match(&mut self.predicate, &join_record)? {
+                continue;
+            }
+
+            // We need to query from the table where this record is from:
+            // - For JoinAction::Insert, did the matching record have a match before this insert? If not, we need to remove its default record.
+            // - For JoinAction::Delete, does the matching record have a match after this delete? If not, we need to insert its default record.
+            let need_to_act_on_default_record = match action {
+                JoinAction::Insert => {
+                    // Because this record is already inserted, the matching record didn't have a match before this insert iif it has 1 now.
+                    count_matches(
+                        &self.layout,
+                        table_of_record,
+                        record_branch,
+                        join_key,
+                        matching_record,
+                        &mut self.predicate,
+                        2,
+                    )? == 1
+                }
+                JoinAction::Delete => {
+                    count_matches(
+                        &self.layout,
+                        table_of_record,
+                        record_branch,
+                        join_key,
+                        matching_record,
+                        &mut self.predicate,
+                        1,
+                    )? == 0
+                }
+            };
 
             if need_to_act_on_default_record {
                 let default_join_record = default_join_records(matching_record);
@@ -131,16 +251,47 @@ impl JoinOperator {
             }
         }
 
-        output_records
+        Ok(output_records)
+    }
+
+    /// A record without a match is padded with nulls. The padded rows of the other side are retracted
+    /// when it gets its first match, and come back when it loses its last one.
+    fn full_outer_join(
+        &mut self,
+        action: JoinAction,
+        join_key: &JoinKey,
+        record: &Record,
+        record_branch: JoinBranch,
+    ) -> JoinResult<Vec<(JoinAction, Record)>> {
+        let mut output_records = self.outer_join(action, join_key, record, record_branch)?;
+
+        let (table_to_match, branch_to_match) = match record_branch {
+            JoinBranch::Left => (&self.right, JoinBranch::Right),
+            JoinBranch::Right => (&self.left, JoinBranch::Left),
+        };
+        if count_matches(
+            &self.layout,
+            table_to_match,
+            branch_to_match,
+            join_key,
+            record,
+            &mut self.predicate,
+            1,
+        )? == 0
+        {
+            let join_records = create_join_records_fn(&self.layout, record, record_branch);
+            output_records.push((action, join_records(table_to_match.default_record())));
+        }
+        Ok(output_records)
     }
 
     fn join(
-        &self,
+        &mut self,
         action: JoinAction,
         join_key: &JoinKey,
         record: &Record,
         record_branch: JoinBranch,
-    ) -> Vec<(JoinAction, Record)> {
+    ) -> JoinResult<Vec<(JoinAction, Record)>> {
         match (&self.join_type, record_branch) {
             (JoinType::Inner, _) => self.inner_join(action, join_key, record, record_branch, false),
             (JoinType::LeftOuter, JoinBranch::Left) => {
@@ -155,6 +306,9 @@ impl JoinOperator {
             (JoinType::RightOuter, JoinBranch::Right) => {
                 self.inner_join(action, join_key, record, JoinBranch::Right, true)
             }
+            (JoinType::FullOuter, _) => {
+                self.full_outer_join(action, join_key, record, record_branch)
+            }
         }
     }
 
@@ -163,13 +317,16 @@ impl JoinOperator {
         from: JoinBranch,
         old: &Record,
         old_decoded: &Record,
-    ) -> Vec<(JoinAction, Record)> {
+    ) -> JoinResult<Vec<(JoinAction, Record)>> {
         let join_key = match from {
-            JoinBranch::Left => self.left.remove(old_decoded),
-            JoinBranch::Right => self.right.remove(old_decoded),
+            JoinBranch::Left => self.left.remove(old_decoded)?,
+            JoinBranch::Right => self.right.remove(old_decoded)?,
         };
+        self.load(&join_key)?;
 
-        self.join(JoinAction::Delete, &join_key, old, from)
+        let records = self.join(JoinAction::Delete, &join_key, old, from)?;
+        self.spill();
+        Ok(records)
     }
 
     pub fn insert(
@@ -178,43 +335,148 @@ impl JoinOperator {
         new: &Record,
         new_decoded: &Record,
     ) -> JoinResult<Vec<(JoinAction, Record)>> {
+        self.advance_interval(from, new_decoded)?;
         let join_key = match from {
             JoinBranch::Left => self.left.insert(new.clone(), new_decoded)?,
             JoinBranch::Right => self.right.insert(new.clone(), new_decoded)?,
         };
+        self.load(&join_key)?;
+
+        let records = self.join(JoinAction::Insert, &join_key, new, from)?;
+        self.spill();
+        Ok(records)
+    }
+
+    pub fn evict_index(&mut self, now: &Timestamp) -> JoinResult<()> {
+        self.left.evict_index(now)?;
+        self.right.evict_index(now)?;
+        self.spill();
+        Ok(())
+    }
+
+    /// In an interval join, the time of a new record is the time of its side. Records of the other
+    /// side that are out of range at that time are evicted.
+    fn advance_interval(&mut self, from: JoinBranch, record: &Record) -> JoinResult<()> {
+        let Some(now) = self
+            .interval
+            .as_ref()
+            .and_then(|interval| interval.time(from, record))
+        else {
+            return Ok(());
+        };
+        match from {
+            JoinBranch::Left => self.right.evict_index(&now),
+            JoinBranch::Right => self.left.evict_index(&now),
+        }
+    }
+
+    /// Reads the records of `join_key` on both sides back from the state store, if they have been spilled.
+    fn load(&mut self, join_key: &JoinKey) -> JoinResult<()> {
+        self.left.load(join_key)?;
+        self.right.load(join_key)
+    }
+
+    fn spill(&mut self) {
+        self.left.spill();
+        self.right.spill();
+    }
+
+    /// Removes all records of `branch`, after its input was truncated. Every joined record has a record
+    /// of `branch` or is an unmatched record of the other side padded with nulls, so all of them are
+    /// deleted. The join keeps the records of the other side, see [`JoinOperator::for_each_unmatched_record`].
+    pub fn truncate(&mut self, branch: JoinBranch) -> JoinResult<()> {
+        match branch {
+            JoinBranch::Left => self.left.clear(),
+            JoinBranch::Right => self.right.clear(),
+        }
+    }
+
+    /// Calls `f` on the joined records left after `truncated` was truncated. They're the records of
+    /// the other side padded with nulls, if the join keeps the unmatched records of that side.
+    pub fn for_each_unmatched_record(
+        &self,
+        truncated: JoinBranch,
+        mut f: impl FnMut(Record),
+    ) -> JoinResult<()> {
+        let keeps_other_side = match (&self.join_type, truncated) {
+            (JoinType::Inner, _)
+            | (JoinType::LeftOuter, JoinBranch::Left)
+            | (JoinType::RightOuter, JoinBranch::Right) => false,
+            (JoinType::LeftOuter, JoinBranch::Right)
+            | (JoinType::RightOuter, JoinBranch::Left)
+            | (JoinType::FullOuter, _) => true,
+        };
+        if !keeps_other_side {
+            return Ok(());
+        }
+        let (truncated_table, other_table, other_branch) = match truncated {
+            JoinBranch::Left => (&self.left, &self.right, JoinBranch::Right),
+            JoinBranch::Right => (&self.right, &self.left, JoinBranch::Left),
+        };
+        other_table.for_each_record(|record| {
+            let join_records = create_join_records_fn(&self.layout, record, other_branch);
+            f(join_records(truncated_table.default_record()));
+            Ok(())
+        })
+    }
 
-        Ok(self.join(JoinAction::Insert, &join_key, new, from))
+    /// Loads both tables from `store`, and writes them through to it from now on.
+    pub fn restore(&mut self, store: StateStore) -> JoinResult<()> {
+        // The tables share the memory budget.
+        let memory_budget = store.memory_budget().map(|budget| budget / 2);
+        let store = store.with_memory_budget(memory_budget);
+        self.left.restore(store.clone(), "left", "left_evictions")?;
+        self.right.restore(store, "right", "right_evictions")
     }
+}
 
-    pub fn evict_index(&mut self, now: &Timestamp) {
-        self.left.evict_index(now);
-        self.right.evict_index(now);
+fn is_match(predicate: &mut Option<JoinPredicate>, join_record: &Record) -> JoinResult<bool> {
+    match predicate {
+        Some(predicate) => predicate.is_satisfied(join_record),
+        None => Ok(true),
     }
 }
 
-fn create_join_records_fn(
+/// Counts the records of `table`, which is on `table_branch`, that match `record` of the other side, up to `limit`.
+fn count_matches(
+    layout: &JoinLayout,
+    table: &JoinTable,
+    table_branch: JoinBranch,
+    join_key: &JoinKey,
     record: &Record,
+    predicate: &mut Option<JoinPredicate>,
+    limit: usize,
+) -> JoinResult<usize> {
+    let matching_records = table.get_matching_records(join_key, false);
+    let Some(predicate) = predicate else {
+        return Ok(matching_records.take(limit).count());
+    };
+
+    let mut count = 0;
+    for matching_record in matching_records {
+        if count == limit {
+            break;
+        }
+        let join_record = create_join_records_fn(layout, matching_record, table_branch)(record);
+        if predicate.is_satisfied(&join_record)? {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+fn create_join_records_fn<'a>(
+    layout: &'a JoinLayout,
+    record: &'a Record,
     record_branch: JoinBranch,
-) -> impl Fn(&Record) -> Record + '_ {
+) -> impl Fn(&Record) -> Record + 'a {
     let lifetime = record.get_lifetime();
     move |matching_record| {
         let matching_lifetime = matching_record.get_lifetime();
 
         let mut output_record = match record_branch {
-            JoinBranch::Left => {
-                let len = record.values().len() + matching_record.values().len();
-                let mut data = Vec::with_capacity(len);
-                data.extend_from_slice(record.values());
-                data.extend_from_slice(matching_record.values());
-                Record::new(data)
-            }
-            JoinBranch::Right => {
-                let len = record.values().len() + matching_record.values().len();
-                let mut data = Vec::with_capacity(len);
-                data.extend_from_slice(matching_record.values());
-                data.extend_from_slice(record.values());
-                Record::new(data)
-            }
+            JoinBranch::Left => layout.join(record, matching_record),
+            JoinBranch::Right => layout.join(matching_record, record),
         };
 
         if let Some(lifetime) = &lifetime {
@@ -1,24 +1,30 @@
 use std::{
     collections::{
         hash_map::{self, Values},
-        HashMap,
+        BTreeMap, HashMap,
     },
     iter::{once, Flatten, Once},
 };
 
+use dozer_core::state_store::{encoded_size, StateStore};
 use dozer_types::{
     chrono,
-    types::{Field, Record, Schema, Timestamp},
+    types::{Field, Lifetime, Record, Schema, Timestamp},
 };
-use linked_hash_map::LinkedHashMap;
 
 use crate::{
     errors::JoinError,
-    utils::record_hashtable_key::{get_record_hash, RecordKey},
+    utils::{
+        record_hashtable_key::{get_record_hash, RecordKey},
+        spill::SpillTracker,
+    },
 };
 
 pub type JoinKey = RecordKey;
 type IndexKey = (JoinKey, u64); // (join_key, primary_key)
+/// An eviction instant, encoded by [`instant_key`].
+type InstantKey = [u8; 12];
+type EvictionKey = (InstantKey, JoinKey, u64); // (eviction_instant, join_key, primary_key)
 
 #[derive(Debug, Clone)]
 pub struct JoinTable {
@@ -26,8 +32,26 @@ pub struct JoinTable {
     primary_key_indexes: Vec<usize>,
     default_record: Record,
     map: HashMap<JoinKey, HashMap<u64, Vec<Record>>>,
-    lifetime_map: LinkedHashMap<Timestamp, Vec<IndexKey>>,
+    /// Records by eviction instant, unless the eviction index is only kept in the store. Interval
+    /// retention doesn't evict records in insertion order.
+    lifetime_map: BTreeMap<InstantKey, Vec<IndexKey>>,
+    /// The index of the time field and how long after that time records with a lifetime are evicted,
+    /// in an interval join.
+    retention: Option<(usize, chrono::Duration)>,
     accurate_keys: bool,
+    /// The store records and the eviction index are written through to.
+    state_store: Option<TableStore>,
+    /// Tracks the records of each join key in memory, if the store has a memory budget.
+    spill_tracker: Option<SpillTracker<JoinKey>>,
+}
+
+#[derive(Debug, Clone)]
+struct TableStore {
+    store: StateStore,
+    /// The records, by [`IndexKey`].
+    namespace: &'static str,
+    /// How many records to evict, by [`EvictionKey`].
+    eviction_namespace: &'static str,
 }
 
 impl JoinTable {
@@ -48,10 +72,110 @@ impl JoinTable {
             default_record: Record::nulls_from_schema(schema),
             map: Default::default(),
             lifetime_map: Default::default(),
+            retention: None,
             accurate_keys,
+            state_store: None,
+            spill_tracker: None,
         })
     }
 
+    /// Evicts records with a lifetime `retention` after the time in field `time_index`, or at the end
+    /// of their lifetime if it's earlier. Records without a lifetime are kept.
+    pub fn with_retention(mut self, time_index: usize, retention: chrono::Duration) -> Self {
+        self.retention = Some((time_index, retention));
+        self
+    }
+
+    /// Loads the records written to `namespace` of `store` and their eviction index written to
+    /// `eviction_namespace`, and writes both through to it from now on.
+    ///
+    /// If the store has a memory budget, records are left in the store until their join key is used,
+    /// and the eviction index is only kept in the store.
+    pub fn restore(
+        &mut self,
+        store: StateStore,
+        namespace: &'static str,
+        eviction_namespace: &'static str,
+    ) -> Result<(), JoinError> {
+        let mut spill_tracker = store.memory_budget().map(SpillTracker::new);
+        match &mut spill_tracker {
+            Some(spill_tracker) => spill_tracker.mark_spilled(),
+            None => {
+                store.for_each_entry(
+                    namespace,
+                    |(join_key, primary_key): IndexKey, records: Vec<Record>| {
+                        self.map
+                            .entry(join_key)
+                            .or_default()
+                            .insert(primary_key, records);
+                        Ok::<_, JoinError>(())
+                    },
+                )?;
+                let lifetime_map = &mut self.lifetime_map;
+                store.for_each_entry(
+                    eviction_namespace,
+                    |(instant, join_key, primary_key): EvictionKey, count: u64| {
+                        let index_keys = lifetime_map.entry(instant).or_default();
+                        for _ in 0..count {
+                            index_keys.push((join_key.clone(), primary_key));
+                        }
+                        Ok::<_, JoinError>(())
+                    },
+                )?;
+            }
+        }
+        self.state_store = Some(TableStore {
+            store,
+            namespace,
+            eviction_namespace,
+        });
+        self.spill_tracker = spill_tracker;
+        Ok(())
+    }
+
+    /// Reads the records of `join_key` back from the state store, if they have been spilled.
+    pub fn load(&mut self, join_key: &JoinKey) -> Result<(), JoinError> {
+        let (
+            Some(spill_tracker),
+            Some(TableStore {
+                store, namespace, ..
+            }),
+        ) = (&mut self.spill_tracker, &self.state_store)
+        else {
+            return Ok(());
+        };
+        if self.map.contains_key(join_key) {
+            spill_tracker.touch(join_key);
+            return Ok(());
+        }
+        if !spill_tracker.has_spilled() {
+            return Ok(());
+        }
+
+        let mut records_map = HashMap::new();
+        let mut size = 0;
+        for ((_, primary_key), records) in
+            store.entries_with_prefix::<IndexKey, Vec<Record>>(namespace, join_key)?
+        {
+            size += encoded_size(&records)?;
+            records_map.insert(primary_key, records);
+        }
+        if !records_map.is_empty() {
+            self.map.insert(join_key.clone(), records_map);
+            spill_tracker.set(join_key, size);
+        }
+        Ok(())
+    }
+
+    /// Drops the least recently used join keys from memory until the records are within the memory budget.
+    pub fn spill(&mut self) {
+        if let Some(spill_tracker) = &mut self.spill_tracker {
+            while let Some(join_key) = spill_tracker.pop_over_budget() {
+                self.map.remove(&join_key);
+            }
+        }
+    }
+
     pub fn get_matching_records<'a>(
         &'a self,
         join_key: &JoinKey,
@@ -77,22 +201,14 @@ impl JoinTable {
     ) -> Result<JoinKey, JoinError> {
         let join_key = self.get_join_key(record_decoded);
         let primary_key = get_record_key_hash(record_decoded, &self.primary_key_indexes);
+        self.load(&join_key)?;
+        let size = match self.spill_tracker {
+            Some(_) => encoded_size(&record)?,
+            None => 0,
+        };
 
-        if let Some(lifetime) = record.get_lifetime() {
-            let Some(eviction_instant) =
-                lifetime
-                    .reference
-                    .checked_add_signed(chrono::Duration::nanoseconds(
-                        lifetime.duration.as_nanos() as i64,
-                    ))
-            else {
-                return Err(JoinError::EvictionTimeOverflow);
-            };
-
-            self.lifetime_map
-                .entry(eviction_instant)
-                .or_default()
-                .push((join_key.clone(), primary_key));
+        if let Some(eviction_instant) = record_eviction_instant(&record, self.retention)? {
+            self.index_eviction(instant_key(&eviction_instant), &join_key, primary_key)?;
         }
 
         self.map
@@ -101,38 +217,182 @@ impl JoinTable {
             .entry(primary_key)
             .or_default()
             .push(record);
+        self.resize(&join_key, size, 0);
+        self.write_through(&join_key, primary_key)?;
 
         Ok(join_key)
     }
 
-    pub fn remove(&mut self, record: &Record) -> JoinKey {
+    pub fn remove(&mut self, record: &Record) -> Result<JoinKey, JoinError> {
         let join_key = self.get_join_key(record);
-        if let hash_map::Entry::Occupied(record_map) = self.map.entry(join_key.clone()) {
-            let primary_key = get_record_key_hash(record, &self.primary_key_indexes);
-            remove_record_using_primary_key(record_map, primary_key);
-        }
-        join_key
-    }
-
-    pub fn evict_index(&mut self, now: &Timestamp) {
-        let mut keys_to_remove = vec![];
-        for (eviction_instant, join_index_keys) in self.lifetime_map.iter() {
-            if eviction_instant <= now {
-                keys_to_remove.push(*eviction_instant);
-                for (join_key, primary_key) in join_index_keys {
-                    if let hash_map::Entry::Occupied(record_map) = self.map.entry(join_key.clone())
-                    {
-                        remove_record_using_primary_key(record_map, *primary_key);
-                    }
+        let primary_key = get_record_key_hash(record, &self.primary_key_indexes);
+        self.remove_using_primary_key(&join_key, primary_key)?;
+        Ok(join_key)
+    }
+
+    /// Removes the records whose eviction instant is `now` or earlier.
+    pub fn evict_index(&mut self, now: &Timestamp) -> Result<(), JoinError> {
+        if let (Some(_), Some(table_store)) = (&self.spill_tracker, self.state_store.clone()) {
+            let TableStore {
+                store,
+                eviction_namespace,
+                ..
+            } = table_store;
+            // The keys of the instants up to `now` sort before the key of the next instant.
+            let end = now
+                .checked_add_signed(chrono::Duration::nanoseconds(1))
+                .ok_or(JoinError::EvictionTimeOverflow)?;
+            for (eviction_key, count) in
+                store.entries_before::<EvictionKey, u64>(eviction_namespace, &instant_key(&end))?
+            {
+                let (_, join_key, primary_key) = &eviction_key;
+                for _ in 0..count {
+                    self.remove_using_primary_key(join_key, *primary_key)?;
                 }
-            } else {
+                store.delete(eviction_namespace, &eviction_key)?;
+            }
+            return Ok(());
+        }
+
+        let now = instant_key(now);
+        while let Some(entry) = self.lifetime_map.first_entry() {
+            if *entry.key() > now {
                 break;
             }
+            let (instant, index_keys) = entry.remove_entry();
+            for (join_key, primary_key) in index_keys {
+                self.remove_using_primary_key(&join_key, primary_key)?;
+                if let Some(TableStore {
+                    store,
+                    eviction_namespace,
+                    ..
+                }) = &self.state_store
+                {
+                    store.delete(eviction_namespace, &(instant, join_key, primary_key))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes all records, from the state store too.
+    pub fn clear(&mut self) -> Result<(), JoinError> {
+        self.map.clear();
+        self.lifetime_map.clear();
+        if let Some(spill_tracker) = &mut self.spill_tracker {
+            spill_tracker.clear();
+        }
+        if let Some(TableStore {
+            store,
+            namespace,
+            eviction_namespace,
+        }) = &self.state_store
+        {
+            store.delete_namespace(namespace)?;
+            store.delete_namespace(eviction_namespace)?;
+        }
+        Ok(())
+    }
+
+    /// Calls `f` on all records, reading them from the state store if there's one, as spilled
+    /// records are only there.
+    pub fn for_each_record(
+        &self,
+        mut f: impl FnMut(&Record) -> Result<(), JoinError>,
+    ) -> Result<(), JoinError> {
+        if let Some(TableStore {
+            store, namespace, ..
+        }) = &self.state_store
+        {
+            return store.for_each_entry(namespace, |_: IndexKey, records: Vec<Record>| {
+                records.iter().try_for_each(&mut f)
+            });
+        }
+        self.map
+            .values()
+            .flat_map(HashMap::values)
+            .flatten()
+            .try_for_each(f)
+    }
+
+    /// Adds a record to evict at `instant` to the eviction index, which is only kept in the store if
+    /// the store has a memory budget.
+    fn index_eviction(
+        &mut self,
+        instant: InstantKey,
+        join_key: &JoinKey,
+        primary_key: u64,
+    ) -> Result<(), JoinError> {
+        if let Some(TableStore {
+            store,
+            eviction_namespace,
+            ..
+        }) = &self.state_store
+        {
+            let eviction_key = (instant, join_key.clone(), primary_key);
+            let count = store
+                .get::<u64>(eviction_namespace, &eviction_key)?
+                .unwrap_or_default();
+            store.put(eviction_namespace, &eviction_key, &(count + 1))?;
+        }
+        if self.spill_tracker.is_none() {
+            self.lifetime_map
+                .entry(instant)
+                .or_default()
+                .push((join_key.clone(), primary_key));
+        }
+        Ok(())
+    }
+
+    fn remove_using_primary_key(
+        &mut self,
+        join_key: &JoinKey,
+        primary_key: u64,
+    ) -> Result<(), JoinError> {
+        self.load(join_key)?;
+        if let hash_map::Entry::Occupied(record_map) = self.map.entry(join_key.clone()) {
+            let removed = remove_record_using_primary_key(record_map, primary_key);
+            let size = match (&self.spill_tracker, removed) {
+                (Some(_), Some(removed)) => encoded_size(&removed)?,
+                _ => 0,
+            };
+            self.resize(join_key, 0, size);
+            self.write_through(join_key, primary_key)?;
+        }
+        Ok(())
+    }
+
+    /// Updates the tracked size of `join_key` after records of `added` bytes were added and `removed` bytes were removed.
+    fn resize(&mut self, join_key: &JoinKey, added: usize, removed: usize) {
+        let Some(spill_tracker) = &mut self.spill_tracker else {
+            return;
+        };
+        if self.map.contains_key(join_key) {
+            let size = (spill_tracker.size(join_key) + added).saturating_sub(removed);
+            spill_tracker.set(join_key, size);
+        } else {
+            spill_tracker.remove(join_key);
         }
+    }
 
-        for key in keys_to_remove {
-            self.lifetime_map.remove(&key);
+    /// Writes the records of `join_key` and `primary_key` through to the state store, if there's one.
+    fn write_through(&self, join_key: &JoinKey, primary_key: u64) -> Result<(), JoinError> {
+        let Some(TableStore {
+            store, namespace, ..
+        }) = &self.state_store
+        else {
+            return Ok(());
+        };
+        let index_key = (join_key.clone(), primary_key);
+        match self
+            .map
+            .get(join_key)
+            .and_then(|records_map| records_map.get(&primary_key))
+        {
+            Some(records) => store.put(namespace, &index_key, records)?,
+            None => store.delete(namespace, &index_key)?,
         }
+        Ok(())
     }
 
     fn get_join_key(&self, record: &Record) -> JoinKey {
@@ -163,6 +423,50 @@ impl<'a> Iterator for MatchingRecords<'a> {
     }
 }
 
+fn eviction_instant(lifetime: &Lifetime) -> Result<Timestamp, JoinError> {
+    lifetime
+        .reference
+        .checked_add_signed(chrono::Duration::nanoseconds(
+            lifetime.duration.as_nanos() as i64
+        ))
+        .ok_or(JoinError::EvictionTimeOverflow)
+}
+
+/// `instant` as a key that sorts in time order, as the store compares keys bytewise: the seconds
+/// since the epoch with the sign bit flipped, then the nanoseconds, big-endian.
+fn instant_key(instant: &Timestamp) -> InstantKey {
+    let mut key = [0; 12];
+    key[..8].copy_from_slice(&((instant.timestamp() as u64) ^ (1 << 63)).to_be_bytes());
+    key[8..].copy_from_slice(&instant.timestamp_subsec_nanos().to_be_bytes());
+    key
+}
+
+/// The earlier of the end of the record's lifetime and the end of its retention, if it has a lifetime.
+///
+/// A lifetime opts the record into eviction, so a join without TTL keeps matching late records.
+fn record_eviction_instant(
+    record: &Record,
+    retention: Option<(usize, chrono::Duration)>,
+) -> Result<Option<Timestamp>, JoinError> {
+    let Some(lifetime) = record.get_lifetime() else {
+        return Ok(None);
+    };
+    let lifetime_end = eviction_instant(&lifetime)?;
+    let retention_end = match retention {
+        Some((time_index, retention)) => match &record.values[time_index] {
+            Field::Timestamp(time) => Some(
+                time.checked_add_signed(retention)
+                    .ok_or(JoinError::EvictionTimeOverflow)?,
+            ),
+            _ => None,
+        },
+        None => None,
+    };
+    Ok(Some(
+        retention_end.map_or(lifetime_end, |end| end.min(lifetime_end)),
+    ))
+}
+
 fn get_record_key_hash(record: &Record, key_indexes: &[usize]) -> u64 {
     let key_fields = key_indexes.iter().map(|i| &record.values[*i]);
     get_record_hash(key_fields)
@@ -178,9 +482,10 @@ fn get_record_key_fields(record: &Record, key_indexes: &[usize]) -> Vec<Field> {
 fn remove_record_using_primary_key(
     mut record_map: hash_map::OccupiedEntry<JoinKey, HashMap<u64, Vec<Record>>>,
     primary_key: u64,
-) {
+) -> Option<Record> {
+    let mut removed = None;
     if let hash_map::Entry::Occupied(mut record_vec) = record_map.get_mut().entry(primary_key) {
-        record_vec.get_mut().pop();
+        removed = record_vec.get_mut().pop();
         if record_vec.get().is_empty() {
             record_vec.remove();
         }
@@ -189,6 +494,7 @@ fn remove_record_using_primary_key(
     if record_map.get().is_empty() {
         record_map.remove();
     }
+    removed
 }
 
 #[cfg(test)]
@@ -197,9 +503,8 @@ mod tests {
 
     use super::*;
 
-    #[test]
-    fn test_match_insert_remove() {
-        let schema = Schema {
+    fn schema() -> Schema {
+        Schema {
             fields: vec![FieldDefinition {
                 name: "a".to_string(),
                 typ: FieldType::Int,
@@ -208,7 +513,12 @@ mod tests {
                 description: None,
             }],
             primary_index: vec![0],
-        };
+        }
+    }
+
+    #[test]
+    fn test_match_insert_remove() {
+        let schema = schema();
         let mut table = JoinTable::new(&schema, vec![0], true).unwrap();
 
         let record = Record::new(vec![Field::Int(1)]);
@@ -220,8 +530,141 @@ mod tests {
         assert_eq!(table.get_matching_records(&join_key, true).count(), 1);
         assert_eq!(table.get_matching_records(&join_key, false).count(), 1);
 
-        let join_key = table.remove(&record);
+        let join_key = table.remove(&record).unwrap();
         assert_eq!(table.get_matching_records(&join_key, true).count(), 1);
         assert_eq!(table.get_matching_records(&join_key, false).count(), 0);
     }
+
+    #[test]
+    fn test_restore() {
+        let schema = schema();
+        let store = StateStore::in_memory().unwrap();
+        let mut table = JoinTable::new(&schema, vec![0], true).unwrap();
+        table
+            .restore(store.clone(), "left", "left_evictions")
+            .unwrap();
+
+        let reference = chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap();
+        let mut records = vec![];
+        for (value, seconds) in [(1, 20), (2, 10), (3, 30)] {
+            let mut record = Record::new(vec![Field::Int(value)]);
+            record.set_lifetime(Some(Lifetime {
+                reference,
+                duration: std::time::Duration::from_secs(seconds),
+            }));
+            table.insert(record.clone(), &record).unwrap();
+            records.push(record);
+        }
+        table.remove(&records[2]).unwrap();
+
+        let mut restored = JoinTable::new(&schema, vec![0], true).unwrap();
+        restored.restore(store, "left", "left_evictions").unwrap();
+        for record in &records[..2] {
+            let join_key = restored.get_join_key(record);
+            assert_eq!(
+                restored
+                    .get_matching_records(&join_key, false)
+                    .collect::<Vec<_>>(),
+                vec![record]
+            );
+        }
+        let join_key = restored.get_join_key(&records[2]);
+        assert_eq!(restored.get_matching_records(&join_key, false).count(), 0);
+
+        // Evicts in eviction order although the entries are restored in key order.
+        restored
+            .evict_index(&(reference + chrono::Duration::seconds(15)))
+            .unwrap();
+        let join_key = restored.get_join_key(&records[0]);
+        assert_eq!(restored.get_matching_records(&join_key, false).count(), 1);
+        let join_key = restored.get_join_key(&records[1]);
+        assert_eq!(restored.get_matching_records(&join_key, false).count(), 0);
+    }
+
+    #[test]
+    fn test_spill() {
+        let records = (0..3)
+            .map(|value| Record::new(vec![Field::Int(value)]))
+            .collect::<Vec<_>>();
+        // Fits the records of one join key.
+        let memory_budget = encoded_size(&vec![records[0].clone()]).unwrap();
+        let store = StateStore::temporary()
+            .unwrap()
+            .with_memory_budget(Some(memory_budget));
+        let mut table = JoinTable::new(&schema(), vec![0], true).unwrap();
+        table.restore(store, "left", "left_evictions").unwrap();
+
+        for record in &records {
+            table.insert(record.clone(), record).unwrap();
+            table.spill();
+        }
+        let join_keys = records
+            .iter()
+            .map(|record| table.get_join_key(record))
+            .collect::<Vec<_>>();
+        assert_eq!(table.map.keys().collect::<Vec<_>>(), vec![&join_keys[2]]);
+
+        table.load(&join_keys[0]).unwrap();
+        assert_eq!(
+            table
+                .get_matching_records(&join_keys[0], false)
+                .collect::<Vec<_>>(),
+            vec![&records[0]]
+        );
+        table.spill();
+        assert_eq!(table.map.keys().collect::<Vec<_>>(), vec![&join_keys[0]]);
+
+        // A spilled record is read back to be removed.
+        table.remove(&records[1]).unwrap();
+        table.spill();
+        table.load(&join_keys[1]).unwrap();
+        assert_eq!(table.get_matching_records(&join_keys[1], false).count(), 0);
+    }
+
+    #[test]
+    fn test_evict_from_store() {
+        let reference = chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap();
+        let records = [(1, 20), (2, 10), (2, 10)]
+            .into_iter()
+            .map(|(value, seconds)| {
+                let mut record = Record::new(vec![Field::Int(value)]);
+                record.set_lifetime(Some(Lifetime {
+                    reference,
+                    duration: std::time::Duration::from_secs(seconds),
+                }));
+                record
+            })
+            .collect::<Vec<_>>();
+        let store = StateStore::temporary().unwrap().with_memory_budget(Some(0));
+        let mut table = JoinTable::new(&schema(), vec![0], true).unwrap();
+        table
+            .restore(store.clone(), "left", "left_evictions")
+            .unwrap();
+        for record in &records {
+            table.insert(record.clone(), record).unwrap();
+            table.spill();
+        }
+        assert!(table.lifetime_map.is_empty());
+
+        let mut restored = JoinTable::new(&schema(), vec![0], true).unwrap();
+        restored.restore(store, "left", "left_evictions").unwrap();
+        restored
+            .evict_index(&(reference + chrono::Duration::seconds(10)))
+            .unwrap();
+        let join_keys = records
+            .iter()
+            .map(|record| restored.get_join_key(record))
+            .collect::<Vec<_>>();
+        // Both records of the same key and instant are evicted.
+        restored.load(&join_keys[1]).unwrap();
+        assert_eq!(
+            restored.get_matching_records(&join_keys[1], false).count(),
+            0
+        );
+        restored.load(&join_keys[0]).unwrap();
+        assert_eq!(
+            restored.get_matching_records(&join_keys[0], false).count(),
+            1
+        );
+    }
 }
@@ -1,9 +1,12 @@
 use dozer_core::channels::ProcessorChannelForwarder;
 use dozer_core::epoch::Epoch;
-use dozer_core::node::Processor;
+use dozer_core::errors::SchemaChangeError;
+use dozer_core::node::{PortHandle, Processor};
+use dozer_core::state_store::StateStore;
 use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_sql_expression::builder::{extend_schema_source_def, NameOrAlias};
 use dozer_types::errors::internal::BoxedError;
-use dozer_types::types::{Lifetime, Operation, TableOperation};
+use dozer_types::types::{FieldDefinition, Lifetime, Operation, Schema, TableOperation};
 
 use crate::errors::PipelineError;
 
@@ -12,15 +15,33 @@ use super::operator::{JoinAction, JoinBranch, JoinOperator};
 #[derive(Debug)]
 pub struct ProductProcessor {
     join_operator: JoinOperator,
+    /// The names of both sides, which qualify their fields.
+    tables: (Option<NameOrAlias>, Option<NameOrAlias>),
+    /// The schemas of both sides, qualified by their names.
+    schemas: (Schema, Schema),
+    output_schema: Schema,
 }
 
 impl ProductProcessor {
-    pub fn new(_id: String, join_operator: JoinOperator) -> Self {
-        Self { join_operator }
+    pub fn new(
+        _id: String,
+        join_operator: JoinOperator,
+        tables: (Option<NameOrAlias>, Option<NameOrAlias>),
+        schemas: (Schema, Schema),
+        output_schema: Schema,
+    ) -> Self {
+        Self {
+            join_operator,
+            tables,
+            schemas,
+            output_schema,
+        }
     }
 
-    fn update_eviction_index(&mut self, lifetime: Lifetime) {
-        self.join_operator.evict_index(&lifetime.reference);
+    fn update_eviction_index(&mut self, lifetime: Lifetime) -> Result<(), PipelineError> {
+        self.join_operator
+            .evict_index(&lifetime.reference)
+            .map_err(PipelineError::JoinError)
     }
 }
 
@@ -42,14 +63,16 @@ impl Processor for ProductProcessor {
         let records = match op.op {
             Operation::Delete { old } => {
                 if let Some(lifetime) = old.get_lifetime() {
-                    self.update_eviction_index(lifetime);
+                    self.update_eviction_index(lifetime)?;
                 }
 
-                self.join_operator.delete(from_branch, &old, &old)
+                self.join_operator
+                    .delete(from_branch, &old, &old)
+                    .map_err(PipelineError::JoinError)?
             }
             Operation::Insert { new } => {
                 if let Some(lifetime) = new.get_lifetime() {
-                    self.update_eviction_index(lifetime);
+                    self.update_eviction_index(lifetime)?;
                 }
 
                 self.join_operator
@@ -58,10 +81,13 @@ impl Processor for ProductProcessor {
             }
             Operation::Update { old, new } => {
                 if let Some(lifetime) = old.get_lifetime() {
-                    self.update_eviction_index(lifetime);
+                    self.update_eviction_index(lifetime)?;
                 }
 
-                let mut old_records = self.join_operator.delete(from_branch, &old, &old);
+                let mut old_records = self
+                    .join_operator
+                    .delete(from_branch, &old, &old)
+                    .map_err(PipelineError::JoinError)?;
 
                 let new_records = self
                     .join_operator
@@ -106,22 +132,124 @@ impl Processor for ProductProcessor {
 
         Ok(())
     }
+
+    fn on_schema_change(
+        &mut self,
+        port: PortHandle,
+        schema: &Schema,
+    ) -> Result<Vec<(PortHandle, Schema)>, BoxedError> {
+        let (branch, table, current) = match port {
+            0 => (JoinBranch::Left, &self.tables.0, &mut self.schemas.0),
+            1 => (JoinBranch::Right, &self.tables.1, &mut self.schemas.1),
+            _ => return Err(PipelineError::InvalidPortHandle(port).into()),
+        };
+        let schema = match table {
+            Some(table) => extend_schema_source_def(schema, table),
+            None => schema.clone(),
+        };
+        // The join keys and the predicate refer to the fields the join was planned with, which keep
+        // their index. Only their nullability may have been widened.
+        if schema.fields.len() < current.fields.len()
+            || current
+                .fields
+                .iter()
+                .zip(&schema.fields)
+                .any(|(current, new)| {
+                    current.name != new.name
+                        || current.source != new.source
+                        || current.typ != new.typ
+                })
+        {
+            return Err(SchemaChangeError::Unsupported.into());
+        }
+
+        for (index, field) in schema.fields.iter().enumerate() {
+            match self.join_operator.output_index(branch, index) {
+                Some(output_index) => {
+                    self.output_schema.fields[output_index].nullable |= field.nullable;
+                }
+                None => {
+                    // Records joined before the field was appended don't have it.
+                    self.join_operator.append_field(branch, index);
+                    self.output_schema.fields.push(FieldDefinition {
+                        nullable: true,
+                        ..field.clone()
+                    });
+                }
+            }
+        }
+        *current = schema;
+        Ok(vec![(DEFAULT_PORT_HANDLE, self.output_schema.clone())])
+    }
+
+    fn on_truncate(&mut self, port: PortHandle) -> Result<Vec<PortHandle>, BoxedError> {
+        let branch = match port {
+            0 => JoinBranch::Left,
+            1 => JoinBranch::Right,
+            _ => return Err(PipelineError::InvalidPortHandle(port).into()),
+        };
+        self.join_operator
+            .truncate(branch)
+            .map_err(PipelineError::JoinError)?;
+        Ok(vec![DEFAULT_PORT_HANDLE])
+    }
+
+    fn after_truncate(
+        &mut self,
+        port: PortHandle,
+        fw: &mut dyn ProcessorChannelForwarder,
+    ) -> Result<(), BoxedError> {
+        let branch = match port {
+            0 => JoinBranch::Left,
+            1 => JoinBranch::Right,
+            _ => return Err(PipelineError::InvalidPortHandle(port).into()),
+        };
+        // The records the outer join keeps have no match left.
+        self.join_operator
+            .for_each_unmatched_record(branch, |record| {
+                fw.send(TableOperation::without_id(
+                    Operation::Insert { new: record },
+                    DEFAULT_PORT_HANDLE,
+                ))
+            })
+            .map_err(PipelineError::JoinError)?;
+        Ok(())
+    }
+
+    fn is_stateful(&self) -> bool {
+        true
+    }
+
+    fn restore(&mut self, store: StateStore) -> Result<(), BoxedError> {
+        self.join_operator
+            .restore(store)
+            .map_err(PipelineError::JoinError)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::{
+        collections::HashMap,
+        sync::{Arc, OnceLock},
+    };
 
     use dozer_core::{event::EventHub, node::ProcessorFactory};
     use dozer_sql_expression::builder::NameOrAlias;
     use dozer_sql_expression::sqlparser::ast::JoinOperator as SqlJoinOperator;
-    use dozer_types::types::{Field, FieldDefinition, Record, Schema};
+    use dozer_types::chrono::{DateTime, Duration};
+    use dozer_types::types::{Field, FieldDefinition, FieldType, Record, Schema};
+    use tokio::runtime::Runtime;
 
     use crate::product::join::{
         factory::{LEFT_JOIN_PORT, RIGHT_JOIN_PORT},
         operator::JoinType,
     };
-    use crate::{product::join::factory::JoinProcessorFactory, tests::utils::get_select};
+    use crate::{
+        product::join::factory::JoinProcessorFactory,
+        tests::utils::{create_test_runtime, get_select},
+    };
 
     use super::*;
 
@@ -136,6 +264,10 @@ mod tests {
     }
 
     fn create_schema(table_name: &'static str) -> Schema {
+        create_schema_with_data_type(table_name, FieldType::UInt)
+    }
+
+    fn create_schema_with_data_type(table_name: &'static str, data_type: FieldType) -> Schema {
         let mut schema = Schema::new();
         schema
             .field(
@@ -154,7 +286,7 @@ mod tests {
             .field(
                 FieldDefinition {
                     name: "data".into(),
-                    typ: dozer_types::types::FieldType::UInt,
+                    typ: data_type,
                     nullable: false,
                     source: dozer_types::types::SourceDefinition::Table {
                         connection: "test".into(),
@@ -177,14 +309,33 @@ mod tests {
         forwarder: TestChannelForwarder,
     }
 
+    /// The runtime of the expressions of the join constraints, for UDFs. It's shared by the tests and never dropped,
+    /// as a runtime can't be dropped in the async context of a test.
+    fn runtime() -> Arc<Runtime> {
+        static RUNTIME: OnceLock<Arc<Runtime>> = OnceLock::new();
+        RUNTIME.get_or_init(create_test_runtime).clone()
+    }
+
     impl Executor {
         async fn new(kind: JoinType) -> Self {
-            let left_schema = create_schema("left");
-            let right_schema = create_schema("right");
-
-            let stmt = get_select(
-                "SELECT left.joinkey FROM left INNER JOIN right ON left.joinkey = right.joinkey",
+            Self::with_constraint(
+                kind,
+                "left.joinkey = right.joinkey",
+                create_schema("left"),
+                create_schema("right"),
             )
+            .await
+        }
+
+        async fn with_constraint(
+            kind: JoinType,
+            constraint: &str,
+            left_schema: Schema,
+            right_schema: Schema,
+        ) -> Self {
+            let stmt = get_select(&format!(
+                "SELECT left.joinkey FROM left INNER JOIN right ON {constraint}"
+            ))
             .unwrap();
             let join = &stmt.from[0].joins[0];
             let join_op = join.join_operator.clone();
@@ -195,6 +346,7 @@ mod tests {
                 JoinType::Inner => SqlJoinOperator::Inner(constraint),
                 JoinType::LeftOuter => SqlJoinOperator::LeftOuter(constraint),
                 JoinType::RightOuter => SqlJoinOperator::RightOuter(constraint),
+                JoinType::FullOuter => SqlJoinOperator::FullOuter(constraint),
             };
             let factory = JoinProcessorFactory::new(
                 "test".into(),
@@ -202,6 +354,8 @@ mod tests {
                 Some(NameOrAlias("right".into(), None)),
                 join_op,
                 false,
+                vec![],
+                runtime(),
             );
 
             let schemas = [
@@ -434,4 +588,426 @@ mod tests {
             },]
         );
     }
+
+    #[tokio::test]
+    async fn test_full_outer_join() {
+        let mut exec = Executor::new(JoinType::FullOuter).await;
+
+        let null_record = Record::new(vec![Field::Null, Field::Null]);
+
+        let (left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(0), Field::UInt(1)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), null_record.clone())
+            }]
+        );
+
+        let (right_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(2)]);
+        assert_eq!(
+            ops,
+            &[
+                Operation::Delete {
+                    old: join_record(left_record.clone(), null_record.clone())
+                },
+                Operation::Insert {
+                    new: join_record(left_record.clone(), right_record.clone())
+                }
+            ]
+        );
+
+        let (other_right_record, ops) =
+            exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(3)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), other_right_record.clone())
+            }]
+        );
+
+        assert_eq!(
+            exec.delete(JoinSide::Right, right_record.clone()),
+            &[Operation::Delete {
+                old: join_record(left_record.clone(), right_record.clone())
+            }]
+        );
+        assert_eq!(
+            exec.delete(JoinSide::Right, other_right_record.clone()),
+            &[
+                Operation::Delete {
+                    old: join_record(left_record.clone(), other_right_record.clone())
+                },
+                Operation::Insert {
+                    new: join_record(left_record.clone(), null_record.clone())
+                }
+            ]
+        );
+
+        assert_eq!(
+            exec.delete(JoinSide::Left, left_record.clone()),
+            &[Operation::Delete {
+                old: join_record(left_record.clone(), null_record.clone())
+            }]
+        );
+
+        let (right_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(2)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(null_record.clone(), right_record.clone())
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_full_outer_join_eviction() {
+        let mut exec = Executor::new(JoinType::FullOuter).await;
+
+        let null_record = Record::new(vec![Field::Null, Field::Null]);
+        let reference = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap();
+        let lifetime = |seconds| Lifetime {
+            reference: reference + Duration::seconds(seconds),
+            duration: std::time::Duration::from_secs(10),
+        };
+        let with_lifetime = |mut record: Record, seconds| {
+            record.set_lifetime(Some(lifetime(seconds)));
+            record
+        };
+
+        let left_record = with_lifetime(Record::new(vec![Field::UInt(0), Field::UInt(1)]), 0);
+        let ops = exec.do_op(
+            Operation::Insert {
+                new: left_record.clone(),
+            },
+            JoinSide::Left,
+        );
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: with_lifetime(join_record(left_record.clone(), null_record.clone()), 0)
+            }]
+        );
+
+        // The left record is evicted before the right one is joined, so it's not retracted.
+        let right_record = with_lifetime(Record::new(vec![Field::UInt(0), Field::UInt(2)]), 20);
+        let ops = exec.do_op(
+            Operation::Insert {
+                new: right_record.clone(),
+            },
+            JoinSide::Right,
+        );
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: with_lifetime(join_record(null_record.clone(), right_record.clone()), 20)
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_inner_join_with_residual_predicate() {
+        let mut exec = Executor::with_constraint(
+            JoinType::Inner,
+            "left.joinkey = right.joinkey AND left.data > right.data",
+            create_schema("left"),
+            create_schema("right"),
+        )
+        .await;
+
+        let (left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(0), Field::UInt(5)]);
+        assert_eq!(ops, &[]);
+
+        let (small_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(3)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), small_record.clone())
+            }]
+        );
+        let (large_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(7)]);
+        assert_eq!(ops, &[]);
+
+        let (new_left_record, ops) = exec.update(
+            JoinSide::Left,
+            left_record.clone(),
+            &[Field::UInt(0), Field::UInt(8)],
+        );
+        let mut inserts = ops[1..].to_vec();
+        inserts.sort_by_key(|op| format!("{op:?}"));
+        assert_eq!(
+            ops[0],
+            Operation::Delete {
+                old: join_record(left_record, small_record.clone())
+            }
+        );
+        assert_eq!(
+            inserts,
+            &[
+                Operation::Insert {
+                    new: join_record(new_left_record.clone(), small_record)
+                },
+                Operation::Insert {
+                    new: join_record(new_left_record, large_record)
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_left_outer_join_with_residual_predicate() {
+        let mut exec = Executor::with_constraint(
+            JoinType::LeftOuter,
+            "left.joinkey = right.joinkey AND left.data < right.data",
+            create_schema("left"),
+            create_schema("right"),
+        )
+        .await;
+        let null_record = Record::new(vec![Field::Null, Field::Null]);
+
+        let (left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(0), Field::UInt(5)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), null_record.clone())
+            }]
+        );
+
+        // Has the join key, but doesn't satisfy the predicate.
+        let (_, ops) = exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(3)]);
+        assert_eq!(ops, &[]);
+
+        let (right_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(7)]);
+        assert_eq!(
+            ops,
+            &[
+                Operation::Delete {
+                    old: join_record(left_record.clone(), null_record.clone())
+                },
+                Operation::Insert {
+                    new: join_record(left_record.clone(), right_record.clone())
+                },
+            ]
+        );
+
+        assert_eq!(
+            exec.delete(JoinSide::Right, right_record.clone()),
+            &[
+                Operation::Delete {
+                    old: join_record(left_record.clone(), right_record)
+                },
+                Operation::Insert {
+                    new: join_record(left_record, null_record)
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interval_join() {
+        let mut exec = Executor::with_constraint(
+            JoinType::Inner,
+            "left.joinkey = right.joinkey \
+                AND left.data BETWEEN right.data - INTERVAL '10' SECOND AND right.data",
+            create_schema_with_data_type("left", FieldType::Timestamp),
+            create_schema_with_data_type("right", FieldType::Timestamp),
+        )
+        .await;
+        let reference = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap();
+        let time = |seconds| Field::Timestamp(reference + Duration::seconds(seconds));
+
+        let (right_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(0), time(0)]);
+        assert_eq!(ops, &[]);
+
+        let (left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(0), time(-5)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record.clone(), right_record)
+            }]
+        );
+        let (_, ops) = exec.insert(JoinSide::Left, &[Field::UInt(0), time(-20)]);
+        assert_eq!(ops, &[]);
+
+        // Right time 30s is past the range of the left records, but they have no lifetime and are kept.
+        let (right_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(0), time(30)]);
+        assert_eq!(ops, &[]);
+
+        // So a late right record still matches the left record at -5s.
+        let (late_right_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(0), time(-3)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record, late_right_record)
+            }]
+        );
+
+        let (left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(0), time(25)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record, right_record)
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interval_join_eviction() {
+        let mut exec = Executor::with_constraint(
+            JoinType::Inner,
+            "left.joinkey = right.joinkey \
+                AND left.data BETWEEN right.data - INTERVAL '10' SECOND AND right.data",
+            create_schema_with_data_type("left", FieldType::Timestamp),
+            create_schema_with_data_type("right", FieldType::Timestamp),
+        )
+        .await;
+        let reference = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap();
+        // Records with a TTL of an hour, which is longer than the interval.
+        let record = |seconds| {
+            let time = reference + Duration::seconds(seconds);
+            let mut record = Record::new(vec![Field::UInt(0), Field::Timestamp(time)]);
+            record.set_lifetime(Some(Lifetime {
+                reference: time,
+                duration: std::time::Duration::from_secs(3600),
+            }));
+            record
+        };
+        let mut insert = |side, record: &Record| {
+            exec.do_op(
+                Operation::Insert {
+                    new: record.clone(),
+                },
+                side,
+            )
+        };
+
+        assert_eq!(insert(JoinSide::Right, &record(0)), &[]);
+        let mut joined = join_record(record(-5), record(0));
+        joined.set_lifetime(record(0).get_lifetime());
+        assert_eq!(
+            insert(JoinSide::Left, &record(-5)),
+            &[Operation::Insert { new: joined }]
+        );
+
+        // Right time 30s is past the range of the left record, which is evicted.
+        assert_eq!(insert(JoinSide::Right, &record(30)), &[]);
+        assert_eq!(insert(JoinSide::Right, &record(-3)), &[]);
+    }
+
+    #[tokio::test]
+    async fn test_appended_field() {
+        let mut exec = Executor::new(JoinType::Inner).await;
+
+        let (left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(0), Field::UInt(1)]);
+        assert_eq!(ops, &[]);
+
+        let mut left_schema = create_schema("left");
+        left_schema.field(
+            FieldDefinition {
+                name: "extra".into(),
+                typ: FieldType::String,
+                nullable: false,
+                source: dozer_types::types::SourceDefinition::Table {
+                    connection: "test".into(),
+                    name: "left".into(),
+                },
+                description: None,
+            },
+            false,
+        );
+        let changes = exec
+            .processor
+            .on_schema_change(LEFT_JOIN_PORT, &left_schema)
+            .unwrap();
+        let [(DEFAULT_PORT_HANDLE, output_schema)] = changes.as_slice() else {
+            panic!("unexpected schema changes {changes:?}");
+        };
+        let names = output_schema
+            .fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["joinkey", "data", "joinkey", "data", "extra"]);
+        assert!(output_schema.fields[4].nullable);
+
+        // The appended field comes after the right fields, and records joined before it was appended don't have it.
+        let (right_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(2)]);
+        let mut expected = join_record(left_record, right_record.clone());
+        expected.values.push(Field::Null);
+        assert_eq!(ops, &[Operation::Insert { new: expected }]);
+
+        let (_, ops) = exec.insert(
+            JoinSide::Left,
+            &[Field::UInt(0), Field::UInt(3), Field::String("x".into())],
+        );
+        let mut expected = join_record(
+            Record::new(vec![Field::UInt(0), Field::UInt(3)]),
+            right_record,
+        );
+        expected.values.push(Field::String("x".into()));
+        assert_eq!(ops, &[Operation::Insert { new: expected }]);
+    }
+
+    #[tokio::test]
+    async fn test_truncate() {
+        let mut exec = Executor::new(JoinType::Inner).await;
+
+        exec.insert(JoinSide::Left, &[Field::UInt(0), Field::UInt(1)]);
+        let (right_record, _) = exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(2)]);
+
+        let ports = exec.processor.on_truncate(LEFT_JOIN_PORT).unwrap();
+        assert_eq!(ports, [DEFAULT_PORT_HANDLE]);
+
+        // The right records are kept and match the left records inserted since.
+        let (left_record, ops) = exec.insert(JoinSide::Left, &[Field::UInt(0), Field::UInt(3)]);
+        assert_eq!(
+            ops,
+            &[Operation::Insert {
+                new: join_record(left_record, right_record)
+            }]
+        );
+
+        // The left records of a left outer join are kept when the right input is truncated, and are
+        // sent again padded with nulls.
+        let mut exec = Executor::new(JoinType::LeftOuter).await;
+        let (left_record, _) = exec.insert(JoinSide::Left, &[Field::UInt(0), Field::UInt(1)]);
+        exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(2)]);
+        let ports = exec.processor.on_truncate(RIGHT_JOIN_PORT).unwrap();
+        assert_eq!(ports, [DEFAULT_PORT_HANDLE]);
+        exec.processor
+            .after_truncate(RIGHT_JOIN_PORT, &mut exec.forwarder)
+            .unwrap();
+        let padded = join_record(
+            left_record.clone(),
+            Record::new(vec![Field::Null, Field::Null]),
+        );
+        assert_eq!(
+            exec.forwarder
+                .operations
+                .iter()
+                .map(|op| &op.op)
+                .collect::<Vec<_>>(),
+            [&Operation::Insert {
+                new: padded.clone()
+            }]
+        );
+
+        // They still match the right records inserted since, and can be deleted.
+        let (right_record, ops) = exec.insert(JoinSide::Right, &[Field::UInt(0), Field::UInt(3)]);
+        assert_eq!(
+            ops,
+            &[
+                Operation::Delete { old: padded },
+                Operation::Insert {
+                    new: join_record(left_record.clone(), right_record.clone())
+                }
+            ]
+        );
+        let ops = exec.delete(JoinSide::Left, left_record.clone());
+        assert_eq!(
+            ops,
+            &[Operation::Delete {
+                old: join_record(left_record, right_record)
+            }]
+        );
+    }
 }
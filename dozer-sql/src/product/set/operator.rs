@@ -2,6 +2,7 @@ use super::record_map::{CountingRecordMap, CountingRecordMapEnum};
 use crate::errors::PipelineError;
 use dozer_sql_expression::sqlparser::ast::{SetOperator, SetQuantifier};
 use dozer_types::types::Record;
+use std::cmp::Ordering;
 
 #[derive(Clone, Debug, PartialEq, Eq, Copy)]
 pub enum SetAction {
@@ -10,6 +11,13 @@ pub enum SetAction {
     // Update,
 }
 
+/// The input a record comes from.
+#[derive(Clone, Debug, PartialEq, Eq, Copy)]
+pub enum SetBranch {
+    Left,
+    Right,
+}
+
 #[derive(Clone, Debug)]
 pub struct SetOperation {
     pub op: SetOperator,
@@ -24,30 +32,93 @@ impl SetOperation {
         }
     }
 
+    /// Counts the records of the left input in `record_map`, and those of the right input in
+    /// `right_record_map`, so each input can be truncated on its own. UNION ALL doesn't count records.
     pub fn execute(
         &self,
         action: SetAction,
         record: Record,
+        branch: SetBranch,
         record_map: &mut CountingRecordMapEnum,
+        right_record_map: &mut CountingRecordMapEnum,
     ) -> Result<Vec<(SetAction, Record)>, PipelineError> {
         match (self.op, self.quantifier) {
             (SetOperator::Union, SetQuantifier::All) => Ok(vec![(action, record)]),
             (SetOperator::Union, SetQuantifier::None) => {
-                self.execute_union(action, record, record_map)
+                self.execute_union(action, record, branch, record_map, right_record_map)
+            }
+            (SetOperator::Intersect | SetOperator::Except, _) => {
+                self.execute_intersect_except(action, record, branch, record_map, right_record_map)
             }
             _ => Err(PipelineError::InvalidOperandType(self.op.to_string())),
         }
     }
 
+    /// Emits the difference between how many times the record is in the output before and after the action.
+    fn execute_intersect_except(
+        &self,
+        action: SetAction,
+        record: Record,
+        branch: SetBranch,
+        left_record_map: &mut CountingRecordMapEnum,
+        right_record_map: &mut CountingRecordMapEnum,
+    ) -> Result<Vec<(SetAction, Record)>, PipelineError> {
+        let before = self.output_count(&record, left_record_map, right_record_map);
+        let record_map = match branch {
+            SetBranch::Left => &mut *left_record_map,
+            SetBranch::Right => &mut *right_record_map,
+        };
+        self.update_map(record.clone(), action == SetAction::Delete, record_map);
+        let after = self.output_count(&record, left_record_map, right_record_map);
+
+        match after.cmp(&before) {
+            Ordering::Greater => Ok(vec![(SetAction::Insert, record); (after - before) as usize]),
+            Ordering::Less => Ok(vec![(SetAction::Delete, record); (before - after) as usize]),
+            Ordering::Equal => Ok(vec![]),
+        }
+    }
+
+    /// How many times `record` is in the output of UNION, INTERSECT or EXCEPT.
+    pub fn output_count(
+        &self,
+        record: &Record,
+        left_record_map: &CountingRecordMapEnum,
+        right_record_map: &CountingRecordMapEnum,
+    ) -> u64 {
+        let left_count = left_record_map.estimate_count(record);
+        let right_count = right_record_map.estimate_count(record);
+        match (self.op, self.quantifier) {
+            (SetOperator::Union, _) => (left_count > 0 || right_count > 0) as u64,
+            (SetOperator::Intersect, SetQuantifier::All) => left_count.min(right_count),
+            (SetOperator::Intersect, _) => (left_count > 0 && right_count > 0) as u64,
+            (SetOperator::Except, SetQuantifier::All) => left_count.saturating_sub(right_count),
+            _ => (left_count > 0 && right_count == 0) as u64,
+        }
+    }
+
+    /// Whether records of the other input are left in the output after the input `branch` is truncated.
+    pub fn keeps_other_input(&self, branch: SetBranch) -> bool {
+        matches!(
+            (self.op, branch),
+            (SetOperator::Union, _) | (SetOperator::Except, SetBranch::Right)
+        )
+    }
+
     fn execute_union(
         &self,
         action: SetAction,
         record: Record,
-        record_map: &mut CountingRecordMapEnum,
+        branch: SetBranch,
+        left_record_map: &mut CountingRecordMapEnum,
+        right_record_map: &mut CountingRecordMapEnum,
     ) -> Result<Vec<(SetAction, Record)>, PipelineError> {
+        let (record_map, other_record_map) = match branch {
+            SetBranch::Left => (left_record_map, &*right_record_map),
+            SetBranch::Right => (right_record_map, &*left_record_map),
+        };
         match action {
-            SetAction::Insert => self.union_insert(action, record, record_map),
-            SetAction::Delete => self.union_delete(action, record, record_map),
+            SetAction::Insert => self.union_insert(action, record, record_map, other_record_map),
+            SetAction::Delete => self.union_delete(action, record, record_map, other_record_map),
         }
     }
 
@@ -56,9 +127,10 @@ impl SetOperation {
         action: SetAction,
         record: Record,
         record_map: &mut CountingRecordMapEnum,
+        other_record_map: &CountingRecordMapEnum,
     ) -> Result<Vec<(SetAction, Record)>, PipelineError> {
         let _count = self.update_map(record.clone(), false, record_map);
-        if _count == 1 {
+        if _count == 1 && other_record_map.estimate_count(&record) == 0 {
             Ok(vec![(action, record)])
         } else {
             Ok(vec![])
@@ -70,9 +142,10 @@ impl SetOperation {
         action: SetAction,
         record: Record,
         record_map: &mut CountingRecordMapEnum,
+        other_record_map: &CountingRecordMapEnum,
     ) -> Result<Vec<(SetAction, Record)>, PipelineError> {
         let _count = self.update_map(record.clone(), true, record_map);
-        if _count == 0 {
+        if _count == 0 && other_record_map.estimate_count(&record) == 0 {
             Ok(vec![(action, record)])
         } else {
             Ok(vec![])
@@ -94,3 +167,129 @@ impl SetOperation {
         record_map.estimate_count(&record)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use dozer_types::types::Field;
+
+    use super::*;
+    use crate::product::set::record_map::AccurateCountingRecordMap;
+
+    struct Executor {
+        operation: SetOperation,
+        record_map: CountingRecordMapEnum,
+        right_record_map: CountingRecordMapEnum,
+    }
+
+    impl Executor {
+        fn new(op: SetOperator, quantifier: SetQuantifier) -> Self {
+            Self {
+                operation: SetOperation { op, quantifier },
+                record_map: AccurateCountingRecordMap::new().unwrap().into(),
+                right_record_map: AccurateCountingRecordMap::new().unwrap().into(),
+            }
+        }
+
+        fn execute(&mut self, action: SetAction, branch: SetBranch) -> Vec<SetAction> {
+            let record = Record::new(vec![Field::Int(1)]);
+            self.operation
+                .execute(
+                    action,
+                    record.clone(),
+                    branch,
+                    &mut self.record_map,
+                    &mut self.right_record_map,
+                )
+                .unwrap()
+                .into_iter()
+                .map(|(action, output)| {
+                    assert_eq!(output, record);
+                    action
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_intersect() {
+        for quantifier in [SetQuantifier::None, SetQuantifier::All] {
+            let mut exec = Executor::new(SetOperator::Intersect, quantifier);
+            assert_eq!(exec.execute(SetAction::Insert, SetBranch::Left), &[]);
+            assert_eq!(exec.execute(SetAction::Insert, SetBranch::Left), &[]);
+            assert_eq!(
+                exec.execute(SetAction::Insert, SetBranch::Right),
+                &[SetAction::Insert]
+            );
+            let expected: &[SetAction] = match quantifier {
+                SetQuantifier::All => &[SetAction::Insert],
+                _ => &[],
+            };
+            assert_eq!(exec.execute(SetAction::Insert, SetBranch::Right), expected);
+            assert_eq!(exec.execute(SetAction::Insert, SetBranch::Right), &[]);
+
+            assert_eq!(exec.execute(SetAction::Delete, SetBranch::Right), &[]);
+            let expected: &[SetAction] = match quantifier {
+                SetQuantifier::All => &[SetAction::Delete],
+                _ => &[],
+            };
+            assert_eq!(exec.execute(SetAction::Delete, SetBranch::Left), expected);
+            assert_eq!(
+                exec.execute(SetAction::Delete, SetBranch::Left),
+                &[SetAction::Delete]
+            );
+        }
+    }
+
+    #[test]
+    fn test_union() {
+        let mut exec = Executor::new(SetOperator::Union, SetQuantifier::None);
+        assert_eq!(
+            exec.execute(SetAction::Insert, SetBranch::Left),
+            &[SetAction::Insert]
+        );
+        assert_eq!(exec.execute(SetAction::Insert, SetBranch::Right), &[]);
+        assert_eq!(exec.execute(SetAction::Delete, SetBranch::Left), &[]);
+        assert_eq!(
+            exec.execute(SetAction::Delete, SetBranch::Right),
+            &[SetAction::Delete]
+        );
+    }
+
+    #[test]
+    fn test_except() {
+        let mut exec = Executor::new(SetOperator::Except, SetQuantifier::None);
+        assert_eq!(
+            exec.execute(SetAction::Insert, SetBranch::Left),
+            &[SetAction::Insert]
+        );
+        assert_eq!(exec.execute(SetAction::Insert, SetBranch::Left), &[]);
+        assert_eq!(
+            exec.execute(SetAction::Insert, SetBranch::Right),
+            &[SetAction::Delete]
+        );
+        assert_eq!(
+            exec.execute(SetAction::Delete, SetBranch::Right),
+            &[SetAction::Insert]
+        );
+
+        let mut exec = Executor::new(SetOperator::Except, SetQuantifier::All);
+        assert_eq!(exec.execute(SetAction::Insert, SetBranch::Right), &[]);
+        assert_eq!(exec.execute(SetAction::Insert, SetBranch::Left), &[]);
+        assert_eq!(
+            exec.execute(SetAction::Insert, SetBranch::Left),
+            &[SetAction::Insert]
+        );
+        assert_eq!(
+            exec.execute(SetAction::Insert, SetBranch::Left),
+            &[SetAction::Insert]
+        );
+        assert_eq!(
+            exec.execute(SetAction::Delete, SetBranch::Right),
+            &[SetAction::Insert]
+        );
+        assert_eq!(
+            exec.execute(SetAction::Insert, SetBranch::Right),
+            &[SetAction::Delete]
+        );
+    }
+}
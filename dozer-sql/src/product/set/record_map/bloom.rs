@@ -51,6 +51,19 @@ impl CountingBloomFilter {
     pub fn clear(&mut self) {
         self.counters.iter_mut().for_each(|counter| *counter = 0);
     }
+
+    /// The counters `value` is counted in.
+    pub fn slots<V: Hash>(&self, value: &V) -> impl Iterator<Item = usize> {
+        calculate_slots(&self.hasher, value, self.num_hashes, self.counters.len())
+    }
+
+    pub fn counter(&self, slot: usize) -> u8 {
+        self.counters[slot]
+    }
+
+    pub fn set_counter(&mut self, slot: usize, counter: u8) {
+        self.counters[slot] = counter;
+    }
 }
 
 fn optimal_num_counters(num_items: u32, false_positive_rate: f32) -> usize {
@@ -1,3 +1,4 @@
+use dozer_core::state_store::{StateStore, StateStoreError};
 use dozer_types::{
     errors::types::DeserializationError,
     serde::{Deserialize, Serialize},
@@ -26,6 +27,20 @@ pub trait CountingRecordMap {
 
     /// Clears the map, removing all records.
     fn clear(&mut self);
+
+    /// The count of every record in the map, unless the map only keeps estimates of the counts.
+    fn counts(&self) -> Option<&HashMap<Record, u64>>;
+
+    /// Writes the count of `record` through to `namespace` of `store`, after it's inserted or removed.
+    fn write_through(
+        &self,
+        record: &Record,
+        store: &StateStore,
+        namespace: &str,
+    ) -> Result<(), StateStoreError>;
+
+    /// Loads the counts written through to `namespace` of `store`.
+    fn restore(&mut self, store: &StateStore, namespace: &str) -> Result<(), StateStoreError>;
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -65,6 +80,27 @@ impl CountingRecordMap for AccurateCountingRecordMap {
     fn clear(&mut self) {
         self.map.clear();
     }
+
+    fn counts(&self) -> Option<&HashMap<Record, u64>> {
+        Some(&self.map)
+    }
+
+    fn write_through(
+        &self,
+        record: &Record,
+        store: &StateStore,
+        namespace: &str,
+    ) -> Result<(), StateStoreError> {
+        match self.map.get(record) {
+            Some(count) => store.put(namespace, record, count),
+            None => store.delete(namespace, record),
+        }
+    }
+
+    fn restore(&mut self, store: &StateStore, namespace: &str) -> Result<(), StateStoreError> {
+        self.map = store.entries(namespace)?.into_iter().collect();
+        Ok(())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -103,12 +139,39 @@ impl CountingRecordMap for ProbabilisticCountingRecordMap {
     fn clear(&mut self) {
         self.map.clear();
     }
+
+    fn counts(&self) -> Option<&HashMap<Record, u64>> {
+        None
+    }
+
+    fn write_through(
+        &self,
+        record: &Record,
+        store: &StateStore,
+        namespace: &str,
+    ) -> Result<(), StateStoreError> {
+        for slot in self.map.slots(record) {
+            match self.map.counter(slot) {
+                0 => store.delete(namespace, &(slot as u64))?,
+                counter => store.put(namespace, &(slot as u64), &counter)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn restore(&mut self, store: &StateStore, namespace: &str) -> Result<(), StateStoreError> {
+        for (slot, counter) in store.entries::<u64, u8>(namespace)? {
+            self.map.set_counter(slot as usize, counter);
+        }
+        Ok(())
+    }
 }
 
 mod bloom;
 
 #[cfg(test)]
 mod tests {
+    use dozer_core::state_store::StateStore;
     use dozer_types::types::{Field, Record};
 
     use super::{
@@ -148,6 +211,23 @@ mod tests {
         assert_eq!(map.estimate_count(&b), 0);
     }
 
+    fn test_restore(mut map: CountingRecordMapEnum, mut restored: CountingRecordMapEnum) {
+        let store = StateStore::in_memory().unwrap();
+        let a = Record::new(vec![Field::String('a'.into())]);
+        let b = Record::new(vec![Field::String('b'.into())]);
+
+        for record in [&a, &b, &b] {
+            map.insert(record);
+            map.write_through(record, &store, "records").unwrap();
+        }
+        map.remove(&a);
+        map.write_through(&a, &store, "records").unwrap();
+
+        restored.restore(&store, "records").unwrap();
+        assert_eq!(restored.estimate_count(&a), 0);
+        assert_eq!(restored.estimate_count(&b), 2);
+    }
+
     #[test]
     fn test_maps() {
         let accurate_map = AccurateCountingRecordMap::new().unwrap().into();
@@ -156,4 +236,16 @@ mod tests {
         let probabilistic_map = ProbabilisticCountingRecordMap::new().unwrap().into();
         test_map(probabilistic_map);
     }
+
+    #[test]
+    fn test_maps_restore() {
+        test_restore(
+            AccurateCountingRecordMap::new().unwrap().into(),
+            AccurateCountingRecordMap::new().unwrap().into(),
+        );
+        test_restore(
+            ProbabilisticCountingRecordMap::new().unwrap().into(),
+            ProbabilisticCountingRecordMap::new().unwrap().into(),
+        );
+    }
 }
@@ -8,7 +8,6 @@ use dozer_core::{
     node::{PortHandle, Processor, ProcessorFactory},
     DEFAULT_PORT_HANDLE,
 };
-use dozer_sql_expression::sqlparser::ast::{SetOperator, SetQuantifier};
 use dozer_types::errors::internal::BoxedError;
 use dozer_types::tonic::async_trait;
 use dozer_types::types::{FieldDefinition, Schema, SourceDefinition};
@@ -19,7 +18,7 @@ use super::set_processor::SetProcessor;
 #[derive(Debug)]
 pub struct SetProcessorFactory {
     id: String,
-    set_quantifier: SetQuantifier,
+    set_operation: SetOperation,
     enable_probabilistic_optimizations: bool,
 }
 
@@ -27,12 +26,12 @@ impl SetProcessorFactory {
     /// Creates a new [`FromProcessorFactory`].
     pub fn new(
         id: String,
-        set_quantifier: SetQuantifier,
+        set_operation: SetOperation,
         enable_probabilistic_optimizations: bool,
     ) -> Self {
         Self {
             id,
-            set_quantifier,
+            set_operation,
             enable_probabilistic_optimizations,
         }
     }
@@ -78,10 +77,7 @@ impl ProcessorFactory for SetProcessorFactory {
     ) -> Result<Box<dyn Processor>, BoxedError> {
         Ok(Box::new(SetProcessor::new(
             self.id.clone(),
-            SetOperation {
-                op: SetOperator::Union,
-                quantifier: self.set_quantifier,
-            },
+            self.set_operation.clone(),
             self.enable_probabilistic_optimizations,
         )?))
     }
@@ -1,22 +1,32 @@
-use super::operator::{SetAction, SetOperation};
+use super::operator::{SetAction, SetBranch, SetOperation};
 use super::record_map::{
-    AccurateCountingRecordMap, CountingRecordMapEnum, ProbabilisticCountingRecordMap,
+    AccurateCountingRecordMap, CountingRecordMap, CountingRecordMapEnum,
+    ProbabilisticCountingRecordMap,
 };
 use crate::errors::{PipelineError, ProductError, SetError};
 use dozer_core::channels::ProcessorChannelForwarder;
 use dozer_core::epoch::Epoch;
-use dozer_core::node::Processor;
+use dozer_core::node::{PortHandle, Processor};
+use dozer_core::state_store::StateStore;
 use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_sql_expression::sqlparser::ast::{SetOperator, SetQuantifier};
 use dozer_types::errors::internal::BoxedError;
 use dozer_types::types::{Operation, Record, TableOperation};
 use std::fmt::{Debug, Formatter};
 
+const RECORDS_NAMESPACE: &str = "records";
+const RIGHT_RECORDS_NAMESPACE: &str = "right_records";
+
 pub struct SetProcessor {
     _id: String,
     /// Set operations
     operator: SetOperation,
-    /// Hashmap containing records with its occurrence
+    /// Hashmap containing records with its occurrence, of both inputs for UNION and of the left input otherwise
     record_map: CountingRecordMapEnum,
+    /// Hashmap containing the records of the right input with their occurrence, for INTERSECT and EXCEPT
+    right_record_map: CountingRecordMapEnum,
+    /// The store record occurrences are written through to
+    state_store: Option<StateStore>,
 }
 
 impl SetProcessor {
@@ -28,28 +38,72 @@ impl SetProcessor {
     ) -> Result<Self, SetError> {
         Ok(Self {
             _id: id,
+            record_map: new_record_map(enable_probabilistic_optimizations)?,
+            right_record_map: new_record_map(enable_probabilistic_optimizations)?,
             operator,
-            record_map: if enable_probabilistic_optimizations {
-                ProbabilisticCountingRecordMap::new()?.into()
-            } else {
-                AccurateCountingRecordMap::new()?.into()
-            },
+            state_store: None,
         })
     }
 
-    fn delete(&mut self, record: Record) -> Result<Vec<(SetAction, Record)>, ProductError> {
-        self.operator
-            .execute(SetAction::Delete, record, &mut self.record_map)
+    fn execute(
+        &mut self,
+        action: SetAction,
+        record: Record,
+        branch: SetBranch,
+    ) -> Result<Vec<(SetAction, Record)>, PipelineError> {
+        let Some(store) = &self.state_store else {
+            return self.operator.execute(
+                action,
+                record,
+                branch,
+                &mut self.record_map,
+                &mut self.right_record_map,
+            );
+        };
+        let records = self.operator.execute(
+            action,
+            record.clone(),
+            branch,
+            &mut self.record_map,
+            &mut self.right_record_map,
+        )?;
+        match branch {
+            SetBranch::Left => self
+                .record_map
+                .write_through(&record, store, RECORDS_NAMESPACE)?,
+            SetBranch::Right => {
+                self.right_record_map
+                    .write_through(&record, store, RIGHT_RECORDS_NAMESPACE)?
+            }
+        }
+        Ok(records)
+    }
+
+    fn delete(
+        &mut self,
+        record: Record,
+        branch: SetBranch,
+    ) -> Result<Vec<(SetAction, Record)>, ProductError> {
+        self.execute(SetAction::Delete, record, branch)
             .map_err(|err| {
-                ProductError::DeleteError("UNION query error:".to_string(), Box::new(err))
+                ProductError::DeleteError(
+                    format!("{} query error:", self.operator.op),
+                    Box::new(err),
+                )
             })
     }
 
-    fn insert(&mut self, record: Record) -> Result<Vec<(SetAction, Record)>, ProductError> {
-        self.operator
-            .execute(SetAction::Insert, record, &mut self.record_map)
+    fn insert(
+        &mut self,
+        record: Record,
+        branch: SetBranch,
+    ) -> Result<Vec<(SetAction, Record)>, ProductError> {
+        self.execute(SetAction::Insert, record, branch)
             .map_err(|err| {
-                ProductError::InsertError("UNION query error:".to_string(), Box::new(err))
+                ProductError::InsertError(
+                    format!("{} query error:", self.operator.op),
+                    Box::new(err),
+                )
             })
     }
 
@@ -58,19 +112,24 @@ impl SetProcessor {
         &mut self,
         old: Record,
         new: Record,
+        branch: SetBranch,
     ) -> Result<(Vec<(SetAction, Record)>, Vec<(SetAction, Record)>), ProductError> {
         let old_records = self
-            .operator
-            .execute(SetAction::Delete, old, &mut self.record_map)
+            .execute(SetAction::Delete, old, branch)
             .map_err(|err| {
-                ProductError::UpdateOldError("UNION query error:".to_string(), Box::new(err))
+                ProductError::UpdateOldError(
+                    format!("{} query error:", self.operator.op),
+                    Box::new(err),
+                )
             })?;
 
         let new_records = self
-            .operator
-            .execute(SetAction::Insert, new, &mut self.record_map)
+            .execute(SetAction::Insert, new, branch)
             .map_err(|err| {
-                ProductError::UpdateNewError("UNION query error:".to_string(), Box::new(err))
+                ProductError::UpdateNewError(
+                    format!("{} query error:", self.operator.op),
+                    Box::new(err),
+                )
             })?;
 
         Ok((old_records, new_records))
@@ -93,9 +152,12 @@ impl Processor for SetProcessor {
         op: TableOperation,
         fw: &mut dyn ProcessorChannelForwarder,
     ) -> Result<(), BoxedError> {
+        let branch = get_branch(op.port)?;
         match op.op {
             Operation::Delete { old } => {
-                let records = self.delete(old).map_err(PipelineError::ProductError)?;
+                let records = self
+                    .delete(old, branch)
+                    .map_err(PipelineError::ProductError)?;
 
                 for (action, record) in records.into_iter() {
                     match action {
@@ -115,7 +177,9 @@ impl Processor for SetProcessor {
                 }
             }
             Operation::Insert { new } => {
-                let records = self.insert(new).map_err(PipelineError::ProductError)?;
+                let records = self
+                    .insert(new, branch)
+                    .map_err(PipelineError::ProductError)?;
 
                 for (action, record) in records.into_iter() {
                     match action {
@@ -135,8 +199,9 @@ impl Processor for SetProcessor {
                 }
             }
             Operation::Update { old, new } => {
-                let (old_records, new_records) =
-                    self.update(old, new).map_err(PipelineError::ProductError)?;
+                let (old_records, new_records) = self
+                    .update(old, new, branch)
+                    .map_err(PipelineError::ProductError)?;
 
                 for (action, old) in old_records.into_iter() {
                     match action {
@@ -183,4 +248,186 @@ impl Processor for SetProcessor {
         }
         Ok(())
     }
+
+    fn on_truncate(&mut self, port: PortHandle) -> Result<Vec<PortHandle>, BoxedError> {
+        let branch = get_branch(port)?;
+        // Fail before anything is deleted if the records left can't be sent again.
+        if !self.is_stateful() {
+            return Err(SetError::TruncateUnsupported("UNION ALL".to_string()).into());
+        }
+        let (record_map, other_record_map, namespace) = match branch {
+            SetBranch::Left => (
+                &mut self.record_map,
+                &self.right_record_map,
+                RECORDS_NAMESPACE,
+            ),
+            SetBranch::Right => (
+                &mut self.right_record_map,
+                &self.record_map,
+                RIGHT_RECORDS_NAMESPACE,
+            ),
+        };
+        if self.operator.keeps_other_input(branch) && other_record_map.counts().is_none() {
+            return Err(SetError::TruncateUnsupported(format!(
+                "{} with probabilistic optimizations",
+                self.operator.op
+            ))
+            .into());
+        }
+        record_map.clear();
+        if let Some(store) = &self.state_store {
+            store.delete_namespace(namespace)?;
+        }
+        Ok(vec![DEFAULT_PORT_HANDLE])
+    }
+
+    fn after_truncate(
+        &mut self,
+        port: PortHandle,
+        fw: &mut dyn ProcessorChannelForwarder,
+    ) -> Result<(), BoxedError> {
+        let branch = get_branch(port)?;
+        if !self.operator.keeps_other_input(branch) {
+            return Ok(());
+        }
+        let other_record_map = match branch {
+            SetBranch::Left => &self.right_record_map,
+            SetBranch::Right => &self.record_map,
+        };
+        // Checked in `on_truncate`.
+        let Some(counts) = other_record_map.counts() else {
+            return Ok(());
+        };
+        for record in counts.keys() {
+            let count =
+                self.operator
+                    .output_count(record, &self.record_map, &self.right_record_map);
+            for _ in 0..count {
+                fw.send(TableOperation::without_id(
+                    Operation::Insert {
+                        new: record.clone(),
+                    },
+                    DEFAULT_PORT_HANDLE,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn is_stateful(&self) -> bool {
+        // UNION ALL forwards every record as is.
+        !matches!(
+            (self.operator.op, self.operator.quantifier),
+            (SetOperator::Union, SetQuantifier::All)
+        )
+    }
+
+    fn restore(&mut self, store: StateStore) -> Result<(), BoxedError> {
+        self.record_map.restore(&store, RECORDS_NAMESPACE)?;
+        self.right_record_map
+            .restore(&store, RIGHT_RECORDS_NAMESPACE)?;
+        self.state_store = Some(store);
+        Ok(())
+    }
+}
+
+fn new_record_map(
+    enable_probabilistic_optimizations: bool,
+) -> Result<CountingRecordMapEnum, SetError> {
+    Ok(if enable_probabilistic_optimizations {
+        ProbabilisticCountingRecordMap::new()?.into()
+    } else {
+        AccurateCountingRecordMap::new()?.into()
+    })
+}
+
+fn get_branch(port: PortHandle) -> Result<SetBranch, PipelineError> {
+    match port {
+        0 => Ok(SetBranch::Left),
+        1 => Ok(SetBranch::Right),
+        _ => Err(PipelineError::InvalidPortHandle(port)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dozer_types::types::Field;
+
+    use super::*;
+
+    struct TestChannelForwarder {
+        operations: Vec<Operation>,
+    }
+
+    impl ProcessorChannelForwarder for TestChannelForwarder {
+        fn send(&mut self, op: TableOperation) {
+            self.operations.push(op.op);
+        }
+    }
+
+    fn insert(processor: &mut SetProcessor, port: PortHandle, value: i64) {
+        let op = TableOperation::without_id(
+            Operation::Insert {
+                new: Record::new(vec![Field::Int(value)]),
+            },
+            port,
+        );
+        let mut fw = TestChannelForwarder { operations: vec![] };
+        processor.process(op, &mut fw).unwrap();
+    }
+
+    fn truncate(processor: &mut SetProcessor, port: PortHandle) -> Vec<Operation> {
+        assert_eq!(processor.on_truncate(port).unwrap(), [DEFAULT_PORT_HANDLE]);
+        let mut fw = TestChannelForwarder { operations: vec![] };
+        processor.after_truncate(port, &mut fw).unwrap();
+        fw.operations
+    }
+
+    fn inserts(values: &[i64]) -> Vec<Operation> {
+        values
+            .iter()
+            .map(|value| Operation::Insert {
+                new: Record::new(vec![Field::Int(*value)]),
+            })
+            .collect()
+    }
+
+    fn processor(op: SetOperator, quantifier: SetQuantifier) -> SetProcessor {
+        SetProcessor::new("set".into(), SetOperation { op, quantifier }, false).unwrap()
+    }
+
+    #[test]
+    fn test_truncate_union() {
+        let mut processor = processor(SetOperator::Union, SetQuantifier::None);
+        insert(&mut processor, 0, 1);
+        insert(&mut processor, 0, 2);
+        insert(&mut processor, 1, 2);
+        insert(&mut processor, 1, 3);
+
+        // The right records are sent again.
+        let mut ops = truncate(&mut processor, 0);
+        ops.sort_by_key(|op| format!("{op:?}"));
+        assert_eq!(ops, inserts(&[2, 3]));
+        assert_eq!(truncate(&mut processor, 1), []);
+    }
+
+    #[test]
+    fn test_truncate_except() {
+        let mut processor = processor(SetOperator::Except, SetQuantifier::All);
+        insert(&mut processor, 0, 1);
+        insert(&mut processor, 0, 1);
+        insert(&mut processor, 1, 1);
+
+        // The left records are sent again, without the right records to subtract.
+        assert_eq!(truncate(&mut processor, 1), inserts(&[1, 1]));
+        // No records are left without the left records.
+        insert(&mut processor, 1, 1);
+        assert_eq!(truncate(&mut processor, 0), []);
+    }
+
+    #[test]
+    fn test_truncate_union_all() {
+        let mut processor = processor(SetOperator::Union, SetQuantifier::All);
+        assert!(processor.on_truncate(0).is_err());
+    }
 }
@@ -61,6 +61,9 @@ impl ProcessorFactory for TableProcessorFactory {
         _output_schemas: HashMap<PortHandle, dozer_types::types::Schema>,
         _event_hub: EventHub,
     ) -> Result<Box<dyn Processor>, BoxedError> {
-        Ok(Box::new(TableProcessor::new(self.id.clone())))
+        Ok(Box::new(TableProcessor::new(
+            self.id.clone(),
+            self.table.clone(),
+        )))
     }
 }
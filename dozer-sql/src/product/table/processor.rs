@@ -1,18 +1,20 @@
 use dozer_core::channels::ProcessorChannelForwarder;
 use dozer_core::epoch::Epoch;
-use dozer_core::node::Processor;
+use dozer_core::node::{PortHandle, Processor};
 use dozer_core::DEFAULT_PORT_HANDLE;
+use dozer_sql_expression::builder::{extend_schema_source_def, NameOrAlias};
 use dozer_types::errors::internal::BoxedError;
-use dozer_types::types::TableOperation;
+use dozer_types::types::{Schema, TableOperation};
 
 #[derive(Debug)]
 pub struct TableProcessor {
     _id: String,
+    table: NameOrAlias,
 }
 
 impl TableProcessor {
-    pub fn new(id: String) -> Self {
-        Self { _id: id }
+    pub fn new(id: String, table: NameOrAlias) -> Self {
+        Self { _id: id, table }
     }
 }
 
@@ -30,4 +32,19 @@ impl Processor for TableProcessor {
         fw.send(op);
         Ok(())
     }
+
+    fn on_schema_change(
+        &mut self,
+        _port: PortHandle,
+        schema: &Schema,
+    ) -> Result<Vec<(PortHandle, Schema)>, BoxedError> {
+        Ok(vec![(
+            DEFAULT_PORT_HANDLE,
+            extend_schema_source_def(schema, &self.table),
+        )])
+    }
+
+    fn on_truncate(&mut self, _port: PortHandle) -> Result<Vec<PortHandle>, BoxedError> {
+        Ok(vec![DEFAULT_PORT_HANDLE])
+    }
 }
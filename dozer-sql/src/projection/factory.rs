@@ -144,6 +144,9 @@ impl ProcessorFactory for ProjectionProcessorFactory {
         Ok(Box::new(ProjectionProcessor::new(
             schema.clone(),
             expressions.into_iter().map(|e| e.1).collect(),
+            self.select.clone(),
+            self.udfs.clone(),
+            self.runtime.clone(),
         )?))
     }
 }
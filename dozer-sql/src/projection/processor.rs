@@ -1,24 +1,44 @@
+use std::sync::Arc;
+
 use crate::errors::PipelineError;
+use crate::planner::projection::CommonPlanner;
 use dozer_sql_expression::execution::Expression;
+use dozer_sql_expression::sqlparser::ast::SelectItem;
 
 use dozer_core::channels::ProcessorChannelForwarder;
 use dozer_core::epoch::Epoch;
-use dozer_core::node::Processor;
+use dozer_core::errors::SchemaChangeError;
+use dozer_core::node::{PortHandle, Processor};
 use dozer_core::DEFAULT_PORT_HANDLE;
 use dozer_types::errors::internal::BoxedError;
+use dozer_types::models::udf_config::UdfConfig;
 use dozer_types::types::{Operation, Record, Schema, TableOperation};
+use tokio::runtime::Runtime;
 
 #[derive(Debug)]
 pub struct ProjectionProcessor {
     expressions: Vec<Expression>,
     input_schema: Schema,
+    /// The projection the expressions were planned from, planned again when input types change.
+    select: Vec<SelectItem>,
+    udfs: Vec<UdfConfig>,
+    runtime: Arc<Runtime>,
 }
 
 impl ProjectionProcessor {
-    pub fn new(input_schema: Schema, expressions: Vec<Expression>) -> Result<Self, PipelineError> {
+    pub fn new(
+        input_schema: Schema,
+        expressions: Vec<Expression>,
+        select: Vec<SelectItem>,
+        udfs: Vec<UdfConfig>,
+        runtime: Arc<Runtime>,
+    ) -> Result<Self, PipelineError> {
         Ok(Self {
             input_schema,
             expressions,
+            select,
+            udfs,
+            runtime,
         })
     }
 
@@ -98,4 +118,40 @@ impl Processor for ProjectionProcessor {
     fn commit(&self, _epoch: &Epoch) -> Result<(), BoxedError> {
         Ok(())
     }
+
+    fn on_schema_change(
+        &mut self,
+        _port: PortHandle,
+        schema: &Schema,
+    ) -> Result<Vec<(PortHandle, Schema)>, BoxedError> {
+        // Fields keep their index, but their type and nullability may have been widened.
+        let num_fields = self.input_schema.fields.len();
+        if schema.fields.len() < num_fields
+            || self
+                .input_schema
+                .fields
+                .iter()
+                .zip(&schema.fields)
+                .any(|(current, new)| current.name != new.name || current.source != new.source)
+        {
+            return Err(SchemaChangeError::Unsupported.into());
+        }
+        let widened = schema.fields[..num_fields] != self.input_schema.fields[..];
+        self.input_schema = schema.clone();
+        if !widened {
+            // Appended fields aren't projected, so the output schema doesn't change.
+            return Ok(vec![]);
+        }
+
+        // The output types were inferred from the input types.
+        let mut planner = CommonPlanner::new(schema.clone(), &self.udfs, self.runtime.clone());
+        self.runtime
+            .block_on(planner.plan(self.select.clone(), vec![], None))?;
+        self.expressions = planner.projection_output;
+        Ok(vec![(DEFAULT_PORT_HANDLE, planner.post_projection_schema)])
+    }
+
+    fn on_truncate(&mut self, _port: PortHandle) -> Result<Vec<PortHandle>, BoxedError> {
+        Ok(vec![DEFAULT_PORT_HANDLE])
+    }
 }
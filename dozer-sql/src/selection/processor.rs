@@ -1,6 +1,6 @@
 use dozer_core::channels::ProcessorChannelForwarder;
 use dozer_core::epoch::Epoch;
-use dozer_core::node::Processor;
+use dozer_core::node::{PortHandle, Processor};
 use dozer_core::DEFAULT_PORT_HANDLE;
 use dozer_sql_expression::execution::Expression;
 use dozer_types::errors::internal::BoxedError;
@@ -103,4 +103,18 @@ impl Processor for SelectionProcessor {
         }
         Ok(())
     }
+
+    fn on_schema_change(
+        &mut self,
+        _port: PortHandle,
+        schema: &Schema,
+    ) -> Result<Vec<(PortHandle, Schema)>, BoxedError> {
+        // Fields keep their index, so the expression still refers to the same columns.
+        self.input_schema = schema.clone();
+        Ok(vec![(DEFAULT_PORT_HANDLE, schema.clone())])
+    }
+
+    fn on_truncate(&mut self, _port: PortHandle) -> Result<Vec<PortHandle>, BoxedError> {
+        Ok(vec![DEFAULT_PORT_HANDLE])
+    }
 }
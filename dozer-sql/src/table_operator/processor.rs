@@ -1,6 +1,6 @@
 use dozer_core::channels::ProcessorChannelForwarder;
 use dozer_core::epoch::Epoch;
-use dozer_core::node::Processor;
+use dozer_core::node::{PortHandle, Processor};
 use dozer_core::DEFAULT_PORT_HANDLE;
 use dozer_types::errors::internal::BoxedError;
 use dozer_types::types::{Operation, Schema, TableOperation};
@@ -101,4 +101,8 @@ impl Processor for TableOperatorProcessor {
         }
         Ok(())
     }
+
+    fn on_truncate(&mut self, _port: PortHandle) -> Result<Vec<PortHandle>, BoxedError> {
+        Ok(vec![DEFAULT_PORT_HANDLE])
+    }
 }
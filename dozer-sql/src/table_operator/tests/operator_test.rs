@@ -1,12 +1,15 @@
 use std::time::Duration;
 
+use dozer_core::{node::Processor, DEFAULT_PORT_HANDLE};
 use dozer_sql_expression::execution::Expression;
 use dozer_types::{
     chrono::DateTime,
     types::{Field, FieldDefinition, FieldType, Lifetime, Record, Schema, SourceDefinition},
 };
 
-use crate::table_operator::{lifetime::LifetimeTableOperator, operator::TableOperator};
+use crate::table_operator::{
+    lifetime::LifetimeTableOperator, operator::TableOperator, processor::TableOperatorProcessor,
+};
 
 #[test]
 fn test_lifetime() {
@@ -59,3 +62,20 @@ fn test_lifetime() {
 
     assert_eq!(lifetime_record, &expected_record);
 }
+
+#[test]
+fn test_lifetime_truncate() {
+    let table_operator = LifetimeTableOperator::new(
+        None,
+        Expression::Column { index: 1 },
+        Duration::from_secs(60),
+    );
+    let mut processor =
+        TableOperatorProcessor::new("ttl".to_string(), table_operator.into(), Schema::default());
+
+    // The operator keeps no records, so all of its output is deleted too.
+    assert_eq!(
+        processor.on_truncate(DEFAULT_PORT_HANDLE).unwrap(),
+        [DEFAULT_PORT_HANDLE]
+    );
+}
@@ -1 +1,2 @@
 pub mod record_hashtable_key;
+pub mod spill;
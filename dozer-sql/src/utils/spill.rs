@@ -0,0 +1,121 @@
+use std::hash::Hash;
+
+use dozer_tracing::{
+    constants::{DOZER_METER_NAME, SPILLED_BYTES_COUNTER_NAME},
+    opentelemetry_metrics::Counter,
+};
+use linked_hash_map::LinkedHashMap;
+
+/// Keeps the state of a processor within its memory budget.
+///
+/// The state is a map whose entries are written through to a state store. The tracker knows the
+/// approximate size of the entries in memory, and which ones to drop when they're over the budget.
+/// A dropped entry is spilled: it's only in the store, and has to be read back before it's used.
+#[derive(Debug, Clone)]
+pub struct SpillTracker<K: Hash + Eq> {
+    budget: usize,
+    used: usize,
+    /// The size of the entries in memory, least recently used first.
+    sizes: LinkedHashMap<K, usize>,
+    has_spilled: bool,
+    /// Counts the bytes of every spill, so an entry that's read back and spilled again is counted
+    /// again. It measures the spill traffic, not the size of the state on disk.
+    spilled_bytes: Counter<u64>,
+}
+
+impl<K: Hash + Eq + Clone> SpillTracker<K> {
+    pub fn new(budget: usize) -> Self {
+        let spilled_bytes = dozer_tracing::global::meter(DOZER_METER_NAME)
+            .u64_counter(SPILLED_BYTES_COUNTER_NAME)
+            .with_description(
+                "Bytes of processor state dropped from memory, counted at every spill of an entry",
+            )
+            .init();
+        Self {
+            budget,
+            used: 0,
+            sizes: Default::default(),
+            has_spilled: false,
+            spilled_bytes,
+        }
+    }
+
+    /// Whether an entry missing from memory may be in the store.
+    ///
+    /// Until something is spilled, the store never has to be read.
+    pub fn has_spilled(&self) -> bool {
+        self.has_spilled
+    }
+
+    /// Marks the whole state as being in the store only, as it is after a restore.
+    pub fn mark_spilled(&mut self) {
+        self.has_spilled = true;
+    }
+
+    /// The size of `key`, or 0 if it's not in memory.
+    pub fn size(&self, key: &K) -> usize {
+        self.sizes.get(key).copied().unwrap_or_default()
+    }
+
+    /// Sets the size of `key` and marks it as the most recently used entry.
+    pub fn set(&mut self, key: &K, size: usize) {
+        self.remove(key);
+        self.sizes.insert(key.clone(), size);
+        self.used += size;
+    }
+
+    /// Marks `key` as the most recently used entry.
+    pub fn touch(&mut self, key: &K) {
+        self.sizes.get_refresh(key);
+    }
+
+    /// Forgets `key`, which is no longer in the state.
+    pub fn remove(&mut self, key: &K) {
+        if let Some(size) = self.sizes.remove(key) {
+            self.used -= size;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.sizes.clear();
+        self.used = 0;
+        self.has_spilled = false;
+    }
+
+    /// The least recently used entry to spill, if the state is over budget.
+    pub fn pop_over_budget(&mut self) -> Option<K> {
+        if self.used <= self.budget {
+            return None;
+        }
+        let (key, size) = self.sizes.pop_front()?;
+        self.used -= size;
+        self.has_spilled = true;
+        self.spilled_bytes.add(size as u64, &[]);
+        Some(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spills_least_recently_used() {
+        let mut tracker = SpillTracker::new(10);
+        tracker.set(&1, 4);
+        tracker.set(&2, 4);
+        assert_eq!(tracker.pop_over_budget(), None);
+        assert!(!tracker.has_spilled());
+
+        tracker.touch(&1);
+        tracker.set(&3, 4);
+        assert_eq!(tracker.pop_over_budget(), Some(2));
+        assert_eq!(tracker.pop_over_budget(), None);
+        assert!(tracker.has_spilled());
+
+        tracker.set(&1, 12);
+        assert_eq!(tracker.pop_over_budget(), Some(3));
+        assert_eq!(tracker.pop_over_budget(), Some(1));
+        assert_eq!(tracker.size(&1), 0);
+    }
+}
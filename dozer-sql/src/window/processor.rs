@@ -1,7 +1,7 @@
 use crate::errors::PipelineError;
 use dozer_core::channels::ProcessorChannelForwarder;
 use dozer_core::epoch::Epoch;
-use dozer_core::node::Processor;
+use dozer_core::node::{PortHandle, Processor};
 use dozer_core::DEFAULT_PORT_HANDLE;
 use dozer_types::errors::internal::BoxedError;
 use dozer_types::types::{Operation, TableOperation};
@@ -83,4 +83,8 @@ impl Processor for WindowProcessor {
         }
         Ok(())
     }
+
+    fn on_truncate(&mut self, _port: PortHandle) -> Result<Vec<PortHandle>, BoxedError> {
+        Ok(vec![DEFAULT_PORT_HANDLE])
+    }
 }
@@ -8,6 +8,8 @@ pub const TOTAL_LATENCY_HISTOGRAM_NAME: &str = "total_latency";
 
 pub const SOURCE_OPERATION_COUNTER_NAME: &str = "source_operation";
 
+pub const SPILLED_BYTES_COUNTER_NAME: &str = "spilled_bytes";
+
 //  Labels
 pub const OPERATION_TYPE_LABEL: &str = "operation_type";
 pub const TABLE_LABEL: &str = "table";
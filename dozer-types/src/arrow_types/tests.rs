@@ -132,3 +132,47 @@ fn roundtrip_record_to_record_batch() {
 
     assert_eq!(original_schema, arrow_field_test_cases_schema());
 }
+
+#[test]
+fn maps_every_field_type_to_arrow() {
+    use crate::arrow_types::to_arrow::map_record_to_arrow;
+    use crate::types::{Field, Record};
+
+    let types = [
+        (FieldType::UInt, Field::UInt(1)),
+        (FieldType::U128, Field::U128(u128::MAX)),
+        (FieldType::Int, Field::Int(-1)),
+        (FieldType::Int8, Field::Int8(-8)),
+        (FieldType::I128, Field::I128(i128::MIN)),
+        (FieldType::Float, Field::Float(1.5.into())),
+        (FieldType::Boolean, Field::Boolean(true)),
+        (FieldType::String, Field::String("a".to_string())),
+        (FieldType::Text, Field::Text("b".to_string())),
+        (FieldType::Binary, Field::Binary(vec![1, 2])),
+        (
+            FieldType::Decimal,
+            Field::Decimal(rust_decimal::Decimal::new(-1234, 2)),
+        ),
+        (FieldType::Duration, Field::Null),
+    ];
+    let schema =
+        types
+            .iter()
+            .enumerate()
+            .fold(DozerSchema::default(), |mut schema, (i, (typ, _))| {
+                schema.field(
+                    FieldDefinition::new(format!("f{i}"), *typ, true, SourceDefinition::Dynamic),
+                    false,
+                );
+                schema
+            });
+
+    let values = types.iter().map(|(_, field)| field.clone()).collect();
+    let batch = map_record_to_arrow(Record::new(values), &schema).unwrap();
+    assert_eq!(batch.num_rows(), 1);
+    assert_eq!(batch.num_columns(), types.len());
+
+    let nulls = vec![Field::Null; types.len()];
+    let batch = map_record_to_arrow(Record::new(nulls), &schema).unwrap();
+    assert_eq!(batch.column(1).null_count(), 1);
+}
@@ -54,6 +54,21 @@ pub fn map_record_to_arrow(
             (Field::Null, FieldType::Int) => {
                 Arc::new(arrow_array::Int64Array::from(vec![None as Option<i64>])) as ArrayRef
             }
+            (Field::Int8(v), FieldType::Int8) => {
+                Arc::new(arrow_array::Int64Array::from_iter_values([*v as i64])) as ArrayRef
+            }
+            (Field::Null, FieldType::Int8) => {
+                Arc::new(arrow_array::Int64Array::from(vec![None as Option<i64>])) as ArrayRef
+            }
+            (Field::U128(v), FieldType::U128) => {
+                Arc::new(arrow_array::StringArray::from_iter_values([v.to_string()])) as ArrayRef
+            }
+            (Field::I128(v), FieldType::I128) => {
+                Arc::new(arrow_array::StringArray::from_iter_values([v.to_string()])) as ArrayRef
+            }
+            (Field::Null, FieldType::U128 | FieldType::I128) => {
+                Arc::new(arrow_array::StringArray::from(vec![None as Option<String>])) as ArrayRef
+            }
             (Field::Float(v), FieldType::Float) => {
                 Arc::new(arrow_array::Float64Array::from_iter_values([**v])) as ArrayRef
             }
@@ -83,9 +98,10 @@ pub fn map_record_to_arrow(
                     .with_precision_and_scale(DECIMAL128_MAX_PRECISION, v.scale() as i8)?,
                 &DataType::Decimal256(DECIMAL256_MAX_PRECISION, DECIMAL128_MAX_SCALE),
             )?,
-            (Field::Null, FieldType::Decimal) => Arc::new(arrow_array::Decimal256Array::from(vec![
-                None as Option<i256>,
-            ])) as ArrayRef,
+            (Field::Null, FieldType::Decimal) => Arc::new(
+                arrow_array::Decimal256Array::from(vec![None as Option<i256>])
+                    .with_precision_and_scale(DECIMAL256_MAX_PRECISION, DECIMAL128_MAX_SCALE)?,
+            ) as ArrayRef,
             (Field::Timestamp(v), FieldType::Timestamp) => {
                 Arc::new(arrow_array::TimestampNanosecondArray::from_iter_values([{
                     v.timestamp_nanos_opt().expect(
@@ -130,8 +146,8 @@ pub fn map_record_to_arrow(
                 ])) as ArrayRef
             }
             (Field::Null, FieldType::Duration) => {
-                Arc::new(arrow_array::BinaryArray::from_opt_vec(vec![
-                    None as Option<&[u8]>,
+                Arc::new(arrow_array::DurationNanosecondArray::from(vec![
+                    None as Option<i64>,
                 ])) as ArrayRef
             }
             (a, b) => Err(arrow::error::ArrowError::InvalidArgumentError(format!(
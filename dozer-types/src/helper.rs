@@ -40,7 +40,7 @@ pub fn json_value_to_field(
             .map(Field::Int),
         FieldType::Int8 => serde_json::from_value(value)
             .map_err(DeserializationError::Json)
-            .map(Field::Int),
+            .map(Field::Int8),
         FieldType::I128 => match value {
             Value::String(str) => return Field::from_str(str.as_str(), typ, nullable),
             _ => Err(DeserializationError::Custom(
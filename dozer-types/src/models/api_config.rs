@@ -61,6 +61,7 @@ pub struct GrpcApiOptions {
     pub web: Option<bool>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether to serve the gRPC API if the app has API sinks; Default: true
     pub enabled: Option<bool>,
 }
 
@@ -84,6 +85,7 @@ pub struct PgWireOptions {
     pub host: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether to serve SQL queries over the PostgreSQL wire protocol if the app has API sinks; Default: true
     pub enabled: Option<bool>,
 }
 
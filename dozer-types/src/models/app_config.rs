@@ -15,6 +15,14 @@ pub struct AppConfig {
     /// The event hub's queue capacity. Events that are not processed will be dropped.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub event_hub_capacity: Option<usize>,
+
+    /// How many bytes of state each join or aggregation may keep in memory. State over the budget is spilled to disk. Unlimited if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_memory_budget: Option<usize>,
+
+    /// Whether joins, aggregations and set operations checkpoint their state to disk, so they resume with their sources after a restart instead of reprocessing them from the start. Disabled if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkpoint_processor_state: Option<bool>,
 }
 
 pub fn default_app_buffer_size() -> u32 {
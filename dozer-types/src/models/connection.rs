@@ -1,6 +1,7 @@
 use crate::models::ingestion_types::{
-    ConfigSchemas, DeltaLakeConfig, EthConfig, GrpcConfig, JavaScriptConfig, KafkaConfig,
-    LocalStorage, MongodbConfig, MySQLConfig, S3Storage, SnowflakeConfig, WebhookConfig, SECRET,
+    AzureStorage, ConfigSchemas, DeltaLakeConfig, EthConfig, GcsStorage, GrpcConfig,
+    JavaScriptConfig, KafkaConfig, LocalStorage, MongodbConfig, MySQLConfig, S3Storage,
+    SnowflakeConfig, WebhookConfig, SECRET,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -63,6 +64,14 @@ pub struct PostgresConfig {
     /// The snapshot batch size
     #[serde(skip_serializing_if = "Option::is_none")]
     pub batch_size: Option<u32>,
+
+    /// The number of rows copied per chunk of the initial snapshot (default: 1000000). Snapshot progress is saved after every completely copied table. A restart keeps the copied tables, and copies every other table again from its first row
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_chunk_size: Option<u64>,
+
+    /// The number of chunks, of the same or different tables, copied concurrently during the initial snapshot (default: 4)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_parallelism: Option<u32>,
 }
 
 impl SchemaExample for PostgresConfig {
@@ -276,6 +285,12 @@ pub enum ConnectionConfig {
     /// In yaml, present as tag: `!ObjectStore`
     LocalStorage(LocalStorage),
 
+    /// In yaml, present as tag: `!GcsStorage`
+    GcsStorage(GcsStorage),
+
+    /// In yaml, present as tag: `!AzureStorage`
+    AzureStorage(AzureStorage),
+
     /// In yaml, present as tag" `!DeltaLake`
     DeltaLake(DeltaLakeConfig),
 
@@ -305,6 +320,8 @@ impl ConnectionConfig {
             ConnectionConfig::Kafka(_) => "kafka".to_string(),
             ConnectionConfig::S3Storage(_) => "s3storage".to_string(),
             ConnectionConfig::LocalStorage(_) => "localstorage".to_string(),
+            ConnectionConfig::GcsStorage(_) => "gcsstorage".to_string(),
+            ConnectionConfig::AzureStorage(_) => "azurestorage".to_string(),
             ConnectionConfig::DeltaLake(_) => "deltalake".to_string(),
             ConnectionConfig::MongoDB(_) => "mongodb".to_string(),
             ConnectionConfig::MySQL(_) => "mysql".to_string(),
@@ -9,7 +9,7 @@ use crate::{
     helper::{deserialize_duration_secs_f64, f64_schema, serialize_duration_secs_f64},
     models::connection::SchemaExample,
     node::OpIdentifier,
-    types::Operation,
+    types::{Operation, Schema},
 };
 
 use super::equal_default;
@@ -29,6 +29,20 @@ pub enum IngestionMessage {
         id: Option<OpIdentifier>,
     },
     TransactionInfo(TransactionInfo),
+    /// The schema of a table changed, e.g. after an `ALTER TABLE` upstream.
+    ///
+    /// Operations of the table that follow this message have the new schema.
+    SchemaChange {
+        /// Index of the table whose schema changed.
+        table_index: usize,
+        /// The complete new schema of the table.
+        schema: Schema,
+    },
+    /// All records of a table were deleted, e.g. by a `TRUNCATE` upstream.
+    Truncate {
+        /// Index of the truncated table.
+        table_index: usize,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -43,6 +57,11 @@ pub enum TransactionInfo {
     /// A connector uses this message kind to notify Dozer that a initial snapshot of the source tables is done,
     /// and the data is up-to-date until next CDC event.
     SnapshottingDone { id: Option<OpIdentifier> },
+    /// A connector uses this message kind to notify Dozer that `Connector::serialize_state` would now return `state`,
+    /// e.g. because it has made snapshot progress that a restart can resume from.
+    ///
+    /// Sinks persist the state with their next checkpoint, so it should be followed by a `Commit`.
+    SourceStateChanged { state: Vec<u8> },
 }
 
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash, JsonSchema, Default)]
@@ -274,12 +293,26 @@ pub struct Table {
     pub config: TableConfig,
 
     pub name: String,
+
+    /// Emit updates and deletes when files are modified or deleted. Without it, only the rows of new files are inserted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change_tracking: Option<ChangeTracking>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash, JsonSchema, Default)]
+pub struct ChangeTracking {
+    /// The columns that identify a row of a file. Rows are identified by their position in the file if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub primary_key: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash, JsonSchema)]
 pub enum TableConfig {
     CSV(CsvConfig),
     Parquet(ParquetConfig),
+    /// Newline-delimited JSON.
+    Json(JsonConfig),
+    Avro(AvroConfig),
 }
 
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash, JsonSchema)]
@@ -302,6 +335,26 @@ pub struct ParquetConfig {
     pub marker_extension: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash, JsonSchema)]
+pub struct JsonConfig {
+    pub path: String,
+
+    pub extension: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marker_extension: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash, JsonSchema)]
+pub struct AvroConfig {
+    pub path: String,
+
+    pub extension: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marker_extension: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash, JsonSchema)]
 pub struct S3Details {
     pub access_key_id: String,
@@ -348,6 +401,7 @@ impl SchemaExample for S3Storage {
                     marker_extension: None,
                 }),
                 name: "table_name".to_owned(),
+                change_tracking: None,
             }],
         }
     }
@@ -373,6 +427,90 @@ impl LocalStorage {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash, JsonSchema)]
+pub struct GcsDetails {
+    pub bucket_name: String,
+
+    /// The service account JSON key. Application default credentials are used if neither this nor `service_account_path` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_account_key: Option<String>,
+
+    /// Path to the service account JSON key file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_account_path: Option<String>,
+
+    /// Allow plain HTTP, e.g. for an emulator set as `gcs_base_url` in the service account key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_http: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash, JsonSchema)]
+#[schemars(example = "Self::example")]
+pub struct GcsStorage {
+    pub details: GcsDetails,
+
+    pub tables: Vec<Table>,
+}
+
+impl GcsStorage {
+    pub fn convert_to_table(&self) -> PrettyTable {
+        table!(
+            [
+                "service_account",
+                self.details
+                    .service_account_path
+                    .as_deref()
+                    .or(self.details.service_account_key.as_ref().map(|_| SECRET))
+                    .unwrap_or("default")
+            ],
+            ["bucket_name", self.details.bucket_name]
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash, JsonSchema)]
+pub struct AzureDetails {
+    pub account_name: String,
+
+    pub container_name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_key: Option<String>,
+
+    /// A shared access signature, as the query string of a SAS URL.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sas_token: Option<String>,
+
+    /// Connect to a local Azurite emulator with its well-known account.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_emulator: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash, JsonSchema)]
+#[schemars(example = "Self::example")]
+pub struct AzureStorage {
+    pub details: AzureDetails,
+
+    pub tables: Vec<Table>,
+}
+
+impl AzureStorage {
+    pub fn convert_to_table(&self) -> PrettyTable {
+        table!(
+            ["account_name", self.details.account_name],
+            ["container_name", self.details.container_name],
+            [
+                "access_key",
+                self.details.access_key.as_ref().map_or("", |_| SECRET)
+            ],
+            [
+                "sas_token",
+                self.details.sas_token.as_ref().map_or("", |_| SECRET)
+            ]
+        )
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash, JsonSchema)]
 pub struct DeltaTable {
     pub path: String,
@@ -398,6 +536,24 @@ pub struct DeltaLakeConfig {
 
 pub struct MongodbConfig {
     pub connection_string: String,
+
+    /// Flattens documents into typed columns, instead of a single `data` JSON column.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typed_columns: Option<MongodbTypedColumns>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash, JsonSchema, Default)]
+/// Maps top-level and nested document fields to columns named by their dotted path.
+///
+/// Fields that are not mapped, or whose value doesn't have the type of their column, are kept in an `_overflow` JSON column.
+pub struct MongodbTypedColumns {
+    /// The number of documents sampled per collection to infer the columns and their types. Defaults to 1000.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_size: Option<u32>,
+
+    /// Whether to infer the columns from the `$jsonSchema` validator of a collection that has one, instead of sampling it. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_validator: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash, JsonSchema)]
@@ -444,10 +600,15 @@ pub fn default_snowflake_poll_interval() -> Duration {
     Duration::from_secs(60)
 }
 
+pub fn default_mongodb_sample_size() -> u32 {
+    1000
+}
+
 impl SchemaExample for MongodbConfig {
     fn example() -> Self {
         Self {
             connection_string: "mongodb://localhost:27017/db_name".to_owned(),
+            typed_columns: None,
         }
     }
 }
@@ -505,6 +666,52 @@ impl SchemaExample for LocalStorage {
                     marker_extension: None,
                 }),
                 name: "table_name".to_owned(),
+                change_tracking: None,
+            }],
+        }
+    }
+}
+
+impl SchemaExample for GcsStorage {
+    fn example() -> Self {
+        Self {
+            details: GcsDetails {
+                bucket_name: "".to_owned(),
+                service_account_key: None,
+                service_account_path: Some("path/to/service-account.json".to_owned()),
+                allow_http: None,
+            },
+            tables: vec![Table {
+                config: TableConfig::CSV(CsvConfig {
+                    path: "path/to/table".to_owned(),
+                    extension: ".csv".to_owned(),
+                    marker_extension: None,
+                }),
+                name: "table_name".to_owned(),
+                change_tracking: None,
+            }],
+        }
+    }
+}
+
+impl SchemaExample for AzureStorage {
+    fn example() -> Self {
+        Self {
+            details: AzureDetails {
+                account_name: "".to_owned(),
+                container_name: "".to_owned(),
+                access_key: None,
+                sas_token: Some("".to_owned()),
+                use_emulator: None,
+            },
+            tables: vec![Table {
+                config: TableConfig::CSV(CsvConfig {
+                    path: "path/to/table".to_owned(),
+                    extension: ".csv".to_owned(),
+                    marker_extension: None,
+                }),
+                name: "table_name".to_owned(),
+                change_tracking: None,
             }],
         }
     }
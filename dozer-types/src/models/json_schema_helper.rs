@@ -27,6 +27,8 @@ pub fn get_connection_schemas() -> Result<String, serde_json::Error> {
         ("kafka", schema_for!(ingestion_types::KafkaConfig)),
         ("s3", schema_for!(ingestion_types::S3Storage)),
         ("local_storage", schema_for!(ingestion_types::LocalStorage)),
+        ("gcs", schema_for!(ingestion_types::GcsStorage)),
+        ("azure", schema_for!(ingestion_types::AzureStorage)),
         ("deltalake", schema_for!(ingestion_types::DeltaLakeConfig)),
         ("mongodb", schema_for!(ingestion_types::MongodbConfig)),
         ("mysql", schema_for!(ingestion_types::MySQLConfig)),
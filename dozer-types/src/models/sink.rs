@@ -109,6 +109,10 @@ pub enum SinkConfig {
     Aerospike(AerospikeSinkConfig),
     Clickhouse(ClickhouseSinkConfig),
     Oracle(OracleSinkConfig),
+    Postgres(PostgresSinkConfig),
+    Kafka(KafkaSinkConfig),
+    Lake(LakeSinkConfig),
+    Api(ApiSinkConfig),
 }
 impl SinkConfig {
     pub fn name(&self) -> String {
@@ -117,6 +121,10 @@ impl SinkConfig {
             SinkConfig::Aerospike(_) => "aerospike",
             SinkConfig::Clickhouse(_) => "clickhouse",
             SinkConfig::Oracle(_) => "oracle",
+            SinkConfig::Postgres(_) => "postgres",
+            SinkConfig::Kafka(_) => "kafka",
+            SinkConfig::Lake(_) => "lake",
+            SinkConfig::Api(_) => "api",
         };
         return name.to_string();
     }
@@ -176,6 +184,8 @@ pub struct AerospikeSinkTable {
     pub source_table_name: String,
     pub namespace: String,
     pub set_name: String,
+    /// Columns looked up in other sets when a record is written. A later change to a looked up
+    /// record isn't propagated to records already written; they keep the old values until written again.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub denormalize: Vec<AerospikeDenormalizations>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -200,22 +210,105 @@ pub struct AerospikeSinkConfig {
     pub metadata_set: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone, Default)]
-#[serde(deny_unknown_fields)]
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(try_from = "ClickhouseSinkConfigInput")]
 pub struct ClickhouseSinkConfig {
-    #[serde(default = "ClickhouseSinkConfig::default_host")]
     pub host: String,
-    #[serde(default = "ClickhouseSinkConfig::default_port")]
     pub port: u16,
-    #[serde(default = "ClickhouseSinkConfig::default_user")]
     pub user: String,
-    #[serde(default)]
     pub password: Option<String>,
-    #[serde(default = "ClickhouseSinkConfig::default_scheme")]
     pub scheme: String,
-    #[serde(default = "ClickhouseSinkConfig::default_database")]
     pub database: String,
     pub options: Vec<(String, String)>,
+    pub tables: Vec<ClickhouseSinkTableConfig>,
+}
+
+/// [`ClickhouseSinkConfig`] as written in the config, which may still use the deprecated single table form.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct ClickhouseSinkConfigInput {
+    #[serde(default = "ClickhouseSinkConfig::default_host")]
+    host: String,
+    #[serde(default = "ClickhouseSinkConfig::default_port")]
+    port: u16,
+    #[serde(default = "ClickhouseSinkConfig::default_user")]
+    user: String,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default = "ClickhouseSinkConfig::default_scheme")]
+    scheme: String,
+    #[serde(default = "ClickhouseSinkConfig::default_database")]
+    database: String,
+    options: Vec<(String, String)>,
+    #[serde(default)]
+    tables: Vec<ClickhouseSinkTableConfig>,
+    /// Deprecated, use `tables`. The source table of the only table of the sink.
+    #[serde(default)]
+    source_table_name: Option<String>,
+    /// Deprecated, use `tables`. The sink table of the only table of the sink.
+    #[serde(default)]
+    sink_table_name: Option<String>,
+    /// Deprecated, use `tables`. The create table options of the only table of the sink.
+    #[serde(default)]
+    create_table_options: Option<ClickhouseTableOptions>,
+}
+
+impl TryFrom<ClickhouseSinkConfigInput> for ClickhouseSinkConfig {
+    type Error = String;
+
+    fn try_from(input: ClickhouseSinkConfigInput) -> Result<Self, Self::Error> {
+        let mut tables = input.tables;
+        match (input.source_table_name, input.sink_table_name) {
+            (Some(source_table_name), Some(sink_table_name)) => {
+                if !tables.is_empty() {
+                    return Err(
+                        "clickhouse sink has both `tables` and the deprecated `source_table_name`, only set `tables`"
+                            .to_string(),
+                    );
+                }
+                tables.push(ClickhouseSinkTableConfig {
+                    source_table_name,
+                    sink_table_name,
+                    create_table_options: input.create_table_options,
+                });
+            }
+            (None, None) if input.create_table_options.is_none() => {}
+            _ => {
+                return Err(
+                    "clickhouse sink needs both `source_table_name` and `sink_table_name` in the deprecated single table form, use `tables` instead"
+                        .to_string(),
+                )
+            }
+        }
+        if tables.is_empty() {
+            return Err("clickhouse sink has no tables, set `tables`".to_string());
+        }
+        Ok(Self {
+            host: input.host,
+            port: input.port,
+            user: input.user,
+            password: input.password,
+            scheme: input.scheme,
+            database: input.database,
+            options: input.options,
+            tables,
+        })
+    }
+}
+
+impl JsonSchema for ClickhouseSinkConfig {
+    fn schema_name() -> String {
+        "ClickhouseSinkConfig".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        ClickhouseSinkConfigInput::json_schema(gen)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ClickhouseSinkTableConfig {
     pub source_table_name: String,
     pub sink_table_name: String,
     pub create_table_options: Option<ClickhouseTableOptions>,
@@ -242,12 +335,18 @@ impl ClickhouseSinkConfig {
 #[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct ClickhouseTableOptions {
+    /// Engine of the table created by the sink. Only `ReplacingMergeTree` and `CollapsingMergeTree` tables are written exactly once,
+    /// other tables get the records written after the last checkpoint again when the sink restarts after a crash.
     pub engine: Option<String>,
     pub primary_keys: Option<Vec<String>>,
     pub partition_by: Option<String>,
     pub sample_by: Option<String>,
     pub order_by: Option<Vec<String>>,
     pub cluster: Option<String>,
+    /// Version column of a `ReplacingMergeTree` table, bumped on every update. Defaults to `_version`.
+    pub version_column: Option<String>,
+    /// Column of a `ReplacingMergeTree` table marking deleted rows. Defaults to `_is_deleted`.
+    pub is_deleted_column: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
@@ -261,6 +360,66 @@ pub struct OracleSinkConfig {
     pub owner: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PostgresSinkConfig {
+    pub connection: String,
+    pub table_name: String,
+    #[serde(default)]
+    pub unique_key: Vec<String>,
+    /// The schema of the table. Defaults to the schema of the connection, then `public`.
+    #[serde(default)]
+    pub schema: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct KafkaSinkConfig {
+    /// A Kafka connection. Records are encoded as Avro if it has a schema registry url, as JSON otherwise.
+    pub connection: String,
+    pub table_name: String,
+    pub topic: String,
+    /// Defaults to `dozer-<topic>`. Must be unique among the producers of the cluster.
+    #[serde(default)]
+    pub transactional_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LakeSinkConfig {
+    /// A `LocalStorage` or `S3Storage` connection. Its tables are ignored.
+    pub connection: String,
+    pub table_name: String,
+    /// Directory of the Parquet files, relative to the local path or the bucket root.
+    pub path: String,
+    /// A file is closed at the first epoch boundary after it reaches this size. Defaults to 128 MiB.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size_bytes: Option<u64>,
+    /// A file is closed at the first epoch boundary after it has been open this long. Defaults to 5 minutes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_age_secs: Option<u64>,
+    /// Commits every file to a Delta Lake log in `path`, so readers see atomic appends and the sink can resume.
+    #[serde(default, skip_serializing_if = "equal_default")]
+    pub delta: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ApiSinkConfig {
+    pub table_name: String,
+    /// The endpoint the table is served as by the APIs. Defaults to `table_name`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+}
+
+pub fn default_lake_max_file_size_bytes() -> u64 {
+    128 * 1024 * 1024
+}
+
+pub fn default_lake_max_file_age_secs() -> u64 {
+    300
+}
+
 pub fn default_log_reader_batch_size() -> u32 {
     1000
 }
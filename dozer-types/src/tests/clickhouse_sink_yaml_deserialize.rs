@@ -0,0 +1,60 @@
+use crate::models::sink::{ClickhouseSinkConfig, ClickhouseSinkTableConfig};
+
+#[test]
+fn standard() {
+    let input_config = r#"
+    options: []
+    tables:
+    - source_table_name: users
+      sink_table_name: users_sink
+  "#;
+    let config = serde_yaml::from_str::<ClickhouseSinkConfig>(input_config).unwrap();
+    assert_eq!(config.host, "0.0.0.0");
+    assert_eq!(
+        config.tables,
+        vec![ClickhouseSinkTableConfig {
+            source_table_name: "users".to_string(),
+            sink_table_name: "users_sink".to_string(),
+            create_table_options: None,
+        }]
+    );
+}
+
+#[test]
+fn deprecated_single_table() {
+    let input_config = r#"
+    options: []
+    source_table_name: users
+    sink_table_name: users_sink
+  "#;
+    let config = serde_yaml::from_str::<ClickhouseSinkConfig>(input_config).unwrap();
+    assert_eq!(
+        config.tables,
+        vec![ClickhouseSinkTableConfig {
+            source_table_name: "users".to_string(),
+            sink_table_name: "users_sink".to_string(),
+            create_table_options: None,
+        }]
+    );
+}
+
+#[test]
+fn error_no_tables() {
+    let input_config = r#"
+    options: []
+  "#;
+    let error = serde_yaml::from_str::<ClickhouseSinkConfig>(input_config).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("clickhouse sink has no tables, set `tables`"));
+}
+
+#[test]
+fn error_unknown_field() {
+    let input_config = r#"
+    options: []
+    table_name: users
+  "#;
+    let error = serde_yaml::from_str::<ClickhouseSinkConfig>(input_config).unwrap_err();
+    assert!(error.to_string().starts_with("unknown field `table_name`"));
+}
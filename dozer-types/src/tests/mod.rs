@@ -1,4 +1,5 @@
 mod api_config_yaml_deserialize;
+mod clickhouse_sink_yaml_deserialize;
 mod dozer_yaml_deserialize;
 mod eth_yaml_deserialize;
 mod field_serialize_test;
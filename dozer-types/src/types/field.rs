@@ -168,6 +168,7 @@ impl Ord for Field {
             (Self::UInt(l), Self::UInt(r)) => l.cmp(r),
             (Self::U128(l), Self::U128(r)) => l.cmp(r),
             (Self::Int(l), Self::Int(r)) => l.cmp(r),
+            (Self::Int8(l), Self::Int8(r)) => l.cmp(r),
             (Self::I128(l), Self::I128(r)) => l.cmp(r),
             (Self::Float(l), Self::Float(r)) => l.cmp(r),
             (Self::Boolean(l), Self::Boolean(r)) => l.cmp(r),